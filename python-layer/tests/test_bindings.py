@@ -0,0 +1,47 @@
+"""Tests for the algorithm, metadata and sensor bindings of the Rust core."""
+
+import struct
+
+import pytest
+
+robotics_core = pytest.importorskip("robotics_core")
+
+RECTIFY = """{
+    "id": "rectify",
+    "parameters": {"gain": 2.0},
+    "stages": [
+        {"op": "map", "params": {"function": "Abs"}},
+        {"op": "scale", "params": {"factor": "$gain"}}
+    ]
+}"""
+
+
+def test_definitions_register_and_describe_themselves():
+    engine = robotics_core.CoreEngine()
+    assert engine.register_definition(RECTIFY) == "rectify"
+    assert engine.algorithms() == ["rectify"]
+
+    metadata = engine.metadata("rectify")
+    assert metadata.id == "rectify"
+    assert metadata.parameters == [("gain", "number", "2.0")]
+    with pytest.raises(RuntimeError):
+        engine.metadata("missing")
+
+
+def test_arrays_pass_through_without_bytes():
+    np = pytest.importorskip("numpy")
+    engine = robotics_core.CoreEngine()
+    engine.register_definition(RECTIFY)
+
+    output = engine.execute_array("rectify", np.array([-1.0, 0.5], dtype=np.float32))
+    assert output.dtype == np.float32
+    assert output.tolist() == [2.0, 1.0]
+
+
+def test_sensor_stream_iterates_readings():
+    stream = robotics_core.SensorStream.mock("imu", 100.0, [1.0, 2.0], limit=3)
+    readings = list(stream)
+
+    assert [reading.timestamp for reading in readings] == [0, 10000, 20000]
+    assert [struct.unpack("=f", reading.payload)[0] for reading in readings] == [1.0, 2.0, 1.0]
+    assert all(reading.sensor_id == "imu" for reading in readings)
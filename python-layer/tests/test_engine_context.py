@@ -0,0 +1,28 @@
+"""Tests for using the Rust CoreEngine as a context manager."""
+
+import pytest
+
+robotics_core = pytest.importorskip("robotics_core")
+
+
+def test_exit_releases_resources():
+    with robotics_core.CoreEngine() as engine:
+        engine.allocate("frame", 64)
+        assert engine.memory_bytes() == 64
+        assert engine.execute("passthrough", b"\x01\x02") == b"\x01\x02"
+
+    assert engine.closed
+    assert engine.memory_bytes() == 0
+
+
+def test_exception_in_block_still_releases_resources():
+    engine = robotics_core.CoreEngine()
+    with pytest.raises(ValueError, match="sensor fault"):
+        with engine:
+            engine.allocate("frame", 64)
+            raise ValueError("sensor fault")
+
+    assert engine.closed
+    assert engine.memory_bytes() == 0
+    with pytest.raises(RuntimeError):
+        engine.execute("passthrough", b"")
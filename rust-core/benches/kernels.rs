@@ -0,0 +1,59 @@
+//! Vectorized `math` kernels against their scalar versions, and the filter
+//! and kinematics algorithms built on them
+//!
+//! Run with `cargo bench --features bench-utils`. This uses the crate's own
+//! `bench` harness rather than criterion, so it needs no extra dependencies;
+//! each line reports the median of many runs.
+
+use robotics_core::algorithm::filters::Kalman;
+use robotics_core::algorithm::kinematics::ForwardKinematics;
+use robotics_core::algorithm::samples;
+use robotics_core::bench::{benchmark_algorithm, compare_kernels};
+use robotics_core::error::CoreError;
+use serde_json::json;
+
+const ITERATIONS: usize = 200;
+
+fn diagonal(n: usize, value: f64) -> Vec<f64> {
+    (0..n * n).map(|i| if i % (n + 1) == 0 { value } else { 0.0 }).collect()
+}
+
+fn main() -> Result<(), CoreError> {
+    println!("kernel                 scalar       simd  speedup");
+    for len in [256, 4096, 65536] {
+        println!("-- {} elements", len);
+        for comparison in compare_kernels(len, ITERATIONS)? {
+            println!(
+                "{:<20} {:>9.2?} {:>9.2?} {:>7.2}x",
+                comparison.kernel, comparison.scalar, comparison.simd, comparison.speedup
+            );
+        }
+    }
+    
+    // Constant-velocity tracking of a 2D position: state (x, y, vx, vy)
+    let dt = 0.01;
+    let kalman = Kalman::new(
+        4,
+        2,
+        vec![1.0, 0.0, dt, 0.0, 0.0, 1.0, 0.0, dt, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0],
+        vec![1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+        diagonal(4, 1e-4),
+        diagonal(2, 1e-2),
+    )?;
+    let track: Vec<f32> = (0..2000).flat_map(|i| [i as f32 * 0.01, (i as f32 * 0.01).sin()]).collect();
+    let arm = ForwardKinematics::from_params(&json!({"dh": [
+        {"a": 0.0, "alpha": std::f64::consts::FRAC_PI_2, "d": 0.3},
+        {"a": 0.4, "alpha": 0.0, "d": 0.0},
+        {"a": 0.3, "alpha": 0.0, "d": 0.0},
+    ]}))?;
+    let joints: Vec<f32> = (0..3000).map(|i| (i as f32 * 0.013).sin()).collect();
+    
+    println!("algorithm                  median       MB/s");
+    for (name, report) in [
+        ("kalman", benchmark_algorithm(&kalman, &samples::f32_to_bytes(&track), ITERATIONS)?),
+        ("forward_kinematics", benchmark_algorithm(&arm, &samples::f32_to_bytes(&joints), ITERATIONS)?),
+    ] {
+        println!("{:<20} {:>12.2?} {:>10.1}", name, report.median, report.throughput_mb_s);
+    }
+    Ok(())
+}
@@ -0,0 +1,7 @@
+//! Shared library build of `robotics_core`, for Python and JavaScript
+//!
+//! The core itself is only an rlib so that it also builds without `std`.
+//! This crate links it into a `cdylib`; enable `python-binding` or `wasm`
+//! for the bindings to export.
+
+pub use robotics_core::*;
@@ -0,0 +1,123 @@
+//! Add uniform random noise to a signal
+
+use serde_json::Value;
+
+use crate::algorithm::context::Context;
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, OutputSizeHint, ParameterDefinition, ParameterType};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// Adds noise drawn uniformly from `[-amplitude, amplitude)` to every `f32`
+/// sample, e.g. to test how a pipeline copes with sensor noise
+///
+/// Randomness comes from the execution's context, so a deterministic engine
+/// adds the same noise on every run.
+#[derive(Clone, Debug)]
+pub struct AddNoise {
+    amplitude: f32,
+}
+
+impl AddNoise {
+    pub const ID: &'static str = "add_noise";
+    
+    /// Create the algorithm, rejecting a negative or non-finite amplitude
+    pub fn new(amplitude: f32) -> Result<Self, CoreError> {
+        if !amplitude.is_finite() || amplitude < 0.0 {
+            return Err(CoreError::InvalidParameter(format!(
+                "amplitude must be finite and non-negative, got {}",
+                amplitude
+            )));
+        }
+        Ok(Self { amplitude })
+    }
+    
+    /// Create the algorithm from its `amplitude` parameter
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Self::new(params::require(params, "amplitude")?)
+    }
+}
+
+impl Algorithm for AddNoise {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::with_capacity(input.len());
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, memory: &mut MemoryManager) -> Result<(), CoreError> {
+        Context::with_default(|context| self.process_in_context(input, output, memory, context))
+    }
+    
+    fn process_in_context(
+        &self,
+        input: &[u8],
+        output: &mut Vec<u8>,
+        _memory: &mut MemoryManager,
+        context: &mut Context<'_>,
+    ) -> Result<(), CoreError> {
+        for sample in samples::f32_from_bytes(input)? {
+            let noise = (2.0 * context.unit_f32() - 1.0) * self.amplitude;
+            output.extend_from_slice(&(sample + noise).to_le_bytes());
+        }
+        Ok(())
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Add Noise".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Adds uniform random noise to f32 samples".to_string(),
+            parameters: vec![ParameterDefinition {
+                name: "amplitude".to_string(),
+                parameter_type: ParameterType::Float,
+                description: "Largest magnitude of the added noise".to_string(),
+                default_value: None,
+            }],
+            output_size_hint: OutputSizeHint::SameAsInput,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CoreEngine;
+    
+    fn noisy(engine: &mut CoreEngine, signal: &[f32]) -> Vec<f32> {
+        let output = engine.execute_algorithm(AddNoise::ID, &samples::f32_to_bytes(signal)).unwrap();
+        samples::f32_from_bytes(&output).unwrap()
+    }
+    
+    fn engine(seed: u64) -> CoreEngine {
+        let mut engine = CoreEngine::builder().seed(seed).build();
+        engine.register(Box::new(AddNoise::new(0.5).unwrap())).unwrap();
+        engine
+    }
+    
+    #[test]
+    fn test_same_seed_gives_identical_noise() {
+        let signal = [1.0; 64];
+        let mut seeded = engine(42);
+        let first = noisy(&mut seeded, &signal);
+        assert_eq!(noisy(&mut seeded, &signal), first);
+        assert_eq!(noisy(&mut engine(42), &signal), first);
+        assert_ne!(noisy(&mut engine(7), &signal), first);
+        
+        assert!(first.iter().all(|&x| (0.5..1.5).contains(&x)));
+        assert!(first.iter().any(|&x| x != 1.0));
+    }
+    
+    #[test]
+    fn test_unseeded_engine_draws_fresh_noise() {
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(AddNoise::new(0.5).unwrap())).unwrap();
+        let signal = [0.0; 64];
+        assert_ne!(noisy(&mut engine, &signal), noisy(&mut engine, &signal));
+        assert!(AddNoise::new(-1.0).is_err());
+    }
+}
@@ -0,0 +1,235 @@
+//! Second-order IIR filter section
+
+use serde_json::Value;
+use std::sync::Mutex;
+
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, OutputSizeHint, ParameterDefinition, ParameterType};
+use crate::algorithm::StreamingAlgorithm;
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// Biquad filter over `f32` samples in direct form II transposed
+///
+/// Computes `y[n] = b0 x[n] + b1 x[n-1] + b2 x[n-2] - a1 y[n-1] - a2 y[n-2]`,
+/// with `a0` normalised to 1. The two delay elements carry across the whole
+/// buffer; a stateless filter starts every call from rest, while a stateful
+/// one keeps its delay state between calls so a stream can be filtered in
+/// chunks. A stateful filter is therefore not `Pure` and must not be cached.
+///
+/// Coefficients are not checked for stability: poles outside the unit
+/// circle give output that grows without bound until it overflows to
+/// infinity.
+#[derive(Debug)]
+pub struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    stateful: bool,
+    // Delay elements `[s1, s2]` kept by a stateful filter between calls
+    state: Mutex<[f32; 2]>,
+}
+
+impl Biquad {
+    pub const ID: &'static str = "biquad";
+    
+    /// Create a stateless filter, rejecting non-finite coefficients
+    pub fn new(b0: f32, b1: f32, b2: f32, a1: f32, a2: f32) -> Result<Self, CoreError> {
+        for (name, value) in [("b0", b0), ("b1", b1), ("b2", b2), ("a1", a1), ("a2", a2)] {
+            if !value.is_finite() {
+                return Err(CoreError::InvalidParameter(format!("{} {} is not finite", name, value)));
+            }
+        }
+        Ok(Self {
+            b0,
+            b1,
+            b2,
+            a1,
+            a2,
+            stateful: false,
+            state: Mutex::new([0.0; 2]),
+        })
+    }
+    
+    /// Keep the delay state between calls instead of starting from rest
+    pub fn stateful(mut self, stateful: bool) -> Self {
+        self.stateful = stateful;
+        self
+    }
+    
+    /// Create a filter from its coefficient parameters and optional
+    /// `stateful` flag
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Ok(Self::new(
+            params::require(params, "b0")?,
+            params::require(params, "b1")?,
+            params::require(params, "b2")?,
+            params::require(params, "a1")?,
+            params::require(params, "a2")?,
+        )?
+        .stateful(params::get(params, "stateful")?.unwrap_or(false)))
+    }
+    
+    /// Return a stateful filter to rest
+    pub fn reset(&self) {
+        *self.lock_state() = [0.0; 2];
+    }
+    
+    /// Filter `signal`, starting from and updating the delay state `state`
+    pub fn filter(&self, signal: &[f32], state: &mut [f32; 2]) -> Vec<f32> {
+        let [mut s1, mut s2] = *state;
+        let output = signal
+            .iter()
+            .map(|&x| {
+                let y = self.b0 * x + s1;
+                s1 = self.b1 * x - self.a1 * y + s2;
+                s2 = self.b2 * x - self.a2 * y;
+                y
+            })
+            .collect();
+        *state = [s1, s2];
+        output
+    }
+    
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, [f32; 2]> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Algorithm for Biquad {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::with_capacity(input.len());
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        let signal = samples::f32_from_bytes(input)?;
+        let filtered = if self.stateful {
+            self.filter(&signal, &mut self.lock_state())
+        } else {
+            self.filter(&signal, &mut [0.0; 2])
+        };
+        for sample in filtered {
+            output.extend_from_slice(&sample.to_le_bytes());
+        }
+        Ok(())
+    }
+    
+    fn as_streaming(&self) -> Option<&dyn StreamingAlgorithm> {
+        self.stateful.then_some(self as &dyn StreamingAlgorithm)
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        let coefficient = |name: &str, description: &str| ParameterDefinition {
+            name: name.to_string(),
+            parameter_type: ParameterType::Float,
+            description: description.to_string(),
+            default_value: None,
+        };
+        AlgorithmMetadata {
+            name: "Biquad".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Second-order IIR filter over f32 samples".to_string(),
+            parameters: vec![
+                coefficient("b0", "Feed-forward coefficient for x[n]"),
+                coefficient("b1", "Feed-forward coefficient for x[n-1]"),
+                coefficient("b2", "Feed-forward coefficient for x[n-2]"),
+                coefficient("a1", "Feedback coefficient for y[n-1]"),
+                coefficient("a2", "Feedback coefficient for y[n-2]"),
+                ParameterDefinition {
+                    name: "stateful".to_string(),
+                    parameter_type: ParameterType::Boolean,
+                    description: "Keep the delay state between calls".to_string(),
+                    default_value: Some("false".to_string()),
+                },
+            ],
+            output_size_hint: OutputSizeHint::SameAsInput,
+            ..Default::default()
+        }
+    }
+}
+
+/// The delay elements of a stateful filter, as two little-endian `f32`s
+impl StreamingAlgorithm for Biquad {
+    fn checkpoint(&self) -> Vec<u8> {
+        samples::f32_to_bytes(&*self.lock_state())
+    }
+    
+    fn restore_checkpoint(&self, state: &[u8]) -> Result<(), CoreError> {
+        let state: [f32; 2] = samples::f32_from_bytes(state)?
+            .try_into()
+            .map_err(|_| CoreError::InvalidInput("biquad checkpoint must hold 2 samples".to_string()))?;
+        *self.lock_state() = state;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    fn params() -> Value {
+        json!({ "b0": 0.5, "b1": 0.25, "b2": 0.125, "a1": -0.5, "a2": 0.25 })
+    }
+    
+    /// Impulse response from the difference equation, in direct form I
+    fn reference_impulse_response(len: usize) -> Vec<f32> {
+        let (b, a1, a2) = ([0.5, 0.25, 0.125], -0.5, 0.25);
+        let mut y = vec![0.0f32; len];
+        for n in 0..len {
+            let x = |k: usize| if n == k { 1.0 } else { 0.0 };
+            let past = |k: usize| if n >= k { y[n - k] } else { 0.0 };
+            y[n] = b[0] * x(0) + b[1] * x(1) + b[2] * x(2) - a1 * past(1) - a2 * past(2);
+        }
+        y
+    }
+    
+    fn run(biquad: &Biquad, signal: &[f32]) -> Vec<f32> {
+        let output = biquad.process(&samples::f32_to_bytes(signal), &mut MemoryManager::new()).unwrap();
+        samples::f32_from_bytes(&output).unwrap()
+    }
+    
+    #[test]
+    fn test_impulse_response_matches_difference_equation() {
+        let biquad = Biquad::from_params(&params()).unwrap();
+        let mut impulse = vec![0.0; 16];
+        impulse[0] = 1.0;
+        let response = run(&biquad, &impulse);
+        for (actual, expected) in response.iter().zip(reference_impulse_response(16)) {
+            assert!((actual - expected).abs() < 1e-6, "{} != {}", actual, expected);
+        }
+        assert_eq!(&response[..3], &[0.5, 0.5, 0.25]);
+        
+        // Stateless: a second call starts from rest again
+        assert_eq!(run(&biquad, &impulse), response);
+    }
+    
+    #[test]
+    fn test_stateful_filter_continues_across_calls() {
+        let signal: Vec<f32> = (0..20).map(|i| (i as f32 * 0.7).sin()).collect();
+        let whole = run(&Biquad::from_params(&params()).unwrap(), &signal);
+        
+        let mut stateful = params();
+        stateful["stateful"] = json!(true);
+        let chunked = Biquad::from_params(&stateful).unwrap();
+        let mut output = run(&chunked, &signal[..7]);
+        output.extend(run(&chunked, &signal[7..]));
+        assert_eq!(output, whole);
+        
+        chunked.reset();
+        assert_eq!(run(&chunked, &signal), whole);
+    }
+    
+    #[test]
+    fn test_invalid_coefficients_rejected() {
+        assert!(Biquad::new(1.0, 0.0, 0.0, f32::INFINITY, 0.0).is_err());
+        assert!(Biquad::from_params(&json!({ "b0": 1.0 })).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+//! Clamp samples into a fixed range
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::algorithm::tolerance::{self, DEFAULT_EPSILON};
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, OutputSizeHint, InPlaceAlgorithm, ParameterDefinition, ParameterType, Pure};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// What a NaN sample becomes after clamping
+///
+/// Comparisons against NaN are always false, so without an explicit policy a
+/// NaN would slip through any range check.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NanPolicy {
+    /// Replace NaN with `0.0`, even if zero lies outside the range
+    Zero,
+    /// Replace NaN with the range minimum
+    Min,
+    /// Replace NaN with the range maximum
+    Max,
+    /// Leave NaN untouched
+    #[default]
+    Passthrough,
+}
+
+/// Clamps each `f32` sample into `[min, max]`
+#[derive(Clone, Debug)]
+pub struct Clamp {
+    min: f32,
+    max: f32,
+    nan_policy: NanPolicy,
+    epsilon: f32,
+}
+
+impl Clamp {
+    pub const ID: &'static str = "clamp";
+    
+    /// Create a clamp, rejecting NaN or inverted bounds
+    pub fn new(min: f32, max: f32, nan_policy: NanPolicy) -> Result<Self, CoreError> {
+        if min.is_nan() || max.is_nan() {
+            return Err(CoreError::InvalidParameter("clamp bounds must not be NaN".to_string()));
+        }
+        if min > max {
+            return Err(CoreError::InvalidParameter(format!("clamp min {} is greater than max {}", min, max)));
+        }
+        Ok(Self {
+            min,
+            max,
+            nan_policy,
+            epsilon: DEFAULT_EPSILON,
+        })
+    }
+    
+    /// Snap samples within `epsilon` of a bound onto the bound
+    pub fn with_epsilon(mut self, epsilon: f32) -> Result<Self, CoreError> {
+        self.epsilon = tolerance::validate_epsilon(epsilon)?;
+        Ok(self)
+    }
+    
+    /// Create a clamp from its `min`, `max`, `nan_policy` and `epsilon`
+    /// parameters
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Self::new(
+            params::require(params, "min")?,
+            params::require(params, "max")?,
+            params::get(params, "nan_policy")?.unwrap_or_default(),
+        )?
+        .with_epsilon(params::get(params, "epsilon")?.unwrap_or(DEFAULT_EPSILON))
+    }
+    
+    /// Lower bound of the range
+    pub fn min(&self) -> f32 {
+        self.min
+    }
+    
+    /// Upper bound of the range
+    pub fn max(&self) -> f32 {
+        self.max
+    }
+    
+    /// What NaN samples become
+    pub fn nan_policy(&self) -> NanPolicy {
+        self.nan_policy
+    }
+    
+    /// Clamp a single sample
+    ///
+    /// Samples within epsilon of a bound come out as exactly that bound.
+    pub fn apply(&self, sample: f32) -> f32 {
+        if sample.is_nan() {
+            match self.nan_policy {
+                NanPolicy::Zero => 0.0,
+                NanPolicy::Min => self.min,
+                NanPolicy::Max => self.max,
+                NanPolicy::Passthrough => sample,
+            }
+        } else if tolerance::approx_le(sample, self.min, self.epsilon) {
+            self.min
+        } else if tolerance::approx_ge(sample, self.max, self.epsilon) {
+            self.max
+        } else {
+            sample
+        }
+    }
+}
+
+impl Algorithm for Clamp {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::with_capacity(input.len());
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        samples::map_f32_into(input, output, |sample| self.apply(sample))
+    }
+    
+    fn as_in_place(&self) -> Option<&dyn InPlaceAlgorithm> {
+        Some(self)
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Clamp".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Clamps f32 samples into a range with a NaN policy".to_string(),
+            parameters: vec![
+                ParameterDefinition {
+                    name: "min".to_string(),
+                    parameter_type: ParameterType::Float,
+                    description: "Lower bound".to_string(),
+                    default_value: None,
+                },
+                ParameterDefinition {
+                    name: "max".to_string(),
+                    parameter_type: ParameterType::Float,
+                    description: "Upper bound".to_string(),
+                    default_value: None,
+                },
+                ParameterDefinition {
+                    name: "nan_policy".to_string(),
+                    parameter_type: ParameterType::String,
+                    description: "Zero, Min, Max or Passthrough".to_string(),
+                    default_value: Some("Passthrough".to_string()),
+                },
+                ParameterDefinition {
+                    name: "epsilon".to_string(),
+                    parameter_type: ParameterType::Float,
+                    description: "Tolerance within which a sample snaps to a bound".to_string(),
+                    default_value: Some(DEFAULT_EPSILON.to_string()),
+                },
+            ],
+            output_size_hint: OutputSizeHint::SameAsInput,
+            ..Default::default()
+        }
+    }
+}
+
+impl InPlaceAlgorithm for Clamp {
+    fn process_in_place(&self, buf: &mut [u8], _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        samples::map_f32_in_place(buf, |sample| self.apply(sample))
+    }
+}
+
+impl Pure for Clamp {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    fn run(clamp: &Clamp, input: &[f32]) -> Vec<f32> {
+        let mut memory = MemoryManager::new();
+        let output = clamp.process(&samples::f32_to_bytes(input), &mut memory).unwrap();
+        samples::f32_from_bytes(&output).unwrap()
+    }
+    
+    #[test]
+    fn test_in_range_samples_unchanged() {
+        let clamp = Clamp::new(-1.0, 1.0, NanPolicy::Zero).unwrap();
+        assert_eq!(run(&clamp, &[-1.0, -0.5, 0.0, 0.75, 1.0]), vec![-1.0, -0.5, 0.0, 0.75, 1.0]);
+    }
+    
+    #[test]
+    fn test_out_of_range_samples_clamped() {
+        let clamp = Clamp::new(-1.0, 1.0, NanPolicy::Zero).unwrap();
+        assert_eq!(
+            run(&clamp, &[-5.0, 5.0, f32::NEG_INFINITY, f32::INFINITY]),
+            vec![-1.0, 1.0, -1.0, 1.0]
+        );
+    }
+    
+    #[test]
+    fn test_nan_policies() {
+        let input = [f32::NAN, 0.5];
+        let with = |policy| Clamp::new(2.0, 3.0, policy).unwrap();
+        
+        assert_eq!(run(&with(NanPolicy::Zero), &input), vec![0.0, 2.0]);
+        assert_eq!(run(&with(NanPolicy::Min), &input), vec![2.0, 2.0]);
+        assert_eq!(run(&with(NanPolicy::Max), &input), vec![3.0, 2.0]);
+        
+        let passed = run(&with(NanPolicy::Passthrough), &input);
+        assert!(passed[0].is_nan());
+        assert_eq!(passed[1], 2.0);
+    }
+    
+    #[test]
+    fn test_samples_within_epsilon_snap_to_bounds() {
+        let clamp = Clamp::new(0.0, 1.0, NanPolicy::Zero).unwrap();
+        assert_eq!(run(&clamp, &[1.0e-8, 1.0 - f32::EPSILON, 0.5]), vec![0.0, 1.0, 0.5]);
+        
+        let exact = clamp.with_epsilon(0.0).unwrap();
+        assert_eq!(run(&exact, &[1.0e-8, 1.0 - f32::EPSILON]), vec![1.0e-8, 1.0 - f32::EPSILON]);
+    }
+    
+    #[test]
+    fn test_invalid_bounds_rejected() {
+        assert!(Clamp::new(f32::NAN, 1.0, NanPolicy::Zero).is_err());
+        assert!(Clamp::new(0.0, f32::NAN, NanPolicy::Zero).is_err());
+        assert!(Clamp::new(2.0, 1.0, NanPolicy::Zero).is_err());
+    }
+    
+    #[test]
+    fn test_from_params() {
+        let clamp = Clamp::from_params(&json!({"min": 0.0, "max": 1.0, "nan_policy": "Max"})).unwrap();
+        assert_eq!(run(&clamp, &[f32::NAN, 2.0]), vec![1.0, 1.0]);
+        
+        assert!(Clamp::from_params(&json!({"min": 0.0})).is_err());
+        assert!(Clamp::from_params(&json!({"min": 0.0, "max": 1.0, "nan_policy": "Bogus"})).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+//! Cross-correlation of two signals held in memory
+
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, ParameterDefinition, ParameterType};
+use crate::error::CoreError;
+use crate::math;
+use crate::memory::MemoryManager;
+
+/// Cross-correlation of two `f32` memory regions, for time alignment
+///
+/// For a `signal` of length `N` and a `reference` of length `M` the output
+/// holds `r[k] = sum_n signal[n + k] * reference[n]` for every lag `k` in
+/// `-(M - 1)..=N - 1`, as `N + M - 1` little-endian `f32`s with the most
+/// negative lag first. Terms falling outside either signal count as zero,
+/// so the two may differ in length. A peak at lag `k > 0` means `signal`
+/// trails `reference` by `k` samples. The `peak_lag` attribute reports the
+/// lag of the largest value (the most negative on ties) and `min_lag` the
+/// lag of the first output value. The input bytes are ignored.
+#[derive(Clone, Debug)]
+pub struct CrossCorrelate {
+    signal: String,
+    reference: String,
+}
+
+impl CrossCorrelate {
+    pub const ID: &'static str = "cross_correlate";
+    
+    /// Correlate the regions under `signal` and `reference`
+    pub fn new(signal: &str, reference: &str) -> Self {
+        Self {
+            signal: signal.to_string(),
+            reference: reference.to_string(),
+        }
+    }
+    
+    /// Create a correlation from its `signal` and `reference` region keys
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        let signal: String = params::require(params, "signal")?;
+        let reference: String = params::require(params, "reference")?;
+        Ok(Self::new(&signal, &reference))
+    }
+    
+    /// Correlation of `signal` against `reference` at every lag
+    pub fn correlate(signal: &[f32], reference: &[f32]) -> Vec<f32> {
+        math::correlate(signal, reference)
+    }
+    
+    fn region(&self, key: &str, memory: &MemoryManager) -> Result<Vec<f32>, CoreError> {
+        let data = memory
+            .read(key)
+            .ok_or_else(|| CoreError::MemoryKeyMissing(key.to_string()))?;
+        let values = samples::f32_from_bytes(data)?;
+        if values.is_empty() {
+            return Err(CoreError::InvalidInput(format!("region '{}' holds no samples", key)));
+        }
+        Ok(values)
+    }
+}
+
+impl Algorithm for CrossCorrelate {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::new();
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, _input: &[u8], output: &mut Vec<u8>, memory: &mut MemoryManager) -> Result<(), CoreError> {
+        let signal = self.region(&self.signal, memory)?;
+        let reference = self.region(&self.reference, memory)?;
+        for value in Self::correlate(&signal, &reference) {
+            output.extend_from_slice(&value.to_le_bytes());
+        }
+        Ok(())
+    }
+    
+    fn output_attributes(&self, _input: &[u8], output: &[u8], memory: &MemoryManager) -> HashMap<String, String> {
+        let (Some(reference), Ok(values)) = (memory.read(&self.reference), samples::f32_from_bytes(output)) else {
+            return HashMap::new();
+        };
+        let min_lag = 1 - (reference.len() / 4) as i64;
+        let mut attributes = HashMap::from([("min_lag".to_string(), min_lag.to_string())]);
+        // `max_by` keeps the last of equal maxima, so search from the end
+        if let Some(peak) = (0..values.len()).rev().max_by(|&a, &b| values[a].total_cmp(&values[b])) {
+            attributes.insert("peak_lag".to_string(), (min_lag + peak as i64).to_string());
+        }
+        attributes
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        let key = |name: &str, description: &str| ParameterDefinition {
+            name: name.to_string(),
+            parameter_type: ParameterType::String,
+            description: description.to_string(),
+            default_value: None,
+        };
+        AlgorithmMetadata {
+            name: "Cross-Correlate".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Cross-correlation of two f32 memory regions at every lag".to_string(),
+            parameters: vec![
+                key("signal", "Region holding the signal to align"),
+                key("reference", "Region holding the signal aligned against"),
+            ],
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CoreEngine;
+    use serde_json::json;
+    
+    fn pulse(len: usize, at: usize) -> Vec<f32> {
+        (0..len).map(|i| (-((i as f32 - at as f32).powi(2)) / 4.0).exp()).collect()
+    }
+    
+    fn engine_with(signal: &[f32], reference: &[f32]) -> CoreEngine {
+        let mut engine = CoreEngine::new();
+        let correlate = CrossCorrelate::from_params(&json!({ "signal": "mic", "reference": "ref" })).unwrap();
+        engine.register(Box::new(correlate)).unwrap();
+        engine.memory_mut().write("mic", &samples::f32_to_bytes(signal)).unwrap();
+        engine.memory_mut().write("ref", &samples::f32_to_bytes(reference)).unwrap();
+        engine
+    }
+    
+    #[test]
+    fn test_peak_lag_matches_known_offset() {
+        // The reference pulse, delayed by 7 samples in a longer signal
+        let mut engine = engine_with(&pulse(40, 12), &pulse(24, 5));
+        let output = engine.execute(CrossCorrelate::ID, &[]).unwrap();
+        assert_eq!(samples::f32_from_bytes(&output.data).unwrap().len(), 40 + 24 - 1);
+        assert_eq!(output.attributes["min_lag"], "-23");
+        assert_eq!(output.attributes["peak_lag"], "7");
+        
+        // Swapping the roles negates the lag
+        let mut engine = engine_with(&pulse(24, 5), &pulse(40, 12));
+        assert_eq!(engine.execute(CrossCorrelate::ID, &[]).unwrap().attributes["peak_lag"], "-7");
+    }
+    
+    #[test]
+    fn test_correlation_values_at_each_lag() {
+        // lags -1, 0, 1, 2 of [1, 2, 3] against [1, 1]
+        assert_eq!(CrossCorrelate::correlate(&[1.0, 2.0, 3.0], &[1.0, 1.0]), vec![1.0, 3.0, 5.0, 3.0]);
+    }
+    
+    #[test]
+    fn test_missing_region_errors() {
+        let mut engine = engine_with(&[1.0], &[1.0]);
+        engine.memory_mut().deallocate("ref").unwrap();
+        assert_eq!(
+            engine.execute_algorithm(CrossCorrelate::ID, &[]),
+            Err(CoreError::MemoryKeyMissing("ref".to_string()))
+        );
+        assert!(CrossCorrelate::from_params(&json!({ "signal": "mic" })).is_err());
+    }
+}
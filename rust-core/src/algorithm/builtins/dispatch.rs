@@ -0,0 +1,220 @@
+//! Statically dispatched wrapper over every built-in algorithm
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use super::*;
+use crate::algorithm::context::Context;
+use crate::algorithm::{InPlaceAlgorithm, MultiOutputAlgorithm, StreamingAlgorithm};
+
+/// Declares `BuiltinAlgorithm` with one variant per built-in, each created
+/// by the given constructor from a parameter object
+macro_rules! builtin_algorithms {
+    ($($(#[$attr:meta])* $variant:ident => $create:expr,)*) => {
+        /// Any built-in algorithm, dispatched with a `match` instead of a
+        /// vtable
+        ///
+        /// `create` builds built-ins as this enum, so every built-in the
+        /// engine resolves by ID is one. Holding it by value rather than as
+        /// a `dyn Algorithm` lets the processing calls in a tight loop over
+        /// small buffers be resolved, and inlined, at compile time.
+        pub enum BuiltinAlgorithm {
+            $($(#[$attr])* $variant($variant),)*
+        }
+        
+        impl BuiltinAlgorithm {
+            /// Create a built-in algorithm by ID, configured from a
+            /// parameter object
+            pub fn create(algorithm_id: &str, params: &Value) -> Result<Self, CoreError> {
+                match algorithm_id {
+                    $($(#[$attr])* $variant::ID => Ok(Self::$variant($create(params)?)),)*
+                    _ => Err(CoreError::AlgorithmNotFound(algorithm_id.to_string())),
+                }
+            }
+            
+            /// The wrapped algorithm, for the calls made once per execution
+            fn inner(&self) -> &dyn Algorithm {
+                match self {
+                    $($(#[$attr])* Self::$variant(algorithm) => algorithm,)*
+                }
+            }
+        }
+        
+        impl Algorithm for BuiltinAlgorithm {
+            fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+                match self {
+                    $($(#[$attr])* Self::$variant(algorithm) => algorithm.process(input, memory),)*
+                }
+            }
+            
+            fn process_into(&self, input: &[u8], output: &mut Vec<u8>, memory: &mut MemoryManager) -> Result<(), CoreError> {
+                match self {
+                    $($(#[$attr])* Self::$variant(algorithm) => algorithm.process_into(input, output, memory),)*
+                }
+            }
+            
+            fn process_in_context(
+                &self,
+                input: &[u8],
+                output: &mut Vec<u8>,
+                memory: &mut MemoryManager,
+                context: &mut Context<'_>,
+            ) -> Result<(), CoreError> {
+                match self {
+                    $($(#[$attr])* Self::$variant(algorithm) => algorithm.process_in_context(input, output, memory, context),)*
+                }
+            }
+            
+            fn output_attributes(&self, input: &[u8], output: &[u8], memory: &MemoryManager) -> HashMap<String, String> {
+                self.inner().output_attributes(input, output, memory)
+            }
+            
+            fn as_in_place(&self) -> Option<&dyn InPlaceAlgorithm> {
+                self.inner().as_in_place()
+            }
+            
+            fn as_multi_output(&self) -> Option<&dyn MultiOutputAlgorithm> {
+                self.inner().as_multi_output()
+            }
+            
+            fn as_streaming(&self) -> Option<&dyn StreamingAlgorithm> {
+                self.inner().as_streaming()
+            }
+            
+            fn required_capabilities(&self) -> crate::Capabilities {
+                self.inner().required_capabilities()
+            }
+            
+            fn id(&self) -> &str {
+                self.inner().id()
+            }
+            
+            fn metadata(&self) -> AlgorithmMetadata {
+                self.inner().metadata()
+            }
+        }
+    };
+}
+
+builtin_algorithms! {
+    PassThrough => |_: &Value| Ok::<_, CoreError>(PassThrough),
+    Clamp => Clamp::from_params,
+    Scale => Scale::from_params,
+    ThresholdTrigger => ThresholdTrigger::from_params,
+    WindowStats => WindowStats::from_params,
+    LookupTable => LookupTable::from_params,
+    Histogram => Histogram::from_params,
+    IntScaleOffset => IntScaleOffset::from_params,
+    MinMaxDecimate => MinMaxDecimate::from_params,
+    Biquad => Biquad::from_params,
+    CrossCorrelate => CrossCorrelate::from_params,
+    MatMul => MatMul::from_params,
+    PolyphaseResampler => PolyphaseResampler::from_params,
+    RegionReduce => RegionReduce::from_params,
+    PeakDetect => PeakDetect::from_params,
+    Normalize => Normalize::from_params,
+    AddNoise => AddNoise::from_params,
+    QuaternionRotate => QuaternionRotate::from_params,
+    Shuffle => Shuffle::from_params,
+    Kalman => Kalman::from_params,
+    Complementary => Complementary::from_params,
+    Map => Map::from_params,
+    PidController => PidController::from_params,
+    ForwardKinematics => ForwardKinematics::from_params,
+    KinematicJacobian => KinematicJacobian::from_params,
+    InverseKinematics => InverseKinematics::from_params,
+    AStarPlanner => AStarPlanner::from_params,
+    RrtPlanner => RrtPlanner::from_params,
+    WheelOdometry => WheelOdometry::from_params,
+    TrajectoryGenerator => TrajectoryGenerator::from_params,
+    #[cfg(feature = "fft")]
+    Fft => Fft::from_params,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::samples;
+    use serde_json::json;
+    use std::time::Instant;
+    
+    /// Run `algorithm` `iterations` times over `input` through a concrete
+    /// type, so every call is resolved at compile time
+    fn run_static<A: Algorithm>(algorithm: &A, input: &[u8], iterations: usize) -> Vec<u8> {
+        let mut memory = MemoryManager::new();
+        let mut output = Vec::new();
+        for _ in 0..iterations {
+            output.clear();
+            algorithm.process_into(input, &mut output, &mut memory).unwrap();
+        }
+        output
+    }
+    
+    /// `run_static` through a vtable
+    fn run_dynamic(algorithm: &dyn Algorithm, input: &[u8], iterations: usize) -> Vec<u8> {
+        let mut memory = MemoryManager::new();
+        let mut output = Vec::new();
+        for _ in 0..iterations {
+            output.clear();
+            algorithm.process_into(input, &mut output, &mut memory).unwrap();
+        }
+        output
+    }
+    
+    #[test]
+    fn test_enum_dispatch_matches_boxed_dispatch() {
+        let input = samples::f32_to_bytes(&[-3.0, -0.5, 0.0, 0.25, 2.0, f32::NAN]);
+        let clamp = json!({"min": -1.0, "max": 1.0, "nan_policy": "Zero"});
+        let scale = json!({"factor": 2.5});
+        let normalize = json!({"mode": "MinMax"});
+        let decimate = json!({"factor": 2});
+        let cases: [(&str, &Value, Box<dyn Algorithm>); 5] = [
+            (PassThrough::ID, &Value::Null, Box::new(PassThrough)),
+            (Clamp::ID, &clamp, Box::new(Clamp::from_params(&clamp).unwrap())),
+            (Scale::ID, &scale, Box::new(Scale::from_params(&scale).unwrap())),
+            (Normalize::ID, &normalize, Box::new(Normalize::from_params(&normalize).unwrap())),
+            (MinMaxDecimate::ID, &decimate, Box::new(MinMaxDecimate::from_params(&decimate).unwrap())),
+        ];
+        for (id, params, boxed) in cases {
+            let builtin = BuiltinAlgorithm::create(id, params).unwrap();
+            assert_eq!(builtin.id(), id);
+            assert_eq!(builtin.metadata().name, boxed.metadata().name);
+            assert_eq!(
+                run_static(&builtin, &input, 1),
+                run_dynamic(boxed.as_ref(), &input, 1),
+                "{} differs",
+                id
+            );
+        }
+        assert!(BuiltinAlgorithm::create(Clamp::ID, &Value::Null).is_err());
+        assert!(matches!(
+            BuiltinAlgorithm::create("no_such_algorithm", &Value::Null),
+            Err(CoreError::AlgorithmNotFound(_))
+        ));
+        
+        let clamp = BuiltinAlgorithm::create(Clamp::ID, &json!({"min": 0.0, "max": 1.0})).unwrap();
+        assert!(clamp.as_in_place().is_some());
+    }
+    
+    #[test]
+    fn test_enum_path_over_small_buffers() {
+        // Benchmark-style: `run_static` is monomorphized for the enum, so
+        // its loop makes no vtable call. Timings are logged rather than
+        // asserted, as they depend on the machine.
+        let input = samples::f32_to_bytes(&[0.5; 8]);
+        let params = json!({"factor": 2.0});
+        let builtin = BuiltinAlgorithm::create(Scale::ID, &params).unwrap();
+        let boxed: Box<dyn Algorithm> = Box::new(Scale::from_params(&params).unwrap());
+        
+        let start = Instant::now();
+        let by_enum = run_static(&builtin, &input, 10_000);
+        let enum_time = start.elapsed();
+        let start = Instant::now();
+        let by_vtable = run_dynamic(boxed.as_ref(), &input, 10_000);
+        log::info!("10000 scale calls: enum {:?}, vtable {:?}", enum_time, start.elapsed());
+        
+        assert_eq!(by_enum, by_vtable);
+        assert_eq!(samples::f32_from_bytes(&by_enum).unwrap(), vec![1.0; 8]);
+    }
+}
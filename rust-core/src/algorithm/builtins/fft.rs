@@ -0,0 +1,165 @@
+//! Magnitude spectrum of a sampled signal
+
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, OutputSizeHint, ParameterDefinition, ParameterType, Pure};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// One-sided magnitude spectrum of `f32` input
+///
+/// Input whose length is not a power of two is zero-padded up to the next
+/// power of two, so `N` below is the padded length. Output holds the `N / 2 + 1`
+/// unnormalised magnitudes `|X[k]|` for bins `0..=N / 2` as little-endian
+/// `f32`s. Bin `k` sits at `k * sample_rate / N` Hz; `sample_rate` is used
+/// only to report that resolution in the `bin_resolution_hz` attribute,
+/// alongside the padded `fft_size`.
+#[derive(Clone, Debug)]
+pub struct Fft {
+    sample_rate: f32,
+}
+
+impl Fft {
+    pub const ID: &'static str = "fft";
+    
+    /// Create a transform for samples taken at `sample_rate` Hz
+    pub fn new(sample_rate: f32) -> Result<Self, CoreError> {
+        if !(sample_rate.is_finite() && sample_rate > 0.0) {
+            return Err(CoreError::InvalidParameter(format!(
+                "sample_rate must be positive, got {}",
+                sample_rate
+            )));
+        }
+        Ok(Self { sample_rate })
+    }
+    
+    /// Create a transform from its optional `sample_rate` parameter
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Self::new(params::get(params, "sample_rate")?.unwrap_or(1.0))
+    }
+    
+    /// Transform length used for `len` input samples
+    pub fn fft_size(len: usize) -> usize {
+        len.next_power_of_two()
+    }
+    
+    /// Compute the magnitude spectrum of `signal`
+    pub fn magnitudes(&self, signal: &[f32]) -> Vec<f32> {
+        if signal.is_empty() {
+            return Vec::new();
+        }
+        let size = Self::fft_size(signal.len());
+        let mut buffer: Vec<Complex<f32>> = signal.iter().map(|&re| Complex::new(re, 0.0)).collect();
+        buffer.resize(size, Complex::new(0.0, 0.0));
+        
+        FftPlanner::new().plan_fft_forward(size).process(&mut buffer);
+        buffer[..=size / 2].iter().map(|bin| bin.norm()).collect()
+    }
+}
+
+impl Algorithm for Fft {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::new();
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        for magnitude in self.magnitudes(&samples::f32_from_bytes(input)?) {
+            output.extend_from_slice(&magnitude.to_le_bytes());
+        }
+        Ok(())
+    }
+    
+    fn output_attributes(&self, input: &[u8], _output: &[u8], _memory: &MemoryManager) -> HashMap<String, String> {
+        let size = Self::fft_size(input.len() / 4);
+        HashMap::from([
+            ("fft_size".to_string(), size.to_string()),
+            ("bin_resolution_hz".to_string(), (self.sample_rate / size as f32).to_string()),
+        ])
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "FFT".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Magnitude spectrum of f32 samples, zero-padded to a power of two".to_string(),
+            parameters: vec![ParameterDefinition {
+                name: "sample_rate".to_string(),
+                parameter_type: ParameterType::Float,
+                description: "Sampling frequency in Hz, used to report bin resolution".to_string(),
+                default_value: Some("1.0".to_string()),
+            }],
+            output_size_hint: OutputSizeHint::Ratio(0.5),
+            min_input_bytes: 4,
+            ..Default::default()
+        }
+    }
+}
+
+impl Pure for Fft {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CoreEngine;
+    
+    fn sine(len: usize, cycles: f32) -> Vec<f32> {
+        (0..len)
+            .map(|i| (2.0 * std::f32::consts::PI * cycles * i as f32 / len as f32).sin())
+            .collect()
+    }
+    
+    fn peak(spectrum: &[f32]) -> usize {
+        (0..spectrum.len())
+            .max_by(|&a, &b| spectrum[a].total_cmp(&spectrum[b]))
+            .unwrap()
+    }
+    
+    #[test]
+    fn test_sine_peaks_at_expected_bin() {
+        // 50 Hz sampled at 800 Hz over 64 samples: 4 cycles, 12.5 Hz per bin
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(Fft::new(800.0).unwrap())).unwrap();
+        let output = engine.execute(Fft::ID, &samples::f32_to_bytes(&sine(64, 4.0))).unwrap();
+        
+        let spectrum = samples::f32_from_bytes(&output.data).unwrap();
+        assert_eq!(spectrum.len(), 33);
+        assert_eq!(peak(&spectrum), 4);
+        assert!((spectrum[4] - 32.0).abs() < 1e-3);
+        assert_eq!(output.attributes["fft_size"], "64");
+        assert_eq!(output.attributes["bin_resolution_hz"], "12.5");
+    }
+    
+    #[test]
+    fn test_non_power_of_two_is_zero_padded() {
+        let fft = Fft::new(1.0).unwrap();
+        let spectrum = fft.magnitudes(&sine(48, 6.0));
+        let expected = fft.magnitudes(&[sine(48, 6.0), vec![0.0; 16]].concat());
+        assert_eq!(spectrum.len(), 33);
+        assert_eq!(spectrum, expected);
+        assert_eq!(peak(&spectrum), 8);
+    }
+    
+    #[test]
+    fn test_empty_input_rejected() {
+        let mut engine = CoreEngine::new();
+        assert_eq!(
+            engine.execute_algorithm(Fft::ID, &[]),
+            Err(CoreError::InputTooSmall { required: 4, actual: 0 })
+        );
+    }
+    
+    #[test]
+    fn test_invalid_sample_rate_rejected() {
+        assert!(Fft::new(0.0).is_err());
+        assert!(Fft::from_params(&serde_json::json!({ "sample_rate": -10.0 })).is_err());
+    }
+}
@@ -0,0 +1,162 @@
+//! Distribution of sample values
+
+use serde_json::Value;
+
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, OutputSizeHint, ParameterDefinition, ParameterType, Pure};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// Counts `f32` samples into `bins` equal-width bins over `[min, max]`
+///
+/// Output is `bins + 2` little-endian `u32` counts: the bins in ascending
+/// order, then an underflow counter for samples below `min` and an overflow
+/// counter for samples above `max`. A sample equal to `max` falls in the
+/// last bin. NaN samples are not counted anywhere.
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    bins: usize,
+    min: f32,
+    max: f32,
+}
+
+impl Histogram {
+    pub const ID: &'static str = "histogram";
+    
+    /// Create a histogram, rejecting zero bins or an empty or non-finite range
+    pub fn new(bins: usize, min: f32, max: f32) -> Result<Self, CoreError> {
+        if bins == 0 {
+            return Err(CoreError::InvalidParameter("histogram needs at least 1 bin".to_string()));
+        }
+        if !(min.is_finite() && max.is_finite() && min < max) {
+            return Err(CoreError::InvalidParameter(format!(
+                "histogram range [{}, {}] must be finite and non-empty",
+                min, max
+            )));
+        }
+        Ok(Self { bins, min, max })
+    }
+    
+    /// Create a histogram from its `bins`, `min` and `max` parameters
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Self::new(
+            params::require(params, "bins")?,
+            params::require(params, "min")?,
+            params::require(params, "max")?,
+        )
+    }
+    
+    /// Count samples, returning the bins followed by underflow and overflow
+    pub fn counts(&self, signal: &[f32]) -> Vec<u32> {
+        let mut counts = vec![0u32; self.bins + 2];
+        let width = (self.max as f64 - self.min as f64) / self.bins as f64;
+        for &sample in signal {
+            let slot = if sample.is_nan() {
+                continue;
+            } else if sample < self.min {
+                self.bins
+            } else if sample > self.max {
+                self.bins + 1
+            } else {
+                // Rounding can push samples near `max` one bin too far
+                (((sample as f64 - self.min as f64) / width) as usize).min(self.bins - 1)
+            };
+            counts[slot] = counts[slot].saturating_add(1);
+        }
+        counts
+    }
+}
+
+impl Algorithm for Histogram {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::with_capacity((self.bins + 2) * 4);
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        for count in self.counts(&samples::f32_from_bytes(input)?) {
+            output.extend_from_slice(&count.to_le_bytes());
+        }
+        Ok(())
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Histogram".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Equal-width bin counts of f32 samples with underflow and overflow".to_string(),
+            parameters: vec![
+                ParameterDefinition {
+                    name: "bins".to_string(),
+                    parameter_type: ParameterType::Integer,
+                    description: "Number of equal-width bins".to_string(),
+                    default_value: None,
+                },
+                ParameterDefinition {
+                    name: "min".to_string(),
+                    parameter_type: ParameterType::Float,
+                    description: "Lower edge of the first bin".to_string(),
+                    default_value: None,
+                },
+                ParameterDefinition {
+                    name: "max".to_string(),
+                    parameter_type: ParameterType::Float,
+                    description: "Upper edge of the last bin".to_string(),
+                    default_value: None,
+                },
+            ],
+            output_size_hint: OutputSizeHint::Fixed((self.bins + 2) * 4),
+            ..Default::default()
+        }
+    }
+}
+
+impl Pure for Histogram {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    fn run(histogram: &Histogram, signal: &[f32]) -> Vec<u32> {
+        let output = histogram
+            .process(&samples::f32_to_bytes(signal), &mut MemoryManager::new())
+            .unwrap();
+        output
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect()
+    }
+    
+    #[test]
+    fn test_uniform_input_fills_bins_evenly() {
+        let histogram = Histogram::new(4, 0.0, 1.0).unwrap();
+        let signal: Vec<f32> = (0..1000).map(|i| i as f32 / 999.0).collect();
+        let counts = run(&histogram, &signal);
+        
+        assert_eq!(counts.len(), 6);
+        for &count in &counts[..4] {
+            assert!((245..=255).contains(&count), "uneven bin: {:?}", counts);
+        }
+        assert_eq!(counts[..4].iter().sum::<u32>(), 1000);
+        assert_eq!(&counts[4..], &[0, 0]);
+    }
+    
+    #[test]
+    fn test_out_of_range_values_hit_overflow_counters() {
+        let histogram = Histogram::from_params(&json!({ "bins": 2, "min": -1.0, "max": 1.0 })).unwrap();
+        let counts = run(&histogram, &[-5.0, -1.0, -0.5, 0.0, 1.0, 1.5, 2.0, f32::INFINITY, f32::NAN]);
+        assert_eq!(counts, vec![2, 2, 1, 3]);
+    }
+    
+    #[test]
+    fn test_invalid_configuration_rejected() {
+        assert!(Histogram::new(0, 0.0, 1.0).is_err());
+        assert!(Histogram::new(4, 1.0, 1.0).is_err());
+        assert!(Histogram::new(4, 0.0, f32::INFINITY).is_err());
+    }
+}
@@ -0,0 +1,202 @@
+//! Integer gain and offset for raw sensor counts
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, OutputSizeHint, InPlaceAlgorithm, ParameterDefinition, ParameterType, Pure};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// What happens when an integer result does not fit its type
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OverflowMode {
+    /// Clamp to the nearest representable value, as suits sensor readings
+    #[default]
+    Saturate,
+    /// Keep the low bits, two's-complement style
+    Wrap,
+    /// Fail with `CoreError::ArithmeticOverflow` on the first overflow
+    Error,
+}
+
+/// Computes `sample * scale + offset` for each little-endian `i16` sample
+///
+/// The result is computed exactly and only then fitted back into `i16`
+/// according to the overflow mode, so an offset can pull an out-of-range
+/// product back into range.
+#[derive(Clone, Debug)]
+pub struct IntScaleOffset {
+    scale: i32,
+    offset: i32,
+    overflow: OverflowMode,
+}
+
+impl IntScaleOffset {
+    pub const ID: &'static str = "int_scale_offset";
+    
+    /// Create the transform
+    pub fn new(scale: i32, offset: i32, overflow: OverflowMode) -> Self {
+        Self { scale, offset, overflow }
+    }
+    
+    /// Create the transform from its `scale`, `offset` and `overflow`
+    /// parameters, defaulting to the identity with saturation
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Ok(Self::new(
+            params::get(params, "scale")?.unwrap_or(1),
+            params::get(params, "offset")?.unwrap_or(0),
+            params::get(params, "overflow")?.unwrap_or_default(),
+        ))
+    }
+    
+    /// How results outside the `i16` range are handled
+    pub fn overflow(&self) -> OverflowMode {
+        self.overflow
+    }
+    
+    /// Transform a single sample
+    pub fn apply(&self, sample: i16) -> Result<i16, CoreError> {
+        let exact = sample as i64 * self.scale as i64 + self.offset as i64;
+        match self.overflow {
+            OverflowMode::Saturate => Ok(exact.clamp(i16::MIN as i64, i16::MAX as i64) as i16),
+            OverflowMode::Wrap => Ok(exact as i16),
+            OverflowMode::Error => i16::try_from(exact).map_err(|_| {
+                CoreError::ArithmeticOverflow(format!(
+                    "{} * {} + {} = {} does not fit in i16",
+                    sample, self.scale, self.offset, exact
+                ))
+            }),
+        }
+    }
+}
+
+impl Algorithm for IntScaleOffset {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::with_capacity(input.len());
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        for sample in samples::i16_from_bytes(input)? {
+            output.extend_from_slice(&self.apply(sample)?.to_le_bytes());
+        }
+        Ok(())
+    }
+    
+    fn as_in_place(&self) -> Option<&dyn InPlaceAlgorithm> {
+        Some(self)
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Integer Scale/Offset".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Applies sample * scale + offset to i16 samples with a chosen overflow mode".to_string(),
+            parameters: vec![
+                ParameterDefinition {
+                    name: "scale".to_string(),
+                    parameter_type: ParameterType::Integer,
+                    description: "Factor each sample is multiplied by".to_string(),
+                    default_value: Some("1".to_string()),
+                },
+                ParameterDefinition {
+                    name: "offset".to_string(),
+                    parameter_type: ParameterType::Integer,
+                    description: "Value added after scaling".to_string(),
+                    default_value: Some("0".to_string()),
+                },
+                ParameterDefinition {
+                    name: "overflow".to_string(),
+                    parameter_type: ParameterType::String,
+                    description: "Saturate, Wrap or Error".to_string(),
+                    default_value: Some("Saturate".to_string()),
+                },
+            ],
+            output_size_hint: OutputSizeHint::SameAsInput,
+            ..Default::default()
+        }
+    }
+}
+
+impl InPlaceAlgorithm for IntScaleOffset {
+    fn process_in_place(&self, buf: &mut [u8], _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        let input = samples::i16_from_bytes(buf)?;
+        // Fail before touching the buffer so an overflow leaves it intact
+        if self.overflow == OverflowMode::Error {
+            for &sample in &input {
+                self.apply(sample)?;
+            }
+        }
+        for (chunk, sample) in buf.chunks_exact_mut(2).zip(input) {
+            chunk.copy_from_slice(&self.apply(sample)?.to_le_bytes());
+        }
+        Ok(())
+    }
+}
+
+impl Pure for IntScaleOffset {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    fn run(transform: &IntScaleOffset, input: &[i16]) -> Result<Vec<i16>, CoreError> {
+        let output = transform.process(&samples::i16_to_bytes(input), &mut MemoryManager::new())?;
+        samples::i16_from_bytes(&output)
+    }
+    
+    fn with(overflow: OverflowMode) -> IntScaleOffset {
+        IntScaleOffset::new(2, 1, overflow)
+    }
+    
+    #[test]
+    fn test_saturate_at_boundary() {
+        let transform = with(OverflowMode::Saturate);
+        assert_eq!(run(&transform, &[16383, 16384, -16384, -16385]).unwrap(), vec![32767, 32767, -32767, -32768]);
+    }
+    
+    #[test]
+    fn test_wrap_at_boundary() {
+        let transform = with(OverflowMode::Wrap);
+        assert_eq!(run(&transform, &[16383, 16384, -16385]).unwrap(), vec![32767, -32767, -32769i32 as i16]);
+    }
+    
+    #[test]
+    fn test_error_on_first_overflow() {
+        let transform = with(OverflowMode::Error);
+        assert_eq!(run(&transform, &[16383, -16384]).unwrap(), vec![32767, -32767]);
+        assert!(matches!(run(&transform, &[0, 16384]), Err(CoreError::ArithmeticOverflow(_))));
+    }
+    
+    #[test]
+    fn test_offset_can_bring_product_back_in_range() {
+        let transform = IntScaleOffset::new(4, -40000, OverflowMode::Error);
+        assert_eq!(run(&transform, &[10000]).unwrap(), vec![0]);
+    }
+    
+    #[test]
+    fn test_in_place_overflow_leaves_buffer_intact() {
+        let mut buf = samples::i16_to_bytes(&[1, 16384]);
+        let original = buf.clone();
+        assert!(with(OverflowMode::Error).process_in_place(&mut buf, &mut MemoryManager::new()).is_err());
+        assert_eq!(buf, original);
+        
+        with(OverflowMode::Saturate).process_in_place(&mut buf, &mut MemoryManager::new()).unwrap();
+        assert_eq!(samples::i16_from_bytes(&buf).unwrap(), vec![3, 32767]);
+    }
+    
+    #[test]
+    fn test_from_params() {
+        let transform = IntScaleOffset::from_params(&json!({ "scale": 3, "overflow": "Wrap" })).unwrap();
+        assert_eq!(transform.overflow(), OverflowMode::Wrap);
+        assert_eq!(run(&transform, &[-2, 11000]).unwrap(), vec![-6, 33000i32 as i16]);
+        assert_eq!(IntScaleOffset::from_params(&Value::Null).unwrap().overflow(), OverflowMode::Saturate);
+        assert!(IntScaleOffset::from_params(&json!({ "overflow": "Clip" })).is_err());
+    }
+}
@@ -0,0 +1,257 @@
+//! Piecewise-linear calibration curves
+
+use serde_json::Value;
+
+use crate::algorithm::tolerance::{self, DEFAULT_EPSILON};
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, OutputSizeHint, ParameterDefinition, ParameterType};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// Where a `LookupTable` finds its curve
+#[derive(Clone, Debug)]
+enum TableSource {
+    /// Validated points fixed at construction
+    Points(Vec<(f32, f32)>),
+    /// A memory region of packed little-endian `f32` `(input, output)`
+    /// pairs, read and validated on every call
+    Region(String),
+}
+
+/// Maps each `f32` sample through a piecewise-linear curve
+///
+/// The curve is a table of `(input, output)` points with inputs strictly
+/// increasing. Samples between two points are linearly interpolated, samples
+/// beyond either end clamp to that end's output, and NaN samples stay NaN.
+/// Samples within epsilon of a point's input map to exactly its output.
+#[derive(Clone, Debug)]
+pub struct LookupTable {
+    source: TableSource,
+    epsilon: f32,
+}
+
+impl LookupTable {
+    pub const ID: &'static str = "lookup_table";
+    
+    /// Create a lookup table from fixed points
+    pub fn new(points: Vec<(f32, f32)>) -> Result<Self, CoreError> {
+        validate(&points)?;
+        Ok(Self {
+            source: TableSource::Points(points),
+            epsilon: DEFAULT_EPSILON,
+        })
+    }
+    
+    /// Create a lookup table whose points are read from a memory region
+    ///
+    /// The region can be recalibrated between calls without rebuilding the
+    /// algorithm.
+    pub fn from_region(key: &str) -> Self {
+        Self {
+            source: TableSource::Region(key.to_string()),
+            epsilon: DEFAULT_EPSILON,
+        }
+    }
+    
+    /// Snap samples within `epsilon` of a point's input onto that point
+    pub fn with_epsilon(mut self, epsilon: f32) -> Result<Self, CoreError> {
+        self.epsilon = tolerance::validate_epsilon(epsilon)?;
+        Ok(self)
+    }
+    
+    /// Create a lookup table from exactly one of its `points` (a list of
+    /// `[input, output]` pairs) or `region` parameters, and an optional
+    /// `epsilon`
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        let points: Option<Vec<(f32, f32)>> = params::get(params, "points")?;
+        let region: Option<String> = params::get(params, "region")?;
+        let table = match (points, region) {
+            (Some(points), None) => Self::new(points)?,
+            (None, Some(region)) => Self::from_region(&region),
+            _ => {
+                return Err(CoreError::InvalidParameter(
+                    "lookup table needs exactly one of 'points' or 'region'".to_string(),
+                ))
+            }
+        };
+        table.with_epsilon(params::get(params, "epsilon")?.unwrap_or(DEFAULT_EPSILON))
+    }
+    
+    /// Map samples through the curve, resolving the table from `memory` if
+    /// it lives in a region
+    pub fn map(&self, signal: &[f32], memory: &MemoryManager) -> Result<Vec<f32>, CoreError> {
+        let region_points;
+        let points = match &self.source {
+            TableSource::Points(points) => points,
+            TableSource::Region(key) => {
+                let data = memory
+                    .read(key)
+                    .ok_or_else(|| CoreError::MemoryKeyMissing(key.clone()))?;
+                let values = samples::f32_from_bytes(data)?;
+                if !values.len().is_multiple_of(2) {
+                    return Err(CoreError::InvalidParameter(format!(
+                        "lookup table region '{}' holds an odd number of values",
+                        key
+                    )));
+                }
+                region_points = values.chunks(2).map(|pair| (pair[0], pair[1])).collect::<Vec<_>>();
+                validate(&region_points)?;
+                &region_points
+            }
+        };
+        Ok(signal
+            .iter()
+            .map(|&sample| interpolate(points, sample, self.epsilon))
+            .collect())
+    }
+}
+
+/// Check that a table is non-empty with finite, strictly increasing inputs
+fn validate(points: &[(f32, f32)]) -> Result<(), CoreError> {
+    if points.is_empty() {
+        return Err(CoreError::InvalidParameter("lookup table is empty".to_string()));
+    }
+    if let Some(&(x, _)) = points.iter().find(|(x, _)| !x.is_finite()) {
+        return Err(CoreError::InvalidParameter(format!("lookup table input {} is not finite", x)));
+    }
+    if let Some(pair) = points.windows(2).find(|pair| pair[0].0 >= pair[1].0) {
+        return Err(CoreError::InvalidParameter(format!(
+            "lookup table inputs must be strictly increasing, got {} then {}",
+            pair[0].0, pair[1].0
+        )));
+    }
+    Ok(())
+}
+
+fn interpolate(points: &[(f32, f32)], sample: f32, epsilon: f32) -> f32 {
+    if sample.is_nan() {
+        return sample;
+    }
+    // Index of the first point whose input is above the sample
+    let upper = points.partition_point(|&(x, _)| tolerance::approx_le(x, sample, epsilon));
+    if upper == 0 {
+        return points[0].1;
+    }
+    if upper == points.len() {
+        return points[upper - 1].1;
+    }
+    let (x0, y0) = points[upper - 1];
+    if tolerance::approx_eq(sample, x0, epsilon) {
+        return y0;
+    }
+    let (x1, y1) = points[upper];
+    y0 + (sample - x0) / (x1 - x0) * (y1 - y0)
+}
+
+impl Algorithm for LookupTable {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::with_capacity(input.len());
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, memory: &mut MemoryManager) -> Result<(), CoreError> {
+        for value in self.map(&samples::f32_from_bytes(input)?, memory)? {
+            output.extend_from_slice(&value.to_le_bytes());
+        }
+        Ok(())
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Lookup Table".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Piecewise-linear mapping of f32 samples through a calibration table".to_string(),
+            parameters: vec![
+                ParameterDefinition {
+                    name: "points".to_string(),
+                    parameter_type: ParameterType::Array,
+                    description: "[input, output] pairs with strictly increasing inputs".to_string(),
+                    default_value: None,
+                },
+                ParameterDefinition {
+                    name: "region".to_string(),
+                    parameter_type: ParameterType::String,
+                    description: "Memory region holding packed f32 (input, output) pairs".to_string(),
+                    default_value: None,
+                },
+                ParameterDefinition {
+                    name: "epsilon".to_string(),
+                    parameter_type: ParameterType::Float,
+                    description: "Tolerance within which a sample maps to a point's exact output".to_string(),
+                    default_value: Some(DEFAULT_EPSILON.to_string()),
+                },
+            ],
+            output_size_hint: OutputSizeHint::SameAsInput,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    fn curve() -> LookupTable {
+        LookupTable::from_params(&json!({ "points": [[0.0, 10.0], [1.0, 20.0], [3.0, 0.0]] })).unwrap()
+    }
+    
+    fn map(table: &LookupTable, signal: &[f32]) -> Vec<f32> {
+        table.map(signal, &MemoryManager::new()).unwrap()
+    }
+    
+    #[test]
+    fn test_exact_points() {
+        assert_eq!(map(&curve(), &[0.0, 1.0, 3.0]), vec![10.0, 20.0, 0.0]);
+    }
+    
+    #[test]
+    fn test_interpolated_midpoints() {
+        assert_eq!(map(&curve(), &[0.5, 2.0, 2.5]), vec![15.0, 10.0, 5.0]);
+    }
+    
+    #[test]
+    fn test_out_of_range_clamps_to_endpoints() {
+        assert_eq!(map(&curve(), &[-5.0, 3.5, f32::INFINITY]), vec![10.0, 0.0, 0.0]);
+        assert!(map(&curve(), &[f32::NAN])[0].is_nan());
+    }
+    
+    #[test]
+    fn test_samples_within_epsilon_of_a_point_map_exactly() {
+        let near = [0.995, 1.0, 1.005];
+        let loose = curve().with_epsilon(0.01).unwrap();
+        assert_eq!(map(&loose, &near), vec![20.0, 20.0, 20.0]);
+        
+        let interpolated = map(&curve(), &near);
+        assert!(interpolated[0] < 20.0 && interpolated[2] < 20.0);
+        assert!(curve().with_epsilon(f32::NAN).is_err());
+    }
+    
+    #[test]
+    fn test_non_increasing_table_rejected() {
+        assert!(LookupTable::new(vec![(0.0, 1.0), (0.0, 2.0)]).is_err());
+        assert!(LookupTable::new(vec![(1.0, 1.0), (0.0, 2.0)]).is_err());
+        assert!(LookupTable::new(Vec::new()).is_err());
+        assert!(LookupTable::from_params(&json!({})).is_err());
+    }
+    
+    #[test]
+    fn test_table_from_memory_region() {
+        let mut memory = MemoryManager::new();
+        memory
+            .write("curve", &samples::f32_to_bytes(&[0.0, 0.0, 2.0, 4.0]))
+            .unwrap();
+        let table = LookupTable::from_params(&json!({ "region": "curve" })).unwrap();
+        let output = table.process(&samples::f32_to_bytes(&[1.0]), &mut memory).unwrap();
+        assert_eq!(samples::f32_from_bytes(&output).unwrap(), vec![2.0]);
+        
+        memory
+            .write("curve", &samples::f32_to_bytes(&[2.0, 0.0, 0.0, 4.0]))
+            .unwrap();
+        assert!(table.process(&samples::f32_to_bytes(&[1.0]), &mut memory).is_err());
+    }
+}
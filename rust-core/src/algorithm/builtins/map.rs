@@ -0,0 +1,142 @@
+//! Apply an elementwise function to samples
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, InPlaceAlgorithm, OutputSizeHint, ParameterDefinition, ParameterType, Pure};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// Function `Map` applies to every sample
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MapFunction {
+    Abs,
+    Negate,
+    Square,
+    Sqrt,
+    Exp,
+    /// Natural logarithm
+    Ln,
+    Sin,
+    Cos,
+    Tanh,
+    /// `1 / (1 + e^-x)`
+    Sigmoid,
+    /// `max(x, 0)`
+    Relu,
+}
+
+impl MapFunction {
+    pub fn apply(&self, x: f32) -> f32 {
+        match self {
+            MapFunction::Abs => x.abs(),
+            MapFunction::Negate => -x,
+            MapFunction::Square => x * x,
+            MapFunction::Sqrt => x.sqrt(),
+            MapFunction::Exp => x.exp(),
+            MapFunction::Ln => x.ln(),
+            MapFunction::Sin => x.sin(),
+            MapFunction::Cos => x.cos(),
+            MapFunction::Tanh => x.tanh(),
+            MapFunction::Sigmoid => 1.0 / (1.0 + (-x).exp()),
+            MapFunction::Relu => x.max(0.0),
+        }
+    }
+}
+
+/// Replaces each `f32` sample with `function` of it, the building block for
+/// nonlinear stages in declarative definitions
+///
+/// Inputs outside the function's domain, such as the square root of a
+/// negative sample, give NaN as in `f32`'s own methods.
+#[derive(Clone, Debug)]
+pub struct Map {
+    function: MapFunction,
+}
+
+impl Map {
+    pub const ID: &'static str = "map";
+    
+    pub fn new(function: MapFunction) -> Self {
+        Self { function }
+    }
+    
+    /// Create the algorithm from its `function` parameter
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Ok(Self::new(params::require(params, "function")?))
+    }
+    
+    pub fn function(&self) -> MapFunction {
+        self.function
+    }
+}
+
+impl Algorithm for Map {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::with_capacity(input.len());
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        for sample in samples::f32_from_bytes(input)? {
+            output.extend_from_slice(&self.function.apply(sample).to_le_bytes());
+        }
+        Ok(())
+    }
+    
+    fn as_in_place(&self) -> Option<&dyn InPlaceAlgorithm> {
+        Some(self)
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Map".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Applies an elementwise function to f32 samples".to_string(),
+            parameters: vec![ParameterDefinition {
+                name: "function".to_string(),
+                parameter_type: ParameterType::String,
+                description: "Function applied to every sample, such as \"Abs\" or \"Sigmoid\"".to_string(),
+                default_value: None,
+            }],
+            output_size_hint: OutputSizeHint::SameAsInput,
+            ..Default::default()
+        }
+    }
+}
+
+impl InPlaceAlgorithm for Map {
+    fn process_in_place(&self, buf: &mut [u8], _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        samples::map_f32_in_place(buf, |sample| self.function.apply(sample))
+    }
+}
+
+impl Pure for Map {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[test]
+    fn test_applies_function_to_every_sample() {
+        let input = samples::f32_to_bytes(&[-2.0, 0.0, 3.0]);
+        let mut memory = MemoryManager::new();
+        let relu = Map::from_params(&json!({"function": "Relu"})).unwrap();
+        let output = relu.process(&input, &mut memory).unwrap();
+        assert_eq!(samples::f32_from_bytes(&output).unwrap(), vec![0.0, 0.0, 3.0]);
+        
+        let mut buf = input.clone();
+        Map::new(MapFunction::Square).process_in_place(&mut buf, &mut memory).unwrap();
+        assert_eq!(samples::f32_from_bytes(&buf).unwrap(), vec![4.0, 0.0, 9.0]);
+        assert_eq!(MapFunction::Sigmoid.apply(0.0), 0.5);
+        
+        assert!(Map::from_params(&json!({"function": "Cube"})).is_err());
+        assert!(Map::from_params(&json!({})).is_err());
+    }
+}
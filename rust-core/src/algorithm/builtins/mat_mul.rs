@@ -0,0 +1,168 @@
+//! Product of two small matrices held in memory
+
+use serde_json::Value;
+
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, OutputSizeHint, ParameterDefinition, ParameterType};
+use crate::error::CoreError;
+use crate::math;
+use crate::memory::MemoryManager;
+
+/// Product of two row-major `f32` matrices in memory regions, for
+/// kinematics
+///
+/// Each region must hold exactly the `rows * cols` elements its shape
+/// parameters declare. The output is the `lhs_rows x rhs_cols` product as
+/// row-major little-endian `f32`s. The inner loop runs over contiguous rows
+/// of `rhs`, which the compiler vectorizes. The input bytes are ignored.
+#[derive(Clone, Debug)]
+pub struct MatMul {
+    lhs: String,
+    rhs: String,
+    // (rows, cols) of each operand
+    lhs_shape: (usize, usize),
+    rhs_shape: (usize, usize),
+}
+
+impl MatMul {
+    pub const ID: &'static str = "mat_mul";
+    
+    /// Multiply the `lhs` region by the `rhs` region, failing with
+    /// `CoreError::SchemaMismatch` unless `lhs` has as many columns as `rhs`
+    /// has rows
+    pub fn new(lhs: &str, lhs_shape: (usize, usize), rhs: &str, rhs_shape: (usize, usize)) -> Result<Self, CoreError> {
+        if lhs_shape.1 != rhs_shape.0 {
+            return Err(CoreError::SchemaMismatch(format!(
+                "cannot multiply {}x{} by {}x{}: inner dimensions {} and {} differ",
+                lhs_shape.0, lhs_shape.1, rhs_shape.0, rhs_shape.1, lhs_shape.1, rhs_shape.0
+            )));
+        }
+        Ok(Self {
+            lhs: lhs.to_string(),
+            rhs: rhs.to_string(),
+            lhs_shape,
+            rhs_shape,
+        })
+    }
+    
+    /// Create a product from its `lhs` and `rhs` region keys and their
+    /// `*_rows` and `*_cols` shapes
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        let lhs: String = params::require(params, "lhs")?;
+        let rhs: String = params::require(params, "rhs")?;
+        Self::new(
+            &lhs,
+            (params::require(params, "lhs_rows")?, params::require(params, "lhs_cols")?),
+            &rhs,
+            (params::require(params, "rhs_rows")?, params::require(params, "rhs_cols")?),
+        )
+    }
+    
+    /// Product of row-major `lhs` (`rows x inner`) and `rhs` (`inner x cols`)
+    pub fn multiply(lhs: &[f32], rhs: &[f32], rows: usize, inner: usize, cols: usize) -> Vec<f32> {
+        math::mat_mul(lhs, rhs, rows, inner, cols)
+    }
+    
+    fn region(key: &str, (rows, cols): (usize, usize), memory: &MemoryManager) -> Result<Vec<f32>, CoreError> {
+        let data = memory
+            .read(key)
+            .ok_or_else(|| CoreError::MemoryKeyMissing(key.to_string()))?;
+        let values = samples::f32_from_bytes(data)?;
+        if Some(values.len()) != rows.checked_mul(cols) {
+            return Err(CoreError::SchemaMismatch(format!(
+                "region '{}' holds {} elements, not a {}x{} matrix",
+                key,
+                values.len(),
+                rows,
+                cols
+            )));
+        }
+        Ok(values)
+    }
+}
+
+impl Algorithm for MatMul {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::new();
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, _input: &[u8], output: &mut Vec<u8>, memory: &mut MemoryManager) -> Result<(), CoreError> {
+        let lhs = Self::region(&self.lhs, self.lhs_shape, memory)?;
+        let rhs = Self::region(&self.rhs, self.rhs_shape, memory)?;
+        let (rows, inner) = self.lhs_shape;
+        for value in Self::multiply(&lhs, &rhs, rows, inner, self.rhs_shape.1) {
+            output.extend_from_slice(&value.to_le_bytes());
+        }
+        Ok(())
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        let parameter = |name: &str, parameter_type: ParameterType, description: &str| ParameterDefinition {
+            name: name.to_string(),
+            parameter_type,
+            description: description.to_string(),
+            default_value: None,
+        };
+        AlgorithmMetadata {
+            name: "Matrix Multiply".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Product of two row-major f32 matrices in memory regions".to_string(),
+            parameters: vec![
+                parameter("lhs", ParameterType::String, "Region holding the left operand"),
+                parameter("lhs_rows", ParameterType::Integer, "Rows of the left operand"),
+                parameter("lhs_cols", ParameterType::Integer, "Columns of the left operand"),
+                parameter("rhs", ParameterType::String, "Region holding the right operand"),
+                parameter("rhs_rows", ParameterType::Integer, "Rows of the right operand"),
+                parameter("rhs_cols", ParameterType::Integer, "Columns of the right operand"),
+            ],
+            output_size_hint: OutputSizeHint::Fixed(self.lhs_shape.0 * self.rhs_shape.1 * 4),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CoreEngine;
+    use serde_json::json;
+    
+    fn params(lhs_cols: usize) -> Value {
+        json!({
+            "lhs": "a", "lhs_rows": 2, "lhs_cols": lhs_cols,
+            "rhs": "b", "rhs_rows": 3, "rhs_cols": 2,
+        })
+    }
+    
+    #[test]
+    fn test_two_by_three_times_three_by_two() {
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(MatMul::from_params(&params(3)).unwrap())).unwrap();
+        let a = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let b = [7.0, 8.0, 9.0, 10.0, 11.0, 12.0];
+        engine.memory_mut().write("a", &samples::f32_to_bytes(&a)).unwrap();
+        engine.memory_mut().write("b", &samples::f32_to_bytes(&b)).unwrap();
+        
+        let output = engine.execute(MatMul::ID, &[]).unwrap();
+        // [1 2 3; 4 5 6] * [7 8; 9 10; 11 12]
+        assert_eq!(samples::f32_from_bytes(&output.data).unwrap(), vec![58.0, 64.0, 139.0, 154.0]);
+    }
+    
+    #[test]
+    fn test_mismatched_dimensions_error() {
+        let error = MatMul::from_params(&params(2)).unwrap_err();
+        assert!(matches!(&error, CoreError::SchemaMismatch(msg) if msg.contains("2x2") && msg.contains("3x2")));
+        
+        // A region that does not hold its declared shape
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(MatMul::from_params(&params(3)).unwrap())).unwrap();
+        engine.memory_mut().write("a", &samples::f32_to_bytes(&[1.0; 6])).unwrap();
+        engine.memory_mut().write("b", &samples::f32_to_bytes(&[1.0; 4])).unwrap();
+        assert!(matches!(engine.execute_algorithm(MatMul::ID, &[]), Err(CoreError::SchemaMismatch(_))));
+    }
+}
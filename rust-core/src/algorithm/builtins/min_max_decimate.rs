@@ -0,0 +1,123 @@
+//! Envelope-preserving downsampling
+
+use serde_json::Value;
+
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, OutputSizeHint, ParameterDefinition, ParameterType, Pure};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// Downsamples `f32` input by emitting the min and max of each bucket
+///
+/// Every `factor` consecutive samples form a bucket, and each bucket emits
+/// its minimum followed by its maximum, so a waveform drawn from the output
+/// keeps its peaks. A final partial bucket still emits its pair. NaN samples
+/// are ignored unless a bucket holds nothing else.
+#[derive(Clone, Debug)]
+pub struct MinMaxDecimate {
+    factor: usize,
+}
+
+impl MinMaxDecimate {
+    pub const ID: &'static str = "min_max_decimate";
+    
+    /// Create a decimator, rejecting a zero factor
+    pub fn new(factor: usize) -> Result<Self, CoreError> {
+        if factor == 0 {
+            return Err(CoreError::InvalidParameter("factor must be at least 1".to_string()));
+        }
+        Ok(Self { factor })
+    }
+    
+    /// Create a decimator from its `factor` parameter
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Self::new(params::require(params, "factor")?)
+    }
+    
+    /// Compute the `(min, max)` pair of every bucket
+    pub fn envelope(&self, signal: &[f32]) -> Vec<(f32, f32)> {
+        signal
+            .chunks(self.factor)
+            .map(|bucket| {
+                bucket
+                    .iter()
+                    .fold((f32::NAN, f32::NAN), |(min, max), &sample| (min.min(sample), max.max(sample)))
+            })
+            .collect()
+    }
+}
+
+impl Algorithm for MinMaxDecimate {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::new();
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        for (min, max) in self.envelope(&samples::f32_from_bytes(input)?) {
+            output.extend_from_slice(&min.to_le_bytes());
+            output.extend_from_slice(&max.to_le_bytes());
+        }
+        Ok(())
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Min/Max Decimate".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Downsamples f32 samples to interleaved per-bucket min and max".to_string(),
+            parameters: vec![ParameterDefinition {
+                name: "factor".to_string(),
+                parameter_type: ParameterType::Integer,
+                description: "Number of input samples per output bucket".to_string(),
+                default_value: None,
+            }],
+            output_size_hint: OutputSizeHint::Ratio(2.0 / self.factor as f32),
+            ..Default::default()
+        }
+    }
+}
+
+impl Pure for MinMaxDecimate {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn run(factor: usize, signal: &[f32]) -> Vec<f32> {
+        let decimate = MinMaxDecimate::new(factor).unwrap();
+        let output = decimate
+            .process(&samples::f32_to_bytes(signal), &mut MemoryManager::new())
+            .unwrap();
+        samples::f32_from_bytes(&output).unwrap()
+    }
+    
+    #[test]
+    fn test_single_sample_spike_survives() {
+        let mut signal = vec![0.0f32; 1000];
+        signal[537] = 42.0;
+        signal[812] = -7.0;
+        let envelope = run(100, &signal);
+        
+        assert_eq!(envelope.len(), 20);
+        assert_eq!(&envelope[10..12], &[0.0, 42.0]);
+        assert_eq!(&envelope[16..18], &[-7.0, 0.0]);
+        assert!(envelope.iter().enumerate().all(|(i, &v)| v == 0.0 || i == 11 || i == 16));
+    }
+    
+    #[test]
+    fn test_partial_bucket_emits_pair() {
+        assert_eq!(run(3, &[1.0, 5.0, 3.0, 9.0, -2.0]), vec![1.0, 5.0, -2.0, 9.0]);
+        assert_eq!(run(3, &[f32::NAN, 4.0]), vec![4.0, 4.0]);
+        assert!(run(3, &[]).is_empty());
+    }
+    
+    #[test]
+    fn test_zero_factor_rejected() {
+        assert!(MinMaxDecimate::new(0).is_err());
+    }
+}
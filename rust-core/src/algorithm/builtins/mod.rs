@@ -0,0 +1,157 @@
+//! Built-in algorithms shipped with the core
+
+#[cfg(feature = "std")]
+use alloc::boxed::Box;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use serde_json::Value;
+
+use super::{Algorithm, AlgorithmMetadata, OutputSizeHint, Pure};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+#[cfg(feature = "std")]
+mod add_noise;
+#[cfg(feature = "std")]
+mod biquad;
+#[cfg(feature = "std")]
+mod clamp;
+#[cfg(feature = "std")]
+mod cross_correlate;
+#[cfg(feature = "std")]
+mod dispatch;
+#[cfg(feature = "fft")]
+mod fft;
+#[cfg(feature = "std")]
+mod histogram;
+#[cfg(feature = "std")]
+mod int_scale_offset;
+#[cfg(feature = "std")]
+mod lookup_table;
+#[cfg(feature = "std")]
+mod map;
+#[cfg(feature = "std")]
+mod mat_mul;
+#[cfg(feature = "std")]
+mod peak_detect;
+mod pid;
+#[cfg(feature = "std")]
+mod min_max_decimate;
+#[cfg(feature = "std")]
+mod normalize;
+#[cfg(feature = "std")]
+mod polyphase_resampler;
+#[cfg(feature = "std")]
+mod quaternion_rotate;
+#[cfg(feature = "std")]
+mod region_reduce;
+#[cfg(feature = "std")]
+mod scale;
+#[cfg(feature = "std")]
+mod shuffle;
+#[cfg(feature = "std")]
+mod threshold;
+#[cfg(feature = "std")]
+mod window_stats;
+
+#[cfg(feature = "std")]
+pub use add_noise::AddNoise;
+#[cfg(feature = "std")]
+pub use biquad::Biquad;
+#[cfg(feature = "std")]
+pub use clamp::{Clamp, NanPolicy};
+#[cfg(feature = "std")]
+pub use cross_correlate::CrossCorrelate;
+#[cfg(feature = "std")]
+pub use dispatch::BuiltinAlgorithm;
+#[cfg(feature = "fft")]
+pub use fft::Fft;
+#[cfg(feature = "std")]
+pub use histogram::Histogram;
+#[cfg(feature = "std")]
+pub use int_scale_offset::{IntScaleOffset, OverflowMode};
+#[cfg(feature = "std")]
+pub use lookup_table::LookupTable;
+#[cfg(feature = "std")]
+pub use map::{Map, MapFunction};
+#[cfg(feature = "std")]
+pub use mat_mul::MatMul;
+#[cfg(feature = "std")]
+pub use peak_detect::PeakDetect;
+pub use pid::{PidController, PidGains};
+#[cfg(feature = "std")]
+pub use min_max_decimate::MinMaxDecimate;
+#[cfg(feature = "std")]
+pub use normalize::{Normalize, NormalizeMode};
+#[cfg(feature = "std")]
+pub use polyphase_resampler::PolyphaseResampler;
+#[cfg(feature = "std")]
+pub use quaternion_rotate::QuaternionRotate;
+#[cfg(feature = "std")]
+pub use region_reduce::{Reducer, RegionReduce};
+#[cfg(feature = "std")]
+pub use scale::Scale;
+#[cfg(feature = "std")]
+pub use shuffle::Shuffle;
+#[cfg(feature = "std")]
+pub use threshold::{Crossing, Edge, ThresholdTrigger};
+#[cfg(feature = "std")]
+pub use window_stats::WindowStats;
+pub use super::filters::{Complementary, Kalman};
+pub use super::kinematics::{ForwardKinematics, InverseKinematics, KinematicJacobian};
+#[cfg(feature = "std")]
+pub use super::odometry::WheelOdometry;
+#[cfg(feature = "std")]
+pub use super::planning::{AStarPlanner, RrtPlanner};
+#[cfg(feature = "std")]
+pub use super::trajectory::TrajectoryGenerator;
+
+/// Look up a built-in algorithm that needs no parameters by its ID
+#[cfg(feature = "std")]
+pub fn by_id(algorithm_id: &str) -> Option<Box<dyn Algorithm>> {
+    create(algorithm_id, &Value::Null).ok()
+}
+
+/// Create a built-in algorithm by ID, configured from a parameter object
+///
+/// The algorithm is a boxed `BuiltinAlgorithm`; use
+/// `BuiltinAlgorithm::create` to keep it unboxed.
+#[cfg(feature = "std")]
+pub fn create(algorithm_id: &str, params: &Value) -> Result<Box<dyn Algorithm>, CoreError> {
+    Ok(Box::new(BuiltinAlgorithm::create(algorithm_id, params)?))
+}
+
+/// Returns its input unchanged
+pub struct PassThrough;
+
+impl PassThrough {
+    pub const ID: &'static str = "passthrough";
+}
+
+impl Algorithm for PassThrough {
+    fn process(&self, input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        Ok(input.to_vec())
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        output.extend_from_slice(input);
+        Ok(())
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Pass Through".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Returns the input unchanged".to_string(),
+            output_size_hint: OutputSizeHint::SameAsInput,
+            ..Default::default()
+        }
+    }
+}
+
+impl Pure for PassThrough {}
@@ -0,0 +1,142 @@
+//! Rescale a buffer of samples by its own statistics
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, OutputSizeHint, ParameterDefinition, ParameterType, Pure};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// How `Normalize` rescales the buffer
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NormalizeMode {
+    /// Subtract the mean and divide by the population standard deviation
+    #[default]
+    ZScore,
+    /// Map the smallest sample to `0.0` and the largest to `1.0`
+    MinMax,
+}
+
+/// Rescales `f32` samples using statistics computed over the whole input,
+/// e.g. to condition features before a classifier
+///
+/// A buffer with zero variance or zero range has nothing to rescale by, so
+/// it normalizes to all zeros rather than NaN or infinity. Statistics are
+/// computed in `f64`.
+#[derive(Clone, Debug, Default)]
+pub struct Normalize {
+    mode: NormalizeMode,
+}
+
+impl Normalize {
+    pub const ID: &'static str = "normalize";
+    
+    pub fn new(mode: NormalizeMode) -> Self {
+        Self { mode }
+    }
+    
+    /// Create the algorithm from its `mode` parameter
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Ok(Self::new(params::get(params, "mode")?.unwrap_or_default()))
+    }
+    
+    /// Offset and divisor that map `samples` onto the normalized range, or
+    /// `None` if the spread is zero or not finite
+    fn affine(&self, samples: &[f32]) -> Option<(f64, f64)> {
+        let (offset, divisor) = match self.mode {
+            NormalizeMode::ZScore => {
+                let n = samples.len() as f64;
+                let mean = samples.iter().map(|&x| x as f64).sum::<f64>() / n;
+                let variance = samples.iter().map(|&x| (x as f64 - mean).powi(2)).sum::<f64>() / n;
+                (mean, variance.sqrt())
+            }
+            NormalizeMode::MinMax => {
+                let min = samples.iter().copied().fold(f32::INFINITY, f32::min) as f64;
+                let max = samples.iter().copied().fold(f32::NEG_INFINITY, f32::max) as f64;
+                (min, max - min)
+            }
+        };
+        (divisor.is_finite() && divisor > 0.0).then_some((offset, divisor))
+    }
+}
+
+impl Algorithm for Normalize {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::with_capacity(input.len());
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        let samples = samples::f32_from_bytes(input)?;
+        let normalized: Vec<f32> = match self.affine(&samples) {
+            Some((offset, divisor)) => samples.iter().map(|&x| ((x as f64 - offset) / divisor) as f32).collect(),
+            None => vec![0.0; samples.len()],
+        };
+        output.extend_from_slice(&samples::f32_to_bytes(&normalized));
+        Ok(())
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Normalize".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Z-score or min-max normalizes f32 samples over the buffer".to_string(),
+            parameters: vec![ParameterDefinition {
+                name: "mode".to_string(),
+                parameter_type: ParameterType::String,
+                description: "ZScore or MinMax".to_string(),
+                default_value: Some("ZScore".to_string()),
+            }],
+            output_size_hint: OutputSizeHint::SameAsInput,
+            ..Default::default()
+        }
+    }
+}
+
+impl Pure for Normalize {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    fn run(normalize: &Normalize, input: &[f32]) -> Vec<f32> {
+        let mut memory = MemoryManager::new();
+        let output = normalize.process(&samples::f32_to_bytes(input), &mut memory).unwrap();
+        samples::f32_from_bytes(&output).unwrap()
+    }
+    
+    #[test]
+    fn test_z_score() {
+        let output = run(&Normalize::new(NormalizeMode::ZScore), &[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+        // Mean 5, standard deviation 2
+        assert_eq!(output, vec![-1.5, -0.5, -0.5, -0.5, 0.0, 0.0, 1.0, 2.0]);
+    }
+    
+    #[test]
+    fn test_min_max() {
+        let output = run(&Normalize::new(NormalizeMode::MinMax), &[-2.0, 0.0, 3.0, 8.0]);
+        assert_eq!(output, vec![0.0, 0.2, 0.5, 1.0]);
+    }
+    
+    #[test]
+    fn test_constant_input_normalizes_to_zeros() {
+        for mode in [NormalizeMode::ZScore, NormalizeMode::MinMax] {
+            assert_eq!(run(&Normalize::new(mode), &[3.5; 5]), vec![0.0; 5]);
+            assert_eq!(run(&Normalize::new(mode), &[]), Vec::<f32>::new());
+        }
+    }
+    
+    #[test]
+    fn test_from_params() {
+        let normalize = Normalize::from_params(&json!({"mode": "MinMax"})).unwrap();
+        assert_eq!(run(&normalize, &[1.0, 3.0]), vec![0.0, 1.0]);
+        assert_eq!(Normalize::from_params(&Value::Null).unwrap().mode, NormalizeMode::ZScore);
+        assert!(Normalize::from_params(&json!({"mode": "Bogus"})).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+//! Detect peaks above a threshold, with hysteresis against noise
+
+use serde_json::Value;
+
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, ParameterDefinition, ParameterType, Pure};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// Emits the indices of peaks in `f32` input as a JSON list
+///
+/// A peak starts when the signal rises above `threshold` and ends when it
+/// falls below `threshold - hysteresis`; the index of the largest sample in
+/// between is emitted once. Noise that dips under the threshold without
+/// leaving the hysteresis band therefore stays part of the same peak.
+#[derive(Clone, Debug)]
+pub struct PeakDetect {
+    threshold: f32,
+    hysteresis: f32,
+}
+
+impl PeakDetect {
+    pub const ID: &'static str = "peak_detect";
+    
+    /// Create a detector, rejecting a non-finite threshold and a negative
+    /// or non-finite hysteresis
+    pub fn new(threshold: f32, hysteresis: f32) -> Result<Self, CoreError> {
+        if !threshold.is_finite() {
+            return Err(CoreError::InvalidParameter("threshold must be finite".to_string()));
+        }
+        if !hysteresis.is_finite() || hysteresis < 0.0 {
+            return Err(CoreError::InvalidParameter(
+                "hysteresis must be finite and non-negative".to_string(),
+            ));
+        }
+        Ok(Self { threshold, hysteresis })
+    }
+    
+    /// Create a detector from its `threshold` and `hysteresis` parameters
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Self::new(
+            params::require(params, "threshold")?,
+            params::get(params, "hysteresis")?.unwrap_or(0.0),
+        )
+    }
+    
+    /// Find the peak indices in a sequence of samples
+    ///
+    /// A peak still in progress when the samples end is reported. NaN
+    /// samples neither start, end nor extend a peak.
+    pub fn peaks(&self, samples: &[f32]) -> Vec<usize> {
+        let rearm = self.threshold - self.hysteresis;
+        let mut peaks = Vec::new();
+        // Index and value of the largest sample of the current peak
+        let mut current: Option<(usize, f32)> = None;
+        for (index, &value) in samples.iter().enumerate() {
+            current = match current {
+                None if value > self.threshold => Some((index, value)),
+                Some((peak, _)) if value < rearm => {
+                    peaks.push(peak);
+                    None
+                }
+                Some((_, max)) if value > max => Some((index, value)),
+                unchanged => unchanged,
+            };
+        }
+        peaks.extend(current.map(|(peak, _)| peak));
+        peaks
+    }
+}
+
+impl Algorithm for PeakDetect {
+    fn process(&self, input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let peaks = self.peaks(&samples::f32_from_bytes(input)?);
+        serde_json::to_vec(&peaks).map_err(|e| CoreError::ProcessingFailed(e.to_string()))
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Peak Detect".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Emits the indices of peaks in f32 input, with hysteresis against re-triggering".to_string(),
+            parameters: vec![
+                ParameterDefinition {
+                    name: "threshold".to_string(),
+                    parameter_type: ParameterType::Float,
+                    description: "Level the signal must rise above to start a peak".to_string(),
+                    default_value: None,
+                },
+                ParameterDefinition {
+                    name: "hysteresis".to_string(),
+                    parameter_type: ParameterType::Float,
+                    description: "How far below the threshold the signal must fall to end a peak".to_string(),
+                    default_value: Some("0".to_string()),
+                },
+            ],
+            ..Default::default()
+        }
+    }
+}
+
+impl Pure for PeakDetect {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn run(detector: &PeakDetect, signal: &[f32]) -> Vec<usize> {
+        let output = detector
+            .process(&samples::f32_to_bytes(signal), &mut MemoryManager::new())
+            .unwrap();
+        serde_json::from_slice(&output).unwrap()
+    }
+    
+    // One contact whose force chatters around the threshold, then a second
+    const NOISY: [f32; 12] = [0.0, 1.2, 0.9, 1.5, 0.95, 1.1, 0.4, 0.2, 1.3, 1.8, 1.6, 0.1];
+    
+    #[test]
+    fn test_noisy_peak_counted_once() {
+        let detector = PeakDetect::new(1.0, 0.3).unwrap();
+        assert_eq!(run(&detector, &NOISY), vec![3, 9]);
+        
+        // Without hysteresis every dip under the threshold re-arms
+        let bare = PeakDetect::new(1.0, 0.0).unwrap();
+        assert_eq!(run(&bare, &NOISY), vec![1, 3, 5, 9]);
+    }
+    
+    #[test]
+    fn test_rearms_only_below_band() {
+        let detector = PeakDetect::new(1.0, 0.5).unwrap();
+        // 0.6 stays inside the band, so the rise to 2.0 is the same peak
+        assert_eq!(detector.peaks(&[1.5, 0.6, 2.0, 0.4, 1.2]), vec![2, 4]);
+        // Touching the threshold exactly does not start a peak
+        assert!(detector.peaks(&[0.0, 1.0, 0.0]).is_empty());
+        assert!(detector.peaks(&[]).is_empty());
+    }
+    
+    #[test]
+    fn test_rejects_invalid_parameters() {
+        assert!(PeakDetect::new(f32::NAN, 0.1).is_err());
+        assert!(PeakDetect::new(1.0, -0.1).is_err());
+        let params = serde_json::json!({ "threshold": 2.0, "hysteresis": 0.5 });
+        assert_eq!(PeakDetect::from_params(&params).unwrap().peaks(&[3.0, 1.0]), vec![0]);
+    }
+}
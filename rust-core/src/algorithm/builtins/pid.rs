@@ -0,0 +1,361 @@
+//! PID controller with output limits, anti-windup and a filtered derivative
+
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use serde_json::Value;
+
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, OutputSizeHint, ParameterDefinition, ParameterType};
+use crate::algorithm::StreamingAlgorithm;
+use crate::compat::{self, Mutex, MutexGuard};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// Proportional, integral and derivative gains
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PidGains {
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+}
+
+impl PidGains {
+    fn check(&self) -> Result<(), CoreError> {
+        for (name, gain) in [("kp", self.kp), ("ki", self.ki), ("kd", self.kd)] {
+            if !gain.is_finite() {
+                return Err(CoreError::InvalidParameter(format!("{} {} is not finite", name, gain)));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct PidState {
+    // Accumulated `ki * error * dt`, so retuning `ki` does not step the output
+    integral: f32,
+    previous_measurement: Option<f32>,
+    // Filtered rate of change of the measurement
+    derivative: f32,
+}
+
+#[derive(Debug)]
+struct Shared {
+    gains: PidGains,
+    state: PidState,
+}
+
+/// PID controller over `f32` `(setpoint, measurement)` pairs sampled every
+/// `dt` seconds, emitting one clamped control output per pair
+///
+/// The derivative acts on the measurement rather than the error, so a
+/// setpoint step gives no derivative kick, and passes through a first-order
+/// low-pass with time constant `derivative_filter` seconds (0 disables it).
+/// With `anti_windup`, the integral stops accumulating once the output
+/// saturates in the direction the error pushes, and never alone exceeds
+/// the output limits, so the output leaves the limit as soon as the error
+/// changes sign.
+///
+/// The integral and derivative state carry across calls, one call per
+/// control tick, so the controller is not `Pure`. Clones share gains and
+/// state, like `NullDevice`, so keep a clone of a registered controller to
+/// retune it with `set_gains` while it runs.
+#[derive(Clone, Debug)]
+pub struct PidController {
+    dt: f32,
+    output_min: f32,
+    output_max: f32,
+    anti_windup: bool,
+    derivative_filter: f32,
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl PidController {
+    pub const ID: &'static str = "pid";
+    
+    /// Create an unlimited controller with anti-windup and no derivative
+    /// filter, rejecting non-finite gains or a non-positive `dt`
+    pub fn new(gains: PidGains, dt: f32) -> Result<Self, CoreError> {
+        gains.check()?;
+        if !dt.is_finite() || dt <= 0.0 {
+            return Err(CoreError::InvalidParameter(format!("dt must be finite and positive, got {}", dt)));
+        }
+        Ok(Self {
+            dt,
+            output_min: f32::NEG_INFINITY,
+            output_max: f32::INFINITY,
+            anti_windup: true,
+            derivative_filter: 0.0,
+            shared: Arc::new(Mutex::new(Shared {
+                gains,
+                state: PidState::default(),
+            })),
+        })
+    }
+    
+    /// Clamp every output to `[min, max]`
+    pub fn with_output_limits(mut self, min: f32, max: f32) -> Result<Self, CoreError> {
+        if min.is_nan() || max.is_nan() || min > max {
+            return Err(CoreError::InvalidParameter(format!("output limits [{}, {}] are not a range", min, max)));
+        }
+        self.output_min = min;
+        self.output_max = max;
+        Ok(self)
+    }
+    
+    pub fn with_anti_windup(mut self, anti_windup: bool) -> Self {
+        self.anti_windup = anti_windup;
+        self
+    }
+    
+    /// Low-pass the derivative with time constant `tau` seconds
+    pub fn with_derivative_filter(mut self, tau: f32) -> Result<Self, CoreError> {
+        if !tau.is_finite() || tau < 0.0 {
+            return Err(CoreError::InvalidParameter(format!(
+                "derivative filter must be finite and non-negative, got {}",
+                tau
+            )));
+        }
+        self.derivative_filter = tau;
+        Ok(self)
+    }
+    
+    /// Create the controller from its gain, `dt`, limit, `anti_windup` and
+    /// `derivative_filter` parameters
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        let gains = PidGains {
+            kp: params::require(params, "kp")?,
+            ki: params::get(params, "ki")?.unwrap_or(0.0),
+            kd: params::get(params, "kd")?.unwrap_or(0.0),
+        };
+        Ok(Self::new(gains, params::require(params, "dt")?)?
+            .with_output_limits(
+                params::get(params, "output_min")?.unwrap_or(f32::NEG_INFINITY),
+                params::get(params, "output_max")?.unwrap_or(f32::INFINITY),
+            )?
+            .with_derivative_filter(params::get(params, "derivative_filter")?.unwrap_or(0.0))?
+            .with_anti_windup(params::get(params, "anti_windup")?.unwrap_or(true)))
+    }
+    
+    pub fn gains(&self) -> PidGains {
+        self.lock().gains
+    }
+    
+    /// Replace the gains from the next step on, keeping the integral and
+    /// derivative state
+    pub fn set_gains(&self, gains: PidGains) -> Result<(), CoreError> {
+        gains.check()?;
+        self.lock().gains = gains;
+        Ok(())
+    }
+    
+    /// Clear the integral and derivative state
+    pub fn reset(&self) {
+        self.lock().state = PidState::default();
+    }
+    
+    /// Advance one step per `(setpoint, measurement)` pair, returning each
+    /// control output
+    pub fn step(&self, pairs: &[(f32, f32)]) -> Vec<f32> {
+        let mut shared = self.lock();
+        let Shared { gains, state } = &mut *shared;
+        pairs
+            .iter()
+            .map(|&(setpoint, measurement)| {
+                let error = setpoint - measurement;
+                if let Some(previous) = state.previous_measurement {
+                    let rate = (measurement - previous) / self.dt;
+                    let alpha = self.dt / (self.derivative_filter + self.dt);
+                    state.derivative += alpha * (rate - state.derivative);
+                }
+                state.previous_measurement = Some(measurement);
+                
+                let proportional = gains.kp * error;
+                let derivative = -gains.kd * state.derivative;
+                let integral = state.integral + gains.ki * error * self.dt;
+                let unclamped = proportional + integral + derivative;
+                // Saturated, the integral only grows as far as the limit
+                // and never further than it already was
+                let others = proportional + derivative;
+                state.integral = if !self.anti_windup {
+                    integral
+                } else if unclamped > self.output_max && error > 0.0 {
+                    integral.min(state.integral.max(self.output_max - others))
+                } else if unclamped < self.output_min && error < 0.0 {
+                    integral.max(state.integral.min(self.output_min - others))
+                } else {
+                    integral.clamp(self.output_min, self.output_max)
+                };
+                (proportional + state.integral + derivative).clamp(self.output_min, self.output_max)
+            })
+            .collect()
+    }
+    
+    fn lock(&self) -> MutexGuard<'_, Shared> {
+        compat::lock(&self.shared)
+    }
+}
+
+impl Algorithm for PidController {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::with_capacity(input.len() / 2);
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        let values = samples::f32_from_bytes(input)?;
+        if !values.len().is_multiple_of(2) {
+            return Err(CoreError::InvalidInput(format!(
+                "{} f32 values do not form whole (setpoint, measurement) pairs",
+                values.len()
+            )));
+        }
+        let pairs: Vec<(f32, f32)> = values.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect();
+        for control in self.step(&pairs) {
+            output.extend_from_slice(&control.to_le_bytes());
+        }
+        Ok(())
+    }
+    
+    fn as_streaming(&self) -> Option<&dyn StreamingAlgorithm> {
+        Some(self)
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        let float = |name: &str, description: &str, default_value: Option<&str>| ParameterDefinition {
+            name: name.to_string(),
+            parameter_type: ParameterType::Float,
+            description: description.to_string(),
+            default_value: default_value.map(str::to_string),
+        };
+        AlgorithmMetadata {
+            name: "PID Controller".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Turns f32 (setpoint, measurement) pairs into one control output per pair".to_string(),
+            parameters: vec![
+                float("kp", "Proportional gain", None),
+                float("ki", "Integral gain", Some("0")),
+                float("kd", "Derivative gain", Some("0")),
+                float("dt", "Seconds between pairs", None),
+                float("output_min", "Lowest output; unlimited if absent", None),
+                float("output_max", "Highest output; unlimited if absent", None),
+                ParameterDefinition {
+                    name: "anti_windup".to_string(),
+                    parameter_type: ParameterType::Boolean,
+                    description: "Stop integrating while the output is saturated".to_string(),
+                    default_value: Some("true".to_string()),
+                },
+                float("derivative_filter", "Time constant of the derivative low-pass in seconds; 0 disables it", Some("0")),
+            ],
+            output_size_hint: OutputSizeHint::Ratio(0.5),
+            ..Default::default()
+        }
+    }
+}
+
+/// The integral, filtered derivative and previous measurement (NaN before
+/// the first step), as three little-endian `f32`s
+impl StreamingAlgorithm for PidController {
+    fn checkpoint(&self) -> Vec<u8> {
+        let state = &self.lock().state;
+        samples::f32_to_bytes(&[state.integral, state.derivative, state.previous_measurement.unwrap_or(f32::NAN)])
+    }
+    
+    fn restore_checkpoint(&self, state: &[u8]) -> Result<(), CoreError> {
+        let [integral, derivative, previous]: [f32; 3] = samples::f32_from_bytes(state)?
+            .try_into()
+            .map_err(|_| CoreError::InvalidInput("PID checkpoint must hold 3 samples".to_string()))?;
+        self.lock().state = PidState {
+            integral,
+            previous_measurement: (!previous.is_nan()).then_some(previous),
+            derivative,
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    fn gains(kp: f32, ki: f32, kd: f32) -> PidGains {
+        PidGains { kp, ki, kd }
+    }
+    
+    #[test]
+    fn test_terms_and_limits() {
+        let pid = PidController::from_params(&json!({"kp": 2.0, "ki": 1.0, "kd": 0.5, "dt": 0.5})).unwrap();
+        // error 1: P 2 + I 0.5; then measurement rises by 0.5 at 1/s: P 1 +
+        // I 0.75 - D 0.5
+        assert_eq!(pid.step(&[(1.0, 0.0), (1.0, 0.5)]), vec![2.5, 1.25]);
+        
+        let limited = PidController::new(gains(10.0, 0.0, 0.0), 0.1).unwrap().with_output_limits(-1.0, 1.0).unwrap();
+        assert_eq!(limited.step(&[(5.0, 0.0), (-5.0, 0.0)]), vec![1.0, -1.0]);
+        assert!(PidController::new(gains(1.0, 0.0, 0.0), 0.1).unwrap().with_output_limits(1.0, -1.0).is_err());
+        assert!(PidController::new(gains(f32::NAN, 0.0, 0.0), 0.1).is_err());
+        assert!(PidController::from_params(&json!({"kp": 1.0})).is_err());
+        
+        let mut memory = MemoryManager::new();
+        let odd = samples::f32_to_bytes(&[1.0]);
+        assert!(matches!(pid.process(&odd, &mut memory), Err(CoreError::InvalidInput(_))));
+    }
+    
+    #[test]
+    fn test_anti_windup_recovers_from_saturation() {
+        let saturating = [(10.0, 0.0); 20];
+        let recover = |anti_windup: bool| {
+            let pid = PidController::new(gains(0.0, 1.0, 0.0), 1.0)
+                .unwrap()
+                .with_output_limits(0.0, 1.0)
+                .unwrap()
+                .with_anti_windup(anti_windup);
+            pid.step(&saturating);
+            // Once the setpoint is reached the output should leave the limit
+            pid.step(&[(0.0, 0.0), (0.0, 1.0)])
+        };
+        assert_eq!(recover(true), vec![1.0, 0.0]);
+        assert_eq!(recover(false), vec![1.0, 1.0]);
+    }
+    
+    #[test]
+    fn test_derivative_filter_smooths_steps() {
+        let unfiltered = PidController::new(gains(0.0, 0.0, 1.0), 1.0).unwrap();
+        let filtered = PidController::new(gains(0.0, 0.0, 1.0), 1.0).unwrap().with_derivative_filter(1.0).unwrap();
+        let steps = [(0.0, 0.0), (0.0, 1.0), (0.0, 1.0)];
+        assert_eq!(unfiltered.step(&steps), vec![0.0, -1.0, 0.0]);
+        assert_eq!(filtered.step(&steps), vec![0.0, -0.5, -0.25]);
+    }
+    
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_retuning_keeps_integral() {
+        let pid = PidController::new(gains(0.0, 1.0, 0.0), 1.0).unwrap();
+        let handle = pid.clone();
+        let mut engine = crate::CoreEngine::new();
+        engine.register(Box::new(pid)).unwrap();
+        let run = |engine: &mut crate::CoreEngine, setpoint: f32| {
+            let output = engine.execute_algorithm(PidController::ID, &samples::f32_to_bytes(&[setpoint, 0.0])).unwrap();
+            samples::f32_from_bytes(&output).unwrap()
+        };
+        assert_eq!(run(&mut engine, 2.0), vec![2.0]);
+        
+        // Doubling ki scales new error only, not what was integrated
+        handle.set_gains(gains(0.0, 2.0, 0.0)).unwrap();
+        assert_eq!(handle.gains().ki, 2.0);
+        assert_eq!(run(&mut engine, 1.0), vec![4.0]);
+        assert!(handle.set_gains(gains(0.0, f32::INFINITY, 0.0)).is_err());
+        
+        let checkpoint = handle.checkpoint();
+        handle.reset();
+        assert_eq!(run(&mut engine, 0.0), vec![0.0]);
+        handle.restore_checkpoint(&checkpoint).unwrap();
+        assert_eq!(run(&mut engine, 0.0), vec![4.0]);
+    }
+}
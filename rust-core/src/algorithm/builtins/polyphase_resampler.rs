@@ -0,0 +1,357 @@
+//! Rational sample-rate conversion with a polyphase FIR
+
+use serde_json::Value;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, OutputSizeHint, ParameterDefinition, ParameterType};
+use crate::algorithm::StreamingAlgorithm;
+use crate::error::CoreError;
+use crate::math;
+use crate::memory::MemoryManager;
+
+/// Kaiser window shape; roughly 50 dB of stopband attenuation
+const KAISER_BETA: f64 = 5.0;
+
+/// Resampler from one fixed rate to another over `f32` samples
+///
+/// With `input_rate / output_rate` reduced to `M / L`, the signal is
+/// conceptually upsampled by `L`, low-pass filtered and decimated by `M`,
+/// but only the filter phases that land on an output sample are computed.
+/// The anti-aliasing filter is a Kaiser-windowed sinc of
+/// `taps_per_phase * L` taps with its cutoff at the lower of the two
+/// Nyquist rates, so content the output rate cannot represent is removed
+/// rather than folded back.
+///
+/// The filter history and output phase carry across calls, so a stream
+/// resampled in chunks of any size gives the same samples as resampling it
+/// whole, and over a long stream the output holds `L / M` samples per input
+/// sample. The filter delays the signal by about `taps_per_phase / 2` input
+/// samples, and the first output starts from rest. Rates with a large
+/// reduced `L`, such as two nearby primes, make the filter correspondingly
+/// large.
+#[derive(Debug)]
+pub struct PolyphaseResampler {
+    input_rate: u32,
+    output_rate: u32,
+    up: usize,
+    down: usize,
+    // Filter taps by phase, oldest first: `phases[p][n - 1 - k]` applies to
+    // the input `k` samples back from an output at phase `p`
+    phases: Vec<Vec<f32>>,
+    state: Mutex<ResamplerState>,
+}
+
+#[derive(Debug)]
+struct ResamplerState {
+    // The last `taps_per_phase - 1` input samples
+    history: Vec<f32>,
+    // Upsampled index of the next output, relative to the next input sample
+    position: usize,
+}
+
+impl PolyphaseResampler {
+    pub const ID: &'static str = "polyphase_resampler";
+    
+    /// Default filter taps per polyphase branch
+    pub const DEFAULT_TAPS_PER_PHASE: usize = 32;
+    
+    /// Create a resampler between two rates in Hz
+    pub fn new(input_rate: u32, output_rate: u32, taps_per_phase: usize) -> Result<Self, CoreError> {
+        if input_rate == 0 || output_rate == 0 {
+            return Err(CoreError::InvalidParameter(format!(
+                "rates must be positive, got {} Hz to {} Hz",
+                input_rate, output_rate
+            )));
+        }
+        if taps_per_phase < 2 {
+            return Err(CoreError::InvalidParameter(format!(
+                "taps_per_phase must be at least 2, got {}",
+                taps_per_phase
+            )));
+        }
+        let divisor = gcd(input_rate, output_rate);
+        let up = (output_rate / divisor) as usize;
+        let down = (input_rate / divisor) as usize;
+        let taps = design_filter(up, down, taps_per_phase);
+        let phases = (0..up)
+            .map(|phase| (0..taps_per_phase).rev().map(|k| taps[phase + k * up] as f32).collect())
+            .collect();
+        Ok(Self {
+            input_rate,
+            output_rate,
+            up,
+            down,
+            phases,
+            state: Mutex::new(ResamplerState {
+                history: vec![0.0; taps_per_phase - 1],
+                position: 0,
+            }),
+        })
+    }
+    
+    /// Create a resampler from its `input_rate` and `output_rate` and
+    /// optional `taps_per_phase`
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Self::new(
+            params::require(params, "input_rate")?,
+            params::require(params, "output_rate")?,
+            params::get(params, "taps_per_phase")?.unwrap_or(Self::DEFAULT_TAPS_PER_PHASE),
+        )
+    }
+    
+    /// Reduced upsampling and downsampling factors `(L, M)`
+    pub fn factors(&self) -> (usize, usize) {
+        (self.up, self.down)
+    }
+    
+    /// Return the resampler to rest, as at the start of a stream
+    pub fn reset(&self) {
+        let mut state = self.lock_state();
+        state.history.fill(0.0);
+        state.position = 0;
+    }
+    
+    /// Resample the next chunk of the stream
+    pub fn resample(&self, signal: &[f32]) -> Vec<f32> {
+        let mut state = self.lock_state();
+        let history = state.history.len();
+        let mut buffer = std::mem::take(&mut state.history);
+        buffer.extend_from_slice(signal);
+        
+        let mut output = Vec::with_capacity(signal.len() * self.up / self.down + 1);
+        let mut position = state.position;
+        while position / self.up < signal.len() {
+            let newest = history + position / self.up;
+            let taps = &self.phases[position % self.up];
+            output.push(math::dot(taps, &buffer[newest + 1 - taps.len()..=newest]));
+            position += self.down;
+        }
+        state.position = position - signal.len() * self.up;
+        state.history = buffer.split_off(buffer.len() - history);
+        output
+    }
+    
+    fn lock_state(&self) -> MutexGuard<'_, ResamplerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Kaiser-windowed sinc low-pass at the upsampled rate, scaled for a DC
+/// gain of `up` so interpolation preserves amplitude
+fn design_filter(up: usize, down: usize, taps_per_phase: usize) -> Vec<f64> {
+    let len = taps_per_phase * up;
+    let center = (len - 1) as f64 / 2.0;
+    // Cutoff in cycles per upsampled sample
+    let cutoff = 0.5 / up.max(down) as f64;
+    let mut taps: Vec<f64> = (0..len)
+        .map(|i| {
+            let t = i as f64 - center;
+            let sinc = if t == 0.0 {
+                2.0 * cutoff
+            } else {
+                (2.0 * std::f64::consts::PI * cutoff * t).sin() / (std::f64::consts::PI * t)
+            };
+            let ratio = t / center;
+            sinc * bessel_i0(KAISER_BETA * (1.0 - ratio * ratio).max(0.0).sqrt()) / bessel_i0(KAISER_BETA)
+        })
+        .collect();
+    let gain = up as f64 / taps.iter().sum::<f64>();
+    taps.iter_mut().for_each(|tap| *tap *= gain);
+    taps
+}
+
+/// Zeroth-order modified Bessel function of the first kind
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    for k in 1..50 {
+        term *= (x / (2.0 * k as f64)).powi(2);
+        sum += term;
+        if term < sum * 1e-12 {
+            break;
+        }
+    }
+    sum
+}
+
+impl Algorithm for PolyphaseResampler {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::new();
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        for sample in self.resample(&samples::f32_from_bytes(input)?) {
+            output.extend_from_slice(&sample.to_le_bytes());
+        }
+        Ok(())
+    }
+    
+    fn as_streaming(&self) -> Option<&dyn StreamingAlgorithm> {
+        Some(self)
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        let parameter = |name: &str, description: &str, default_value: Option<String>| ParameterDefinition {
+            name: name.to_string(),
+            parameter_type: ParameterType::Integer,
+            description: description.to_string(),
+            default_value,
+        };
+        AlgorithmMetadata {
+            name: "Polyphase Resampler".to_string(),
+            version: "1.0.0".to_string(),
+            description: format!(
+                "Streaming {} Hz to {} Hz rational resampler over f32 samples",
+                self.input_rate, self.output_rate
+            ),
+            parameters: vec![
+                parameter("input_rate", "Sample rate of the input in Hz", None),
+                parameter("output_rate", "Sample rate of the output in Hz", None),
+                parameter(
+                    "taps_per_phase",
+                    "Anti-aliasing filter taps per polyphase branch",
+                    Some(Self::DEFAULT_TAPS_PER_PHASE.to_string()),
+                ),
+            ],
+            output_size_hint: OutputSizeHint::Ratio(self.up as f32 / self.down as f32),
+            ..Default::default()
+        }
+    }
+}
+
+/// The output phase as a little-endian `u64`, then the filter history as
+/// little-endian `f32`s
+impl StreamingAlgorithm for PolyphaseResampler {
+    fn checkpoint(&self) -> Vec<u8> {
+        let state = self.lock_state();
+        let mut checkpoint = (state.position as u64).to_le_bytes().to_vec();
+        checkpoint.extend(samples::f32_to_bytes(&state.history));
+        checkpoint
+    }
+    
+    fn restore_checkpoint(&self, state: &[u8]) -> Result<(), CoreError> {
+        let mut current = self.lock_state();
+        let invalid = || {
+            CoreError::InvalidInput(format!(
+                "resampler checkpoint must hold a position and {} samples",
+                current.history.len()
+            ))
+        };
+        let (position, history) = state.split_first_chunk::<8>().ok_or_else(invalid)?;
+        let history = samples::f32_from_bytes(history).map_err(|_| invalid())?;
+        let position = usize::try_from(u64::from_le_bytes(*position)).map_err(|_| invalid())?;
+        if history.len() != current.history.len() || position >= self.down {
+            return Err(invalid());
+        }
+        *current = ResamplerState { history, position };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    fn sine(frequency: f64, rate: f64, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| (2.0 * std::f64::consts::PI * frequency * i as f64 / rate).sin() as f32)
+            .collect()
+    }
+    
+    /// Frequency from the upward zero crossings of `signal`
+    fn frequency(signal: &[f32], rate: f64) -> f64 {
+        let crossings: Vec<usize> = (1..signal.len())
+            .filter(|&i| signal[i - 1] < 0.0 && signal[i] >= 0.0)
+            .collect();
+        let cycles = (crossings.len() - 1) as f64;
+        cycles * rate / (crossings[crossings.len() - 1] - crossings[0]) as f64
+    }
+    
+    fn rms(signal: &[f32]) -> f32 {
+        (signal.iter().map(|x| x * x).sum::<f32>() / signal.len() as f32).sqrt()
+    }
+    
+    #[test]
+    fn test_sine_keeps_frequency_at_rate_ratio() {
+        let resampler = PolyphaseResampler::from_params(&json!({ "input_rate": 48000, "output_rate": 44100 })).unwrap();
+        assert_eq!(resampler.factors(), (147, 160));
+        let input = sine(1000.0, 48000.0, 48000);
+        
+        let mut output = Vec::new();
+        for chunk in input.chunks(480) {
+            output.extend(resampler.resample(chunk));
+        }
+        assert!(output.len().abs_diff(44100) <= 1, "{} samples", output.len());
+        
+        // Skip the filter's start-up transient
+        let steady = &output[100..];
+        let measured = frequency(steady, 44100.0);
+        assert!((measured - 1000.0).abs() < 0.5, "measured {} Hz", measured);
+        assert!((rms(steady) - std::f32::consts::FRAC_1_SQRT_2).abs() < 0.01);
+    }
+    
+    #[test]
+    fn test_chunked_stream_matches_whole() {
+        let input = sine(440.0, 44100.0, 2000);
+        let whole = PolyphaseResampler::new(44100, 48000, 16).unwrap().resample(&input);
+        
+        let chunked = PolyphaseResampler::new(44100, 48000, 16).unwrap();
+        let mut output = Vec::new();
+        for chunk in input.chunks(37) {
+            output.extend(chunked.resample(chunk));
+        }
+        assert_eq!(output, whole);
+        
+        chunked.reset();
+        assert_eq!(chunked.resample(&input), whole);
+    }
+    
+    #[test]
+    fn test_content_above_output_nyquist_is_not_aliased() {
+        // 40 kHz would fold to 8 kHz at 48 kHz without the filter
+        let resampler = PolyphaseResampler::new(96000, 48000, 32).unwrap();
+        let output = resampler.resample(&sine(40000.0, 96000.0, 9600));
+        assert_eq!(output.len(), 4800);
+        assert!(rms(&output[100..]) < 0.01, "rms {}", rms(&output[100..]));
+    }
+    
+    #[test]
+    fn test_invalid_rates_rejected() {
+        assert!(PolyphaseResampler::new(0, 48000, 32).is_err());
+        assert!(PolyphaseResampler::new(48000, 44100, 1).is_err());
+        assert!(PolyphaseResampler::from_params(&json!({ "input_rate": 48000 })).is_err());
+    }
+    
+    #[test]
+    fn test_checkpoint_resumes_stream() {
+        let signal = sine(440.0, 8000.0, 300);
+        let whole = PolyphaseResampler::new(8000, 6000, 16).unwrap().resample(&signal);
+        
+        let first = PolyphaseResampler::new(8000, 6000, 16).unwrap();
+        let mut output = first.resample(&signal[..101]);
+        let checkpoint = first.checkpoint();
+        
+        let resumed = PolyphaseResampler::new(8000, 6000, 16).unwrap();
+        resumed.restore_checkpoint(&checkpoint).unwrap();
+        output.extend(resumed.resample(&signal[101..]));
+        assert_eq!(output, whole);
+        
+        let other = PolyphaseResampler::new(8000, 6000, 8).unwrap();
+        assert!(matches!(other.restore_checkpoint(&checkpoint), Err(CoreError::InvalidInput(_))));
+        assert!(resumed.restore_checkpoint(&checkpoint[..7]).is_err());
+    }
+}
@@ -0,0 +1,159 @@
+//! Rotate 3D points between coordinate frames
+
+use serde_json::Value;
+
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, OutputSizeHint, ParameterDefinition, ParameterType, Pure};
+use crate::error::CoreError;
+use crate::math;
+use crate::memory::MemoryManager;
+
+/// Rotates `(x, y, z)` points, stored as consecutive `f32` triples, by a
+/// unit quaternion
+///
+/// The quaternion is given as `(w, x, y, z)` and normalized on creation, so
+/// only its direction matters. It is applied as the equivalent rotation
+/// matrix, computed once in `f64`.
+#[derive(Clone, Debug)]
+pub struct QuaternionRotate {
+    quaternion: [f32; 4],
+    matrix: [[f32; 3]; 3],
+}
+
+impl QuaternionRotate {
+    pub const ID: &'static str = "quaternion_rotate";
+    
+    /// Create the rotation, rejecting a zero or non-finite quaternion
+    pub fn new(quaternion: [f32; 4]) -> Result<Self, CoreError> {
+        let norm = quaternion.iter().map(|&q| (q as f64).powi(2)).sum::<f64>().sqrt();
+        if !norm.is_finite() || norm == 0.0 {
+            return Err(CoreError::InvalidParameter(format!(
+                "quaternion {:?} cannot be normalized",
+                quaternion
+            )));
+        }
+        let [w, x, y, z] = quaternion.map(|q| q as f64 / norm);
+        let matrix = [
+            [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - w * z), 2.0 * (x * z + w * y)],
+            [2.0 * (x * y + w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - w * x)],
+            [2.0 * (x * z - w * y), 2.0 * (y * z + w * x), 1.0 - 2.0 * (x * x + y * y)],
+        ]
+        .map(|row| row.map(|m| m as f32));
+        Ok(Self {
+            quaternion: [w, x, y, z].map(|q| q as f32),
+            matrix,
+        })
+    }
+    
+    /// Create the rotation from its `quaternion` parameter, a `[w, x, y, z]`
+    /// array
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Self::new(params::require(params, "quaternion")?)
+    }
+    
+    /// The normalized quaternion, as `[w, x, y, z]`
+    pub fn quaternion(&self) -> [f32; 4] {
+        self.quaternion
+    }
+    
+    /// Rotate a single point
+    pub fn apply(&self, point: [f32; 3]) -> [f32; 3] {
+        self.matrix.map(|row| row[0] * point[0] + row[1] * point[1] + row[2] * point[2])
+    }
+}
+
+impl Algorithm for QuaternionRotate {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::with_capacity(input.len());
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        let values = samples::f32_from_bytes(input)?;
+        if !values.len().is_multiple_of(3) {
+            return Err(CoreError::InvalidInput(format!(
+                "{} f32 values do not form whole (x, y, z) points",
+                values.len()
+            )));
+        }
+        let mut rotated = Vec::with_capacity(values.len());
+        math::rotate_points(&self.matrix, &values, &mut rotated);
+        for value in rotated {
+            output.extend_from_slice(&value.to_le_bytes());
+        }
+        Ok(())
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Quaternion Rotate".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Rotates f32 (x, y, z) points by a quaternion".to_string(),
+            parameters: vec![ParameterDefinition {
+                name: "quaternion".to_string(),
+                parameter_type: ParameterType::Array,
+                description: "Rotation as [w, x, y, z], normalized internally".to_string(),
+                default_value: None,
+            }],
+            output_size_hint: OutputSizeHint::SameAsInput,
+            ..Default::default()
+        }
+    }
+}
+
+impl Pure for QuaternionRotate {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::tolerance;
+    use serde_json::json;
+    
+    fn run(rotate: &QuaternionRotate, points: &[f32]) -> Vec<f32> {
+        let mut memory = MemoryManager::new();
+        let output = rotate.process(&samples::f32_to_bytes(points), &mut memory).unwrap();
+        samples::f32_from_bytes(&output).unwrap()
+    }
+    
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        assert!(
+            actual.iter().zip(expected).all(|(&a, &e)| tolerance::approx_eq(a, e, 1e-6)),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+    
+    #[test]
+    fn test_quarter_turn_about_z() {
+        let half = std::f32::consts::FRAC_PI_4;
+        // Unnormalized on purpose: scaling must not change the rotation
+        let rotate = QuaternionRotate::new([3.0 * half.cos(), 0.0, 0.0, 3.0 * half.sin()]).unwrap();
+        assert_close(&rotate.quaternion(), &[half.cos(), 0.0, 0.0, half.sin()]);
+        assert_close(&run(&rotate, &[1.0, 0.0, 0.0, 0.0, 2.0, 5.0]), &[0.0, 1.0, 0.0, -2.0, 0.0, 5.0]);
+    }
+    
+    #[test]
+    fn test_quarter_turn_about_x_from_params() {
+        let half = std::f32::consts::FRAC_PI_4;
+        let rotate = QuaternionRotate::from_params(&json!({"quaternion": [half.cos(), half.sin(), 0.0, 0.0]})).unwrap();
+        assert_close(&run(&rotate, &[0.0, 1.0, 0.0]), &[0.0, 0.0, 1.0]);
+        assert!(QuaternionRotate::from_params(&json!({"quaternion": [1.0, 0.0]})).is_err());
+    }
+    
+    #[test]
+    fn test_invalid_quaternion_or_input_rejected() {
+        assert!(matches!(QuaternionRotate::new([0.0; 4]), Err(CoreError::InvalidParameter(_))));
+        assert!(QuaternionRotate::new([f32::NAN, 0.0, 0.0, 1.0]).is_err());
+        
+        let identity = QuaternionRotate::new([1.0, 0.0, 0.0, 0.0]).unwrap();
+        let mut memory = MemoryManager::new();
+        let partial = samples::f32_to_bytes(&[1.0, 2.0, 3.0, 4.0]);
+        assert!(matches!(identity.process(&partial, &mut memory), Err(CoreError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,212 @@
+//! Elementwise fusion of several memory regions
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, ParameterDefinition, ParameterType};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// How `RegionReduce` combines the values at one index
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Reducer {
+    /// Arithmetic mean
+    #[default]
+    Mean,
+    /// Largest value, ignoring NaN unless every value is NaN
+    Max,
+    /// Smallest value, ignoring NaN unless every value is NaN
+    Min,
+    /// Middle value; with an even number of regions, the mean of the two
+    /// middle values. NaN sorts above every number.
+    Median,
+}
+
+impl Reducer {
+    /// Reduce one index's values, reordering them
+    ///
+    /// No values reduce to NaN.
+    pub fn reduce(self, values: &mut [f32]) -> f32 {
+        match self {
+            Reducer::Mean => values.iter().sum::<f32>() / values.len() as f32,
+            Reducer::Max => values.iter().copied().fold(f32::NAN, f32::max),
+            Reducer::Min => values.iter().copied().fold(f32::NAN, f32::min),
+            Reducer::Median => {
+                values.sort_unstable_by(f32::total_cmp);
+                let middle = values.len() / 2;
+                match (middle.checked_sub(1).and_then(|i| values.get(i)), values.get(middle)) {
+                    (Some(low), Some(high)) if values.len().is_multiple_of(2) => (low + high) / 2.0,
+                    (_, Some(&value)) => value,
+                    (_, None) => f32::NAN,
+                }
+            }
+        }
+    }
+}
+
+/// Combines several equal-length `f32` regions elementwise, for fusing
+/// redundant sensor channels
+///
+/// Output element `i` is the reducer applied to element `i` of every input
+/// region. All regions must hold the same number of elements. The input
+/// bytes are ignored.
+#[derive(Clone, Debug)]
+pub struct RegionReduce {
+    inputs: Vec<String>,
+    reducer: Reducer,
+}
+
+impl RegionReduce {
+    pub const ID: &'static str = "region_reduce";
+    
+    /// Reduce the regions under `inputs`, rejecting an empty list
+    pub fn new(inputs: Vec<String>, reducer: Reducer) -> Result<Self, CoreError> {
+        if inputs.is_empty() {
+            return Err(CoreError::InvalidParameter("region reduce needs at least one input".to_string()));
+        }
+        Ok(Self { inputs, reducer })
+    }
+    
+    /// Create a reduction from its `inputs` (a list of region keys) and
+    /// `reducer` parameters
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Self::new(
+            params::require(params, "inputs")?,
+            params::get(params, "reducer")?.unwrap_or_default(),
+        )
+    }
+    
+    /// Reduce the input regions held in `memory`
+    pub fn reduce(&self, memory: &MemoryManager) -> Result<Vec<f32>, CoreError> {
+        let regions = self
+            .inputs
+            .iter()
+            .map(|key| {
+                let data = memory
+                    .read(key)
+                    .ok_or_else(|| CoreError::MemoryKeyMissing(key.clone()))?;
+                samples::f32_from_bytes(data)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let len = regions[0].len();
+        if let Some((key, region)) = self.inputs.iter().zip(&regions).find(|(_, region)| region.len() != len) {
+            return Err(CoreError::SchemaMismatch(format!(
+                "region '{}' holds {} elements but '{}' holds {}",
+                key,
+                region.len(),
+                self.inputs[0],
+                len
+            )));
+        }
+        let mut column = vec![0.0; regions.len()];
+        Ok((0..len)
+            .map(|i| {
+                for (value, region) in column.iter_mut().zip(&regions) {
+                    *value = region[i];
+                }
+                self.reducer.reduce(&mut column)
+            })
+            .collect())
+    }
+}
+
+impl Algorithm for RegionReduce {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::new();
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, _input: &[u8], output: &mut Vec<u8>, memory: &mut MemoryManager) -> Result<(), CoreError> {
+        for value in self.reduce(memory)? {
+            output.extend_from_slice(&value.to_le_bytes());
+        }
+        Ok(())
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Region Reduce".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Elementwise mean, max, min or median of equal-length f32 regions".to_string(),
+            parameters: vec![
+                ParameterDefinition {
+                    name: "inputs".to_string(),
+                    parameter_type: ParameterType::Array,
+                    description: "Keys of the regions to combine".to_string(),
+                    default_value: None,
+                },
+                ParameterDefinition {
+                    name: "reducer".to_string(),
+                    parameter_type: ParameterType::String,
+                    description: "Mean, Max, Min or Median".to_string(),
+                    default_value: Some("Mean".to_string()),
+                },
+            ],
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    fn memory(regions: &[[f32; 3]]) -> MemoryManager {
+        let mut memory = MemoryManager::new();
+        for (i, region) in regions.iter().enumerate() {
+            memory.write(&format!("ch{}", i), &samples::f32_to_bytes(region)).unwrap();
+        }
+        memory
+    }
+    
+    fn reduce(reducer: &str, regions: &[[f32; 3]]) -> Vec<f32> {
+        let inputs: Vec<String> = (0..regions.len()).map(|i| format!("ch{}", i)).collect();
+        let reduce = RegionReduce::from_params(&json!({ "inputs": inputs, "reducer": reducer })).unwrap();
+        let output = reduce.process(&[], &mut memory(regions)).unwrap();
+        samples::f32_from_bytes(&output).unwrap()
+    }
+    
+    const THREE: [[f32; 3]; 3] = [[1.0, 10.0, -3.0], [2.0, 40.0, -1.0], [6.0, 10.0, -2.0]];
+    const FOUR: [[f32; 3]; 4] = [[1.0, 0.0, 5.0], [2.0, 8.0, 5.0], [3.0, 2.0, 5.0], [10.0, 4.0, 5.0]];
+    
+    #[test]
+    fn test_mean_of_three_and_four_regions() {
+        assert_eq!(reduce("Mean", &THREE), vec![3.0, 20.0, -2.0]);
+        assert_eq!(reduce("Mean", &FOUR), vec![4.0, 3.5, 5.0]);
+    }
+    
+    #[test]
+    fn test_median_of_three_and_four_regions() {
+        assert_eq!(reduce("Median", &THREE), vec![2.0, 10.0, -2.0]);
+        // Even counts average the two middle values
+        assert_eq!(reduce("Median", &FOUR), vec![2.5, 3.0, 5.0]);
+    }
+    
+    #[test]
+    fn test_min_and_max() {
+        assert_eq!(reduce("Max", &THREE), vec![6.0, 40.0, -1.0]);
+        assert_eq!(reduce("Min", &THREE), vec![1.0, 10.0, -3.0]);
+    }
+    
+    #[test]
+    fn test_unequal_lengths_error() {
+        let mut memory = memory(&THREE);
+        memory.write("short", &samples::f32_to_bytes(&[1.0])).unwrap();
+        let reduce = RegionReduce::new(vec!["ch0".to_string(), "short".to_string()], Reducer::Mean).unwrap();
+        assert!(matches!(reduce.process(&[], &mut memory), Err(CoreError::SchemaMismatch(_))));
+        assert!(RegionReduce::new(Vec::new(), Reducer::Mean).is_err());
+    }
+    
+    #[test]
+    fn test_reducing_no_values_is_nan() {
+        for reducer in [Reducer::Mean, Reducer::Max, Reducer::Min, Reducer::Median] {
+            assert!(reducer.reduce(&mut []).is_nan());
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Multiply samples by a constant factor
+
+use serde_json::Value;
+
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, OutputSizeHint, InPlaceAlgorithm, ParameterDefinition, ParameterType, Pure};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// Multiplies each `f32` sample by `factor`
+#[derive(Clone, Debug)]
+pub struct Scale {
+    factor: f32,
+}
+
+impl Scale {
+    pub const ID: &'static str = "scale";
+    
+    /// Create a scale, rejecting a non-finite factor
+    pub fn new(factor: f32) -> Result<Self, CoreError> {
+        if !factor.is_finite() {
+            return Err(CoreError::InvalidParameter(format!("scale factor {} is not finite", factor)));
+        }
+        Ok(Self { factor })
+    }
+    
+    /// Create a scale from its `factor` parameter
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Self::new(params::require(params, "factor")?)
+    }
+    
+    /// The factor samples are multiplied by
+    pub fn factor(&self) -> f32 {
+        self.factor
+    }
+    
+    /// Scale a single sample
+    pub fn apply(&self, sample: f32) -> f32 {
+        sample * self.factor
+    }
+}
+
+impl Algorithm for Scale {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::with_capacity(input.len());
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        samples::map_f32_into(input, output, |sample| self.apply(sample))
+    }
+    
+    fn as_in_place(&self) -> Option<&dyn InPlaceAlgorithm> {
+        Some(self)
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Scale".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Multiplies f32 samples by a constant factor".to_string(),
+            parameters: vec![ParameterDefinition {
+                name: "factor".to_string(),
+                parameter_type: ParameterType::Float,
+                description: "Multiplier applied to every sample".to_string(),
+                default_value: None,
+            }],
+            output_size_hint: OutputSizeHint::SameAsInput,
+            ..Default::default()
+        }
+    }
+}
+
+impl InPlaceAlgorithm for Scale {
+    fn process_in_place(&self, buf: &mut [u8], _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        samples::map_f32_in_place(buf, |sample| self.apply(sample))
+    }
+}
+
+impl Pure for Scale {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[test]
+    fn test_scales_every_sample() {
+        let scale = Scale::from_params(&json!({ "factor": -2.0 })).unwrap();
+        let output = scale
+            .process(&samples::f32_to_bytes(&[1.0, 0.25, -3.0]), &mut MemoryManager::new())
+            .unwrap();
+        assert_eq!(samples::f32_from_bytes(&output).unwrap(), vec![-2.0, -0.5, 6.0]);
+    }
+    
+    #[test]
+    fn test_invalid_factor_rejected() {
+        assert!(Scale::new(f32::NAN).is_err());
+        assert!(Scale::from_params(&json!({})).is_err());
+    }
+}
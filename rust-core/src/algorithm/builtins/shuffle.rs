@@ -0,0 +1,154 @@
+//! Deterministically permute fixed-size records
+
+use serde_json::Value;
+
+use crate::algorithm::context::{RngCore, SeededRng};
+use crate::algorithm::{params, Algorithm, AlgorithmMetadata, OutputSizeHint, ParameterDefinition, ParameterType, Pure};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// Reorders the input's `record_size`-byte records by a Fisher-Yates
+/// shuffle seeded with `seed`, e.g. to augment a training batch
+///
+/// Every call starts from the seed, so the same seed and record count always
+/// give the same permutation. Indices are drawn as `u64` by rejection
+/// sampling, so the permutation is also the same on every platform.
+#[derive(Clone, Debug)]
+pub struct Shuffle {
+    record_size: usize,
+    seed: u64,
+}
+
+impl Shuffle {
+    pub const ID: &'static str = "shuffle";
+    
+    /// Create the shuffle, rejecting a zero record size
+    pub fn new(record_size: usize, seed: u64) -> Result<Self, CoreError> {
+        if record_size == 0 {
+            return Err(CoreError::InvalidParameter("record_size must be at least 1".to_string()));
+        }
+        Ok(Self { record_size, seed })
+    }
+    
+    /// Create the shuffle from its `record_size` and `seed` parameters
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Self::new(params::require(params, "record_size")?, params::require(params, "seed")?)
+    }
+    
+    /// Source index of each output record, for `count` records
+    pub fn permutation(&self, count: usize) -> Vec<usize> {
+        let mut rng = SeededRng::new(self.seed);
+        let mut order: Vec<usize> = (0..count).collect();
+        for i in (1..count).rev() {
+            let j = below(&mut rng, i as u64 + 1) as usize;
+            order.swap(i, j);
+        }
+        order
+    }
+}
+
+/// Uniform draw from `0..bound`, rejecting the top partial range so no
+/// value is favored
+fn below(rng: &mut SeededRng, bound: u64) -> u64 {
+    let zone = u64::MAX - u64::MAX % bound;
+    loop {
+        let value = rng.next_u64();
+        if value < zone {
+            return value % bound;
+        }
+    }
+}
+
+impl Algorithm for Shuffle {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::with_capacity(input.len());
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        if !input.len().is_multiple_of(self.record_size) {
+            return Err(CoreError::InvalidInput(format!(
+                "{} bytes do not form whole {}-byte records",
+                input.len(),
+                self.record_size
+            )));
+        }
+        let records: Vec<&[u8]> = input.chunks_exact(self.record_size).collect();
+        for index in self.permutation(records.len()) {
+            output.extend_from_slice(records[index]);
+        }
+        Ok(())
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Shuffle".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Permutes fixed-size records in a seeded, reproducible order".to_string(),
+            parameters: vec![
+                ParameterDefinition {
+                    name: "record_size".to_string(),
+                    parameter_type: ParameterType::Integer,
+                    description: "Bytes per record".to_string(),
+                    default_value: None,
+                },
+                ParameterDefinition {
+                    name: "seed".to_string(),
+                    parameter_type: ParameterType::Integer,
+                    description: "Seed selecting the permutation".to_string(),
+                    default_value: None,
+                },
+            ],
+            output_size_hint: OutputSizeHint::SameAsInput,
+            ..Default::default()
+        }
+    }
+}
+
+impl Pure for Shuffle {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    fn records(count: u16) -> Vec<u8> {
+        (0..count).flat_map(|i| i.to_le_bytes()).collect()
+    }
+    
+    #[test]
+    fn test_same_seed_gives_same_permutation() {
+        let shuffle = Shuffle::from_params(&json!({"record_size": 2, "seed": 42})).unwrap();
+        let mut memory = MemoryManager::new();
+        let input = records(8);
+        let output = shuffle.process(&input, &mut memory).unwrap();
+        assert_eq!(shuffle.process(&input, &mut memory).unwrap(), output);
+        // Pinned, so a platform-dependent draw would show up here
+        assert_eq!(shuffle.permutation(8), vec![3, 1, 6, 2, 4, 0, 7, 5]);
+        
+        let other = Shuffle::new(2, 43).unwrap().process(&input, &mut memory).unwrap();
+        assert_ne!(other, output);
+    }
+    
+    #[test]
+    fn test_output_is_a_permutation_of_records() {
+        let shuffle = Shuffle::new(2, 7).unwrap();
+        let mut memory = MemoryManager::new();
+        let input = records(100);
+        let output = shuffle.process(&input, &mut memory).unwrap();
+        assert_ne!(output, input);
+        
+        let mut sorted: Vec<&[u8]> = output.chunks_exact(2).collect();
+        sorted.sort_by_key(|record| u16::from_le_bytes([record[0], record[1]]));
+        assert_eq!(sorted.concat(), input);
+        
+        assert!(matches!(shuffle.process(&[0; 3], &mut memory), Err(CoreError::InvalidInput(_))));
+        assert!(shuffle.process(&[], &mut memory).unwrap().is_empty());
+        assert!(Shuffle::new(0, 7).is_err());
+    }
+}
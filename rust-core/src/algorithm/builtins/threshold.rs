@@ -0,0 +1,190 @@
+//! Detect samples crossing a threshold
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::algorithm::tolerance::{self, DEFAULT_EPSILON};
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, ParameterDefinition, ParameterType, Pure};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// Which crossing direction triggers an event
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Edge {
+    /// Signal goes from below the threshold to at or above it
+    #[default]
+    Rising,
+    /// Signal goes from at or above the threshold to below it
+    Falling,
+    /// Either direction
+    Both,
+}
+
+/// A threshold crossing found in the input
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Crossing {
+    /// Index of the first sample on the new side of the threshold
+    pub index: usize,
+    /// Value of that sample
+    pub value: f32,
+}
+
+/// Emits the threshold crossings in `f32` input as a JSON list of `Crossing`s
+#[derive(Clone, Debug)]
+pub struct ThresholdTrigger {
+    threshold: f32,
+    edge: Edge,
+    epsilon: f32,
+}
+
+impl ThresholdTrigger {
+    pub const ID: &'static str = "threshold_trigger";
+    
+    /// Create a trigger, rejecting a NaN threshold
+    pub fn new(threshold: f32, edge: Edge) -> Result<Self, CoreError> {
+        if threshold.is_nan() {
+            return Err(CoreError::InvalidParameter("threshold must not be NaN".to_string()));
+        }
+        Ok(Self {
+            threshold,
+            edge,
+            epsilon: DEFAULT_EPSILON,
+        })
+    }
+    
+    /// Treat samples within `epsilon` of the threshold as at it
+    pub fn with_epsilon(mut self, epsilon: f32) -> Result<Self, CoreError> {
+        self.epsilon = tolerance::validate_epsilon(epsilon)?;
+        Ok(self)
+    }
+    
+    /// Create a trigger from its `threshold`, `edge` and `epsilon`
+    /// parameters
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Self::new(
+            params::require(params, "threshold")?,
+            params::get(params, "edge")?.unwrap_or_default(),
+        )?
+        .with_epsilon(params::get(params, "epsilon")?.unwrap_or(DEFAULT_EPSILON))
+    }
+    
+    /// Find crossings in a sequence of samples
+    ///
+    /// Samples within epsilon below the threshold count as at it, so noise
+    /// around the level does not register as repeated crossings. NaN
+    /// samples count as below the threshold.
+    pub fn crossings(&self, samples: &[f32]) -> Vec<Crossing> {
+        samples
+            .windows(2)
+            .enumerate()
+            .filter_map(|(i, pair)| {
+                let was_above = tolerance::approx_ge(pair[0], self.threshold, self.epsilon);
+                let is_above = tolerance::approx_ge(pair[1], self.threshold, self.epsilon);
+                let triggered = match self.edge {
+                    Edge::Rising => !was_above && is_above,
+                    Edge::Falling => was_above && !is_above,
+                    Edge::Both => was_above != is_above,
+                };
+                triggered.then_some(Crossing {
+                    index: i + 1,
+                    value: pair[1],
+                })
+            })
+            .collect()
+    }
+}
+
+impl Algorithm for ThresholdTrigger {
+    fn process(&self, input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let crossings = self.crossings(&samples::f32_from_bytes(input)?);
+        serde_json::to_vec(&crossings).map_err(|e| CoreError::ProcessingFailed(e.to_string()))
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Threshold Trigger".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Emits indices and values where f32 input crosses a threshold".to_string(),
+            parameters: vec![
+                ParameterDefinition {
+                    name: "threshold".to_string(),
+                    parameter_type: ParameterType::Float,
+                    description: "Level that triggers a crossing".to_string(),
+                    default_value: None,
+                },
+                ParameterDefinition {
+                    name: "edge".to_string(),
+                    parameter_type: ParameterType::String,
+                    description: "Rising, Falling or Both".to_string(),
+                    default_value: Some("Rising".to_string()),
+                },
+                ParameterDefinition {
+                    name: "epsilon".to_string(),
+                    parameter_type: ParameterType::Float,
+                    description: "Tolerance within which a sample counts as at the threshold".to_string(),
+                    default_value: Some(DEFAULT_EPSILON.to_string()),
+                },
+            ],
+            ..Default::default()
+        }
+    }
+}
+
+impl Pure for ThresholdTrigger {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn run(edge: Edge, signal: &[f32]) -> Vec<Crossing> {
+        let trigger = ThresholdTrigger::new(1.0, edge).unwrap();
+        let output = trigger
+            .process(&samples::f32_to_bytes(signal), &mut MemoryManager::new())
+            .unwrap();
+        serde_json::from_slice(&output).unwrap()
+    }
+    
+    const SIGNAL: [f32; 7] = [0.0, 0.5, 1.5, 2.0, 0.5, 1.0, 0.0];
+    
+    fn indices(crossings: &[Crossing]) -> Vec<usize> {
+        crossings.iter().map(|crossing| crossing.index).collect()
+    }
+    
+    #[test]
+    fn test_rising_edges_only() {
+        let crossings = run(Edge::Rising, &SIGNAL);
+        assert_eq!(indices(&crossings), vec![2, 5]);
+        assert_eq!(crossings[0].value, 1.5);
+    }
+    
+    #[test]
+    fn test_falling_edges_only() {
+        assert_eq!(indices(&run(Edge::Falling, &SIGNAL)), vec![4, 6]);
+    }
+    
+    #[test]
+    fn test_both_edges() {
+        assert_eq!(indices(&run(Edge::Both, &SIGNAL)), vec![2, 4, 5, 6]);
+    }
+    
+    #[test]
+    fn test_no_crossings_yields_empty_list() {
+        assert!(run(Edge::Both, &[0.0, 0.1, 0.2]).is_empty());
+        assert!(run(Edge::Both, &[]).is_empty());
+    }
+    
+    #[test]
+    fn test_noise_within_epsilon_does_not_flap() {
+        let below = 1.0 - f32::EPSILON;
+        let noisy = [0.0, 1.0, below, 1.0, below, 1.0];
+        assert_eq!(indices(&run(Edge::Both, &noisy)), vec![1]);
+        
+        let exact = ThresholdTrigger::new(1.0, Edge::Both).unwrap().with_epsilon(0.0).unwrap();
+        assert_eq!(indices(&exact.crossings(&noisy)), vec![1, 2, 3, 4, 5]);
+        assert!(ThresholdTrigger::new(1.0, Edge::Both).unwrap().with_epsilon(-1.0).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+//! Rolling min/max/mean over a trailing window
+
+use serde_json::Value;
+use std::collections::VecDeque;
+
+use crate::algorithm::{params, Algorithm, AlgorithmMetadata, OutputSizeHint, ParameterDefinition, ParameterType, Pure};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// Per-position statistics over the trailing `window` samples of `f32` input
+///
+/// Output holds one `(min, max, mean)` triple per input sample, packed as
+/// little-endian `f32`s. Near the start of the stream the window shrinks to
+/// the samples seen so far, so the first triple describes only the first
+/// sample.
+#[derive(Clone, Debug)]
+pub struct WindowStats {
+    window: usize,
+}
+
+impl WindowStats {
+    pub const ID: &'static str = "window_stats";
+    
+    /// Create a stats window, rejecting a zero width
+    pub fn new(window: usize) -> Result<Self, CoreError> {
+        if window == 0 {
+            return Err(CoreError::InvalidParameter("window must be at least 1".to_string()));
+        }
+        Ok(Self { window })
+    }
+    
+    /// Create a stats window from its `window` parameter
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Self::new(params::require(params, "window")?)
+    }
+    
+    /// Compute `(min, max, mean)` for every position
+    pub fn compute(&self, samples: &[f32]) -> Vec<(f32, f32, f32)> {
+        let mut stats = Vec::with_capacity(samples.len());
+        self.for_each(samples.len(), |i| samples[i], |min, max, mean| stats.push((min, max, mean)));
+        stats
+    }
+    
+    /// Pass `(min, max, mean)` for each of the `len` samples, read through
+    /// `sample`, to `emit` in order
+    fn for_each(&self, len: usize, sample: impl Fn(usize) -> f32, mut emit: impl FnMut(f32, f32, f32)) {
+        // Monotonic deques of indices give O(1) amortized min/max
+        let mut mins: VecDeque<usize> = VecDeque::new();
+        let mut maxs: VecDeque<usize> = VecDeque::new();
+        let mut sum = CompensatedSum::default();
+        
+        for i in 0..len {
+            let value = sample(i);
+            while mins.back().is_some_and(|&j| sample(j) >= value) {
+                mins.pop_back();
+            }
+            mins.push_back(i);
+            while maxs.back().is_some_and(|&j| sample(j) <= value) {
+                maxs.pop_back();
+            }
+            maxs.push_back(i);
+            sum.add(value as f64);
+            
+            if i >= self.window {
+                sum.add(-(sample(i - self.window) as f64));
+            }
+            let start = (i + 1).saturating_sub(self.window);
+            while mins.front().is_some_and(|&j| j < start) {
+                mins.pop_front();
+            }
+            while maxs.front().is_some_and(|&j| j < start) {
+                maxs.pop_front();
+            }
+            
+            let count = (i + 1 - start) as f64;
+            emit(sample(mins[0]), sample(maxs[0]), (sum.value() / count) as f32);
+        }
+    }
+}
+
+/// Neumaier-compensated sum, so sliding add/subtract does not drift
+#[derive(Default)]
+struct CompensatedSum {
+    sum: f64,
+    compensation: f64,
+}
+
+impl CompensatedSum {
+    fn add(&mut self, value: f64) {
+        let total = self.sum + value;
+        if self.sum.abs() >= value.abs() {
+            self.compensation += (self.sum - total) + value;
+        } else {
+            self.compensation += (value - total) + self.sum;
+        }
+        self.sum = total;
+    }
+    
+    fn value(&self) -> f64 {
+        self.sum + self.compensation
+    }
+}
+
+impl Algorithm for WindowStats {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::with_capacity(input.len() * 3);
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        if !input.len().is_multiple_of(4) {
+            return Err(CoreError::InvalidInput(format!("length {} is not a multiple of 4", input.len())));
+        }
+        // Samples are read straight from the input bytes, so nothing is
+        // decoded into an intermediate buffer
+        let sample = |i: usize| {
+            let bytes = &input[4 * i..4 * i + 4];
+            f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+        };
+        output.reserve(input.len() * 3);
+        self.for_each(input.len() / 4, sample, |min, max, mean| {
+            for value in [min, max, mean] {
+                output.extend_from_slice(&value.to_le_bytes());
+            }
+        });
+        Ok(())
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Window Stats".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Trailing-window min, max and mean of f32 samples".to_string(),
+            parameters: vec![ParameterDefinition {
+                name: "window".to_string(),
+                parameter_type: ParameterType::Integer,
+                description: "Number of trailing samples in each window".to_string(),
+                default_value: None,
+            }],
+            output_size_hint: OutputSizeHint::Ratio(3.0),
+            ..Default::default()
+        }
+    }
+}
+
+impl Pure for WindowStats {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::samples;
+    
+    fn run(window: usize, signal: &[f32]) -> Vec<(f32, f32, f32)> {
+        let stats = WindowStats::new(window).unwrap();
+        let output = stats
+            .process(&samples::f32_to_bytes(signal), &mut MemoryManager::new())
+            .unwrap();
+        samples::f32_from_bytes(&output)
+            .unwrap()
+            .chunks(3)
+            .map(|triple| (triple[0], triple[1], triple[2]))
+            .collect()
+    }
+    
+    #[test]
+    fn test_ramp_with_shrinking_start() {
+        let stats = run(3, &[1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(
+            stats,
+            vec![(1.0, 1.0, 1.0), (1.0, 2.0, 1.5), (1.0, 3.0, 2.0), (2.0, 4.0, 3.0), (3.0, 5.0, 4.0)]
+        );
+    }
+    
+    #[test]
+    fn test_outlier_enters_and_leaves_window() {
+        let stats = run(2, &[0.0, 0.0, 100.0, 0.0, 0.0, -50.0]);
+        let maxima: Vec<f32> = stats.iter().map(|s| s.1).collect();
+        let minima: Vec<f32> = stats.iter().map(|s| s.0).collect();
+        assert_eq!(maxima, vec![0.0, 0.0, 100.0, 100.0, 0.0, 0.0]);
+        assert_eq!(minima, vec![0.0, 0.0, 0.0, 0.0, 0.0, -50.0]);
+        assert_eq!(stats[3].2, 50.0);
+        assert_eq!(stats[4].2, 0.0);
+    }
+    
+    #[test]
+    fn test_mean_does_not_drift_over_long_streams() {
+        // Large offsets entering and leaving the window would leave a residue
+        // in a naive running sum
+        let mut signal = vec![1.0e7f32; 10_000];
+        signal.extend(std::iter::repeat_n(0.1f32, 1_000));
+        let stats = run(500, &signal);
+        assert!((stats.last().unwrap().2 - 0.1).abs() < 1e-6);
+    }
+    
+    #[test]
+    fn test_zero_window_rejected() {
+        assert!(WindowStats::new(0).is_err());
+    }
+    
+    #[test]
+    fn test_process_into_matches_compute() {
+        let stats = WindowStats::new(2).unwrap();
+        let signal = [3.0, -1.0, 4.0, 1.5];
+        let mut output = Vec::new();
+        stats
+            .process_into(&samples::f32_to_bytes(&signal), &mut output, &mut MemoryManager::new())
+            .unwrap();
+        let flat: Vec<f32> = stats.compute(&signal).into_iter().flat_map(|(min, max, mean)| [min, max, mean]).collect();
+        assert_eq!(samples::f32_from_bytes(&output).unwrap(), flat);
+        assert!(stats.process_into(&[0; 5], &mut output, &mut MemoryManager::new()).is_err());
+    }
+}
@@ -0,0 +1,224 @@
+//! Time and randomness injected into an execution
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+pub use rand_core::RngCore;
+
+use crate::batch::CancellationToken;
+use crate::clock::{Clock, MockClock, SystemClock};
+use crate::preemption::Lane;
+
+/// Sources an algorithm must draw time and randomness from, instead of
+/// reaching for globals
+///
+/// The engine builds one per execution. An engine made deterministic with
+/// `CoreEngineBuilder::seed` hands every execution a clock frozen at zero
+/// and a generator seeded with the same value, so algorithms that take
+/// everything from their context give the same output on every run.
+pub struct Context<'a> {
+    /// Clock to read or sleep on
+    pub clock: &'a dyn Clock,
+    /// Random number generator
+    pub rng: &'a mut dyn RngCore,
+    job: Option<&'a Job>,
+}
+
+impl<'a> Context<'a> {
+    pub fn new(clock: &'a dyn Clock, rng: &'a mut dyn RngCore) -> Self {
+        Self { clock, rng, job: None }
+    }
+    
+    /// Whether the caller cancelled the asynchronous execution this is part
+    /// of
+    ///
+    /// Long-running algorithms should check this between steps and return
+    /// `CoreError::Cancelled` once it is set. Always `false` for synchronous
+    /// executions.
+    pub fn is_cancelled(&self) -> bool {
+        self.job.is_some_and(|job| job.cancel.is_cancelled())
+    }
+    
+    /// Report how much of the work is done, as a fraction within `[0, 1]`,
+    /// to whoever polls the asynchronous execution
+    ///
+    /// Ignored for synchronous executions.
+    pub fn report_progress(&self, fraction: f32) {
+        if let Some(job) = self.job {
+            job.set_progress(fraction);
+        }
+    }
+    
+    /// Cooperative yield point: pause while a more urgent execution runs
+    /// or this execution's class has used its CPU budget
+    ///
+    /// Call it between steps, next to `is_cancelled`; a pause ends early
+    /// once the execution is cancelled. Returns at once for synchronous
+    /// executions. See `ExecutionClass` for who yields to whom.
+    pub fn yield_now(&self) {
+        if let Some(job) = self.job {
+            if let Some(lane) = &job.lane {
+                lane.checkpoint(&job.cancel);
+            }
+        }
+    }
+    
+    /// Run `f` with the system clock and a generator seeded from entropy,
+    /// as `Algorithm::process` does for algorithms that use a context
+    pub fn with_default<R>(f: impl FnOnce(&mut Context<'_>) -> R) -> R {
+        let clock = SystemClock::new();
+        let mut rng = SeededRng::from_entropy();
+        f(&mut Context::new(&clock, &mut rng))
+    }
+    
+    /// Uniform sample in `[0, 1)`
+    pub fn unit_f32(&mut self) -> f32 {
+        // The top 24 bits fill an f32 mantissa exactly
+        (self.rng.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+}
+
+/// Small, fast generator (SplitMix64) whose output depends only on its
+/// seed
+///
+/// Not suitable for cryptography.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+    
+    /// Seed from the per-process randomness the standard library uses for
+    /// hash maps, mixed with the current time
+    pub fn from_entropy() -> Self {
+        let mut hasher = RandomState::new().build_hasher();
+        // The browser has no system time to read
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Ok(elapsed) = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+            hasher.write_u128(elapsed.as_nanos());
+        }
+        Self::new(hasher.finish())
+    }
+}
+
+impl RngCore for SeededRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+    
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        rand_core::impls::fill_bytes_via_next(self, dst)
+    }
+}
+
+/// Cancellation and progress shared between an asynchronous execution's
+/// handle and the algorithm it runs
+#[derive(Debug)]
+pub(crate) struct Job {
+    pub(crate) cancel: CancellationToken,
+    // Bits of the last reported fraction, or `NO_PROGRESS`
+    progress: AtomicU32,
+    // The class the execution runs in, paused at yield points
+    pub(crate) lane: Option<Lane>,
+}
+
+// A NaN, which `set_progress` never stores
+const NO_PROGRESS: u32 = u32::MAX;
+
+impl Job {
+    pub(crate) fn new() -> Self {
+        Self {
+            cancel: CancellationToken::new(),
+            progress: AtomicU32::new(NO_PROGRESS),
+            lane: None,
+        }
+    }
+    
+    /// The same job, running in `lane`
+    pub(crate) fn with_lane(self, lane: Lane) -> Self {
+        Self {
+            lane: Some(lane),
+            ..self
+        }
+    }
+    
+    /// The last fraction reported, if any
+    pub(crate) fn progress(&self) -> Option<f32> {
+        Some(self.progress.load(Ordering::Relaxed))
+            .filter(|&bits| bits != NO_PROGRESS)
+            .map(f32::from_bits)
+    }
+    
+    /// Record `fraction`, clamped to `[0, 1]`; NaN is ignored
+    pub(crate) fn set_progress(&self, fraction: f32) {
+        if !fraction.is_nan() {
+            self.progress.store(fraction.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+        }
+    }
+}
+
+/// What the engine builds each execution's `Context` from
+#[derive(Clone)]
+pub(crate) struct ContextSource {
+    clock: Arc<dyn Clock>,
+    // Set in deterministic mode
+    seed: Option<u64>,
+    // Set for asynchronous executions
+    job: Option<Arc<Job>>,
+}
+
+impl ContextSource {
+    pub(crate) fn new(clock: Arc<dyn Clock>, seed: Option<u64>) -> Self {
+        Self { clock, seed, job: None }
+    }
+    
+    /// The same source, with contexts reporting to `job`
+    pub(crate) fn with_job(&self, job: Arc<Job>) -> Self {
+        Self {
+            job: Some(job),
+            ..self.clone()
+        }
+    }
+    
+    pub(crate) fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+    
+    /// Run `f` with a fresh context for one execution
+    pub(crate) fn run<R>(&self, f: impl FnOnce(&mut Context<'_>) -> R) -> R {
+        let job = self.job.as_deref();
+        match self.seed {
+            Some(seed) => {
+                let clock = MockClock::new();
+                let mut rng = SeededRng::new(seed);
+                f(&mut Context {
+                    clock: &clock,
+                    rng: &mut rng,
+                    job,
+                })
+            }
+            None => {
+                let mut rng = SeededRng::from_entropy();
+                f(&mut Context {
+                    clock: self.clock.as_ref(),
+                    rng: &mut rng,
+                    job,
+                })
+            }
+        }
+    }
+}
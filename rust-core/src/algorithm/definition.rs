@@ -0,0 +1,360 @@
+//! Declarative algorithm definitions
+//!
+//! A definition describes an algorithm as an ordered list of built-in stages,
+//! each fed the previous stage's output, along with named parameters the
+//! stages' own parameters can refer to and optional input and output
+//! schemas. Definitions deserialize from JSON,
+//! and from YAML or TOML with the respective features; all formats map onto
+//! the same `AlgorithmDefinition` and build identical algorithms.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::context::Context;
+use super::schema::DataSchema;
+use super::{builtins, Algorithm, AlgorithmMetadata, ParameterDefinition, ParameterType};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// Serializable description of a composed algorithm
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AlgorithmDefinition {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    /// Named values, with their defaults, that stage parameters refer to as
+    /// `"$name"`
+    #[serde(default)]
+    pub parameters: Map<String, Value>,
+    /// Schema every input must conform to
+    #[serde(default)]
+    pub input_schema: Option<DataSchema>,
+    /// Schema every output must conform to
+    #[serde(default)]
+    pub output_schema: Option<DataSchema>,
+    pub stages: Vec<StageDefinition>,
+}
+
+/// One built-in operation within a definition
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StageDefinition {
+    /// ID of the built-in algorithm to run
+    pub op: String,
+    /// Parameters passed to the built-in
+    #[serde(default)]
+    pub params: Value,
+}
+
+impl AlgorithmDefinition {
+    /// Parse a definition from JSON
+    pub fn from_json(source: &str) -> Result<Self, CoreError> {
+        serde_json::from_str(source).map_err(|e| CoreError::InvalidDefinition(e.to_string()))
+    }
+    
+    /// Parse a definition from YAML
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(source: &str) -> Result<Self, CoreError> {
+        serde_yaml::from_str(source).map_err(|e| CoreError::InvalidDefinition(e.to_string()))
+    }
+    
+    /// Parse a definition from TOML
+    #[cfg(feature = "toml")]
+    pub fn from_toml(source: &str) -> Result<Self, CoreError> {
+        toml::from_str(source).map_err(|e| CoreError::InvalidDefinition(e.to_string()))
+    }
+    
+    /// Instantiate the algorithm with its parameters' defaults, validating
+    /// every stage
+    pub fn build(&self) -> Result<Box<dyn Algorithm>, CoreError> {
+        self.build_with(&Value::Null)
+    }
+    
+    /// Instantiate the algorithm with some parameters overridden by the
+    /// fields of `overrides`
+    ///
+    /// Overriding a parameter the definition does not declare fails with
+    /// `CoreError::InvalidParameter`.
+    pub fn build_with(&self, overrides: &Value) -> Result<Box<dyn Algorithm>, CoreError> {
+        if self.stages.is_empty() {
+            return Err(CoreError::InvalidDefinition(format!("'{}' has no stages", self.id)));
+        }
+        let mut parameters = self.parameters.clone();
+        match overrides {
+            Value::Null => {}
+            Value::Object(fields) => {
+                for (name, value) in fields {
+                    let Some(slot) = parameters.get_mut(name) else {
+                        return Err(CoreError::InvalidParameter(format!("'{}' has no parameter '{}'", self.id, name)));
+                    };
+                    *slot = value.clone();
+                }
+            }
+            _ => return Err(CoreError::InvalidParameter("parameter overrides must be an object".to_string())),
+        }
+        let stages = self
+            .stages
+            .iter()
+            .enumerate()
+            .map(|(index, stage)| {
+                substitute(&stage.params, &parameters)
+                    .and_then(|params| builtins::create(&stage.op, &params))
+                    .map_err(|e| {
+                        CoreError::InvalidDefinition(format!("stage {} ('{}') of '{}': {}", index, stage.op, self.id, e))
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Box::new(DefinedAlgorithm {
+            definition: AlgorithmDefinition {
+                parameters,
+                ..self.clone()
+            },
+            stages,
+        }))
+    }
+}
+
+/// `params` with every `"$name"` string replaced by parameter `name`
+///
+/// A string starting with `$$` stands for itself with one `$` dropped.
+fn substitute(params: &Value, parameters: &Map<String, Value>) -> Result<Value, CoreError> {
+    Ok(match params {
+        Value::String(text) => match text.strip_prefix('$') {
+            Some(escaped) if escaped.starts_with('$') => Value::String(escaped.to_string()),
+            Some(name) => parameters
+                .get(name)
+                .cloned()
+                .ok_or_else(|| CoreError::InvalidParameter(format!("unknown parameter '{}'", name)))?,
+            None => params.clone(),
+        },
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| substitute(item, parameters))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), substitute(value, parameters)?)))
+                .collect::<Result<_, CoreError>>()?,
+        ),
+        _ => params.clone(),
+    })
+}
+
+/// Type a parameter's default value declares it as
+fn parameter_type(value: &Value) -> ParameterType {
+    match value {
+        Value::Bool(_) => ParameterType::Boolean,
+        Value::Number(n) if n.is_i64() || n.is_u64() => ParameterType::Integer,
+        Value::Number(_) => ParameterType::Float,
+        Value::Array(_) => ParameterType::Array,
+        Value::Object(_) => ParameterType::Object,
+        Value::Null | Value::String(_) => ParameterType::String,
+    }
+}
+
+/// Algorithm built from an `AlgorithmDefinition`
+struct DefinedAlgorithm {
+    definition: AlgorithmDefinition,
+    stages: Vec<Box<dyn Algorithm>>,
+}
+
+impl Algorithm for DefinedAlgorithm {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::new();
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, memory: &mut MemoryManager) -> Result<(), CoreError> {
+        Context::with_default(|context| self.process_in_context(input, output, memory, context))
+    }
+    
+    fn process_in_context(
+        &self,
+        input: &[u8],
+        output: &mut Vec<u8>,
+        memory: &mut MemoryManager,
+        context: &mut Context<'_>,
+    ) -> Result<(), CoreError> {
+        let Some((last, rest)) = self.stages.split_last() else {
+            return Err(CoreError::InvalidDefinition(format!("'{}' has no stages", self.definition.id)));
+        };
+        if let Some(reason) = self.definition.input_schema.as_ref().and_then(|schema| schema.mismatch(input)) {
+            return Err(CoreError::SchemaMismatch(format!("input of '{}': {}", self.definition.id, reason)));
+        }
+        let mut current = input.to_vec();
+        for stage in rest {
+            let mut staged = Vec::new();
+            stage.process_in_context(&current, &mut staged, memory, context)?;
+            current = staged;
+        }
+        let start = output.len();
+        last.process_in_context(&current, output, memory, context)?;
+        if let Some(reason) = self.definition.output_schema.as_ref().and_then(|schema| schema.mismatch(&output[start..])) {
+            output.truncate(start);
+            return Err(CoreError::SchemaMismatch(format!("output of '{}': {}", self.definition.id, reason)));
+        }
+        Ok(())
+    }
+    
+    fn id(&self) -> &str {
+        &self.definition.id
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: self.definition.name.clone(),
+            version: self.definition.version.clone(),
+            description: self.definition.description.clone(),
+            parameters: self
+                .definition
+                .parameters
+                .iter()
+                .map(|(name, value)| ParameterDefinition {
+                    name: name.clone(),
+                    parameter_type: parameter_type(value),
+                    description: String::new(),
+                    default_value: Some(value.as_str().map_or_else(|| value.to_string(), str::to_string)),
+                })
+                .collect(),
+            input_schema: self.definition.input_schema.clone(),
+            output_schema: self.definition.output_schema.clone(),
+            // Later stages see intermediate data, so only the first stage's
+            // minimum applies to the caller's input
+            min_input_bytes: self.stages.first().map_or(0, |stage| stage.metadata().min_input_bytes),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::samples;
+    
+    const JSON: &str = r#"{
+        "id": "sanitize",
+        "name": "Sanitize",
+        "version": "1.0.0",
+        "stages": [
+            {"op": "clamp", "params": {"min": -1.0, "max": 1.0, "nan_policy": "Zero"}},
+            {"op": "passthrough"},
+            {"op": "clamp", "params": {"min": 0.0, "max": 0.5}}
+        ]
+    }"#;
+    
+    fn run(algorithm: &dyn Algorithm) -> Vec<f32> {
+        let input = samples::f32_to_bytes(&[-3.0, 0.25, f32::NAN, 0.75, 9.0]);
+        let output = algorithm.process(&input, &mut MemoryManager::new()).unwrap();
+        samples::f32_from_bytes(&output).unwrap()
+    }
+    
+    #[test]
+    fn test_json_definition_runs_stages_in_order() {
+        let algorithm = crate::algorithm::create_algorithm_from_json(JSON).unwrap();
+        assert_eq!(algorithm.id(), "sanitize");
+        assert_eq!(algorithm.metadata().name, "Sanitize");
+        assert_eq!(run(algorithm.as_ref()), vec![0.0, 0.25, 0.0, 0.5, 0.5]);
+    }
+    
+    #[test]
+    fn test_invalid_definitions_rejected() {
+        assert!(matches!(AlgorithmDefinition::from_json("{"), Err(CoreError::InvalidDefinition(_))));
+        
+        let no_stages = AlgorithmDefinition::from_json(r#"{"id": "empty", "stages": []}"#).unwrap();
+        assert!(no_stages.build().is_err());
+        
+        let bad_stage = r#"{"id": "bad", "stages": [{"op": "clamp", "params": {"min": 1.0}}]}"#;
+        assert!(matches!(
+            crate::algorithm::create_algorithm_from_json(bad_stage),
+            Err(CoreError::InvalidDefinition(_))
+        ));
+    }
+    
+    #[test]
+    fn test_parameters_and_schemas_configure_the_composition() {
+        const DSL: &str = r#"{
+            "id": "rectify",
+            "parameters": {"gain": 2.0, "limit": 3.0},
+            "input_schema": {"element_type": "F32", "elements": null},
+            "output_schema": {"element_type": "F32", "elements": 3},
+            "stages": [
+                {"op": "map", "params": {"function": "Abs"}},
+                {"op": "scale", "params": {"factor": "$gain"}},
+                {"op": "clamp", "params": {"min": 0.0, "max": "$limit"}}
+            ]
+        }"#;
+        let definition = AlgorithmDefinition::from_json(DSL).unwrap();
+        let mut memory = MemoryManager::new();
+        let input = samples::f32_to_bytes(&[-1.0, 0.5, 4.0]);
+        
+        let algorithm = definition.build().unwrap();
+        let output = algorithm.process(&input, &mut memory).unwrap();
+        assert_eq!(samples::f32_from_bytes(&output).unwrap(), vec![2.0, 1.0, 3.0]);
+        let metadata = algorithm.metadata();
+        assert_eq!(metadata.parameters.len(), 2);
+        assert_eq!(metadata.parameters[0].default_value.as_deref(), Some("2.0"));
+        assert!(metadata.output_schema.is_some());
+        
+        let gentle = definition.build_with(&serde_json::json!({"gain": 0.5})).unwrap();
+        let output = gentle.process(&input, &mut memory).unwrap();
+        assert_eq!(samples::f32_from_bytes(&output).unwrap(), vec![0.5, 0.25, 2.0]);
+        assert!(definition.build_with(&serde_json::json!({"offset": 1.0})).is_err());
+        
+        // Both schemas are enforced around the stages
+        assert!(matches!(algorithm.process(&[0; 3], &mut memory), Err(CoreError::SchemaMismatch(_))));
+        let four = samples::f32_to_bytes(&[1.0; 4]);
+        assert!(matches!(algorithm.process(&four, &mut memory), Err(CoreError::SchemaMismatch(_))));
+        
+        let unknown = DSL.replace("$limit", "$ceiling");
+        assert!(matches!(
+            crate::algorithm::create_algorithm_from_json(&unknown),
+            Err(CoreError::InvalidDefinition(_))
+        ));
+    }
+    
+    #[cfg(all(feature = "yaml", feature = "toml"))]
+    #[test]
+    fn test_formats_are_interchangeable() {
+        const YAML: &str = r#"
+id: sanitize
+name: Sanitize
+version: 1.0.0
+stages:
+  - op: clamp
+    params: { min: -1.0, max: 1.0, nan_policy: Zero }
+  - op: passthrough
+  - op: clamp
+    params: { min: 0.0, max: 0.5 }
+"#;
+        const TOML: &str = r#"
+id = "sanitize"
+name = "Sanitize"
+version = "1.0.0"
+
+[[stages]]
+op = "clamp"
+params = { min = -1.0, max = 1.0, nan_policy = "Zero" }
+
+[[stages]]
+op = "passthrough"
+
+[[stages]]
+op = "clamp"
+params = { min = 0.0, max = 0.5 }
+"#;
+        let json = AlgorithmDefinition::from_json(JSON).unwrap();
+        assert_eq!(AlgorithmDefinition::from_yaml(YAML).unwrap(), json);
+        assert_eq!(AlgorithmDefinition::from_toml(TOML).unwrap(), json);
+        
+        let expected = run(crate::algorithm::create_algorithm_from_json(JSON).unwrap().as_ref());
+        assert_eq!(run(crate::algorithm::create_algorithm_from_yaml(YAML).unwrap().as_ref()), expected);
+        assert_eq!(run(crate::algorithm::create_algorithm_from_toml(TOML).unwrap().as_ref()), expected);
+    }
+}
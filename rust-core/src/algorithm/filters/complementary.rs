@@ -0,0 +1,149 @@
+//! Complementary filter fusing a rate sensor with an absolute one
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use serde_json::Value;
+
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, OutputSizeHint, ParameterDefinition, ParameterType, Pure};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// Fuses a rate, such as a gyro's, with an absolute measurement of what it
+/// is the rate of, such as an accelerometer's tilt angle
+///
+/// The input is interleaved `f32` `(rate, measurement)` pairs sampled every
+/// `dt` seconds. Each estimate integrates the rate from the previous one
+/// and blends in the measurement:
+/// `angle = alpha * (angle + rate * dt) + (1 - alpha) * measurement`. The
+/// rate's short-term accuracy and the measurement's freedom from drift
+/// combine, with `alpha` near 1 trusting the rate more. The first estimate
+/// is the first measurement, and one estimate is emitted per pair.
+#[derive(Clone, Debug)]
+pub struct Complementary {
+    alpha: f32,
+    dt: f32,
+}
+
+impl Complementary {
+    pub const ID: &'static str = "complementary";
+    
+    /// Create the filter, rejecting an `alpha` outside `[0, 1]` or a
+    /// non-positive `dt`
+    pub fn new(alpha: f32, dt: f32) -> Result<Self, CoreError> {
+        if !(0.0..=1.0).contains(&alpha) {
+            return Err(CoreError::InvalidParameter(format!("alpha must be within [0, 1], got {}", alpha)));
+        }
+        if !dt.is_finite() || dt <= 0.0 {
+            return Err(CoreError::InvalidParameter(format!("dt must be finite and positive, got {}", dt)));
+        }
+        Ok(Self { alpha, dt })
+    }
+    
+    /// Create the filter from its `alpha` and `dt` parameters
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Self::new(params::require(params, "alpha")?, params::require(params, "dt")?)
+    }
+    
+    /// Estimate after each `(rate, measurement)` pair
+    pub fn fuse(&self, pairs: &[(f32, f32)]) -> Vec<f32> {
+        let mut estimate = None;
+        pairs
+            .iter()
+            .map(|&(rate, measurement)| {
+                let next = match estimate {
+                    None => measurement,
+                    Some(angle) => self.alpha * (angle + rate * self.dt) + (1.0 - self.alpha) * measurement,
+                };
+                estimate = Some(next);
+                next
+            })
+            .collect()
+    }
+}
+
+impl Algorithm for Complementary {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::with_capacity(input.len() / 2);
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        let values = samples::f32_from_bytes(input)?;
+        if !values.len().is_multiple_of(2) {
+            return Err(CoreError::InvalidInput(format!(
+                "{} f32 values do not form whole (rate, measurement) pairs",
+                values.len()
+            )));
+        }
+        let pairs: Vec<(f32, f32)> = values.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect();
+        for estimate in self.fuse(&pairs) {
+            output.extend_from_slice(&estimate.to_le_bytes());
+        }
+        Ok(())
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Complementary Filter".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Fuses f32 (rate, measurement) pairs into one estimate per pair".to_string(),
+            parameters: vec![
+                ParameterDefinition {
+                    name: "alpha".to_string(),
+                    parameter_type: ParameterType::Float,
+                    description: "Weight of the integrated rate, within [0, 1]".to_string(),
+                    default_value: None,
+                },
+                ParameterDefinition {
+                    name: "dt".to_string(),
+                    parameter_type: ParameterType::Float,
+                    description: "Seconds between pairs".to_string(),
+                    default_value: None,
+                },
+            ],
+            output_size_hint: OutputSizeHint::Ratio(0.5),
+            ..Default::default()
+        }
+    }
+}
+
+impl Pure for Complementary {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[test]
+    fn test_blends_integrated_rate_with_measurement() {
+        let filter = Complementary::from_params(&json!({"alpha": 0.5, "dt": 0.1})).unwrap();
+        // 0.5 * (1.0 + 2.0 * 0.1) + 0.5 * 2.0 = 1.6
+        let input = samples::f32_to_bytes(&[0.0, 1.0, 2.0, 2.0]);
+        let mut memory = MemoryManager::new();
+        let output = samples::f32_from_bytes(&filter.process(&input, &mut memory).unwrap()).unwrap();
+        assert_eq!(output.len(), 2);
+        assert!((output[0] - 1.0).abs() < 1e-6 && (output[1] - 1.6).abs() < 1e-6, "{:?}", output);
+    }
+    
+    #[test]
+    fn test_alpha_extremes_follow_one_sensor() {
+        let pairs = [(0.0, 5.0), (1.0, 5.0), (1.0, 5.0)];
+        assert_eq!(Complementary::new(1.0, 1.0).unwrap().fuse(&pairs), vec![5.0, 6.0, 7.0]);
+        assert_eq!(Complementary::new(0.0, 1.0).unwrap().fuse(&pairs), vec![5.0, 5.0, 5.0]);
+        
+        assert!(Complementary::new(1.5, 1.0).is_err());
+        assert!(Complementary::new(0.5, 0.0).is_err());
+        let mut memory = MemoryManager::new();
+        let odd = samples::f32_to_bytes(&[1.0]);
+        assert!(matches!(
+            Complementary::new(0.5, 1.0).unwrap().process(&odd, &mut memory),
+            Err(CoreError::InvalidInput(_))
+        ));
+    }
+}
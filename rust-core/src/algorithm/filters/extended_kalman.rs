@@ -0,0 +1,243 @@
+//! Extended Kalman filter for nonlinear models
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use super::kalman::{write_states, Tuning};
+use super::matrix::Matrix;
+use crate::algorithm::{Algorithm, AlgorithmMetadata};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// Nonlinear system an `ExtendedKalman` tracks
+///
+/// Vectors and Jacobians are `f64` slices; Jacobians are row-major.
+pub trait NonlinearModel: Send + Sync {
+    /// Number of state variables
+    fn state_dim(&self) -> usize;
+    
+    /// Number of values per measurement
+    fn measurement_dim(&self) -> usize;
+    
+    /// State one step after `state`
+    fn transition(&self, state: &[f64]) -> Vec<f64>;
+    
+    /// Jacobian of `transition` at `state`, `state_dim x state_dim`
+    fn transition_jacobian(&self, state: &[f64]) -> Vec<f64>;
+    
+    /// Measurement expected in `state`
+    fn observe(&self, state: &[f64]) -> Vec<f64>;
+    
+    /// Jacobian of `observe` at `state`, `measurement_dim x state_dim`
+    fn observation_jacobian(&self, state: &[f64]) -> Vec<f64>;
+}
+
+/// Extended Kalman filter, linearizing a `NonlinearModel` around each
+/// estimate
+///
+/// Input and output are laid out as for `Kalman`, and each call likewise
+/// starts from the initial state. The model is code rather than
+/// parameters, so this filter is built with `new` and registered like any
+/// user algorithm instead of being created by ID; register several under
+/// different IDs with `AlgorithmRegistry::register_as`.
+pub struct ExtendedKalman {
+    model: Box<dyn NonlinearModel>,
+    tuning: Tuning,
+}
+
+impl ExtendedKalman {
+    pub const ID: &'static str = "extended_kalman";
+    
+    /// Create the filter, checking the noise covariances against the
+    /// model's dimensions
+    pub fn new(
+        model: Box<dyn NonlinearModel>,
+        process_noise: Vec<f64>,
+        measurement_noise: Vec<f64>,
+    ) -> Result<Self, CoreError> {
+        let tuning = Tuning::new(model.state_dim(), model.measurement_dim(), process_noise, measurement_noise)?;
+        Ok(Self { model, tuning })
+    }
+    
+    /// Start from `state` with `covariance` instead of zero and identity
+    pub fn with_initial(mut self, state: Vec<f64>, covariance: Vec<f64>) -> Result<Self, CoreError> {
+        self.tuning = self.tuning.with_initial(state, covariance)?;
+        Ok(self)
+    }
+    
+    /// State estimate after each measurement
+    ///
+    /// Fails with `CoreError::ProcessingFailed` if the model returns a
+    /// vector or Jacobian of the wrong size or with non-finite values.
+    pub fn filter(&self, measurements: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, CoreError> {
+        let n = self.tuning.state_dim();
+        let m = self.tuning.measurement_dim();
+        let mut estimate = self.tuning.start();
+        measurements
+            .iter()
+            .map(|z| {
+                let predicted = self.model_output("transition", n, 1, self.model.transition(estimate.state()))?;
+                let f = self.model_output("transition_jacobian", n, n, self.model.transition_jacobian(estimate.state()))?;
+                estimate.predict(predicted.data(), &f, &self.tuning);
+                let expected = self.model_output("observe", m, 1, self.model.observe(estimate.state()))?;
+                let h = self.model_output("observation_jacobian", m, n, self.model.observation_jacobian(estimate.state()))?;
+                let residual: Vec<f64> = z.iter().zip(expected.data()).map(|(z, h)| z - h).collect();
+                estimate.update(&residual, &h, &self.tuning)?;
+                Ok(estimate.state().to_vec())
+            })
+            .collect()
+    }
+    
+    fn model_output(&self, name: &str, rows: usize, cols: usize, data: Vec<f64>) -> Result<Matrix, CoreError> {
+        Matrix::new(name, rows, cols, data).map_err(|e| CoreError::ProcessingFailed(format!("model {}", e)))
+    }
+}
+
+impl Algorithm for ExtendedKalman {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::new();
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        write_states(self.filter(&self.tuning.measurements(input)?)?, output);
+        Ok(())
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Extended Kalman Filter".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Extended Kalman filter over a nonlinear model, emitting a state estimate per f32 measurement vector"
+                .to_string(),
+            parameters: Tuning::parameters(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::filters::Kalman;
+    
+    /// A constant observed through its square
+    struct Squared;
+    
+    impl NonlinearModel for Squared {
+        fn state_dim(&self) -> usize {
+            1
+        }
+        
+        fn measurement_dim(&self) -> usize {
+            1
+        }
+        
+        fn transition(&self, state: &[f64]) -> Vec<f64> {
+            state.to_vec()
+        }
+        
+        fn transition_jacobian(&self, _state: &[f64]) -> Vec<f64> {
+            vec![1.0]
+        }
+        
+        fn observe(&self, state: &[f64]) -> Vec<f64> {
+            vec![state[0] * state[0]]
+        }
+        
+        fn observation_jacobian(&self, state: &[f64]) -> Vec<f64> {
+            vec![2.0 * state[0]]
+        }
+    }
+    
+    /// The constant-velocity model of the `Kalman` tests, written out
+    struct ConstantVelocity;
+    
+    impl NonlinearModel for ConstantVelocity {
+        fn state_dim(&self) -> usize {
+            2
+        }
+        
+        fn measurement_dim(&self) -> usize {
+            1
+        }
+        
+        fn transition(&self, state: &[f64]) -> Vec<f64> {
+            vec![state[0] + state[1], state[1]]
+        }
+        
+        fn transition_jacobian(&self, _state: &[f64]) -> Vec<f64> {
+            vec![1.0, 1.0, 0.0, 1.0]
+        }
+        
+        fn observe(&self, state: &[f64]) -> Vec<f64> {
+            vec![state[0]]
+        }
+        
+        fn observation_jacobian(&self, _state: &[f64]) -> Vec<f64> {
+            vec![1.0, 0.0]
+        }
+    }
+    
+    #[test]
+    fn test_converges_through_nonlinear_observation() {
+        let ekf = ExtendedKalman::new(Box::new(Squared), vec![1e-4], vec![0.1])
+            .unwrap()
+            .with_initial(vec![2.0], vec![1.0])
+            .unwrap();
+        let estimates = ekf.filter(&vec![vec![9.0]; 30]).unwrap();
+        let last = estimates.last().unwrap()[0];
+        assert!((last - 3.0).abs() < 1e-3, "{}", last);
+    }
+    
+    #[test]
+    fn test_linear_model_matches_kalman() {
+        let noise = (vec![1e-3, 0.0, 0.0, 1e-3], vec![0.5]);
+        let ekf = ExtendedKalman::new(Box::new(ConstantVelocity), noise.0.clone(), noise.1.clone()).unwrap();
+        let kalman = Kalman::new(2, 1, vec![1.0, 1.0, 0.0, 1.0], vec![1.0, 0.0], noise.0, noise.1).unwrap();
+        let measurements: Vec<Vec<f64>> = [0.9, 2.2, 2.8, 4.1, 5.0].iter().map(|&z| vec![z]).collect();
+        assert_eq!(ekf.filter(&measurements).unwrap(), kalman.filter(&measurements).unwrap());
+    }
+    
+    #[test]
+    fn test_malformed_model_output_fails_processing() {
+        struct Broken;
+        
+        impl NonlinearModel for Broken {
+            fn state_dim(&self) -> usize {
+                1
+            }
+            
+            fn measurement_dim(&self) -> usize {
+                1
+            }
+            
+            fn transition(&self, _state: &[f64]) -> Vec<f64> {
+                vec![0.0, 0.0]
+            }
+            
+            fn transition_jacobian(&self, _state: &[f64]) -> Vec<f64> {
+                vec![1.0]
+            }
+            
+            fn observe(&self, state: &[f64]) -> Vec<f64> {
+                state.to_vec()
+            }
+            
+            fn observation_jacobian(&self, _state: &[f64]) -> Vec<f64> {
+                vec![1.0]
+            }
+        }
+        
+        let ekf = ExtendedKalman::new(Box::new(Broken), vec![0.0], vec![1.0]).unwrap();
+        assert!(matches!(ekf.filter(&[vec![1.0]]), Err(CoreError::ProcessingFailed(_))));
+        assert!(ExtendedKalman::new(Box::new(Broken), vec![0.0; 4], vec![1.0]).is_err());
+    }
+}
@@ -0,0 +1,337 @@
+//! Linear Kalman filter
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use serde_json::Value;
+
+use super::matrix::Matrix;
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, OutputSizeHint, ParameterDefinition, ParameterType, Pure};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// Noise covariances and starting point shared by the Kalman filters
+#[derive(Clone, Debug)]
+pub(crate) struct Tuning {
+    process_noise: Matrix,
+    measurement_noise: Matrix,
+    initial_state: Vec<f64>,
+    initial_covariance: Matrix,
+}
+
+impl Tuning {
+    /// Tuning for `state_dim` states and `measurement_dim` measurements,
+    /// starting from a zero state with identity covariance
+    pub(crate) fn new(
+        state_dim: usize,
+        measurement_dim: usize,
+        process_noise: Vec<f64>,
+        measurement_noise: Vec<f64>,
+    ) -> Result<Self, CoreError> {
+        if state_dim == 0 || measurement_dim == 0 {
+            return Err(CoreError::InvalidParameter(format!(
+                "state and measurement dimensions must be positive, got {} and {}",
+                state_dim, measurement_dim
+            )));
+        }
+        Ok(Self {
+            process_noise: Matrix::new("process_noise", state_dim, state_dim, process_noise)?,
+            measurement_noise: Matrix::new("measurement_noise", measurement_dim, measurement_dim, measurement_noise)?,
+            initial_state: vec![0.0; state_dim],
+            initial_covariance: Matrix::identity(state_dim),
+        })
+    }
+    
+    pub(crate) fn with_initial(mut self, state: Vec<f64>, covariance: Vec<f64>) -> Result<Self, CoreError> {
+        let n = self.state_dim();
+        self.initial_state = Matrix::new("initial_state", n, 1, state)?.data().to_vec();
+        self.initial_covariance = Matrix::new("initial_covariance", n, n, covariance)?;
+        Ok(self)
+    }
+    
+    /// Read the optional `initial_state` and `initial_covariance` parameters
+    pub(crate) fn with_initial_params(mut self, params: &Value) -> Result<Self, CoreError> {
+        let n = self.state_dim();
+        let state = Matrix::from_param(params, "initial_state", n, 1, || Matrix::zeros(n, 1))?;
+        self.initial_state = state.data().to_vec();
+        self.initial_covariance = Matrix::from_param(params, "initial_covariance", n, n, || Matrix::identity(n))?;
+        Ok(self)
+    }
+    
+    pub(crate) fn state_dim(&self) -> usize {
+        self.process_noise.rows()
+    }
+    
+    pub(crate) fn measurement_dim(&self) -> usize {
+        self.measurement_noise.rows()
+    }
+    
+    pub(crate) fn start(&self) -> Estimate {
+        Estimate {
+            state: Matrix::column(&self.initial_state),
+            covariance: self.initial_covariance.clone(),
+        }
+    }
+    
+    /// Split `input` into measurement vectors of `measurement_dim` values
+    pub(crate) fn measurements(&self, input: &[u8]) -> Result<Vec<Vec<f64>>, CoreError> {
+        let values = samples::f32_from_bytes(input)?;
+        let m = self.measurement_dim();
+        if !values.len().is_multiple_of(m) {
+            return Err(CoreError::InvalidInput(format!(
+                "{} f32 values do not form whole {}-element measurements",
+                values.len(),
+                m
+            )));
+        }
+        Ok(values.chunks_exact(m).map(|z| z.iter().map(|&v| v as f64).collect()).collect())
+    }
+    
+    /// Standard parameter definitions for the noise and starting point
+    pub(crate) fn parameters() -> Vec<ParameterDefinition> {
+        let matrix = |name: &str, description: &str, default_value: Option<&str>| ParameterDefinition {
+            name: name.to_string(),
+            parameter_type: ParameterType::Array,
+            description: description.to_string(),
+            default_value: default_value.map(str::to_string),
+        };
+        vec![
+            matrix("process_noise", "Process noise covariance Q, state_dim x state_dim row-major", None),
+            matrix("measurement_noise", "Measurement noise covariance R, measurement_dim x measurement_dim row-major", None),
+            matrix("initial_state", "State before the first measurement", Some("zeros")),
+            matrix("initial_covariance", "Covariance of the initial state, row-major", Some("identity")),
+        ]
+    }
+}
+
+/// State estimate and its covariance
+pub(crate) struct Estimate {
+    state: Matrix,
+    covariance: Matrix,
+}
+
+impl Estimate {
+    pub(crate) fn state(&self) -> &[f64] {
+        self.state.data()
+    }
+    
+    /// Move to the `predicted` state through a transition with Jacobian
+    /// `jacobian`, adding process noise
+    pub(crate) fn predict(&mut self, predicted: &[f64], jacobian: &Matrix, tuning: &Tuning) {
+        self.state = Matrix::column(predicted);
+        self.covariance = jacobian
+            .mul(&self.covariance)
+            .mul(&jacobian.transpose())
+            .add(&tuning.process_noise);
+    }
+    
+    /// Correct the estimate by the difference between a measurement and
+    /// its prediction, through an observation with Jacobian `jacobian`
+    pub(crate) fn update(&mut self, residual: &[f64], jacobian: &Matrix, tuning: &Tuning) -> Result<(), CoreError> {
+        let covariance_h = self.covariance.mul(&jacobian.transpose());
+        let innovation = jacobian.mul(&covariance_h).add(&tuning.measurement_noise);
+        let gain = covariance_h.mul(&innovation.inverse().ok_or_else(|| {
+            CoreError::ProcessingFailed("innovation covariance is singular".to_string())
+        })?);
+        self.state = self.state.add(&gain.mul(&Matrix::column(residual)));
+        let n = self.state.rows();
+        self.covariance = Matrix::identity(n).sub(&gain.mul(jacobian)).mul(&self.covariance);
+        Ok(())
+    }
+}
+
+/// Write each state vector of `states` as `f32`s
+pub(crate) fn write_states(states: impl IntoIterator<Item = Vec<f64>>, output: &mut Vec<u8>) {
+    for state in states {
+        for value in state {
+            output.extend_from_slice(&(value as f32).to_le_bytes());
+        }
+    }
+}
+
+/// Linear Kalman filter over a stream of `f32` measurement vectors
+///
+/// The input holds consecutive measurements of `measurement_dim` values;
+/// for each one the filter predicts through the `transition` matrix F,
+/// corrects by the `observation` matrix H, and emits the `state_dim`-value
+/// state estimate, so the output is `state_dim / measurement_dim` times the
+/// input's length. Every call starts from the initial state, so the filter
+/// is `Pure`; run a whole stream in one call, or carry the last estimate
+/// into `with_initial` for the next. Matrices are row-major, and the
+/// arithmetic is done in `f64`.
+#[derive(Clone, Debug)]
+pub struct Kalman {
+    transition: Matrix,
+    observation: Matrix,
+    tuning: Tuning,
+}
+
+impl Kalman {
+    pub const ID: &'static str = "kalman";
+    
+    /// Create the filter, checking every matrix against the dimensions
+    pub fn new(
+        state_dim: usize,
+        measurement_dim: usize,
+        transition: Vec<f64>,
+        observation: Vec<f64>,
+        process_noise: Vec<f64>,
+        measurement_noise: Vec<f64>,
+    ) -> Result<Self, CoreError> {
+        let tuning = Tuning::new(state_dim, measurement_dim, process_noise, measurement_noise)?;
+        Ok(Self {
+            transition: Matrix::new("transition", state_dim, state_dim, transition)?,
+            observation: Matrix::new("observation", measurement_dim, state_dim, observation)?,
+            tuning,
+        })
+    }
+    
+    /// Start from `state` with `covariance` instead of zero and identity
+    pub fn with_initial(mut self, state: Vec<f64>, covariance: Vec<f64>) -> Result<Self, CoreError> {
+        self.tuning = self.tuning.with_initial(state, covariance)?;
+        Ok(self)
+    }
+    
+    /// Create the filter from its `state_dim`, `measurement_dim`,
+    /// `transition`, `observation`, `process_noise` and `measurement_noise`
+    /// parameters and optional `initial_state` and `initial_covariance`
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        let mut kalman = Self::new(
+            params::require(params, "state_dim")?,
+            params::require(params, "measurement_dim")?,
+            params::require(params, "transition")?,
+            params::require(params, "observation")?,
+            params::require(params, "process_noise")?,
+            params::require(params, "measurement_noise")?,
+        )?;
+        kalman.tuning = kalman.tuning.with_initial_params(params)?;
+        Ok(kalman)
+    }
+    
+    /// State estimate after each measurement
+    pub fn filter(&self, measurements: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, CoreError> {
+        let mut estimate = self.tuning.start();
+        measurements
+            .iter()
+            .map(|z| {
+                let predicted = self.transition.mul(&Matrix::column(estimate.state()));
+                estimate.predict(predicted.data(), &self.transition, &self.tuning);
+                let expected = self.observation.mul(&Matrix::column(estimate.state()));
+                let residual: Vec<f64> = z.iter().zip(expected.data()).map(|(z, h)| z - h).collect();
+                estimate.update(&residual, &self.observation, &self.tuning)?;
+                Ok(estimate.state().to_vec())
+            })
+            .collect()
+    }
+}
+
+impl Algorithm for Kalman {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::new();
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        write_states(self.filter(&self.tuning.measurements(input)?)?, output);
+        Ok(())
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        let dimension = |name: &str, description: &str| ParameterDefinition {
+            name: name.to_string(),
+            parameter_type: ParameterType::Integer,
+            description: description.to_string(),
+            default_value: None,
+        };
+        let matrix = |name: &str, description: &str| ParameterDefinition {
+            name: name.to_string(),
+            parameter_type: ParameterType::Array,
+            description: description.to_string(),
+            default_value: None,
+        };
+        let mut parameters = vec![
+            dimension("state_dim", "Number of state variables"),
+            dimension("measurement_dim", "Number of values per measurement"),
+            matrix("transition", "State transition F, state_dim x state_dim row-major"),
+            matrix("observation", "Observation H, measurement_dim x state_dim row-major"),
+        ];
+        parameters.extend(Tuning::parameters());
+        AlgorithmMetadata {
+            name: "Kalman Filter".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Linear Kalman filter emitting a state estimate per f32 measurement vector".to_string(),
+            parameters,
+            output_size_hint: OutputSizeHint::Ratio(self.tuning.state_dim() as f32 / self.tuning.measurement_dim() as f32),
+            ..Default::default()
+        }
+    }
+}
+
+impl Pure for Kalman {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    fn assert_close(actual: &[f64], expected: &[f64], tolerance: f64) {
+        assert!(
+            actual.iter().zip(expected).all(|(a, e)| (a - e).abs() < tolerance),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+    
+    #[test]
+    fn test_static_state_tracks_running_mean() {
+        // A constant observed directly, with a vague prior, is estimated by
+        // the mean of the measurements so far
+        let kalman = Kalman::new(1, 1, vec![1.0], vec![1.0], vec![0.0], vec![1.0])
+            .unwrap()
+            .with_initial(vec![0.0], vec![1e9])
+            .unwrap();
+        let estimates = kalman.filter(&[vec![1.0], vec![2.0], vec![3.0], vec![4.0]]).unwrap();
+        assert_close(&estimates.concat(), &[1.0, 1.5, 2.0, 2.5], 1e-6);
+    }
+    
+    #[test]
+    fn test_constant_velocity_recovers_speed_from_positions() {
+        let params = json!({
+            "state_dim": 2,
+            "measurement_dim": 1,
+            "transition": [1.0, 1.0, 0.0, 1.0],
+            "observation": [1.0, 0.0],
+            "process_noise": [1e-6, 0.0, 0.0, 1e-6],
+            "measurement_noise": [0.01],
+            "initial_covariance": [100.0, 0.0, 0.0, 100.0],
+        });
+        let kalman = Kalman::from_params(&params).unwrap();
+        let positions: Vec<f32> = (1..=50).map(|t| 2.0 * t as f32).collect();
+        let mut memory = MemoryManager::new();
+        let output = kalman.process(&samples::f32_to_bytes(&positions), &mut memory).unwrap();
+        let states = samples::f32_from_bytes(&output).unwrap();
+        assert_eq!(states.len(), 100);
+        assert!((states[98] - 100.0).abs() < 0.01 && (states[99] - 2.0).abs() < 0.01, "{:?}", &states[98..]);
+    }
+    
+    #[test]
+    fn test_mismatched_dimensions_rejected() {
+        assert!(matches!(
+            Kalman::new(2, 1, vec![1.0; 3], vec![1.0, 0.0], vec![0.0; 4], vec![1.0]),
+            Err(CoreError::InvalidParameter(_))
+        ));
+        assert!(Kalman::new(0, 1, vec![], vec![], vec![], vec![1.0]).is_err());
+        
+        let kalman = Kalman::new(1, 2, vec![1.0], vec![1.0, 1.0], vec![0.0], vec![1.0, 0.0, 0.0, 1.0]).unwrap();
+        assert!(kalman.clone().with_initial(vec![0.0, 0.0], vec![1.0]).is_err());
+        let mut memory = MemoryManager::new();
+        let odd = samples::f32_to_bytes(&[1.0, 2.0, 3.0]);
+        assert!(matches!(kalman.process(&odd, &mut memory), Err(CoreError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,180 @@
+//! Small dense `f64` matrices for the state estimators
+
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use serde_json::Value;
+
+use crate::algorithm::params;
+use crate::error::CoreError;
+use crate::math;
+
+/// Row-major matrix, sized for state vectors of a handful of elements
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Matrix {
+    rows: usize,
+    cols: usize,
+    data: Vec<f64>,
+}
+
+impl Matrix {
+    /// Wrap `data` as a `rows x cols` matrix, rejecting a wrong element
+    /// count or non-finite elements
+    pub(crate) fn new(name: &str, rows: usize, cols: usize, data: Vec<f64>) -> Result<Self, CoreError> {
+        if data.len() != rows * cols {
+            return Err(CoreError::InvalidParameter(format!(
+                "{} needs {}x{} = {} elements, got {}",
+                name,
+                rows,
+                cols,
+                rows * cols,
+                data.len()
+            )));
+        }
+        if let Some(value) = data.iter().find(|value| !value.is_finite()) {
+            return Err(CoreError::InvalidParameter(format!("{} holds non-finite {}", name, value)));
+        }
+        Ok(Self { rows, cols, data })
+    }
+    
+    /// Read the `rows x cols` matrix parameter `name`, or `default` if it
+    /// is absent
+    pub(crate) fn from_param(
+        params: &Value,
+        name: &str,
+        rows: usize,
+        cols: usize,
+        default: impl FnOnce() -> Matrix,
+    ) -> Result<Self, CoreError> {
+        match params::get(params, name)? {
+            Some(data) => Self::new(name, rows, cols, data),
+            None => Ok(default()),
+        }
+    }
+    
+    pub(crate) fn zeros(rows: usize, cols: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            data: vec![0.0; rows * cols],
+        }
+    }
+    
+    pub(crate) fn identity(n: usize) -> Self {
+        let mut identity = Self::zeros(n, n);
+        for i in 0..n {
+            identity.data[i * n + i] = 1.0;
+        }
+        identity
+    }
+    
+    /// A single column holding `values`
+    pub(crate) fn column(values: &[f64]) -> Self {
+        Self {
+            rows: values.len(),
+            cols: 1,
+            data: values.to_vec(),
+        }
+    }
+    
+    pub(crate) fn rows(&self) -> usize {
+        self.rows
+    }
+    
+    pub(crate) fn data(&self) -> &[f64] {
+        &self.data
+    }
+    
+    fn at(&self, row: usize, col: usize) -> f64 {
+        self.data[row * self.cols + col]
+    }
+    
+    /// Product `self * rhs`; the caller guarantees the shapes agree
+    pub(crate) fn mul(&self, rhs: &Matrix) -> Matrix {
+        Matrix {
+            rows: self.rows,
+            cols: rhs.cols,
+            data: math::mat_mul_f64(&self.data, &rhs.data, self.rows, self.cols, rhs.cols),
+        }
+    }
+    
+    pub(crate) fn transpose(&self) -> Matrix {
+        let mut transposed = Matrix::zeros(self.cols, self.rows);
+        math::transpose_f64_into(&self.data, self.rows, self.cols, &mut transposed.data);
+        transposed
+    }
+    
+    pub(crate) fn add(&self, rhs: &Matrix) -> Matrix {
+        self.zip(rhs, |a, b| a + b)
+    }
+    
+    pub(crate) fn sub(&self, rhs: &Matrix) -> Matrix {
+        self.zip(rhs, |a, b| a - b)
+    }
+    
+    pub(crate) fn scaled(&self, factor: f64) -> Matrix {
+        Matrix {
+            rows: self.rows,
+            cols: self.cols,
+            data: self.data.iter().map(|&a| a * factor).collect(),
+        }
+    }
+    
+    fn zip(&self, rhs: &Matrix, f: impl Fn(f64, f64) -> f64) -> Matrix {
+        Matrix {
+            rows: self.rows,
+            cols: self.cols,
+            data: self.data.iter().zip(&rhs.data).map(|(&a, &b)| f(a, b)).collect(),
+        }
+    }
+    
+    /// Inverse of a square matrix by Gauss-Jordan elimination with partial
+    /// pivoting, or `None` if it is singular
+    pub(crate) fn inverse(&self) -> Option<Matrix> {
+        let n = self.rows;
+        let mut work = self.clone();
+        let mut inverse = Matrix::identity(n);
+        for col in 0..n {
+            let pivot = (col..n).max_by(|&a, &b| work.at(a, col).abs().total_cmp(&work.at(b, col).abs()))?;
+            if work.at(pivot, col).abs() < f64::EPSILON {
+                return None;
+            }
+            work.swap_rows(col, pivot);
+            inverse.swap_rows(col, pivot);
+            let scale = work.at(col, col);
+            for j in 0..n {
+                work.data[col * n + j] /= scale;
+                inverse.data[col * n + j] /= scale;
+            }
+            for row in (0..n).filter(|&row| row != col) {
+                let factor = work.at(row, col);
+                for j in 0..n {
+                    work.data[row * n + j] -= factor * work.data[col * n + j];
+                    inverse.data[row * n + j] -= factor * inverse.data[col * n + j];
+                }
+            }
+        }
+        Some(inverse)
+    }
+    
+    fn swap_rows(&mut self, a: usize, b: usize) {
+        for col in 0..self.cols {
+            self.data.swap(a * self.cols + col, b * self.cols + col);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_inverse_round_trips_and_detects_singular() {
+        let m = Matrix::new("m", 2, 2, vec![4.0, 7.0, 2.0, 6.0]).unwrap();
+        let product = m.mul(&m.inverse().unwrap());
+        assert!(product.sub(&Matrix::identity(2)).data().iter().all(|x| x.abs() < 1e-12));
+        
+        assert!(Matrix::new("s", 2, 2, vec![1.0, 2.0, 2.0, 4.0]).unwrap().inverse().is_none());
+        assert!(Matrix::new("bad", 2, 2, vec![1.0; 3]).is_err());
+        assert!(Matrix::new("bad", 1, 1, vec![f64::NAN]).is_err());
+    }
+}
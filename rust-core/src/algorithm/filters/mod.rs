@@ -0,0 +1,14 @@
+//! State estimators for sensor fusion
+//!
+//! `Kalman` and `Complementary` are configured entirely by parameters, so
+//! they are also built-ins created by ID. `ExtendedKalman` takes its model
+//! as code and is registered like any user algorithm.
+
+mod complementary;
+mod extended_kalman;
+mod kalman;
+pub(crate) mod matrix;
+
+pub use complementary::Complementary;
+pub use extended_kalman::{ExtendedKalman, NonlinearModel};
+pub use kalman::Kalman;
@@ -0,0 +1,370 @@
+//! GPU compute backends for elementwise built-ins
+//!
+//! Enabled by the `gpu` feature on native targets. `GpuScale` and `GpuClamp`
+//! share their ID, parameters and output with `Scale` and `Clamp`, so
+//! registering one in an engine swaps the backend without changing callers.
+//! Each uploads the samples, runs a compute shader and reads the result
+//! back, and falls back to the CPU path when no adapter is available, the
+//! buffer is too small to be worth the round trip, or it exceeds what the
+//! device can bind.
+
+use serde_json::Value;
+use std::sync::{mpsc, OnceLock};
+use wgpu::util::DeviceExt;
+
+use super::builtins::{Clamp, NanPolicy, Scale};
+use super::{samples, Algorithm, AlgorithmMetadata};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// Buffers with fewer samples than this stay on the CPU
+pub const GPU_MIN_SAMPLES: usize = 4096;
+
+const WORKGROUP_SIZE: u32 = 64;
+
+const SCALE_SHADER: &str = "
+struct Params { a: f32, b: f32, mode: u32, stride: u32 }
+@group(0) @binding(0) var<storage, read_write> data: array<f32>;
+@group(0) @binding(1) var<uniform> params: Params;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x + id.y * params.stride;
+    if (i >= arrayLength(&data)) {
+        return;
+    }
+    data[i] = data[i] * params.a;
+}
+";
+
+// NaN is detected from the bit pattern, since float comparisons against NaN
+// may be optimised away by shader compilers
+const CLAMP_SHADER: &str = "
+struct Params { a: f32, b: f32, mode: u32, stride: u32 }
+@group(0) @binding(0) var<storage, read_write> data: array<f32>;
+@group(0) @binding(1) var<uniform> params: Params;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x + id.y * params.stride;
+    if (i >= arrayLength(&data)) {
+        return;
+    }
+    let x = data[i];
+    if ((bitcast<u32>(x) & 0x7fffffffu) > 0x7f800000u) {
+        switch params.mode {
+            case 0u: { data[i] = 0.0; }
+            case 1u: { data[i] = params.a; }
+            case 2u: { data[i] = params.b; }
+            default: {}
+        }
+        return;
+    }
+    data[i] = min(max(x, params.a), params.b);
+}
+";
+
+/// Device and queue shared by every GPU backend in the process
+struct GpuContext {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+}
+
+static CONTEXT: OnceLock<Option<GpuContext>> = OnceLock::new();
+
+fn context() -> Option<&'static GpuContext> {
+    CONTEXT.get_or_init(|| pollster::block_on(GpuContext::request())).as_ref()
+}
+
+/// Whether a GPU adapter was found; otherwise every backend runs on the CPU
+pub fn is_available() -> bool {
+    context().is_some()
+}
+
+impl GpuContext {
+    async fn request() -> Option<Self> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle());
+        let adapter = match instance.request_adapter(&wgpu::RequestAdapterOptions::default()).await {
+            Ok(adapter) => adapter,
+            Err(e) => {
+                log::info!("No GPU adapter, using CPU backends: {}", e);
+                return None;
+            }
+        };
+        match adapter.request_device(&wgpu::DeviceDescriptor::default()).await {
+            Ok((device, queue)) => Some(Self { device, queue }),
+            Err(e) => {
+                log::warn!("GPU device request failed, using CPU backends: {}", e);
+                None
+            }
+        }
+    }
+    
+    /// Whether `len` samples fit in one storage binding and one dispatch
+    fn fits(&self, len: usize) -> bool {
+        let limits = self.device.limits();
+        let groups = (len as u64).div_ceil(WORKGROUP_SIZE as u64);
+        let max_groups = limits.max_compute_workgroups_per_dimension as u64;
+        len as u64 * 4 <= limits.max_storage_buffer_binding_size && groups <= max_groups * max_groups
+    }
+    
+    /// Run `shader` over `values` in place with parameters `(a, b, mode)`
+    fn run(&self, shader: &str, values: &[f32], a: f32, b: f32, mode: u32) -> Result<Vec<f32>, CoreError> {
+        let device = &self.device;
+        let size = (values.len() * 4) as u64;
+        
+        // Spread workgroups over two dimensions to stay under the per-dimension cap
+        let groups = (values.len() as u32).div_ceil(WORKGROUP_SIZE);
+        let groups_x = groups.min(device.limits().max_compute_workgroups_per_dimension);
+        let groups_y = groups.div_ceil(groups_x);
+        let mut params = Vec::with_capacity(16);
+        params.extend_from_slice(&a.to_le_bytes());
+        params.extend_from_slice(&b.to_le_bytes());
+        params.extend_from_slice(&mode.to_le_bytes());
+        params.extend_from_slice(&(groups_x * WORKGROUP_SIZE).to_le_bytes());
+        
+        let data = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("samples"),
+            contents: &samples::f32_to_bytes(values),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        });
+        let uniforms = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("params"),
+            contents: &params,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let staging = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(shader.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: data.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: uniforms.as_entire_binding(),
+                },
+            ],
+        });
+        
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: None,
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(groups_x, groups_y, 1);
+        }
+        encoder.copy_buffer_to_buffer(&data, 0, &staging, 0, size);
+        self.queue.submit(Some(encoder.finish()));
+        
+        let (sender, receiver) = mpsc::channel();
+        staging.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .map_err(|e| CoreError::ProcessingFailed(format!("GPU wait failed: {}", e)))?;
+        receiver
+            .recv()
+            .map_err(|_| CoreError::ProcessingFailed("GPU readback was dropped".to_string()))?
+            .map_err(|e| CoreError::ProcessingFailed(format!("GPU readback failed: {}", e)))?;
+        
+        let output = {
+            let view = staging
+                .get_mapped_range(..)
+                .map_err(|e| CoreError::ProcessingFailed(format!("GPU readback failed: {}", e)))?;
+            samples::f32_from_bytes(&view)?
+        };
+        staging.unmap();
+        Ok(output)
+    }
+}
+
+/// Run on the GPU when worthwhile, returning `None` to use the CPU path
+fn offload(values: &[f32], shader: &str, a: f32, b: f32, mode: u32) -> Option<Vec<f32>> {
+    if values.len() < GPU_MIN_SAMPLES {
+        return None;
+    }
+    let context = context().filter(|context| context.fits(values.len()))?;
+    match context.run(shader, values, a, b, mode) {
+        Ok(output) => Some(output),
+        Err(e) => {
+            log::warn!("GPU run failed, using CPU: {}", e);
+            None
+        }
+    }
+}
+
+/// `Scale` running on the GPU
+#[derive(Clone, Debug)]
+pub struct GpuScale {
+    cpu: Scale,
+}
+
+impl GpuScale {
+    /// Create a GPU scale, rejecting a non-finite factor
+    pub fn new(factor: f32) -> Result<Self, CoreError> {
+        Ok(Self { cpu: Scale::new(factor)? })
+    }
+    
+    /// Create a GPU scale from the same parameters as `Scale`
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Ok(Self {
+            cpu: Scale::from_params(params)?,
+        })
+    }
+}
+
+impl Algorithm for GpuScale {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::with_capacity(input.len());
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, memory: &mut MemoryManager) -> Result<(), CoreError> {
+        let values = samples::f32_from_bytes(input)?;
+        match offload(&values, SCALE_SHADER, self.cpu.factor(), 0.0, 0) {
+            Some(scaled) => output.extend_from_slice(&samples::f32_to_bytes(&scaled)),
+            None => return self.cpu.process_into(input, output, memory),
+        }
+        Ok(())
+    }
+    
+    fn id(&self) -> &str {
+        self.cpu.id()
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Scale (GPU)".to_string(),
+            ..self.cpu.metadata()
+        }
+    }
+}
+
+/// `Clamp` running on the GPU
+#[derive(Clone, Debug)]
+pub struct GpuClamp {
+    cpu: Clamp,
+}
+
+impl GpuClamp {
+    /// Create a GPU clamp, rejecting NaN or inverted bounds
+    pub fn new(min: f32, max: f32, nan_policy: NanPolicy) -> Result<Self, CoreError> {
+        Ok(Self {
+            cpu: Clamp::new(min, max, nan_policy)?,
+        })
+    }
+    
+    /// Create a GPU clamp from the same parameters as `Clamp`
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Ok(Self {
+            cpu: Clamp::from_params(params)?,
+        })
+    }
+}
+
+impl Algorithm for GpuClamp {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::with_capacity(input.len());
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, memory: &mut MemoryManager) -> Result<(), CoreError> {
+        let values = samples::f32_from_bytes(input)?;
+        let mode = match self.cpu.nan_policy() {
+            NanPolicy::Zero => 0,
+            NanPolicy::Min => 1,
+            NanPolicy::Max => 2,
+            NanPolicy::Passthrough => 3,
+        };
+        match offload(&values, CLAMP_SHADER, self.cpu.min(), self.cpu.max(), mode) {
+            Some(clamped) => output.extend_from_slice(&samples::f32_to_bytes(&clamped)),
+            None => return self.cpu.process_into(input, output, memory),
+        }
+        Ok(())
+    }
+    
+    fn id(&self) -> &str {
+        self.cpu.id()
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Clamp (GPU)".to_string(),
+            ..self.cpu.metadata()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn signal() -> Vec<f32> {
+        let mut signal: Vec<f32> = (0..100_000).map(|i| ((i as f32) * 0.37).sin() * 1e3).collect();
+        signal[17] = f32::NAN;
+        signal[99_999] = f32::INFINITY;
+        signal
+    }
+    
+    fn assert_backends_agree(gpu: &dyn Algorithm, cpu: &dyn Algorithm) {
+        if !is_available() {
+            eprintln!("no GPU adapter; only the CPU fallback is compared");
+        }
+        let input = samples::f32_to_bytes(&signal());
+        let mut memory = MemoryManager::new();
+        let gpu_output = samples::f32_from_bytes(&gpu.process(&input, &mut memory).unwrap()).unwrap();
+        let cpu_output = samples::f32_from_bytes(&cpu.process(&input, &mut memory).unwrap()).unwrap();
+        
+        assert_eq!(gpu_output.len(), cpu_output.len());
+        for (g, c) in gpu_output.iter().zip(&cpu_output) {
+            assert!(
+                (g.is_nan() && c.is_nan()) || g == c || (g - c).abs() <= 1e-6 * c.abs(),
+                "GPU {} differs from CPU {}",
+                g,
+                c
+            );
+        }
+    }
+    
+    #[test]
+    fn test_gpu_scale_matches_cpu() {
+        assert_backends_agree(&GpuScale::new(-2.5).unwrap(), &Scale::new(-2.5).unwrap());
+    }
+    
+    #[test]
+    fn test_gpu_clamp_matches_cpu() {
+        for policy in [NanPolicy::Zero, NanPolicy::Min, NanPolicy::Max, NanPolicy::Passthrough] {
+            assert_backends_agree(
+                &GpuClamp::new(-300.0, 500.0, policy).unwrap(),
+                &Clamp::new(-300.0, 500.0, policy).unwrap(),
+            );
+        }
+    }
+}
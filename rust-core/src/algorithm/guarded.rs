@@ -0,0 +1,141 @@
+//! Running an algorithm only while a condition over memory holds
+
+use std::collections::HashMap;
+
+use super::context::Context;
+use super::{Algorithm, AlgorithmMetadata};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+type Predicate = Box<dyn Fn(&[u8]) -> bool + Send + Sync>;
+
+/// What a `Guarded` algorithm does when its guard is false
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GuardFallback {
+    /// Fail with `CoreError::PreconditionFailed`
+    #[default]
+    Error,
+    /// Succeed, returning the input unchanged
+    PassThrough,
+}
+
+/// Wraps an algorithm so it runs only when a predicate over a memory region
+/// holds, e.g. a state machine's flag byte
+///
+/// The predicate sees the region's bytes as they are when the call starts.
+/// A missing guard region fails with `CoreError::MemoryKeyMissing` rather
+/// than counting as false, so a misspelled key is not mistaken for a closed
+/// guard. The wrapper reports the inner algorithm's ID and metadata.
+pub struct Guarded {
+    inner: Box<dyn Algorithm>,
+    guard_key: String,
+    predicate: Predicate,
+    fallback: GuardFallback,
+}
+
+impl Guarded {
+    /// Run `inner` only while `predicate` holds for region `guard_key`
+    pub fn new(
+        inner: Box<dyn Algorithm>,
+        guard_key: &str,
+        predicate: impl Fn(&[u8]) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            inner,
+            guard_key: guard_key.to_string(),
+            predicate: Box::new(predicate),
+            fallback: GuardFallback::default(),
+        }
+    }
+    
+    /// Choose what happens when the guard is false
+    pub fn with_fallback(mut self, fallback: GuardFallback) -> Self {
+        self.fallback = fallback;
+        self
+    }
+}
+
+impl Algorithm for Guarded {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::new();
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, memory: &mut MemoryManager) -> Result<(), CoreError> {
+        Context::with_default(|context| self.process_in_context(input, output, memory, context))
+    }
+    
+    fn process_in_context(
+        &self,
+        input: &[u8],
+        output: &mut Vec<u8>,
+        memory: &mut MemoryManager,
+        context: &mut Context<'_>,
+    ) -> Result<(), CoreError> {
+        let guard = memory
+            .read(&self.guard_key)
+            .ok_or_else(|| CoreError::MemoryKeyMissing(self.guard_key.clone()))?;
+        if (self.predicate)(guard) {
+            return self.inner.process_in_context(input, output, memory, context);
+        }
+        match self.fallback {
+            GuardFallback::Error => Err(CoreError::PreconditionFailed(format!(
+                "guard '{}' of '{}' does not hold",
+                self.guard_key,
+                self.inner.id()
+            ))),
+            GuardFallback::PassThrough => {
+                output.extend_from_slice(input);
+                Ok(())
+            }
+        }
+    }
+    
+    fn output_attributes(&self, input: &[u8], output: &[u8], memory: &MemoryManager) -> HashMap<String, String> {
+        self.inner.output_attributes(input, output, memory)
+    }
+    
+    fn id(&self) -> &str {
+        self.inner.id()
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        self.inner.metadata()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::builtins::Scale;
+    use crate::algorithm::samples;
+    
+    fn guarded() -> Guarded {
+        Guarded::new(Box::new(Scale::new(2.0).unwrap()), "armed", |flag| flag.first() == Some(&1))
+    }
+    
+    #[test]
+    fn test_runs_when_guard_holds() {
+        let mut memory = MemoryManager::new();
+        memory.write("armed", &[1]).unwrap();
+        let output = guarded().process(&samples::f32_to_bytes(&[1.5]), &mut memory).unwrap();
+        assert_eq!(samples::f32_from_bytes(&output).unwrap(), vec![3.0]);
+    }
+    
+    #[test]
+    fn test_false_guard_errors_or_passes_through() {
+        let mut memory = MemoryManager::new();
+        memory.write("armed", &[0]).unwrap();
+        let input = samples::f32_to_bytes(&[1.5]);
+        
+        let result = guarded().process(&input, &mut memory);
+        assert!(matches!(result, Err(CoreError::PreconditionFailed(ref msg)) if msg.contains("armed")), "{:?}", result);
+        
+        let skipping = guarded().with_fallback(GuardFallback::PassThrough);
+        assert_eq!(skipping.process(&input, &mut memory).unwrap(), input);
+        
+        let mut unset = MemoryManager::new();
+        assert!(matches!(skipping.process(&input, &mut unset), Err(CoreError::MemoryKeyMissing(_))));
+    }
+}
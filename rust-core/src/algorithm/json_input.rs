@@ -0,0 +1,121 @@
+//! Adapter for algorithms taking structured JSON input
+
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+
+use super::{Algorithm, AlgorithmMetadata};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+type Validator<T> = Box<dyn Fn(&T) -> bool + Send + Sync>;
+type Handler<T> = Box<dyn Fn(T, &mut MemoryManager) -> Result<Vec<u8>, CoreError> + Send + Sync>;
+
+/// An algorithm whose input bytes are a JSON document of type `T`
+///
+/// Each call deserializes the input, checks it against the validator if
+/// one is set, and hands the typed value to the handler, whose bytes are
+/// the output. Malformed JSON fails with `CoreError::ProcessingFailed`
+/// carrying the serde error; a value the validator rejects fails with
+/// `CoreError::InvalidInput`.
+pub struct JsonInput<T> {
+    id: String,
+    validate: Option<Validator<T>>,
+    handle: Handler<T>,
+    // `T` is only produced and consumed, never stored
+    _input: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> JsonInput<T> {
+    /// Create an algorithm registered as `id` that runs `handle` on each
+    /// deserialized input
+    pub fn new(
+        id: &str,
+        handle: impl Fn(T, &mut MemoryManager) -> Result<Vec<u8>, CoreError> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            id: id.to_string(),
+            validate: None,
+            handle: Box::new(handle),
+            _input: PhantomData,
+        }
+    }
+    
+    /// Reject inputs for which `validate` returns false, before `handle`
+    /// sees them
+    pub fn with_validation(mut self, validate: impl Fn(&T) -> bool + Send + Sync + 'static) -> Self {
+        self.validate = Some(Box::new(validate));
+        self
+    }
+}
+
+impl<T: DeserializeOwned> Algorithm for JsonInput<T> {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let value: T = serde_json::from_slice(input)
+            .map_err(|e| CoreError::ProcessingFailed(format!("'{}' input is not valid JSON: {}", self.id, e)))?;
+        if let Some(validate) = &self.validate {
+            if !validate(&value) {
+                return Err(CoreError::InvalidInput(format!("'{}' input failed validation", self.id)));
+            }
+        }
+        (self.handle)(value, memory)
+    }
+    
+    fn id(&self) -> &str {
+        &self.id
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: self.id.clone(),
+            version: "1.0.0".to_string(),
+            description: "Processes a JSON document".to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::samples;
+    use crate::CoreEngine;
+    use serde::Deserialize;
+    
+    #[derive(Deserialize)]
+    struct Waypoint {
+        x: f32,
+        y: f32,
+        speed: f32,
+    }
+    
+    fn engine() -> CoreEngine {
+        let algorithm = JsonInput::new("waypoint", |waypoint: Waypoint, _memory: &mut MemoryManager| {
+            Ok([waypoint.x, waypoint.y, waypoint.speed].iter().flat_map(|v| v.to_le_bytes()).collect())
+        })
+        .with_validation(|waypoint| waypoint.speed > 0.0);
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(algorithm)).unwrap();
+        engine
+    }
+    
+    #[test]
+    fn test_valid_payload_reaches_handler() {
+        let output = engine().execute_algorithm("waypoint", br#"{"x": 1.5, "y": -2.0, "speed": 0.5}"#).unwrap();
+        assert_eq!(samples::f32_from_bytes(&output).unwrap(), vec![1.5, -2.0, 0.5]);
+    }
+    
+    #[test]
+    fn test_malformed_or_invalid_payload_errors() {
+        let mut engine = engine();
+        let malformed = engine.execute_algorithm("waypoint", br#"{"x": 1.5, "y": "#);
+        assert!(matches!(malformed, Err(CoreError::ProcessingFailed(ref e)) if e.contains("EOF")), "{:?}", malformed);
+        let missing = engine.execute_algorithm("waypoint", br#"{"x": 1.5}"#);
+        assert!(matches!(missing, Err(CoreError::ProcessingFailed(ref e)) if e.contains("missing field")));
+        
+        assert!(matches!(
+            engine.execute_algorithm("waypoint", br#"{"x": 0, "y": 0, "speed": -1}"#),
+            Err(CoreError::InvalidInput(_))
+        ));
+    }
+}
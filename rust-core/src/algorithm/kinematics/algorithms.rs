@@ -0,0 +1,359 @@
+//! Kinematics built-ins over streams of `f32` joint vectors and poses
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use serde_json::Value;
+
+use super::{IkOptions, KinematicChain, Transform};
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, OutputSizeHint, ParameterDefinition, ParameterType, Pure};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// Values in a pose record: position, then the `(w, x, y, z)` quaternion
+const POSE_LEN: usize = 7;
+
+/// Split `input` into consecutive `len`-value `f64` vectors
+fn vectors(input: &[u8], len: usize, what: &str) -> Result<Vec<Vec<f64>>, CoreError> {
+    let values = samples::f32_from_bytes(input)?;
+    if !values.len().is_multiple_of(len) {
+        return Err(CoreError::InvalidInput(format!(
+            "{} f32 values do not form whole {}-value {}",
+            values.len(),
+            len,
+            what
+        )));
+    }
+    Ok(values.chunks_exact(len).map(|chunk| chunk.iter().map(|&v| f64::from(v)).collect()).collect())
+}
+
+fn write(values: impl IntoIterator<Item = f64>, output: &mut Vec<u8>) {
+    for value in values {
+        output.extend_from_slice(&(value as f32).to_le_bytes());
+    }
+}
+
+fn pose_values(pose: &Transform) -> impl Iterator<Item = f64> {
+    pose.translation().into_iter().chain(pose.quaternion())
+}
+
+fn chain_parameters() -> Vec<ParameterDefinition> {
+    let parameter = |name: &str, parameter_type: ParameterType, description: &str| ParameterDefinition {
+        name: name.to_string(),
+        parameter_type,
+        description: description.to_string(),
+        default_value: None,
+    };
+    vec![
+        parameter(
+            "dh",
+            ParameterType::Array,
+            "Denavit-Hartenberg links as {a, alpha, d, theta, joint} objects",
+        ),
+        parameter("urdf", ParameterType::String, "URDF document, instead of 'dh' (needs the 'urdf' feature)"),
+        parameter("base_link", ParameterType::String, "URDF link the chain starts from"),
+        parameter("tip_link", ParameterType::String, "URDF link the chain ends at"),
+    ]
+}
+
+/// Tool pose for each joint vector
+///
+/// The input holds consecutive vectors of one `f32` position per joint;
+/// each gives a 7-value pose: the position, then the orientation as a
+/// `(w, x, y, z)` quaternion.
+#[derive(Clone, Debug)]
+pub struct ForwardKinematics {
+    chain: KinematicChain,
+}
+
+impl ForwardKinematics {
+    pub const ID: &'static str = "forward_kinematics";
+    
+    pub fn new(chain: KinematicChain) -> Self {
+        Self { chain }
+    }
+    
+    /// Create the algorithm from the chain parameters of
+    /// `KinematicChain::from_params`
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Ok(Self::new(KinematicChain::from_params(params)?))
+    }
+}
+
+impl Algorithm for ForwardKinematics {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::new();
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        for positions in vectors(input, self.chain.dof(), "joint vectors")? {
+            write(pose_values(&self.chain.forward(&positions)?), output);
+        }
+        Ok(())
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Forward Kinematics".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Turns f32 joint vectors into tool poses (position, then w, x, y, z)".to_string(),
+            parameters: chain_parameters(),
+            output_size_hint: OutputSizeHint::Ratio(POSE_LEN as f32 / self.chain.dof() as f32),
+            ..Default::default()
+        }
+    }
+}
+
+impl Pure for ForwardKinematics {}
+
+/// Geometric Jacobian for each joint vector, as 6 rows by one column per
+/// joint, row-major
+#[derive(Clone, Debug)]
+pub struct KinematicJacobian {
+    chain: KinematicChain,
+}
+
+impl KinematicJacobian {
+    pub const ID: &'static str = "jacobian";
+    
+    pub fn new(chain: KinematicChain) -> Self {
+        Self { chain }
+    }
+    
+    /// Create the algorithm from the chain parameters of
+    /// `KinematicChain::from_params`
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Ok(Self::new(KinematicChain::from_params(params)?))
+    }
+}
+
+impl Algorithm for KinematicJacobian {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::with_capacity(input.len() * 6);
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        for positions in vectors(input, self.chain.dof(), "joint vectors")? {
+            write(self.chain.jacobian(&positions)?, output);
+        }
+        Ok(())
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Kinematic Jacobian".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Turns f32 joint vectors into 6 x joints row-major Jacobians".to_string(),
+            parameters: chain_parameters(),
+            output_size_hint: OutputSizeHint::Ratio(6.0),
+            ..Default::default()
+        }
+    }
+}
+
+impl Pure for KinematicJacobian {}
+
+/// Joint vector reaching each target pose, by damped least squares
+///
+/// The input holds 7-value poses as `ForwardKinematics` emits them, or
+/// 3-value positions with `position_only`. The first solve starts from the
+/// `initial` joint vector and each later one from the previous solution,
+/// so a trajectory's poses solve quickly and without flipping between
+/// equivalent configurations. A pose that does not converge fails with
+/// `CoreError::ProcessingFailed`.
+#[derive(Clone, Debug)]
+pub struct InverseKinematics {
+    chain: KinematicChain,
+    initial: Vec<f64>,
+    options: IkOptions,
+}
+
+impl InverseKinematics {
+    pub const ID: &'static str = "inverse_kinematics";
+    
+    /// Solve from all joints at zero
+    pub fn new(chain: KinematicChain, options: IkOptions) -> Self {
+        let initial = vec![0.0; chain.dof()];
+        Self { chain, initial, options }
+    }
+    
+    /// Start the first solve from `initial`
+    pub fn with_initial(mut self, initial: Vec<f64>) -> Result<Self, CoreError> {
+        self.chain.check_positions(&initial)?;
+        self.initial = initial;
+        Ok(self)
+    }
+    
+    /// Create the algorithm from the chain parameters of
+    /// `KinematicChain::from_params` and its solver parameters
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        let defaults = IkOptions::default();
+        let options = IkOptions {
+            damping: params::get(params, "damping")?.unwrap_or(defaults.damping),
+            tolerance: params::get(params, "tolerance")?.unwrap_or(defaults.tolerance),
+            max_iterations: params::get(params, "max_iterations")?.unwrap_or(defaults.max_iterations),
+            position_only: params::get(params, "position_only")?.unwrap_or(defaults.position_only),
+        };
+        let algorithm = Self::new(KinematicChain::from_params(params)?, options);
+        match params::get(params, "initial")? {
+            Some(initial) => algorithm
+                .with_initial(initial)
+                .map_err(|e| CoreError::InvalidParameter(format!("'initial': {}", e))),
+            None => Ok(algorithm),
+        }
+    }
+    
+    fn target(&self, values: &[f64]) -> Result<Transform, CoreError> {
+        let position = Transform::from_translation([values[0], values[1], values[2]]);
+        if self.options.position_only {
+            return Ok(position);
+        }
+        Ok(position.then(&Transform::from_quaternion([values[3], values[4], values[5], values[6]])?))
+    }
+}
+
+impl Algorithm for InverseKinematics {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::new();
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        let len = if self.options.position_only { 3 } else { POSE_LEN };
+        let mut seed = self.initial.clone();
+        for (i, values) in vectors(input, len, "targets")?.into_iter().enumerate() {
+            let solution = self.chain.inverse(&self.target(&values)?, &seed, &self.options)?;
+            if !solution.converged {
+                return Err(CoreError::ProcessingFailed(format!(
+                    "target {} not reached after {} iterations (position error {}, orientation error {})",
+                    i, solution.iterations, solution.position_error, solution.orientation_error
+                )));
+            }
+            write(solution.joints.iter().copied(), output);
+            seed = solution.joints;
+        }
+        Ok(())
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        let mut parameters = chain_parameters();
+        let solver = |name: &str, parameter_type: ParameterType, description: &str, default_value: Option<String>| {
+            ParameterDefinition {
+                name: name.to_string(),
+                parameter_type,
+                description: description.to_string(),
+                default_value,
+            }
+        };
+        let defaults = IkOptions::default();
+        parameters.extend([
+            solver(
+                "damping",
+                ParameterType::Float,
+                "Damping factor lambda",
+                Some(defaults.damping.to_string()),
+            ),
+            solver(
+                "tolerance",
+                ParameterType::Float,
+                "Largest position and orientation error accepted",
+                Some(defaults.tolerance.to_string()),
+            ),
+            solver(
+                "max_iterations",
+                ParameterType::Integer,
+                "Iterations per target before giving up",
+                Some(defaults.max_iterations.to_string()),
+            ),
+            solver(
+                "position_only",
+                ParameterType::Boolean,
+                "Take 3-value positions and ignore orientation",
+                Some("false".to_string()),
+            ),
+            solver("initial", ParameterType::Array, "Joint vector the first solve starts from", None),
+        ]);
+        let len = if self.options.position_only { 3 } else { POSE_LEN };
+        AlgorithmMetadata {
+            name: "Inverse Kinematics".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Turns f32 target poses into joint vectors by damped least squares".to_string(),
+            parameters,
+            output_size_hint: OutputSizeHint::Ratio(self.chain.dof() as f32 / len as f32),
+            ..Default::default()
+        }
+    }
+}
+
+impl Pure for InverseKinematics {}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::CoreEngine;
+    use serde_json::json;
+    
+    fn arm() -> Value {
+        json!({"dh": [
+            {"a": 0.0, "alpha": std::f64::consts::FRAC_PI_2, "d": 0.3},
+            {"a": 0.4, "alpha": 0.0, "d": 0.0},
+            {"a": 0.3, "alpha": 0.0, "d": 0.0},
+        ]})
+    }
+    
+    fn run(engine: &mut CoreEngine, id: &str, values: &[f32]) -> Result<Vec<f32>, CoreError> {
+        samples::f32_from_bytes(&engine.execute_algorithm(id, &samples::f32_to_bytes(values))?)
+    }
+    
+    #[test]
+    fn test_registered_algorithms_round_trip() {
+        let mut ik_params = arm();
+        ik_params["position_only"] = json!(true);
+        ik_params["initial"] = json!([0.1, 0.5, -0.5]);
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(ForwardKinematics::from_params(&arm()).unwrap())).unwrap();
+        engine.register(Box::new(KinematicJacobian::from_params(&arm()).unwrap())).unwrap();
+        engine.register(Box::new(InverseKinematics::from_params(&ik_params).unwrap())).unwrap();
+        
+        let joints = [0.3, 0.6, -0.8, 0.0, 0.2, 0.4];
+        let poses = run(&mut engine, ForwardKinematics::ID, &joints).unwrap();
+        assert_eq!(poses.len(), 14);
+        assert_eq!(run(&mut engine, KinematicJacobian::ID, &joints).unwrap().len(), 36);
+        
+        let positions: Vec<f32> = poses.chunks(7).flat_map(|pose| pose[..3].to_vec()).collect();
+        let solved = run(&mut engine, InverseKinematics::ID, &positions).unwrap();
+        let reached = run(&mut engine, ForwardKinematics::ID, &solved).unwrap();
+        for (pose, target) in reached.chunks(7).zip(positions.chunks(3)) {
+            for (a, b) in pose[..3].iter().zip(target) {
+                assert!((a - b).abs() < 1e-4, "{:?} != {:?}", pose, target);
+            }
+        }
+        
+        // Beyond the arm's 0.7 reach
+        assert!(matches!(
+            run(&mut engine, InverseKinematics::ID, &[2.0, 0.0, 0.3]),
+            Err(CoreError::ProcessingFailed(_))
+        ));
+        assert!(run(&mut engine, ForwardKinematics::ID, &[0.0; 4]).is_err());
+        ik_params["initial"] = json!([0.0]);
+        assert!(InverseKinematics::from_params(&ik_params).is_err());
+        assert!(ForwardKinematics::from_params(&json!({})).is_err());
+    }
+}
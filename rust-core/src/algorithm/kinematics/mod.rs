@@ -0,0 +1,657 @@
+//! Forward and inverse kinematics of serial manipulators
+//!
+//! A `KinematicChain` is built from Denavit-Hartenberg parameters or, with
+//! the `urdf` feature, from the joints between two links of a URDF robot.
+//! `ForwardKinematics`, `KinematicJacobian` and `InverseKinematics` wrap
+//! the chain as built-ins configured by the same description.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::filters::matrix::Matrix;
+use super::params;
+#[cfg(all(not(feature = "std"), not(test)))]
+use crate::compat::Float;
+use crate::error::CoreError;
+use crate::math;
+
+mod algorithms;
+#[cfg(feature = "urdf")]
+mod urdf;
+
+pub use algorithms::{ForwardKinematics, InverseKinematics, KinematicJacobian};
+
+/// A rigid transform: a rotation followed by a translation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    rotation: [[f64; 3]; 3],
+    translation: [f64; 3],
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Self {
+            rotation: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            translation: [0.0; 3],
+        }
+    }
+    
+    pub fn from_translation(translation: [f64; 3]) -> Self {
+        Self {
+            translation,
+            ..Self::identity()
+        }
+    }
+    
+    /// Rotation by `angle` radians about the unit vector `axis`
+    pub fn from_axis_angle(axis: [f64; 3], angle: f64) -> Self {
+        let [x, y, z] = axis;
+        let (s, c) = angle.sin_cos();
+        let t = 1.0 - c;
+        Self {
+            rotation: [
+                [t * x * x + c, t * x * y - s * z, t * x * z + s * y],
+                [t * x * y + s * z, t * y * y + c, t * y * z - s * x],
+                [t * x * z - s * y, t * y * z + s * x, t * z * z + c],
+            ],
+            translation: [0.0; 3],
+        }
+    }
+    
+    /// Rotation by roll, pitch and yaw about the fixed X, Y and Z axes, in
+    /// that order, as in URDF
+    pub fn from_rpy(roll: f64, pitch: f64, yaw: f64) -> Self {
+        Self::from_axis_angle([0.0, 0.0, 1.0], yaw)
+            .then(&Self::from_axis_angle([0.0, 1.0, 0.0], pitch))
+            .then(&Self::from_axis_angle([1.0, 0.0, 0.0], roll))
+    }
+    
+    /// Rotation by the quaternion `(w, x, y, z)`, normalized first
+    ///
+    /// Fails with `CoreError::InvalidInput` for a zero or non-finite
+    /// quaternion.
+    pub fn from_quaternion(quaternion: [f64; 4]) -> Result<Self, CoreError> {
+        let norm = quaternion.iter().map(|q| q * q).sum::<f64>().sqrt();
+        if !norm.is_finite() || norm == 0.0 {
+            return Err(CoreError::InvalidInput(format!("quaternion {:?} cannot be normalized", quaternion)));
+        }
+        let [w, x, y, z] = quaternion.map(|q| q / norm);
+        Ok(Self {
+            rotation: [
+                [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - w * z), 2.0 * (x * z + w * y)],
+                [2.0 * (x * y + w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - w * x)],
+                [2.0 * (x * z - w * y), 2.0 * (y * z + w * x), 1.0 - 2.0 * (x * x + y * y)],
+            ],
+            translation: [0.0; 3],
+        })
+    }
+    
+    /// Row-major rotation matrix
+    pub fn rotation(&self) -> [[f64; 3]; 3] {
+        self.rotation
+    }
+    
+    pub fn translation(&self) -> [f64; 3] {
+        self.translation
+    }
+    
+    /// The rotation as a unit quaternion `(w, x, y, z)` with `w >= 0`
+    pub fn quaternion(&self) -> [f64; 4] {
+        let r = &self.rotation;
+        let trace = r[0][0] + r[1][1] + r[2][2];
+        let q = if trace > 0.0 {
+            let s = 2.0 * (trace + 1.0).sqrt();
+            [s / 4.0, (r[2][1] - r[1][2]) / s, (r[0][2] - r[2][0]) / s, (r[1][0] - r[0][1]) / s]
+        } else if r[0][0] > r[1][1] && r[0][0] > r[2][2] {
+            let s = 2.0 * (1.0 + r[0][0] - r[1][1] - r[2][2]).sqrt();
+            [(r[2][1] - r[1][2]) / s, s / 4.0, (r[0][1] + r[1][0]) / s, (r[0][2] + r[2][0]) / s]
+        } else if r[1][1] > r[2][2] {
+            let s = 2.0 * (1.0 + r[1][1] - r[0][0] - r[2][2]).sqrt();
+            [(r[0][2] - r[2][0]) / s, (r[0][1] + r[1][0]) / s, s / 4.0, (r[1][2] + r[2][1]) / s]
+        } else {
+            let s = 2.0 * (1.0 + r[2][2] - r[0][0] - r[1][1]).sqrt();
+            [(r[1][0] - r[0][1]) / s, (r[0][2] + r[2][0]) / s, (r[1][2] + r[2][1]) / s, s / 4.0]
+        };
+        if q[0] < 0.0 {
+            q.map(|v| -v)
+        } else {
+            q
+        }
+    }
+    
+    /// This transform followed by `next`, expressed in this one's frame
+    pub fn then(&self, next: &Transform) -> Transform {
+        let mut composed = Transform::identity();
+        composed.rotation = math::mat3_mul_f64(&self.rotation, &next.rotation);
+        for row in 0..3 {
+            composed.translation[row] = self.translation[row] + dot(self.rotation[row], next.translation);
+        }
+        composed
+    }
+    
+    /// The transform undoing this one
+    pub fn inverse(&self) -> Transform {
+        let r = &self.rotation;
+        let rotation = [[r[0][0], r[1][0], r[2][0]], [r[0][1], r[1][1], r[2][1]], [r[0][2], r[1][2], r[2][2]]];
+        let translation = rotation.map(|row| -dot(row, self.translation));
+        Transform { rotation, translation }
+    }
+    
+    /// Map `point` from the frame this transform describes into the frame
+    /// it is expressed in
+    pub fn apply(&self, point: [f64; 3]) -> [f64; 3] {
+        let rotated = self.rotate(point);
+        [0, 1, 2].map(|i| rotated[i] + self.translation[i])
+    }
+    
+    /// The transform a fraction `t` of the way from this one to `other`,
+    /// interpolating the rotation along the shortest arc
+    pub fn interpolate(&self, other: &Transform, t: f64) -> Transform {
+        let from = self.quaternion();
+        let mut to = other.quaternion();
+        let mut cos = (0..4).map(|i| from[i] * to[i]).sum::<f64>();
+        if cos < 0.0 {
+            to = to.map(|q| -q);
+            cos = -cos;
+        }
+        // Nearly equal rotations: blend linearly, avoiding a division by
+        // a vanishing sine
+        let (a, b) = if cos > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let angle = cos.acos();
+            (((1.0 - t) * angle).sin() / angle.sin(), (t * angle).sin() / angle.sin())
+        };
+        let quaternion = [0, 1, 2, 3].map(|i| a * from[i] + b * to[i]);
+        let mut interpolated = Transform::from_quaternion(quaternion).unwrap_or(*self);
+        let (start, end) = (self.translation, other.translation);
+        interpolated.translation = [0, 1, 2].map(|i| start[i] + t * (end[i] - start[i]));
+        interpolated
+    }
+    
+    /// Rotate `vector` without translating it
+    fn rotate(&self, vector: [f64; 3]) -> [f64; 3] {
+        self.rotation.map(|row| dot(row, vector))
+    }
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    math::dot_f64(&a, &b)
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn norm(v: &[f64]) -> f64 {
+    v.iter().map(|x| x * x).sum::<f64>().sqrt()
+}
+
+/// Rotation vector (axis times angle) taking `from` to `to`, both rotations
+/// in the same frame
+fn rotation_error(from: &[[f64; 3]; 3], to: &[[f64; 3]; 3]) -> [f64; 3] {
+    let r = math::mat3_mul_f64(to, &math::transpose3_f64(from));
+    let v = [r[2][1] - r[1][2], r[0][2] - r[2][0], r[1][0] - r[0][1]].map(|x| x / 2.0);
+    let sin = norm(&v);
+    let cos = ((r[0][0] + r[1][1] + r[2][2] - 1.0) / 2.0).clamp(-1.0, 1.0);
+    let angle = sin.atan2(cos);
+    if sin > 1e-9 {
+        return v.map(|x| x * angle / sin);
+    }
+    if cos > 0.0 {
+        return [0.0; 3];
+    }
+    // Half a turn: the axis comes from the diagonal, its signs from the
+    // largest component's row
+    let diagonal = [r[0][0], r[1][1], r[2][2]];
+    let major = (0..3).max_by(|&a, &b| diagonal[a].total_cmp(&diagonal[b])).unwrap_or(0);
+    let major_component = ((diagonal[major] + 1.0) / 2.0).max(0.0).sqrt();
+    let axis: [f64; 3] = core::array::from_fn(|i| {
+        if i == major {
+            major_component
+        } else {
+            (r[major][i] + r[i][major]) / (4.0 * major_component)
+        }
+    });
+    axis.map(|x| x * angle)
+}
+
+/// How a joint moves its child link
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JointType {
+    /// Rotation about the joint axis, in radians
+    Revolute,
+    /// Translation along the joint axis
+    Prismatic,
+}
+
+/// One actuated joint: a fixed offset from the previous joint, then motion
+/// along or about `axis`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Joint {
+    pub name: String,
+    pub joint_type: JointType,
+    /// Pose of the joint frame in the previous joint's moved frame
+    pub origin: Transform,
+    /// Unit axis of motion in the joint frame
+    pub axis: [f64; 3],
+    /// Lowest and highest position, if the joint is limited
+    pub limits: Option<(f64, f64)>,
+}
+
+impl Joint {
+    fn motion(&self, position: f64) -> Transform {
+        match self.joint_type {
+            JointType::Revolute => Transform::from_axis_angle(self.axis, position),
+            JointType::Prismatic => Transform::from_translation(self.axis.map(|a| a * position)),
+        }
+    }
+}
+
+/// A link in standard Denavit-Hartenberg convention:
+/// `Rot_z(theta) Trans_z(d) Trans_x(a) Rot_x(alpha)`, with the joint
+/// position added to `theta` for a revolute joint or to `d` for a
+/// prismatic one
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub struct DhParameters {
+    pub a: f64,
+    pub alpha: f64,
+    pub d: f64,
+    #[serde(default)]
+    pub theta: f64,
+    #[serde(default = "revolute")]
+    pub joint: JointType,
+}
+
+fn revolute() -> JointType {
+    JointType::Revolute
+}
+
+/// Joints from a robot's base to its tool
+#[derive(Clone, Debug, PartialEq)]
+pub struct KinematicChain {
+    joints: Vec<Joint>,
+    tool: Transform,
+}
+
+/// Settings for `KinematicChain::inverse`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IkOptions {
+    /// Damping factor lambda; larger values move more slowly but stay
+    /// stable near singularities
+    pub damping: f64,
+    /// Largest position error, and orientation error in radians, counted as
+    /// converged
+    pub tolerance: f64,
+    pub max_iterations: usize,
+    /// Reach the target position with any orientation
+    pub position_only: bool,
+}
+
+impl Default for IkOptions {
+    fn default() -> Self {
+        Self {
+            damping: 0.05,
+            tolerance: 1e-6,
+            max_iterations: 200,
+            position_only: false,
+        }
+    }
+}
+
+/// Pose error at one set of joint positions, with the frames it came from
+struct PoseError {
+    frames: Vec<Transform>,
+    tool: Transform,
+    position: [f64; 3],
+    orientation: [f64; 3],
+}
+
+impl PoseError {
+    fn within(&self, tolerance: f64) -> bool {
+        norm(&self.position) <= tolerance && norm(&self.orientation) <= tolerance
+    }
+    
+    fn size(&self) -> f64 {
+        norm(&self.position).powi(2) + norm(&self.orientation).powi(2)
+    }
+}
+
+/// Outcome of `KinematicChain::inverse`
+#[derive(Clone, Debug, PartialEq)]
+pub struct IkSolution {
+    /// Joint positions reached, within the joint limits
+    pub joints: Vec<f64>,
+    pub iterations: usize,
+    pub position_error: f64,
+    /// Orientation error in radians, 0 for a position-only solve
+    pub orientation_error: f64,
+    /// Whether both errors fell within the tolerance
+    pub converged: bool,
+}
+
+impl KinematicChain {
+    /// Chain `joints` from the base, ending in the fixed `tool` transform
+    ///
+    /// Fails with `CoreError::InvalidDefinition` for a chain without joints
+    /// or a joint axis of zero length; axes are normalized.
+    pub fn new(mut joints: Vec<Joint>, tool: Transform) -> Result<Self, CoreError> {
+        if joints.is_empty() {
+            return Err(CoreError::InvalidDefinition("kinematic chain has no joints".to_string()));
+        }
+        for joint in &mut joints {
+            let length = norm(&joint.axis);
+            if !length.is_finite() || length == 0.0 {
+                return Err(CoreError::InvalidDefinition(format!("joint '{}' has no axis", joint.name)));
+            }
+            joint.axis = joint.axis.map(|a| a / length);
+        }
+        Ok(Self { joints, tool })
+    }
+    
+    /// Chain of Denavit-Hartenberg links, with joints named `joint1` on
+    pub fn from_dh(links: &[DhParameters]) -> Result<Self, CoreError> {
+        // Each link's fixed part follows its joint's motion, so it becomes
+        // the next joint's origin, and the last one the tool
+        let mut joints = Vec::with_capacity(links.len());
+        let mut origin = Transform::identity();
+        for (i, link) in links.iter().enumerate() {
+            joints.push(Joint {
+                name: format!("joint{}", i + 1),
+                joint_type: link.joint,
+                origin,
+                axis: [0.0, 0.0, 1.0],
+                limits: None,
+            });
+            origin = Transform::from_axis_angle([0.0, 0.0, 1.0], link.theta)
+                .then(&Transform::from_translation([link.a, 0.0, link.d]))
+                .then(&Transform::from_axis_angle([1.0, 0.0, 0.0], link.alpha));
+        }
+        Self::new(joints, origin)
+    }
+    
+    /// Read a chain from the `dh` parameter, an array of
+    /// `{a, alpha, d, theta, joint}` objects, or with the `urdf` feature
+    /// from the `urdf` document between the `base_link` and `tip_link`
+    /// parameters
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        if let Some(links) = params::get::<Vec<DhParameters>>(params, "dh")? {
+            return Self::from_dh(&links);
+        }
+        match params::get::<String>(params, "urdf")? {
+            #[cfg(feature = "urdf")]
+            Some(document) => Self::from_urdf(
+                &document,
+                &params::require::<String>(params, "base_link")?,
+                &params::require::<String>(params, "tip_link")?,
+            ),
+            #[cfg(not(feature = "urdf"))]
+            Some(_) => Err(CoreError::UnsupportedCapability(
+                "URDF chains need the 'urdf' feature".to_string(),
+            )),
+            None => Err(CoreError::InvalidParameter("missing 'dh' or 'urdf'".to_string())),
+        }
+    }
+    
+    pub fn joints(&self) -> &[Joint] {
+        &self.joints
+    }
+    
+    /// Number of joints, the length of every joint vector
+    pub fn dof(&self) -> usize {
+        self.joints.len()
+    }
+    
+    fn check_positions(&self, positions: &[f64]) -> Result<(), CoreError> {
+        if positions.len() != self.dof() {
+            return Err(CoreError::InvalidInput(format!(
+                "chain has {} joints, got {} positions",
+                self.dof(),
+                positions.len()
+            )));
+        }
+        Ok(())
+    }
+    
+    /// Pose of the tool in the base frame at joint `positions`
+    pub fn forward(&self, positions: &[f64]) -> Result<Transform, CoreError> {
+        self.check_positions(positions)?;
+        Ok(self.frames(positions).1)
+    }
+    
+    /// Each joint's frame before its motion, and the tool pose
+    fn frames(&self, positions: &[f64]) -> (Vec<Transform>, Transform) {
+        let mut pose = Transform::identity();
+        let mut frames = Vec::with_capacity(self.dof());
+        for (joint, &position) in self.joints.iter().zip(positions) {
+            pose = pose.then(&joint.origin);
+            frames.push(pose);
+            pose = pose.then(&joint.motion(position));
+        }
+        (frames, pose.then(&self.tool))
+    }
+    
+    /// Geometric Jacobian at joint `positions`: 6 rows, linear then angular
+    /// velocity of the tool in the base frame, by one column per joint,
+    /// row-major
+    pub fn jacobian(&self, positions: &[f64]) -> Result<Vec<f64>, CoreError> {
+        self.check_positions(positions)?;
+        let (frames, tool) = self.frames(positions);
+        Ok(self.jacobian_at(&frames, &tool))
+    }
+    
+    fn jacobian_at(&self, frames: &[Transform], tool: &Transform) -> Vec<f64> {
+        let n = self.dof();
+        let mut jacobian = vec![0.0; 6 * n];
+        for (col, (joint, frame)) in self.joints.iter().zip(frames).enumerate() {
+            let axis = frame.rotate(joint.axis);
+            let (linear, angular) = match joint.joint_type {
+                JointType::Revolute => {
+                    let lever = [0, 1, 2].map(|i| tool.translation[i] - frame.translation[i]);
+                    (cross(axis, lever), axis)
+                }
+                JointType::Prismatic => (axis, [0.0; 3]),
+            };
+            for row in 0..3 {
+                jacobian[row * n + col] = linear[row];
+                jacobian[(row + 3) * n + col] = angular[row];
+            }
+        }
+        jacobian
+    }
+    
+    /// Joint positions placing the tool at `target`, by damped least
+    /// squares from `initial`
+    ///
+    /// Each iteration steps by `J^T (J J^T + lambda^2 I)^-1 e` for the pose
+    /// error `e` and clamps the joints to their limits. As in
+    /// Levenberg-Marquardt, a step that would increase the error is
+    /// rejected and retried with ten times the damping, which relaxes back
+    /// towards `damping` after each accepted step; this keeps the solver
+    /// from oscillating near singularities, such as a fully stretched arm.
+    /// An unreachable target gives the closest solution found, with
+    /// `converged` unset.
+    pub fn inverse(&self, target: &Transform, initial: &[f64], options: &IkOptions) -> Result<IkSolution, CoreError> {
+        self.check_positions(initial)?;
+        if !options.damping.is_finite() || options.damping < 0.0 {
+            return Err(CoreError::InvalidParameter(format!(
+                "damping must be finite and non-negative, got {}",
+                options.damping
+            )));
+        }
+        let mut joints = initial.to_vec();
+        self.clamp(&mut joints);
+        let mut current = self.pose_error(&joints, target, options);
+        let mut damping = options.damping;
+        let mut iterations = 0;
+        while !current.within(options.tolerance) && iterations < options.max_iterations {
+            iterations += 1;
+            let candidate = self.step(&joints, &current, damping, options).map(|mut candidate| {
+                self.clamp(&mut candidate);
+                let error = self.pose_error(&candidate, target, options);
+                (candidate, error)
+            });
+            match candidate {
+                Some((candidate, error)) if error.size() < current.size() => {
+                    joints = candidate;
+                    current = error;
+                    damping = (damping / 2.0).max(options.damping);
+                }
+                _ => damping = (damping * 10.0).max(1e-6),
+            }
+        }
+        Ok(IkSolution {
+            joints,
+            iterations,
+            position_error: norm(&current.position),
+            orientation_error: norm(&current.orientation),
+            converged: current.within(options.tolerance),
+        })
+    }
+    
+    fn pose_error(&self, positions: &[f64], target: &Transform, options: &IkOptions) -> PoseError {
+        let (frames, tool) = self.frames(positions);
+        PoseError {
+            position: [0, 1, 2].map(|i| target.translation[i] - tool.translation[i]),
+            orientation: if options.position_only {
+                [0.0; 3]
+            } else {
+                rotation_error(&tool.rotation, &target.rotation)
+            },
+            frames,
+            tool,
+        }
+    }
+    
+    /// Joints after one damped least squares step, or `None` if
+    /// `J J^T + lambda^2 I` is singular
+    fn step(&self, joints: &[f64], error: &PoseError, damping: f64, options: &IkOptions) -> Option<Vec<f64>> {
+        let n = self.dof();
+        let rows = if options.position_only { 3 } else { 6 };
+        let full = self.jacobian_at(&error.frames, &error.tool);
+        let jacobian = Matrix::new("jacobian", rows, n, full[..rows * n].to_vec()).ok()?;
+        let values: Vec<f64> = error.position.iter().chain(&error.orientation).take(rows).copied().collect();
+        let transposed = jacobian.transpose();
+        let inverse = jacobian.mul(&transposed).add(&Matrix::identity(rows).scaled(damping * damping)).inverse()?;
+        let delta = transposed.mul(&inverse).mul(&Matrix::column(&values));
+        Some(joints.iter().zip(delta.data()).map(|(joint, delta)| joint + delta).collect())
+    }
+    
+    fn clamp(&self, positions: &mut [f64]) {
+        for (position, joint) in positions.iter_mut().zip(&self.joints) {
+            if let Some((lower, upper)) = joint.limits {
+                *position = position.clamp(lower, upper);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+    
+    /// Planar arm with two unit links
+    fn planar() -> KinematicChain {
+        let link = DhParameters {
+            a: 1.0,
+            alpha: 0.0,
+            d: 0.0,
+            theta: 0.0,
+            joint: JointType::Revolute,
+        };
+        KinematicChain::from_dh(&[link, link]).unwrap()
+    }
+    
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-9, "{:?} != {:?}", actual, expected);
+        }
+    }
+    
+    #[test]
+    fn test_forward_kinematics_of_planar_arm() {
+        let chain = planar();
+        assert_close(&chain.forward(&[0.0, 0.0]).unwrap().translation(), &[2.0, 0.0, 0.0]);
+        let bent = chain.forward(&[FRAC_PI_2, -FRAC_PI_2]).unwrap();
+        assert_close(&bent.translation(), &[1.0, 1.0, 0.0]);
+        assert_close(&bent.quaternion(), &[1.0, 0.0, 0.0, 0.0]);
+        assert!(matches!(chain.forward(&[0.0]), Err(CoreError::InvalidInput(_))));
+        assert!(KinematicChain::from_dh(&[]).is_err());
+        
+        let quarter = Transform::from_rpy(0.0, 0.0, FRAC_PI_2);
+        let round_trip = Transform::from_quaternion(quarter.quaternion()).unwrap();
+        assert_close(&round_trip.rotation().concat(), &quarter.rotation().concat());
+    }
+    
+    #[test]
+    fn test_jacobian_matches_finite_differences() {
+        let chain = KinematicChain::from_params(&serde_json::json!({"dh": [
+            {"a": 0.0, "alpha": FRAC_PI_2, "d": 0.4},
+            {"a": 0.5, "alpha": 0.0, "d": 0.0, "theta": 0.3},
+            {"a": 0.0, "alpha": 0.0, "d": 0.1, "joint": "prismatic"},
+        ]}))
+        .unwrap();
+        let q = [0.2, -0.4, 0.3];
+        let jacobian = chain.jacobian(&q).unwrap();
+        let h = 1e-6;
+        for col in 0..3 {
+            let mut shifted = q;
+            shifted[col] += h;
+            let ahead = chain.forward(&shifted).unwrap().translation();
+            let here = chain.forward(&q).unwrap().translation();
+            for row in 0..3 {
+                let numeric = (ahead[row] - here[row]) / h;
+                assert!((jacobian[row * 3 + col] - numeric).abs() < 1e-5, "J[{}][{}]", row, col);
+            }
+        }
+        // The prismatic joint adds no angular velocity
+        assert_eq!([jacobian[3 * 3 + 2], jacobian[4 * 3 + 2], jacobian[5 * 3 + 2]], [0.0; 3]);
+    }
+    
+    #[test]
+    fn test_inverse_kinematics_reaches_forward_pose() {
+        let chain = planar();
+        let target = chain.forward(&[0.7, 0.9]).unwrap();
+        let solution = chain.inverse(&target, &[0.1, 0.1], &IkOptions::default()).unwrap();
+        assert!(solution.converged, "{:?}", solution);
+        let reached = chain.forward(&solution.joints).unwrap().translation();
+        assert!(norm(&[0, 1, 2].map(|i| reached[i] - target.translation()[i])) <= 1e-6);
+        
+        // Out of reach, the arm stretches towards the target
+        let far = Transform::from_translation([3.0, 0.0, 0.0]);
+        let options = IkOptions {
+            position_only: true,
+            ..Default::default()
+        };
+        let stretched = chain.inverse(&far, &[0.3, 0.3], &options).unwrap();
+        assert!(!stretched.converged);
+        assert!((stretched.position_error - 1.0).abs() < 1e-3, "{:?}", stretched);
+        
+        // Limits hold even when the target lies beyond them
+        let mut limited = planar();
+        limited.joints[0].limits = Some((0.0, 0.5));
+        let solution = limited.inverse(&target, &[0.0, 0.0], &options).unwrap();
+        assert!(solution.joints[0] <= 0.5 && !solution.converged);
+    }
+    
+    #[test]
+    fn test_rotation_error_handles_half_turns() {
+        let identity = Transform::identity().rotation();
+        let half = Transform::from_axis_angle([0.0, 1.0, 0.0], std::f64::consts::PI).rotation();
+        let error = rotation_error(&identity, &half);
+        assert_close(&error.map(f64::abs), &[0.0, std::f64::consts::PI, 0.0]);
+        assert_eq!(rotation_error(&half, &half), [0.0; 3]);
+    }
+}
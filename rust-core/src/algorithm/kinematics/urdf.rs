@@ -0,0 +1,192 @@
+//! Kinematic chains from the joints of a URDF robot description
+
+use std::collections::HashMap;
+
+use super::{Joint, JointType, KinematicChain, Transform};
+use crate::error::CoreError;
+
+fn invalid(message: String) -> CoreError {
+    CoreError::InvalidDefinition(format!("URDF: {}", message))
+}
+
+/// Parse a space-separated triple such as an `xyz` or `rpy` attribute
+fn triple(node: roxmltree::Node<'_, '_>, attribute: &str, default: [f64; 3]) -> Result<[f64; 3], CoreError> {
+    let Some(text) = node.attribute(attribute) else {
+        return Ok(default);
+    };
+    let values = text
+        .split_whitespace()
+        .map(|value| value.parse::<f64>().map_err(|_| invalid(format!("'{}' in {} is not a number", value, attribute))))
+        .collect::<Result<Vec<f64>, CoreError>>()?;
+    <[f64; 3]>::try_from(values).map_err(|_| invalid(format!("{} needs three values, got '{}'", attribute, text)))
+}
+
+fn child<'a, 'input>(node: roxmltree::Node<'a, 'input>, name: &str) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+fn link_of(joint: roxmltree::Node<'_, '_>, tag: &str, name: &str) -> Result<String, CoreError> {
+    child(joint, tag)
+        .and_then(|node| node.attribute("link"))
+        .map(str::to_string)
+        .ok_or_else(|| invalid(format!("joint '{}' has no {} link", name, tag)))
+}
+
+/// A URDF `<joint>` element, before fixed joints are folded away
+struct UrdfJoint {
+    name: String,
+    kind: String,
+    parent: String,
+    origin: Transform,
+    axis: [f64; 3],
+    limits: Option<(f64, f64)>,
+}
+
+fn parse_joint(node: roxmltree::Node<'_, '_>) -> Result<(String, UrdfJoint), CoreError> {
+    let name = node.attribute("name").unwrap_or_default().to_string();
+    let kind = node.attribute("type").unwrap_or_default().to_string();
+    let origin = match child(node, "origin") {
+        Some(origin) => {
+            let [roll, pitch, yaw] = triple(origin, "rpy", [0.0; 3])?;
+            Transform::from_translation(triple(origin, "xyz", [0.0; 3])?).then(&Transform::from_rpy(roll, pitch, yaw))
+        }
+        None => Transform::identity(),
+    };
+    let axis = match child(node, "axis") {
+        Some(axis) => triple(axis, "xyz", [1.0, 0.0, 0.0])?,
+        None => [1.0, 0.0, 0.0],
+    };
+    // Continuous joints have no limits even if the element is present
+    let limits = match (node.attribute("type"), child(node, "limit")) {
+        (Some("continuous"), _) | (_, None) => None,
+        (_, Some(limit)) => {
+            let bound = |attribute: &str| {
+                limit
+                    .attribute(attribute)
+                    .unwrap_or("0")
+                    .parse::<f64>()
+                    .map_err(|_| invalid(format!("joint '{}' has a malformed {} limit", name, attribute)))
+            };
+            Some((bound("lower")?, bound("upper")?))
+        }
+    };
+    let child_link = link_of(node, "child", &name)?;
+    let parent = link_of(node, "parent", &name)?;
+    Ok((
+        child_link,
+        UrdfJoint {
+            name,
+            kind,
+            parent,
+            origin,
+            axis,
+            limits,
+        },
+    ))
+}
+
+impl KinematicChain {
+    /// Chain of the joints leading from `base_link` to `tip_link` in the
+    /// URDF document `urdf`
+    ///
+    /// Revolute, continuous and prismatic joints become chain joints, and
+    /// fixed joints are folded into the next joint's origin or the tool.
+    /// Links, inertias and other elements are ignored. A malformed document,
+    /// a floating or planar joint on the path, or links not connected from
+    /// base to tip fail with `CoreError::InvalidDefinition`.
+    pub fn from_urdf(urdf: &str, base_link: &str, tip_link: &str) -> Result<Self, CoreError> {
+        let document = roxmltree::Document::parse(urdf).map_err(|e| invalid(e.to_string()))?;
+        let mut by_child = HashMap::new();
+        for node in document.root_element().children().filter(|node| node.has_tag_name("joint")) {
+            let (child_link, joint) = parse_joint(node)?;
+            by_child.insert(child_link, joint);
+        }
+        
+        // Walk up from the tip, then reverse into base-to-tip order
+        let mut path = Vec::new();
+        let mut link = tip_link.to_string();
+        while link != base_link {
+            let joint = by_child
+                .remove(&link)
+                .ok_or_else(|| invalid(format!("no joint path from '{}' to '{}'", base_link, tip_link)))?;
+            link = joint.parent.clone();
+            path.push(joint);
+        }
+        path.reverse();
+        
+        let mut joints = Vec::new();
+        let mut pending = Transform::identity();
+        for joint in path {
+            let origin = pending.then(&joint.origin);
+            let joint_type = match joint.kind.as_str() {
+                "revolute" | "continuous" => JointType::Revolute,
+                "prismatic" => JointType::Prismatic,
+                "fixed" => {
+                    pending = origin;
+                    continue;
+                }
+                other => return Err(invalid(format!("joint '{}' has unsupported type '{}'", joint.name, other))),
+            };
+            joints.push(Joint {
+                name: joint.name,
+                joint_type,
+                origin,
+                axis: joint.axis,
+                limits: joint.limits,
+            });
+            pending = Transform::identity();
+        }
+        Self::new(joints, pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const ARM: &str = r#"<?xml version="1.0"?>
+<robot name="arm">
+  <link name="base"/><link name="upper"/><link name="lower"/><link name="flange"/><link name="tool"/>
+  <joint name="shoulder" type="revolute">
+    <parent link="base"/><child link="upper"/>
+    <origin xyz="0 0 0.5"/><axis xyz="0 0 1"/>
+    <limit lower="-1.5" upper="1.5" effort="10" velocity="1"/>
+  </joint>
+  <joint name="elbow" type="continuous">
+    <parent link="upper"/><child link="lower"/>
+    <origin xyz="1 0 0"/><axis xyz="0 0 1"/>
+  </joint>
+  <joint name="wrist_mount" type="fixed">
+    <parent link="lower"/><child link="flange"/>
+    <origin xyz="1 0 0"/>
+  </joint>
+  <joint name="slide" type="prismatic">
+    <parent link="flange"/><child link="tool"/>
+    <origin rpy="0 0 1.5707963267948966"/><axis xyz="1 0 0"/>
+    <limit lower="0" upper="0.2"/>
+  </joint>
+</robot>"#;
+    
+    #[test]
+    fn test_chain_from_urdf_folds_fixed_joints() {
+        let chain = KinematicChain::from_urdf(ARM, "base", "tool").unwrap();
+        let names: Vec<&str> = chain.joints().iter().map(|joint| joint.name.as_str()).collect();
+        assert_eq!(names, vec!["shoulder", "elbow", "slide"]);
+        assert_eq!(chain.joints()[0].limits, Some((-1.5, 1.5)));
+        assert_eq!(chain.joints()[1].limits, None);
+        
+        // The slide points along +y once the fixed mount and its yaw apply
+        let pose = chain.forward(&[0.0, 0.0, 0.1]).unwrap().translation();
+        for (actual, expected) in pose.iter().zip([2.0, 0.1, 0.5]) {
+            assert!((actual - expected).abs() < 1e-9, "{:?}", pose);
+        }
+        
+        assert!(KinematicChain::from_urdf(ARM, "tool", "base").is_err());
+        assert!(KinematicChain::from_urdf("<robot>", "base", "tool").is_err());
+        let floating = ARM.replace("type=\"continuous\"", "type=\"floating\"");
+        assert!(matches!(
+            KinematicChain::from_urdf(&floating, "base", "tool"),
+            Err(CoreError::InvalidDefinition(_))
+        ));
+    }
+}
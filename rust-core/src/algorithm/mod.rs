@@ -0,0 +1,761 @@
+//! Algorithm framework for processing data
+
+#[cfg(all(not(feature = "std"), not(test)))]
+use crate::compat::Float;
+use crate::compat::HashMap;
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+use crate::wire;
+#[cfg(feature = "std")]
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use serde::{Serialize, Deserialize};
+use serde_json::{json, Map, Value};
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+use core::str::FromStr;
+use core::time::Duration;
+
+pub mod builtins;
+#[cfg(feature = "std")]
+pub mod context;
+#[cfg(feature = "std")]
+pub mod definition;
+pub mod filters;
+#[cfg(all(feature = "gpu", not(target_arch = "wasm32")))]
+pub mod gpu;
+#[cfg(feature = "std")]
+pub mod guarded;
+#[cfg(feature = "std")]
+pub mod json_input;
+pub mod kinematics;
+#[cfg(feature = "std")]
+pub mod odometry;
+pub mod params;
+#[cfg(feature = "std")]
+pub mod planning;
+#[cfg(feature = "std")]
+pub mod recording;
+#[cfg(feature = "std")]
+pub mod registry;
+pub mod samples;
+pub mod schema;
+#[cfg(feature = "std")]
+pub mod stateful;
+pub mod tensor;
+#[cfg(feature = "std")]
+pub mod tolerance;
+#[cfg(feature = "std")]
+pub mod trajectory;
+
+/// Trait for algorithm implementation
+///
+/// Algorithms must be shareable across threads so the engine can run them
+/// concurrently.
+pub trait Algorithm: Send + Sync {
+    /// Process input data and return output
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError>;
+    
+    /// Process input data, appending the result to a caller-provided buffer
+    ///
+    /// The engine pre-sizes `output` from the metadata's `output_size_hint`,
+    /// so implementations that write directly into it avoid reallocations.
+    /// The default implementation delegates to `process` and copies its
+    /// result, so only overriding algorithms, such as the `scale`, `clamp`
+    /// and `window_stats` built-ins, benefit.
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, memory: &mut MemoryManager) -> Result<(), CoreError> {
+        let result = self.process(input, memory)?;
+        output.extend_from_slice(&result);
+        Ok(())
+    }
+    
+    /// Process input like `process_into`, drawing time and randomness from
+    /// `context`
+    ///
+    /// The engine always calls this form. Algorithms that read a clock or
+    /// random numbers override it, and implement `process` by running it
+    /// under `context::Context::with_default`; everything else keeps the
+    /// default, which ignores the context and delegates to `process_into`.
+    #[cfg(feature = "std")]
+    fn process_in_context(
+        &self,
+        input: &[u8],
+        output: &mut Vec<u8>,
+        memory: &mut MemoryManager,
+        _context: &mut context::Context<'_>,
+    ) -> Result<(), CoreError> {
+        self.process_into(input, output, memory)
+    }
+    
+    /// Process a typed input into a typed output
+    ///
+    /// Lets algorithms compose through `tensor::Tensor`s instead of each
+    /// inventing a byte layout; `CoreEngine::execute_typed` calls it.
+    /// Algorithms that need the shape override it. The default
+    /// implementation runs `process` on the tensor's element bytes and
+    /// returns the output as a flat tensor of the metadata's output element
+    /// type, or of the input's if no output schema is declared.
+    fn process_typed(&self, input: &tensor::Tensor, memory: &mut MemoryManager) -> Result<tensor::Tensor, CoreError> {
+        let output = self.process(input.data(), memory)?;
+        let dtype = self.metadata().output_schema.map_or(input.dtype(), |schema| schema.element_type);
+        tensor::Tensor::flat(dtype, output)
+    }
+    
+    /// Attributes describing the `output` produced for `input`
+    ///
+    /// Called after a successful run with `memory` as the run left it. The
+    /// engine attaches these to the `AlgorithmOutput` returned by
+    /// `CoreEngine::execute`. The default implementation adds none.
+    fn output_attributes(&self, _input: &[u8], _output: &[u8], _memory: &MemoryManager) -> HashMap<String, String> {
+        HashMap::new()
+    }
+    
+    /// The algorithm's in-place form, if it has one
+    ///
+    /// `CoreEngine::execute_in_place` uses this to find in-place support
+    /// through a `dyn Algorithm`. Implementors of `InPlaceAlgorithm` override
+    /// it to return `Some(self)`.
+    fn as_in_place(&self) -> Option<&dyn InPlaceAlgorithm> {
+        None
+    }
+    
+    /// The algorithm's multi-output form, if it has one
+    ///
+    /// `CoreEngine::execute_multi_output` uses this to find multi-output
+    /// support through a `dyn Algorithm`. Implementors of
+    /// `MultiOutputAlgorithm` override it to return `Some(self)`.
+    fn as_multi_output(&self) -> Option<&dyn MultiOutputAlgorithm> {
+        None
+    }
+    
+    /// The algorithm's checkpointable streaming form, if it has one
+    ///
+    /// `CoreEngine::checkpoint_stream`, `CoreEngine::resume_stream` and
+    /// `CoreEngine::peek_stream_buffer` use this to reach the state of a
+    /// `dyn Algorithm`. Implementors of
+    /// `StreamingAlgorithm` override it to return `Some(self)`.
+    fn as_streaming(&self) -> Option<&dyn StreamingAlgorithm> {
+        None
+    }
+    
+    /// The algorithm's lifecycle, if it is a wrapped `StatefulAlgorithm`
+    ///
+    /// `CoreEngine::reset_algorithm` and `CoreEngine::shutdown` use this to
+    /// reach the instance behind a `dyn Algorithm`; only
+    /// `stateful::Stateful` overrides it.
+    #[cfg(feature = "std")]
+    fn as_stateful(&self) -> Option<&stateful::Stateful> {
+        None
+    }
+    
+    /// Optional crate features the algorithm needs to run
+    ///
+    /// Registration fails with `CoreError::UnsupportedCapability` unless
+    /// every flag set here is also set in `CoreEngine::capabilities`. The
+    /// default requires nothing.
+    #[cfg(feature = "std")]
+    fn required_capabilities(&self) -> crate::Capabilities {
+        crate::Capabilities::default()
+    }
+    
+    /// Get the algorithm's unique identifier
+    fn id(&self) -> &str;
+    
+    /// Get the algorithm's metadata
+    fn metadata(&self) -> AlgorithmMetadata;
+}
+
+/// Algorithm that can transform a buffer without producing a separate output
+///
+/// The result must equal what `process` returns for the same bytes, and an
+/// error must leave the buffer unchanged.
+pub trait InPlaceAlgorithm: Algorithm {
+    /// Transform `buf` in place
+    fn process_in_place(&self, buf: &mut [u8], memory: &mut MemoryManager) -> Result<(), CoreError>;
+}
+
+/// Algorithm that produces several named outputs from one input
+///
+/// Saves concatenating results into `process`'s single buffer and
+/// splitting them again afterwards; `CoreEngine::execute_multi_output`
+/// stores each output in its own memory region.
+pub trait MultiOutputAlgorithm: Algorithm {
+    /// Process input data into outputs keyed by name
+    fn process_multi(&self, input: &[u8], memory: &mut MemoryManager) -> Result<HashMap<String, Vec<u8>>, CoreError>;
+}
+
+/// Algorithm carrying state from one call to the next, so a stream can be
+/// processed in chunks, whose state can be saved and restored
+///
+/// Restoring a checkpoint and feeding the data that followed it must give
+/// the same output as an uninterrupted run. Both methods take `&self`:
+/// state lives behind interior mutability, as registered algorithms are
+/// shared.
+pub trait StreamingAlgorithm: Algorithm {
+    /// Serialize the state carried between calls
+    fn checkpoint(&self) -> Vec<u8>;
+    
+    /// Replace the carried state with one saved by `checkpoint`
+    ///
+    /// A state of the wrong shape, e.g. from an algorithm configured
+    /// differently, fails with `CoreError::InvalidInput` and leaves the
+    /// current state untouched.
+    fn restore_checkpoint(&self, state: &[u8]) -> Result<(), CoreError>;
+    
+    /// Copy of the input bytes held back waiting for more data, e.g. a
+    /// partial frame, without consuming them
+    ///
+    /// For troubleshooting stalled streams. Returns a copy because the
+    /// buffer lives behind the algorithm's lock; the default reports
+    /// nothing buffered.
+    fn peek_buffered(&self) -> Vec<u8> {
+        Vec::new()
+    }
+}
+
+/// Marker for algorithms whose output depends only on their input
+///
+/// Implementing it promises that `process` neither reads nor writes memory
+/// regions and is deterministic, which is what lets the engine cache the
+/// algorithm's outputs (see `CoreEngine::register_pure`).
+pub trait Pure: Algorithm {}
+
+/// Result of an execution: output bytes plus string side-band attributes
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AlgorithmOutput {
+    pub data: Vec<u8>,
+    pub attributes: AttributeMap,
+}
+
+/// String attributes with typed accessors
+///
+/// Values stay strings on the wire (the map serializes as a plain JSON
+/// object), but `set` and `get_parsed` convert through `Display` and
+/// `FromStr` so callers need not parse by hand. The map dereferences to the
+/// underlying `HashMap` for everything else.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AttributeMap(HashMap<String, String>);
+
+impl AttributeMap {
+    /// Create an empty map
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Store `value` under `key` in its `Display` form
+    pub fn set<T: fmt::Display>(&mut self, key: &str, value: T) {
+        self.0.insert(key.to_string(), value.to_string());
+    }
+    
+    /// Parse the value under `key`, failing if it is absent or malformed
+    pub fn get_parsed<T: FromStr>(&self, key: &str) -> Result<T, CoreError>
+    where
+        T::Err: fmt::Display,
+    {
+        let value = self
+            .0
+            .get(key)
+            .ok_or_else(|| CoreError::InvalidInput(format!("missing attribute '{}'", key)))?;
+        value
+            .parse()
+            .map_err(|e| CoreError::InvalidInput(format!("attribute '{}' = '{}': {}", key, value, e)))
+    }
+    
+    /// Unwrap the underlying map
+    pub fn into_inner(self) -> HashMap<String, String> {
+        self.0
+    }
+}
+
+impl Deref for AttributeMap {
+    type Target = HashMap<String, String>;
+    
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for AttributeMap {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<HashMap<String, String>> for AttributeMap {
+    fn from(map: HashMap<String, String>) -> Self {
+        Self(map)
+    }
+}
+
+impl AlgorithmOutput {
+    /// Version byte of the format written by `to_portable_bytes`
+    pub const PORTABLE_VERSION: u8 = 1;
+    
+    /// Wrap output bytes with no attributes
+    pub fn new(data: Vec<u8>) -> Self {
+        Self {
+            data,
+            attributes: AttributeMap::new(),
+        }
+    }
+    
+    /// Add an attribute
+    pub fn with_attribute(mut self, key: &str, value: impl Into<String>) -> Self {
+        self.attributes.insert(key.to_string(), value.into());
+        self
+    }
+    
+    /// Encode in a host-independent binary format
+    ///
+    /// The format is a version byte (`PORTABLE_VERSION`), the data as a
+    /// `u32` length and bytes, a `u32` attribute count, then each attribute
+    /// as length-prefixed UTF-8 key and value, sorted by key. Every integer
+    /// is little-endian regardless of the host.
+    pub fn to_portable_bytes(&self) -> Result<Vec<u8>, CoreError> {
+        let mut out = vec![Self::PORTABLE_VERSION];
+        wire::put_bytes(&mut out, &self.data)?;
+        let mut attributes: Vec<_> = self.attributes.iter().collect();
+        attributes.sort();
+        out.extend_from_slice(&(attributes.len() as u32).to_le_bytes());
+        for (key, value) in attributes {
+            wire::put_bytes(&mut out, key.as_bytes())?;
+            wire::put_bytes(&mut out, value.as_bytes())?;
+        }
+        Ok(out)
+    }
+    
+    /// Decode a buffer produced by `to_portable_bytes`, on any host
+    pub fn from_portable_bytes(bytes: &[u8]) -> Result<Self, CoreError> {
+        let mut reader = wire::Reader::new(bytes, "portable output", CoreError::InvalidInput);
+        let version = reader.u8()?;
+        if version != Self::PORTABLE_VERSION {
+            return Err(reader.error(&format!("unsupported version {}", version)));
+        }
+        let data = reader.bytes()?.to_vec();
+        let count = reader.u32()?;
+        let mut attributes = AttributeMap::new();
+        for _ in 0..count {
+            let key = reader.str()?;
+            attributes.insert(key, reader.str()?);
+        }
+        reader.finish()?;
+        Ok(Self { data, attributes })
+    }
+
+}
+
+/// Metadata for algorithm description and configuration
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AlgorithmMetadata {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub parameters: Vec<ParameterDefinition>,
+    /// Expected output size, used to pre-allocate the output buffer
+    #[serde(default)]
+    pub output_size_hint: OutputSizeHint,
+    /// Schema the input must conform to, if declared
+    #[serde(default)]
+    pub input_schema: Option<schema::DataSchema>,
+    /// Schema the output conforms to, if declared
+    #[serde(default)]
+    pub output_schema: Option<schema::DataSchema>,
+    /// Shortest input the algorithm accepts; the engine rejects anything
+    /// shorter with `CoreError::InputTooSmall`
+    ///
+    /// Zero, the default, accepts empty input, which elementwise algorithms
+    /// map to empty output.
+    #[serde(default)]
+    pub min_input_bytes: usize,
+    /// Time budget for one execution; `CoreEngine::execute_algorithm_timed`
+    /// counts executions that take longer as deadline misses
+    #[serde(default)]
+    pub expected_duration: Option<Duration>,
+}
+
+impl AlgorithmMetadata {
+    /// Describe the parameter object as a JSON Schema document
+    ///
+    /// Parameters without a default are listed as required. Defaults are
+    /// emitted typed according to their `ParameterType`; a default that does
+    /// not parse as its declared type is omitted rather than misreported.
+    pub fn to_json_schema(&self) -> String {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        for parameter in &self.parameters {
+            let mut property = Map::new();
+            property.insert("type".to_string(), json!(parameter.parameter_type.json_schema_type()));
+            property.insert("description".to_string(), json!(parameter.description));
+            match &parameter.default_value {
+                Some(default) => {
+                    if let Some(value) = parameter.parameter_type.default_to_json(default) {
+                        property.insert("default".to_string(), value);
+                    }
+                }
+                None => required.push(json!(parameter.name)),
+            }
+            properties.insert(parameter.name.clone(), Value::Object(property));
+        }
+        
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": self.name,
+            "description": self.description,
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        })
+        .to_string()
+    }
+}
+
+/// Hint describing how large an algorithm's output is relative to its input
+///
+/// The hint only affects buffer pre-allocation: a wrong hint costs a resize,
+/// never incorrect output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum OutputSizeHint {
+    /// Output is the same length as the input
+    SameAsInput,
+    /// Output length is the input length scaled by the given ratio
+    Ratio(f32),
+    /// Output has a fixed length regardless of input
+    Fixed(usize),
+    /// No useful estimate; the output grows on demand
+    #[default]
+    Unknown,
+}
+
+impl OutputSizeHint {
+    /// Number of bytes to reserve for an input of `input_len` bytes
+    pub fn capacity_for(&self, input_len: usize) -> usize {
+        match *self {
+            OutputSizeHint::SameAsInput => input_len,
+            OutputSizeHint::Ratio(ratio) if ratio.is_finite() && ratio > 0.0 => {
+                (input_len as f64 * ratio as f64).ceil() as usize
+            }
+            OutputSizeHint::Ratio(_) => 0,
+            OutputSizeHint::Fixed(size) => size,
+            OutputSizeHint::Unknown => 0,
+        }
+    }
+}
+
+/// Parameter definition for algorithm configuration
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ParameterDefinition {
+    pub name: String,
+    pub parameter_type: ParameterType,
+    pub description: String,
+    pub default_value: Option<String>,
+}
+
+/// Types of parameters supported in algorithms
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParameterType {
+    Integer,
+    Float,
+    Boolean,
+    String,
+    Array,
+    Object,
+}
+
+impl ParameterType {
+    /// JSON Schema `type` keyword for this parameter type
+    pub fn json_schema_type(&self) -> &'static str {
+        match self {
+            ParameterType::Integer => "integer",
+            ParameterType::Float => "number",
+            ParameterType::Boolean => "boolean",
+            ParameterType::String => "string",
+            ParameterType::Array => "array",
+            ParameterType::Object => "object",
+        }
+    }
+    
+    /// Every parameter type, in declaration order
+    pub const ALL: [ParameterType; 6] = [
+        ParameterType::Integer,
+        ParameterType::Float,
+        ParameterType::Boolean,
+        ParameterType::String,
+        ParameterType::Array,
+        ParameterType::Object,
+    ];
+    
+    /// A value of this type in the string encoding `parse` accepts
+    ///
+    /// Strings are taken verbatim; every other type is encoded as JSON.
+    pub fn example_value(&self) -> &'static str {
+        match self {
+            ParameterType::Integer => "42",
+            ParameterType::Float => "0.5",
+            ParameterType::Boolean => "true",
+            ParameterType::String => "fast",
+            ParameterType::Array => "[1, 2]",
+            ParameterType::Object => r#"{"key": "value"}"#,
+        }
+    }
+    
+    /// Decode a string-encoded value of this type, such as a parameter
+    /// default
+    ///
+    /// Integers must fit an `i64`; floats also accept integer literals.
+    pub fn parse(&self, s: &str) -> Result<ParsedValue, CoreError> {
+        let invalid = || CoreError::InvalidParameter(format!("'{}' is not a valid {}", s, self.json_schema_type()));
+        if *self == ParameterType::String {
+            return Ok(ParsedValue::String(s.to_string()));
+        }
+        let value = match (self, serde_json::from_str::<Value>(s)) {
+            (_, Ok(value)) => value,
+            // Numbers also come in the looser formats of `params::parse_numeric`
+            (ParameterType::Integer | ParameterType::Float, Err(_)) => {
+                let number = params::parse_numeric(s).map_err(|_| invalid())?;
+                return match self {
+                    ParameterType::Integer if number.fract() == 0.0 && number.abs() < i64::MAX as f64 => {
+                        Ok(ParsedValue::Integer(number as i64))
+                    }
+                    ParameterType::Float => Ok(ParsedValue::Float(number)),
+                    _ => Err(invalid()),
+                };
+            }
+            (_, Err(_)) => return Err(invalid()),
+        };
+        match (self, value) {
+            (ParameterType::Integer, Value::Number(n)) => n.as_i64().map(ParsedValue::Integer).ok_or_else(invalid),
+            (ParameterType::Float, Value::Number(n)) => n.as_f64().map(ParsedValue::Float).ok_or_else(invalid),
+            (ParameterType::Boolean, Value::Bool(b)) => Ok(ParsedValue::Boolean(b)),
+            (ParameterType::Array, Value::Array(items)) => Ok(ParsedValue::Array(items)),
+            (ParameterType::Object, Value::Object(fields)) => Ok(ParsedValue::Object(fields)),
+            _ => Err(invalid()),
+        }
+    }
+    
+    /// Convert a string-encoded default into a typed JSON value
+    fn default_to_json(&self, default: &str) -> Option<Value> {
+        self.parse(default).ok().map(Value::from)
+    }
+}
+
+/// A parameter value decoded by `ParameterType::parse`
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParsedValue {
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    String(String),
+    Array(Vec<Value>),
+    Object(Map<String, Value>),
+}
+
+impl ParsedValue {
+    /// Type of the value
+    pub fn parameter_type(&self) -> ParameterType {
+        match self {
+            ParsedValue::Integer(_) => ParameterType::Integer,
+            ParsedValue::Float(_) => ParameterType::Float,
+            ParsedValue::Boolean(_) => ParameterType::Boolean,
+            ParsedValue::String(_) => ParameterType::String,
+            ParsedValue::Array(_) => ParameterType::Array,
+            ParsedValue::Object(_) => ParameterType::Object,
+        }
+    }
+}
+
+impl From<ParsedValue> for Value {
+    fn from(value: ParsedValue) -> Self {
+        match value {
+            ParsedValue::Integer(n) => Value::from(n),
+            ParsedValue::Float(x) => Value::from(x),
+            ParsedValue::Boolean(b) => Value::Bool(b),
+            ParsedValue::String(s) => Value::String(s),
+            ParsedValue::Array(items) => Value::Array(items),
+            ParsedValue::Object(fields) => Value::Object(fields),
+        }
+    }
+}
+
+/// Factory function to get algorithm by ID
+#[cfg(feature = "std")]
+pub fn get_algorithm_by_id(algorithm_id: &str) -> Option<Box<dyn Algorithm>> {
+    // Only built-in algorithms are available by ID for now
+    builtins::by_id(algorithm_id)
+}
+
+/// Create an algorithm from JSON definition
+#[cfg(feature = "std")]
+pub fn create_algorithm_from_json(json_definition: &str) -> Result<Box<dyn Algorithm>, CoreError> {
+    definition::AlgorithmDefinition::from_json(json_definition)?.build()
+}
+
+/// Create an algorithm from YAML definition
+#[cfg(feature = "yaml")]
+pub fn create_algorithm_from_yaml(yaml_definition: &str) -> Result<Box<dyn Algorithm>, CoreError> {
+    definition::AlgorithmDefinition::from_yaml(yaml_definition)?.build()
+}
+
+/// Create an algorithm from TOML definition
+#[cfg(feature = "toml")]
+pub fn create_algorithm_from_toml(toml_definition: &str) -> Result<Box<dyn Algorithm>, CoreError> {
+    definition::AlgorithmDefinition::from_toml(toml_definition)?.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn parameter(name: &str, parameter_type: ParameterType, default_value: Option<&str>) -> ParameterDefinition {
+        ParameterDefinition {
+            name: name.to_string(),
+            parameter_type,
+            description: format!("The {} parameter", name),
+            default_value: default_value.map(str::to_string),
+        }
+    }
+    
+    fn sample_metadata() -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "Sample".to_string(),
+            description: "Exercises every parameter type".to_string(),
+            parameters: vec![
+                parameter("gain", ParameterType::Float, None),
+                parameter("taps", ParameterType::Integer, Some("8")),
+                parameter("enabled", ParameterType::Boolean, Some("true")),
+                parameter("mode", ParameterType::String, Some("fast")),
+                parameter("weights", ParameterType::Array, Some("[1, 2]")),
+                parameter("extra", ParameterType::Object, None),
+            ],
+            ..Default::default()
+        }
+    }
+    
+    #[test]
+    fn test_json_schema_types_and_required() {
+        let schema: Value = serde_json::from_str(&sample_metadata().to_json_schema()).unwrap();
+        let properties = &schema["properties"];
+        
+        assert_eq!(properties["gain"]["type"], "number");
+        assert_eq!(properties["taps"]["type"], "integer");
+        assert_eq!(properties["taps"]["default"], 8);
+        assert_eq!(properties["enabled"]["type"], "boolean");
+        assert_eq!(properties["mode"]["default"], "fast");
+        assert_eq!(properties["weights"]["type"], "array");
+        assert_eq!(properties["extra"]["type"], "object");
+        assert_eq!(properties["gain"]["description"], "The gain parameter");
+        assert_eq!(schema["required"], json!(["gain", "extra"]));
+    }
+    
+    #[test]
+    fn test_parameter_types_parse_their_examples() {
+        for parameter_type in ParameterType::ALL {
+            let parsed = parameter_type.parse(parameter_type.example_value()).unwrap();
+            assert_eq!(parsed.parameter_type(), parameter_type);
+        }
+        assert_eq!(ParameterType::Integer.parse("-8").unwrap(), ParsedValue::Integer(-8));
+        assert_eq!(ParameterType::Float.parse("3").unwrap(), ParsedValue::Float(3.0));
+        assert_eq!(ParameterType::Integer.parse("0x1F").unwrap(), ParsedValue::Integer(31));
+        assert_eq!(ParameterType::Float.parse("1_000.5f").unwrap(), ParsedValue::Float(1000.5));
+        assert_eq!(ParameterType::Boolean.parse("false").unwrap(), ParsedValue::Boolean(false));
+        assert_eq!(ParameterType::String.parse("[1]").unwrap(), ParsedValue::String("[1]".to_string()));
+        assert_eq!(
+            ParameterType::Array.parse(r#"[1, "two", null]"#).unwrap(),
+            ParsedValue::Array(vec![json!(1), json!("two"), Value::Null])
+        );
+        let object = ParameterType::Object.parse(r#"{"gain": 2.5, "taps": [1]}"#).unwrap();
+        assert_eq!(object.parameter_type(), ParameterType::Object);
+        assert_eq!(Value::from(object), json!({ "gain": 2.5, "taps": [1] }));
+    }
+    
+    #[test]
+    fn test_malformed_parameter_values_rejected() {
+        let rejected = [
+            (ParameterType::Integer, "1.5"),
+            (ParameterType::Integer, "18446744073709551615"),
+            (ParameterType::Integer, "eight"),
+            (ParameterType::Integer, "2.5f"),
+            (ParameterType::Float, "\"0.5\""),
+            (ParameterType::Boolean, "yes"),
+            (ParameterType::Array, "[1, 2"),
+            (ParameterType::Array, r#"{"a": 1}"#),
+            (ParameterType::Object, "[]"),
+            (ParameterType::Object, ""),
+        ];
+        for (parameter_type, s) in rejected {
+            assert!(
+                matches!(parameter_type.parse(s), Err(CoreError::InvalidParameter(_))),
+                "{:?} accepted {:?}",
+                parameter_type,
+                s
+            );
+        }
+    }
+    
+    #[test]
+    fn test_attribute_map_round_trips_typed_values() {
+        let mut attributes = AttributeMap::new();
+        attributes.set("fft_size", 512usize);
+        attributes.set("bin_resolution_hz", 1.953125f32);
+        attributes.set("label", "imu");
+        
+        assert_eq!(attributes["fft_size"], "512");
+        assert_eq!(attributes.get_parsed::<usize>("fft_size").unwrap(), 512);
+        assert_eq!(attributes.get_parsed::<f32>("bin_resolution_hz").unwrap(), 1.953125);
+        assert!(matches!(attributes.get_parsed::<i32>("label"), Err(CoreError::InvalidInput(_))));
+        assert!(matches!(attributes.get_parsed::<i32>("absent"), Err(CoreError::InvalidInput(_))));
+        
+        let output = AlgorithmOutput::new(Vec::new()).with_attribute("fft_size", "64");
+        let json = serde_json::to_value(&output).unwrap();
+        assert_eq!(json["attributes"], json!({ "fft_size": "64" }));
+    }
+    
+    #[test]
+    fn test_portable_bytes_round_trip() {
+        let output = AlgorithmOutput::new(vec![1, 2, 3, 4])
+            .with_attribute("served_by", "primary")
+            .with_attribute("fft_size", "64");
+        let bytes = output.to_portable_bytes().unwrap();
+        assert_eq!(AlgorithmOutput::from_portable_bytes(&bytes).unwrap(), output);
+        
+        assert!(AlgorithmOutput::from_portable_bytes(&bytes[..bytes.len() - 1]).is_err());
+        let mut future = bytes.clone();
+        future[0] = 2;
+        assert!(AlgorithmOutput::from_portable_bytes(&future).is_err());
+        assert!(AlgorithmOutput::from_portable_bytes(&[]).is_err());
+    }
+    
+    #[test]
+    fn test_portable_bytes_written_by_hand() {
+        // What a big-endian peer emits after swapping each length to little-endian
+        let bytes = [
+            1, // version
+            2, 0, 0, 0, 0xbe, 0xef, // data
+            1, 0, 0, 0, // one attribute
+            1, 0, 0, 0, b'k', // key
+            2, 0, 0, 0, b'v', b'1', // value
+        ];
+        let output = AlgorithmOutput::from_portable_bytes(&bytes).unwrap();
+        assert_eq!(output.data, vec![0xbe, 0xef]);
+        assert_eq!(output.attributes["k"], "v1");
+        assert_eq!(output.to_portable_bytes().unwrap(), bytes);
+        
+        // The same buffer left in big-endian order reads as absurd lengths
+        let unswapped = [1, 0, 0, 0, 2, 0xbe, 0xef, 0, 0, 0, 0];
+        assert!(AlgorithmOutput::from_portable_bytes(&unswapped).is_err());
+    }
+    
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_json_schema_validates_parameter_sets() {
+        let schema: Value = serde_json::from_str(&sample_metadata().to_json_schema()).unwrap();
+        let validator = jsonschema::validator_for(&schema).unwrap();
+        
+        assert!(validator.is_valid(&json!({"gain": 1.5, "extra": {}, "weights": [0.5]})));
+        assert!(!validator.is_valid(&json!({"extra": {}})), "missing required gain");
+        assert!(!validator.is_valid(&json!({"gain": "loud", "extra": {}})), "wrong type");
+        assert!(!validator.is_valid(&json!({"gain": 1.0, "extra": [], "taps": 2.5})));
+    }
+}
@@ -0,0 +1,506 @@
+//! Wheel odometry for differential-drive and mecanum bases
+//!
+//! `WheelOdometry` turns per-wheel readings, either encoder ticks or wheel
+//! angular velocities, into the base's planar pose in the frame it started
+//! in. Each step moves the base along the arc the wheels describe,
+//! integrated at its midpoint heading, and grows a covariance estimate in
+//! proportion to the distance each wheel rolled.
+
+use serde_json::Value;
+use std::f64::consts::TAU;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::kinematics::Transform;
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, OutputSizeHint, ParameterDefinition, ParameterType};
+use crate::algorithm::StreamingAlgorithm;
+use crate::error::CoreError;
+use crate::math::{mat3_mul_f64, transpose3_f64};
+use crate::memory::MemoryManager;
+use crate::transform::TransformTree;
+
+type Matrix3 = [[f64; 3]; 3];
+
+/// Values in an output record: `x`, `y`, `theta`, then the row-major
+/// covariance
+const RECORD_LEN: usize = 12;
+
+/// A position and heading in the plane
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Pose2D {
+    pub x: f64,
+    pub y: f64,
+    /// Heading in radians, counter-clockwise from the X axis
+    pub theta: f64,
+}
+
+impl Pose2D {
+    /// The pose as a rigid transform, for a `TransformTree`
+    pub fn to_transform(&self) -> Transform {
+        let heading = Transform::from_axis_angle([0.0, 0.0, 1.0], self.theta);
+        Transform::from_translation([self.x, self.y, 0.0]).then(&heading)
+    }
+}
+
+/// Wheel layout of a mobile base, with lengths in metres
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DriveGeometry {
+    /// Two driven wheels on a common axle, read as `(left, right)`
+    Differential { wheel_radius: f64, track_width: f64 },
+    /// Four mecanum wheels with rollers in an X, read as `(front_left,
+    /// front_right, rear_left, rear_right)`
+    ///
+    /// `wheelbase` separates the front and rear axles, `track_width` the
+    /// left and right wheels.
+    Mecanum {
+        wheel_radius: f64,
+        wheelbase: f64,
+        track_width: f64,
+    },
+}
+
+impl DriveGeometry {
+    /// Readings per step: one per wheel
+    pub fn wheels(&self) -> usize {
+        match self {
+            DriveGeometry::Differential { .. } => 2,
+            DriveGeometry::Mecanum { .. } => 4,
+        }
+    }
+    
+    pub(crate) fn wheel_radius(&self) -> f64 {
+        match *self {
+            DriveGeometry::Differential { wheel_radius, .. } => wheel_radius,
+            DriveGeometry::Mecanum { wheel_radius, .. } => wheel_radius,
+        }
+    }
+    
+    pub(crate) fn check(&self) -> Result<(), CoreError> {
+        let lengths = match *self {
+            DriveGeometry::Differential {
+                wheel_radius,
+                track_width,
+            } => vec![("wheel_radius", wheel_radius), ("track_width", track_width)],
+            DriveGeometry::Mecanum {
+                wheel_radius,
+                wheelbase,
+                track_width,
+            } => vec![("wheel_radius", wheel_radius), ("wheelbase", wheelbase), ("track_width", track_width)],
+        };
+        for (name, length) in lengths {
+            if !length.is_finite() || length <= 0.0 {
+                return Err(CoreError::InvalidParameter(format!(
+                    "{} must be finite and positive, got {}",
+                    name, length
+                )));
+            }
+        }
+        Ok(())
+    }
+    
+    /// Rows of the matrix taking each wheel's travel to the base's forward,
+    /// leftward and turning motion
+    fn motion_matrix(&self) -> [[f64; 4]; 3] {
+        match *self {
+            DriveGeometry::Differential { track_width, .. } => [
+                [0.5, 0.5, 0.0, 0.0],
+                [0.0, 0.0, 0.0, 0.0],
+                [-1.0 / track_width, 1.0 / track_width, 0.0, 0.0],
+            ],
+            DriveGeometry::Mecanum {
+                wheelbase, track_width, ..
+            } => {
+                let turn = 1.0 / (2.0 * (wheelbase + track_width));
+                [
+                    [0.25, 0.25, 0.25, 0.25],
+                    [-0.25, 0.25, 0.25, -0.25],
+                    [-turn, turn, -turn, turn],
+                ]
+            }
+        }
+    }
+    
+    /// How far the base moves forward and leftward and how much it turns
+    /// when each wheel rolls `travel` metres
+    pub(crate) fn body_motion(&self, travel: &[f64]) -> [f64; 3] {
+        self.motion_matrix()
+            .map(|row| row.iter().zip(travel).map(|(m, d)| m * d).sum::<f64>())
+    }
+}
+
+/// How wheel readings are given
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WheelInput {
+    /// Encoder ticks counted since the previous reading
+    Ticks { ticks_per_rev: f64 },
+    /// Wheel angular velocities in rad/s, sampled every `dt` seconds
+    Velocity { dt: f64 },
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Estimate {
+    pose: Pose2D,
+    covariance: Matrix3,
+}
+
+/// Pose of a wheeled base integrated from its wheel readings, with a
+/// covariance estimate
+///
+/// The input is consecutive records of one `f32` reading per wheel, in the
+/// order `DriveGeometry` lists them. Each record emits 12 `f32`s: `x`, `y`
+/// and `theta`, then the 3x3 covariance of the pose row by row. Each wheel
+/// contributes variance `slip` per metre it rolls, propagated through the
+/// motion like an extended Kalman filter's prediction step, so the
+/// uncertainty grows with distance and turns.
+///
+/// The pose carries across calls, one call per batch of readings, so the
+/// odometry is not `Pure`. Clones share it, so keep a clone of a registered
+/// instance to read the pose or `publish` it while it runs.
+#[derive(Clone, Debug)]
+pub struct WheelOdometry {
+    geometry: DriveGeometry,
+    input: WheelInput,
+    slip: f64,
+    estimate: Arc<Mutex<Estimate>>,
+}
+
+impl WheelOdometry {
+    pub const ID: &'static str = "wheel_odometry";
+    
+    /// Slip variance, in square metres per metre of wheel travel, unless
+    /// configured otherwise
+    pub const DEFAULT_SLIP: f64 = 0.01;
+    
+    /// Start at the origin with zero covariance, rejecting non-positive
+    /// lengths, ticks per revolution or `dt`
+    pub fn new(geometry: DriveGeometry, input: WheelInput) -> Result<Self, CoreError> {
+        geometry.check()?;
+        let (name, value) = match input {
+            WheelInput::Ticks { ticks_per_rev } => ("ticks_per_rev", ticks_per_rev),
+            WheelInput::Velocity { dt } => ("dt", dt),
+        };
+        if !value.is_finite() || value <= 0.0 {
+            return Err(CoreError::InvalidParameter(format!("{} must be finite and positive, got {}", name, value)));
+        }
+        Ok(Self {
+            geometry,
+            input,
+            slip: Self::DEFAULT_SLIP,
+            estimate: Arc::new(Mutex::new(Estimate::default())),
+        })
+    }
+    
+    /// Add `slip` variance per metre each wheel rolls
+    pub fn with_slip(mut self, slip: f64) -> Result<Self, CoreError> {
+        if !slip.is_finite() || slip < 0.0 {
+            return Err(CoreError::InvalidParameter(format!("slip must be finite and non-negative, got {}", slip)));
+        }
+        self.slip = slip;
+        Ok(self)
+    }
+    
+    /// Create the odometry from its `drive` (`differential` or `mecanum`),
+    /// geometry, `ticks_per_rev` or `dt`, and optional `slip` parameters
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        let drive: String = params::require(params, "drive")?;
+        let wheel_radius = params::require(params, "wheel_radius")?;
+        let track_width = params::require(params, "track_width")?;
+        let geometry = match drive.as_str() {
+            "differential" => DriveGeometry::Differential {
+                wheel_radius,
+                track_width,
+            },
+            "mecanum" => DriveGeometry::Mecanum {
+                wheel_radius,
+                wheelbase: params::require(params, "wheelbase")?,
+                track_width,
+            },
+            other => {
+                return Err(CoreError::InvalidParameter(format!(
+                    "drive must be 'differential' or 'mecanum', got '{}'",
+                    other
+                )));
+            }
+        };
+        let input = match (params::get(params, "ticks_per_rev")?, params::get(params, "dt")?) {
+            (Some(ticks_per_rev), None) => WheelInput::Ticks { ticks_per_rev },
+            (None, Some(dt)) => WheelInput::Velocity { dt },
+            _ => {
+                return Err(CoreError::InvalidParameter(
+                    "give either ticks_per_rev, for encoder ticks, or dt, for wheel velocities".to_string(),
+                ));
+            }
+        };
+        Self::new(geometry, input)?.with_slip(params::get(params, "slip")?.unwrap_or(Self::DEFAULT_SLIP))
+    }
+    
+    pub fn geometry(&self) -> DriveGeometry {
+        self.geometry
+    }
+    
+    pub fn pose(&self) -> Pose2D {
+        self.lock().pose
+    }
+    
+    /// Covariance of `(x, y, theta)`
+    pub fn covariance(&self) -> [[f64; 3]; 3] {
+        self.lock().covariance
+    }
+    
+    /// Restart from `pose`, taken as exact
+    pub fn reset(&self, pose: Pose2D) {
+        *self.lock() = Estimate {
+            pose,
+            covariance: Matrix3::default(),
+        };
+    }
+    
+    /// Advance by one reading per wheel, returning the new pose
+    pub fn update(&self, readings: &[f64]) -> Result<Pose2D, CoreError> {
+        if readings.len() != self.geometry.wheels() {
+            return Err(CoreError::InvalidInput(format!(
+                "{} wheel readings given, the base has {} wheels",
+                readings.len(),
+                self.geometry.wheels()
+            )));
+        }
+        let scale = match self.input {
+            WheelInput::Ticks { ticks_per_rev } => TAU * self.geometry.wheel_radius() / ticks_per_rev,
+            WheelInput::Velocity { dt } => self.geometry.wheel_radius() * dt,
+        };
+        let travel: Vec<f64> = readings.iter().map(|reading| reading * scale).collect();
+        let mut estimate = self.lock();
+        self.step(&mut estimate, &travel);
+        Ok(estimate.pose)
+    }
+    
+    /// Move `estimate` by each wheel's `travel` in metres
+    fn step(&self, estimate: &mut Estimate, travel: &[f64]) {
+        let motion = self.geometry.motion_matrix();
+        let [forward, left, turn] = self.geometry.body_motion(travel);
+        
+        let Pose2D { x, y, theta } = estimate.pose;
+        let (sin, cos) = (theta + turn / 2.0).sin_cos();
+        let dx = forward * cos - left * sin;
+        let dy = forward * sin + left * cos;
+        estimate.pose = Pose2D {
+            x: x + dx,
+            y: y + dy,
+            theta: theta + turn,
+        };
+        
+        // Jacobians of the new pose by the old pose and by the base motion
+        let by_pose = [[1.0, 0.0, -dy], [0.0, 1.0, dx], [0.0, 0.0, 1.0]];
+        let by_motion = [[cos, -sin, -dy / 2.0], [sin, cos, dx / 2.0], [0.0, 0.0, 1.0]];
+        // Wheel noise mapped into the base motion: M diag(slip |d|) M^T
+        let mut motion_noise = Matrix3::default();
+        for (row, noise_row) in motion_noise.iter_mut().enumerate() {
+            for (col, noise) in noise_row.iter_mut().enumerate() {
+                *noise = travel
+                    .iter()
+                    .enumerate()
+                    .map(|(wheel, d)| motion[row][wheel] * motion[col][wheel] * self.slip * d.abs())
+                    .sum();
+            }
+        }
+        let carried = mat3_mul_f64(&mat3_mul_f64(&by_pose, &estimate.covariance), &transpose3_f64(&by_pose));
+        let added = mat3_mul_f64(&mat3_mul_f64(&by_motion, &motion_noise), &transpose3_f64(&by_motion));
+        estimate.covariance = [0, 1, 2].map(|row| [0, 1, 2].map(|col| carried[row][col] + added[row][col]));
+    }
+    
+    /// Set the current pose as the transform from `parent` to `child`, such
+    /// as `odom` to `base_link`, at `timestamp` microseconds
+    pub fn publish(
+        &self,
+        tree: &mut TransformTree,
+        parent: &str,
+        child: &str,
+        timestamp: u64,
+    ) -> Result<(), CoreError> {
+        tree.set(parent, child, self.pose().to_transform(), timestamp)
+    }
+    
+    fn lock(&self) -> MutexGuard<'_, Estimate> {
+        self.estimate.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Algorithm for WheelOdometry {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::with_capacity(input.len() * RECORD_LEN / self.geometry.wheels());
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        let values = samples::f32_from_bytes(input)?;
+        let wheels = self.geometry.wheels();
+        if !values.len().is_multiple_of(wheels) {
+            return Err(CoreError::InvalidInput(format!(
+                "{} f32 values do not form whole {}-wheel readings",
+                values.len(),
+                wheels
+            )));
+        }
+        for record in values.chunks_exact(wheels) {
+            let readings: Vec<f64> = record.iter().map(|&value| f64::from(value)).collect();
+            let pose = self.update(&readings)?;
+            let covariance = self.covariance();
+            let record = [pose.x, pose.y, pose.theta].into_iter().chain(covariance.into_iter().flatten());
+            for value in record {
+                output.extend_from_slice(&(value as f32).to_le_bytes());
+            }
+        }
+        Ok(())
+    }
+    
+    fn as_streaming(&self) -> Option<&dyn StreamingAlgorithm> {
+        Some(self)
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        let parameter = |name: &str, parameter_type: ParameterType, description: &str, default_value: Option<&str>| {
+            ParameterDefinition {
+                name: name.to_string(),
+                parameter_type,
+                description: description.to_string(),
+                default_value: default_value.map(str::to_string),
+            }
+        };
+        AlgorithmMetadata {
+            name: "Wheel Odometry".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Integrates per-wheel f32 readings into (x, y, theta) poses with their covariance".to_string(),
+            parameters: vec![
+                parameter("drive", ParameterType::String, "Wheel layout: 'differential' or 'mecanum'", None),
+                parameter("wheel_radius", ParameterType::Float, "Wheel radius in metres", None),
+                parameter("track_width", ParameterType::Float, "Left to right wheel distance in metres", None),
+                parameter("wheelbase", ParameterType::Float, "Distance between front and rear axles (mecanum)", None),
+                parameter("ticks_per_rev", ParameterType::Float, "Encoder ticks per wheel turn", None),
+                parameter("dt", ParameterType::Float, "Seconds between readings, for velocities in rad/s", None),
+                parameter("slip", ParameterType::Float, "Variance added per metre each wheel rolls", Some("0.01")),
+            ],
+            output_size_hint: OutputSizeHint::Ratio(RECORD_LEN as f32 / self.geometry.wheels() as f32),
+            ..Default::default()
+        }
+    }
+}
+
+/// The pose and row-major covariance, as twelve little-endian `f64`s
+impl StreamingAlgorithm for WheelOdometry {
+    fn checkpoint(&self) -> Vec<u8> {
+        let Estimate { pose, covariance } = *self.lock();
+        [pose.x, pose.y, pose.theta]
+            .into_iter()
+            .chain(covariance.into_iter().flatten())
+            .flat_map(f64::to_le_bytes)
+            .collect()
+    }
+    
+    fn restore_checkpoint(&self, state: &[u8]) -> Result<(), CoreError> {
+        if state.len() != RECORD_LEN * 8 {
+            return Err(CoreError::InvalidInput(format!(
+                "odometry checkpoint must hold {} bytes, got {}",
+                RECORD_LEN * 8,
+                state.len()
+            )));
+        }
+        let mut values = [0.0; RECORD_LEN];
+        for (value, bytes) in values.iter_mut().zip(state.chunks_exact(8)) {
+            let mut le = [0; 8];
+            le.copy_from_slice(bytes);
+            *value = f64::from_le_bytes(le);
+        }
+        *self.lock() = Estimate {
+            pose: Pose2D {
+                x: values[0],
+                y: values[1],
+                theta: values[2],
+            },
+            covariance: [0, 1, 2].map(|row| [0, 1, 2].map(|col| values[3 + row * 3 + col])),
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::f64::consts::FRAC_PI_2;
+    
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-5, "{} != {}", actual, expected);
+    }
+    
+    #[test]
+    fn test_differential_drive_from_ticks() {
+        let odometry = WheelOdometry::from_params(&json!({
+            "drive": "differential",
+            "wheel_radius": 0.1,
+            "track_width": 0.5,
+            "ticks_per_rev": 1000.0
+        }))
+        .unwrap();
+        let mut memory = MemoryManager::new();
+        // One wheel turn straight ahead, then a quarter turn on the spot
+        let output = odometry.process(&samples::f32_to_bytes(&[1000.0, 1000.0, -625.0, 625.0]), &mut memory).unwrap();
+        let records = samples::f32_from_bytes(&output).unwrap();
+        assert_eq!(records.len(), 2 * RECORD_LEN);
+        
+        let pose = odometry.pose();
+        assert_close(pose.x, TAU * 0.1);
+        assert_close(pose.y, 0.0);
+        assert_close(pose.theta, FRAC_PI_2);
+        let covariance = odometry.covariance();
+        assert!(covariance[0][0] > 0.0 && covariance[2][2] > 0.0);
+        assert_close(covariance[0][2], covariance[2][0]);
+        
+        let mut tree = TransformTree::new();
+        odometry.publish(&mut tree, "odom", "base_link", 1_000).unwrap();
+        let ahead = tree.transform_point("base_link", "odom", 1_000, [1.0, 0.0, 0.0]).unwrap();
+        assert_close(ahead[0], TAU * 0.1);
+        assert_close(ahead[1], 1.0);
+        
+        let checkpoint = odometry.checkpoint();
+        odometry.reset(Pose2D::default());
+        odometry.restore_checkpoint(&checkpoint).unwrap();
+        assert_eq!(odometry.pose(), pose);
+        assert!(odometry.process(&samples::f32_to_bytes(&[1.0]), &mut memory).is_err());
+    }
+    
+    #[test]
+    fn test_mecanum_strafes_and_rejects_bad_config() {
+        let geometry = DriveGeometry::Mecanum {
+            wheel_radius: 0.05,
+            wheelbase: 0.3,
+            track_width: 0.2,
+        };
+        let odometry = WheelOdometry::new(geometry, WheelInput::Velocity { dt: 0.1 }).unwrap();
+        for _ in 0..10 {
+            odometry.update(&[-10.0, 10.0, 10.0, -10.0]).unwrap();
+        }
+        let pose = odometry.pose();
+        assert_close(pose.x, 0.0);
+        assert_close(pose.y, 0.5);
+        assert_close(pose.theta, 0.0);
+        
+        // Spinning on the spot: each wheel rolls 5 mm, turning about half the
+        // wheelbase plus half the track
+        odometry.reset(Pose2D::default());
+        let spun = odometry.update(&[-1.0, 1.0, -1.0, 1.0]).unwrap();
+        assert_close(spun.theta, 0.005 / 0.25);
+        assert!(odometry.update(&[1.0, 1.0]).is_err());
+        
+        let base = json!({ "drive": "mecanum", "wheel_radius": 0.05, "track_width": 0.2, "dt": 0.1 });
+        assert!(WheelOdometry::from_params(&base).is_err(), "wheelbase is required");
+        let tank = json!({ "drive": "tank", "wheel_radius": 0.1, "track_width": 0.5, "dt": 0.1 });
+        assert!(WheelOdometry::from_params(&tank).is_err());
+        let no_input = json!({ "drive": "differential", "wheel_radius": 0.1, "track_width": 0.5 });
+        assert!(WheelOdometry::from_params(&no_input).is_err(), "ticks_per_rev or dt is required");
+        assert!(WheelOdometry::new(geometry, WheelInput::Ticks { ticks_per_rev: 0.0 }).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+//! Helpers for reading algorithm parameters
+//!
+//! Parameters are passed as a JSON object so the same configuration can come
+//! from code, definition files, or the Python layer.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+#[cfg(all(not(feature = "std"), not(test)))]
+use crate::compat::Float;
+use crate::error::CoreError;
+
+/// Read an optional parameter, failing if it is present but malformed
+///
+/// A string given where a number is expected is read with
+/// `parse_numeric`, so `"0x1F"` or `"1_000"` configure a numeric parameter.
+pub fn get<T: DeserializeOwned>(params: &Value, name: &str) -> Result<Option<T>, CoreError> {
+    let value = match params.get(name) {
+        None | Some(Value::Null) => return Ok(None),
+        Some(value) => value,
+    };
+    serde_json::from_value(value.clone())
+        .or_else(|e| match value.as_str().map(parse_numeric) {
+            Some(Ok(number)) => serde_json::from_value(numeric_value(number)).map_err(|_| e),
+            _ => Err(e),
+        })
+        .map(Some)
+        .map_err(|e| CoreError::InvalidParameter(format!("'{}': {}", name, e)))
+}
+
+/// Read a required parameter
+pub fn require<T: DeserializeOwned>(params: &Value, name: &str) -> Result<T, CoreError> {
+    get(params, name)?.ok_or_else(|| CoreError::InvalidParameter(format!("missing '{}'", name)))
+}
+
+/// Parse a number in any of the formats sensors and hand-written configs
+/// commonly use
+///
+/// Beyond what `f64::from_str` takes, this accepts `_` between digits
+/// (`1_000`), hexadecimal integers (`0x1F`, optionally signed) and a
+/// trailing type suffix on decimal numbers (`3.14f`, `2.5f64`, `1e3d`).
+/// Surrounding whitespace is ignored. Anything else, including `inf`,
+/// `NaN` and values overflowing `f64`, fails with
+/// `CoreError::InvalidParameter`.
+pub fn parse_numeric(s: &str) -> Result<f64, CoreError> {
+    let invalid = || CoreError::InvalidParameter(format!("'{}' is not a number", s));
+    let trimmed = s.trim();
+    let (negative, unsigned) = match trimmed.as_bytes().first() {
+        Some(b'-') => (true, &trimmed[1..]),
+        Some(b'+') => (false, &trimmed[1..]),
+        _ => (false, trimmed),
+    };
+    let magnitude = if let Some(hex) = unsigned.strip_prefix("0x").or_else(|| unsigned.strip_prefix("0X")) {
+        let digits = without_separators(hex, |c| c.is_ascii_hexdigit()).ok_or_else(invalid)?;
+        u64::from_str_radix(&digits, 16).map_err(|_| invalid())? as f64
+    } else {
+        let number = ["f32", "f64", "f", "F", "d", "D"]
+            .iter()
+            .find_map(|suffix| unsigned.strip_suffix(suffix))
+            .unwrap_or(unsigned);
+        let digits = without_separators(number, |c| c.is_ascii_digit()).ok_or_else(invalid)?;
+        // `from_str` would take words like "inf"; require a digit
+        if !digits.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+            return Err(invalid());
+        }
+        digits.parse::<f64>().map_err(|_| invalid())?
+    };
+    if !magnitude.is_finite() {
+        return Err(invalid());
+    }
+    Ok(if negative { -magnitude } else { magnitude })
+}
+
+/// `text` with its `_` separators removed, or `None` if one is not between
+/// two digits
+fn without_separators(text: &str, is_digit: impl Fn(char) -> bool) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '_' && !(i > 0 && is_digit(chars[i - 1]) && chars.get(i + 1).is_some_and(|&next| is_digit(next))) {
+            return None;
+        }
+    }
+    Some(chars.into_iter().filter(|&c| c != '_').collect())
+}
+
+/// `number` as a JSON integer when it is one, so it also deserializes into
+/// integer types
+fn numeric_value(number: f64) -> Value {
+    if number.fract() == 0.0 && number.abs() < i64::MAX as f64 {
+        Value::from(number as i64)
+    } else {
+        Value::from(number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[test]
+    fn test_parse_numeric_accepts_common_formats() {
+        assert_eq!(parse_numeric("1_000").unwrap(), 1000.0);
+        assert_eq!(parse_numeric("1_000.5_5").unwrap(), 1000.55);
+        assert_eq!(parse_numeric("1e3").unwrap(), 1000.0);
+        assert_eq!(parse_numeric("-2.5E-2").unwrap(), -0.025);
+        assert_eq!(parse_numeric("0x1F").unwrap(), 31.0);
+        assert_eq!(parse_numeric("-0xff_ff").unwrap(), -65535.0);
+        assert_eq!(parse_numeric("2.75f").unwrap(), 2.75);
+        assert_eq!(parse_numeric("2.5f64").unwrap(), 2.5);
+        assert_eq!(parse_numeric(" 1e3d ").unwrap(), 1000.0);
+        assert_eq!(parse_numeric(".5").unwrap(), 0.5);
+    }
+    
+    #[test]
+    fn test_parse_numeric_rejects_garbage() {
+        for garbage in [
+            "", "-", "abc", "1.2.3", "0x", "0xg1", "_1", "1_", "1__0", "1_.5", "f", "inf", "NaN", "1e999", "12 34",
+            "3.14ff", "0x1.8",
+        ] {
+            assert!(
+                matches!(parse_numeric(garbage), Err(CoreError::InvalidParameter(_))),
+                "accepted {:?}",
+                garbage
+            );
+        }
+    }
+    
+    #[test]
+    fn test_get_falls_back_to_numeric_strings() {
+        let params = json!({ "taps": "0x10", "gain": "1.5f", "name": "1e3", "bad": "ten" });
+        assert_eq!(get::<u32>(&params, "taps").unwrap(), Some(16));
+        assert_eq!(get::<f32>(&params, "gain").unwrap(), Some(1.5));
+        // Strings stay strings where a string is expected
+        assert_eq!(get::<String>(&params, "name").unwrap(), Some("1e3".to_string()));
+        assert!(get::<f32>(&params, "bad").is_err());
+        assert!(get::<u8>(&json!({ "n": "1.5" }), "n").is_err());
+    }
+}
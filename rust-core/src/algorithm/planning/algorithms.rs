@@ -0,0 +1,347 @@
+//! Planners as built-ins from a start pose to a goal pose
+
+use serde_json::Value;
+
+use super::{plan_astar, plan_rrt, OccupancyGrid2D, Point, RrtOptions};
+use crate::algorithm::context::Context;
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, ParameterDefinition, ParameterType};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// Values in a pose: x, y, then heading in radians
+const POSE_LEN: usize = 3;
+
+/// Where a planner finds its occupancy grid and how it inflates it
+#[derive(Clone, Debug)]
+struct GridSource {
+    region: String,
+    width: usize,
+    height: usize,
+    resolution: f64,
+    origin: Point,
+    inflation: f64,
+}
+
+impl GridSource {
+    fn from_params(params: &Value) -> Result<Self, CoreError> {
+        let source = Self {
+            region: params::require(params, "grid")?,
+            width: params::require(params, "width")?,
+            height: params::require(params, "height")?,
+            resolution: params::require(params, "resolution")?,
+            origin: params::get(params, "origin")?.unwrap_or([0.0, 0.0]),
+            inflation: params::get(params, "inflation")?.unwrap_or(0.0),
+        };
+        OccupancyGrid2D::new(source.width, source.height, source.resolution, source.origin)?;
+        if !(source.inflation.is_finite() && source.inflation >= 0.0) {
+            return Err(CoreError::InvalidParameter(format!(
+                "inflation must be finite and non-negative, got {}",
+                source.inflation
+            )));
+        }
+        Ok(source)
+    }
+    
+    fn load(&self, memory: &MemoryManager) -> Result<OccupancyGrid2D, CoreError> {
+        let cells = memory
+            .read(&self.region)
+            .ok_or_else(|| CoreError::MemoryKeyMissing(self.region.clone()))?;
+        let grid = OccupancyGrid2D::from_cells(self.width, self.height, self.resolution, self.origin, cells)?;
+        Ok(if self.inflation > 0.0 { grid.inflate(self.inflation) } else { grid })
+    }
+    
+    fn parameters() -> Vec<ParameterDefinition> {
+        let parameter = |name: &str, parameter_type: ParameterType, description: &str, default_value: Option<&str>| {
+            ParameterDefinition {
+                name: name.to_string(),
+                parameter_type,
+                description: description.to_string(),
+                default_value: default_value.map(str::to_string),
+            }
+        };
+        vec![
+            parameter(
+                "grid",
+                ParameterType::String,
+                "Region holding the row-major grid, one byte per cell, non-zero if occupied",
+                None,
+            ),
+            parameter("width", ParameterType::Integer, "Cells per row", None),
+            parameter("height", ParameterType::Integer, "Rows of cells", None),
+            parameter("resolution", ParameterType::Float, "Side of a cell in metres", None),
+            parameter("origin", ParameterType::Array, "World position of the grid's corner", Some("[0.0, 0.0]")),
+            parameter(
+                "inflation",
+                ParameterType::Float,
+                "Metres every obstacle is grown by before planning",
+                Some("0.0"),
+            ),
+        ]
+    }
+}
+
+/// Start and goal poses from exactly two 3-value poses
+fn endpoints(input: &[u8]) -> Result<([f64; POSE_LEN], [f64; POSE_LEN]), CoreError> {
+    let values: Vec<f64> = samples::f32_from_bytes(input)?.into_iter().map(f64::from).collect();
+    match values.as_slice() {
+        &[sx, sy, sh, gx, gy, gh] => Ok(([sx, sy, sh], [gx, gy, gh])),
+        _ => Err(CoreError::InvalidInput(format!(
+            "expected a start and a goal pose of {} f32 values each, got {} values",
+            POSE_LEN,
+            values.len()
+        ))),
+    }
+}
+
+/// Write `waypoints` as poses, each heading towards the next waypoint
+/// except the start's and goal's, which keep the requested headings
+fn write_poses(
+    waypoints: Option<Vec<Point>>,
+    start: [f64; POSE_LEN],
+    goal: [f64; POSE_LEN],
+    output: &mut Vec<u8>,
+) -> Result<(), CoreError> {
+    let waypoints = waypoints.ok_or_else(|| {
+        CoreError::ProcessingFailed(format!("no path from {:?} to {:?}", &start[..2], &goal[..2]))
+    })?;
+    for (i, point) in waypoints.iter().enumerate() {
+        let heading = match (i, waypoints.get(i + 1)) {
+            (0, _) => start[2],
+            (_, None) => goal[2],
+            (_, Some(next)) => (next[1] - point[1]).atan2(next[0] - point[0]),
+        };
+        for value in [point[0], point[1], heading] {
+            output.extend_from_slice(&(value as f32).to_le_bytes());
+        }
+    }
+    Ok(())
+}
+
+/// Shortest grid path between two poses, by A*
+///
+/// The input holds a start and a goal pose of `x`, `y` and heading as
+/// `f32`s, and the output is the waypoints of `plan_astar` as poses of the
+/// same layout: each heads towards the next, except the first and last,
+/// which keep the start's and goal's headings. The grid is read from a
+/// memory region on every execution, so a mapping stage can update it in
+/// between. An unreachable goal fails with `CoreError::ProcessingFailed`.
+#[derive(Clone, Debug)]
+pub struct AStarPlanner {
+    grid: GridSource,
+}
+
+impl AStarPlanner {
+    pub const ID: &'static str = "astar";
+    
+    /// Create the planner from its grid parameters
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        Ok(Self {
+            grid: GridSource::from_params(params)?,
+        })
+    }
+}
+
+impl Algorithm for AStarPlanner {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::new();
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, memory: &mut MemoryManager) -> Result<(), CoreError> {
+        let (start, goal) = endpoints(input)?;
+        let grid = self.grid.load(memory)?;
+        let waypoints = plan_astar(&grid, [start[0], start[1]], [goal[0], goal[1]])?;
+        write_poses(waypoints, start, goal, output)
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            name: "A* Planner".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Plans f32 (x, y, heading) waypoints between two poses on an occupancy grid by A*"
+                .to_string(),
+            parameters: GridSource::parameters(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Path between two poses by a rapidly-exploring random tree, or RRT* when
+/// given a `rewire_radius`
+///
+/// Takes and emits poses as `AStarPlanner` does. Randomness comes from the
+/// execution's context, so a deterministic engine plans the same path on
+/// every run. A goal the tree does not reach fails with
+/// `CoreError::ProcessingFailed`.
+#[derive(Clone, Debug)]
+pub struct RrtPlanner {
+    grid: GridSource,
+    options: RrtOptions,
+}
+
+impl RrtPlanner {
+    pub const ID: &'static str = "rrt";
+    
+    /// Create the planner from its grid parameters and the settings of
+    /// `RrtOptions`
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        let defaults = RrtOptions::default();
+        let planner = Self {
+            grid: GridSource::from_params(params)?,
+            options: RrtOptions {
+                step_size: params::get(params, "step_size")?.unwrap_or(defaults.step_size),
+                goal_bias: params::get(params, "goal_bias")?.unwrap_or(defaults.goal_bias),
+                max_iterations: params::get(params, "max_iterations")?.unwrap_or(defaults.max_iterations),
+                rewire_radius: params::get(params, "rewire_radius")?,
+            },
+        };
+        planner.options.validate()?;
+        Ok(planner)
+    }
+}
+
+impl Algorithm for RrtPlanner {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::new();
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, memory: &mut MemoryManager) -> Result<(), CoreError> {
+        Context::with_default(|context| self.process_in_context(input, output, memory, context))
+    }
+    
+    fn process_in_context(
+        &self,
+        input: &[u8],
+        output: &mut Vec<u8>,
+        memory: &mut MemoryManager,
+        context: &mut Context<'_>,
+    ) -> Result<(), CoreError> {
+        let (start, goal) = endpoints(input)?;
+        let grid = self.grid.load(memory)?;
+        let waypoints = plan_rrt(&grid, [start[0], start[1]], [goal[0], goal[1]], &self.options, context.rng)?;
+        write_poses(waypoints, start, goal, output)
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        let setting = |name: &str, parameter_type: ParameterType, description: &str, default_value: Option<String>| {
+            ParameterDefinition {
+                name: name.to_string(),
+                parameter_type,
+                description: description.to_string(),
+                default_value,
+            }
+        };
+        let defaults = RrtOptions::default();
+        let mut parameters = GridSource::parameters();
+        parameters.extend([
+            setting(
+                "step_size",
+                ParameterType::Float,
+                "Longest edge added to the tree, in metres",
+                Some(defaults.step_size.to_string()),
+            ),
+            setting(
+                "goal_bias",
+                ParameterType::Float,
+                "Probability of growing towards the goal",
+                Some(defaults.goal_bias.to_string()),
+            ),
+            setting(
+                "max_iterations",
+                ParameterType::Integer,
+                "Samples drawn before giving up",
+                Some(defaults.max_iterations.to_string()),
+            ),
+            setting(
+                "rewire_radius",
+                ParameterType::Float,
+                "Neighbourhood radius in metres; set to plan by RRT*",
+                None,
+            ),
+        ]);
+        AlgorithmMetadata {
+            name: "RRT Planner".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Plans f32 (x, y, heading) waypoints between two poses on an occupancy grid by RRT or RRT*"
+                .to_string(),
+            parameters,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CoreEngine;
+    use serde_json::json;
+    
+    fn engine(seed: u64) -> CoreEngine {
+        let grid = json!({"grid": "map", "width": 20, "height": 20, "resolution": 0.5, "inflation": 0.5});
+        let mut rrt = grid.clone();
+        rrt["rewire_radius"] = json!(1.5);
+        rrt["max_iterations"] = json!(1500);
+        let mut engine = CoreEngine::builder().seed(seed).build();
+        engine.register(Box::new(AStarPlanner::from_params(&grid).unwrap())).unwrap();
+        engine.register(Box::new(RrtPlanner::from_params(&rrt).unwrap())).unwrap();
+        
+        // A wall along x = 5 m, open above y = 8 m
+        let mut map = OccupancyGrid2D::new(20, 20, 0.5, [0.0, 0.0]).unwrap();
+        map.insert_rectangle([5.0, 0.0], [5.4, 7.9]);
+        engine.memory_mut().write("map", &map.to_cells()).unwrap();
+        engine
+    }
+    
+    fn plan(engine: &mut CoreEngine, id: &str, poses: &[f32]) -> Result<Vec<f32>, CoreError> {
+        samples::f32_from_bytes(&engine.execute_algorithm(id, &samples::f32_to_bytes(poses))?)
+    }
+    
+    #[test]
+    fn test_planners_connect_poses_around_the_wall() {
+        let mut engine = engine(3);
+        let poses = [1.0, 1.0, 0.5, 9.0, 1.0, -1.0];
+        for id in [AStarPlanner::ID, RrtPlanner::ID] {
+            let path = plan(&mut engine, id, &poses).unwrap();
+            assert!(path.len() >= 9 && path.len().is_multiple_of(POSE_LEN), "{}: {:?}", id, path);
+            assert_eq!(&path[..3], &poses[..3]);
+            assert_eq!(&path[path.len() - 3..], &poses[3..]);
+            assert!(path.chunks(3).any(|pose| pose[1] > 8.0), "{}: {:?}", id, path);
+            // Interior waypoints head towards the next one
+            let (a, b) = (&path[3..6], &path[6..9]);
+            assert!((a[2] - (b[1] - a[1]).atan2(b[0] - a[0])).abs() < 1e-5);
+        }
+        assert_eq!(plan(&mut engine, RrtPlanner::ID, &poses), plan(&mut self::engine(3), RrtPlanner::ID, &poses));
+        
+        // Inflation makes the cells beside the wall unusable
+        assert!(matches!(
+            plan(&mut engine, AStarPlanner::ID, &[4.8, 1.0, 0.0, 9.0, 1.0, 0.0]),
+            Err(CoreError::InvalidInput(_))
+        ));
+        assert!(plan(&mut engine, AStarPlanner::ID, &poses[..3]).is_err());
+        
+        // Closing the gap leaves no path
+        let mut closed = OccupancyGrid2D::new(20, 20, 0.5, [0.0, 0.0]).unwrap();
+        closed.insert_rectangle([5.0, 0.0], [5.4, 10.0]);
+        engine.memory_mut().write("map", &closed.to_cells()).unwrap();
+        for id in [AStarPlanner::ID, RrtPlanner::ID] {
+            assert!(matches!(plan(&mut engine, id, &poses), Err(CoreError::ProcessingFailed(_))), "{}", id);
+        }
+        
+        assert!(AStarPlanner::from_params(&json!({"grid": "map", "width": 0, "height": 4, "resolution": 1.0})).is_err());
+        assert!(RrtPlanner::from_params(&json!({
+            "grid": "map", "width": 4, "height": 4, "resolution": 1.0, "step_size": -1.0,
+        }))
+        .is_err());
+    }
+}
@@ -0,0 +1,244 @@
+//! A* search over the cells of an occupancy grid
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use super::{OccupancyGrid2D, Point};
+use crate::error::CoreError;
+
+/// Moves to the 8 neighbouring cells, with their costs in cells
+const MOVES: [(isize, isize, f64); 8] = [
+    (1, 0, 1.0),
+    (-1, 0, 1.0),
+    (0, 1, 1.0),
+    (0, -1, 1.0),
+    (1, 1, std::f64::consts::SQRT_2),
+    (1, -1, std::f64::consts::SQRT_2),
+    (-1, 1, std::f64::consts::SQRT_2),
+    (-1, -1, std::f64::consts::SQRT_2),
+];
+
+/// Distance between cells over 8-connected moves with no obstacles, which
+/// never overestimates the true cost
+fn octile(a: (usize, usize), b: (usize, usize)) -> f64 {
+    let dx = a.0.abs_diff(b.0) as f64;
+    let dy = a.1.abs_diff(b.1) as f64;
+    dx.max(dy) + (std::f64::consts::SQRT_2 - 1.0) * dx.min(dy)
+}
+
+/// A cell waiting in the open set, ordered so the heap pops the lowest
+/// estimated total cost first
+struct Open {
+    estimate: f64,
+    cost: f64,
+    index: usize,
+}
+
+impl PartialEq for Open {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Open {}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Open {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Among equal estimates the cell furthest along is expanded first
+        other
+            .estimate
+            .total_cmp(&self.estimate)
+            .then_with(|| self.cost.total_cmp(&other.cost))
+    }
+}
+
+/// Free cells reachable from `(x, y)` in one move, with the move's cost
+///
+/// A diagonal move must not cut the corner of an occupied cell.
+pub(super) fn neighbours(grid: &OccupancyGrid2D, (x, y): (usize, usize)) -> impl Iterator<Item = ((usize, usize), f64)> + '_ {
+    MOVES.iter().filter_map(move |&(dx, dy, cost)| {
+        let next = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+        let corners_free = dx == 0 || dy == 0 || (!grid.is_occupied((next.0, y)) && !grid.is_occupied((x, next.1)));
+        (corners_free && !grid.is_occupied(next)).then_some((next, cost))
+    })
+}
+
+/// Shortest 8-connected path of free cells from `start` to `goal` and its
+/// length in cells, or `None` if `goal` cannot be reached
+fn search(grid: &OccupancyGrid2D, start: (usize, usize), goal: (usize, usize)) -> Option<(Vec<(usize, usize)>, f64)> {
+    let width = grid.width();
+    let index = |(x, y): (usize, usize)| y * width + x;
+    let mut costs = vec![f64::INFINITY; width * grid.height()];
+    let mut came_from = vec![usize::MAX; costs.len()];
+    let mut open = BinaryHeap::new();
+    costs[index(start)] = 0.0;
+    open.push(Open {
+        estimate: octile(start, goal),
+        cost: 0.0,
+        index: index(start),
+    });
+    while let Some(Open { cost, index: current, .. }) = open.pop() {
+        // A cell is pushed again whenever a cheaper path to it is found;
+        // only its cheapest entry is expanded
+        if cost > costs[current] {
+            continue;
+        }
+        let cell = (current % width, current / width);
+        if cell == goal {
+            let mut path = vec![cell];
+            let mut at = current;
+            while came_from[at] != usize::MAX {
+                at = came_from[at];
+                path.push((at % width, at / width));
+            }
+            path.reverse();
+            return Some((path, cost));
+        }
+        for (next, step) in neighbours(grid, cell) {
+            let next_cost = cost + step;
+            let next_index = index(next);
+            if next_cost < costs[next_index] {
+                costs[next_index] = next_cost;
+                came_from[next_index] = current;
+                open.push(Open {
+                    estimate: next_cost + octile(next, goal),
+                    cost: next_cost,
+                    index: next_index,
+                });
+            }
+        }
+    }
+    None
+}
+
+/// Cell of `point`, failing with `CoreError::InvalidInput` if it is outside
+/// the grid or occupied
+pub(super) fn free_cell(grid: &OccupancyGrid2D, point: Point, what: &str) -> Result<(usize, usize), CoreError> {
+    match grid.cell_of(point) {
+        Some(cell) if !grid.is_occupied(cell) => Ok(cell),
+        Some(_) => Err(CoreError::InvalidInput(format!("{} {:?} is in an occupied cell", what, point))),
+        None => Err(CoreError::InvalidInput(format!("{} {:?} is outside the grid", what, point))),
+    }
+}
+
+/// Shortest path from `start` to `goal` through the free cells of `grid`,
+/// or `None` if no path exists
+///
+/// The search moves between the 8 neighbours of each cell, never cutting
+/// the corner of an occupied one, and is guided by the octile distance so
+/// the path is a shortest one over those moves. The waypoints are `start`,
+/// the centre of every cell where the path turns, and `goal`. A start or
+/// goal outside the grid or in an occupied cell fails with
+/// `CoreError::InvalidInput`.
+pub fn plan_astar(grid: &OccupancyGrid2D, start: Point, goal: Point) -> Result<Option<Vec<Point>>, CoreError> {
+    let start_cell = free_cell(grid, start, "start")?;
+    let goal_cell = free_cell(grid, goal, "goal")?;
+    let Some((cells, _)) = search(grid, start_cell, goal_cell) else {
+        return Ok(None);
+    };
+    let direction = |a: (usize, usize), b: (usize, usize)| (b.0 as isize - a.0 as isize, b.1 as isize - a.1 as isize);
+    let mut waypoints = vec![start];
+    for turn in cells.windows(3) {
+        if direction(turn[0], turn[1]) != direction(turn[1], turn[2]) {
+            waypoints.push(grid.center_of(turn[1]));
+        }
+    }
+    waypoints.push(goal);
+    Ok(Some(waypoints))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::planning::path_length;
+    use std::time::Instant;
+    
+    /// A 10x10 grid of 1 m cells with a wall along x = 5 open only at the
+    /// top row
+    fn walled() -> OccupancyGrid2D {
+        let mut grid = OccupancyGrid2D::new(10, 10, 1.0, [0.0, 0.0]).unwrap();
+        grid.insert_rectangle([5.0, 0.0], [5.5, 8.5]);
+        grid
+    }
+    
+    /// Dijkstra's algorithm with the open set scanned linearly for its
+    /// cheapest cell, as a reference for `search`
+    fn naive(grid: &OccupancyGrid2D, start: (usize, usize), goal: (usize, usize)) -> Option<f64> {
+        let width = grid.width();
+        let mut costs = vec![f64::INFINITY; width * grid.height()];
+        let mut done = vec![false; costs.len()];
+        costs[start.1 * width + start.0] = 0.0;
+        loop {
+            let (current, &cost) = costs
+                .iter()
+                .enumerate()
+                .filter(|&(i, cost)| !done[i] && cost.is_finite())
+                .min_by(|a, b| a.1.total_cmp(b.1))?;
+            let cell = (current % width, current / width);
+            if cell == goal {
+                return Some(cost);
+            }
+            done[current] = true;
+            for (next, step) in neighbours(grid, cell) {
+                let next_index = next.1 * width + next.0;
+                costs[next_index] = costs[next_index].min(cost + step);
+            }
+        }
+    }
+    
+    #[test]
+    fn test_path_goes_around_the_wall() {
+        let grid = walled();
+        let path = plan_astar(&grid, [1.5, 1.5], [8.5, 1.5]).unwrap().unwrap();
+        assert_eq!(path.first(), Some(&[1.5, 1.5]));
+        assert_eq!(path.last(), Some(&[8.5, 1.5]));
+        assert!(path.windows(2).all(|pair| grid.is_segment_free(pair[0], pair[1])), "{:?}", path);
+        assert!(path.iter().any(|point| point[1] > 8.0));
+        
+        // Up to the gap, across it and back down, cutting diagonally where
+        // the wall allows
+        let (_, cost) = search(&grid, (1, 1), (8, 1)).unwrap();
+        assert!((cost - (5.0 * std::f64::consts::SQRT_2 + 13.0)).abs() < 1e-9, "{}", cost);
+        assert!((path_length(&path) - cost).abs() < 1e-9);
+        
+        let mut closed = walled();
+        closed.set_occupied((5, 9), true);
+        assert_eq!(plan_astar(&closed, [1.5, 1.5], [8.5, 1.5]).unwrap(), None);
+        assert!(matches!(plan_astar(&grid, [5.5, 1.5], [8.5, 1.5]), Err(CoreError::InvalidInput(_))));
+        assert!(matches!(plan_astar(&grid, [1.5, 1.5], [18.5, 1.5]), Err(CoreError::InvalidInput(_))));
+    }
+    
+    #[test]
+    fn test_astar_against_naive_dijkstra() {
+        // Benchmark-style: both searches must agree on the cost, while
+        // their timings are logged rather than asserted
+        let mut grid = OccupancyGrid2D::new(60, 60, 0.2, [0.0, 0.0]).unwrap();
+        for i in 0..5 {
+            let x = 1.5 + 2.0 * i as f64;
+            if i % 2 == 0 {
+                grid.insert_rectangle([x, 0.0], [x + 0.2, 10.0]);
+            } else {
+                grid.insert_rectangle([x, 2.0], [x + 0.2, 12.0]);
+            }
+        }
+        grid.insert_circle([6.0, 6.0], 0.4);
+        let (start, goal) = ((1, 55), (57, 2));
+        
+        let begin = Instant::now();
+        let (path, cost) = search(&grid, start, goal).unwrap();
+        let astar_time = begin.elapsed();
+        let begin = Instant::now();
+        let reference = naive(&grid, start, goal).unwrap();
+        log::info!("60x60 grid: A* {:?}, naive Dijkstra {:?}", astar_time, begin.elapsed());
+        
+        assert!((cost - reference).abs() < 1e-9, "A* {} vs Dijkstra {}", cost, reference);
+        assert_eq!((path[0], path[path.len() - 1]), (start, goal));
+        assert!(path.iter().all(|&cell| !grid.is_occupied(cell)));
+    }
+}
@@ -0,0 +1,339 @@
+//! Occupancy grids of free and occupied square cells
+
+use super::Point;
+use crate::error::CoreError;
+
+/// A `width x height` grid of square cells, each free or occupied
+///
+/// Cell `(x, y)` covers `resolution` metres from `origin + (x, y) *
+/// resolution` along each axis, so cell `(0, 0)` is the one in the grid's
+/// bottom-left corner. Anything outside the grid counts as occupied.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OccupancyGrid2D {
+    width: usize,
+    height: usize,
+    resolution: f64,
+    origin: Point,
+    // Row-major, `y * width + x`
+    occupied: Vec<bool>,
+}
+
+impl OccupancyGrid2D {
+    /// An all-free grid, failing with `CoreError::InvalidParameter` for an
+    /// empty grid or a resolution that is not positive and finite
+    pub fn new(width: usize, height: usize, resolution: f64, origin: Point) -> Result<Self, CoreError> {
+        if width == 0 || height == 0 {
+            return Err(CoreError::InvalidParameter(format!("grid must not be empty, got {}x{}", width, height)));
+        }
+        if !(resolution.is_finite() && resolution > 0.0) {
+            return Err(CoreError::InvalidParameter(format!(
+                "resolution must be positive and finite, got {}",
+                resolution
+            )));
+        }
+        if !origin.iter().all(|v| v.is_finite()) {
+            return Err(CoreError::InvalidParameter(format!("origin must be finite, got {:?}", origin)));
+        }
+        Ok(Self {
+            width,
+            height,
+            resolution,
+            origin,
+            occupied: vec![false; width * height],
+        })
+    }
+    
+    /// Grid from row-major cells, any non-zero byte being occupied
+    ///
+    /// Fails with `CoreError::SchemaMismatch` unless there are exactly
+    /// `width * height` cells.
+    pub fn from_cells(width: usize, height: usize, resolution: f64, origin: Point, cells: &[u8]) -> Result<Self, CoreError> {
+        let mut grid = Self::new(width, height, resolution, origin)?;
+        if cells.len() != grid.occupied.len() {
+            return Err(CoreError::SchemaMismatch(format!(
+                "{} cells do not fill a {}x{} grid",
+                cells.len(),
+                width,
+                height
+            )));
+        }
+        for (occupied, &cell) in grid.occupied.iter_mut().zip(cells) {
+            *occupied = cell != 0;
+        }
+        Ok(grid)
+    }
+    
+    /// Row-major cells, 1 for occupied and 0 for free
+    pub fn to_cells(&self) -> Vec<u8> {
+        self.occupied.iter().map(|&occupied| u8::from(occupied)).collect()
+    }
+    
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    
+    pub fn resolution(&self) -> f64 {
+        self.resolution
+    }
+    
+    pub fn origin(&self) -> Point {
+        self.origin
+    }
+    
+    /// Corner of the grid opposite `origin`
+    pub fn extent(&self) -> Point {
+        [
+            self.origin[0] + self.width as f64 * self.resolution,
+            self.origin[1] + self.height as f64 * self.resolution,
+        ]
+    }
+    
+    /// Position in cell units, which may lie outside the grid
+    fn to_grid(&self, point: Point) -> Point {
+        [
+            (point[0] - self.origin[0]) / self.resolution,
+            (point[1] - self.origin[1]) / self.resolution,
+        ]
+    }
+    
+    fn to_world(&self, point: Point) -> Point {
+        [
+            self.origin[0] + point[0] * self.resolution,
+            self.origin[1] + point[1] * self.resolution,
+        ]
+    }
+    
+    /// Cell containing `point`, or `None` outside the grid
+    pub fn cell_of(&self, point: Point) -> Option<(usize, usize)> {
+        let [x, y] = self.to_grid(point).map(f64::floor);
+        let inside = x >= 0.0 && y >= 0.0 && x < self.width as f64 && y < self.height as f64;
+        inside.then_some((x as usize, y as usize))
+    }
+    
+    /// Centre of cell `(x, y)`
+    pub fn center_of(&self, (x, y): (usize, usize)) -> Point {
+        self.to_world([x as f64 + 0.5, y as f64 + 0.5])
+    }
+    
+    /// Whether cell `(x, y)` is occupied or outside the grid
+    pub fn is_occupied(&self, (x, y): (usize, usize)) -> bool {
+        x >= self.width || y >= self.height || self.occupied[y * self.width + x]
+    }
+    
+    /// `is_occupied` for cells addressed with signed coordinates
+    fn blocked(&self, [x, y]: [i64; 2]) -> bool {
+        match (usize::try_from(x), usize::try_from(y)) {
+            (Ok(x), Ok(y)) => self.is_occupied((x, y)),
+            _ => true,
+        }
+    }
+    
+    /// Whether `point` lies in a free cell of the grid
+    pub fn is_free_at(&self, point: Point) -> bool {
+        self.cell_of(point).is_some_and(|cell| !self.is_occupied(cell))
+    }
+    
+    /// Mark cell `(x, y)`; cells outside the grid are ignored
+    pub fn set_occupied(&mut self, (x, y): (usize, usize), occupied: bool) {
+        if x < self.width && y < self.height {
+            self.occupied[y * self.width + x] = occupied;
+        }
+    }
+    
+    /// Occupy the cell containing `point`
+    pub fn insert_point(&mut self, point: Point) {
+        if let Some(cell) = self.cell_of(point) {
+            self.set_occupied(cell, true);
+        }
+    }
+    
+    /// Inclusive range of cells along one axis overlapping `[low, high]`
+    /// in cell units, clipped to `0..len`
+    fn span(low: f64, high: f64, len: usize) -> Option<(usize, usize)> {
+        if high < 0.0 || low >= len as f64 || low > high {
+            return None;
+        }
+        let first = low.max(0.0).floor() as usize;
+        let last = (high.floor() as usize).min(len - 1);
+        Some((first, last))
+    }
+    
+    /// Occupy every cell overlapping the axis-aligned rectangle between
+    /// corners `min` and `max`
+    pub fn insert_rectangle(&mut self, min: Point, max: Point) {
+        let (low, high) = (self.to_grid(min), self.to_grid(max));
+        let (Some((x0, x1)), Some((y0, y1))) = (
+            Self::span(low[0], high[0], self.width),
+            Self::span(low[1], high[1], self.height),
+        ) else {
+            return;
+        };
+        for y in y0..=y1 {
+            self.occupied[y * self.width + x0..=y * self.width + x1].fill(true);
+        }
+    }
+    
+    /// Occupy the cell containing `center` and every cell whose centre is
+    /// within `radius` of it
+    pub fn insert_circle(&mut self, center: Point, radius: f64) {
+        self.insert_point(center);
+        let [cx, cy] = self.to_grid(center);
+        let r = radius / self.resolution;
+        let (Some((x0, x1)), Some((y0, y1))) = (
+            Self::span(cx - r - 0.5, cx + r - 0.5, self.width),
+            Self::span(cy - r - 0.5, cy + r - 0.5, self.height),
+        ) else {
+            return;
+        };
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                if (x as f64 + 0.5 - cx).hypot(y as f64 + 0.5 - cy) <= r {
+                    self.occupied[y * self.width + x] = true;
+                }
+            }
+        }
+    }
+    
+    /// Copy of the grid with every obstacle grown by `radius` metres
+    ///
+    /// A cell becomes occupied when its centre is within `radius` of an
+    /// occupied cell's centre. Planning on the inflated grid keeps a robot
+    /// of that radius clear of obstacles while treating it as a point.
+    pub fn inflate(&self, radius: f64) -> Self {
+        let r = (radius / self.resolution).max(0.0);
+        let reach = r.floor() as i64;
+        let offsets: Vec<(i64, i64)> = (-reach..=reach)
+            .flat_map(|dy| (-reach..=reach).map(move |dx| (dx, dy)))
+            .filter(|&(dx, dy)| ((dx * dx + dy * dy) as f64).sqrt() <= r)
+            .collect();
+        let mut inflated = self.clone();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if !self.occupied[y * self.width + x] {
+                    continue;
+                }
+                for &(dx, dy) in &offsets {
+                    if let (Some(nx), Some(ny)) = (x.checked_add_signed(dx as isize), y.checked_add_signed(dy as isize)) {
+                        inflated.set_occupied((nx, ny), true);
+                    }
+                }
+            }
+        }
+        inflated
+    }
+    
+    /// First point along the segment from `from` to `to` that lies in an
+    /// occupied cell or outside the grid, or `None` if the whole segment is
+    /// free
+    ///
+    /// Walks every cell the segment passes through (Amanatides-Woo), so
+    /// thin obstacles are never stepped over. A segment through the exact
+    /// corner of a cell also checks one of the two cells beside the corner.
+    pub fn raycast(&self, from: Point, to: Point) -> Option<Point> {
+        let start = self.to_grid(from);
+        let end = self.to_grid(to);
+        let delta = [end[0] - start[0], end[1] - start[1]];
+        let mut cell = start.map(|v| v.floor() as i64);
+        let last = end.map(|v| v.floor() as i64);
+        let step = delta.map(|d| if d > 0.0 { 1 } else if d < 0.0 { -1 } else { 0 });
+        // Ray parameter at which the next cell boundary is crossed on each
+        // axis, and the parameter spanned by one cell
+        let mut t_next = [0, 1].map(|axis| match step[axis] {
+            1 => (cell[axis] as f64 + 1.0 - start[axis]) / delta[axis],
+            -1 => (cell[axis] as f64 - start[axis]) / delta[axis],
+            _ => f64::INFINITY,
+        });
+        let t_cell = delta.map(|d| if d == 0.0 { f64::INFINITY } else { 1.0 / d.abs() });
+        let mut t = 0.0;
+        loop {
+            if self.blocked(cell) {
+                return Some(self.to_world([start[0] + delta[0] * t, start[1] + delta[1] * t]));
+            }
+            if cell == last {
+                return None;
+            }
+            let axis = if t_next[0] < t_next[1] { 0 } else { 1 };
+            t = t_next[axis];
+            // Rounding can carry the walk past the end cell
+            if t > 1.0 {
+                return None;
+            }
+            cell[axis] += step[axis];
+            t_next[axis] += t_cell[axis];
+        }
+    }
+    
+    /// Whether the segment from `from` to `to` crosses only free cells
+    pub fn is_segment_free(&self, from: Point, to: Point) -> bool {
+        self.raycast(from, to).is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn grid() -> OccupancyGrid2D {
+        OccupancyGrid2D::new(10, 8, 0.5, [-1.0, 2.0]).unwrap()
+    }
+    
+    #[test]
+    fn test_obstacles_and_inflation() {
+        let mut grid = grid();
+        assert_eq!(grid.cell_of([-1.0, 2.0]), Some((0, 0)));
+        assert_eq!(grid.cell_of([3.9, 5.9]), Some((9, 7)));
+        assert_eq!(grid.cell_of([4.0, 3.0]), None);
+        assert_eq!(grid.center_of((1, 2)), [-0.25, 3.25]);
+        
+        grid.insert_rectangle([0.0, 3.0], [0.9, 3.4]);
+        let occupied: Vec<(usize, usize)> = (0..8)
+            .flat_map(|y| (0..10).map(move |x| (x, y)))
+            .filter(|&cell| grid.is_occupied(cell))
+            .collect();
+        assert_eq!(occupied, vec![(2, 2), (3, 2)]);
+        
+        // Growing one cell by a cell's width reaches its four neighbours
+        let mut single = self::grid();
+        single.insert_point([1.2, 4.2]);
+        let inflated = single.inflate(0.5);
+        assert_eq!(inflated.to_cells().iter().filter(|&&cell| cell == 1).count(), 5);
+        assert!(inflated.is_occupied((3, 4)) && inflated.is_occupied((4, 5)) && !inflated.is_occupied((5, 5)));
+        assert_eq!(single.inflate(0.0), single);
+        
+        let mut circle = self::grid();
+        circle.insert_circle([1.5, 4.5], 0.5);
+        assert_eq!(circle.to_cells().iter().filter(|&&cell| cell == 1).count(), 4);
+        
+        assert!(grid.is_occupied((10, 0)));
+        assert!(OccupancyGrid2D::new(0, 4, 1.0, [0.0, 0.0]).is_err());
+        assert!(OccupancyGrid2D::new(4, 4, 0.0, [0.0, 0.0]).is_err());
+        assert!(matches!(
+            OccupancyGrid2D::from_cells(2, 2, 1.0, [0.0, 0.0], &[0, 1, 0]),
+            Err(CoreError::SchemaMismatch(_))
+        ));
+    }
+    
+    #[test]
+    fn test_raycast_stops_at_first_obstacle() {
+        let mut grid = OccupancyGrid2D::new(10, 10, 1.0, [0.0, 0.0]).unwrap();
+        grid.set_occupied((5, 2), true);
+        
+        let hits = |from: Point, to: Point, expected: Point| {
+            let hit = grid.raycast(from, to).unwrap();
+            assert!((hit[0] - expected[0]).hypot(hit[1] - expected[1]) < 1e-9, "{:?}", hit);
+        };
+        hits([0.5, 2.5], [9.5, 2.5], [5.0, 2.5]);
+        hits([9.5, 2.5], [0.5, 2.5], [6.0, 2.5]);
+        assert!(grid.is_segment_free([0.5, 0.5], [9.5, 1.5]));
+        assert!(!grid.is_segment_free([0.5, 0.5], [9.5, 4.5]));
+        
+        // Starting inside an obstacle, and leaving the grid
+        hits([5.5, 2.5], [5.5, 8.5], [5.5, 2.5]);
+        hits([8.5, 8.5], [12.0, 8.5], [10.0, 8.5]);
+        assert!(grid.is_segment_free([3.0, 3.0], [3.0, 3.0]));
+    }
+}
@@ -0,0 +1,30 @@
+//! Path planning over 2D occupancy grids
+//!
+//! An `OccupancyGrid2D` divides the plane into square cells that are free
+//! or occupied. `plan_astar` finds the shortest 8-connected path through
+//! its cells, and `plan_rrt` grows a random tree through free space,
+//! rewiring it towards shorter paths as RRT* when given a radius.
+//! `AStarPlanner` and `RrtPlanner` wrap them as built-ins that read the
+//! grid from a memory region.
+
+mod algorithms;
+mod astar;
+mod grid;
+mod rrt;
+
+pub use algorithms::{AStarPlanner, RrtPlanner};
+pub use astar::plan_astar;
+pub use grid::OccupancyGrid2D;
+pub use rrt::{plan_rrt, RrtOptions};
+
+/// A position in world coordinates, in metres
+pub type Point = [f64; 2];
+
+fn distance(a: Point, b: Point) -> f64 {
+    (b[0] - a[0]).hypot(b[1] - a[1])
+}
+
+/// Total length of the polyline through `waypoints`
+pub fn path_length(waypoints: &[Point]) -> f64 {
+    waypoints.windows(2).map(|pair| distance(pair[0], pair[1])).sum()
+}
@@ -0,0 +1,278 @@
+//! Rapidly-exploring random trees through the free space of a grid
+
+use super::astar::free_cell;
+use super::{distance, OccupancyGrid2D, Point};
+use crate::algorithm::context::RngCore;
+use crate::error::CoreError;
+
+/// Settings of `plan_rrt`
+#[derive(Clone, Debug, PartialEq)]
+pub struct RrtOptions {
+    /// Longest edge added to the tree, in metres
+    pub step_size: f64,
+    /// Probability of growing towards the goal instead of a random point
+    pub goal_bias: f64,
+    /// Samples drawn before giving up
+    pub max_iterations: usize,
+    /// Radius in metres within which RRT* picks the cheapest parent for
+    /// each new node and reroutes its neighbours through it, or `None` for
+    /// plain RRT
+    pub rewire_radius: Option<f64>,
+}
+
+impl Default for RrtOptions {
+    fn default() -> Self {
+        Self {
+            step_size: 0.5,
+            goal_bias: 0.05,
+            max_iterations: 5000,
+            rewire_radius: None,
+        }
+    }
+}
+
+impl RrtOptions {
+    pub(super) fn validate(&self) -> Result<(), CoreError> {
+        if !(self.step_size.is_finite() && self.step_size > 0.0) {
+            return Err(CoreError::InvalidParameter(format!(
+                "step_size must be positive and finite, got {}",
+                self.step_size
+            )));
+        }
+        if !(0.0..=1.0).contains(&self.goal_bias) {
+            return Err(CoreError::InvalidParameter(format!(
+                "goal_bias must be within [0, 1], got {}",
+                self.goal_bias
+            )));
+        }
+        if let Some(radius) = self.rewire_radius.filter(|radius| !(radius.is_finite() && *radius > 0.0)) {
+            return Err(CoreError::InvalidParameter(format!(
+                "rewire_radius must be positive and finite, got {}",
+                radius
+            )));
+        }
+        Ok(())
+    }
+}
+
+struct Node {
+    point: Point,
+    parent: usize,
+    // Length of the tree path from the root
+    cost: f64,
+    children: Vec<usize>,
+}
+
+/// Uniform sample in `[0, 1)`
+fn unit(rng: &mut dyn RngCore) -> f64 {
+    // The top 53 bits fill an f64 mantissa exactly
+    (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Move `node` under `parent`, updating the cost of its whole subtree
+fn reparent(nodes: &mut [Node], node: usize, parent: usize, cost: f64) {
+    let old_parent = nodes[node].parent;
+    nodes[old_parent].children.retain(|&child| child != node);
+    nodes[parent].children.push(node);
+    nodes[node].parent = parent;
+    let saving = nodes[node].cost - cost;
+    let mut stack = vec![node];
+    while let Some(index) = stack.pop() {
+        nodes[index].cost -= saving;
+        stack.extend_from_slice(&nodes[index].children);
+    }
+}
+
+/// Path from `start` to `goal` through the free cells of `grid`, found by
+/// growing a rapidly-exploring random tree, or `None` if the tree does not
+/// reach the goal within `options.max_iterations` samples
+///
+/// Each sample is a uniform point of the grid, or the goal with probability
+/// `goal_bias`; the nearest node grows towards it by at most `step_size`
+/// when the segment is free. Plain RRT returns the first path found. With a
+/// `rewire_radius` it runs as RRT*, spending every iteration improving the
+/// tree and returning the shortest path to the goal, which approaches the
+/// optimum as iterations grow. A start and goal in sight of each other give
+/// the straight segment. Randomness comes only from `rng`, so a seeded
+/// generator plans the same path every time. A start or goal outside the
+/// grid or in an occupied cell fails with `CoreError::InvalidInput`.
+pub fn plan_rrt(
+    grid: &OccupancyGrid2D,
+    start: Point,
+    goal: Point,
+    options: &RrtOptions,
+    rng: &mut dyn RngCore,
+) -> Result<Option<Vec<Point>>, CoreError> {
+    options.validate()?;
+    free_cell(grid, start, "start")?;
+    free_cell(grid, goal, "goal")?;
+    if grid.is_segment_free(start, goal) {
+        return Ok(Some(vec![start, goal]));
+    }
+    
+    let (low, high) = (grid.origin(), grid.extent());
+    let mut nodes = vec![Node {
+        point: start,
+        parent: 0,
+        cost: 0.0,
+        children: Vec::new(),
+    }];
+    // Nodes with a free segment to the goal
+    let mut reaching_goal = Vec::new();
+    for _ in 0..options.max_iterations {
+        let sample = if unit(rng) < options.goal_bias {
+            goal
+        } else {
+            [
+                low[0] + unit(rng) * (high[0] - low[0]),
+                low[1] + unit(rng) * (high[1] - low[1]),
+            ]
+        };
+        let Some(nearest) = (0..nodes.len()).min_by(|&a, &b| {
+            distance(nodes[a].point, sample).total_cmp(&distance(nodes[b].point, sample))
+        }) else {
+            break;
+        };
+        let from = nodes[nearest].point;
+        let gap = distance(from, sample);
+        if gap == 0.0 {
+            continue;
+        }
+        let reach = (options.step_size / gap).min(1.0);
+        let point = [from[0] + (sample[0] - from[0]) * reach, from[1] + (sample[1] - from[1]) * reach];
+        if !grid.is_segment_free(from, point) {
+            continue;
+        }
+        
+        let mut parent = nearest;
+        let mut cost = nodes[nearest].cost + distance(from, point);
+        let neighbours: Vec<usize> = match options.rewire_radius {
+            Some(radius) => (0..nodes.len())
+                .filter(|&i| i != nearest && distance(nodes[i].point, point) <= radius)
+                .filter(|&i| grid.is_segment_free(nodes[i].point, point))
+                .collect(),
+            None => Vec::new(),
+        };
+        for &neighbour in &neighbours {
+            let through = nodes[neighbour].cost + distance(nodes[neighbour].point, point);
+            if through < cost {
+                parent = neighbour;
+                cost = through;
+            }
+        }
+        let index = nodes.len();
+        nodes.push(Node {
+            point,
+            parent,
+            cost,
+            children: Vec::new(),
+        });
+        nodes[parent].children.push(index);
+        for neighbour in neighbours {
+            let through = cost + distance(point, nodes[neighbour].point);
+            // The margin keeps rounding from reparenting a node onto its
+            // own descendant
+            if neighbour != parent && through + 1e-9 < nodes[neighbour].cost {
+                reparent(&mut nodes, neighbour, index, through);
+            }
+        }
+        
+        if distance(point, goal) <= options.step_size && grid.is_segment_free(point, goal) {
+            reaching_goal.push(index);
+            if options.rewire_radius.is_none() {
+                break;
+            }
+        }
+    }
+    
+    let length_through = |node: usize| nodes[node].cost + distance(nodes[node].point, goal);
+    let Some(last) = reaching_goal.into_iter().min_by(|&a, &b| length_through(a).total_cmp(&length_through(b))) else {
+        return Ok(None);
+    };
+    // A node grown onto the goal ends the path itself
+    let mut path = if nodes[last].point == goal { Vec::new() } else { vec![goal] };
+    let mut at = last;
+    loop {
+        path.push(nodes[at].point);
+        if at == 0 {
+            break;
+        }
+        at = nodes[at].parent;
+    }
+    path.reverse();
+    Ok(Some(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::context::SeededRng;
+    use crate::algorithm::planning::{path_length, plan_astar};
+    
+    /// A 10 m square with a wall along x = 5 open only above y = 8
+    fn walled() -> OccupancyGrid2D {
+        let mut grid = OccupancyGrid2D::new(50, 50, 0.2, [0.0, 0.0]).unwrap();
+        grid.insert_rectangle([4.8, 0.0], [5.2, 7.9]);
+        grid
+    }
+    
+    fn plan(options: &RrtOptions, seed: u64) -> Option<Vec<Point>> {
+        plan_rrt(&walled(), [1.0, 1.0], [9.0, 1.0], options, &mut SeededRng::new(seed)).unwrap()
+    }
+    
+    #[test]
+    fn test_rrt_and_rrt_star_find_free_paths() {
+        let grid = walled();
+        let rrt = RrtOptions::default();
+        let star = RrtOptions {
+            rewire_radius: Some(1.5),
+            max_iterations: 3000,
+            ..RrtOptions::default()
+        };
+        let shortest = path_length(&plan_astar(&grid, [1.0, 1.0], [9.0, 1.0]).unwrap().unwrap());
+        let rrt_path = plan(&rrt, 7).unwrap();
+        let star_path = plan(&star, 7).unwrap();
+        for path in [&rrt_path, &star_path] {
+            assert_eq!((path[0], path[path.len() - 1]), ([1.0, 1.0], [9.0, 1.0]));
+            assert!(path.windows(2).all(|pair| grid.is_segment_free(pair[0], pair[1])));
+        }
+        assert!(rrt_path.windows(2).all(|pair| distance(pair[0], pair[1]) <= rrt.step_size + 1e-9));
+        // The same seed plans the same path
+        assert_eq!(plan(&rrt, 7), Some(rrt_path.clone()));
+        
+        // RRT* spends its iterations shortening the path towards the
+        // optimum, which A* bounds from above on this grid
+        let (rrt_length, star_length) = (path_length(&rrt_path), path_length(&star_path));
+        log::info!("RRT {:.2} m, RRT* {:.2} m, A* {:.2} m", rrt_length, star_length, shortest);
+        assert!(star_length < rrt_length && star_length < shortest * 1.15, "{} vs {}", star_length, shortest);
+    }
+    
+    #[test]
+    fn test_unreachable_goal_and_bad_options() {
+        let mut closed = walled();
+        closed.insert_rectangle([4.8, 7.9], [5.2, 10.0]);
+        let options = RrtOptions {
+            max_iterations: 500,
+            ..RrtOptions::default()
+        };
+        let mut rng = SeededRng::new(1);
+        assert_eq!(plan_rrt(&closed, [1.0, 1.0], [9.0, 1.0], &options, &mut rng).unwrap(), None);
+        
+        // In sight of the start, the goal is reached without sampling
+        let path = plan_rrt(&closed, [1.0, 1.0], [4.0, 9.0], &options, &mut rng).unwrap();
+        assert_eq!(path, Some(vec![[1.0, 1.0], [4.0, 9.0]]));
+        
+        let bad = RrtOptions {
+            goal_bias: 1.5,
+            ..RrtOptions::default()
+        };
+        assert!(matches!(
+            plan_rrt(&closed, [1.0, 1.0], [9.0, 1.0], &bad, &mut rng),
+            Err(CoreError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            plan_rrt(&closed, [5.0, 1.0], [9.0, 1.0], &options, &mut rng),
+            Err(CoreError::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,138 @@
+//! Sampling live traffic into regression fixtures
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard};
+
+use super::context::Context;
+use super::{Algorithm, AlgorithmMetadata};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// Wraps an algorithm and keeps a copy of every `every`th call's input and
+/// output
+///
+/// Calls are counted from the first, whether or not they succeed, and the
+/// sampled ones are kept only if they succeed. Once `capacity` pairs are
+/// held the oldest is dropped for each new one, so the buffer holds the
+/// most recent samples. Outputs are passed through unchanged.
+pub struct RecordingAlgorithm {
+    inner: Box<dyn Algorithm>,
+    every: usize,
+    capacity: usize,
+    recording: Mutex<Recording>,
+}
+
+#[derive(Default)]
+struct Recording {
+    calls: usize,
+    pairs: VecDeque<(Vec<u8>, Vec<u8>)>,
+}
+
+impl RecordingAlgorithm {
+    /// Record every `every`th call of `inner`, keeping at most `capacity`
+    /// pairs
+    pub fn new(inner: Box<dyn Algorithm>, every: usize, capacity: usize) -> Result<Self, CoreError> {
+        if every == 0 {
+            return Err(CoreError::InvalidParameter("sampling interval must be positive".to_string()));
+        }
+        Ok(Self {
+            inner,
+            every,
+            capacity,
+            recording: Mutex::new(Recording::default()),
+        })
+    }
+    
+    /// The recorded `(input, output)` pairs, oldest first
+    pub fn captured_pairs(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.lock().pairs.iter().cloned().collect()
+    }
+    
+    /// Drop the recorded pairs; the call count carries on
+    pub fn clear(&self) {
+        self.lock().pairs.clear();
+    }
+    
+    fn lock(&self) -> MutexGuard<'_, Recording> {
+        self.recording.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Algorithm for RecordingAlgorithm {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::new();
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, memory: &mut MemoryManager) -> Result<(), CoreError> {
+        Context::with_default(|context| self.process_in_context(input, output, memory, context))
+    }
+    
+    fn process_in_context(
+        &self,
+        input: &[u8],
+        output: &mut Vec<u8>,
+        memory: &mut MemoryManager,
+        context: &mut Context<'_>,
+    ) -> Result<(), CoreError> {
+        let sampled = {
+            let mut recording = self.lock();
+            let call = recording.calls;
+            recording.calls += 1;
+            call.is_multiple_of(self.every)
+        };
+        let start = output.len();
+        self.inner.process_in_context(input, output, memory, context)?;
+        if sampled && self.capacity > 0 {
+            let mut recording = self.lock();
+            if recording.pairs.len() == self.capacity {
+                recording.pairs.pop_front();
+            }
+            recording.pairs.push_back((input.to_vec(), output[start..].to_vec()));
+        }
+        Ok(())
+    }
+    
+    fn output_attributes(&self, input: &[u8], output: &[u8], memory: &MemoryManager) -> HashMap<String, String> {
+        self.inner.output_attributes(input, output, memory)
+    }
+    
+    fn id(&self) -> &str {
+        self.inner.id()
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        self.inner.metadata()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::builtins::Scale;
+    use crate::algorithm::samples;
+    
+    #[test]
+    fn test_captures_sampled_calls_with_identical_outputs() {
+        let plain = Scale::new(2.0).unwrap();
+        let recording = RecordingAlgorithm::new(Box::new(Scale::new(2.0).unwrap()), 3, 2).unwrap();
+        let mut memory = MemoryManager::new();
+        
+        let mut expected = Vec::new();
+        for i in 0..10 {
+            let input = samples::f32_to_bytes(&[i as f32]);
+            let output = recording.process(&input, &mut memory).unwrap();
+            assert_eq!(output, plain.process(&input, &mut memory).unwrap());
+            if i % 3 == 0 {
+                expected.push((input, output));
+            }
+        }
+        // Calls 0, 3, 6 and 9 were sampled; the cap keeps the last two
+        assert_eq!(recording.captured_pairs(), expected[2..]);
+        
+        recording.clear();
+        assert!(recording.captured_pairs().is_empty());
+        assert!(RecordingAlgorithm::new(Box::new(plain), 0, 2).is_err());
+    }
+}
@@ -0,0 +1,336 @@
+//! Registry of algorithm instances available to the engine
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use super::{Algorithm, AlgorithmMetadata};
+use crate::error::CoreError;
+use crate::CoreEngine;
+
+/// Algorithms registered by ID
+///
+/// Instances are shared behind `Arc` so one registration can serve
+/// concurrent executions.
+#[derive(Clone, Default)]
+pub struct AlgorithmRegistry {
+    algorithms: HashMap<String, Arc<dyn Algorithm>>,
+    // Algorithms serving in place of a failed primary, by primary ID
+    fallbacks: HashMap<String, Arc<dyn Algorithm>>,
+}
+
+impl AlgorithmRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Register an algorithm under its own ID, rejecting duplicates and
+    /// algorithms needing features this build lacks
+    pub fn register(&mut self, algorithm: Box<dyn Algorithm>) -> Result<(), CoreError> {
+        let id = algorithm.id().to_string();
+        self.insert(id, Arc::from(algorithm))
+    }
+    
+    /// Register an algorithm under `id` instead of its own ID
+    pub fn register_as(&mut self, id: &str, algorithm: Box<dyn Algorithm>) -> Result<(), CoreError> {
+        self.insert(id.to_string(), Arc::from(algorithm))
+    }
+    
+    /// Register a primary algorithm under `id` with a fallback that serves
+    /// whenever the primary fails
+    pub fn register_with_fallback(
+        &mut self,
+        id: &str,
+        primary: Box<dyn Algorithm>,
+        fallback: Box<dyn Algorithm>,
+    ) -> Result<(), CoreError> {
+        check_capabilities(fallback.as_ref())?;
+        self.insert(id.to_string(), Arc::from(primary))?;
+        self.fallbacks.insert(id.to_string(), Arc::from(fallback));
+        Ok(())
+    }
+    
+    fn insert(&mut self, id: String, algorithm: Arc<dyn Algorithm>) -> Result<(), CoreError> {
+        if self.algorithms.contains_key(&id) {
+            return Err(CoreError::DuplicateAlgorithm(id));
+        }
+        check_capabilities(algorithm.as_ref())?;
+        self.algorithms.insert(id, algorithm);
+        Ok(())
+    }
+    
+    /// Remove an algorithm and any fallback registered for it, returning
+    /// the algorithm
+    ///
+    /// Executions already holding the instance finish with it. Fails with
+    /// `CoreError::AlgorithmNotFound` if nothing is registered under
+    /// `algorithm_id`.
+    pub fn unregister(&mut self, algorithm_id: &str) -> Result<Arc<dyn Algorithm>, CoreError> {
+        let algorithm = self
+            .algorithms
+            .remove(algorithm_id)
+            .ok_or_else(|| CoreError::AlgorithmNotFound(algorithm_id.to_string()))?;
+        self.fallbacks.remove(algorithm_id);
+        Ok(algorithm)
+    }
+    
+    /// Registered IDs with their algorithms' metadata, in ID order
+    pub fn list(&self) -> Vec<(String, AlgorithmMetadata)> {
+        let mut entries: Vec<_> = self
+            .algorithms
+            .iter()
+            .map(|(id, algorithm)| (id.clone(), algorithm.metadata()))
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries
+    }
+    
+    /// Look up a registered algorithm
+    pub fn get(&self, algorithm_id: &str) -> Option<Arc<dyn Algorithm>> {
+        self.algorithms.get(algorithm_id).cloned()
+    }
+    
+    /// Look up the fallback registered for an algorithm, if any
+    pub fn fallback(&self, algorithm_id: &str) -> Option<Arc<dyn Algorithm>> {
+        self.fallbacks.get(algorithm_id).cloned()
+    }
+    
+    /// Every registered algorithm and fallback, in no particular order
+    pub(crate) fn instances(&self) -> impl Iterator<Item = &Arc<dyn Algorithm>> {
+        self.algorithms.values().chain(self.fallbacks.values())
+    }
+    
+    /// Capture the metadata of everything registered, for provenance
+    pub fn export(&self) -> RegistryManifest {
+        let metadata = |algorithms: &HashMap<String, Arc<dyn Algorithm>>| {
+            algorithms
+                .iter()
+                .map(|(id, algorithm)| (id.clone(), algorithm.metadata()))
+                .collect()
+        };
+        RegistryManifest {
+            algorithms: metadata(&self.algorithms),
+            fallbacks: metadata(&self.fallbacks),
+        }
+    }
+}
+
+/// Serializable record of a registry's contents
+///
+/// Entries are keyed by registered ID and kept sorted, so two manifests of
+/// the same registrations serialize identically.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RegistryManifest {
+    /// Metadata of each registered algorithm
+    pub algorithms: BTreeMap<String, AlgorithmMetadata>,
+    /// Metadata of each fallback, under its primary's ID
+    #[serde(default)]
+    pub fallbacks: BTreeMap<String, AlgorithmMetadata>,
+}
+
+/// An algorithm registered in both manifests with different versions
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionChange {
+    pub id: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// Differences in registered algorithms between two manifests
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryDiff {
+    /// IDs registered only in the second manifest
+    pub added: Vec<String>,
+    /// IDs registered only in the first manifest
+    pub removed: Vec<String>,
+    /// IDs whose version differs, in ID order
+    pub version_changed: Vec<VersionChange>,
+}
+
+impl RegistryDiff {
+    /// Whether the manifests register the same IDs at the same versions
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.version_changed.is_empty()
+    }
+}
+
+/// Compare the algorithms registered in `a` against those in `b`
+pub fn diff_manifests(a: &RegistryManifest, b: &RegistryManifest) -> RegistryDiff {
+    let mut diff = RegistryDiff::default();
+    for (id, before) in &a.algorithms {
+        match b.algorithms.get(id) {
+            None => diff.removed.push(id.clone()),
+            Some(after) if after.version != before.version => diff.version_changed.push(VersionChange {
+                id: id.clone(),
+                from: before.version.clone(),
+                to: after.version.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    diff.added = b
+        .algorithms
+        .keys()
+        .filter(|id| !a.algorithms.contains_key(*id))
+        .cloned()
+        .collect();
+    diff
+}
+
+/// Reject an algorithm needing features this build lacks
+fn check_capabilities(algorithm: &dyn Algorithm) -> Result<(), CoreError> {
+    let missing = CoreEngine::capabilities().missing(&algorithm.required_capabilities());
+    if missing.is_empty() {
+        return Ok(());
+    }
+    Err(CoreError::UnsupportedCapability(format!(
+        "'{}' needs features not compiled in: {}",
+        algorithm.id(),
+        missing.join(", ")
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::builtins::{PassThrough, Scale};
+    use crate::memory::MemoryManager;
+    
+    /// Pass-through reporting a configurable version
+    struct Versioned(&'static str);
+    
+    impl Algorithm for Versioned {
+        fn process(&self, input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            Ok(input.to_vec())
+        }
+        
+        fn id(&self) -> &str {
+            "filter"
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata {
+                name: "Filter".to_string(),
+                version: self.0.to_string(),
+                ..Default::default()
+            }
+        }
+    }
+    
+    /// Pass-through requiring the given capabilities
+    struct Needs(crate::Capabilities);
+    
+    impl Algorithm for Needs {
+        fn process(&self, input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            Ok(input.to_vec())
+        }
+        
+        fn id(&self) -> &str {
+            "needs"
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata::default()
+        }
+        
+        fn required_capabilities(&self) -> crate::Capabilities {
+            self.0
+        }
+    }
+    
+    fn manifest(filter_version: &'static str, with_scale: bool) -> RegistryManifest {
+        let mut registry = AlgorithmRegistry::new();
+        registry.register(Box::new(PassThrough)).unwrap();
+        registry.register(Box::new(Versioned(filter_version))).unwrap();
+        if with_scale {
+            registry.register(Box::new(Scale::new(2.0).unwrap())).unwrap();
+        }
+        registry.export()
+    }
+    
+    #[test]
+    fn test_register_and_get() {
+        let mut registry = AlgorithmRegistry::new();
+        registry.register(Box::new(PassThrough)).unwrap();
+        
+        assert_eq!(registry.get("passthrough").unwrap().id(), "passthrough");
+        assert!(registry.get("missing").is_none());
+        assert_eq!(
+            registry.register(Box::new(PassThrough)),
+            Err(CoreError::DuplicateAlgorithm("passthrough".to_string()))
+        );
+    }
+    
+    #[test]
+    fn test_unregister_and_list() {
+        let mut registry = AlgorithmRegistry::new();
+        registry.register(Box::new(Versioned("2.0.0"))).unwrap();
+        registry
+            .register_with_fallback("scale", Box::new(Scale::new(2.0).unwrap()), Box::new(PassThrough))
+            .unwrap();
+        
+        let listed: Vec<_> = registry.list().into_iter().map(|(id, metadata)| (id, metadata.version)).collect();
+        assert_eq!(listed[0], ("filter".to_string(), "2.0.0".to_string()));
+        assert_eq!(listed[1].0, "scale");
+        
+        assert_eq!(registry.unregister("scale").unwrap().id(), "scale");
+        assert!(registry.get("scale").is_none());
+        assert!(registry.fallback("scale").is_none());
+        assert!(matches!(registry.unregister("scale"), Err(CoreError::AlgorithmNotFound(_))));
+        
+        // The ID is free again
+        registry.register(Box::new(Scale::new(3.0).unwrap())).unwrap();
+        assert_eq!(registry.list().len(), 2);
+    }
+    
+    #[test]
+    fn test_export_round_trips_through_json() {
+        let exported = manifest("1.0.0", false);
+        assert_eq!(exported.algorithms["filter"].name, "Filter");
+        
+        let json = serde_json::to_string(&exported).unwrap();
+        let restored: RegistryManifest = serde_json::from_str(&json).unwrap();
+        assert!(diff_manifests(&exported, &restored).is_empty());
+        assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+    }
+    
+    #[test]
+    fn test_diff_reports_version_change_and_membership() {
+        let diff = diff_manifests(&manifest("1.0.0", true), &manifest("1.1.0", false));
+        assert_eq!(
+            diff.version_changed,
+            vec![VersionChange {
+                id: "filter".to_string(),
+                from: "1.0.0".to_string(),
+                to: "1.1.0".to_string(),
+            }]
+        );
+        assert_eq!(diff.removed, vec!["scale".to_string()]);
+        assert!(diff.added.is_empty());
+        
+        let reverse = diff_manifests(&manifest("1.1.0", false), &manifest("1.1.0", true));
+        assert_eq!(reverse.added, vec!["scale".to_string()]);
+        assert!(reverse.version_changed.is_empty());
+    }
+    
+    #[test]
+    fn test_registration_checks_required_capabilities() {
+        let mut registry = AlgorithmRegistry::new();
+        registry.register(Box::new(Needs(CoreEngine::capabilities()))).unwrap();
+        assert!(registry.get("needs").is_some());
+        
+        let fft = crate::Capabilities {
+            fft: true,
+            ..Default::default()
+        };
+        let expected = if cfg!(feature = "fft") {
+            Ok(())
+        } else {
+            Err(CoreError::UnsupportedCapability(
+                "'needs' needs features not compiled in: fft".to_string(),
+            ))
+        };
+        assert_eq!(AlgorithmRegistry::new().register(Box::new(Needs(fft))), expected);
+    }
+}
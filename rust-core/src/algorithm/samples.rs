@@ -0,0 +1,97 @@
+//! Conversions between byte buffers and numeric samples
+//!
+//! Built-in algorithms exchange samples as little-endian byte buffers so the
+//! format does not depend on the host.
+
+use alloc::format;
+use alloc::vec::Vec;
+
+use crate::error::CoreError;
+
+/// Decode a buffer of little-endian `f32` samples
+pub fn f32_from_bytes(bytes: &[u8]) -> Result<Vec<f32>, CoreError> {
+    if !bytes.len().is_multiple_of(4) {
+        return Err(CoreError::InvalidInput(format!("length {} is not a multiple of 4", bytes.len())));
+    }
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect())
+}
+
+/// Apply `f` to every little-endian `f32` sample of `buf` in place
+pub fn map_f32_in_place(buf: &mut [u8], f: impl Fn(f32) -> f32) -> Result<(), CoreError> {
+    if !buf.len().is_multiple_of(4) {
+        return Err(CoreError::InvalidInput(format!("length {} is not a multiple of 4", buf.len())));
+    }
+    for chunk in buf.chunks_exact_mut(4) {
+        let sample = f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        chunk.copy_from_slice(&f(sample).to_le_bytes());
+    }
+    Ok(())
+}
+
+/// Apply `f` to every little-endian `f32` sample of `input`, appending the
+/// results to `output` without decoding into an intermediate buffer
+pub fn map_f32_into(input: &[u8], output: &mut Vec<u8>, f: impl Fn(f32) -> f32) -> Result<(), CoreError> {
+    if !input.len().is_multiple_of(4) {
+        return Err(CoreError::InvalidInput(format!("length {} is not a multiple of 4", input.len())));
+    }
+    output.reserve(input.len());
+    for chunk in input.chunks_exact(4) {
+        let sample = f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        output.extend_from_slice(&f(sample).to_le_bytes());
+    }
+    Ok(())
+}
+
+/// Encode `f32` samples as little-endian bytes
+pub fn f32_to_bytes(samples: &[f32]) -> Vec<u8> {
+    samples.iter().flat_map(|sample| sample.to_le_bytes()).collect()
+}
+
+/// Decode a buffer of little-endian `i16` samples
+pub fn i16_from_bytes(bytes: &[u8]) -> Result<Vec<i16>, CoreError> {
+    if !bytes.len().is_multiple_of(2) {
+        return Err(CoreError::InvalidInput(format!("length {} is not a multiple of 2", bytes.len())));
+    }
+    Ok(bytes
+        .chunks_exact(2)
+        .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]))
+        .collect())
+}
+
+/// Encode `i16` samples as little-endian bytes
+pub fn i16_to_bytes(samples: &[i16]) -> Vec<u8> {
+    samples.iter().flat_map(|sample| sample.to_le_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_f32_round_trip() {
+        let samples = [0.0, -1.5, f32::INFINITY, 3.25];
+        assert_eq!(f32_from_bytes(&f32_to_bytes(&samples)).unwrap(), samples);
+        assert!(f32_from_bytes(&[0, 0, 0]).is_err());
+    }
+    
+    #[test]
+    fn test_map_into_appends_without_reallocating() {
+        let mut output = Vec::with_capacity(12);
+        output.extend_from_slice(&f32_to_bytes(&[7.0]));
+        let buffer = output.as_ptr();
+        map_f32_into(&f32_to_bytes(&[1.0, -2.0]), &mut output, |sample| sample * 2.0).unwrap();
+        assert_eq!(f32_from_bytes(&output).unwrap(), [7.0, 2.0, -4.0]);
+        assert_eq!(output.as_ptr(), buffer, "a pre-sized buffer was reallocated");
+        assert!(map_f32_into(&[0, 0, 0], &mut output, |sample| sample).is_err());
+    }
+    
+    #[test]
+    fn test_i16_round_trip() {
+        let samples = [0, -1, i16::MIN, i16::MAX];
+        assert_eq!(i16_from_bytes(&i16_to_bytes(&samples)).unwrap(), samples);
+        assert!(i16_from_bytes(&[0]).is_err());
+    }
+}
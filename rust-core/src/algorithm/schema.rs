@@ -0,0 +1,222 @@
+//! Data schemas describing algorithm inputs and outputs
+
+#[cfg(feature = "std")]
+use alloc::boxed::Box;
+use alloc::string::String;
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+use alloc::format;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "std")]
+use super::context::Context;
+#[cfg(feature = "std")]
+use super::{Algorithm, AlgorithmMetadata};
+use crate::error::CoreError;
+#[cfg(feature = "std")]
+use crate::memory::MemoryManager;
+
+/// Primitive element type of a byte buffer
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ElementType {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    F32,
+    F64,
+}
+
+impl ElementType {
+    /// Size of one element in bytes
+    pub fn size(&self) -> usize {
+        match self {
+            ElementType::U8 | ElementType::I8 => 1,
+            ElementType::U16 | ElementType::I16 => 2,
+            ElementType::U32 | ElementType::I32 | ElementType::F32 => 4,
+            ElementType::F64 => 8,
+        }
+    }
+}
+
+/// Shape a byte buffer must have: an element type and optional element count
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataSchema {
+    pub element_type: ElementType,
+    /// Exact number of elements required, or `None` for any length
+    pub elements: Option<usize>,
+}
+
+impl DataSchema {
+    /// Schema accepting any number of elements of the given type
+    pub fn new(element_type: ElementType) -> Self {
+        Self {
+            element_type,
+            elements: None,
+        }
+    }
+    
+    /// Require exactly `elements` elements
+    pub fn with_elements(mut self, elements: usize) -> Self {
+        self.elements = Some(elements);
+        self
+    }
+    
+    /// Check that `data` conforms to this schema
+    pub fn validate(&self, data: &[u8]) -> Result<(), CoreError> {
+        match self.mismatch(data) {
+            Some(reason) => Err(CoreError::SchemaMismatch(reason)),
+            None => Ok(()),
+        }
+    }
+    
+    /// Describe why `data` does not conform, if it doesn't
+    pub(super) fn mismatch(&self, data: &[u8]) -> Option<String> {
+        let size = self.element_type.size();
+        if !data.len().is_multiple_of(size) {
+            return Some(format!(
+                "{} bytes is not a whole number of {:?} elements",
+                data.len(),
+                self.element_type
+            ));
+        }
+        match self.elements {
+            Some(expected) if data.len() / size != expected => Some(format!(
+                "expected {} {:?} elements, got {}",
+                expected,
+                self.element_type,
+                data.len() / size
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Wraps an algorithm so every call validates its input and output schemas
+#[cfg(feature = "std")]
+pub struct SchemaGuarded {
+    inner: Box<dyn Algorithm>,
+    input_schema: DataSchema,
+    output_schema: DataSchema,
+}
+
+#[cfg(feature = "std")]
+impl SchemaGuarded {
+    /// Guard `inner` with the given input and output schemas
+    pub fn new(inner: Box<dyn Algorithm>, input_schema: DataSchema, output_schema: DataSchema) -> Self {
+        Self {
+            inner,
+            input_schema,
+            output_schema,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Algorithm for SchemaGuarded {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::new();
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, memory: &mut MemoryManager) -> Result<(), CoreError> {
+        Context::with_default(|context| self.process_in_context(input, output, memory, context))
+    }
+    
+    fn process_in_context(
+        &self,
+        input: &[u8],
+        output: &mut Vec<u8>,
+        memory: &mut MemoryManager,
+        context: &mut Context<'_>,
+    ) -> Result<(), CoreError> {
+        if let Some(reason) = self.input_schema.mismatch(input) {
+            return Err(CoreError::SchemaMismatch(format!("input of '{}': {}", self.inner.id(), reason)));
+        }
+        
+        let start = output.len();
+        self.inner.process_in_context(input, output, memory, context)?;
+        if let Some(reason) = self.output_schema.mismatch(&output[start..]) {
+            output.truncate(start);
+            return Err(CoreError::SchemaMismatch(format!("output of '{}': {}", self.inner.id(), reason)));
+        }
+        Ok(())
+    }
+    
+    fn id(&self) -> &str {
+        self.inner.id()
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        AlgorithmMetadata {
+            input_schema: Some(self.input_schema.clone()),
+            output_schema: Some(self.output_schema.clone()),
+            ..self.inner.metadata()
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    
+    /// Emits a fixed number of bytes regardless of input
+    struct FixedOutput(usize);
+    
+    impl Algorithm for FixedOutput {
+        fn process(&self, _input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            Ok(vec![0; self.0])
+        }
+        
+        fn id(&self) -> &str {
+            "fixed_output"
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata {
+                name: "Fixed Output".to_string(),
+                ..Default::default()
+            }
+        }
+    }
+    
+    fn guarded(output_len: usize) -> SchemaGuarded {
+        SchemaGuarded::new(
+            Box::new(FixedOutput(output_len)),
+            DataSchema::new(ElementType::F32),
+            DataSchema::new(ElementType::F32).with_elements(2),
+        )
+    }
+    
+    #[test]
+    fn test_conforming_output_passes() {
+        let mut memory = MemoryManager::new();
+        let output = guarded(8).process(&[0; 12], &mut memory).unwrap();
+        assert_eq!(output.len(), 8);
+    }
+    
+    #[test]
+    fn test_wrong_length_output_rejected() {
+        let mut memory = MemoryManager::new();
+        let result = guarded(12).process(&[0; 12], &mut memory);
+        assert!(matches!(result, Err(CoreError::SchemaMismatch(_))));
+    }
+    
+    #[test]
+    fn test_nonconforming_input_rejected() {
+        let mut memory = MemoryManager::new();
+        let result = guarded(8).process(&[0; 3], &mut memory);
+        assert!(matches!(result, Err(CoreError::SchemaMismatch(_))));
+    }
+    
+    #[test]
+    fn test_metadata_surfaces_schemas() {
+        let metadata = guarded(8).metadata();
+        assert_eq!(metadata.name, "Fixed Output");
+        assert_eq!(metadata.input_schema, Some(DataSchema::new(ElementType::F32)));
+        assert_eq!(metadata.output_schema.unwrap().elements, Some(2));
+    }
+}
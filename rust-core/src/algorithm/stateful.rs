@@ -0,0 +1,277 @@
+//! Algorithms that own mutable state across calls, with a lifecycle
+//!
+//! An `Algorithm` takes `&self`, so filters and trackers otherwise hide
+//! their state behind their own locks. A `StatefulAlgorithm` takes
+//! `&mut self` instead and is wrapped in a `Stateful`, which serializes
+//! calls and drives the lifecycle: `initialize` before the first call,
+//! `reset` on request and `shutdown` when the engine lets go of it.
+
+use serde_json::Value;
+use std::sync::{Mutex, MutexGuard};
+
+use super::{Algorithm, AlgorithmMetadata};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+use crate::CoreEngine;
+
+/// Algorithm with mutable state and explicit lifecycle hooks
+pub trait StatefulAlgorithm: Send {
+    /// Configure the algorithm from `params` and set up its state
+    ///
+    /// Called before the first `process`, and again after a `shutdown`
+    /// if the algorithm is used once more. The default accepts anything.
+    fn initialize(&mut self, _params: &Value) -> Result<(), CoreError> {
+        Ok(())
+    }
+    
+    /// Process input data, updating the carried state
+    fn process(&mut self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError>;
+    
+    /// Return to the state `initialize` left, keeping the configuration
+    fn reset(&mut self);
+    
+    /// Release what the algorithm holds, such as devices or threads
+    ///
+    /// The default does nothing.
+    fn shutdown(&mut self) {}
+    
+    /// Get the algorithm's unique identifier
+    fn id(&self) -> &str;
+    
+    /// Get the algorithm's metadata
+    fn metadata(&self) -> AlgorithmMetadata;
+}
+
+/// A `StatefulAlgorithm` shared as an `Algorithm`
+///
+/// Calls take turns on the instance's lock, so one instance never runs
+/// concurrently with itself. After a `shutdown` the next call initializes
+/// the instance again with the same parameters.
+pub struct Stateful {
+    id: String,
+    metadata: AlgorithmMetadata,
+    params: Value,
+    instance: Mutex<Instance>,
+}
+
+struct Instance {
+    algorithm: Box<dyn StatefulAlgorithm>,
+    initialized: bool,
+}
+
+impl Instance {
+    fn ensure_initialized(&mut self, params: &Value) -> Result<(), CoreError> {
+        if !self.initialized {
+            self.algorithm.initialize(params)?;
+            self.initialized = true;
+        }
+        Ok(())
+    }
+}
+
+impl Stateful {
+    /// Wrap `algorithm`, initializing it with `params` now so a bad
+    /// configuration fails here rather than on the first call
+    pub fn new(algorithm: Box<dyn StatefulAlgorithm>, params: Value) -> Result<Self, CoreError> {
+        let stateful = Self {
+            id: algorithm.id().to_string(),
+            metadata: algorithm.metadata(),
+            params,
+            instance: Mutex::new(Instance {
+                algorithm,
+                initialized: false,
+            }),
+        };
+        stateful.lock().ensure_initialized(&stateful.params)?;
+        Ok(stateful)
+    }
+    
+    // A call that panicked leaves the state as it was at the panic; `reset`
+    // is the way back to a known state
+    fn lock(&self) -> MutexGuard<'_, Instance> {
+        self.instance.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    /// The parameters the instance is initialized with
+    pub fn params(&self) -> &Value {
+        &self.params
+    }
+    
+    /// Whether the instance is initialized, i.e. not shut down
+    pub fn is_initialized(&self) -> bool {
+        self.lock().initialized
+    }
+    
+    /// Return the instance to its initial state
+    ///
+    /// A shut-down instance stays shut down until its next call.
+    pub fn reset(&self) {
+        let mut instance = self.lock();
+        if instance.initialized {
+            instance.algorithm.reset();
+        }
+    }
+    
+    /// Shut the instance down; the next call initializes it again
+    pub fn shutdown(&self) {
+        let mut instance = self.lock();
+        if instance.initialized {
+            instance.algorithm.shutdown();
+            instance.initialized = false;
+        }
+    }
+}
+
+impl Algorithm for Stateful {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut instance = self.lock();
+        instance.ensure_initialized(&self.params)?;
+        instance.algorithm.process(input, memory)
+    }
+    
+    fn as_stateful(&self) -> Option<&Stateful> {
+        Some(self)
+    }
+    
+    fn id(&self) -> &str {
+        &self.id
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        self.metadata.clone()
+    }
+}
+
+impl CoreEngine {
+    /// Initialize `algorithm` with `params` and register it under its own ID
+    ///
+    /// The engine keeps the one instance for every execution, pipeline
+    /// stage and scheduled run that names it, rather than building one per
+    /// call. Register copies under other IDs with `register_stateful_as`
+    /// to give each pipeline or task its own state.
+    pub fn register_stateful(&mut self, algorithm: Box<dyn StatefulAlgorithm>, params: Value) -> Result<(), CoreError> {
+        let id = algorithm.id().to_string();
+        self.register_stateful_as(&id, algorithm, params)
+    }
+    
+    /// Initialize `algorithm` with `params` and register it under `id`
+    pub fn register_stateful_as(
+        &mut self,
+        id: &str,
+        algorithm: Box<dyn StatefulAlgorithm>,
+        params: Value,
+    ) -> Result<(), CoreError> {
+        let stateful = Stateful::new(algorithm, params)?;
+        self.registry.register_as(id, Box::new(stateful))
+    }
+    
+    /// Return a registered stateful algorithm to its initial state
+    ///
+    /// Fails with `CoreError::AlgorithmNotFound` if nothing is registered
+    /// under `algorithm_id` and `CoreError::UnsupportedCapability` if what
+    /// is registered has no lifecycle.
+    pub fn reset_algorithm(&self, algorithm_id: &str) -> Result<(), CoreError> {
+        let algorithm = self
+            .registry
+            .get(algorithm_id)
+            .ok_or_else(|| CoreError::AlgorithmNotFound(algorithm_id.to_string()))?;
+        let stateful = algorithm.as_stateful().ok_or_else(|| {
+            CoreError::UnsupportedCapability(format!("algorithm '{}' has no lifecycle to reset", algorithm_id))
+        })?;
+        stateful.reset();
+        Ok(())
+    }
+    
+    /// Reset every registered stateful algorithm, e.g. before replaying a
+    /// run from the start
+    pub fn reset_stateful(&self) {
+        for algorithm in self.registry.instances() {
+            if let Some(stateful) = algorithm.as_stateful() {
+                stateful.reset();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::params;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    
+    /// Sums its input bytes onto a starting value, counting shutdowns
+    struct Total {
+        start: u64,
+        sum: u64,
+        shutdowns: Arc<AtomicUsize>,
+    }
+    
+    impl StatefulAlgorithm for Total {
+        fn initialize(&mut self, params: &Value) -> Result<(), CoreError> {
+            self.start = params::require(params, "start")?;
+            self.sum = self.start;
+            Ok(())
+        }
+        
+        fn process(&mut self, input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            self.sum += input.iter().map(|&b| u64::from(b)).sum::<u64>();
+            Ok(self.sum.to_le_bytes().to_vec())
+        }
+        
+        fn reset(&mut self) {
+            self.sum = self.start;
+        }
+        
+        fn shutdown(&mut self) {
+            self.shutdowns.fetch_add(1, Ordering::SeqCst);
+        }
+        
+        fn id(&self) -> &str {
+            "total"
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata::default()
+        }
+    }
+    
+    fn total(shutdowns: &Arc<AtomicUsize>) -> Box<Total> {
+        Box::new(Total {
+            start: 0,
+            sum: 0,
+            shutdowns: Arc::clone(shutdowns),
+        })
+    }
+    
+    fn sum(output: Vec<u8>) -> u64 {
+        u64::from_le_bytes(output.try_into().unwrap())
+    }
+    
+    #[test]
+    fn test_engine_drives_lifecycle_of_one_instance() {
+        let shutdowns = Arc::new(AtomicUsize::new(0));
+        let mut engine = CoreEngine::new();
+        assert!(engine.register_stateful(total(&shutdowns), Value::Null).is_err(), "start is required");
+        engine.register_stateful(total(&shutdowns), serde_json::json!({ "start": 10 })).unwrap();
+        engine.register_stateful_as("other", total(&shutdowns), serde_json::json!({ "start": 0 })).unwrap();
+        
+        engine.execute_algorithm("total", &[1, 2]).unwrap();
+        assert_eq!(sum(engine.execute_pipeline(&["total", "other"], &[3]).unwrap()), 16);
+        assert_eq!(sum(engine.execute_algorithm("other", &[]).unwrap()), 16);
+        
+        engine.reset_algorithm("total").unwrap();
+        assert_eq!(sum(engine.execute_algorithm("total", &[1]).unwrap()), 11);
+        assert!(matches!(engine.reset_algorithm("passthrough"), Err(CoreError::AlgorithmNotFound(_))));
+        engine.register(Box::new(crate::algorithm::builtins::PassThrough)).unwrap();
+        assert!(matches!(engine.reset_algorithm("passthrough"), Err(CoreError::UnsupportedCapability(_))));
+        
+        // Shutting down releases every instance, which starts over on use
+        engine.shutdown();
+        assert_eq!(shutdowns.load(Ordering::SeqCst), 2);
+        assert_eq!(sum(engine.execute_algorithm("total", &[5]).unwrap()), 15);
+        engine.unregister("total").unwrap();
+        engine.unregister("other").unwrap();
+        assert_eq!(shutdowns.load(Ordering::SeqCst), 3, "'other' was already shut down");
+    }
+}
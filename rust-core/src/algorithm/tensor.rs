@@ -0,0 +1,298 @@
+//! Shaped, typed buffers for algorithms that compose without agreeing on
+//! byte layouts
+
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use serde::{Deserialize, Serialize};
+
+use super::schema::ElementType;
+use crate::error::CoreError;
+use crate::wire;
+
+/// A dense array: an element type, a shape and the elements' bytes
+///
+/// Elements are little-endian and stored contiguously in row-major order,
+/// so `data` holds exactly the product of `shape` elements. A tensor with
+/// an empty shape is a scalar holding one element.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawTensor")]
+pub struct Tensor {
+    dtype: ElementType,
+    shape: Vec<usize>,
+    data: Vec<u8>,
+}
+
+/// `Tensor` as deserialized, before its length is checked against its shape
+#[derive(Deserialize)]
+struct RawTensor {
+    dtype: ElementType,
+    shape: Vec<usize>,
+    data: Vec<u8>,
+}
+
+impl TryFrom<RawTensor> for Tensor {
+    type Error = CoreError;
+    
+    fn try_from(raw: RawTensor) -> Result<Self, CoreError> {
+        Tensor::new(raw.dtype, raw.shape, raw.data)
+    }
+}
+
+const DTYPES: [ElementType; 8] = [
+    ElementType::U8,
+    ElementType::I8,
+    ElementType::U16,
+    ElementType::I16,
+    ElementType::U32,
+    ElementType::I32,
+    ElementType::F32,
+    ElementType::F64,
+];
+
+impl Tensor {
+    /// Wrap `data` as a tensor of `shape`
+    ///
+    /// Fails with `CoreError::InvalidInput` unless `data` holds exactly as
+    /// many elements as the shape, or if the shape has more than 255
+    /// dimensions.
+    pub fn new(dtype: ElementType, shape: Vec<usize>, data: Vec<u8>) -> Result<Self, CoreError> {
+        if shape.len() > usize::from(u8::MAX) {
+            return Err(CoreError::InvalidInput(format!("{} dimensions exceed the limit of 255", shape.len())));
+        }
+        let expected = byte_len(dtype, &shape)
+            .ok_or_else(|| CoreError::InvalidInput(format!("shape {:?} is too large", shape)))?;
+        if data.len() != expected {
+            return Err(CoreError::InvalidInput(format!(
+                "shape {:?} of {:?} needs {} bytes, got {}",
+                shape,
+                dtype,
+                expected,
+                data.len()
+            )));
+        }
+        Ok(Self { dtype, shape, data })
+    }
+    
+    /// A one-dimensional tensor of however many elements `data` holds
+    pub fn flat(dtype: ElementType, data: Vec<u8>) -> Result<Self, CoreError> {
+        let size = dtype.size();
+        if !data.len().is_multiple_of(size) {
+            return Err(CoreError::InvalidInput(format!(
+                "{} bytes is not a whole number of {:?} elements",
+                data.len(),
+                dtype
+            )));
+        }
+        Self::new(dtype, vec![data.len() / size], data)
+    }
+    
+    pub fn from_f32(shape: Vec<usize>, values: &[f32]) -> Result<Self, CoreError> {
+        Self::new(ElementType::F32, shape, values.iter().flat_map(|v| v.to_le_bytes()).collect())
+    }
+    
+    pub fn from_f64(shape: Vec<usize>, values: &[f64]) -> Result<Self, CoreError> {
+        Self::new(ElementType::F64, shape, values.iter().flat_map(|v| v.to_le_bytes()).collect())
+    }
+    
+    pub fn dtype(&self) -> ElementType {
+        self.dtype
+    }
+    
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+    
+    /// The elements' bytes
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+    
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+    
+    /// Number of elements
+    pub fn elements(&self) -> usize {
+        self.data.len() / self.dtype.size()
+    }
+    
+    /// The same elements under another shape with as many elements
+    pub fn reshape(self, shape: Vec<usize>) -> Result<Self, CoreError> {
+        Self::new(self.dtype, shape, self.data)
+    }
+    
+    /// Decode the elements, failing with `CoreError::SchemaMismatch` unless
+    /// they are `F32`
+    pub fn to_f32(&self) -> Result<Vec<f32>, CoreError> {
+        self.expect_dtype(ElementType::F32)?;
+        Ok(self
+            .data
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect())
+    }
+    
+    /// Decode the elements, failing with `CoreError::SchemaMismatch` unless
+    /// they are `F64`
+    pub fn to_f64(&self) -> Result<Vec<f64>, CoreError> {
+        self.expect_dtype(ElementType::F64)?;
+        Ok(self
+            .data
+            .chunks_exact(8)
+            .map(|chunk| {
+                let mut bytes = [0u8; 8];
+                bytes.copy_from_slice(chunk);
+                f64::from_le_bytes(bytes)
+            })
+            .collect())
+    }
+    
+    fn expect_dtype(&self, dtype: ElementType) -> Result<(), CoreError> {
+        if self.dtype != dtype {
+            return Err(CoreError::SchemaMismatch(format!(
+                "tensor holds {:?} elements, read as {:?}",
+                self.dtype, dtype
+            )));
+        }
+        Ok(())
+    }
+    
+    /// Serialize the tensor into a self-describing byte record, for passing
+    /// through byte-oriented APIs
+    ///
+    /// The record holds the element type (`u8` tag), the number of
+    /// dimensions (`u8`), each dimension (`u64`, little-endian) and then the
+    /// element bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut record = Vec::with_capacity(2 + 8 * self.shape.len() + self.data.len());
+        record.push(self.dtype_tag());
+        record.push(self.shape.len() as u8);
+        for &dim in &self.shape {
+            record.extend_from_slice(&(dim as u64).to_le_bytes());
+        }
+        record.extend_from_slice(&self.data);
+        record
+    }
+    
+    /// Parse a record produced by `to_bytes`
+    pub fn from_bytes(record: &[u8]) -> Result<Self, CoreError> {
+        let mut reader = wire::Reader::new(record, "tensor record", CoreError::InvalidInput);
+        let tag = reader.u8()?;
+        let dtype = *DTYPES
+            .get(usize::from(tag))
+            .ok_or_else(|| reader.error(&format!("unknown element type {}", tag)))?;
+        let rank = reader.u8()?;
+        let shape = (0..rank)
+            .map(|_| {
+                let dim = reader.u64()?;
+                usize::try_from(dim).map_err(|_| reader.error(&format!("dimension {} is too large", dim)))
+            })
+            .collect::<Result<Vec<usize>, CoreError>>()?;
+        let len = byte_len(dtype, &shape).ok_or_else(|| reader.error(&format!("shape {:?} is too large", shape)))?;
+        let data = reader.take(len)?.to_vec();
+        reader.finish()?;
+        Self::new(dtype, shape, data)
+    }
+    
+    fn dtype_tag(&self) -> u8 {
+        DTYPES.iter().position(|&dtype| dtype == self.dtype).unwrap_or(0) as u8
+    }
+}
+
+/// Bytes a tensor of `shape` holds, or `None` if that overflows
+fn byte_len(dtype: ElementType, shape: &[usize]) -> Option<usize> {
+    shape.iter().try_fold(dtype.size(), |bytes, &dim| bytes.checked_mul(dim))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "std")]
+    use crate::algorithm::builtins::Scale;
+    #[cfg(feature = "std")]
+    use crate::algorithm::{Algorithm, AlgorithmMetadata};
+    #[cfg(feature = "std")]
+    use crate::memory::MemoryManager;
+    #[cfg(feature = "std")]
+    use crate::CoreEngine;
+    
+    /// Transposes a 2-D `F32` tensor, which has no byte-level equivalent
+    /// without a shape to go on
+    #[cfg(feature = "std")]
+    struct Transpose;
+    
+    #[cfg(feature = "std")]
+    impl Algorithm for Transpose {
+        fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            Ok(self.process_typed(&Tensor::from_bytes(input)?, memory)?.to_bytes())
+        }
+        
+        fn process_typed(&self, input: &Tensor, _memory: &mut MemoryManager) -> Result<Tensor, CoreError> {
+            let &[rows, cols] = input.shape() else {
+                return Err(CoreError::InvalidInput(format!("expected 2 dimensions, got {:?}", input.shape())));
+            };
+            let values = input.to_f32()?;
+            let transposed: Vec<f32> = (0..cols)
+                .flat_map(|col| (0..rows).map(move |row| (row, col)))
+                .map(|(row, col)| values[row * cols + col])
+                .collect();
+            Tensor::from_f32(vec![cols, rows], &transposed)
+        }
+        
+        fn id(&self) -> &str {
+            "transpose"
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata::default()
+        }
+    }
+    
+    #[test]
+    fn test_shape_must_match_data() {
+        assert!(Tensor::from_f32(vec![2, 2], &[1.0; 4]).is_ok());
+        assert!(Tensor::from_f32(vec![2, 3], &[1.0; 4]).is_err());
+        assert!(Tensor::new(ElementType::U8, vec![usize::MAX, 2], Vec::new()).is_err());
+        
+        let scalar = Tensor::from_f64(Vec::new(), &[2.5]).unwrap();
+        assert_eq!((scalar.elements(), scalar.to_f64().unwrap()), (1, vec![2.5]));
+        assert!(matches!(scalar.to_f32(), Err(CoreError::SchemaMismatch(_))));
+        assert_eq!(scalar.reshape(vec![1, 1]).unwrap().shape(), &[1, 1]);
+    }
+    
+    #[test]
+    fn test_byte_and_serde_round_trips() {
+        let tensor = Tensor::new(ElementType::I16, vec![3, 1], vec![1, 0, 2, 0, 3, 0]).unwrap();
+        let record = tensor.to_bytes();
+        assert_eq!(Tensor::from_bytes(&record).unwrap(), tensor);
+        assert!(Tensor::from_bytes(&record[..record.len() - 1]).is_err());
+        assert!(Tensor::from_bytes(&[9, 0]).is_err());
+        
+        let json = serde_json::to_string(&tensor).unwrap();
+        assert_eq!(serde_json::from_str::<Tensor>(&json).unwrap(), tensor);
+        let short = r#"{"dtype":"U8","shape":[4],"data":[1,2]}"#;
+        assert!(serde_json::from_str::<Tensor>(short).is_err());
+    }
+    
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_typed_execution_through_engine() {
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(Transpose)).unwrap();
+        engine.register(Box::new(Scale::new(2.0).unwrap())).unwrap();
+        
+        let matrix = Tensor::from_f32(vec![2, 3], &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+        let transposed = engine.execute_typed("transpose", &matrix).unwrap();
+        assert_eq!(transposed.shape(), &[3, 2]);
+        assert_eq!(transposed.to_f32().unwrap(), vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+        // The byte path carries the same tensor as a record
+        let via_bytes = engine.execute_algorithm("transpose", &matrix.to_bytes()).unwrap();
+        assert_eq!(Tensor::from_bytes(&via_bytes).unwrap(), transposed);
+        
+        // Byte-oriented algorithms see the elements and return a flat tensor
+        let scaled = engine.execute_typed(Scale::ID, &transposed).unwrap();
+        assert_eq!(scaled.shape(), &[6]);
+        assert_eq!(scaled.to_f32().unwrap(), vec![2.0, 8.0, 4.0, 10.0, 6.0, 12.0]);
+        assert!(matches!(engine.execute_typed("absent", &scaled), Err(CoreError::AlgorithmNotFound(_))));
+    }
+}
@@ -0,0 +1,64 @@
+//! Approximate float comparisons for comparison-based built-ins
+//!
+//! Sensor noise of a few ULPs should not flip a decision back and forth, so
+//! built-ins that compare samples against fixed levels treat values within
+//! an `epsilon` of a level as equal to it. Each such built-in takes an
+//! `epsilon` parameter defaulting to `DEFAULT_EPSILON`.
+
+use crate::error::CoreError;
+
+/// Default tolerance, a few `f32` ULPs
+pub const DEFAULT_EPSILON: f32 = 4.0 * f32::EPSILON;
+
+/// Whether `a` and `b` are within `epsilon` of each other
+///
+/// The tolerance is scaled by the larger magnitude once that exceeds 1, so
+/// it is relative for large values and absolute near zero. NaN is never
+/// approximately equal to anything, and an infinity only to itself.
+pub fn approx_eq(a: f32, b: f32, epsilon: f32) -> bool {
+    if a.is_infinite() || b.is_infinite() {
+        return a == b;
+    }
+    (a - b).abs() <= epsilon * a.abs().max(b.abs()).max(1.0)
+}
+
+/// Whether `a` is greater than or approximately equal to `b`
+pub fn approx_ge(a: f32, b: f32, epsilon: f32) -> bool {
+    a >= b || approx_eq(a, b, epsilon)
+}
+
+/// Whether `a` is less than or approximately equal to `b`
+pub fn approx_le(a: f32, b: f32, epsilon: f32) -> bool {
+    a <= b || approx_eq(a, b, epsilon)
+}
+
+/// Check that `epsilon` is usable as a tolerance
+pub fn validate_epsilon(epsilon: f32) -> Result<f32, CoreError> {
+    if !epsilon.is_finite() || epsilon < 0.0 {
+        return Err(CoreError::InvalidParameter(format!(
+            "epsilon must be finite and non-negative, got {}",
+            epsilon
+        )));
+    }
+    Ok(epsilon)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_tolerance_scales_with_magnitude() {
+        assert!(approx_eq(1.0, 1.0 + f32::EPSILON, DEFAULT_EPSILON));
+        assert!(!approx_eq(1.0, 1.001, DEFAULT_EPSILON));
+        assert!(approx_eq(1.0e6, 1.0e6 + 0.25, DEFAULT_EPSILON));
+        assert!(approx_eq(0.0, 1.0e-7, DEFAULT_EPSILON));
+        assert!(!approx_eq(f32::NAN, f32::NAN, 1.0));
+        assert!(!approx_eq(f32::INFINITY, 1.0, DEFAULT_EPSILON));
+        
+        assert!(approx_ge(1.0 - f32::EPSILON, 1.0, DEFAULT_EPSILON));
+        assert!(!approx_ge(1.0 - f32::EPSILON, 1.0, 0.0));
+        assert!(validate_epsilon(-1.0).is_err());
+        assert!(validate_epsilon(f32::NAN).is_err());
+    }
+}
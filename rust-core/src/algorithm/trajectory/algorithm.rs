@@ -0,0 +1,183 @@
+//! Trajectory generation as a built-in over `f32` waypoints
+
+use serde_json::Value;
+
+use super::{Interpolation, JointLimits, Trajectory};
+use crate::algorithm::{params, samples, Algorithm, AlgorithmMetadata, ParameterDefinition, ParameterType, Pure};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+
+/// Most setpoints one execution may produce
+const MAX_SETPOINTS: f64 = 1_000_000.0;
+
+/// Setpoints at a fixed rate along a trajectory through the input
+/// waypoints
+///
+/// The input holds consecutive waypoints of one `f32` position per joint,
+/// such as a planner's `x`, `y`, heading poses. Each setpoint is written
+/// as `f32`s: the time in seconds, then every joint's position, velocity
+/// and acceleration, in that order, so a control loop can feed forward
+/// the velocity and acceleration. The number of joints is the length of
+/// the `velocity` limits.
+#[derive(Clone, Debug)]
+pub struct TrajectoryGenerator {
+    interpolation: Interpolation,
+    limits: Vec<JointLimits>,
+    period: f64,
+}
+
+impl TrajectoryGenerator {
+    pub const ID: &'static str = "trajectory";
+    
+    /// Generate setpoints every `period` seconds
+    pub fn new(interpolation: Interpolation, limits: Vec<JointLimits>, period: f64) -> Result<Self, CoreError> {
+        super::check_limits(interpolation, &limits)?;
+        if !(period.is_finite() && period > 0.0) {
+            return Err(CoreError::InvalidParameter(format!("period must be positive and finite, got {}", period)));
+        }
+        Ok(Self {
+            interpolation,
+            limits,
+            period,
+        })
+    }
+    
+    /// Create the generator from `interpolation`, per-joint `velocity`,
+    /// `acceleration` and optional `jerk` limits, and `period` parameters
+    pub fn from_params(params: &Value) -> Result<Self, CoreError> {
+        let velocity: Vec<f64> = params::require(params, "velocity")?;
+        let acceleration: Vec<f64> = params::require(params, "acceleration")?;
+        let jerk: Option<Vec<f64>> = params::get(params, "jerk")?;
+        let joints = velocity.len();
+        if acceleration.len() != joints || jerk.as_ref().is_some_and(|jerk| jerk.len() != joints) {
+            return Err(CoreError::InvalidParameter(format!(
+                "'acceleration' and 'jerk' need one limit for each of the {} joints",
+                joints
+            )));
+        }
+        let limits = (0..joints)
+            .map(|joint| JointLimits {
+                velocity: velocity[joint],
+                acceleration: acceleration[joint],
+                jerk: jerk.as_ref().map(|jerk| jerk[joint]),
+            })
+            .collect();
+        Self::new(
+            params::get(params, "interpolation")?.unwrap_or_default(),
+            limits,
+            params::get(params, "period")?.unwrap_or(0.01),
+        )
+    }
+    
+    /// The trajectory through `input`'s waypoints
+    pub fn trajectory(&self, input: &[u8]) -> Result<Trajectory, CoreError> {
+        let values = samples::f32_from_bytes(input)?;
+        let joints = self.limits.len();
+        if !values.len().is_multiple_of(joints) {
+            return Err(CoreError::InvalidInput(format!(
+                "{} f32 values do not form whole {}-joint waypoints",
+                values.len(),
+                joints
+            )));
+        }
+        let waypoints: Vec<Vec<f64>> = values
+            .chunks_exact(joints)
+            .map(|chunk| chunk.iter().map(|&v| f64::from(v)).collect())
+            .collect();
+        Trajectory::new(self.interpolation, &waypoints, &self.limits)
+    }
+}
+
+impl Algorithm for TrajectoryGenerator {
+    fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::new();
+        self.process_into(input, &mut output, memory)?;
+        Ok(output)
+    }
+    
+    fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+        let trajectory = self.trajectory(input)?;
+        if trajectory.duration() / self.period > MAX_SETPOINTS {
+            return Err(CoreError::InvalidInput(format!(
+                "a {:.1} s trajectory at a {} s period exceeds {} setpoints",
+                trajectory.duration(),
+                self.period,
+                MAX_SETPOINTS
+            )));
+        }
+        for setpoint in trajectory.setpoints(self.period)? {
+            let values = std::iter::once(setpoint.time)
+                .chain(setpoint.position)
+                .chain(setpoint.velocity)
+                .chain(setpoint.acceleration);
+            for value in values {
+                output.extend_from_slice(&(value as f32).to_le_bytes());
+            }
+        }
+        Ok(())
+    }
+    
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        let parameter = |name: &str, parameter_type: ParameterType, description: &str, default_value: Option<&str>| {
+            ParameterDefinition {
+                name: name.to_string(),
+                parameter_type,
+                description: description.to_string(),
+                default_value: default_value.map(str::to_string),
+            }
+        };
+        AlgorithmMetadata {
+            name: "Trajectory Generator".to_string(),
+            version: "1.0.0".to_string(),
+            description: "Times f32 waypoints within joint limits, emitting (time, positions, velocities, \
+                          accelerations) setpoints"
+                .to_string(),
+            parameters: vec![
+                parameter(
+                    "interpolation",
+                    ParameterType::String,
+                    "Trapezoidal, SCurve, Cubic or Quintic",
+                    Some("Trapezoidal"),
+                ),
+                parameter("velocity", ParameterType::Array, "Largest speed of each joint, per second", None),
+                parameter("acceleration", ParameterType::Array, "Largest acceleration of each joint", None),
+                parameter("jerk", ParameterType::Array, "Largest jerk of each joint, needed for SCurve", None),
+                parameter("period", ParameterType::Float, "Seconds between setpoints", Some("0.01")),
+            ],
+            ..Default::default()
+        }
+    }
+}
+
+impl Pure for TrajectoryGenerator {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CoreEngine;
+    use serde_json::json;
+    
+    #[test]
+    fn test_engine_turns_waypoints_into_setpoints() {
+        let params = json!({ "velocity": [1.0], "acceleration": [2.0], "period": 0.5 });
+        let mut engine = CoreEngine::new();
+        engine
+            .register(Box::new(TrajectoryGenerator::from_params(&params).unwrap()))
+            .unwrap();
+        let output = engine.execute_algorithm("trajectory", &samples::f32_to_bytes(&[0.0, 2.0])).unwrap();
+        let values = samples::f32_from_bytes(&output).unwrap();
+        // 2.5 s at 0.5 s steps, each (time, position, velocity, acceleration)
+        assert_eq!(values.len(), 6 * 4);
+        assert_eq!(&values[8..12], [1.0, 0.75, 1.0, 0.0]);
+        assert_eq!(&values[20..], [2.5, 2.0, 0.0, 0.0]);
+        
+        assert!(engine.execute_algorithm("trajectory", &samples::f32_to_bytes(&[0.0])).is_err());
+        assert!(TrajectoryGenerator::from_params(&json!({ "velocity": [1.0], "acceleration": [] })).is_err());
+        let s_curve = json!({ "interpolation": "SCurve", "velocity": [1.0], "acceleration": [2.0] });
+        assert!(TrajectoryGenerator::from_params(&s_curve).is_err());
+    }
+}
@@ -0,0 +1,396 @@
+//! Time-parameterized trajectories through joint-space waypoints
+//!
+//! A `Trajectory` turns waypoints, such as a planner's output, into
+//! setpoints a control loop can follow, keeping every joint within its
+//! `JointLimits`. Trapezoidal and S-curve profiles move in a straight line
+//! from one waypoint to the next, stopping at each; cubic and quintic
+//! splines pass through the waypoints without stopping, timed so the
+//! tightest limit is just met. `TrajectoryGenerator` wraps this as a
+//! built-in.
+
+mod algorithm;
+mod profile;
+mod spline;
+
+pub use algorithm::TrajectoryGenerator;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::CoreError;
+use profile::Profile;
+use spline::Polynomial;
+
+/// How fast one joint may move, in its units per second
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JointLimits {
+    pub velocity: f64,
+    pub acceleration: f64,
+    /// Needed for S-curves; splines respect it when set
+    pub jerk: Option<f64>,
+}
+
+impl JointLimits {
+    pub fn new(velocity: f64, acceleration: f64) -> Self {
+        Self {
+            velocity,
+            acceleration,
+            jerk: None,
+        }
+    }
+    
+    pub fn with_jerk(mut self, jerk: f64) -> Self {
+        self.jerk = Some(jerk);
+        self
+    }
+    
+    /// Fails with `CoreError::InvalidParameter` unless every limit set is
+    /// positive and finite
+    pub fn validate(&self) -> Result<(), CoreError> {
+        let limits = [
+            ("velocity", Some(self.velocity)),
+            ("acceleration", Some(self.acceleration)),
+            ("jerk", self.jerk),
+        ];
+        for (name, limit) in limits {
+            if let Some(limit) = limit {
+                if !(limit.is_finite() && limit > 0.0) {
+                    return Err(CoreError::InvalidParameter(format!(
+                        "{} limit must be positive and finite, got {}",
+                        name, limit
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How a trajectory moves between waypoints
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Interpolation {
+    /// Straight lines at bounded velocity and acceleration, stopping at
+    /// each waypoint
+    #[default]
+    Trapezoidal,
+    /// Like `Trapezoidal`, with jerk bounded too so acceleration ramps
+    /// instead of jumping
+    SCurve,
+    /// Cubic spline through every waypoint, with continuous acceleration
+    Cubic,
+    /// Quintic segments through every waypoint, with continuous and
+    /// smoothly varying acceleration
+    Quintic,
+}
+
+/// Where the joints should be at one instant
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Setpoint {
+    /// Seconds since the trajectory started
+    pub time: f64,
+    pub position: Vec<f64>,
+    pub velocity: Vec<f64>,
+    pub acceleration: Vec<f64>,
+}
+
+/// The motion between two consecutive waypoints
+#[derive(Clone, Debug)]
+enum Motion {
+    /// `start + delta * s(t)` for a unit profile `s`
+    Line { start: Vec<f64>, delta: Vec<f64>, profile: Profile },
+    /// One polynomial per joint
+    Polynomial(Vec<Polynomial>),
+}
+
+#[derive(Clone, Debug)]
+struct Segment {
+    start: f64,
+    duration: f64,
+    motion: Motion,
+}
+
+/// Joint positions as a function of time, from the first waypoint at rest
+/// to the last at rest
+#[derive(Clone, Debug)]
+pub struct Trajectory {
+    joints: usize,
+    segments: Vec<Segment>,
+    end: Vec<f64>,
+}
+
+impl Trajectory {
+    /// Time `waypoints` for joints limited by `limits`, one per joint
+    ///
+    /// Fails with `CoreError::InvalidInput` for fewer than two waypoints,
+    /// waypoints of the wrong length, non-finite positions or two
+    /// consecutive waypoints alike, and with `CoreError::InvalidParameter`
+    /// for bad limits or an S-curve without jerk limits.
+    pub fn new(
+        interpolation: Interpolation,
+        waypoints: &[Vec<f64>],
+        limits: &[JointLimits],
+    ) -> Result<Self, CoreError> {
+        check_limits(interpolation, limits)?;
+        check_waypoints(waypoints, limits.len())?;
+        let segments = match interpolation {
+            Interpolation::Trapezoidal | Interpolation::SCurve => lines(interpolation, waypoints, limits),
+            Interpolation::Cubic | Interpolation::Quintic => splines(interpolation, waypoints, limits),
+        };
+        Ok(Self {
+            joints: limits.len(),
+            segments,
+            end: waypoints[waypoints.len() - 1].clone(),
+        })
+    }
+    
+    /// Seconds from the first waypoint to the last
+    pub fn duration(&self) -> f64 {
+        self.segments.last().map_or(0.0, |segment| segment.start + segment.duration)
+    }
+    
+    pub fn joints(&self) -> usize {
+        self.joints
+    }
+    
+    /// The setpoint at `time`, holding the first waypoint before the start
+    /// and the last after the end
+    pub fn sample(&self, time: f64) -> Setpoint {
+        let time = time.max(0.0);
+        let at_rest = |position: &[f64]| Setpoint {
+            time,
+            position: position.to_vec(),
+            velocity: vec![0.0; self.joints],
+            acceleration: vec![0.0; self.joints],
+        };
+        if time >= self.duration() {
+            return at_rest(&self.end);
+        }
+        let index = self.segments.partition_point(|segment| segment.start <= time).saturating_sub(1);
+        let Some(segment) = self.segments.get(index) else {
+            return at_rest(&self.end);
+        };
+        let local = time - segment.start;
+        match &segment.motion {
+            Motion::Line { start, delta, profile } => {
+                let (s, ds, dds) = profile.at(local);
+                Setpoint {
+                    time,
+                    position: start.iter().zip(delta).map(|(p, d)| p + d * s).collect(),
+                    velocity: delta.iter().map(|d| d * ds).collect(),
+                    acceleration: delta.iter().map(|d| d * dds).collect(),
+                }
+            }
+            Motion::Polynomial(polys) => {
+                let values: Vec<[f64; 4]> = polys.iter().map(|poly| spline::evaluate(poly, local)).collect();
+                Setpoint {
+                    time,
+                    position: values.iter().map(|v| v[0]).collect(),
+                    velocity: values.iter().map(|v| v[1]).collect(),
+                    acceleration: values.iter().map(|v| v[2]).collect(),
+                }
+            }
+        }
+    }
+    
+    /// Setpoints every `period` seconds from the start, ending with one at
+    /// the last waypoint
+    ///
+    /// Fails with `CoreError::InvalidParameter` for a period that is not
+    /// positive and finite.
+    pub fn setpoints(&self, period: f64) -> Result<Vec<Setpoint>, CoreError> {
+        if !(period.is_finite() && period > 0.0) {
+            return Err(CoreError::InvalidParameter(format!("period must be positive and finite, got {}", period)));
+        }
+        let duration = self.duration();
+        let mut setpoints: Vec<Setpoint> = (0..)
+            .map(|i| i as f64 * period)
+            .take_while(|&time| time < duration)
+            .map(|time| self.sample(time))
+            .collect();
+        setpoints.push(self.sample(duration));
+        Ok(setpoints)
+    }
+}
+
+fn check_limits(interpolation: Interpolation, limits: &[JointLimits]) -> Result<(), CoreError> {
+    if limits.is_empty() {
+        return Err(CoreError::InvalidParameter("a trajectory needs at least one joint".to_string()));
+    }
+    for limit in limits {
+        limit.validate()?;
+    }
+    if interpolation == Interpolation::SCurve && limits.iter().any(|limit| limit.jerk.is_none()) {
+        return Err(CoreError::InvalidParameter("an S-curve needs a jerk limit for every joint".to_string()));
+    }
+    Ok(())
+}
+
+fn check_waypoints(waypoints: &[Vec<f64>], joints: usize) -> Result<(), CoreError> {
+    if waypoints.len() < 2 {
+        return Err(CoreError::InvalidInput(format!(
+            "a trajectory needs at least two waypoints, got {}",
+            waypoints.len()
+        )));
+    }
+    for (i, waypoint) in waypoints.iter().enumerate() {
+        if waypoint.len() != joints || waypoint.iter().any(|p| !p.is_finite()) {
+            return Err(CoreError::InvalidInput(format!(
+                "waypoint {} must hold {} finite positions, got {:?}",
+                i,
+                joints,
+                waypoint
+            )));
+        }
+        if i > 0 && waypoints[i - 1] == *waypoint {
+            return Err(CoreError::InvalidInput(format!("waypoint {} repeats the one before it", i)));
+        }
+    }
+    Ok(())
+}
+
+/// Rest-to-rest straight lines between consecutive waypoints
+///
+/// Each line follows one unit profile scaled per joint, with limits
+/// divided by the distance each joint travels, so the joints arrive
+/// together and the one with least slack meets its limit.
+fn lines(interpolation: Interpolation, waypoints: &[Vec<f64>], limits: &[JointLimits]) -> Vec<Segment> {
+    let mut start = 0.0;
+    waypoints
+        .windows(2)
+        .map(|pair| {
+            let delta: Vec<f64> = pair[0].iter().zip(&pair[1]).map(|(a, b)| b - a).collect();
+            let scaled = |limit: fn(&JointLimits) -> f64| {
+                delta
+                    .iter()
+                    .zip(limits)
+                    .filter(|(d, _)| **d != 0.0)
+                    .map(|(d, joint)| limit(joint) / d.abs())
+                    .fold(f64::INFINITY, f64::min)
+            };
+            let velocity = scaled(|joint| joint.velocity);
+            let acceleration = scaled(|joint| joint.acceleration);
+            let profile = match interpolation {
+                Interpolation::SCurve => {
+                    Profile::s_curve(velocity, acceleration, scaled(|joint| joint.jerk.unwrap_or(f64::INFINITY)))
+                }
+                _ => Profile::trapezoidal(velocity, acceleration),
+            };
+            let segment = Segment {
+                start,
+                duration: profile.duration(),
+                motion: Motion::Line {
+                    start: pair[0].clone(),
+                    delta,
+                    profile,
+                },
+            };
+            start += segment.duration;
+            segment
+        })
+        .collect()
+}
+
+/// Spline through every waypoint
+///
+/// Each segment first gets the time a trapezoidal move over it would take.
+/// Scaling every duration by a factor `k` divides velocities by `k`,
+/// accelerations by `k²` and jerks by `k³`, so one rescale by the largest
+/// such ratio over the first spline makes the tightest limit hold exactly.
+fn splines(interpolation: Interpolation, waypoints: &[Vec<f64>], limits: &[JointLimits]) -> Vec<Segment> {
+    let guesses: Vec<f64> = lines(Interpolation::Trapezoidal, waypoints, limits)
+        .iter()
+        .map(|segment| segment.duration)
+        .collect();
+    let fit = |durations: &[f64]| -> Vec<Vec<Polynomial>> {
+        (0..limits.len())
+            .map(|joint| {
+                let positions: Vec<f64> = waypoints.iter().map(|waypoint| waypoint[joint]).collect();
+                match interpolation {
+                    Interpolation::Quintic => spline::quintic(&positions, durations),
+                    _ => spline::cubic(&positions, durations),
+                }
+            })
+            .collect()
+    };
+    let mut scale: f64 = 0.0;
+    for (joint, polys) in fit(&guesses).iter().enumerate() {
+        let limit = limits[joint];
+        for (poly, &duration) in polys.iter().zip(&guesses) {
+            let [velocity, acceleration, jerk] = spline::peaks(poly, duration);
+            scale = scale
+                .max(velocity / limit.velocity)
+                .max((acceleration / limit.acceleration).sqrt())
+                .max(limit.jerk.map_or(0.0, |limit| (jerk / limit).cbrt()));
+        }
+    }
+    let durations: Vec<f64> = guesses.iter().map(|duration| duration * scale).collect();
+    let by_joint = fit(&durations);
+    let mut start = 0.0;
+    durations
+        .iter()
+        .enumerate()
+        .map(|(i, &duration)| {
+            let segment = Segment {
+                start,
+                duration,
+                motion: Motion::Polynomial(by_joint.iter().map(|polys| polys[i]).collect()),
+            };
+            start += duration;
+            segment
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Largest ratio of each setpoint quantity to its limit, sampled finely
+    fn worst_ratios(trajectory: &Trajectory, limits: &[JointLimits]) -> (f64, f64) {
+        let setpoints = trajectory.setpoints(trajectory.duration() / 2000.0).unwrap();
+        let mut worst: (f64, f64) = (0.0, 0.0);
+        for setpoint in &setpoints {
+            for (joint, limit) in limits.iter().enumerate() {
+                worst.0 = worst.0.max(setpoint.velocity[joint].abs() / limit.velocity);
+                worst.1 = worst.1.max(setpoint.acceleration[joint].abs() / limit.acceleration);
+            }
+        }
+        worst
+    }
+    
+    #[test]
+    fn test_every_interpolation_meets_limits_and_waypoints() {
+        let limits = [JointLimits::new(1.0, 2.0).with_jerk(10.0), JointLimits::new(0.5, 4.0).with_jerk(40.0)];
+        let waypoints = vec![vec![0.0, 0.0], vec![2.0, 0.3], vec![1.0, 1.5]];
+        for interpolation in [
+            Interpolation::Trapezoidal,
+            Interpolation::SCurve,
+            Interpolation::Cubic,
+            Interpolation::Quintic,
+        ] {
+            let trajectory = Trajectory::new(interpolation, &waypoints, &limits).unwrap();
+            let (velocity, acceleration) = worst_ratios(&trajectory, &limits);
+            assert!(velocity <= 1.0 + 1e-6 && acceleration <= 1.0 + 1e-6, "{:?}", interpolation);
+            // The tightest limit is reached rather than left with slack
+            assert!(velocity.max(acceleration) > 0.95, "{:?}", interpolation);
+            
+            let start = trajectory.sample(0.0);
+            let end = trajectory.sample(trajectory.duration());
+            assert_eq!((start.position, end.position), (waypoints[0].clone(), waypoints[2].clone()));
+            assert_eq!(end.velocity, [0.0, 0.0]);
+            let setpoints = trajectory.setpoints(0.01).unwrap();
+            assert_eq!(setpoints.last().unwrap().time, trajectory.duration());
+        }
+        
+        assert!(Trajectory::new(Interpolation::Trapezoidal, &waypoints, &limits[..1]).is_err());
+        let single = [JointLimits::new(1.0, 2.0)];
+        // 2 units at 1 unit/s, with 0.5 s ramps at each end, take 2.5 s
+        let trajectory = Trajectory::new(Interpolation::Trapezoidal, &[vec![0.0], vec![2.0]], &single).unwrap();
+        assert!((trajectory.duration() - 2.5).abs() < 1e-9);
+        assert!((trajectory.sample(1.25).position[0] - 1.0).abs() < 1e-9);
+        
+        assert!(Trajectory::new(Interpolation::SCurve, &[vec![0.0], vec![1.0]], &single).is_err());
+        assert!(Trajectory::new(Interpolation::Cubic, &[vec![0.0], vec![0.0]], &single).is_err());
+        assert!(Trajectory::new(Interpolation::Cubic, &[vec![0.0]], &single).is_err());
+        assert!(JointLimits::new(0.0, 1.0).validate().is_err());
+    }
+}
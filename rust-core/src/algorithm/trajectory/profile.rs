@@ -0,0 +1,133 @@
+//! Rest-to-rest motion profiles over a unit distance
+
+/// A stretch of constant jerk, with the state it starts from
+#[derive(Clone, Debug)]
+struct Phase {
+    start: f64,
+    position: f64,
+    velocity: f64,
+    acceleration: f64,
+    jerk: f64,
+}
+
+/// Position along a move from 0 to 1 over time, starting and ending at
+/// rest
+#[derive(Clone, Debug)]
+pub(super) struct Profile {
+    phases: Vec<Phase>,
+    duration: f64,
+}
+
+impl Profile {
+    /// Accelerate at `acceleration` to at most `velocity`, cruise, then
+    /// brake as hard
+    pub(super) fn trapezoidal(velocity: f64, acceleration: f64) -> Self {
+        // Too short to reach `velocity`: accelerate halfway, then brake
+        let peak = velocity.min(acceleration.sqrt());
+        let ramp = peak / acceleration;
+        let cruise = (1.0 - peak * ramp) / peak;
+        Self::from_phases(&[(ramp, acceleration, 0.0), (cruise, 0.0, 0.0), (ramp, -acceleration, 0.0)])
+    }
+    
+    /// Like `trapezoidal`, but ramping acceleration at `jerk` so it never
+    /// jumps
+    pub(super) fn s_curve(velocity: f64, acceleration: f64, jerk: f64) -> Self {
+        // Time to reach `peak` from rest, and how long jerk is applied at
+        // each end of that ramp
+        let ramp = |peak: f64| {
+            if peak * jerk >= acceleration * acceleration {
+                (peak / acceleration + acceleration / jerk, acceleration / jerk)
+            } else {
+                let jerk_time = (peak / jerk).sqrt();
+                (2.0 * jerk_time, jerk_time)
+            }
+        };
+        // Speeding up and slowing down covers `peak * ramp`, which grows
+        // with `peak`, so the largest peak fitting the distance is found
+        // by bisection
+        let mut peak = velocity;
+        if peak * ramp(peak).0 > 1.0 {
+            let (mut low, mut high) = (0.0, velocity);
+            for _ in 0..100 {
+                peak = (low + high) / 2.0;
+                if peak * ramp(peak).0 > 1.0 {
+                    high = peak;
+                } else {
+                    low = peak;
+                }
+            }
+            peak = low;
+        }
+        let (ramp_time, jerk_time) = ramp(peak);
+        let reached = jerk * jerk_time;
+        let level = ramp_time - 2.0 * jerk_time;
+        let cruise = ((1.0 - peak * ramp_time) / peak).max(0.0);
+        Self::from_phases(&[
+            (jerk_time, 0.0, jerk),
+            (level, reached, 0.0),
+            (jerk_time, reached, -jerk),
+            (cruise, 0.0, 0.0),
+            (jerk_time, 0.0, -jerk),
+            (level, -reached, 0.0),
+            (jerk_time, -reached, jerk),
+        ])
+    }
+    
+    /// Chain phases of `(duration, acceleration at start, jerk)` from rest
+    fn from_phases(phases: &[(f64, f64, f64)]) -> Self {
+        let (mut time, mut position, mut velocity) = (0.0, 0.0, 0.0);
+        let phases = phases
+            .iter()
+            .filter(|&&(duration, _, _)| duration > 0.0)
+            .map(|&(duration, acceleration, jerk)| {
+                let phase = Phase {
+                    start: time,
+                    position,
+                    velocity,
+                    acceleration,
+                    jerk,
+                };
+                let (p, v, _) = phase.at(duration);
+                time += duration;
+                position = p;
+                velocity = v;
+                phase
+            })
+            .collect();
+        Self { phases, duration: time }
+    }
+    
+    pub(super) fn duration(&self) -> f64 {
+        self.duration
+    }
+    
+    /// Position, velocity and acceleration at `time`, holding at 1 once
+    /// the move is over
+    pub(super) fn at(&self, time: f64) -> (f64, f64, f64) {
+        if time >= self.duration {
+            return (1.0, 0.0, 0.0);
+        }
+        let index = self.phases.partition_point(|phase| phase.start <= time).saturating_sub(1);
+        match self.phases.get(index) {
+            Some(phase) => phase.at(time - phase.start),
+            None => (0.0, 0.0, 0.0),
+        }
+    }
+}
+
+impl Phase {
+    fn at(&self, t: f64) -> (f64, f64, f64) {
+        let Phase {
+            position: p,
+            velocity: v,
+            acceleration: a,
+            jerk: j,
+            ..
+        } = *self;
+        (
+            p + v * t + a * t * t / 2.0 + j * t * t * t / 6.0,
+            v + a * t + j * t * t / 2.0,
+            a + j * t,
+        )
+    }
+}
@@ -0,0 +1,163 @@
+//! Piecewise polynomials through waypoints
+
+/// Coefficients of a polynomial of degree 5 or less, lowest power first
+pub(super) type Polynomial = [f64; 6];
+
+/// Position, velocity, acceleration and jerk of `poly` at `u`
+pub(super) fn evaluate(poly: &Polynomial, u: f64) -> [f64; 4] {
+    let mut derivative = *poly;
+    let mut values = [0.0; 4];
+    for value in &mut values {
+        *value = derivative.iter().rev().fold(0.0, |sum, &c| sum * u + c);
+        derivative = differentiate(&derivative);
+    }
+    values
+}
+
+fn differentiate(poly: &Polynomial) -> Polynomial {
+    let mut derivative = [0.0; 6];
+    for power in 1..poly.len() {
+        derivative[power - 1] = poly[power] * power as f64;
+    }
+    derivative
+}
+
+/// Largest magnitude of `poly`'s velocity, acceleration and jerk over
+/// `0..=duration`
+pub(super) fn peaks(poly: &Polynomial, duration: f64) -> [f64; 3] {
+    let velocity = differentiate(poly);
+    let acceleration = differentiate(&velocity);
+    let jerk = differentiate(&acceleration);
+    [velocity, acceleration, jerk].map(|curve| max_abs(&curve, duration))
+}
+
+fn max_abs(poly: &Polynomial, duration: f64) -> f64 {
+    let value = |u: f64| poly.iter().rev().fold(0.0, |sum, &c| sum * u + c).abs();
+    roots(&differentiate(poly), 0.0, duration)
+        .into_iter()
+        .chain([0.0, duration])
+        .map(value)
+        .fold(0.0, f64::max)
+}
+
+/// Where `poly` crosses zero strictly between `low` and `high`
+///
+/// The roots of the derivative split the span into stretches where
+/// `poly` is monotonic, and each stretch changing sign holds one root,
+/// found by bisection.
+fn roots(poly: &Polynomial, low: f64, high: f64) -> Vec<f64> {
+    let degree = poly.iter().rposition(|&c| c != 0.0).unwrap_or(0);
+    if degree == 0 {
+        return Vec::new();
+    }
+    let value = |u: f64| poly.iter().rev().fold(0.0, |sum, &c| sum * u + c);
+    let mut bounds = vec![low];
+    bounds.extend(roots(&differentiate(poly), low, high));
+    bounds.push(high);
+    bounds
+        .windows(2)
+        .filter_map(|span| {
+            let (mut a, mut b) = (span[0], span[1]);
+            if value(a).signum() == value(b).signum() {
+                return None;
+            }
+            for _ in 0..100 {
+                let middle = (a + b) / 2.0;
+                if value(middle).signum() == value(a).signum() {
+                    a = middle;
+                } else {
+                    b = middle;
+                }
+            }
+            Some((a + b) / 2.0)
+        })
+        .filter(|&root| root > low && root < high)
+        .collect()
+}
+
+/// Cubic segments through `positions` at intervals `durations`, starting
+/// and ending at rest with continuous acceleration
+pub(super) fn cubic(positions: &[f64], durations: &[f64]) -> Vec<Polynomial> {
+    let n = positions.len();
+    let slope = |i: usize| (positions[i + 1] - positions[i]) / durations[i];
+    // Second derivatives at each waypoint, from the tridiagonal system
+    // of a spline clamped to zero velocity at both ends
+    let mut lower = vec![0.0; n];
+    let mut diagonal = vec![0.0; n];
+    let mut upper = vec![0.0; n];
+    let mut rhs = vec![0.0; n];
+    for i in 0..n {
+        let before = i.checked_sub(1).map(|k| durations[k]);
+        let after = durations.get(i).copied();
+        lower[i] = before.unwrap_or(0.0);
+        upper[i] = after.unwrap_or(0.0);
+        diagonal[i] = 2.0 * (before.unwrap_or(0.0) + after.unwrap_or(0.0));
+        let slope_before = i.checked_sub(1).map_or(0.0, slope);
+        let slope_after = if i + 1 < n { slope(i) } else { 0.0 };
+        rhs[i] = 6.0 * (slope_after - slope_before);
+    }
+    let moments = solve_tridiagonal(&lower, &mut diagonal, &upper, &mut rhs);
+    (0..n - 1)
+        .map(|i| {
+            let h = durations[i];
+            [
+                positions[i],
+                slope(i) - h * (2.0 * moments[i] + moments[i + 1]) / 6.0,
+                moments[i] / 2.0,
+                (moments[i + 1] - moments[i]) / (6.0 * h),
+                0.0,
+                0.0,
+            ]
+        })
+        .collect()
+}
+
+fn solve_tridiagonal(lower: &[f64], diagonal: &mut [f64], upper: &[f64], rhs: &mut [f64]) -> Vec<f64> {
+    let n = diagonal.len();
+    for i in 1..n {
+        let factor = lower[i] / diagonal[i - 1];
+        diagonal[i] -= factor * upper[i - 1];
+        rhs[i] -= factor * rhs[i - 1];
+    }
+    let mut solution = vec![0.0; n];
+    for i in (0..n).rev() {
+        let next = solution.get(i + 1).copied().unwrap_or(0.0);
+        solution[i] = (rhs[i] - upper[i] * next) / diagonal[i];
+    }
+    solution
+}
+
+/// Quintic segments through `positions` at intervals `durations`, at rest
+/// at both ends and with zero acceleration at every waypoint
+///
+/// A waypoint between two moves in the same direction is passed at the
+/// mean of their average velocities, and one where the direction reverses
+/// at rest.
+pub(super) fn quintic(positions: &[f64], durations: &[f64]) -> Vec<Polynomial> {
+    let n = positions.len();
+    let slope = |i: usize| (positions[i + 1] - positions[i]) / durations[i];
+    let velocities: Vec<f64> = (0..n)
+        .map(|i| {
+            if i == 0 || i + 1 == n || slope(i - 1) * slope(i) <= 0.0 {
+                0.0
+            } else {
+                (slope(i - 1) + slope(i)) / 2.0
+            }
+        })
+        .collect();
+    (0..n - 1)
+        .map(|i| {
+            let t = durations[i];
+            let (v0, v1) = (velocities[i], velocities[i + 1]);
+            let distance = positions[i + 1] - positions[i];
+            [
+                positions[i],
+                v0,
+                0.0,
+                (20.0 * distance - (8.0 * v1 + 12.0 * v0) * t) / (2.0 * t.powi(3)),
+                (-30.0 * distance + (14.0 * v1 + 16.0 * v0) * t) / (2.0 * t.powi(4)),
+                (12.0 * distance - 6.0 * (v1 + v0) * t) / (2.0 * t.powi(5)),
+            ]
+        })
+        .collect()
+}
@@ -0,0 +1,408 @@
+//! Executing without blocking the caller
+
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Context as TaskContext, Poll, Waker};
+
+use crate::algorithm::context::Job;
+use crate::error::CoreError;
+use crate::preemption::ExecutionClass;
+use crate::CoreEngine;
+
+/// Where an asynchronous execution has got
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExecutionState {
+    /// Waiting for a worker thread
+    #[default]
+    Queued,
+    /// The algorithm is running
+    Running,
+    /// The output, or the error, is ready
+    Finished,
+}
+
+#[derive(Default)]
+struct Slot {
+    state: ExecutionState,
+    result: Option<Result<Vec<u8>, CoreError>>,
+    waker: Option<Waker>,
+}
+
+struct Shared {
+    job: Arc<Job>,
+    slot: Mutex<Slot>,
+    finished: Condvar,
+}
+
+impl Shared {
+    fn slot(&self) -> MutexGuard<'_, Slot> {
+        self.slot.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    /// Move to `Running`, unless the execution was cancelled while queued
+    fn start(&self) -> bool {
+        if self.job.cancel.is_cancelled() {
+            self.finish(Err(CoreError::Cancelled));
+            return false;
+        }
+        self.slot().state = ExecutionState::Running;
+        true
+    }
+    
+    fn finish(&self, result: Result<Vec<u8>, CoreError>) {
+        let waker = {
+            let mut slot = self.slot();
+            slot.state = ExecutionState::Finished;
+            slot.result = Some(result);
+            slot.waker.take()
+        };
+        self.finished.notify_all();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// Take the result out of a finished slot
+fn take_result(slot: &mut Slot) -> Result<Vec<u8>, CoreError> {
+    slot.result
+        .take()
+        .unwrap_or_else(|| Err(CoreError::ProcessingFailed("output was already taken".to_string())))
+}
+
+/// Handle to an execution running on a worker thread
+///
+/// Await the handle, or block on `wait`, for the algorithm's output. A
+/// cancelled execution resolves to `CoreError::Cancelled`: one still queued
+/// never starts, and a running one is asked to stop through
+/// `Context::is_cancelled`, while an algorithm that ignores the request runs
+/// to completion with its output discarded. Dropping the handle cancels the
+/// execution.
+pub struct AsyncExecution {
+    shared: Arc<Shared>,
+}
+
+impl AsyncExecution {
+    /// Ask the execution to stop
+    pub fn cancel(&self) {
+        self.shared.job.cancel.cancel();
+    }
+    
+    pub fn is_cancelled(&self) -> bool {
+        self.shared.job.cancel.is_cancelled()
+    }
+    
+    pub fn state(&self) -> ExecutionState {
+        self.shared.slot().state
+    }
+    
+    pub fn is_finished(&self) -> bool {
+        self.state() == ExecutionState::Finished
+    }
+    
+    /// Fraction of the work done, as the algorithm last reported it through
+    /// `Context::report_progress`
+    ///
+    /// `None` until the algorithm reports, which algorithms that do not use
+    /// their context never do.
+    pub fn progress(&self) -> Option<f32> {
+        self.shared.job.progress()
+    }
+    
+    /// Block until the execution finishes, returning its output
+    pub fn wait(self) -> Result<Vec<u8>, CoreError> {
+        let mut slot = self.shared.slot();
+        while slot.state != ExecutionState::Finished {
+            slot = self.shared.finished.wait(slot).unwrap_or_else(|e| e.into_inner());
+        }
+        take_result(&mut slot)
+    }
+}
+
+impl Future for AsyncExecution {
+    type Output = Result<Vec<u8>, CoreError>;
+    
+    fn poll(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        let mut slot = self.shared.slot();
+        if slot.state == ExecutionState::Finished {
+            return Poll::Ready(take_result(&mut slot));
+        }
+        slot.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for AsyncExecution {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+impl CoreEngine {
+    /// Start executing an algorithm on a worker thread, returning a handle
+    /// to it at once
+    ///
+    /// The execution runs like one through `executor`: against the
+    /// registry, hooks and log levels as they are now and on its own copy of
+    /// engine memory, whose writes are discarded. Output caches are
+    /// bypassed, and a panic becomes `CoreError::ProcessingFailed`. It runs
+    /// on the engine's worker pool, sized with `CoreEngineBuilder::threads`;
+    /// with the `tokio` feature, one started inside a Tokio runtime runs on
+    /// the runtime's blocking threads instead. Algorithms of
+    /// `ExecutionClass::Background` run on the background pool, sized with
+    /// `CoreEngineBuilder::background_threads`, and pause at their yield
+    /// points while more urgent work runs.
+    ///
+    /// Fails with `CoreError::AlgorithmNotFound` for an unknown ID without
+    /// starting anything.
+    pub fn execute_algorithm_async(&self, algorithm_id: &str, input_data: &[u8]) -> Result<AsyncExecution, CoreError> {
+        if self.get_algorithm(algorithm_id).is_none() {
+            return Err(CoreError::AlgorithmNotFound(algorithm_id.to_string()));
+        }
+        let lane = self.lane(algorithm_id);
+        let background = lane.class() == ExecutionClass::Background;
+        let job = Arc::new(Job::new().with_lane(lane));
+        let shared = Arc::new(Shared {
+            job: Arc::clone(&job),
+            slot: Mutex::default(),
+            finished: Condvar::new(),
+        });
+        let executor = self.executor_with_context(self.context.with_job(job));
+        let worker_shared = Arc::clone(&shared);
+        let algorithm_id = algorithm_id.to_string();
+        let input = input_data.to_vec();
+        let work = move || {
+            // Pausing for more urgent work before starting counts as queued
+            let lane = worker_shared.job.lane.as_ref();
+            let running = lane.map(|lane| lane.start(&worker_shared.job.cancel));
+            if !worker_shared.start() {
+                return;
+            }
+            let result = panic::catch_unwind(AssertUnwindSafe(|| executor.execute(&algorithm_id, &input)))
+                .unwrap_or_else(|payload| {
+                    Err(CoreError::ProcessingFailed(format!(
+                        "'{}' panicked: {}",
+                        algorithm_id,
+                        crate::panic_message(payload.as_ref())
+                    )))
+                });
+            if let Some(lane) = lane {
+                lane.finish();
+            }
+            drop(running);
+            worker_shared.finish(if worker_shared.job.cancel.is_cancelled() {
+                Err(CoreError::Cancelled)
+            } else {
+                result.map(|output| output.data)
+            });
+        };
+        
+        if background {
+            self.background.spawn(work);
+            return Ok(AsyncExecution { shared });
+        }
+        #[cfg(feature = "tokio")]
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn_blocking(work);
+            return Ok(AsyncExecution { shared });
+        }
+        self.pool.spawn(work);
+        Ok(AsyncExecution { shared })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::builtins::Scale;
+    use crate::algorithm::context::Context;
+    use crate::algorithm::{samples, Algorithm, AlgorithmMetadata};
+    use crate::memory::MemoryManager;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc;
+    use std::task::Wake;
+    use std::thread;
+    use std::time::{Duration, Instant};
+    
+    /// Plans in many short steps, reporting progress and honoring
+    /// cancellation
+    struct Planner;
+    
+    impl Algorithm for Planner {
+        fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            let mut output = Vec::new();
+            Context::with_default(|context| self.process_in_context(input, &mut output, memory, context))?;
+            Ok(output)
+        }
+        
+        fn process_in_context(
+            &self,
+            _input: &[u8],
+            output: &mut Vec<u8>,
+            _memory: &mut MemoryManager,
+            context: &mut Context<'_>,
+        ) -> Result<(), CoreError> {
+            const STEPS: usize = 10_000;
+            for step in 0..STEPS {
+                if context.is_cancelled() {
+                    return Err(CoreError::Cancelled);
+                }
+                context.report_progress(step as f32 / STEPS as f32);
+                thread::sleep(Duration::from_millis(1));
+            }
+            output.push(1);
+            Ok(())
+        }
+        
+        fn id(&self) -> &str {
+            "planner"
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata::default()
+        }
+    }
+    
+    /// Blocks until released, counting its runs
+    struct Gate {
+        release: Mutex<mpsc::Receiver<()>>,
+        runs: Arc<AtomicUsize>,
+    }
+    
+    impl Algorithm for Gate {
+        fn process(&self, _input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            let _ = self.release.lock().unwrap().recv();
+            Ok(Vec::new())
+        }
+        
+        fn id(&self) -> &str {
+            "gate"
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata::default()
+        }
+    }
+    
+    fn eventually(condition: impl Fn() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !condition() {
+            assert!(Instant::now() < deadline, "condition not reached");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+    
+    /// Poll a future to completion on this thread
+    fn block_on<F: Future>(future: F) -> F::Output {
+        struct Unpark(thread::Thread);
+        
+        impl Wake for Unpark {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+        
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = TaskContext::from_waker(&waker);
+        let mut future = std::pin::pin!(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+    
+    #[test]
+    fn test_async_output_matches_sync() {
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(Scale::new(3.0).unwrap())).unwrap();
+        let input = samples::f32_to_bytes(&[1.0, -2.0]);
+        
+        let expected = engine.execute_algorithm(Scale::ID, &input).unwrap();
+        assert_eq!(engine.execute_algorithm_async(Scale::ID, &input).unwrap().wait().unwrap(), expected);
+        let execution = engine.execute_algorithm_async(Scale::ID, &input).unwrap();
+        assert_eq!(block_on(execution).unwrap(), expected);
+        
+        assert!(matches!(
+            engine.execute_algorithm_async("absent", &[]),
+            Err(CoreError::AlgorithmNotFound(_))
+        ));
+    }
+    
+    #[test]
+    fn test_cancel_stops_running_algorithm() {
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(Planner)).unwrap();
+        let execution = engine.execute_algorithm_async("planner", &[]).unwrap();
+        
+        eventually(|| execution.progress().is_some_and(|fraction| fraction > 0.0));
+        assert_eq!(execution.state(), ExecutionState::Running);
+        assert!(!execution.is_finished());
+        execution.cancel();
+        assert!(execution.is_cancelled());
+        assert_eq!(execution.wait(), Err(CoreError::Cancelled));
+    }
+    
+    #[test]
+    fn test_cancelled_while_queued_never_starts() {
+        let (release, gate) = mpsc::channel();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let mut engine = CoreEngine::builder().threads(1).build();
+        engine
+            .register(Box::new(Gate {
+                release: Mutex::new(gate),
+                runs: Arc::clone(&runs),
+            }))
+            .unwrap();
+        
+        let first = engine.execute_algorithm_async("gate", &[]).unwrap();
+        eventually(|| first.state() == ExecutionState::Running);
+        let second = engine.execute_algorithm_async("gate", &[]).unwrap();
+        assert_eq!(second.state(), ExecutionState::Queued);
+        second.cancel();
+        
+        release.send(()).unwrap();
+        assert_eq!(first.wait(), Ok(Vec::new()));
+        assert_eq!(second.wait(), Err(CoreError::Cancelled));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+    
+    #[test]
+    fn test_panic_becomes_processing_failure() {
+        struct Exploding;
+        
+        impl Algorithm for Exploding {
+            fn process(&self, _input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+                panic!("planner exploded");
+            }
+            
+            fn id(&self) -> &str {
+                "exploding"
+            }
+            
+            fn metadata(&self) -> AlgorithmMetadata {
+                AlgorithmMetadata::default()
+            }
+        }
+        
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(Exploding)).unwrap();
+        let result = engine.execute_algorithm_async("exploding", &[]).unwrap().wait();
+        assert!(matches!(result, Err(CoreError::ProcessingFailed(msg)) if msg.contains("planner exploded")));
+    }
+    
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_awaits_inside_tokio_runtime() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(Scale::new(2.0).unwrap())).unwrap();
+        let input = samples::f32_to_bytes(&[4.0]);
+        let output = runtime.block_on(async { engine.execute_algorithm_async(Scale::ID, &input).unwrap().await });
+        assert_eq!(output.unwrap(), samples::f32_to_bytes(&[8.0]));
+    }
+}
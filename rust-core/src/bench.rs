@@ -0,0 +1,220 @@
+//! Lightweight harness for measuring algorithm throughput
+
+use serde::{Deserialize, Serialize};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use crate::algorithm::Algorithm;
+use crate::error::CoreError;
+use crate::math;
+use crate::memory::MemoryManager;
+
+/// Latency and throughput measured over repeated executions
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BenchReport {
+    pub iterations: usize,
+    pub input_bytes: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+    /// Input megabytes processed per second at the median latency
+    pub throughput_mb_s: f64,
+}
+
+/// Time `iterations` executions of `algo` over `input`
+///
+/// All iterations share one `MemoryManager` and one output buffer, so the
+/// numbers reflect the algorithm rather than setup costs.
+pub fn benchmark_algorithm(algo: &dyn Algorithm, input: &[u8], iterations: usize) -> Result<BenchReport, CoreError> {
+    if iterations == 0 {
+        return Err(CoreError::InvalidParameter("iterations must be at least 1".to_string()));
+    }
+    
+    let mut memory = MemoryManager::new();
+    let mut output = Vec::new();
+    let mut samples = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        output.clear();
+        let start = Instant::now();
+        algo.process_into(input, &mut output, &mut memory)?;
+        samples.push(start.elapsed());
+    }
+    samples.sort();
+    
+    let median = samples[samples.len() / 2];
+    let throughput_mb_s = if median.is_zero() {
+        f64::INFINITY
+    } else {
+        input.len() as f64 / 1_000_000.0 / median.as_secs_f64()
+    };
+    Ok(BenchReport {
+        iterations,
+        input_bytes: input.len(),
+        min: samples[0],
+        median,
+        max: samples[samples.len() - 1],
+        throughput_mb_s,
+    })
+}
+
+/// Median latency of a `math` kernel next to its scalar version
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KernelComparison {
+    pub kernel: String,
+    pub scalar: Duration,
+    pub simd: Duration,
+    /// Scalar latency over vectorized latency
+    pub speedup: f64,
+}
+
+fn median_latency(iterations: usize, mut run: impl FnMut()) -> Duration {
+    let mut samples: Vec<Duration> = (0..iterations)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .collect();
+    samples.sort();
+    samples[samples.len() / 2]
+}
+
+fn compare(kernel: &str, iterations: usize, scalar: impl FnMut(), simd: impl FnMut()) -> KernelComparison {
+    let scalar = median_latency(iterations, scalar);
+    let simd = median_latency(iterations, simd);
+    KernelComparison {
+        kernel: kernel.to_string(),
+        scalar,
+        simd,
+        speedup: scalar.as_secs_f64() / simd.as_secs_f64().max(f64::MIN_POSITIVE),
+    }
+}
+
+/// Time each `math` kernel against its scalar version over `len`-element
+/// inputs, `iterations` times apiece
+///
+/// Matrix multiplication uses square matrices of about `len` elements, in
+/// `f32` and `f64`, and quaternion products run `len / 4` times per
+/// iteration.
+pub fn compare_kernels(len: usize, iterations: usize) -> Result<Vec<KernelComparison>, CoreError> {
+    if iterations == 0 {
+        return Err(CoreError::InvalidParameter("iterations must be at least 1".to_string()));
+    }
+    let a: Vec<f32> = (0..len).map(|i| (i as f32 * 0.37).sin()).collect();
+    let b: Vec<f32> = (0..len).map(|i| (i as f32 * 0.11).cos()).collect();
+    let a64: Vec<f64> = a.iter().map(|&x| x as f64).collect();
+    let b64: Vec<f64> = b.iter().map(|&x| x as f64).collect();
+    let side = (len as f64).sqrt() as usize;
+    let square = &a[..side * side];
+    let square64 = &a64[..side * side];
+    let matrix = [[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]];
+    let quaternions: Vec<[f32; 4]> = a.chunks_exact(4).map(|q| [q[0], q[1], q[2], q[3]]).collect();
+    let mut rotated = Vec::with_capacity(len);
+    const IDENTITY: [f32; 4] = [1.0, 0.0, 0.0, 0.0];
+    
+    Ok(vec![
+        compare(
+            "dot",
+            iterations,
+            || {
+                black_box(math::scalar::dot(black_box(&a), black_box(&b)));
+            },
+            || {
+                black_box(math::dot(black_box(&a), black_box(&b)));
+            },
+        ),
+        compare(
+            "mat_mul",
+            iterations,
+            || {
+                black_box(math::scalar::mat_mul(black_box(square), square, side, side, side));
+            },
+            || {
+                black_box(math::mat_mul(black_box(square), square, side, side, side));
+            },
+        ),
+        compare(
+            "dot_f64",
+            iterations,
+            || {
+                black_box(math::scalar::dot_f64(black_box(&a64), black_box(&b64)));
+            },
+            || {
+                black_box(math::dot_f64(black_box(&a64), black_box(&b64)));
+            },
+        ),
+        compare(
+            "mat_mul_f64",
+            iterations,
+            || {
+                black_box(math::scalar::mat_mul_f64(black_box(square64), square64, side, side, side));
+            },
+            || {
+                black_box(math::mat_mul_f64(black_box(square64), square64, side, side, side));
+            },
+        ),
+        compare(
+            "quaternion_multiply",
+            iterations,
+            || {
+                black_box(quaternions.iter().fold(IDENTITY, |q, &r| math::scalar::quaternion_multiply(q, r)));
+            },
+            || {
+                black_box(quaternions.iter().fold(IDENTITY, |q, &r| math::quaternion_multiply(q, r)));
+            },
+        ),
+        {
+            let mut scalar_rotated = Vec::with_capacity(len);
+            compare(
+                "rotate_points",
+                iterations,
+                || {
+                    scalar_rotated.clear();
+                    math::scalar::rotate_points(&matrix, black_box(&a), &mut scalar_rotated);
+                    black_box(&scalar_rotated);
+                },
+                || {
+                    rotated.clear();
+                    math::rotate_points(&matrix, black_box(&a), &mut rotated);
+                    black_box(&rotated);
+                },
+            )
+        },
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::builtins::PassThrough;
+    
+    #[test]
+    fn test_report_fields_are_sensible() {
+        let input = vec![0u8; 1 << 20];
+        let report = benchmark_algorithm(&PassThrough, &input, 25).unwrap();
+        
+        assert_eq!(report.iterations, 25);
+        assert_eq!(report.input_bytes, input.len());
+        assert!(report.min <= report.median);
+        assert!(report.median <= report.max);
+        assert!(report.max > Duration::ZERO);
+        assert!(report.throughput_mb_s > 0.0);
+    }
+    
+    #[test]
+    fn test_zero_iterations_rejected() {
+        assert!(benchmark_algorithm(&PassThrough, &[], 0).is_err());
+        assert!(compare_kernels(64, 0).is_err());
+    }
+    
+    #[test]
+    fn test_kernel_comparison_covers_each_kernel() {
+        let comparisons = compare_kernels(4096, 5).unwrap();
+        let kernels: Vec<&str> = comparisons.iter().map(|c| c.kernel.as_str()).collect();
+        assert_eq!(
+            kernels,
+            ["dot", "mat_mul", "dot_f64", "mat_mul_f64", "quaternion_multiply", "rotate_points"]
+        );
+        assert!(comparisons.iter().all(|c| c.speedup > 0.0 && c.speedup.is_finite()));
+    }
+}
@@ -0,0 +1,329 @@
+//! In-process publish/subscribe bus with typed topics
+//!
+//! Producers publish values of one Rust type to a named topic, and every
+//! subscriber to that topic receives its own clone through a bounded queue.
+//! Each subscription chooses what happens when its queue is full: keep only
+//! the latest messages, or hold the publisher back until there is room.
+
+use std::any::{self, Any};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::error::CoreError;
+use crate::sensor::{Sensor, SensorFrame};
+use crate::CoreEngine;
+
+/// What a subscription does with a message that finds its queue full
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DropPolicy {
+    /// Discard the oldest queued message, so the queue always holds the
+    /// newest; with a capacity of 1 the subscriber only ever sees the latest
+    #[default]
+    LatestOnly,
+    /// Block the publisher until the subscriber makes room
+    Backpressure,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// One subscriber's queue
+struct Queue<T> {
+    state: Mutex<QueueState<T>>,
+    // Signalled when a message arrives, and when room frees up
+    arrived: Condvar,
+    room: Condvar,
+    capacity: usize,
+    policy: DropPolicy,
+    dropped: AtomicU64,
+}
+
+struct QueueState<T> {
+    messages: VecDeque<T>,
+    // Set once the subscription is dropped, releasing blocked publishers
+    closed: bool,
+}
+
+impl<T> Queue<T> {
+    /// Queue `message`, returning whether the subscriber will see it
+    fn push(&self, message: T) -> bool {
+        let mut state = lock(&self.state);
+        if state.messages.len() >= self.capacity {
+            match self.policy {
+                DropPolicy::LatestOnly => {
+                    state.messages.pop_front();
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                DropPolicy::Backpressure => {
+                    while state.messages.len() >= self.capacity && !state.closed {
+                        state = self.room.wait(state).unwrap_or_else(|e| e.into_inner());
+                    }
+                }
+            }
+        }
+        if state.closed {
+            return false;
+        }
+        state.messages.push_back(message);
+        self.arrived.notify_one();
+        true
+    }
+    
+    fn pop(&self) -> Option<T> {
+        let message = lock(&self.state).messages.pop_front();
+        if message.is_some() {
+            self.room.notify_all();
+        }
+        message
+    }
+}
+
+/// Subscribers of one topic
+struct Topic<T> {
+    subscribers: Mutex<Vec<Arc<Queue<T>>>>,
+}
+
+struct TopicEntry {
+    type_name: &'static str,
+    topic: Arc<dyn Any + Send + Sync>,
+}
+
+/// Registry of named topics, each carrying messages of a single type
+///
+/// Clones share the same topics, so a bus can be handed to every producer
+/// and consumer.
+#[derive(Clone, Default)]
+pub struct MessageBus {
+    topics: Arc<Mutex<HashMap<String, TopicEntry>>>,
+}
+
+impl MessageBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// The topic `name`, created for `T` on first use
+    ///
+    /// Fails with `CoreError::SchemaMismatch` if the topic already carries
+    /// another type.
+    fn topic<T: Send + 'static>(&self, name: &str) -> Result<Arc<Topic<T>>, CoreError> {
+        let mut topics = lock(&self.topics);
+        let entry = topics.entry(name.to_string()).or_insert_with(|| TopicEntry {
+            type_name: any::type_name::<T>(),
+            topic: Arc::new(Topic::<T> {
+                subscribers: Mutex::new(Vec::new()),
+            }),
+        });
+        Arc::clone(&entry.topic).downcast::<Topic<T>>().map_err(|_| {
+            CoreError::SchemaMismatch(format!(
+                "topic '{}' carries {}, not {}",
+                name,
+                entry.type_name,
+                any::type_name::<T>()
+            ))
+        })
+    }
+    
+    /// A handle publishing `T` messages to topic `name`
+    pub fn publisher<T: Clone + Send + 'static>(&self, name: &str) -> Result<Publisher<T>, CoreError> {
+        Ok(Publisher {
+            topic: self.topic(name)?,
+            name: name.to_string(),
+        })
+    }
+    
+    /// Subscribe to topic `name`, queueing up to `capacity` messages and
+    /// applying `policy` when full
+    pub fn subscribe<T: Clone + Send + 'static>(
+        &self,
+        name: &str,
+        capacity: usize,
+        policy: DropPolicy,
+    ) -> Result<Subscription<T>, CoreError> {
+        if capacity == 0 {
+            return Err(CoreError::InvalidParameter("subscriber capacity must be at least 1".to_string()));
+        }
+        let queue = Arc::new(Queue {
+            state: Mutex::new(QueueState {
+                messages: VecDeque::with_capacity(capacity),
+                closed: false,
+            }),
+            arrived: Condvar::new(),
+            room: Condvar::new(),
+            capacity,
+            policy,
+            dropped: AtomicU64::new(0),
+        });
+        lock(&self.topic::<T>(name)?.subscribers).push(Arc::clone(&queue));
+        Ok(Subscription { queue })
+    }
+    
+    /// Names of every topic published or subscribed to, sorted
+    pub fn topics(&self) -> Vec<String> {
+        let mut names: Vec<String> = lock(&self.topics).keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+/// Producer end of a topic
+#[derive(Clone)]
+pub struct Publisher<T> {
+    topic: Arc<Topic<T>>,
+    name: String,
+}
+
+impl<T: Clone + Send + 'static> Publisher<T> {
+    /// Name of the topic published to
+    pub fn topic(&self) -> &str {
+        &self.name
+    }
+    
+    /// Deliver `message` to every current subscriber
+    ///
+    /// Returns how many subscribers queued it. Blocks while a
+    /// `DropPolicy::Backpressure` subscriber's queue is full. Subscriptions
+    /// that have been dropped are forgotten.
+    pub fn publish(&self, message: T) -> usize {
+        let subscribers: Vec<Arc<Queue<T>>> = {
+            let mut subscribers = lock(&self.topic.subscribers);
+            subscribers.retain(|queue| !lock(&queue.state).closed);
+            subscribers.clone()
+        };
+        subscribers.iter().filter(|queue| queue.push(message.clone())).count()
+    }
+}
+
+impl Publisher<SensorFrame> {
+    /// Read one frame from `sensor` and publish it
+    ///
+    /// A sensor error is returned as is, with nothing published.
+    pub fn publish_from(&self, sensor: &mut dyn Sensor) -> Result<usize, CoreError> {
+        Ok(self.publish(sensor.read_frame()?))
+    }
+}
+
+/// Consumer end of a topic, with its own bounded queue
+///
+/// Dropping the subscription unsubscribes it.
+pub struct Subscription<T> {
+    queue: Arc<Queue<T>>,
+}
+
+impl<T> Subscription<T> {
+    /// Take the oldest queued message without waiting, if there is one
+    pub fn try_recv(&self) -> Option<T> {
+        self.queue.pop()
+    }
+    
+    /// Wait up to `timeout` for a message
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<T> {
+        let state = lock(&self.queue.state);
+        let (mut state, _) = self
+            .queue
+            .arrived
+            .wait_timeout_while(state, timeout, |state| state.messages.is_empty())
+            .unwrap_or_else(|e| e.into_inner());
+        let message = state.messages.pop_front();
+        drop(state);
+        if message.is_some() {
+            self.queue.room.notify_all();
+        }
+        message
+    }
+    
+    /// Take every queued message, oldest first
+    pub fn drain(&self) -> Vec<T> {
+        let messages: Vec<T> = lock(&self.queue.state).messages.drain(..).collect();
+        self.queue.room.notify_all();
+        messages
+    }
+    
+    /// Number of messages waiting
+    pub fn len(&self) -> usize {
+        lock(&self.queue.state).messages.len()
+    }
+    
+    /// Whether no messages are waiting
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    
+    /// Messages discarded by `DropPolicy::LatestOnly` to make room
+    pub fn dropped(&self) -> u64 {
+        self.queue.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl<T> Drop for Subscription<T> {
+    fn drop(&mut self) {
+        lock(&self.queue.state).closed = true;
+        self.queue.room.notify_all();
+    }
+}
+
+impl CoreEngine {
+    /// The engine's message bus
+    pub fn bus(&self) -> &MessageBus {
+        &self.bus
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensor::MockSensor;
+    use std::thread;
+    
+    #[test]
+    fn test_latest_only_keeps_newest_and_topics_are_typed() {
+        let bus = MessageBus::new();
+        let publisher = bus.publisher::<u32>("odometry").unwrap();
+        let latest = bus.subscribe::<u32>("odometry", 1, DropPolicy::LatestOnly).unwrap();
+        let recent = bus.subscribe::<u32>("odometry", 3, DropPolicy::LatestOnly).unwrap();
+        
+        for value in 0..5 {
+            assert_eq!(publisher.publish(value), 2);
+        }
+        assert_eq!(latest.drain(), vec![4]);
+        assert_eq!(latest.dropped(), 4);
+        assert_eq!(recent.drain(), vec![2, 3, 4]);
+        
+        drop(latest);
+        assert_eq!(publisher.publish(5), 1);
+        assert!(matches!(
+            bus.subscribe::<f32>("odometry", 1, DropPolicy::LatestOnly),
+            Err(CoreError::SchemaMismatch(_))
+        ));
+        assert!(bus.subscribe::<u32>("odometry", 0, DropPolicy::LatestOnly).is_err());
+        
+        let frames = bus.publisher::<SensorFrame>("imu").unwrap();
+        let imu = bus.subscribe::<SensorFrame>("imu", 4, DropPolicy::LatestOnly).unwrap();
+        let mut sensor = MockSensor::new("imu", 100.0, vec![0.5]).unwrap();
+        assert_eq!(frames.publish_from(&mut sensor).unwrap(), 1);
+        assert_eq!(imu.try_recv().unwrap().sensor_id, "imu");
+        assert_eq!(bus.topics(), ["imu", "odometry"]);
+    }
+    
+    #[test]
+    fn test_backpressure_holds_publisher_until_consumed() {
+        let bus = MessageBus::new();
+        let publisher = bus.publisher::<u32>("commands").unwrap();
+        let subscription = bus.subscribe::<u32>("commands", 2, DropPolicy::Backpressure).unwrap();
+        
+        let producer = thread::spawn(move || (0..10).map(|value| publisher.publish(value)).sum::<usize>());
+        let mut received = Vec::new();
+        while received.len() < 10 {
+            if let Some(value) = subscription.recv_timeout(Duration::from_secs(5)) {
+                assert!(subscription.len() <= 2);
+                received.push(value);
+            }
+        }
+        assert_eq!(producer.join().unwrap(), 10);
+        assert_eq!(received, (0..10).collect::<Vec<_>>());
+        assert_eq!(subscription.dropped(), 0);
+    }
+}
@@ -0,0 +1,228 @@
+//! Memoization of pure algorithm outputs
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::algorithm::{AlgorithmOutput, Pure};
+use crate::error::CoreError;
+use crate::hashing::Hasher;
+use crate::CoreEngine;
+
+/// Least-recently-used map from input bytes to the output they produced
+///
+/// Entries are keyed by the engine hasher's digest of the input and keep the
+/// input itself, so a hit is always for identical bytes and a digest
+/// collision is only a miss.
+pub(crate) struct OutputCache {
+    capacity: usize,
+    hasher: Arc<dyn Hasher>,
+    entries: HashMap<Vec<u8>, Entry>,
+    // Monotonic use counter; the entry with the oldest stamp is evicted
+    clock: u64,
+}
+
+struct Entry {
+    input: Vec<u8>,
+    output: AlgorithmOutput,
+    last_used: u64,
+}
+
+impl OutputCache {
+    fn new(capacity: usize, hasher: Arc<dyn Hasher>) -> Self {
+        Self {
+            capacity,
+            hasher,
+            entries: HashMap::with_capacity(capacity),
+            clock: 0,
+        }
+    }
+    
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+    
+    pub(crate) fn clear(&mut self) {
+        self.entries = HashMap::new();
+    }
+    
+    pub(crate) fn get(&mut self, input: &[u8]) -> Option<AlgorithmOutput> {
+        self.clock += 1;
+        let entry = self.entries.get_mut(&self.hasher.digest(input))?;
+        if entry.input != input {
+            return None;
+        }
+        entry.last_used = self.clock;
+        Some(entry.output.clone())
+    }
+    
+    pub(crate) fn insert(&mut self, input: &[u8], output: AlgorithmOutput) {
+        let key = self.hasher.digest(input);
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.clock += 1;
+        let entry = Entry {
+            input: input.to_vec(),
+            output,
+            last_used: self.clock,
+        };
+        self.entries.insert(key, entry);
+    }
+    
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+impl CoreEngine {
+    /// Register an algorithm whose output depends only on its input
+    ///
+    /// Only algorithms registered this way can have a cache enabled.
+    pub fn register_pure<A: Pure + 'static>(&mut self, algorithm: A) -> Result<(), CoreError> {
+        let id = algorithm.id().to_string();
+        self.registry.register(Box::new(algorithm))?;
+        self.pure.insert(id);
+        Ok(())
+    }
+    
+    /// Cache up to `capacity` outputs of a pure algorithm
+    ///
+    /// Later executions of `algorithm_id` on input seen before return the
+    /// stored output without running the algorithm, so a hit also skips any
+    /// memory access the algorithm would have made. Once full, the least
+    /// recently used entry is evicted. Enabling again resets the cache.
+    pub fn enable_cache(&mut self, algorithm_id: &str, capacity: usize) -> Result<(), CoreError> {
+        if !self.pure.contains(algorithm_id) {
+            return Err(CoreError::InvalidParameter(format!(
+                "'{}' was not registered as a pure algorithm",
+                algorithm_id
+            )));
+        }
+        if capacity == 0 {
+            return Err(CoreError::InvalidParameter("cache capacity must be positive".to_string()));
+        }
+        self.caches.insert(algorithm_id.to_string(), OutputCache::new(capacity, Arc::clone(&self.hasher)));
+        Ok(())
+    }
+    
+    /// Drop the cache of an algorithm, if it has one
+    pub fn disable_cache(&mut self, algorithm_id: &str) {
+        self.caches.remove(algorithm_id);
+    }
+    
+    /// Number of outputs currently cached for an algorithm
+    pub fn cached_outputs(&self, algorithm_id: &str) -> usize {
+        self.caches.get(algorithm_id).map_or(0, OutputCache::len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{Algorithm, AlgorithmMetadata};
+    use crate::memory::MemoryManager;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    
+    /// Reverses its input, counting executions
+    struct Reverse(Arc<AtomicUsize>);
+    
+    impl Algorithm for Reverse {
+        fn process(&self, input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(input.iter().rev().copied().collect())
+        }
+        
+        fn id(&self) -> &str {
+            "reverse"
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata::default()
+        }
+    }
+    
+    impl Pure for Reverse {}
+    
+    fn cached_engine(capacity: usize) -> (CoreEngine, Arc<AtomicUsize>) {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let mut engine = CoreEngine::new();
+        engine.register_pure(Reverse(runs.clone())).unwrap();
+        engine.enable_cache("reverse", capacity).unwrap();
+        (engine, runs)
+    }
+    
+    #[test]
+    fn test_hit_skips_execution() {
+        let (mut engine, runs) = cached_engine(4);
+        let first = engine.execute_algorithm("reverse", &[1, 2, 3]).unwrap();
+        let second = engine.execute_algorithm("reverse", &[1, 2, 3]).unwrap();
+        assert_eq!(first, vec![3, 2, 1]);
+        assert_eq!(second, first);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        
+        engine.execute_algorithm("reverse", &[1, 2]).unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+    
+    #[test]
+    fn test_least_recently_used_is_evicted_at_capacity() {
+        let (mut engine, runs) = cached_engine(2);
+        engine.execute_algorithm("reverse", &[1]).unwrap();
+        engine.execute_algorithm("reverse", &[2]).unwrap();
+        // Touch [1] so [2] becomes the oldest entry
+        engine.execute_algorithm("reverse", &[1]).unwrap();
+        engine.execute_algorithm("reverse", &[3]).unwrap();
+        assert_eq!(engine.cached_outputs("reverse"), 2);
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        
+        engine.execute_algorithm("reverse", &[1]).unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 3, "[1] should still be cached");
+        engine.execute_algorithm("reverse", &[2]).unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 4, "[2] should have been evicted");
+    }
+    
+    /// Gives every input the same digest
+    struct Colliding;
+    
+    impl Hasher for Colliding {
+        fn name(&self) -> &'static str {
+            "colliding"
+        }
+        
+        fn digest(&self, _bytes: &[u8]) -> Vec<u8> {
+            vec![0]
+        }
+    }
+    
+    #[test]
+    fn test_digest_collision_is_a_miss() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let mut engine = CoreEngine::builder().hasher(Arc::new(Colliding)).build();
+        engine.register_pure(Reverse(runs.clone())).unwrap();
+        engine.enable_cache("reverse", 4).unwrap();
+        
+        assert_eq!(engine.execute_algorithm("reverse", &[1, 2]).unwrap(), vec![2, 1]);
+        assert_eq!(engine.execute_algorithm("reverse", &[3, 4]).unwrap(), vec![4, 3]);
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        assert_eq!(engine.execute_algorithm("reverse", &[3, 4]).unwrap(), vec![4, 3]);
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+    
+    #[test]
+    fn test_cache_requires_pure_registration() {
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(Reverse(Arc::default()))).unwrap();
+        assert!(engine.enable_cache("reverse", 8).is_err());
+        assert!(engine.enable_cache("passthrough", 8).is_err());
+        
+        let (mut engine, _) = cached_engine(1);
+        assert!(engine.enable_cache("reverse", 0).is_err());
+    }
+}
@@ -0,0 +1,207 @@
+//! Calibration of sensors and actuators, kept on disk per device
+//!
+//! A `CalibrationStore` keeps every `Calibration` saved for a device ID,
+//! numbered by version, so a deployment carries its IMU biases, camera
+//! intrinsics, encoder offsets and joint limits from one run to the next.
+//! Hand the store to a `SensorManager` and sensors added to it start from
+//! their stored calibration; `SensorManager::start_calibration` measures a
+//! new one and, once it validates, writes it back. Algorithms hold an
+//! `Arc` of the store and query it by device ID, as they would a
+//! `ParameterServer`.
+
+mod routine;
+mod store;
+
+pub use routine::CalibrationRoutine;
+pub use store::{CalibrationRecord, CalibrationStore};
+
+use serde::{Deserialize, Serialize};
+
+use crate::algorithm::trajectory::JointLimits;
+use crate::error::CoreError;
+use crate::sensor::imu::ImuCalibration;
+
+/// Gyroscope biases above this, in rad/s, mean the device moved while
+/// being calibrated
+const MAX_GYRO_BIAS: f32 = 0.5;
+
+/// Accelerometer biases above this, in m/s², mean the device was not
+/// level while being calibrated
+const MAX_ACCEL_BIAS: f32 = 2.0;
+
+/// Pinhole camera intrinsics with Brown-Conrady distortion, in pixels
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CameraIntrinsics {
+    pub width: u32,
+    pub height: u32,
+    /// Focal lengths along x and y
+    pub fx: f64,
+    pub fy: f64,
+    /// Principal point
+    pub cx: f64,
+    pub cy: f64,
+    /// `k1, k2, p1, p2` and optionally `k3`; empty for an undistorted
+    /// image
+    #[serde(default)]
+    pub distortion: Vec<f64>,
+}
+
+impl CameraIntrinsics {
+    /// Pixel at which the camera sees `point`, given in the camera frame
+    /// with z along the optical axis, or `None` behind the camera
+    pub fn project(&self, point: [f64; 3]) -> Option<[f64; 2]> {
+        let [x, y, z] = point;
+        if z <= 0.0 {
+            return None;
+        }
+        let (x, y) = (x / z, y / z);
+        let coefficient = |i: usize| self.distortion.get(i).copied().unwrap_or(0.0);
+        let (k1, k2, p1, p2, k3) = (coefficient(0), coefficient(1), coefficient(2), coefficient(3), coefficient(4));
+        let r2 = x * x + y * y;
+        let radial = 1.0 + r2 * (k1 + r2 * (k2 + r2 * k3));
+        let xd = x * radial + 2.0 * p1 * x * y + p2 * (r2 + 2.0 * x * x);
+        let yd = y * radial + p1 * (r2 + 2.0 * y * y) + 2.0 * p2 * x * y;
+        Some([self.fx * xd + self.cx, self.fy * yd + self.cy])
+    }
+    
+    fn validate(&self) -> Result<(), CoreError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(invalid("camera image must not be empty"));
+        }
+        if ![self.fx, self.fy].iter().all(|f| f.is_finite() && *f > 0.0) {
+            return Err(invalid("camera focal lengths must be positive and finite"));
+        }
+        let inside = |c: f64, size: u32| c.is_finite() && (0.0..=f64::from(size)).contains(&c);
+        if !(inside(self.cx, self.width) && inside(self.cy, self.height)) {
+            return Err(invalid("camera principal point must lie within the image"));
+        }
+        if !matches!(self.distortion.len(), 0 | 4 | 5) || !self.distortion.iter().all(|k| k.is_finite()) {
+            return Err(invalid("camera distortion must be 0, 4 or 5 finite coefficients"));
+        }
+        Ok(())
+    }
+}
+
+/// What one device was calibrated with
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum Calibration {
+    /// Accelerometer and gyroscope biases
+    Imu(ImuCalibration),
+    Camera(CameraIntrinsics),
+    /// Zero offset of each channel, such as the encoder count at each
+    /// joint's zero position
+    Offsets(Vec<f64>),
+    /// Limits of each joint, in joint order
+    JointLimits(Vec<JointLimits>),
+}
+
+fn invalid(message: &str) -> CoreError {
+    CoreError::InvalidParameter(format!("invalid calibration: {}", message))
+}
+
+impl Calibration {
+    /// Fails with `CoreError::InvalidParameter` unless the values are
+    /// plausible for a device calibrated as its routine asks
+    ///
+    /// IMU biases must be finite, under 0.5 rad/s for the gyroscope and
+    /// 2 m/s² for the accelerometer; larger ones mean the device moved or
+    /// was not level.
+    pub fn validate(&self) -> Result<(), CoreError> {
+        match self {
+            Calibration::Imu(imu) => {
+                let within = |biases: &[f32; 3], max: f32| biases.iter().all(|bias| bias.abs() <= max);
+                if !within(&imu.gyro_bias, MAX_GYRO_BIAS) || !within(&imu.accel_bias, MAX_ACCEL_BIAS) {
+                    return Err(invalid(&format!(
+                        "IMU biases {:?} (accel) and {:?} (gyro) are too large; was the device still and level?",
+                        imu.accel_bias, imu.gyro_bias
+                    )));
+                }
+                Ok(())
+            }
+            Calibration::Camera(camera) => camera.validate(),
+            Calibration::Offsets(offsets) => {
+                if offsets.is_empty() || !offsets.iter().all(|offset| offset.is_finite()) {
+                    return Err(invalid("offsets must be finite, one per channel"));
+                }
+                Ok(())
+            }
+            Calibration::JointLimits(limits) => {
+                if limits.is_empty() {
+                    return Err(invalid("joint limits must cover at least one joint"));
+                }
+                limits.iter().try_for_each(JointLimits::validate)
+            }
+        }
+    }
+    
+    /// Name of the kind of calibration, as stored
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Calibration::Imu(_) => "imu",
+            Calibration::Camera(_) => "camera",
+            Calibration::Offsets(_) => "offsets",
+            Calibration::JointLimits(_) => "joint_limits",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn camera() -> CameraIntrinsics {
+        CameraIntrinsics {
+            width: 640,
+            height: 480,
+            fx: 500.0,
+            fy: 500.0,
+            cx: 320.0,
+            cy: 240.0,
+            distortion: Vec::new(),
+        }
+    }
+    
+    #[test]
+    fn test_validation_rejects_implausible_calibrations() {
+        let still = ImuCalibration {
+            accel_bias: [0.1, -0.05, 0.2],
+            gyro_bias: [0.01, 0.0, -0.02],
+        };
+        assert!(Calibration::Imu(still).validate().is_ok());
+        let moved = ImuCalibration {
+            gyro_bias: [0.0, 0.0, 1.2],
+            ..still
+        };
+        assert!(Calibration::Imu(moved).validate().is_err());
+        
+        assert!(Calibration::Camera(camera()).validate().is_ok());
+        let off_image = CameraIntrinsics { cx: 700.0, ..camera() };
+        assert!(Calibration::Camera(off_image).validate().is_err());
+        let odd_distortion = CameraIntrinsics {
+            distortion: vec![0.1, 0.01],
+            ..camera()
+        };
+        assert!(Calibration::Camera(odd_distortion).validate().is_err());
+        assert!(Calibration::Offsets(vec![f64::NAN]).validate().is_err());
+        assert!(Calibration::JointLimits(vec![JointLimits::new(1.0, 0.0)]).validate().is_err());
+        
+        let json = serde_json::to_value(Calibration::Offsets(vec![12.0])).unwrap();
+        assert_eq!(json, serde_json::json!({"kind": "offsets", "value": [12.0]}));
+    }
+    
+    #[test]
+    fn test_projection_applies_distortion() {
+        let pinhole = camera();
+        assert_eq!(pinhole.project([0.0, 0.0, 2.0]), Some([320.0, 240.0]));
+        assert_eq!(pinhole.project([0.2, -0.1, 1.0]), Some([420.0, 190.0]));
+        assert_eq!(pinhole.project([0.0, 0.0, -1.0]), None);
+        // Barrel distortion pulls points towards the centre
+        let barrel = CameraIntrinsics {
+            distortion: vec![-0.2, 0.0, 0.0, 0.0],
+            ..camera()
+        };
+        let [u, _] = barrel.project([0.2, 0.0, 1.0]).unwrap();
+        assert!((u - (320.0 + 500.0 * 0.2 * (1.0 - 0.2 * 0.04))).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,174 @@
+//! Guided calibration of one sensor, stored once it validates
+
+use std::sync::Arc;
+
+use super::{Calibration, CalibrationRecord, CalibrationStore};
+use crate::error::CoreError;
+use crate::sensor::{Sensor, SensorManager};
+
+/// A calibration in progress on one sensor of a `SensorManager`
+///
+/// Place the device as its `Sensor::calibrate` asks, such as still and
+/// level for an IMU, then `measure`, as many times as needed, and `commit`
+/// to store the result as the sensor's next version. Until committed, the
+/// measured calibration only lives in the sensor: dropping the routine, or
+/// a measurement that fails validation, puts the previous one back.
+pub struct CalibrationRoutine<'a> {
+    sensor: &'a mut dyn Sensor,
+    store: Arc<CalibrationStore>,
+    previous: Calibration,
+    measured: Option<Calibration>,
+}
+
+impl CalibrationRoutine<'_> {
+    pub fn sensor_id(&self) -> &str {
+        self.sensor.id()
+    }
+    
+    /// The calibration in effect when the routine started
+    pub fn previous(&self) -> &Calibration {
+        &self.previous
+    }
+    
+    /// Calibrate the sensor and validate the result, returning it for
+    /// inspection
+    ///
+    /// A failed or implausible measurement restores the previous
+    /// calibration and leaves the routine ready to measure again.
+    pub fn measure(&mut self) -> Result<&Calibration, CoreError> {
+        self.measured = None;
+        let measured = self.sensor.calibrate().and_then(|()| {
+            let calibration = self.sensor.calibration().ok_or_else(|| unsupported(self.sensor.id()))?;
+            calibration.validate()?;
+            Ok(calibration)
+        });
+        match measured {
+            Ok(calibration) => Ok(self.measured.insert(calibration)),
+            Err(e) => {
+                self.restore();
+                Err(e)
+            }
+        }
+    }
+    
+    /// The calibration `measure` last produced, if it validated
+    pub fn measured(&self) -> Option<&Calibration> {
+        self.measured.as_ref()
+    }
+    
+    /// Store the measured calibration under the sensor's ID
+    ///
+    /// Fails with `CoreError::PreconditionFailed` before a successful
+    /// `measure`. If the store cannot be written the previous calibration
+    /// is restored.
+    pub fn commit(mut self) -> Result<CalibrationRecord, CoreError> {
+        let calibration = self.measured.take().ok_or_else(|| {
+            CoreError::PreconditionFailed(format!("sensor '{}' has not been measured", self.sensor.id()))
+        })?;
+        let saved = self.store.save(self.sensor.id(), calibration);
+        if saved.is_err() {
+            self.restore();
+        }
+        saved
+    }
+    
+    fn restore(&mut self) {
+        if let Err(e) = self.sensor.set_calibration(&self.previous) {
+            log::warn!("Sensor {} kept an uncommitted calibration: {}", self.sensor.id(), e);
+        }
+    }
+}
+
+impl Drop for CalibrationRoutine<'_> {
+    fn drop(&mut self) {
+        if self.measured.is_some() {
+            self.restore();
+        }
+    }
+}
+
+fn unsupported(sensor_id: &str) -> CoreError {
+    CoreError::UnsupportedCapability(format!("sensor '{}' cannot report its calibration", sensor_id))
+}
+
+impl SensorManager {
+    /// Begin calibrating sensor `sensor_id`, to store the result in the
+    /// manager's calibration store
+    ///
+    /// Fails with `CoreError::PreconditionFailed` without a store, and
+    /// with `CoreError::UnsupportedCapability` for a sensor that cannot
+    /// report its calibration.
+    pub fn start_calibration(&mut self, sensor_id: &str) -> Result<CalibrationRoutine<'_>, CoreError> {
+        let store = self
+            .calibration_store()
+            .cloned()
+            .ok_or_else(|| CoreError::PreconditionFailed("sensor manager has no calibration store".to_string()))?;
+        let sensor = self.sensor_mut(sensor_id)?;
+        let previous = sensor.calibration().ok_or_else(|| unsupported(sensor_id))?;
+        Ok(CalibrationRoutine {
+            sensor,
+            store,
+            previous,
+            measured: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensor::imu::{ImuDriver, ImuSample, ImuSensor, GRAVITY};
+    use crate::sensor::MockSensor;
+    use std::fs;
+    
+    /// Reads a constant rotation, as a device turning while calibrated
+    struct Turning;
+    
+    impl ImuDriver for Turning {
+        fn read_sample(&mut self) -> Result<ImuSample, CoreError> {
+            Ok(ImuSample {
+                accel: [0.0, 0.0, GRAVITY],
+                gyro: [0.0, 0.0, 1.0],
+                temperature: 25.0,
+            })
+        }
+        
+        fn sample_rate(&self) -> f64 {
+            100.0
+        }
+    }
+    
+    #[test]
+    fn test_routine_stores_validated_results_for_the_next_start() {
+        let dir = std::env::temp_dir().join(format!("robotics-core-calibration-routine-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let store = Arc::new(CalibrationStore::open(&dir).unwrap());
+        let mut sensors = SensorManager::new().with_calibration_store(Arc::clone(&store));
+        sensors.add(Box::new(MockSensor::new("gyro", 100.0, vec![0.5, 1.5]).unwrap()), None).unwrap();
+        sensors.add(Box::new(ImuSensor::new("imu", Turning).with_calibration_samples(5)), None).unwrap();
+        
+        let mut routine = sensors.start_calibration("gyro").unwrap();
+        assert_eq!(routine.previous(), &Calibration::Offsets(vec![0.0]));
+        assert_eq!(routine.measure().unwrap(), &Calibration::Offsets(vec![0.5]));
+        assert_eq!(routine.commit().unwrap().version, 1);
+        
+        // A device that moved fails validation and keeps its calibration
+        let mut routine = sensors.start_calibration("imu").unwrap();
+        assert!(matches!(routine.measure(), Err(CoreError::InvalidParameter(_))));
+        assert!(matches!(routine.commit(), Err(CoreError::PreconditionFailed(_))));
+        assert_eq!(store.devices(), ["gyro"]);
+        
+        // A fresh sensor with the same ID starts from the stored offset
+        let mut restarted = SensorManager::new().with_calibration_store(Arc::clone(&store));
+        let fresh = MockSensor::new("gyro", 100.0, vec![2.0]).unwrap();
+        restarted.add(Box::new(fresh), None).unwrap();
+        let mut routine = restarted.start_calibration("gyro").unwrap();
+        assert_eq!(routine.previous(), &Calibration::Offsets(vec![0.5]));
+        // Dropped uncommitted: the stored offset is back in force
+        assert_eq!(routine.measure().unwrap(), &Calibration::Offsets(vec![2.0]));
+        drop(routine);
+        assert_eq!(restarted.sensor_mut("gyro").unwrap().calibration(), Some(Calibration::Offsets(vec![0.5])));
+        assert!(SensorManager::new().start_calibration("gyro").is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,241 @@
+//! Versioned calibration files, one per device
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{Calibration, CameraIntrinsics};
+use crate::algorithm::trajectory::JointLimits;
+use crate::error::CoreError;
+use crate::sensor::imu::ImuCalibration;
+
+/// Layout of the files this build writes; files of a later layout are
+/// refused rather than misread
+const FORMAT_VERSION: u32 = 1;
+
+/// One calibration of one device
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationRecord {
+    pub device_id: String,
+    /// 1 for the device's first calibration, counting up with each save
+    pub version: u64,
+    /// When the record was saved, in seconds since the Unix epoch
+    pub saved_at: u64,
+    pub calibration: Calibration,
+}
+
+/// Contents of one device's file: every record saved, oldest first
+#[derive(Serialize, Deserialize)]
+struct CalibrationFile {
+    format: u32,
+    records: Vec<CalibrationRecord>,
+}
+
+/// Calibrations kept in a directory, one JSON file per device ID
+///
+/// Each save appends a new version to the device's file, keeping the
+/// earlier ones for `history`, and replaces the file in one rename so a
+/// crash mid-save leaves the previous version in place. The latest record
+/// of every device is held in memory, so queries never touch the disk.
+pub struct CalibrationStore {
+    dir: PathBuf,
+    latest: RwLock<BTreeMap<String, CalibrationRecord>>,
+}
+
+impl CalibrationStore {
+    /// Open the store kept in `dir`, creating the directory if need be
+    ///
+    /// Fails with `CoreError::InvalidInput` if any device's file is
+    /// malformed or of a later format.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, CoreError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        let mut latest = BTreeMap::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+            if let Some(record) = read_file(&path)?.records.pop() {
+                latest.insert(record.device_id.clone(), record);
+            }
+        }
+        Ok(Self {
+            dir,
+            latest: RwLock::new(latest),
+        })
+    }
+    
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+    
+    /// IDs of the devices with a stored calibration, in order
+    pub fn devices(&self) -> Vec<String> {
+        self.read().keys().cloned().collect()
+    }
+    
+    /// The latest calibration of `device_id`
+    pub fn get(&self, device_id: &str) -> Option<CalibrationRecord> {
+        self.read().get(device_id).cloned()
+    }
+    
+    /// The biases of IMU `device_id`, if its latest calibration is an IMU
+    /// one
+    pub fn imu(&self, device_id: &str) -> Option<ImuCalibration> {
+        match self.get(device_id)?.calibration {
+            Calibration::Imu(imu) => Some(imu),
+            _ => None,
+        }
+    }
+    
+    /// The intrinsics of camera `device_id`, if its latest calibration is a
+    /// camera one
+    pub fn camera(&self, device_id: &str) -> Option<CameraIntrinsics> {
+        match self.get(device_id)?.calibration {
+            Calibration::Camera(camera) => Some(camera),
+            _ => None,
+        }
+    }
+    
+    /// The channel offsets of `device_id`, if its latest calibration holds
+    /// offsets
+    pub fn offsets(&self, device_id: &str) -> Option<Vec<f64>> {
+        match self.get(device_id)?.calibration {
+            Calibration::Offsets(offsets) => Some(offsets),
+            _ => None,
+        }
+    }
+    
+    /// The joint limits of `device_id`, if its latest calibration holds
+    /// limits
+    pub fn joint_limits(&self, device_id: &str) -> Option<Vec<JointLimits>> {
+        match self.get(device_id)?.calibration {
+            Calibration::JointLimits(limits) => Some(limits),
+            _ => None,
+        }
+    }
+    
+    /// Every calibration saved for `device_id`, oldest first
+    pub fn history(&self, device_id: &str) -> Result<Vec<CalibrationRecord>, CoreError> {
+        let path = self.path(device_id)?;
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        Ok(read_file(&path)?.records)
+    }
+    
+    /// Validate `calibration` and save it as the next version for
+    /// `device_id`, returning the new record
+    ///
+    /// Device IDs may hold ASCII letters, digits, `-`, `_` and `.`, but
+    /// not start with a dot, since they name the file. An invalid
+    /// calibration fails with `CoreError::InvalidParameter` and changes
+    /// nothing.
+    pub fn save(&self, device_id: &str, calibration: Calibration) -> Result<CalibrationRecord, CoreError> {
+        calibration.validate()?;
+        let path = self.path(device_id)?;
+        // Held across the write, so concurrent saves take turns
+        let mut latest = self.write();
+        let mut file = if path.exists() {
+            read_file(&path)?
+        } else {
+            CalibrationFile {
+                format: FORMAT_VERSION,
+                records: Vec::new(),
+            }
+        };
+        let record = CalibrationRecord {
+            device_id: device_id.to_string(),
+            version: file.records.last().map_or(1, |last| last.version + 1),
+            saved_at: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs()),
+            calibration,
+        };
+        file.format = FORMAT_VERSION;
+        file.records.push(record.clone());
+        let json = serde_json::to_string_pretty(&file)
+            .map_err(|e| CoreError::ProcessingFailed(format!("calibration does not serialize: {}", e)))?;
+        let staged = path.with_extension("json.tmp");
+        fs::write(&staged, json)?;
+        fs::rename(&staged, &path)?;
+        latest.insert(device_id.to_string(), record.clone());
+        Ok(record)
+    }
+    
+    fn path(&self, device_id: &str) -> Result<PathBuf, CoreError> {
+        let valid = !device_id.is_empty()
+            && !device_id.starts_with('.')
+            && device_id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid {
+            return Err(CoreError::InvalidParameter(format!(
+                "'{}' cannot name a calibration file",
+                device_id
+            )));
+        }
+        Ok(self.dir.join(format!("{}.json", device_id)))
+    }
+    
+    fn read(&self) -> RwLockReadGuard<'_, BTreeMap<String, CalibrationRecord>> {
+        self.latest.read().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    fn write(&self) -> RwLockWriteGuard<'_, BTreeMap<String, CalibrationRecord>> {
+        self.latest.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn read_file(path: &Path) -> Result<CalibrationFile, CoreError> {
+    let file: CalibrationFile = serde_json::from_str(&fs::read_to_string(path)?)
+        .map_err(|e| CoreError::InvalidInput(format!("{}: {}", path.display(), e)))?;
+    if file.format > FORMAT_VERSION {
+        return Err(CoreError::InvalidInput(format!(
+            "{}: calibration format {} is newer than this build reads ({})",
+            path.display(),
+            file.format,
+            FORMAT_VERSION
+        )));
+    }
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("robotics-core-calibration-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+    
+    #[test]
+    fn test_saves_are_versioned_and_survive_reopening() {
+        let dir = scratch("versions");
+        let store = CalibrationStore::open(&dir).unwrap();
+        assert_eq!(store.get("arm"), None);
+        store.save("arm", Calibration::Offsets(vec![10.0, -4.0])).unwrap();
+        let second = store.save("arm", Calibration::Offsets(vec![11.0, -4.5])).unwrap();
+        assert_eq!(second.version, 2);
+        let limits = vec![JointLimits::new(1.0, 2.0)];
+        store.save("arm-limits", Calibration::JointLimits(limits.clone())).unwrap();
+        assert!(store.save("arm", Calibration::Offsets(Vec::new())).is_err());
+        assert!(store.save("../escape", Calibration::Offsets(vec![1.0])).is_err());
+        
+        let reopened = CalibrationStore::open(&dir).unwrap();
+        assert_eq!(reopened.devices(), ["arm", "arm-limits"]);
+        assert_eq!(reopened.offsets("arm"), Some(vec![11.0, -4.5]));
+        assert_eq!(reopened.joint_limits("arm-limits"), Some(limits));
+        assert_eq!(reopened.imu("arm"), None, "a different kind");
+        let history = reopened.history("arm").unwrap();
+        assert_eq!(history.iter().map(|record| record.version).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(history[0].calibration, Calibration::Offsets(vec![10.0, -4.0]));
+        
+        // A file from a later layout is refused
+        fs::write(dir.join("newer.json"), r#"{"format": 99, "records": []}"#).unwrap();
+        assert!(matches!(CalibrationStore::open(&dir), Err(CoreError::InvalidInput(_))));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
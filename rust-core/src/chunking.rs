@@ -0,0 +1,194 @@
+//! Feeding a long input to an algorithm in chunks of tuned size
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::error::CoreError;
+use crate::CoreEngine;
+
+/// Most recent decisions a `ChunkTuner` keeps for inspection
+const DECISION_HISTORY: usize = 256;
+
+/// How a `ChunkTuner` sized the chunk after one it measured
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChunkDecision {
+    /// Bytes in the measured chunk
+    pub bytes: usize,
+    /// Time the chunk took to process
+    pub elapsed: Duration,
+    /// Size chosen for the next chunk
+    pub next_size: usize,
+}
+
+/// Chooses chunk sizes for `CoreEngine::execute_chunked`
+///
+/// Small chunks pay per-call overhead, large ones delay the first output.
+/// An adaptive tuner measures each chunk and sizes the next so it would
+/// take about `latency_budget` at the measured cost per byte, moving at
+/// most a factor of two per chunk and staying within its bounds. A fixed
+/// tuner always uses one size.
+#[derive(Clone, Debug)]
+pub struct ChunkTuner {
+    size: usize,
+    min: usize,
+    max: usize,
+    latency_budget: Duration,
+    decisions: VecDeque<ChunkDecision>,
+}
+
+impl ChunkTuner {
+    /// Always use chunks of `size` bytes
+    pub fn fixed(size: usize) -> Result<Self, CoreError> {
+        Self::adaptive(size, size, size, Duration::ZERO)
+    }
+    
+    /// Start at `initial` bytes and adapt within `[min, max]` towards
+    /// chunks taking `latency_budget`
+    pub fn adaptive(initial: usize, min: usize, max: usize, latency_budget: Duration) -> Result<Self, CoreError> {
+        if min == 0 || min > max {
+            return Err(CoreError::InvalidParameter(format!(
+                "chunk bounds must satisfy 0 < min <= max, got [{}, {}]",
+                min, max
+            )));
+        }
+        Ok(Self {
+            size: initial.clamp(min, max),
+            min,
+            max,
+            latency_budget,
+            decisions: VecDeque::new(),
+        })
+    }
+    
+    /// Size of the next chunk
+    pub fn size(&self) -> usize {
+        self.size
+    }
+    
+    /// Decisions made so far, oldest first, up to the most recent 256
+    pub fn decisions(&self) -> impl Iterator<Item = &ChunkDecision> {
+        self.decisions.iter()
+    }
+    
+    /// Account for a chunk of `bytes` that took `elapsed`, choosing the next
+    /// size
+    pub fn record(&mut self, bytes: usize, elapsed: Duration) {
+        if self.min < self.max && bytes > 0 {
+            // Bytes the budget fits at this chunk's cost per byte
+            let target = if elapsed.is_zero() {
+                self.max
+            } else {
+                let fitting = self.latency_budget.as_nanos() * bytes as u128 / elapsed.as_nanos();
+                usize::try_from(fitting).unwrap_or(usize::MAX)
+            };
+            self.size = target.clamp(self.size / 2, self.size.saturating_mul(2)).clamp(self.min, self.max);
+        }
+        let decision = ChunkDecision {
+            bytes,
+            elapsed,
+            next_size: self.size,
+        };
+        log::debug!("Chunk of {} bytes took {:?}, next chunk {} bytes", bytes, elapsed, self.size);
+        if self.decisions.len() == DECISION_HISTORY {
+            self.decisions.pop_front();
+        }
+        self.decisions.push_back(decision);
+    }
+}
+
+impl CoreEngine {
+    /// Execute an algorithm over `input` one chunk at a time, returning the
+    /// concatenated outputs
+    ///
+    /// Chunks are sized by `tuner`, timed on the engine's clock, and end at
+    /// arbitrary byte offsets, so the algorithm must carry whatever it needs
+    /// across calls (see `algorithm::StreamingAlgorithm`). The first failing
+    /// chunk's error is returned. The tuner keeps its state, so reuse it to
+    /// carry what it learned to the rest of a stream.
+    pub fn execute_chunked(
+        &mut self,
+        algorithm_id: &str,
+        input: &[u8],
+        tuner: &mut ChunkTuner,
+    ) -> Result<Vec<u8>, CoreError> {
+        let mut output = Vec::new();
+        let mut rest = input;
+        while !rest.is_empty() {
+            let (chunk, remaining) = rest.split_at(tuner.size().min(rest.len()));
+            let start = self.clock.now();
+            output.extend(self.execute_algorithm(algorithm_id, chunk)?);
+            tuner.record(chunk.len(), self.clock.now().saturating_sub(start));
+            rest = remaining;
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{Algorithm, AlgorithmMetadata};
+    use crate::clock::MockClock;
+    use crate::memory::MemoryManager;
+    use std::sync::Arc;
+    
+    /// Echoes its input, advancing the clock by a per-byte cost that the
+    /// input's first byte selects
+    struct Costly(Arc<MockClock>);
+    
+    impl Algorithm for Costly {
+        fn process(&self, input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            let micros_per_byte = u64::from(input.first().copied().unwrap_or(0));
+            self.0.advance(Duration::from_micros(micros_per_byte * input.len() as u64));
+            Ok(input.to_vec())
+        }
+        
+        fn id(&self) -> &str {
+            "costly"
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata::default()
+        }
+    }
+    
+    #[test]
+    fn test_adaptive_size_converges_within_bounds() {
+        let clock = Arc::new(MockClock::new());
+        let mut engine = CoreEngine::builder().clock(clock.clone()).build();
+        engine.register(Box::new(Costly(clock))).unwrap();
+        let mut tuner = ChunkTuner::adaptive(64, 16, 4096, Duration::from_millis(1)).unwrap();
+        
+        // 1 us per byte: a 1 ms budget fits 1000 bytes
+        let cheap = vec![1u8; 20_000];
+        assert_eq!(engine.execute_chunked("costly", &cheap, &mut tuner).unwrap(), cheap);
+        assert_eq!(tuner.size(), 1000);
+        
+        // 4 us per byte: 250 bytes
+        let dear = vec![4u8; 20_000];
+        engine.execute_chunked("costly", &dear, &mut tuner).unwrap();
+        assert_eq!(tuner.size(), 250);
+        
+        // Free: capped at the upper bound
+        let free = vec![0u8; 50_000];
+        engine.execute_chunked("costly", &free, &mut tuner).unwrap();
+        assert_eq!(tuner.size(), 4096);
+        
+        assert!(tuner.decisions().all(|d| (16..=4096).contains(&d.next_size)));
+        // Growth is limited to doubling per chunk
+        let first = tuner.decisions().next().unwrap();
+        assert_eq!((first.bytes, first.elapsed, first.next_size), (64, Duration::from_micros(64), 128));
+    }
+    
+    #[test]
+    fn test_fixed_tuner_never_changes() {
+        let mut tuner = ChunkTuner::fixed(100).unwrap();
+        tuner.record(100, Duration::from_secs(1));
+        tuner.record(100, Duration::ZERO);
+        assert_eq!(tuner.size(), 100);
+        assert_eq!(tuner.decisions().count(), 2);
+        
+        assert!(ChunkTuner::fixed(0).is_err());
+        assert!(ChunkTuner::adaptive(10, 20, 10, Duration::ZERO).is_err());
+    }
+}
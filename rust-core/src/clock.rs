@@ -0,0 +1,112 @@
+//! Time sources for anything that measures or waits
+//!
+//! Code that reads the time or sleeps takes a `Clock` rather than calling
+//! `Instant::now` or `thread::sleep`, so tests can substitute a `MockClock`
+//! and advance time deterministically.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// A monotonic time source
+pub trait Clock: Send + Sync {
+    /// Time elapsed since the clock's origin
+    fn now(&self) -> Duration;
+    
+    /// Block for `duration`
+    fn sleep(&self, duration: Duration);
+}
+
+/// Wall-clock time from the operating system
+///
+/// WebAssembly in the browser has no monotonic clock or blocking sleep, so
+/// on `wasm32` the time stays at zero and `sleep` returns immediately.
+#[derive(Clone, Debug)]
+pub struct SystemClock {
+    #[cfg(not(target_arch = "wasm32"))]
+    origin: std::time::Instant,
+}
+
+impl SystemClock {
+    /// Create a clock whose origin is the current instant
+    pub fn new() -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            origin: std::time::Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        #[cfg(not(target_arch = "wasm32"))]
+        return self.origin.elapsed();
+        #[cfg(target_arch = "wasm32")]
+        return Duration::ZERO;
+    }
+    
+    fn sleep(&self, duration: Duration) {
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::sleep(duration);
+        #[cfg(target_arch = "wasm32")]
+        let _ = duration;
+    }
+}
+
+/// A clock that only moves when told to
+///
+/// `sleep` advances the clock by the requested duration instead of
+/// blocking, so code that waits runs instantly under test.
+#[derive(Debug, Default)]
+pub struct MockClock {
+    now: Mutex<Duration>,
+}
+
+impl MockClock {
+    /// Create a clock starting at zero
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Move the clock forward
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner()) += duration;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Duration {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_mock_clock_advances_manually() {
+        let clock = MockClock::new();
+        assert_eq!(clock.now(), Duration::ZERO);
+        clock.advance(Duration::from_millis(5));
+        clock.sleep(Duration::from_millis(10));
+        assert_eq!(clock.now(), Duration::from_millis(15));
+    }
+    
+    #[test]
+    fn test_system_clock_is_monotonic() {
+        let clock = SystemClock::new();
+        let before = clock.now();
+        clock.sleep(Duration::from_millis(1));
+        assert!(clock.now() >= before + Duration::from_millis(1));
+    }
+}
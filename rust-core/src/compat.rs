@@ -0,0 +1,170 @@
+//! Stand-ins for the parts of `std` used by the modules that also build
+//! without it
+//!
+//! With the `std` feature these are std's own items. Without it, maps and
+//! sets come from `hashbrown`, float functions from `libm`, and `Mutex` is
+//! a spin lock, which needs atomic compare-and-swap: Cortex-M3 and up, not
+//! M0.
+
+#[cfg(feature = "std")]
+pub(crate) use std::collections::{HashMap, HashSet};
+#[cfg(feature = "std")]
+pub(crate) use std::sync::{Mutex, MutexGuard};
+
+#[cfg(not(feature = "std"))]
+pub(crate) use hashbrown::{HashMap, HashSet};
+#[cfg(not(feature = "std"))]
+pub(crate) use spin::{Mutex, MutexGuard};
+
+/// Lock `mutex`, recovering the data if a panicking holder poisoned it
+#[cfg(feature = "std")]
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Lock `mutex`, spinning until it is free
+#[cfg(not(feature = "std"))]
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock()
+}
+
+/// Float functions that `core` leaves to `std`, computed by `libm`
+///
+/// Import it only when the crate is `no_std`: wherever `std` is linked,
+/// tests included, its inherent methods of the same names take precedence.
+#[cfg(all(not(feature = "std"), not(test)))]
+pub(crate) trait Float: Sized {
+    fn sqrt(self) -> Self;
+    fn sin(self) -> Self;
+    fn sin_cos(self) -> (Self, Self);
+    fn acos(self) -> Self;
+    fn atan2(self, other: Self) -> Self;
+    fn hypot(self, other: Self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn ceil(self) -> Self;
+    fn fract(self) -> Self;
+}
+
+#[cfg(all(not(feature = "std"), not(test)))]
+macro_rules! impl_float {
+    ($ty:ty, $sqrt:ident, $sin:ident, $cos:ident, $acos:ident, $atan2:ident, $hypot:ident, $pow:ident, $ceil:ident,
+     $trunc:ident) => {
+        impl Float for $ty {
+            fn sqrt(self) -> Self {
+                libm::$sqrt(self)
+            }
+            
+            fn sin(self) -> Self {
+                libm::$sin(self)
+            }
+            
+            fn sin_cos(self) -> (Self, Self) {
+                (libm::$sin(self), libm::$cos(self))
+            }
+            
+            fn acos(self) -> Self {
+                libm::$acos(self)
+            }
+            
+            fn atan2(self, other: Self) -> Self {
+                libm::$atan2(self, other)
+            }
+            
+            fn hypot(self, other: Self) -> Self {
+                libm::$hypot(self, other)
+            }
+            
+            fn powi(self, n: i32) -> Self {
+                libm::$pow(self, n as $ty)
+            }
+            
+            fn ceil(self) -> Self {
+                libm::$ceil(self)
+            }
+            
+            fn fract(self) -> Self {
+                self - libm::$trunc(self)
+            }
+        }
+    };
+}
+
+#[cfg(all(not(feature = "std"), not(test)))]
+impl_float!(f64, sqrt, sin, cos, acos, atan2, hypot, pow, ceil, trunc);
+#[cfg(all(not(feature = "std"), not(test)))]
+impl_float!(f32, sqrtf, sinf, cosf, acosf, atan2f, hypotf, powf, ceilf, truncf);
+
+#[cfg(not(feature = "std"))]
+mod spin {
+    use core::cell::UnsafeCell;
+    use core::fmt;
+    use core::ops::{Deref, DerefMut};
+    use core::sync::atomic::{AtomicBool, Ordering};
+    
+    /// Mutual exclusion by busy-waiting, for targets without an OS
+    ///
+    /// Never take it from an interrupt handler that may preempt a holder:
+    /// the handler would spin forever.
+    #[derive(Default)]
+    pub(crate) struct Mutex<T> {
+        locked: AtomicBool,
+        data: UnsafeCell<T>,
+    }
+    
+    // SAFETY: `locked` admits one guard at a time, so `data` is only ever
+    // reached from one thread, as with `std::sync::Mutex`
+    unsafe impl<T: Send> Sync for Mutex<T> {}
+    
+    impl<T> Mutex<T> {
+        pub(crate) const fn new(data: T) -> Self {
+            Self {
+                locked: AtomicBool::new(false),
+                data: UnsafeCell::new(data),
+            }
+        }
+        
+        pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+            while self
+                .locked
+                .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+            {
+                core::hint::spin_loop();
+            }
+            MutexGuard { mutex: self }
+        }
+    }
+    
+    impl<T> fmt::Debug for Mutex<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Mutex").finish_non_exhaustive()
+        }
+    }
+    
+    /// Access to a locked `Mutex`, released on drop
+    pub(crate) struct MutexGuard<'a, T> {
+        mutex: &'a Mutex<T>,
+    }
+    
+    impl<T> Deref for MutexGuard<'_, T> {
+        type Target = T;
+        
+        fn deref(&self) -> &T {
+            // SAFETY: the guard holds the lock, so no other reference exists
+            unsafe { &*self.mutex.data.get() }
+        }
+    }
+    
+    impl<T> DerefMut for MutexGuard<'_, T> {
+        fn deref_mut(&mut self) -> &mut T {
+            // SAFETY: the guard holds the lock, so no other reference exists
+            unsafe { &mut *self.mutex.data.get() }
+        }
+    }
+    
+    impl<T> Drop for MutexGuard<'_, T> {
+        fn drop(&mut self) {
+            self.mutex.locked.store(false, Ordering::Release);
+        }
+    }
+}
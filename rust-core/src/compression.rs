@@ -0,0 +1,210 @@
+//! Interchangeable compression codecs for snapshots and archived logs
+//!
+//! Compressed data is stored in a small container: a 4-byte magic, one byte
+//! naming the `Codec`, then the codec's output. `unpack` reads the codec
+//! from that header, so loaders never need to be told how data was saved.
+
+use crate::error::CoreError;
+
+/// Leading bytes of every container written by `pack`
+pub const MAGIC: [u8; 4] = *b"RCPK";
+
+/// Codec identifiers as stored in the container header
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Codec {
+    /// Stored as is
+    None,
+    /// Zstandard, favouring ratio (`compression` feature)
+    Zstd,
+    /// LZ4 block format, favouring speed (`lz4` feature)
+    Lz4,
+}
+
+impl Codec {
+    /// Byte identifying the codec in a container header
+    pub fn id(&self) -> u8 {
+        match self {
+            Codec::None => 0,
+            Codec::Zstd => 1,
+            Codec::Lz4 => 2,
+        }
+    }
+    
+    /// Codec named by a header byte
+    pub fn from_id(id: u8) -> Result<Self, CoreError> {
+        match id {
+            0 => Ok(Codec::None),
+            1 => Ok(Codec::Zstd),
+            2 => Ok(Codec::Lz4),
+            other => Err(CoreError::InvalidInput(format!("unknown codec {}", other))),
+        }
+    }
+    
+    /// Compressor implementing this codec, if it is compiled in
+    pub fn compressor(&self) -> Result<Box<dyn Compressor>, CoreError> {
+        match self {
+            Codec::None => Ok(Box::new(Uncompressed)),
+            #[cfg(all(feature = "compression", not(target_arch = "wasm32")))]
+            Codec::Zstd => Ok(Box::new(Zstd::default())),
+            #[cfg(feature = "lz4")]
+            Codec::Lz4 => Ok(Box::new(Lz4)),
+            #[allow(unreachable_patterns)]
+            other => Err(CoreError::InvalidParameter(format!(
+                "codec {:?} is not enabled in this build",
+                other
+            ))),
+        }
+    }
+}
+
+/// A compression algorithm usable for snapshots and archived logs
+pub trait Compressor: Send + Sync {
+    /// Codec recorded in the container header
+    fn codec(&self) -> Codec;
+    
+    /// Compress `data`
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CoreError>;
+    
+    /// Reverse `compress`, failing cleanly on corrupt input
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, CoreError>;
+}
+
+/// Stores data unchanged
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Uncompressed;
+
+impl Compressor for Uncompressed {
+    fn codec(&self) -> Codec {
+        Codec::None
+    }
+    
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CoreError> {
+        Ok(data.to_vec())
+    }
+    
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, CoreError> {
+        Ok(data.to_vec())
+    }
+}
+
+/// Zstandard at a configurable level
+#[cfg(all(feature = "compression", not(target_arch = "wasm32")))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Zstd {
+    /// Compression level; 0 picks the library default
+    pub level: i32,
+}
+
+#[cfg(all(feature = "compression", not(target_arch = "wasm32")))]
+impl Compressor for Zstd {
+    fn codec(&self) -> Codec {
+        Codec::Zstd
+    }
+    
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CoreError> {
+        zstd::bulk::compress(data, self.level).map_err(|e| CoreError::ProcessingFailed(format!("zstd: {}", e)))
+    }
+    
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, CoreError> {
+        zstd::stream::decode_all(data).map_err(|e| CoreError::InvalidInput(format!("zstd: {}", e)))
+    }
+}
+
+/// LZ4 block compression, with the uncompressed size prepended
+#[cfg(feature = "lz4")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Lz4;
+
+#[cfg(feature = "lz4")]
+impl Compressor for Lz4 {
+    fn codec(&self) -> Codec {
+        Codec::Lz4
+    }
+    
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CoreError> {
+        Ok(lz4_flex::compress_prepend_size(data))
+    }
+    
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, CoreError> {
+        // LZ4 expands at most ~255x, so a larger claimed size is corruption
+        // and must not be allocated
+        let claimed = data
+            .get(..4)
+            .and_then(|size| size.try_into().ok())
+            .map_or(0, u32::from_le_bytes);
+        if claimed as usize > data.len().saturating_mul(255) {
+            return Err(CoreError::InvalidInput(format!("lz4: implausible size {}", claimed)));
+        }
+        lz4_flex::decompress_size_prepended(data).map_err(|e| CoreError::InvalidInput(format!("lz4: {}", e)))
+    }
+}
+
+/// Compress `data` into a container recording the codec used
+pub fn pack(data: &[u8], compressor: &dyn Compressor) -> Result<Vec<u8>, CoreError> {
+    let mut out = MAGIC.to_vec();
+    out.push(compressor.codec().id());
+    out.extend_from_slice(&compressor.compress(data)?);
+    Ok(out)
+}
+
+/// Decompress a container written by `pack` with whichever codec it names
+pub fn unpack(container: &[u8]) -> Result<Vec<u8>, CoreError> {
+    if container.len() < MAGIC.len() + 1 || container[..MAGIC.len()] != MAGIC {
+        return Err(CoreError::InvalidInput("not a compressed container".to_string()));
+    }
+    let codec = Codec::from_id(container[MAGIC.len()])?;
+    codec.compressor()?.decompress(&container[MAGIC.len() + 1..])
+}
+
+/// Codec a container was written with
+pub fn codec_of(container: &[u8]) -> Result<Codec, CoreError> {
+    match container.get(..MAGIC.len() + 1) {
+        Some(header) if header[..MAGIC.len()] == MAGIC => Codec::from_id(header[MAGIC.len()]),
+        _ => Err(CoreError::InvalidInput("not a compressed container".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn sample() -> Vec<u8> {
+        (0..4096u32).flat_map(|i| ((i / 16) as u16).to_le_bytes()).collect()
+    }
+    
+    fn round_trip(compressor: &dyn Compressor) {
+        let data = sample();
+        let container = pack(&data, compressor).unwrap();
+        assert_eq!(codec_of(&container).unwrap(), compressor.codec());
+        assert_eq!(unpack(&container).unwrap(), data);
+        let mut corrupt = container.clone();
+        corrupt.truncate(container.len() / 2);
+        if compressor.codec() != Codec::None {
+            assert!(unpack(&corrupt).is_err());
+        }
+    }
+    
+    #[test]
+    fn test_uncompressed_round_trip() {
+        round_trip(&Uncompressed);
+    }
+    
+    #[cfg(all(feature = "compression", not(target_arch = "wasm32")))]
+    #[test]
+    fn test_zstd_round_trip() {
+        round_trip(&Zstd { level: 3 });
+        assert!(pack(&sample(), &Zstd::default()).unwrap().len() < sample().len() / 4);
+    }
+    
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn test_lz4_round_trip() {
+        round_trip(&Lz4);
+    }
+    
+    #[test]
+    fn test_unpack_rejects_foreign_data() {
+        assert!(unpack(b"not a container").is_err());
+        assert!(unpack(&[b'R', b'C', b'P', b'K', 9, 0]).is_err());
+    }
+}
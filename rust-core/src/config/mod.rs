@@ -0,0 +1,161 @@
+//! Canonical record of an engine's configuration, for reproducible
+//! deployments, and engines built from setup files
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::error::CoreError;
+use crate::hashing;
+use crate::memory::MissingKeyPolicy;
+use crate::{Capabilities, CoreEngine};
+
+mod setup;
+
+pub use setup::EngineSetup;
+
+/// Everything about an engine's setup that affects what it computes
+///
+/// Collections are sorted, and nothing run-dependent such as timestamps,
+/// addresses or region contents is included, so identically configured
+/// engines produce identical records.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EngineConfig {
+    /// Version of each registered algorithm, by ID
+    pub algorithms: BTreeMap<String, String>,
+    /// Version of each fallback, under its primary's ID
+    pub fallbacks: BTreeMap<String, String>,
+    /// IDs registered as pure
+    pub pure: BTreeSet<String>,
+    /// Output cache capacity, by algorithm ID
+    pub caches: BTreeMap<String, usize>,
+    /// Names of registered devices
+    pub devices: BTreeSet<String>,
+    /// Number of preprocessors added
+    pub preprocessors: usize,
+    /// Number of postprocessors added
+    pub postprocessors: usize,
+    /// Deepest pipeline or DAG the engine accepts
+    pub max_depth: usize,
+    /// Worker threads available to batch and DAG execution
+    pub threads: usize,
+    /// Seed of a deterministic engine's execution contexts
+    pub seed: Option<u64>,
+    /// Name of the hasher keying caches and computing fingerprints
+    pub hasher: String,
+    /// Memory manager settings
+    pub memory: MemoryConfig,
+    /// Optional features compiled in
+    pub capabilities: Capabilities,
+}
+
+/// Memory manager settings recorded in an `EngineConfig`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryConfig {
+    /// What `write` does with a missing key
+    pub missing_key_policy: MissingKeyPolicy,
+    /// Whether `allocate` refuses existing keys
+    pub strict_keys: bool,
+    /// Largest region served from the buffer pool, if pooling is enabled
+    pub pool_block_size: Option<usize>,
+    /// Blocks preallocated for a fixed pool
+    #[serde(default)]
+    pub pool_blocks: Option<usize>,
+}
+
+impl CoreEngine {
+    /// Capture the engine's current configuration
+    pub fn config(&self) -> EngineConfig {
+        let manifest = self.registry.export();
+        let versions = |algorithms: BTreeMap<String, crate::algorithm::AlgorithmMetadata>| {
+            algorithms
+                .into_iter()
+                .map(|(id, metadata)| (id, metadata.version))
+                .collect()
+        };
+        EngineConfig {
+            algorithms: versions(manifest.algorithms),
+            fallbacks: versions(manifest.fallbacks),
+            pure: self.pure.iter().cloned().collect(),
+            caches: self
+                .caches
+                .iter()
+                .map(|(id, cache)| (id.clone(), cache.capacity()))
+                .collect(),
+            devices: self.devices.keys().cloned().collect(),
+            preprocessors: self.hooks.preprocessors(),
+            postprocessors: self.hooks.postprocessors(),
+            max_depth: self.max_depth,
+            threads: self.pool.threads(),
+            seed: self.context.seed(),
+            hasher: self.hasher.name().to_string(),
+            memory: self.memory_manager.config(),
+            capabilities: Self::capabilities(),
+        }
+    }
+    
+    /// The configuration as canonical JSON, suitable for checking into
+    /// version control and diffing across deployments
+    ///
+    /// Object keys are sorted at every level and the output is compact, so
+    /// the same configuration always yields byte-identical JSON.
+    pub fn config_json(&self) -> Result<String, CoreError> {
+        // Going through `Value` sorts keys, since its maps are ordered
+        serde_json::to_value(self.config())
+            .and_then(|value| serde_json::to_string(&value))
+            .map_err(|e| CoreError::ProcessingFailed(format!("cannot serialize engine config: {}", e)))
+    }
+    
+    /// Stable hash of `config_json` by the engine's hasher, in hex
+    ///
+    /// With the default 64-bit FNV-1a that is 16 hex digits, fixed across
+    /// Rust releases and platforms unlike the standard library's hasher. It
+    /// detects configuration drift; configure `hashing::Blake3` for a
+    /// cryptographic digest.
+    pub fn config_fingerprint(&self) -> Result<String, CoreError> {
+        Ok(hashing::to_hex(&self.hasher.digest(self.config_json()?.as_bytes())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::builtins::{PassThrough, Scale};
+    
+    fn configured() -> CoreEngine {
+        let mut engine = CoreEngine::builder().threads(2).max_depth(8).build();
+        engine.register(Box::new(Scale::new(2.0).unwrap())).unwrap();
+        engine.register_pure(PassThrough).unwrap();
+        engine.enable_cache("passthrough", 16).unwrap();
+        engine.add_preprocessor(Box::new(|input| Ok(input.to_vec())));
+        engine.memory_mut().set_strict_keys(true);
+        engine
+    }
+    
+    #[test]
+    fn test_identical_engines_share_fingerprint() {
+        let (a, b) = (configured(), configured());
+        assert_eq!(a.config_json().unwrap(), b.config_json().unwrap());
+        assert_eq!(a.config_fingerprint().unwrap(), b.config_fingerprint().unwrap());
+        assert_eq!(a.config_fingerprint().unwrap().len(), 16);
+        
+        let config = a.config();
+        assert_eq!(config.algorithms.keys().collect::<Vec<_>>(), ["passthrough", "scale"]);
+        assert_eq!(config.caches["passthrough"], 16);
+        assert_eq!((config.preprocessors, config.threads, config.max_depth), (1, 2, 8));
+        assert!(config.memory.strict_keys);
+        
+        let json = a.config_json().unwrap();
+        assert!(json.find("\"algorithms\"").unwrap() < json.find("\"caches\"").unwrap());
+        let restored: EngineConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, config);
+    }
+    
+    #[test]
+    fn test_fingerprint_tracks_configuration_changes() {
+        let mut engine = configured();
+        let before = engine.config_fingerprint().unwrap();
+        engine.add_postprocessor(Box::new(|output| Ok(output.to_vec())));
+        assert_ne!(engine.config_fingerprint().unwrap(), before);
+        assert_ne!(CoreEngine::new().config_fingerprint().unwrap(), before);
+    }
+}
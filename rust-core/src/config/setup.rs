@@ -0,0 +1,529 @@
+//! Building an engine, its sensors and its scheduler from a setup file
+//!
+//! A setup file is JSON, or YAML or TOML with the respective features,
+//! chosen by its extension. Every section is optional:
+//!
+//! - `engine`: `threads`, `max_depth` and `seed`, as on `CoreEngineBuilder`
+//! - `memory`: `strict_keys`, `missing_key_policy` (`AutoCreate` or
+//!   `Error`), and limits: `pool_block_size` to serve regions from a buffer
+//!   pool, `reserve_bytes` to fill it up front or `pool_blocks` to fix it at
+//!   that many preallocated blocks, or `spill_threshold` to spill larger
+//!   regions to disk (`spill` feature)
+//! - `algorithms`: a list registering each `id` either as a `builtin` or
+//!   from a `definition` file, relative to the setup file, with `params`
+//!   configuring the built-in or overriding the definition's defaults
+//! - `sensors`: a list of sensors with an `id` and a `type`: `mock`
+//!   replaying `samples` at `rate_hz`, or `sine` of `frequency_hz` and
+//!   `amplitude` sampled at `rate_hz`. `poll_hz` polls slower than that.
+//! - `devices`: a list of devices with a `name` and a `backend`: `null`, or
+//!   `mock`, `gpio`, `pwm` (with `chip` and `period_us`) or `serial` (with
+//!   `path` and `baud`) hardware, driven as `output` `pins`, `pwm` or
+//!   `serial` on the listed `channels`
+//! - `tasks`: a list of scheduled runs of an `algorithm` every `period_ms`,
+//!   with an optional `priority` and `deadline_ms`
+//!
+//! Unknown keys are rejected, since they are most likely misspelt. Errors
+//! are `CoreError::InvalidDefinition` naming the offending key, such as
+//! `sensors[1].rate_hz: must be positive, got 0`.
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::algorithm::builtins;
+use crate::algorithm::definition::AlgorithmDefinition;
+use crate::error::CoreError;
+use crate::hardware::{Device, HardwareDevice, HardwareInterface, MockHardware, NullDevice, OutputMapping};
+use crate::memory::{MemoryManager, MissingKeyPolicy};
+use crate::scheduler::{Scheduler, Task};
+use crate::sensor::{FunctionSensor, MockSensor, Sensor, SensorManager};
+use crate::CoreEngine;
+
+/// An engine with the sensors and scheduled tasks a setup file describes
+pub struct EngineSetup {
+    pub engine: CoreEngine,
+    /// Configured sensors, publishing into `engine`'s memory when polled
+    pub sensors: SensorManager,
+    /// Configured tasks, run on `engine` when ticked
+    pub scheduler: Scheduler,
+}
+
+impl EngineSetup {
+    /// Load the setup file at `path`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CoreError> {
+        let path = path.as_ref();
+        let base = path.parent().unwrap_or(Path::new(""));
+        Self::from_value(&read_document(path)?, base)
+    }
+    
+    /// Parse a setup from JSON, with definition files relative to the
+    /// working directory
+    pub fn from_json(source: &str) -> Result<Self, CoreError> {
+        Self::from_value(&parse(source, Format::Json)?, Path::new(""))
+    }
+    
+    /// Parse a setup from YAML
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(source: &str) -> Result<Self, CoreError> {
+        Self::from_value(&parse(source, Format::Yaml)?, Path::new(""))
+    }
+    
+    /// Parse a setup from TOML
+    #[cfg(feature = "toml")]
+    pub fn from_toml(source: &str) -> Result<Self, CoreError> {
+        Self::from_value(&parse(source, Format::Toml)?, Path::new(""))
+    }
+    
+    fn from_value(document: &Value, base: &Path) -> Result<Self, CoreError> {
+        let root = Table::new(String::new(), document)?;
+        root.check_keys(&["engine", "memory", "algorithms", "sensors", "devices", "tasks"])?;
+        let mut engine = build_engine(root.table("engine")?)?;
+        if let Some(memory) = root.table("memory")? {
+            engine.memory_manager = build_memory(&memory)?;
+        }
+        for entry in root.list("algorithms")? {
+            register_algorithm(&mut engine, &entry, base)?;
+        }
+        let mut sensors = SensorManager::new();
+        for entry in root.list("sensors")? {
+            let poll_hz = entry.get("poll_hz")?;
+            let sensor = build_sensor(&entry)?;
+            sensors.add(sensor, poll_hz).map_err(|e| entry.invalid(e))?;
+        }
+        for entry in root.list("devices")? {
+            let device = build_device(&entry)?;
+            if engine.devices.contains_key(device.name()) {
+                return Err(entry.invalid(format!("device '{}' is already registered", device.name())));
+            }
+            engine.register_device(device);
+        }
+        let mut scheduler = Scheduler::new();
+        for entry in root.list("tasks")? {
+            let task = build_task(&engine, &entry)?;
+            scheduler.add_task(task).map_err(|e| entry.invalid(e))?;
+        }
+        Ok(Self {
+            engine,
+            sensors,
+            scheduler,
+        })
+    }
+}
+
+impl CoreEngine {
+    /// Create an engine as described by the setup file at `path`
+    ///
+    /// Sensors and tasks in the file are validated but not kept; use
+    /// `EngineSetup::load` to poll and schedule them.
+    pub fn from_config(path: impl AsRef<Path>) -> Result<Self, CoreError> {
+        Ok(EngineSetup::load(path)?.engine)
+    }
+}
+
+fn build_engine(section: Option<Table>) -> Result<CoreEngine, CoreError> {
+    let mut builder = CoreEngine::builder();
+    let Some(section) = section else {
+        return Ok(builder.build());
+    };
+    section.check_keys(&["threads", "max_depth", "seed"])?;
+    if let Some(threads) = section.get::<usize>("threads")? {
+        builder = builder.threads(section.at_least("threads", threads, 1)?);
+    }
+    if let Some(max_depth) = section.get::<usize>("max_depth")? {
+        builder = builder.max_depth(section.at_least("max_depth", max_depth, 1)?);
+    }
+    if let Some(seed) = section.get("seed")? {
+        builder = builder.seed(seed);
+    }
+    Ok(builder.build())
+}
+
+fn build_memory(section: &Table) -> Result<MemoryManager, CoreError> {
+    section.check_keys(&[
+        "strict_keys",
+        "missing_key_policy",
+        "pool_block_size",
+        "pool_blocks",
+        "reserve_bytes",
+        "spill_threshold",
+    ])?;
+    let pool_block_size = section.get::<usize>("pool_block_size")?;
+    let pool_blocks = section.get::<usize>("pool_blocks")?;
+    if pool_blocks.is_some() && pool_block_size.is_none() {
+        return Err(section.invalid_key("pool_blocks", "needs pool_block_size"));
+    }
+    let spill_threshold = section.get::<usize>("spill_threshold")?;
+    let mut memory = match (pool_block_size, spill_threshold) {
+        (Some(_), Some(_)) => {
+            return Err(section.invalid_key("spill_threshold", "cannot be combined with pool_block_size"));
+        }
+        (Some(block_size), None) => {
+            let block_size = section.at_least("pool_block_size", block_size, 1)?;
+            match pool_blocks {
+                Some(blocks) => MemoryManager::with_fixed_pool(block_size, blocks),
+                None => MemoryManager::with_pool(block_size),
+            }
+        }
+        #[cfg(all(feature = "spill", not(target_arch = "wasm32")))]
+        (None, Some(threshold)) => MemoryManager::with_spill_threshold(threshold),
+        #[cfg(not(all(feature = "spill", not(target_arch = "wasm32"))))]
+        (None, Some(_)) => return Err(section.invalid_key("spill_threshold", "needs the `spill` feature")),
+        (None, None) => MemoryManager::new(),
+    };
+    if let Some(bytes) = section.get::<usize>("reserve_bytes")? {
+        if pool_block_size.is_none() {
+            return Err(section.invalid_key("reserve_bytes", "needs pool_block_size"));
+        }
+        memory.reserve(bytes);
+    }
+    if let Some(strict) = section.get("strict_keys")? {
+        memory.set_strict_keys(strict);
+    }
+    if let Some(policy) = section.get::<MissingKeyPolicy>("missing_key_policy")? {
+        memory.set_missing_key_policy(policy);
+    }
+    Ok(memory)
+}
+
+fn register_algorithm(engine: &mut CoreEngine, entry: &Table, base: &Path) -> Result<(), CoreError> {
+    entry.check_keys(&["id", "builtin", "definition", "params"])?;
+    let id: String = entry.require("id")?;
+    let params = entry.get::<Value>("params")?.unwrap_or(Value::Null);
+    let algorithm = match (entry.get::<String>("builtin")?, entry.get::<String>("definition")?) {
+        (Some(builtin), None) => builtins::create(&builtin, &params),
+        (None, Some(definition)) => {
+            let path = base.join(&definition);
+            let definition: AlgorithmDefinition = serde_json::from_value(read_document(&path)?)
+                .map_err(|e| entry.invalid_key("definition", format!("{}: {}", path.display(), e)))?;
+            definition.build_with(&params)
+        }
+        _ => return Err(entry.invalid("needs exactly one of builtin or definition")),
+    };
+    let algorithm = algorithm.map_err(|e| entry.invalid(e))?;
+    engine.registry.register_as(&id, algorithm).map_err(|e| entry.invalid(e))
+}
+
+fn build_sensor(entry: &Table) -> Result<Box<dyn Sensor>, CoreError> {
+    let kind: String = entry.require("type")?;
+    let id: String = entry.require("id")?;
+    let rate_hz = entry.positive("rate_hz")?;
+    let sensor: Box<dyn Sensor> = match kind.as_str() {
+        "mock" => {
+            entry.check_keys(&["id", "type", "rate_hz", "poll_hz", "samples"])?;
+            Box::new(MockSensor::new(&id, rate_hz, entry.require("samples")?).map_err(|e| entry.invalid(e))?)
+        }
+        "sine" => {
+            entry.check_keys(&["id", "type", "rate_hz", "poll_hz", "frequency_hz", "amplitude"])?;
+            let frequency = entry.positive("frequency_hz")?;
+            let amplitude = entry.get::<f64>("amplitude")?.unwrap_or(1.0);
+            let interval = Duration::try_from_secs_f64(1.0 / rate_hz)
+                .map_err(|_| entry.invalid_key("rate_hz", format!("is too small, got {}", rate_hz)))?;
+            let sensor = FunctionSensor::new(&id, interval, move |micros| {
+                let phase = 2.0 * std::f64::consts::PI * frequency * micros as f64 / 1e6;
+                ((amplitude * phase.sin()) as f32).to_ne_bytes().to_vec()
+            });
+            Box::new(sensor.map_err(|e| entry.invalid_key("rate_hz", e))?)
+        }
+        other => return Err(entry.invalid_key("type", format!("unknown sensor type '{}'", other))),
+    };
+    Ok(sensor)
+}
+
+fn build_device(entry: &Table) -> Result<Box<dyn Device>, CoreError> {
+    let name: String = entry.require("name")?;
+    let backend: String = entry.require("backend")?;
+    if backend == "null" {
+        entry.check_keys(&["name", "backend"])?;
+        return Ok(Box::new(NullDevice::new(&name)));
+    }
+    let hardware: Box<dyn HardwareInterface> = match backend.as_str() {
+        "mock" => {
+            entry.check_keys(&["name", "backend", "output", "channels"])?;
+            Box::new(MockHardware::new(&name))
+        }
+        #[cfg(all(feature = "gpio", target_os = "linux"))]
+        "gpio" => {
+            entry.check_keys(&["name", "backend", "output", "channels"])?;
+            Box::new(crate::hardware::SysfsGpio::new(&name))
+        }
+        #[cfg(all(feature = "pwm", target_os = "linux"))]
+        "pwm" => {
+            entry.check_keys(&["name", "backend", "output", "channels", "chip", "period_us"])?;
+            let period = Duration::from_micros(entry.require("period_us")?);
+            let pwm = crate::hardware::SysfsPwm::new(&name, entry.require("chip")?, period);
+            Box::new(pwm.map_err(|e| entry.invalid(e))?)
+        }
+        #[cfg(all(feature = "serial", unix))]
+        "serial" => {
+            entry.check_keys(&["name", "backend", "output", "channels", "path", "baud"])?;
+            let path: String = entry.require("path")?;
+            let port = crate::hardware::SerialPort::open(&name, path, entry.require("baud")?);
+            Box::new(port.map_err(|e| entry.invalid(e))?)
+        }
+        // Reached only for backends compiled out of this build
+        #[allow(unreachable_patterns)]
+        "gpio" | "pwm" | "serial" => {
+            return Err(entry.invalid_key("backend", format!("needs the `{}` feature on this platform", backend)));
+        }
+        other => return Err(entry.invalid_key("backend", format!("unknown backend '{}'", other))),
+    };
+    let channels = entry.get::<Vec<u32>>("channels")?;
+    let mapping = match (entry.require::<String>("output")?.as_str(), channels) {
+        ("pins", Some(pins)) => OutputMapping::Pins(pins),
+        ("pwm", Some(channels)) => OutputMapping::Pwm(channels),
+        ("serial", None) => OutputMapping::Serial,
+        ("pins" | "pwm", None) => return Err(entry.invalid_key("channels", "is required")),
+        ("serial", Some(_)) => return Err(entry.invalid_key("channels", "does not apply to serial output")),
+        (other, _) => return Err(entry.invalid_key("output", format!("unknown output '{}'", other))),
+    };
+    Ok(Box::new(HardwareDevice::new(&name, hardware, mapping)))
+}
+
+fn build_task(engine: &CoreEngine, entry: &Table) -> Result<Task, CoreError> {
+    entry.check_keys(&["algorithm", "period_ms", "priority", "deadline_ms"])?;
+    let algorithm: String = entry.require("algorithm")?;
+    if crate::resolve(&engine.registry, &algorithm).is_none() {
+        return Err(entry.invalid_key("algorithm", format!("'{}' is not registered", algorithm)));
+    }
+    let millis = |key: &str, ms: f64| {
+        Duration::try_from_secs_f64(ms / 1e3)
+            .map_err(|_| entry.invalid_key(key, format!("is out of range, got {}", ms)))
+    };
+    let mut task = Task::new(&algorithm, millis("period_ms", entry.positive("period_ms")?)?);
+    if let Some(priority) = entry.get("priority")? {
+        task = task.with_priority(priority);
+    }
+    if let Some(deadline) = entry.get("deadline_ms")? {
+        task = task.with_deadline(millis("deadline_ms", deadline)?);
+    }
+    Ok(task)
+}
+
+/// A table of the setup document, with its path for errors
+struct Table<'a> {
+    path: String,
+    fields: &'a Map<String, Value>,
+}
+
+impl<'a> Table<'a> {
+    fn new(path: String, value: &'a Value) -> Result<Self, CoreError> {
+        match value {
+            Value::Object(fields) => Ok(Self { path, fields }),
+            _ => Err(invalid(&path, "must be a table")),
+        }
+    }
+    
+    fn key_path(&self, key: &str) -> String {
+        if self.path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", self.path, key)
+        }
+    }
+    
+    fn invalid(&self, message: impl std::fmt::Display) -> CoreError {
+        invalid(&self.path, message)
+    }
+    
+    fn invalid_key(&self, key: &str, message: impl std::fmt::Display) -> CoreError {
+        invalid(&self.key_path(key), message)
+    }
+    
+    fn check_keys(&self, known: &[&str]) -> Result<(), CoreError> {
+        match self.fields.keys().find(|key| !known.contains(&key.as_str())) {
+            Some(key) => Err(self.invalid_key(key, "unknown key")),
+            None => Ok(()),
+        }
+    }
+    
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, CoreError> {
+        self.fields
+            .get(key)
+            .map(|value| T::deserialize(value).map_err(|e| self.invalid_key(key, e)))
+            .transpose()
+    }
+    
+    fn require<T: DeserializeOwned>(&self, key: &str) -> Result<T, CoreError> {
+        self.get(key)?.ok_or_else(|| self.invalid_key(key, "is required"))
+    }
+    
+    /// A required number that must be finite and positive
+    fn positive(&self, key: &str) -> Result<f64, CoreError> {
+        let value: f64 = self.require(key)?;
+        if !value.is_finite() || value <= 0.0 {
+            return Err(self.invalid_key(key, format!("must be positive, got {}", value)));
+        }
+        Ok(value)
+    }
+    
+    fn at_least(&self, key: &str, value: usize, min: usize) -> Result<usize, CoreError> {
+        if value < min {
+            return Err(self.invalid_key(key, format!("must be at least {}, got {}", min, value)));
+        }
+        Ok(value)
+    }
+    
+    fn table(&self, key: &str) -> Result<Option<Table<'a>>, CoreError> {
+        self.fields.get(key).map(|value| Table::new(self.key_path(key), value)).transpose()
+    }
+    
+    fn list(&self, key: &str) -> Result<Vec<Table<'a>>, CoreError> {
+        match self.fields.get(key) {
+            None => Ok(Vec::new()),
+            Some(Value::Array(entries)) => entries
+                .iter()
+                .enumerate()
+                .map(|(index, entry)| Table::new(format!("{}[{}]", self.key_path(key), index), entry))
+                .collect(),
+            Some(_) => Err(self.invalid_key(key, "must be a list")),
+        }
+    }
+}
+
+fn invalid(path: &str, message: impl std::fmt::Display) -> CoreError {
+    if path.is_empty() {
+        CoreError::InvalidDefinition(format!("setup {}", message))
+    } else {
+        CoreError::InvalidDefinition(format!("{}: {}", path, message))
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    Json,
+    #[cfg(feature = "yaml")]
+    Yaml,
+    #[cfg(feature = "toml")]
+    Toml,
+}
+
+/// Read a JSON, YAML or TOML document, by the extension of `path`
+fn read_document(path: &Path) -> Result<Value, CoreError> {
+    let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or("");
+    let format = match extension {
+        "json" => Format::Json,
+        #[cfg(feature = "yaml")]
+        "yaml" | "yml" => Format::Yaml,
+        #[cfg(feature = "toml")]
+        "toml" => Format::Toml,
+        #[allow(unreachable_patterns)]
+        "yaml" | "yml" | "toml" => {
+            return Err(CoreError::UnsupportedCapability(format!(
+                "{} needs the `{}` feature",
+                path.display(),
+                if extension == "toml" { "toml" } else { "yaml" }
+            )));
+        }
+        _ => {
+            return Err(CoreError::InvalidDefinition(format!(
+                "{} is not .json, .yaml or .toml",
+                path.display()
+            )));
+        }
+    };
+    let source = fs::read_to_string(path)?;
+    parse(&source, format).map_err(|e| match e {
+        CoreError::InvalidDefinition(message) => {
+            CoreError::InvalidDefinition(format!("{}: {}", path.display(), message))
+        }
+        other => other,
+    })
+}
+
+fn parse(source: &str, format: Format) -> Result<Value, CoreError> {
+    let parsed = match format {
+        Format::Json => serde_json::from_str(source).map_err(|e| e.to_string()),
+        #[cfg(feature = "yaml")]
+        Format::Yaml => serde_yaml::from_str(source).map_err(|e| e.to_string()),
+        #[cfg(feature = "toml")]
+        Format::Toml => toml::from_str(source).map_err(|e| e.to_string()),
+    };
+    parsed.map_err(CoreError::InvalidDefinition)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::samples;
+    
+    const SETUP: &str = r#"{
+        "engine": { "threads": 2, "seed": 7 },
+        "memory": { "strict_keys": true, "missing_key_policy": "AutoCreate", "pool_block_size": 64, "pool_blocks": 16 },
+        "algorithms": [
+            { "id": "gain", "builtin": "scale", "params": { "factor": 2.0 } },
+            { "id": "limit", "builtin": "clamp", "params": { "min": 0.0, "max": 1.0 } }
+        ],
+        "sensors": [
+            { "id": "imu", "type": "mock", "rate_hz": 100.0, "samples": [0.5, 1.5] },
+            { "id": "wave", "type": "sine", "rate_hz": 50.0, "frequency_hz": 1.0, "poll_hz": 10.0 }
+        ],
+        "devices": [
+            { "name": "log", "backend": "null" },
+            { "name": "motors", "backend": "mock", "output": "pwm", "channels": [0, 1] }
+        ],
+        "tasks": [{ "algorithm": "gain", "period_ms": 10, "priority": 3, "deadline_ms": 2.5 }]
+    }"#;
+    
+    #[test]
+    fn test_setup_builds_engine_sensors_and_tasks() {
+        let EngineSetup {
+            mut engine,
+            mut sensors,
+            mut scheduler,
+        } = EngineSetup::from_json(SETUP).unwrap();
+        let config = engine.config();
+        assert_eq!((config.threads, config.seed), (2, Some(7)));
+        assert_eq!((config.memory.pool_block_size, config.memory.pool_blocks), (Some(64), Some(16)));
+        assert!(config.memory.strict_keys);
+        assert_eq!(config.devices.iter().collect::<Vec<_>>(), ["log", "motors"]);
+        
+        let input = samples::f32_to_bytes(&[0.25, 3.0]);
+        assert_eq!(engine.execute_algorithm("gain", &input).unwrap(), samples::f32_to_bytes(&[0.5, 6.0]));
+        assert_eq!(engine.execute_algorithm("limit", &input).unwrap(), samples::f32_to_bytes(&[0.25, 1.0]));
+        
+        assert_eq!(sensors.ids(), ["imu", "wave"]);
+        let polls = sensors.poll(engine.memory_mut(), Duration::ZERO);
+        assert!(polls.iter().all(|poll| poll.result.is_ok()));
+        assert_eq!(sensors.latest("imu").unwrap().payload_as_f32(), [0.5]);
+        assert_eq!(scheduler.tick(&mut engine, Duration::ZERO).len(), 1);
+    }
+    
+    #[test]
+    fn test_errors_name_the_offending_key() {
+        let error = |setup: &str| match EngineSetup::from_json(setup) {
+            Err(CoreError::InvalidDefinition(message)) => message,
+            Err(other) => panic!("unexpected error {:?}", other),
+            Ok(_) => panic!("{} was accepted", setup),
+        };
+        let message = error(r#"{ "sensors": [{ "id": "a", "type": "mock", "rate_hz": 1, "samples": [0] },
+            { "id": "b", "type": "mock", "rate_hz": 0, "samples": [0] }] }"#);
+        assert_eq!(message, "sensors[1].rate_hz: must be positive, got 0");
+        assert!(error(r#"{ "memory": { "strict_key": true } }"#).starts_with("memory.strict_key: unknown key"));
+        assert!(error(r#"{ "engine": { "threads": "two" } }"#).starts_with("engine.threads: invalid type"));
+        let message = error(r#"{ "tasks": [{ "algorithm": "missing", "period_ms": 5 }] }"#);
+        assert_eq!(message, "tasks[0].algorithm: 'missing' is not registered");
+        assert!(error(r#"{ "algorithms": [{ "id": "x", "builtin": "scale" }] }"#).starts_with("algorithms[0]: "));
+        assert!(error(r#"{ "devices": [{ "name": "m", "backend": "mock", "output": "pins" }] }"#)
+            .starts_with("devices[0].channels: is required"));
+        assert!(error("[]").starts_with("setup must be a table"));
+        
+        let path = std::env::temp_dir().join(format!("robotics_core_setup_{}.json", std::process::id()));
+        fs::write(&path, r#"{ "engine": { "max_depth": 4 } }"#).unwrap();
+        assert_eq!(CoreEngine::from_config(&path).unwrap().config().max_depth, 4);
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(CoreEngine::from_config("setup.ini"), Err(CoreError::InvalidDefinition(_))));
+    }
+    
+    #[cfg(all(feature = "yaml", feature = "toml"))]
+    #[test]
+    fn test_formats_are_interchangeable() {
+        const YAML: &str = "engine: { max_depth: 4 }\ntasks:\n  - { algorithm: passthrough, period_ms: 5 }\n";
+        const TOML: &str = "engine = { max_depth = 4 }\n\n[[tasks]]\nalgorithm = \"passthrough\"\nperiod_ms = 5\n";
+        for setup in [EngineSetup::from_yaml(YAML).unwrap(), EngineSetup::from_toml(TOML).unwrap()] {
+            assert_eq!(setup.engine.config().max_depth, 4);
+            assert_eq!(setup.scheduler.task_stats()[0].0, "passthrough");
+        }
+    }
+}
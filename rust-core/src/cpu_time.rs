@@ -0,0 +1,151 @@
+//! Accounting for the CPU time an execution consumes
+
+use std::time::Duration;
+
+use crate::error::CoreError;
+use crate::CoreEngine;
+
+/// Time one execution took, by the wall and by the CPU
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExecutionMetrics {
+    /// Elapsed time, as measured by the engine's clock
+    pub duration: Duration,
+    /// CPU time the executing thread consumed, or `duration` when
+    /// `cpu_measured` is false
+    pub cpu_duration: Duration,
+    /// Whether `cpu_duration` came from a thread CPU clock
+    ///
+    /// False when the crate was built without the `cpu-time` feature or the
+    /// platform has no per-thread CPU clock (anything but Unix); the
+    /// wall-clock duration then stands in for CPU time, which overstates it
+    /// for a thread that was descheduled or blocked.
+    pub cpu_measured: bool,
+}
+
+/// CPU time consumed by the calling thread so far
+#[cfg(all(feature = "cpu-time", unix))]
+pub(crate) fn thread_cpu_time() -> Option<Duration> {
+    let mut now = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: `now` is a valid, writable timespec for the duration of the
+    // call, and CLOCK_THREAD_CPUTIME_ID needs no other setup.
+    if unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut now) } != 0 {
+        return None;
+    }
+    Some(Duration::new(u64::try_from(now.tv_sec).ok()?, u32::try_from(now.tv_nsec).ok()?))
+}
+
+#[cfg(not(all(feature = "cpu-time", unix)))]
+pub(crate) fn thread_cpu_time() -> Option<Duration> {
+    None
+}
+
+impl CoreEngine {
+    /// Execute an algorithm and return its output with the wall-clock and
+    /// CPU time it took
+    ///
+    /// The algorithm runs on the calling thread, so its CPU time is the
+    /// thread's: time spent sleeping, blocked or descheduled is excluded.
+    /// See `ExecutionMetrics::cpu_measured` for when that is unavailable.
+    pub fn execute_algorithm_measured(
+        &mut self,
+        algorithm_id: &str,
+        input_data: &[u8],
+    ) -> Result<(Vec<u8>, ExecutionMetrics), CoreError> {
+        let cpu_start = thread_cpu_time();
+        let start = self.clock.now();
+        let output = self.execute_algorithm(algorithm_id, input_data)?;
+        let duration = self.clock.now().saturating_sub(start);
+        let cpu_duration = cpu_start.zip(thread_cpu_time()).map(|(start, end)| end.saturating_sub(start));
+        let metrics = ExecutionMetrics {
+            duration,
+            cpu_duration: cpu_duration.unwrap_or(duration),
+            cpu_measured: cpu_duration.is_some(),
+        };
+        Ok((output, metrics))
+    }
+    
+    /// Execute an algorithm like `execute_algorithm_measured`, failing with
+    /// `CoreError::CpuBudgetExceeded` if it used more than `budget` of CPU
+    /// time
+    ///
+    /// The budget is checked once the algorithm returns, not enforced while
+    /// it runs: an over-budget execution still completes and keeps any memory
+    /// writes it made, but its output is dropped. Without a thread CPU clock
+    /// the wall-clock duration is checked instead.
+    pub fn execute_algorithm_cpu_budgeted(
+        &mut self,
+        algorithm_id: &str,
+        input_data: &[u8],
+        budget: Duration,
+    ) -> Result<(Vec<u8>, ExecutionMetrics), CoreError> {
+        let (output, metrics) = self.execute_algorithm_measured(algorithm_id, input_data)?;
+        if metrics.cpu_duration > budget {
+            return Err(CoreError::CpuBudgetExceeded {
+                budget,
+                used: metrics.cpu_duration,
+            });
+        }
+        Ok((output, metrics))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{Algorithm, AlgorithmMetadata};
+    use crate::memory::MemoryManager;
+    
+    /// Sleeps without using the CPU
+    struct Sleepy;
+    
+    impl Algorithm for Sleepy {
+        fn process(&self, input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            std::thread::sleep(Duration::from_millis(20));
+            Ok(input.to_vec())
+        }
+        
+        fn id(&self) -> &str {
+            "sleepy"
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata::default()
+        }
+    }
+    
+    #[test]
+    fn test_cpu_duration_within_wall_clock_for_sleeping_algorithm() {
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(Sleepy)).unwrap();
+        let (output, metrics) = engine.execute_algorithm_measured("sleepy", &[1, 2]).unwrap();
+        assert_eq!(output, vec![1, 2]);
+        assert!(metrics.duration >= Duration::from_millis(20));
+        assert!(metrics.cpu_duration <= metrics.duration, "{:?}", metrics);
+        assert_eq!(metrics.cpu_measured, cfg!(all(feature = "cpu-time", unix)));
+        if metrics.cpu_measured {
+            // Sleeping uses next to no CPU
+            assert!(metrics.cpu_duration < Duration::from_millis(10), "{:?}", metrics);
+        } else {
+            assert_eq!(metrics.cpu_duration, metrics.duration);
+        }
+    }
+    
+    #[test]
+    fn test_cpu_budget_checked_after_execution() {
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(Sleepy)).unwrap();
+        assert!(engine.execute_algorithm_cpu_budgeted("sleepy", &[], Duration::from_secs(5)).is_ok());
+        
+        let result = engine.execute_algorithm_cpu_budgeted("passthrough", &[1], Duration::ZERO);
+        match result {
+            Err(CoreError::CpuBudgetExceeded { budget, used }) => assert!(budget == Duration::ZERO && used > budget),
+            // A coarse clock can read no time at all for a trivial run
+            Ok((_, metrics)) => assert_eq!(metrics.cpu_duration, Duration::ZERO),
+            Err(e) => panic!("unexpected error {:?}", e),
+        }
+        assert!(matches!(
+            engine.execute_algorithm_cpu_budgeted("missing", &[], Duration::MAX),
+            Err(CoreError::AlgorithmNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,138 @@
+//! Soft-real-time monitoring of execution time budgets
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::error::CoreError;
+use crate::CoreEngine;
+
+/// An execution that took longer than its algorithm's `expected_duration`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeadlineMiss {
+    /// ID the algorithm was executed under
+    pub algorithm_id: String,
+    /// Budget declared in the algorithm's metadata
+    pub expected: Duration,
+    /// Time the execution actually took
+    pub actual: Duration,
+}
+
+type MissCallback = Box<dyn Fn(&DeadlineMiss) + Send + Sync>;
+
+/// Miss counters and the optional callback, owned by the engine
+#[derive(Default)]
+pub(crate) struct DeadlineMonitor {
+    misses: HashMap<String, u64>,
+    on_miss: Option<MissCallback>,
+}
+
+impl CoreEngine {
+    /// Execute an algorithm and return its output with the time it took
+    ///
+    /// The time is measured on the engine's clock. If the algorithm declares
+    /// an `expected_duration` and the execution exceeds it, the miss is
+    /// counted and passed to the `on_deadline_miss` callback; the output is
+    /// returned either way, since the budget is monitored, not enforced.
+    pub fn execute_algorithm_timed(&mut self, algorithm_id: &str, input_data: &[u8]) -> Result<(Vec<u8>, Duration), CoreError> {
+        let expected = self
+            .get_algorithm(algorithm_id)
+            .and_then(|algorithm| algorithm.metadata().expected_duration);
+        let start = self.clock.now();
+        let output = self.execute_algorithm(algorithm_id, input_data)?;
+        let actual = self.clock.now().saturating_sub(start);
+        
+        if let Some(expected) = expected.filter(|&expected| actual > expected) {
+            *self.deadlines.misses.entry(algorithm_id.to_string()).or_insert(0) += 1;
+            let miss = DeadlineMiss {
+                algorithm_id: algorithm_id.to_string(),
+                expected,
+                actual,
+            };
+            crate::logging::algorithm_log!(
+                self.log_levels,
+                algorithm_id,
+                log::Level::Warn,
+                "Algorithm {} took {:?}, budget {:?}",
+                algorithm_id,
+                actual,
+                expected
+            );
+            if let Some(callback) = &self.deadlines.on_miss {
+                callback(&miss);
+            }
+        }
+        Ok((output, actual))
+    }
+    
+    /// Call `callback` on every deadline miss, replacing any previous one
+    pub fn on_deadline_miss(&mut self, callback: impl Fn(&DeadlineMiss) + Send + Sync + 'static) {
+        self.deadlines.on_miss = Some(Box::new(callback));
+    }
+    
+    /// Number of timed executions of `algorithm_id` that exceeded its budget
+    pub fn deadline_misses(&self, algorithm_id: &str) -> u64 {
+        self.deadlines.misses.get(algorithm_id).copied().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{Algorithm, AlgorithmMetadata};
+    use crate::clock::MockClock;
+    use crate::memory::MemoryManager;
+    use std::sync::{Arc, Mutex};
+    
+    /// Advances the mock clock by the length of its input, in milliseconds
+    struct Slow(Arc<MockClock>);
+    
+    impl Algorithm for Slow {
+        fn process(&self, input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            self.0.advance(Duration::from_millis(input.len() as u64));
+            Ok(input.to_vec())
+        }
+        
+        fn id(&self) -> &str {
+            "slow"
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata {
+                expected_duration: Some(Duration::from_millis(2)),
+                ..Default::default()
+            }
+        }
+    }
+    
+    #[test]
+    fn test_overrun_counts_and_fires_callback() {
+        let clock = Arc::new(MockClock::new());
+        let mut engine = CoreEngine::with_clock(clock.clone());
+        engine.register(Box::new(Slow(clock))).unwrap();
+        let misses = Arc::new(Mutex::new(Vec::new()));
+        let seen = misses.clone();
+        engine.on_deadline_miss(move |miss| seen.lock().unwrap().push(miss.clone()));
+        
+        let (output, took) = engine.execute_algorithm_timed("slow", &[0; 2]).unwrap();
+        assert_eq!((output, took), (vec![0; 2], Duration::from_millis(2)));
+        assert_eq!(engine.deadline_misses("slow"), 0);
+        
+        let (output, took) = engine.execute_algorithm_timed("slow", &[0; 5]).unwrap();
+        assert_eq!((output.len(), took), (5, Duration::from_millis(5)));
+        assert_eq!(engine.deadline_misses("slow"), 1);
+        assert_eq!(
+            *misses.lock().unwrap(),
+            vec![DeadlineMiss {
+                algorithm_id: "slow".to_string(),
+                expected: Duration::from_millis(2),
+                actual: Duration::from_millis(5),
+            }]
+        );
+        
+        // Untimed executions and algorithms without a budget are not monitored
+        engine.execute_algorithm("slow", &[0; 5]).unwrap();
+        engine.execute_algorithm_timed("passthrough", &[0; 5]).unwrap();
+        assert_eq!(engine.deadline_misses("slow"), 1);
+        assert_eq!(engine.deadline_misses("passthrough"), 0);
+    }
+}
@@ -0,0 +1,230 @@
+//! Error types shared across the core
+
+use alloc::string::String;
+use core::fmt;
+use core::time::Duration;
+
+use crate::memory::RegionType;
+
+/// Errors produced by the core engine and its components
+#[derive(Clone, Debug, PartialEq)]
+pub enum CoreError {
+    /// No algorithm is registered under the given ID
+    AlgorithmNotFound(String),
+    /// An algorithm is already registered under the given ID
+    DuplicateAlgorithm(String),
+    /// An algorithm parameter is missing or malformed
+    InvalidParameter(String),
+    /// Input data cannot be interpreted by the algorithm
+    InvalidInput(String),
+    /// Input is shorter than the algorithm's declared minimum
+    InputTooSmall { required: usize, actual: usize },
+    /// Data does not conform to a declared schema
+    SchemaMismatch(String),
+    /// An algorithm definition cannot be parsed or built
+    InvalidDefinition(String),
+    /// An algorithm failed while processing its input
+    ProcessingFailed(String),
+    /// A condition required before running does not hold
+    PreconditionFailed(String),
+    /// An execution used more CPU time than it was allowed
+    CpuBudgetExceeded { budget: Duration, used: Duration },
+    /// Integer arithmetic left the range of its type
+    ArithmeticOverflow(String),
+    /// A pipeline or DAG is deeper than the engine allows
+    MaxDepthExceeded { depth: usize, max: usize },
+    /// A memory manager operation failed
+    MemoryError(String),
+    /// The system could not provide a buffer of the requested size
+    AllocationFailed { size: usize },
+    /// Every block of a fixed memory pool is in use
+    PoolExhausted { block_size: usize, blocks: usize },
+    /// The requested memory region does not exist
+    MemoryKeyMissing(String),
+    /// A memory region already exists under the given key
+    KeyAlreadyExists(String),
+    /// The region is still referenced and cannot be freed
+    RegionInUse { key: String, refs: usize },
+    /// A region tagged with one element type was read as another
+    TypeMismatch {
+        key: String,
+        expected: RegionType,
+        actual: RegionType,
+    },
+    /// The operation is not allowed on the region
+    PermissionDenied(String),
+    /// The region was frozen and can no longer be modified
+    RegionFrozen(String),
+    /// Reading or writing a file failed
+    IoError(String),
+    /// A sensor failed to deliver a reading
+    SensorError(String),
+    /// No device is registered under the given name
+    DeviceNotFound(String),
+    /// No pipeline is registered under the given ID
+    PipelineNotFound(String),
+    /// An algorithm needs crate features this build was compiled without, or
+    /// a hardware backend lacks an operation
+    UnsupportedCapability(String),
+    /// A finite frame source has no more frames
+    EndOfStream,
+    /// The caller cancelled the execution
+    Cancelled,
+}
+
+impl fmt::Display for CoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoreError::AlgorithmNotFound(id) => write!(f, "Algorithm not found: {}", id),
+            CoreError::DuplicateAlgorithm(id) => write!(f, "Algorithm already registered: {}", id),
+            CoreError::InvalidParameter(msg) => write!(f, "Invalid parameter: {}", msg),
+            CoreError::InvalidInput(msg) => write!(f, "Invalid input: {}", msg),
+            CoreError::InputTooSmall { required, actual } => {
+                write!(f, "Input too small: {} bytes, at least {} required", actual, required)
+            }
+            CoreError::SchemaMismatch(msg) => write!(f, "Schema mismatch: {}", msg),
+            CoreError::InvalidDefinition(msg) => write!(f, "Invalid definition: {}", msg),
+            CoreError::ProcessingFailed(msg) => write!(f, "Processing failed: {}", msg),
+            CoreError::PreconditionFailed(msg) => write!(f, "Precondition failed: {}", msg),
+            CoreError::CpuBudgetExceeded { budget, used } => {
+                write!(f, "Used {:?} of CPU time, budget {:?}", used, budget)
+            }
+            CoreError::ArithmeticOverflow(msg) => write!(f, "Arithmetic overflow: {}", msg),
+            CoreError::MaxDepthExceeded { depth, max } => {
+                write!(f, "Depth {} exceeds the maximum of {}", depth, max)
+            }
+            CoreError::MemoryError(msg) => write!(f, "Memory error: {}", msg),
+            CoreError::AllocationFailed { size } => write!(f, "Failed to allocate {} bytes", size),
+            CoreError::PoolExhausted { block_size, blocks } => {
+                write!(f, "All {} pool blocks of {} bytes are in use", blocks, block_size)
+            }
+            CoreError::MemoryKeyMissing(key) => write!(f, "Memory key missing: {}", key),
+            CoreError::KeyAlreadyExists(key) => write!(f, "Memory key already exists: {}", key),
+            CoreError::RegionInUse { key, refs } => {
+                write!(f, "Region '{}' is still referenced {} time(s)", key, refs)
+            }
+            CoreError::TypeMismatch { key, expected, actual } => {
+                write!(f, "Region '{}' holds {} elements, read as {}", key, actual, expected)
+            }
+            CoreError::PermissionDenied(msg) => write!(f, "Permission denied: {}", msg),
+            CoreError::RegionFrozen(key) => write!(f, "Region '{}' is frozen", key),
+            CoreError::IoError(msg) => write!(f, "I/O error: {}", msg),
+            CoreError::SensorError(msg) => write!(f, "Sensor error: {}", msg),
+            CoreError::DeviceNotFound(name) => write!(f, "Device not found: {}", name),
+            CoreError::PipelineNotFound(id) => write!(f, "Pipeline not found: {}", id),
+            CoreError::UnsupportedCapability(msg) => write!(f, "Unsupported capability: {}", msg),
+            CoreError::EndOfStream => write!(f, "End of stream"),
+            CoreError::Cancelled => write!(f, "Execution cancelled"),
+        }
+    }
+}
+
+impl core::error::Error for CoreError {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for CoreError {
+    fn from(err: std::io::Error) -> Self {
+        CoreError::IoError(err.to_string())
+    }
+}
+
+/// How a supervisor should treat a failed operation
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Recoverability {
+    /// The same call may succeed if retried, e.g. after a sensor recovers
+    Transient,
+    /// Retrying the same call will fail the same way; skip it or fix the
+    /// request
+    Permanent,
+    /// The engine's state can no longer be trusted; halt
+    Fatal,
+}
+
+impl CoreError {
+    /// Classify the error for retry decisions
+    ///
+    /// - `Transient`: `SensorError`, `IoError`, `ProcessingFailed` (which
+    ///   also carries caught panics), `RegionInUse`, since references are
+    ///   eventually released, `AllocationFailed` and `PoolExhausted`, since
+    ///   memory may be freed in the meantime, and `PreconditionFailed`,
+    ///   since the state it checks may change.
+    /// - `Fatal`: `MemoryError`, which covers a poisoned lock or a region
+    ///   that no longer has the layout its users expect.
+    /// - `Permanent`: everything else. These reject the request itself:
+    ///   lookups of unknown IDs, keys, devices or pipelines, invalid
+    ///   parameters, input or definitions, type and schema mismatches,
+    ///   permission and key conflicts, frozen regions, arithmetic overflow,
+    ///   excessive depth or CPU time, missing capabilities, the end of a
+    ///   stream, and cancellation, which the caller asked for.
+    pub fn recoverable(&self) -> Recoverability {
+        match self {
+            CoreError::SensorError(_)
+            | CoreError::IoError(_)
+            | CoreError::ProcessingFailed(_)
+            | CoreError::RegionInUse { .. }
+            | CoreError::AllocationFailed { .. }
+            | CoreError::PoolExhausted { .. }
+            | CoreError::PreconditionFailed(_) => Recoverability::Transient,
+            CoreError::MemoryError(_) => Recoverability::Fatal,
+            CoreError::AlgorithmNotFound(_)
+            | CoreError::DuplicateAlgorithm(_)
+            | CoreError::InvalidParameter(_)
+            | CoreError::InvalidInput(_)
+            | CoreError::InputTooSmall { .. }
+            | CoreError::SchemaMismatch(_)
+            | CoreError::InvalidDefinition(_)
+            | CoreError::ArithmeticOverflow(_)
+            | CoreError::MaxDepthExceeded { .. }
+            | CoreError::CpuBudgetExceeded { .. }
+            | CoreError::MemoryKeyMissing(_)
+            | CoreError::KeyAlreadyExists(_)
+            | CoreError::TypeMismatch { .. }
+            | CoreError::PermissionDenied(_)
+            | CoreError::RegionFrozen(_)
+            | CoreError::DeviceNotFound(_)
+            | CoreError::PipelineNotFound(_)
+            | CoreError::UnsupportedCapability(_)
+            | CoreError::EndOfStream
+            | CoreError::Cancelled => Recoverability::Permanent,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_recoverability_classification() {
+        assert_eq!(CoreError::SensorError("timeout".to_string()).recoverable(), Recoverability::Transient);
+        assert_eq!(
+            CoreError::RegionInUse { key: "a".to_string(), refs: 1 }.recoverable(),
+            Recoverability::Transient
+        );
+        assert_eq!(CoreError::AllocationFailed { size: 1 << 40 }.recoverable(), Recoverability::Transient);
+        assert_eq!(
+            CoreError::PoolExhausted { block_size: 64, blocks: 4 }.recoverable(),
+            Recoverability::Transient
+        );
+        assert_eq!(
+            CoreError::PreconditionFailed("flag".to_string()).recoverable(),
+            Recoverability::Transient
+        );
+        assert_eq!(CoreError::AlgorithmNotFound("x".to_string()).recoverable(), Recoverability::Permanent);
+        assert_eq!(
+            CoreError::InputTooSmall { required: 4, actual: 0 }.recoverable(),
+            Recoverability::Permanent
+        );
+        assert_eq!(CoreError::EndOfStream.recoverable(), Recoverability::Permanent);
+        assert_eq!(CoreError::Cancelled.recoverable(), Recoverability::Permanent);
+        assert_eq!(CoreError::MemoryError("poisoned".to_string()).recoverable(), Recoverability::Fatal);
+    }
+    
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_io_error_converts_to_io_variant() {
+        let err = CoreError::from(std::io::Error::new(std::io::ErrorKind::NotFound, "no such file"));
+        assert_eq!(err, CoreError::IoError("no such file".to_string()));
+        assert_eq!(err.recoverable(), Recoverability::Transient);
+    }
+}
@@ -0,0 +1,153 @@
+//! Executing through a shared reference
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::algorithm::registry::AlgorithmRegistry;
+use crate::algorithm::context::ContextSource;
+use crate::algorithm::AlgorithmOutput;
+use crate::error::CoreError;
+use crate::hooks::Hooks;
+use crate::logging::LogLevels;
+use crate::memory::MemoryManager;
+use crate::telemetry::Telemetry;
+use crate::CoreEngine;
+
+/// Executes algorithms through `&self`, for callers that only hold a shared
+/// reference or an `Arc`
+///
+/// A handle captures the engine's registry, log levels and hooks as they
+/// were when the handle was made, and works on its own copy of engine memory
+/// kept behind a mutex. The protected region is still shared with the
+/// engine, as with DAG and batch executions. Every `execute` holds the lock for the whole run, so
+/// executions through one handle are serialized: threads sharing a handle
+/// take turns rather than running in parallel, and a slow algorithm delays
+/// every other caller. Use one handle per thread, or `execute_batch`, for
+/// parallelism. Output caches and deadline monitoring stay with the engine;
+/// executions are recorded in the engine's telemetry.
+pub struct ExecutorHandle {
+    registry: AlgorithmRegistry,
+    log_levels: LogLevels,
+    hooks: Hooks,
+    context: ContextSource,
+    telemetry: Arc<Telemetry>,
+    memory: Mutex<MemoryManager>,
+}
+
+impl ExecutorHandle {
+    /// Execute an algorithm, returning its output along with any attributes
+    ///
+    /// Blocks while another thread is executing through this handle.
+    pub fn execute(&self, algorithm_id: &str, input_data: &[u8]) -> Result<AlgorithmOutput, CoreError> {
+        self.telemetry.timed(algorithm_id, input_data.len(), || {
+            crate::execute_on(
+                &self.registry,
+                &self.log_levels,
+                &self.hooks,
+                &self.context,
+                &mut self.lock(),
+                algorithm_id,
+                input_data,
+            )
+        })
+    }
+    
+    /// Run `f` with exclusive access to the handle's memory
+    pub fn with_memory<R>(&self, f: impl FnOnce(&mut MemoryManager) -> R) -> R {
+        f(&mut self.lock())
+    }
+    
+    /// Take the handle's memory, e.g. to inspect what executions wrote
+    pub fn into_memory(self) -> MemoryManager {
+        self.memory.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    // An algorithm that panicked mid-run leaves memory as it was at the
+    // panic, which is no worse than the engine's own behavior
+    fn lock(&self) -> MutexGuard<'_, MemoryManager> {
+        self.memory.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl CoreEngine {
+    /// Make a handle that executes through `&self`
+    ///
+    /// Algorithms registered after this call are not visible to the handle.
+    pub fn executor(&self) -> ExecutorHandle {
+        self.executor_with_context(self.context.clone())
+    }
+    
+    /// Make a handle like `executor`, building contexts from `context`
+    pub(crate) fn executor_with_context(&self, context: ContextSource) -> ExecutorHandle {
+        ExecutorHandle {
+            registry: self.registry.clone(),
+            log_levels: self.log_levels.clone(),
+            hooks: self.hooks.clone(),
+            context,
+            telemetry: Arc::clone(&self.telemetry),
+            memory: Mutex::new(self.memory_manager.fork()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{Algorithm, AlgorithmMetadata};
+    use std::sync::Arc;
+    use std::thread;
+    
+    /// Appends its input to the `log` region
+    struct Logger;
+    
+    impl Algorithm for Logger {
+        fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            memory.append("log", input)?;
+            Ok(input.to_vec())
+        }
+        
+        fn id(&self) -> &str {
+            "logger"
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata::default()
+        }
+    }
+    
+    #[test]
+    fn test_handle_shared_across_threads() {
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(Logger)).unwrap();
+        let handle = Arc::new(engine.executor());
+        
+        let workers: Vec<_> = [1u8, 2]
+            .into_iter()
+            .map(|byte| {
+                let handle = Arc::clone(&handle);
+                thread::spawn(move || {
+                    for _ in 0..50 {
+                        assert_eq!(handle.execute("logger", &[byte, byte]).unwrap().data, vec![byte, byte]);
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        
+        // The lock keeps each two-byte append whole
+        let memory = Arc::try_unwrap(handle).ok().unwrap().into_memory();
+        let log = memory.read("log").unwrap();
+        assert_eq!(log.len(), 200);
+        assert!(log.chunks_exact(2).all(|pair| pair[0] == pair[1]));
+        assert_eq!(engine.memory().read("log"), None);
+    }
+    
+    #[test]
+    fn test_handle_resolves_builtins_and_reports_missing() {
+        let handle = CoreEngine::new().executor();
+        assert!(handle.execute(crate::algorithm::builtins::PassThrough::ID, &[1]).is_ok());
+        assert!(matches!(handle.execute("absent", &[1]), Err(CoreError::AlgorithmNotFound(_))));
+        assert_eq!(handle.with_memory(|memory| memory.stats().region_count), 0);
+    }
+}
@@ -0,0 +1,103 @@
+//! Linux I2C adapters through `/dev/i2c-*`
+
+use std::fs::{File, OpenOptions};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+use super::I2cBus;
+use crate::error::CoreError;
+
+// From linux/i2c-dev.h and linux/i2c.h
+const I2C_RDWR: libc::c_ulong = 0x0707;
+const I2C_M_RD: u16 = 0x0001;
+
+#[repr(C)]
+struct I2cMsg {
+    addr: u16,
+    flags: u16,
+    len: u16,
+    buf: *mut u8,
+}
+
+#[repr(C)]
+struct I2cRdwrIoctlData {
+    msgs: *mut I2cMsg,
+    nmsgs: u32,
+}
+
+/// An I2C adapter such as `/dev/i2c-1`
+///
+/// Each call is one combined transaction, so `write_read` issues a repeated
+/// start rather than a stop between its two halves.
+pub struct LinuxI2c {
+    file: File,
+}
+
+impl LinuxI2c {
+    /// Open the adapter at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CoreError> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        Ok(Self { file })
+    }
+    
+    fn transfer(&mut self, messages: &mut [I2cMsg]) -> Result<(), CoreError> {
+        let mut data = I2cRdwrIoctlData {
+            msgs: messages.as_mut_ptr(),
+            nmsgs: messages.len() as u32,
+        };
+        // SAFETY: `data` points at `messages`, whose buffers are valid for
+        // their stated lengths for the duration of the call, and the
+        // descriptor stays open until `self` is dropped.
+        let result = unsafe { libc::ioctl(self.file.as_raw_fd(), I2C_RDWR as _, &mut data as *mut I2cRdwrIoctlData) };
+        if result < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+}
+
+fn length(bytes: usize) -> Result<u16, CoreError> {
+    u16::try_from(bytes).map_err(|_| CoreError::InvalidInput(format!("I2C transfer of {} bytes is too long", bytes)))
+}
+
+impl I2cBus for LinuxI2c {
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), CoreError> {
+        // The kernel only reads from a write message's buffer
+        let mut message = I2cMsg {
+            addr: u16::from(address),
+            flags: 0,
+            len: length(bytes.len())?,
+            buf: bytes.as_ptr() as *mut u8,
+        };
+        self.transfer(std::slice::from_mut(&mut message))
+    }
+    
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), CoreError> {
+        let mut messages = [
+            I2cMsg {
+                addr: u16::from(address),
+                flags: 0,
+                len: length(bytes.len())?,
+                buf: bytes.as_ptr() as *mut u8,
+            },
+            I2cMsg {
+                addr: u16::from(address),
+                flags: I2C_M_RD,
+                len: length(buffer.len())?,
+                buf: buffer.as_mut_ptr(),
+            },
+        ];
+        self.transfer(&mut messages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_missing_adapter_fails_to_open() {
+        assert!(LinuxI2c::open("/dev/i2c-robotics-core-nope").is_err());
+        assert_eq!(std::mem::size_of::<I2cRdwrIoctlData>(), std::mem::size_of::<usize>() * 2);
+    }
+}
@@ -0,0 +1,276 @@
+//! I2C and SPI buses, and the register access sensors build on them
+//!
+//! The traits follow embedded-hal's `I2c` and `SpiDevice`, so drivers
+//! written against them port to microcontroller HALs with thin adapters.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::error::CoreError;
+
+#[cfg(all(feature = "i2c", target_os = "linux"))]
+mod i2cdev;
+#[cfg(all(feature = "spi", target_os = "linux"))]
+mod spidev;
+
+#[cfg(all(feature = "i2c", target_os = "linux"))]
+pub use i2cdev::LinuxI2c;
+#[cfg(all(feature = "spi", target_os = "linux"))]
+pub use spidev::LinuxSpi;
+
+/// An I2C bus controller addressing devices by 7-bit address
+pub trait I2cBus: Send {
+    /// Write `bytes` to the device at `address`
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), CoreError>;
+    
+    /// Write `bytes` to the device at `address`, then fill `buffer` from it
+    /// after a repeated start, without releasing the bus in between
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), CoreError>;
+}
+
+/// One device on a SPI bus, with its chip select handled by the bus
+pub trait SpiDevice: Send {
+    /// Shift out `buffer` while replacing it with the bytes shifted in, as
+    /// one transaction with the chip selected throughout
+    fn transfer_in_place(&mut self, buffer: &mut [u8]) -> Result<(), CoreError>;
+}
+
+/// A device exposing byte-wide registers, as most sensors do
+///
+/// Multi-byte reads start at `first` and continue through the following
+/// registers, relying on the device's address auto-increment.
+pub trait RegisterDevice: Send {
+    /// Fill `buffer` from the registers starting at `first`
+    fn read_registers(&mut self, first: u8, buffer: &mut [u8]) -> Result<(), CoreError>;
+    
+    fn write_register(&mut self, register: u8, value: u8) -> Result<(), CoreError>;
+    
+    fn read_register(&mut self, register: u8) -> Result<u8, CoreError> {
+        let mut value = [0];
+        self.read_registers(register, &mut value)?;
+        Ok(value[0])
+    }
+}
+
+/// The registers of the device at `address` on an I2C bus
+///
+/// A read writes the register number and reads the values back after a
+/// repeated start; a write sends the register number followed by the value.
+pub struct I2cRegisters<B: I2cBus> {
+    bus: B,
+    address: u8,
+}
+
+impl<B: I2cBus> I2cRegisters<B> {
+    pub fn new(bus: B, address: u8) -> Self {
+        Self { bus, address }
+    }
+    
+    pub fn into_inner(self) -> B {
+        self.bus
+    }
+}
+
+impl<B: I2cBus> RegisterDevice for I2cRegisters<B> {
+    fn read_registers(&mut self, first: u8, buffer: &mut [u8]) -> Result<(), CoreError> {
+        self.bus.write_read(self.address, &[first], buffer)
+    }
+    
+    fn write_register(&mut self, register: u8, value: u8) -> Result<(), CoreError> {
+        self.bus.write(self.address, &[register, value])
+    }
+}
+
+/// The registers of a SPI device that reads when the top bit of the
+/// register byte is set, and writes when it is clear
+pub struct SpiRegisters<S: SpiDevice> {
+    device: S,
+}
+
+/// Top bit of the first byte of a SPI register read
+const SPI_READ: u8 = 0x80;
+
+impl<S: SpiDevice> SpiRegisters<S> {
+    pub fn new(device: S) -> Self {
+        Self { device }
+    }
+    
+    pub fn into_inner(self) -> S {
+        self.device
+    }
+}
+
+impl<S: SpiDevice> RegisterDevice for SpiRegisters<S> {
+    fn read_registers(&mut self, first: u8, buffer: &mut [u8]) -> Result<(), CoreError> {
+        let mut transfer = vec![0; buffer.len() + 1];
+        transfer[0] = first | SPI_READ;
+        self.device.transfer_in_place(&mut transfer)?;
+        buffer.copy_from_slice(&transfer[1..]);
+        Ok(())
+    }
+    
+    fn write_register(&mut self, register: u8, value: u8) -> Result<(), CoreError> {
+        self.device.transfer_in_place(&mut [register & !SPI_READ, value])
+    }
+}
+
+/// Registers of an I2C mock device, addressed by a full byte
+type RegisterFile = [u8; 256];
+
+/// Read from `first` on, wrapping around the end as address auto-increment
+/// does
+fn read_file(file: &[u8], first: u8, buffer: &mut [u8]) {
+    for (offset, value) in buffer.iter_mut().enumerate() {
+        *value = file[(usize::from(first) + offset) % file.len()];
+    }
+}
+
+fn write_file(file: &mut [u8], first: u8, values: &[u8]) {
+    for (offset, &value) in values.iter().enumerate() {
+        file[(usize::from(first) + offset) % file.len()] = value;
+    }
+}
+
+/// An I2C bus of simulated devices, each a file of 256 registers
+///
+/// Writes set the register pointer from their first byte and store the
+/// rest from there on; reads continue from the pointer. Addresses with no
+/// device fail as a missing acknowledgement would. Clones share the
+/// devices, so keep a clone to set and inspect registers.
+#[derive(Clone, Default)]
+pub struct MockI2c {
+    devices: Arc<Mutex<HashMap<u8, RegisterFile>>>,
+}
+
+impl MockI2c {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Add a device at `address` with every register zero
+    pub fn with_device(self, address: u8) -> Self {
+        self.lock().insert(address, [0; 256]);
+        self
+    }
+    
+    /// Set registers of the device at `address`, starting at `first`
+    pub fn set_registers(&self, address: u8, first: u8, values: &[u8]) {
+        if let Some(file) = self.lock().get_mut(&address) {
+            write_file(file, first, values);
+        }
+    }
+    
+    /// Value of a register of the device at `address`
+    pub fn register(&self, address: u8, register: u8) -> Option<u8> {
+        self.lock().get(&address).map(|file| file[usize::from(register)])
+    }
+    
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<u8, RegisterFile>> {
+        self.devices.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    fn no_device(address: u8) -> CoreError {
+        CoreError::IoError(format!("no acknowledgement from I2C address {:#04x}", address))
+    }
+}
+
+impl I2cBus for MockI2c {
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), CoreError> {
+        let mut devices = self.lock();
+        let file = devices.get_mut(&address).ok_or_else(|| Self::no_device(address))?;
+        if let Some((&first, values)) = bytes.split_first() {
+            write_file(file, first, values);
+        }
+        Ok(())
+    }
+    
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), CoreError> {
+        let mut devices = self.lock();
+        let file = devices.get_mut(&address).ok_or_else(|| Self::no_device(address))?;
+        let first = bytes.first().copied().unwrap_or(0);
+        write_file(file, first, bytes.get(1..).unwrap_or_default());
+        read_file(file, first.wrapping_add(bytes.len().saturating_sub(1) as u8), buffer);
+        Ok(())
+    }
+}
+
+/// A simulated SPI device holding 128 registers, read and written as
+/// `SpiRegisters` expects
+///
+/// Clones share the registers.
+#[derive(Clone)]
+pub struct MockSpi {
+    registers: Arc<Mutex<[u8; 128]>>,
+}
+
+impl Default for MockSpi {
+    fn default() -> Self {
+        Self {
+            registers: Arc::new(Mutex::new([0; 128])),
+        }
+    }
+}
+
+impl MockSpi {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Set registers starting at `first`
+    pub fn set_registers(&self, first: u8, values: &[u8]) {
+        write_file(self.lock().as_mut_slice(), first, values);
+    }
+    
+    pub fn register(&self, register: u8) -> u8 {
+        self.lock()[usize::from(register & !SPI_READ)]
+    }
+    
+    fn lock(&self) -> std::sync::MutexGuard<'_, [u8; 128]> {
+        self.registers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl SpiDevice for MockSpi {
+    fn transfer_in_place(&mut self, buffer: &mut [u8]) -> Result<(), CoreError> {
+        let Some((first, rest)) = buffer.split_first_mut() else {
+            return Ok(());
+        };
+        let register = *first & !SPI_READ;
+        let mut file = self.lock();
+        if *first & SPI_READ != 0 {
+            read_file(file.as_slice(), register, rest);
+        } else {
+            write_file(file.as_mut_slice(), register, rest);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_register_access_over_both_buses() {
+        let bus = MockI2c::new().with_device(0x68);
+        let mut i2c = I2cRegisters::new(bus.clone(), 0x68);
+        i2c.write_register(0x6B, 0x01).unwrap();
+        bus.set_registers(0x68, 0x3B, &[1, 2, 3]);
+        let mut values = [0; 3];
+        i2c.read_registers(0x3B, &mut values).unwrap();
+        assert_eq!((values, bus.register(0x68, 0x6B)), ([1, 2, 3], Some(0x01)));
+        assert!(matches!(
+            I2cRegisters::new(bus, 0x69).read_register(0x75),
+            Err(CoreError::IoError(_))
+        ));
+        
+        let device = MockSpi::new();
+        let mut spi = SpiRegisters::new(device.clone());
+        spi.write_register(0x06, 0x01).unwrap();
+        device.set_registers(0x7F, &[7, 8]);
+        let mut wrapped = [0; 2];
+        spi.read_registers(0x7F, &mut wrapped).unwrap();
+        assert_eq!(wrapped, [7, 8]);
+        assert_eq!((device.register(0x06), spi.read_register(0x06).unwrap()), (0x01, 0x01));
+    }
+}
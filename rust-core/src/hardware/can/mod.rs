@@ -0,0 +1,489 @@
+//! CAN bus access, with drivers for actuators and sensors on the bus
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError, TrySendError};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use super::Device;
+use crate::error::CoreError;
+use crate::sensor::{Sensor, SensorFrame};
+
+#[cfg(all(feature = "can", target_os = "linux"))]
+mod socketcan;
+
+#[cfg(all(feature = "can", target_os = "linux"))]
+pub use socketcan::SocketCan;
+
+/// Largest standard (11-bit) identifier
+const MAX_STANDARD_ID: u32 = 0x7FF;
+/// Largest extended (29-bit) identifier
+const MAX_EXTENDED_ID: u32 = 0x1FFF_FFFF;
+/// Most data bytes a classic CAN frame carries
+const MAX_DATA_LEN: usize = 8;
+
+/// How long receive loops wait for a frame before checking whether to stop
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A classic CAN data frame
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CanFrame {
+    id: u32,
+    extended: bool,
+    data: Vec<u8>,
+}
+
+impl CanFrame {
+    /// A frame with an 11-bit identifier, such as a CANopen PDO or SDO
+    pub fn standard(id: u32, data: &[u8]) -> Result<Self, CoreError> {
+        Self::new(id, false, data)
+    }
+    
+    /// A frame with a 29-bit identifier
+    pub fn extended(id: u32, data: &[u8]) -> Result<Self, CoreError> {
+        Self::new(id, true, data)
+    }
+    
+    fn new(id: u32, extended: bool, data: &[u8]) -> Result<Self, CoreError> {
+        let max = if extended { MAX_EXTENDED_ID } else { MAX_STANDARD_ID };
+        if id > max {
+            return Err(CoreError::InvalidParameter(format!("CAN ID {:#x} exceeds {:#x}", id, max)));
+        }
+        if data.len() > MAX_DATA_LEN {
+            return Err(CoreError::InvalidInput(format!(
+                "CAN frame carries at most {} bytes, got {}",
+                MAX_DATA_LEN,
+                data.len()
+            )));
+        }
+        Ok(Self {
+            id,
+            extended,
+            data: data.to_vec(),
+        })
+    }
+    
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+    
+    /// Whether the identifier is 29-bit rather than 11-bit
+    pub fn is_extended(&self) -> bool {
+        self.extended
+    }
+    
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Accepts frames whose identifier, masked, equals `id` masked
+///
+/// A filter only matches frames of its own identifier width, so a standard
+/// and an extended frame with the same number are told apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CanFilter {
+    pub id: u32,
+    pub mask: u32,
+    pub extended: bool,
+}
+
+impl CanFilter {
+    /// Accept exactly standard identifier `id`
+    pub fn standard(id: u32) -> Self {
+        Self {
+            id,
+            mask: MAX_STANDARD_ID,
+            extended: false,
+        }
+    }
+    
+    /// Accept exactly extended identifier `id`
+    pub fn extended(id: u32) -> Self {
+        Self {
+            id,
+            mask: MAX_EXTENDED_ID,
+            extended: true,
+        }
+    }
+    
+    /// Compare only the identifier bits set in `mask`, such as `0x780` to
+    /// accept a CANopen function code from every node
+    pub fn with_mask(mut self, mask: u32) -> Self {
+        self.mask = mask;
+        self
+    }
+    
+    pub fn matches(&self, frame: &CanFrame) -> bool {
+        frame.extended == self.extended && frame.id & self.mask == self.id & self.mask
+    }
+}
+
+/// A connection to a CAN bus
+///
+/// Frames are received only if they pass the filters set last, or all of
+/// them while none are set.
+pub trait CanInterface: Send + Sync {
+    /// Name of the interface, such as `can0`, for errors and logs
+    fn name(&self) -> &str;
+    
+    /// Queue `frame` for transmission
+    fn send(&mut self, frame: &CanFrame) -> Result<(), CoreError>;
+    
+    /// Wait up to `timeout` for the next frame passing the filters,
+    /// returning `None` if none arrived
+    fn receive(&mut self, timeout: Duration) -> Result<Option<CanFrame>, CoreError>;
+    
+    /// Replace the receive filters; an empty list accepts every frame
+    fn set_filters(&mut self, filters: &[CanFilter]) -> Result<(), CoreError>;
+}
+
+#[derive(Debug, Default)]
+struct MockBus {
+    sent: Vec<CanFrame>,
+    pending: VecDeque<CanFrame>,
+    filters: Vec<CanFilter>,
+}
+
+/// A CAN bus in memory, for tests and CI
+///
+/// Clones share the bus like `MockHardware`, so keep a clone to inspect the
+/// frames sent and to inject the frames nodes would reply with. Injected
+/// frames that fail the filters are discarded when received, as the kernel
+/// would never have delivered them.
+#[derive(Clone, Debug, Default)]
+pub struct MockCan {
+    name: String,
+    bus: Arc<(Mutex<MockBus>, Condvar)>,
+}
+
+impl MockCan {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            bus: Arc::default(),
+        }
+    }
+    
+    /// Every frame sent so far, oldest first
+    pub fn sent(&self) -> Vec<CanFrame> {
+        self.bus().sent.clone()
+    }
+    
+    /// Deliver `frame` as if a node on the bus had sent it
+    pub fn inject(&self, frame: CanFrame) {
+        self.bus().pending.push_back(frame);
+        self.bus.1.notify_all();
+    }
+    
+    fn bus(&self) -> MutexGuard<'_, MockBus> {
+        self.bus.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl CanInterface for MockCan {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn send(&mut self, frame: &CanFrame) -> Result<(), CoreError> {
+        self.bus().sent.push(frame.clone());
+        Ok(())
+    }
+    
+    fn receive(&mut self, timeout: Duration) -> Result<Option<CanFrame>, CoreError> {
+        let deadline = Instant::now() + timeout;
+        let mut bus = self.bus();
+        loop {
+            while let Some(frame) = bus.pending.pop_front() {
+                if bus.filters.is_empty() || bus.filters.iter().any(|filter| filter.matches(&frame)) {
+                    return Ok(Some(frame));
+                }
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            bus = self.bus.1.wait_timeout(bus, remaining).unwrap_or_else(|e| e.into_inner()).0;
+        }
+    }
+    
+    fn set_filters(&mut self, filters: &[CanFilter]) -> Result<(), CoreError> {
+        self.bus().filters = filters.to_vec();
+        Ok(())
+    }
+}
+
+/// A `Device` sending each command as CAN frames with one identifier, such
+/// as a CANopen RPDO driving a motor controller
+///
+/// A command longer than a frame is split into frames of 8 bytes and a
+/// shorter last one, sent in order; an empty command sends one empty frame.
+pub struct CanDevice {
+    name: String,
+    interface: Box<dyn CanInterface>,
+    id: u32,
+    extended: bool,
+}
+
+impl CanDevice {
+    /// Create a device registered under `name` that sends on standard
+    /// identifier `id`
+    pub fn new(name: &str, interface: Box<dyn CanInterface>, id: u32) -> Result<Self, CoreError> {
+        CanFrame::standard(id, &[])?;
+        Ok(Self {
+            name: name.to_string(),
+            interface,
+            id,
+            extended: false,
+        })
+    }
+    
+    /// Create a device registered under `name` that sends on extended
+    /// identifier `id`
+    pub fn extended(name: &str, interface: Box<dyn CanInterface>, id: u32) -> Result<Self, CoreError> {
+        CanFrame::extended(id, &[])?;
+        Ok(Self {
+            name: name.to_string(),
+            interface,
+            id,
+            extended: true,
+        })
+    }
+    
+    /// The interface the device sends on, such as for reading replies
+    pub fn interface_mut(&mut self) -> &mut dyn CanInterface {
+        self.interface.as_mut()
+    }
+}
+
+impl Device for CanDevice {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn write_command(&mut self, command: &[u8]) -> Result<(), CoreError> {
+        let chunks: Vec<&[u8]> = if command.is_empty() {
+            vec![command]
+        } else {
+            command.chunks(MAX_DATA_LEN).collect()
+        };
+        for chunk in chunks {
+            self.interface.send(&CanFrame::new(self.id, self.extended, chunk)?)?;
+        }
+        Ok(())
+    }
+}
+
+/// A `Sensor` reading the frames that pass its filters, such as a CANopen
+/// TPDO from an encoder
+///
+/// Each frame's data becomes a payload, stamped with the microseconds since
+/// the sensor was created. `read_frame` blocks until a frame arrives.
+pub struct CanSensor {
+    id: String,
+    interface: Box<dyn CanInterface>,
+    started: Instant,
+}
+
+impl CanSensor {
+    /// Create a sensor `id` receiving the frames that pass `filters` on
+    /// `interface`, all of them if there are none
+    pub fn new(id: &str, mut interface: Box<dyn CanInterface>, filters: &[CanFilter]) -> Result<Self, CoreError> {
+        interface.set_filters(filters)?;
+        Ok(Self {
+            id: id.to_string(),
+            interface,
+            started: Instant::now(),
+        })
+    }
+}
+
+impl Sensor for CanSensor {
+    fn id(&self) -> &str {
+        &self.id
+    }
+    
+    fn read_frame(&mut self) -> Result<SensorFrame, CoreError> {
+        loop {
+            if let Some(frame) = self.interface.receive(POLL_INTERVAL)? {
+                return Ok(SensorFrame {
+                    sensor_id: self.id.clone(),
+                    timestamp: u64::try_from(self.started.elapsed().as_micros()).unwrap_or(u64::MAX),
+                    payload: frame.data,
+                    ..Default::default()
+                });
+            }
+        }
+    }
+}
+
+/// Frames received on a background thread, so a control loop can take them
+/// without blocking on the bus
+///
+/// The thread owns the interface and queues up to a fixed number of frames;
+/// when the queue is full new frames are dropped and counted in `dropped`.
+/// A receive error stops the thread and is returned once the frames queued
+/// before it are drained. Dropping the receiver stops the thread.
+pub struct CanReceiver {
+    receiver: Receiver<Result<CanFrame, CoreError>>,
+    dropped: Arc<AtomicU64>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<Box<dyn CanInterface>>>,
+}
+
+impl CanReceiver {
+    /// Start receiving on `interface`, queueing up to `capacity` frames
+    pub fn spawn(mut interface: Box<dyn CanInterface>, capacity: usize) -> Result<Self, CoreError> {
+        if capacity == 0 {
+            return Err(CoreError::InvalidParameter("receive capacity must be at least 1".to_string()));
+        }
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = std::thread::Builder::new()
+            .name(format!("can-{}", interface.name()))
+            .spawn({
+                let dropped = Arc::clone(&dropped);
+                let stop = Arc::clone(&stop);
+                move || {
+                    while !stop.load(Ordering::Relaxed) {
+                        match interface.receive(POLL_INTERVAL) {
+                            Ok(None) => {}
+                            Ok(Some(frame)) => match sender.try_send(Ok(frame)) {
+                                Ok(()) => {}
+                                Err(TrySendError::Full(_)) => {
+                                    dropped.fetch_add(1, Ordering::Relaxed);
+                                }
+                                Err(TrySendError::Disconnected(_)) => break,
+                            },
+                            Err(e) => {
+                                let _ = sender.send(Err(e));
+                                break;
+                            }
+                        }
+                    }
+                    interface
+                }
+            })?;
+        Ok(Self {
+            receiver,
+            dropped,
+            stop,
+            thread: Some(thread),
+        })
+    }
+    
+    /// Wait up to `timeout` for the next frame, returning `None` if none
+    /// arrived
+    ///
+    /// Returns `CoreError::EndOfStream` once the thread has stopped and the
+    /// queue is drained.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<Option<CanFrame>, CoreError> {
+        match self.receiver.recv_timeout(timeout) {
+            Ok(frame) => frame.map(Some),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(CoreError::EndOfStream),
+        }
+    }
+    
+    /// Take the next queued frame without waiting, if there is one
+    pub fn try_recv(&self) -> Result<Option<CanFrame>, CoreError> {
+        match self.receiver.try_recv() {
+            Ok(frame) => frame.map(Some),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(CoreError::EndOfStream),
+        }
+    }
+    
+    /// Frames dropped because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+    
+    /// Stop the thread and take back the interface
+    pub fn stop(mut self) -> Result<Box<dyn CanInterface>, CoreError> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.thread
+            .take()
+            .ok_or_else(|| CoreError::ProcessingFailed("CAN receive thread already stopped".to_string()))?
+            .join()
+            .map_err(|_| CoreError::ProcessingFailed("CAN receive thread panicked".to_string()))
+    }
+}
+
+impl Drop for CanReceiver {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::builtins::Scale;
+    use crate::algorithm::samples;
+    use crate::CoreEngine;
+    
+    #[test]
+    fn test_frame_limits_and_filters() {
+        assert!(CanFrame::standard(0x800, &[]).is_err());
+        assert!(CanFrame::extended(0x800, &[]).is_ok());
+        assert!(matches!(CanFrame::standard(1, &[0; 9]), Err(CoreError::InvalidInput(_))));
+        
+        // TPDO1 (0x180 + node) from any node
+        let tpdo1 = CanFilter::standard(0x180).with_mask(0x780);
+        assert!(tpdo1.matches(&CanFrame::standard(0x185, &[]).unwrap()));
+        assert!(!tpdo1.matches(&CanFrame::standard(0x205, &[]).unwrap()));
+        assert!(!tpdo1.matches(&CanFrame::extended(0x185, &[]).unwrap()));
+        
+        let mut bus = MockCan::new("can0");
+        bus.set_filters(&[CanFilter::standard(0x181)]).unwrap();
+        bus.inject(CanFrame::standard(0x182, &[1]).unwrap());
+        bus.inject(CanFrame::standard(0x181, &[2]).unwrap());
+        assert_eq!(bus.receive(Duration::ZERO).unwrap().unwrap().data(), &[2]);
+        assert_eq!(bus.receive(Duration::from_millis(1)).unwrap(), None);
+    }
+    
+    #[test]
+    fn test_device_and_sensor_drivers() {
+        let bus = MockCan::new("can0");
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(Scale::new(2.0).unwrap())).unwrap();
+        engine.register_device(Box::new(CanDevice::new("motor", Box::new(bus.clone()), 0x201).unwrap()));
+        
+        // Three samples take 12 bytes, sent as two frames
+        engine
+            .execute_to_device(Scale::ID, &samples::f32_to_bytes(&[1.0, 2.0, 3.0]), "motor")
+            .unwrap();
+        let sent = bus.sent();
+        assert_eq!(sent.iter().map(|frame| frame.data().len()).collect::<Vec<_>>(), vec![8, 4]);
+        assert!(sent.iter().all(|frame| frame.id() == 0x201));
+        assert!(CanDevice::new("motor", Box::new(bus.clone()), 0x800).is_err());
+        
+        let mut encoder = CanSensor::new("encoder", Box::new(bus.clone()), &[CanFilter::standard(0x181)]).unwrap();
+        bus.inject(CanFrame::standard(0x181, &[7, 0]).unwrap());
+        let frame = encoder.read_frame().unwrap();
+        assert_eq!((frame.sensor_id.as_str(), frame.payload), ("encoder", vec![7, 0]));
+    }
+    
+    #[test]
+    fn test_receiver_queues_frames_in_background() {
+        let bus = MockCan::new("can0");
+        let receiver = CanReceiver::spawn(Box::new(bus.clone()), 2).unwrap();
+        for id in 1..=3 {
+            bus.inject(CanFrame::standard(id, &[]).unwrap());
+        }
+        let first = receiver.recv_timeout(Duration::from_secs(5)).unwrap().unwrap();
+        assert_eq!(first.id(), 1);
+        
+        let interface = receiver.stop().unwrap();
+        assert_eq!(interface.name(), "can0");
+        assert!(CanReceiver::spawn(Box::new(bus), 0).is_err());
+    }
+}
@@ -0,0 +1,200 @@
+//! Linux SocketCAN raw sockets
+
+use std::ffi::CString;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::time::Duration;
+
+use super::{CanFilter, CanFrame, CanInterface, MAX_DATA_LEN};
+use crate::error::CoreError;
+
+/// A raw socket bound to a CAN network interface such as `can0` or `vcan0`
+///
+/// Error frames and remote transmission requests are skipped on receive.
+pub struct SocketCan {
+    name: String,
+    socket: OwnedFd,
+}
+
+impl SocketCan {
+    /// Bind to the network interface `interface`
+    ///
+    /// Fails with `CoreError::DeviceNotFound` if there is no such interface.
+    pub fn open(interface: &str) -> Result<Self, CoreError> {
+        let c_name = CString::new(interface)
+            .map_err(|_| CoreError::InvalidParameter(format!("interface name {:?} contains NUL", interface)))?;
+        // SAFETY: `c_name` is a valid NUL-terminated string for the call.
+        let index = unsafe { libc::if_nametoindex(c_name.as_ptr()) };
+        if index == 0 {
+            return Err(CoreError::DeviceNotFound(interface.to_string()));
+        }
+        // SAFETY: `socket` takes no pointers; a non-negative result is a
+        // descriptor nothing else owns.
+        let socket = unsafe {
+            let fd = libc::socket(libc::PF_CAN, libc::SOCK_RAW | libc::SOCK_CLOEXEC, libc::CAN_RAW);
+            if fd < 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            OwnedFd::from_raw_fd(fd)
+        };
+        // SAFETY: an all-zero `sockaddr_can` is valid, and `bind` reads
+        // exactly `size_of::<sockaddr_can>()` bytes of it.
+        unsafe {
+            let mut address: libc::sockaddr_can = std::mem::zeroed();
+            address.can_family = libc::AF_CAN as libc::sa_family_t;
+            address.can_ifindex = index as libc::c_int;
+            if libc::bind(
+                socket.as_raw_fd(),
+                &address as *const libc::sockaddr_can as *const libc::sockaddr,
+                std::mem::size_of::<libc::sockaddr_can>() as libc::socklen_t,
+            ) != 0
+            {
+                return Err(std::io::Error::last_os_error().into());
+            }
+        }
+        Ok(Self {
+            name: interface.to_string(),
+            socket,
+        })
+    }
+}
+
+fn to_raw(frame: &CanFrame) -> libc::can_frame {
+    // SAFETY: `can_frame` is plain data, valid when zeroed.
+    let mut raw: libc::can_frame = unsafe { std::mem::zeroed() };
+    raw.can_id = frame.id | if frame.extended { libc::CAN_EFF_FLAG } else { 0 };
+    raw.can_dlc = frame.data.len() as u8;
+    raw.data[..frame.data.len()].copy_from_slice(&frame.data);
+    raw
+}
+
+/// The data frame `raw` holds, or `None` for error and remote frames
+fn from_raw(raw: &libc::can_frame) -> Option<CanFrame> {
+    if raw.can_id & (libc::CAN_ERR_FLAG | libc::CAN_RTR_FLAG) != 0 {
+        return None;
+    }
+    let extended = raw.can_id & libc::CAN_EFF_FLAG != 0;
+    let mask = if extended { libc::CAN_EFF_MASK } else { libc::CAN_SFF_MASK };
+    Some(CanFrame {
+        id: raw.can_id & mask,
+        extended,
+        data: raw.data[..usize::from(raw.can_dlc).min(MAX_DATA_LEN)].to_vec(),
+    })
+}
+
+fn to_raw_filter(filter: &CanFilter) -> libc::can_filter {
+    // The identifier-type bit takes part in the match so standard and
+    // extended frames are told apart
+    let flag = if filter.extended { libc::CAN_EFF_FLAG } else { 0 };
+    libc::can_filter {
+        can_id: filter.id | flag,
+        can_mask: filter.mask | libc::CAN_EFF_FLAG | libc::CAN_RTR_FLAG,
+    }
+}
+
+impl CanInterface for SocketCan {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn send(&mut self, frame: &CanFrame) -> Result<(), CoreError> {
+        let raw = to_raw(frame);
+        let size = std::mem::size_of::<libc::can_frame>();
+        // SAFETY: `raw` is a valid `can_frame` of `size` bytes for the call.
+        let written = unsafe { libc::write(self.socket.as_raw_fd(), &raw as *const libc::can_frame as *const libc::c_void, size) };
+        if written < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        if written as usize != size {
+            return Err(CoreError::IoError(format!("short CAN write of {} bytes on {}", written, self.name)));
+        }
+        Ok(())
+    }
+    
+    fn receive(&mut self, timeout: Duration) -> Result<Option<CanFrame>, CoreError> {
+        let timeout_ms = libc::c_int::try_from(timeout.as_millis()).unwrap_or(libc::c_int::MAX);
+        loop {
+            let mut poll = libc::pollfd {
+                fd: self.socket.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            // SAFETY: `poll` is one valid `pollfd` for the call.
+            match unsafe { libc::poll(&mut poll, 1, timeout_ms) } {
+                0 => return Ok(None),
+                ready if ready < 0 => {
+                    let error = std::io::Error::last_os_error();
+                    if error.kind() == std::io::ErrorKind::Interrupted {
+                        continue;
+                    }
+                    return Err(error.into());
+                }
+                _ => {}
+            }
+            // SAFETY: `can_frame` is plain data, valid when zeroed, and
+            // `read` writes at most `size_of::<can_frame>()` bytes into it.
+            let (read, raw) = unsafe {
+                let mut raw: libc::can_frame = std::mem::zeroed();
+                let read = libc::read(
+                    self.socket.as_raw_fd(),
+                    &mut raw as *mut libc::can_frame as *mut libc::c_void,
+                    std::mem::size_of::<libc::can_frame>(),
+                );
+                (read, raw)
+            };
+            if read < 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            // A skipped frame restarts the wait, which is at worst another
+            // full timeout
+            if let Some(frame) = from_raw(&raw) {
+                return Ok(Some(frame));
+            }
+        }
+    }
+    
+    fn set_filters(&mut self, filters: &[CanFilter]) -> Result<(), CoreError> {
+        // No filters at all would accept nothing, so "accept every frame"
+        // is a single filter with an empty mask
+        let raw: Vec<libc::can_filter> = if filters.is_empty() {
+            vec![libc::can_filter { can_id: 0, can_mask: 0 }]
+        } else {
+            filters.iter().map(to_raw_filter).collect()
+        };
+        // SAFETY: `raw` holds `raw.len()` valid `can_filter`s for the call.
+        let result = unsafe {
+            libc::setsockopt(
+                self.socket.as_raw_fd(),
+                libc::SOL_CAN_RAW,
+                libc::CAN_RAW_FILTER,
+                raw.as_ptr() as *const libc::c_void,
+                (raw.len() * std::mem::size_of::<libc::can_filter>()) as libc::socklen_t,
+            )
+        };
+        if result != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_frames_and_filters_convert_to_kernel_layout() {
+        let frame = CanFrame::extended(0x1234_5678, &[1, 2, 3]).unwrap();
+        let raw = to_raw(&frame);
+        assert_eq!((raw.can_id, raw.can_dlc), (0x9234_5678, 3));
+        assert_eq!(from_raw(&raw), Some(frame));
+        
+        let mut remote = to_raw(&CanFrame::standard(0x601, &[]).unwrap());
+        remote.can_id |= libc::CAN_RTR_FLAG;
+        assert_eq!(from_raw(&remote), None);
+        
+        let filter = to_raw_filter(&CanFilter::standard(0x180).with_mask(0x780));
+        assert_eq!((filter.can_id, filter.can_mask), (0x180, 0xC000_0780));
+        
+        assert!(matches!(SocketCan::open("robotics_core_nope0"), Err(CoreError::DeviceNotFound(_))));
+    }
+}
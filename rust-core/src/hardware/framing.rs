@@ -0,0 +1,93 @@
+//! Splitting output into frames that fit a device's maximum transfer size
+
+use crate::error::CoreError;
+
+/// One piece of a split output
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutputFrame<'a> {
+    /// Position of the frame in its output, counting from 0
+    pub sequence: u64,
+    /// Whether this is the output's last frame
+    pub is_final: bool,
+    /// The frame's share of the output bytes
+    pub payload: &'a [u8],
+}
+
+/// Splits algorithm output into frames of at most a fixed size
+///
+/// Every output yields at least one frame, so an empty output becomes a
+/// single empty final frame and the receiver always sees an end marker.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameSplitter {
+    max_frame_size: usize,
+}
+
+impl FrameSplitter {
+    /// Create a splitter producing payloads of at most `max_frame_size`
+    /// bytes, which must be at least 1
+    pub fn new(max_frame_size: usize) -> Result<Self, CoreError> {
+        if max_frame_size == 0 {
+            return Err(CoreError::InvalidParameter("max frame size must be at least 1".to_string()));
+        }
+        Ok(Self { max_frame_size })
+    }
+    
+    /// Largest payload a frame carries
+    pub fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+    
+    /// Number of frames `len` bytes of output split into
+    pub fn frame_count(&self, len: usize) -> usize {
+        len.div_ceil(self.max_frame_size).max(1)
+    }
+    
+    /// Split `output` into frames, in order
+    ///
+    /// All frames but the last are exactly `max_frame_size` bytes long.
+    pub fn split<'a>(&self, output: &'a [u8]) -> impl ExactSizeIterator<Item = OutputFrame<'a>> {
+        let count = self.frame_count(output.len());
+        let size = self.max_frame_size;
+        (0..count).map(move |index| OutputFrame {
+            sequence: index as u64,
+            is_final: index + 1 == count,
+            payload: &output[(index * size).min(output.len())..((index + 1) * size).min(output.len())],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn summary(splitter: &FrameSplitter, output: &[u8]) -> Vec<(u64, bool, usize)> {
+        splitter
+            .split(output)
+            .map(|frame| (frame.sequence, frame.is_final, frame.payload.len()))
+            .collect()
+    }
+    
+    #[test]
+    fn test_exact_and_ragged_splits() {
+        let splitter = FrameSplitter::new(4).unwrap();
+        assert_eq!(summary(&splitter, &[0; 8]), vec![(0, false, 4), (1, true, 4)]);
+        assert_eq!(
+            summary(&splitter, &[0; 9]),
+            vec![(0, false, 4), (1, false, 4), (2, true, 1)]
+        );
+        assert_eq!(summary(&splitter, &[0; 3]), vec![(0, true, 3)]);
+        
+        let output: Vec<u8> = (0..10).collect();
+        let frames = splitter.split(&output);
+        assert_eq!(frames.len(), 3);
+        let reassembled: Vec<u8> = frames.flat_map(|frame| frame.payload.iter().copied()).collect();
+        assert_eq!(reassembled, output);
+    }
+    
+    #[test]
+    fn test_empty_output_yields_one_final_frame() {
+        let splitter = FrameSplitter::new(16).unwrap();
+        assert_eq!(summary(&splitter, &[]), vec![(0, true, 0)]);
+        assert!(FrameSplitter::new(0).is_err());
+    }
+}
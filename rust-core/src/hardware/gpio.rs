@@ -0,0 +1,128 @@
+//! Linux GPIO through the sysfs interface
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::interface::HardwareInterface;
+use crate::error::CoreError;
+
+/// Digital pins driven through `/sys/class/gpio`
+///
+/// Pins are exported and their direction set on first use, then switched
+/// whenever a pin is written after being read or the reverse. Pins the
+/// backend exported are unexported when it is dropped.
+pub struct SysfsGpio {
+    name: String,
+    root: PathBuf,
+    // Direction of each pin in use, `true` for output
+    directions: HashMap<u32, bool>,
+    exported: Vec<u32>,
+}
+
+impl SysfsGpio {
+    /// Use the system's GPIO controller
+    pub fn new(name: &str) -> Self {
+        Self::with_root(name, "/sys/class/gpio")
+    }
+    
+    /// Use the sysfs GPIO tree at `root`
+    pub fn with_root(name: &str, root: impl AsRef<Path>) -> Self {
+        Self {
+            name: name.to_string(),
+            root: root.as_ref().to_path_buf(),
+            directions: HashMap::new(),
+            exported: Vec::new(),
+        }
+    }
+    
+    fn pin_dir(&self, pin: u32) -> PathBuf {
+        self.root.join(format!("gpio{}", pin))
+    }
+    
+    fn prepare(&mut self, pin: u32, output: bool) -> Result<PathBuf, CoreError> {
+        let dir = self.pin_dir(pin);
+        if !dir.exists() {
+            fs::write(self.root.join("export"), pin.to_string())?;
+            self.exported.push(pin);
+        }
+        if self.directions.get(&pin) != Some(&output) {
+            fs::write(dir.join("direction"), if output { "out" } else { "in" })?;
+            self.directions.insert(pin, output);
+        }
+        Ok(dir.join("value"))
+    }
+}
+
+impl HardwareInterface for SysfsGpio {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn write_pin(&mut self, pin: u32, high: bool) -> Result<(), CoreError> {
+        let value = self.prepare(pin, true)?;
+        fs::write(value, if high { "1" } else { "0" })?;
+        Ok(())
+    }
+    
+    fn read_pin(&mut self, pin: u32) -> Result<bool, CoreError> {
+        let value = self.prepare(pin, false)?;
+        match fs::read_to_string(value)?.trim() {
+            "0" => Ok(false),
+            "1" => Ok(true),
+            other => Err(CoreError::IoError(format!("GPIO {} reads unexpected value '{}'", pin, other))),
+        }
+    }
+}
+
+impl Drop for SysfsGpio {
+    fn drop(&mut self) {
+        for pin in &self.exported {
+            if let Err(e) = fs::write(self.root.join("unexport"), pin.to_string()) {
+                log::warn!("Failed to unexport GPIO {}: {}", pin, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// A sysfs tree where pin 17 is already exported
+    fn fake_tree(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("robotics_core_gpio_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("gpio17")).unwrap();
+        fs::write(root.join("gpio17/value"), "0\n").unwrap();
+        root
+    }
+    
+    #[test]
+    fn test_writes_and_reads_switch_direction() {
+        let root = fake_tree("direction");
+        let mut gpio = SysfsGpio::with_root("gpio", &root);
+        
+        gpio.write_pin(17, true).unwrap();
+        assert_eq!(fs::read_to_string(root.join("gpio17/direction")).unwrap(), "out");
+        assert_eq!(fs::read_to_string(root.join("gpio17/value")).unwrap(), "1");
+        
+        fs::write(root.join("gpio17/value"), "0\n").unwrap();
+        assert!(!gpio.read_pin(17).unwrap());
+        assert_eq!(fs::read_to_string(root.join("gpio17/direction")).unwrap(), "in");
+        fs::remove_dir_all(&root).unwrap();
+    }
+    
+    #[test]
+    fn test_unexported_pin_is_exported_and_released() {
+        let root = fake_tree("export");
+        let mut gpio = SysfsGpio::with_root("gpio", &root);
+        // The fake tree does not create the pin's directory on export
+        assert!(matches!(gpio.write_pin(4, true), Err(CoreError::IoError(_))));
+        assert_eq!(fs::read_to_string(root.join("export")).unwrap(), "4");
+        
+        drop(gpio);
+        assert_eq!(fs::read_to_string(root.join("unexport")).unwrap(), "4");
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,178 @@
+//! Pin-level hardware access and its adapter to `Device`
+
+use super::Device;
+use crate::algorithm::samples;
+use crate::error::CoreError;
+
+/// Digital pins, PWM channels and a serial line a motor controller or
+/// encoder is wired to
+///
+/// A backend implements the operations its hardware supports; the rest
+/// keep their default implementations, which fail with
+/// `CoreError::UnsupportedCapability`.
+pub trait HardwareInterface: Send + Sync {
+    /// Name of the backend, for errors and logs
+    fn name(&self) -> &str;
+    
+    /// Drive digital output `pin` high or low
+    fn write_pin(&mut self, pin: u32, high: bool) -> Result<(), CoreError> {
+        let _ = (pin, high);
+        Err(unsupported(self.name(), "digital output"))
+    }
+    
+    /// Read the level of digital input `pin`
+    fn read_pin(&mut self, pin: u32) -> Result<bool, CoreError> {
+        let _ = pin;
+        Err(unsupported(self.name(), "digital input"))
+    }
+    
+    /// Set PWM `channel` to `duty`, the fraction of each period it is high,
+    /// within `[0, 1]`
+    fn set_pwm(&mut self, channel: u32, duty: f64) -> Result<(), CoreError> {
+        let _ = (channel, duty);
+        Err(unsupported(self.name(), "PWM output"))
+    }
+    
+    /// Send `data` over the serial line
+    fn write_serial(&mut self, data: &[u8]) -> Result<(), CoreError> {
+        let _ = data;
+        Err(unsupported(self.name(), "serial output"))
+    }
+    
+    /// Read bytes already received on the serial line into `buf`, returning
+    /// how many were read; 0 means none arrived in time
+    fn read_serial(&mut self, buf: &mut [u8]) -> Result<usize, CoreError> {
+        let _ = buf;
+        Err(unsupported(self.name(), "serial input"))
+    }
+}
+
+fn unsupported(name: &str, what: &str) -> CoreError {
+    CoreError::UnsupportedCapability(format!("hardware '{}' has no {}", name, what))
+}
+
+/// Reject a PWM duty cycle outside `[0, 1]`
+pub(crate) fn check_duty(duty: f64) -> Result<(), CoreError> {
+    if !(0.0..=1.0).contains(&duty) {
+        return Err(CoreError::InvalidParameter(format!("duty cycle must be within [0, 1], got {}", duty)));
+    }
+    Ok(())
+}
+
+/// How a `HardwareDevice` turns a command into hardware operations
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OutputMapping {
+    /// One byte per listed pin, driving it high if non-zero
+    Pins(Vec<u32>),
+    /// One little-endian `f32` duty cycle per listed PWM channel
+    Pwm(Vec<u32>),
+    /// The command's bytes, written to the serial line as is
+    Serial,
+}
+
+/// A `Device` driving a `HardwareInterface`, so algorithm output reaches
+/// motors through `CoreEngine::execute_to_device`
+///
+/// A command of the wrong length for the mapping fails with
+/// `CoreError::InvalidInput` before anything is written.
+pub struct HardwareDevice {
+    name: String,
+    hardware: Box<dyn HardwareInterface>,
+    mapping: OutputMapping,
+}
+
+impl HardwareDevice {
+    /// Create a device registered under `name`
+    pub fn new(name: &str, hardware: Box<dyn HardwareInterface>, mapping: OutputMapping) -> Self {
+        Self {
+            name: name.to_string(),
+            hardware,
+            mapping,
+        }
+    }
+    
+    /// The hardware the device drives, such as for reading encoder pins
+    pub fn hardware_mut(&mut self) -> &mut dyn HardwareInterface {
+        self.hardware.as_mut()
+    }
+}
+
+impl Device for HardwareDevice {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn write_command(&mut self, command: &[u8]) -> Result<(), CoreError> {
+        match &self.mapping {
+            OutputMapping::Pins(pins) => {
+                check_count(command.len(), pins.len(), "pin levels")?;
+                for (&pin, &level) in pins.iter().zip(command) {
+                    self.hardware.write_pin(pin, level != 0)?;
+                }
+                Ok(())
+            }
+            OutputMapping::Pwm(channels) => {
+                let duties = samples::f32_from_bytes(command)?;
+                check_count(duties.len(), channels.len(), "duty cycles")?;
+                for (&channel, &duty) in channels.iter().zip(&duties) {
+                    self.hardware.set_pwm(channel, f64::from(duty))?;
+                }
+                Ok(())
+            }
+            OutputMapping::Serial => self.hardware.write_serial(command),
+        }
+    }
+}
+
+fn check_count(got: usize, expected: usize, what: &str) -> Result<(), CoreError> {
+    if got != expected {
+        return Err(CoreError::InvalidInput(format!("expected {} {}, got {}", expected, what, got)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::MockHardware;
+    
+    #[test]
+    fn test_mappings_drive_hardware() {
+        let hardware = MockHardware::new("board");
+        let mut pins = HardwareDevice::new("brakes", Box::new(hardware.clone()), OutputMapping::Pins(vec![4, 5]));
+        pins.write_command(&[1, 0]).unwrap();
+        assert_eq!((hardware.pin(4), hardware.pin(5)), (Some(true), Some(false)));
+        assert!(matches!(pins.write_command(&[1]), Err(CoreError::InvalidInput(_))));
+        
+        let mut motors = HardwareDevice::new("motors", Box::new(hardware.clone()), OutputMapping::Pwm(vec![0, 1]));
+        motors.write_command(&samples::f32_to_bytes(&[0.25, 1.0])).unwrap();
+        assert_eq!((hardware.duty(0), hardware.duty(1)), (Some(0.25), Some(1.0)));
+        assert!(motors.write_command(&samples::f32_to_bytes(&[0.5, 1.5])).is_err());
+        
+        let mut uart = HardwareDevice::new("uart", Box::new(hardware.clone()), OutputMapping::Serial);
+        uart.write_command(b"M1 100\n").unwrap();
+        assert_eq!(hardware.serial_written(), b"M1 100\n");
+    }
+    
+    #[test]
+    fn test_unimplemented_operations_are_unsupported() {
+        struct PinsOnly;
+        
+        impl HardwareInterface for PinsOnly {
+            fn name(&self) -> &str {
+                "pins_only"
+            }
+            
+            fn write_pin(&mut self, _pin: u32, _high: bool) -> Result<(), CoreError> {
+                Ok(())
+            }
+        }
+        
+        let mut device = HardwareDevice::new("motors", Box::new(PinsOnly), OutputMapping::Pwm(vec![0]));
+        assert!(matches!(
+            device.write_command(&samples::f32_to_bytes(&[0.5])),
+            Err(CoreError::UnsupportedCapability(_))
+        ));
+        assert!(device.hardware_mut().write_pin(3, true).is_ok());
+    }
+}
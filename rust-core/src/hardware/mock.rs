@@ -0,0 +1,125 @@
+//! In-memory hardware for tests and CI
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::interface::{check_duty, HardwareInterface};
+use crate::error::CoreError;
+
+#[derive(Debug, Default)]
+struct MockState {
+    pins: BTreeMap<u32, bool>,
+    duties: BTreeMap<u32, f64>,
+    serial_written: Vec<u8>,
+    serial_pending: VecDeque<u8>,
+}
+
+/// Hardware that records every operation and replays scripted inputs
+///
+/// Clones share their state, like `NullDevice`, so keep a clone to inspect
+/// what a registered device did or to feed it encoder levels and serial
+/// replies. A pin reads the level last written or set, and low if neither.
+#[derive(Clone, Debug, Default)]
+pub struct MockHardware {
+    name: String,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockHardware {
+    /// Create hardware named `name`
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            state: Arc::default(),
+        }
+    }
+    
+    /// Level of `pin`, if it was written or set
+    pub fn pin(&self, pin: u32) -> Option<bool> {
+        self.state().pins.get(&pin).copied()
+    }
+    
+    /// Drive input `pin` as an external signal, such as an encoder, would
+    pub fn set_input(&self, pin: u32, high: bool) {
+        self.state().pins.insert(pin, high);
+    }
+    
+    /// Duty cycle last set on PWM `channel`
+    pub fn duty(&self, channel: u32) -> Option<f64> {
+        self.state().duties.get(&channel).copied()
+    }
+    
+    /// Every byte written to the serial line so far
+    pub fn serial_written(&self) -> Vec<u8> {
+        self.state().serial_written.clone()
+    }
+    
+    /// Make `data` available to later serial reads
+    pub fn queue_serial(&self, data: &[u8]) {
+        self.state().serial_pending.extend(data);
+    }
+    
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl HardwareInterface for MockHardware {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn write_pin(&mut self, pin: u32, high: bool) -> Result<(), CoreError> {
+        self.state().pins.insert(pin, high);
+        Ok(())
+    }
+    
+    fn read_pin(&mut self, pin: u32) -> Result<bool, CoreError> {
+        Ok(self.pin(pin).unwrap_or(false))
+    }
+    
+    fn set_pwm(&mut self, channel: u32, duty: f64) -> Result<(), CoreError> {
+        check_duty(duty)?;
+        self.state().duties.insert(channel, duty);
+        Ok(())
+    }
+    
+    fn write_serial(&mut self, data: &[u8]) -> Result<(), CoreError> {
+        self.state().serial_written.extend_from_slice(data);
+        Ok(())
+    }
+    
+    fn read_serial(&mut self, buf: &mut [u8]) -> Result<usize, CoreError> {
+        let mut state = self.state();
+        let count = buf.len().min(state.serial_pending.len());
+        for (slot, byte) in buf.iter_mut().zip(state.serial_pending.drain(..count)) {
+            *slot = byte;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_inputs_and_serial_replies_are_scripted() {
+        let probe = MockHardware::new("board");
+        let mut hardware: Box<dyn HardwareInterface> = Box::new(probe.clone());
+        
+        assert!(!hardware.read_pin(2).unwrap());
+        probe.set_input(2, true);
+        assert!(hardware.read_pin(2).unwrap());
+        
+        probe.queue_serial(b"OK\n");
+        let mut buf = [0u8; 2];
+        assert_eq!(hardware.read_serial(&mut buf).unwrap(), 2);
+        assert_eq!(&buf, b"OK");
+        assert_eq!(hardware.read_serial(&mut buf).unwrap(), 1);
+        assert_eq!(hardware.read_serial(&mut buf).unwrap(), 0);
+        
+        assert!(hardware.set_pwm(0, -0.1).is_err());
+        assert_eq!(probe.duty(0), None);
+    }
+}
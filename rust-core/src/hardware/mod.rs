@@ -0,0 +1,152 @@
+//! Devices that algorithm output can be sent to
+
+use std::sync::{Arc, Mutex};
+
+use crate::error::CoreError;
+use crate::CoreEngine;
+
+mod bus;
+mod can;
+mod framing;
+#[cfg(all(feature = "gpio", target_os = "linux"))]
+mod gpio;
+mod interface;
+mod mock;
+#[cfg(all(feature = "pwm", target_os = "linux"))]
+mod pwm;
+#[cfg(all(feature = "serial", unix))]
+mod serial;
+
+#[cfg(all(feature = "i2c", target_os = "linux"))]
+pub use bus::LinuxI2c;
+#[cfg(all(feature = "spi", target_os = "linux"))]
+pub use bus::LinuxSpi;
+pub use bus::{I2cBus, I2cRegisters, MockI2c, MockSpi, RegisterDevice, SpiDevice, SpiRegisters};
+#[cfg(all(feature = "can", target_os = "linux"))]
+pub use can::SocketCan;
+pub use can::{CanDevice, CanFilter, CanFrame, CanInterface, CanReceiver, CanSensor, MockCan};
+pub use framing::{FrameSplitter, OutputFrame};
+#[cfg(all(feature = "gpio", target_os = "linux"))]
+pub use gpio::SysfsGpio;
+pub use interface::{HardwareDevice, HardwareInterface, OutputMapping};
+#[cfg(feature = "sim")]
+pub(crate) use interface::check_duty;
+pub use mock::MockHardware;
+#[cfg(all(feature = "pwm", target_os = "linux"))]
+pub use pwm::SysfsPwm;
+#[cfg(all(feature = "serial", unix))]
+pub use serial::SerialPort;
+
+/// An actuator or other device accepting commands as raw bytes
+pub trait Device: Send + Sync {
+    /// Name the device is registered under
+    fn name(&self) -> &str;
+    
+    /// Send one command to the device
+    fn write_command(&mut self, command: &[u8]) -> Result<(), CoreError>;
+}
+
+/// A device that accepts every command and keeps a copy, for tests and dry
+/// runs
+///
+/// Clones share the received commands, so keep a clone to inspect what a
+/// registered device was sent.
+#[derive(Clone, Debug, Default)]
+pub struct NullDevice {
+    name: String,
+    received: Arc<Mutex<Vec<Vec<u8>>>>,
+}
+
+impl NullDevice {
+    /// Create a device registered under `name`
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            received: Arc::default(),
+        }
+    }
+    
+    /// Every command written so far, oldest first
+    pub fn received(&self) -> Vec<Vec<u8>> {
+        self.received.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+impl Device for NullDevice {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn write_command(&mut self, command: &[u8]) -> Result<(), CoreError> {
+        self.received
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(command.to_vec());
+        Ok(())
+    }
+}
+
+impl CoreEngine {
+    /// Register a device under its name, returning any device it replaces
+    pub fn register_device(&mut self, device: Box<dyn Device>) -> Option<Box<dyn Device>> {
+        self.devices.insert(device.name().to_string(), device)
+    }
+    
+    /// Remove a device, returning it
+    pub fn unregister_device(&mut self, name: &str) -> Option<Box<dyn Device>> {
+        self.devices.remove(name)
+    }
+    
+    /// Execute an algorithm and write its output to the device registered
+    /// as `device_name` as a single command
+    ///
+    /// The device is looked up before the algorithm runs, so an unknown name
+    /// fails with `CoreError::DeviceNotFound` without executing anything.
+    /// Errors from the execution or from the device are returned as is.
+    pub fn execute_to_device(&mut self, algorithm_id: &str, input: &[u8], device_name: &str) -> Result<(), CoreError> {
+        if !self.devices.contains_key(device_name) {
+            return Err(CoreError::DeviceNotFound(device_name.to_string()));
+        }
+        let output = self.execute(algorithm_id, input)?;
+        let mut span = self
+            .telemetry
+            .span("device_write")
+            .with("device", device_name)
+            .with("bytes", output.data.len());
+        let result = self
+            .devices
+            .get_mut(device_name)
+            .ok_or_else(|| CoreError::DeviceNotFound(device_name.to_string()))?
+            .write_command(&output.data);
+        self.telemetry.record_device_write(device_name, result.as_ref().err());
+        span.record_result(&result);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::builtins::Scale;
+    use crate::algorithm::samples;
+    
+    #[test]
+    fn test_output_reaches_device() {
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(Scale::new(2.0).unwrap())).unwrap();
+        let device = NullDevice::new("motor");
+        engine.register_device(Box::new(device.clone()));
+        
+        engine
+            .execute_to_device(Scale::ID, &samples::f32_to_bytes(&[1.5, -2.0]), "motor")
+            .unwrap();
+        assert_eq!(device.received(), vec![samples::f32_to_bytes(&[3.0, -4.0])]);
+        
+        assert_eq!(
+            engine.execute_to_device(Scale::ID, &[], "gripper"),
+            Err(CoreError::DeviceNotFound("gripper".to_string()))
+        );
+        assert!(engine.execute_to_device("missing", &[], "motor").is_err());
+        assert_eq!(device.received().len(), 1);
+    }
+}
@@ -0,0 +1,118 @@
+//! Linux PWM through the sysfs interface
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::interface::{check_duty, HardwareInterface};
+use crate::error::CoreError;
+
+/// PWM channels of one controller under `/sys/class/pwm`
+///
+/// Every channel runs at the backend's period. A channel is exported,
+/// given the period and enabled on its first `set_pwm`; channels the
+/// backend enabled are disabled, and those it exported unexported, when it
+/// is dropped.
+pub struct SysfsPwm {
+    name: String,
+    chip: PathBuf,
+    period_ns: u64,
+    enabled: HashSet<u32>,
+    exported: Vec<u32>,
+}
+
+impl SysfsPwm {
+    /// Use controller `pwmchip<chip>` of the system with channels repeating
+    /// every `period`
+    pub fn new(name: &str, chip: u32, period: Duration) -> Result<Self, CoreError> {
+        Self::with_chip_path(name, format!("/sys/class/pwm/pwmchip{}", chip), period)
+    }
+    
+    /// Use the controller at `chip` in a sysfs PWM tree
+    pub fn with_chip_path(name: &str, chip: impl AsRef<Path>, period: Duration) -> Result<Self, CoreError> {
+        let period_ns = u64::try_from(period.as_nanos())
+            .ok()
+            .filter(|&ns| ns > 0)
+            .ok_or_else(|| CoreError::InvalidParameter(format!("PWM period must be 1ns to u64::MAX ns, got {:?}", period)))?;
+        Ok(Self {
+            name: name.to_string(),
+            chip: chip.as_ref().to_path_buf(),
+            period_ns,
+            enabled: HashSet::new(),
+            exported: Vec::new(),
+        })
+    }
+    
+    fn channel_dir(&self, channel: u32) -> PathBuf {
+        self.chip.join(format!("pwm{}", channel))
+    }
+}
+
+impl HardwareInterface for SysfsPwm {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn set_pwm(&mut self, channel: u32, duty: f64) -> Result<(), CoreError> {
+        check_duty(duty)?;
+        let dir = self.channel_dir(channel);
+        if !dir.exists() {
+            fs::write(self.chip.join("export"), channel.to_string())?;
+            self.exported.push(channel);
+        }
+        let duty_ns = (self.period_ns as f64 * duty).round() as u64;
+        if self.enabled.contains(&channel) {
+            fs::write(dir.join("duty_cycle"), duty_ns.to_string())?;
+        } else {
+            // The kernel rejects a duty cycle longer than the period, so
+            // the period goes first
+            fs::write(dir.join("period"), self.period_ns.to_string())?;
+            fs::write(dir.join("duty_cycle"), duty_ns.to_string())?;
+            fs::write(dir.join("enable"), "1")?;
+            self.enabled.insert(channel);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for SysfsPwm {
+    fn drop(&mut self) {
+        for channel in &self.enabled {
+            if let Err(e) = fs::write(self.channel_dir(*channel).join("enable"), "0") {
+                log::warn!("Failed to disable PWM channel {}: {}", channel, e);
+            }
+        }
+        for channel in &self.exported {
+            if let Err(e) = fs::write(self.chip.join("unexport"), channel.to_string()) {
+                log::warn!("Failed to unexport PWM channel {}: {}", channel, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_channel_enabled_with_period_then_duty_updated() {
+        let chip = std::env::temp_dir().join(format!("robotics_core_pwmchip_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&chip);
+        fs::create_dir_all(chip.join("pwm0")).unwrap();
+        let read = |file: &str| fs::read_to_string(chip.join("pwm0").join(file)).unwrap();
+        
+        let mut pwm = SysfsPwm::with_chip_path("pwm", &chip, Duration::from_micros(20)).unwrap();
+        pwm.set_pwm(0, 0.25).unwrap();
+        assert_eq!((read("period"), read("duty_cycle"), read("enable")), ("20000".into(), "5000".into(), "1".into()));
+        pwm.set_pwm(0, 1.0).unwrap();
+        assert_eq!(read("duty_cycle"), "20000");
+        assert!(pwm.set_pwm(0, 2.0).is_err());
+        
+        drop(pwm);
+        assert_eq!(read("enable"), "0");
+        assert!(!chip.join("unexport").exists());
+        assert!(SysfsPwm::with_chip_path("pwm", &chip, Duration::ZERO).is_err());
+        fs::remove_dir_all(&chip).unwrap();
+    }
+}
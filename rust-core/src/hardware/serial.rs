@@ -0,0 +1,145 @@
+//! UART serial lines through termios
+
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+use super::interface::HardwareInterface;
+use crate::error::CoreError;
+
+/// A serial device such as `/dev/ttyUSB0`, configured raw at a fixed baud
+/// rate with 8 data bits, no parity and one stop bit
+///
+/// Reads return what arrived within a tenth of a second, so polling an idle
+/// line returns 0 instead of blocking.
+pub struct SerialPort {
+    name: String,
+    file: File,
+}
+
+impl SerialPort {
+    /// Open and configure the device at `path`
+    ///
+    /// Fails with `CoreError::InvalidParameter` for a baud rate termios has
+    /// no constant for.
+    pub fn open(name: &str, path: impl AsRef<Path>, baud: u32) -> Result<Self, CoreError> {
+        let speed = speed(baud)?;
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NOCTTY)
+            .open(path)?;
+        let fd = file.as_raw_fd();
+        // SAFETY: `termios` is plain data that `tcgetattr` fully initializes
+        // before it is read, and `fd` is open for the duration of the calls.
+        unsafe {
+            let mut termios: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(fd, &mut termios) != 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            libc::cfmakeraw(&mut termios);
+            termios.c_cflag &= !libc::CSTOPB;
+            termios.c_cflag |= libc::CLOCAL | libc::CREAD;
+            termios.c_cc[libc::VMIN] = 0;
+            termios.c_cc[libc::VTIME] = 1;
+            if libc::cfsetispeed(&mut termios, speed) != 0
+                || libc::cfsetospeed(&mut termios, speed) != 0
+                || libc::tcsetattr(fd, libc::TCSANOW, &termios) != 0
+            {
+                return Err(std::io::Error::last_os_error().into());
+            }
+        }
+        Ok(Self {
+            name: name.to_string(),
+            file,
+        })
+    }
+}
+
+fn speed(baud: u32) -> Result<libc::speed_t, CoreError> {
+    Ok(match baud {
+        1200 => libc::B1200,
+        2400 => libc::B2400,
+        4800 => libc::B4800,
+        9600 => libc::B9600,
+        19200 => libc::B19200,
+        38400 => libc::B38400,
+        57600 => libc::B57600,
+        115200 => libc::B115200,
+        230400 => libc::B230400,
+        _ => return Err(CoreError::InvalidParameter(format!("unsupported baud rate {}", baud))),
+    })
+}
+
+impl HardwareInterface for SerialPort {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn write_serial(&mut self, data: &[u8]) -> Result<(), CoreError> {
+        self.file.write_all(data)?;
+        Ok(self.file.flush()?)
+    }
+    
+    fn read_serial(&mut self, buf: &mut [u8]) -> Result<usize, CoreError> {
+        loop {
+            match self.file.read(buf) {
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                result => return Ok(result?),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+    use std::os::unix::io::FromRawFd;
+    
+    /// Open a pseudo-terminal, returning its controlling side and the path
+    /// of the device side
+    fn pty() -> (File, String) {
+        // SAFETY: the descriptor returned by `posix_openpt` is checked and
+        // then owned by the `File`; `ptsname_r` writes a NUL-terminated name
+        // into the buffer it is given.
+        unsafe {
+            let fd = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+            assert!(fd >= 0);
+            let master = File::from_raw_fd(fd);
+            assert_eq!(libc::grantpt(fd), 0);
+            assert_eq!(libc::unlockpt(fd), 0);
+            let mut name = [0 as libc::c_char; 128];
+            assert_eq!(libc::ptsname_r(fd, name.as_mut_ptr(), name.len()), 0);
+            (master, CStr::from_ptr(name.as_ptr()).to_string_lossy().into_owned())
+        }
+    }
+    
+    #[test]
+    fn test_bytes_cross_the_line_both_ways() {
+        let (mut master, path) = pty();
+        let mut port = SerialPort::open("uart", &path, 115200).unwrap();
+        
+        port.write_serial(b"M1 100\n").unwrap();
+        let mut received = [0u8; 7];
+        master.read_exact(&mut received).unwrap();
+        assert_eq!(&received, b"M1 100\n");
+        
+        master.write_all(b"OK").unwrap();
+        let mut reply = [0u8; 8];
+        let mut read = 0;
+        for _ in 0..50 {
+            if read == 2 {
+                break;
+            }
+            read += port.read_serial(&mut reply[read..]).unwrap();
+        }
+        assert_eq!(&reply[..read], b"OK");
+        // Nothing more arrives, so the read times out empty
+        assert_eq!(port.read_serial(&mut reply).unwrap(), 0);
+        
+        assert!(SerialPort::open("uart", &path, 12345).is_err());
+    }
+}
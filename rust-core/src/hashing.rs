@@ -0,0 +1,124 @@
+//! Content hashing for output cache keys and integrity checks
+//!
+//! Deployments trade speed against collision resistance by choosing a
+//! `Hasher` with `CoreEngineBuilder::hasher`. Every implementation is
+//! seedless and fixed across runs, Rust releases and platforms, so digests
+//! can be stored and compared later.
+
+/// Digest of a byte string
+pub trait Hasher: Send + Sync {
+    /// Name recorded in `EngineConfig::hasher`
+    fn name(&self) -> &'static str;
+    
+    /// Digest of `bytes`, always the same length for a given hasher
+    fn digest(&self, bytes: &[u8]) -> Vec<u8>;
+}
+
+/// 64-bit FNV-1a, the default
+///
+/// Fast on the short inputs typical of cache keys and dependency-free, but
+/// easy to collide on purpose.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Fnv1a;
+
+impl Fnv1a {
+    /// The 64-bit hash of `bytes`
+    pub fn hash(bytes: &[u8]) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+        bytes
+            .iter()
+            .fold(OFFSET_BASIS, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(PRIME))
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn name(&self) -> &'static str {
+        "fnv1a"
+    }
+    
+    fn digest(&self, bytes: &[u8]) -> Vec<u8> {
+        Self::hash(bytes).to_be_bytes().to_vec()
+    }
+}
+
+/// 64-bit xxHash with seed 0, fast on long inputs (`xxhash` feature)
+#[cfg(feature = "xxhash")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct XxHash;
+
+#[cfg(feature = "xxhash")]
+impl Hasher for XxHash {
+    fn name(&self) -> &'static str {
+        "xxhash64"
+    }
+    
+    fn digest(&self, bytes: &[u8]) -> Vec<u8> {
+        twox_hash::XxHash64::oneshot(0, bytes).to_be_bytes().to_vec()
+    }
+}
+
+/// 256-bit BLAKE3, a cryptographic hash for when collisions must be
+/// infeasible (`blake3` feature)
+#[cfg(feature = "blake3")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Blake3;
+
+#[cfg(feature = "blake3")]
+impl Hasher for Blake3 {
+    fn name(&self) -> &'static str {
+        "blake3"
+    }
+    
+    fn digest(&self, bytes: &[u8]) -> Vec<u8> {
+        blake3::hash(bytes).as_bytes().to_vec()
+    }
+}
+
+/// Lowercase hex of `digest`
+pub fn to_hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn hashers() -> Vec<Box<dyn Hasher>> {
+        vec![
+            Box::new(Fnv1a),
+            #[cfg(feature = "xxhash")]
+            Box::new(XxHash),
+            #[cfg(feature = "blake3")]
+            Box::new(Blake3),
+        ]
+    }
+    
+    #[test]
+    fn test_digests_are_stable_and_distinct() {
+        let inputs: [&[u8]; 4] = [b"", b"a", b"b", b"robotics-core"];
+        for hasher in hashers() {
+            let digests: Vec<Vec<u8>> = inputs.iter().map(|input| hasher.digest(input)).collect();
+            for (input, digest) in inputs.iter().zip(&digests) {
+                assert_eq!(&hasher.digest(input), digest, "{} is not stable", hasher.name());
+                assert_eq!(digest.len(), digests[0].len());
+            }
+            for (i, a) in digests.iter().enumerate() {
+                assert!(digests[i + 1..].iter().all(|b| a != b), "{} collided", hasher.name());
+            }
+        }
+    }
+    
+    #[test]
+    fn test_known_digests() {
+        // Published test vectors, pinning the digests across platforms
+        assert_eq!(to_hex(&Fnv1a.digest(b"a")), "af63dc4c8601ec8c");
+        #[cfg(feature = "xxhash")]
+        assert_eq!(to_hex(&XxHash.digest(b"")), "ef46db3751d8e999");
+        #[cfg(feature = "blake3")]
+        assert_eq!(
+            to_hex(&Blake3.digest(b"")),
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! Input and output transformations applied around every execution
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use crate::error::CoreError;
+use crate::CoreEngine;
+
+/// A transformation of execution input or output bytes
+pub type Hook = dyn Fn(&[u8]) -> Result<Vec<u8>, CoreError> + Send + Sync;
+
+/// Preprocessors and postprocessors, each run in registration order
+#[derive(Clone, Default)]
+pub(crate) struct Hooks {
+    pre: Vec<Arc<Hook>>,
+    post: Vec<Arc<Hook>>,
+}
+
+impl Hooks {
+    pub(crate) fn preprocessors(&self) -> usize {
+        self.pre.len()
+    }
+    
+    pub(crate) fn postprocessors(&self) -> usize {
+        self.post.len()
+    }
+    
+    /// Run the preprocessors over `input`, borrowing it when there are none
+    pub(crate) fn preprocess<'a>(&self, input: &'a [u8]) -> Result<Cow<'a, [u8]>, CoreError> {
+        chain(&self.pre, Cow::Borrowed(input))
+    }
+    
+    /// Run the postprocessors over `output`
+    pub(crate) fn postprocess(&self, output: Vec<u8>) -> Result<Vec<u8>, CoreError> {
+        Ok(chain(&self.post, Cow::Owned(output))?.into_owned())
+    }
+}
+
+fn chain<'a>(hooks: &[Arc<Hook>], mut data: Cow<'a, [u8]>) -> Result<Cow<'a, [u8]>, CoreError> {
+    for hook in hooks {
+        data = Cow::Owned(hook(&data)?);
+    }
+    Ok(data)
+}
+
+impl CoreEngine {
+    /// Transform the input of every execution before the algorithm sees it
+    ///
+    /// Preprocessors run in the order they were added, each on the previous
+    /// one's result, for executions, pipeline stages, batch items and DAG
+    /// nodes alike. An error aborts the execution with that error. Inputs
+    /// are cached as given, before preprocessing.
+    pub fn add_preprocessor(&mut self, hook: Box<Hook>) {
+        self.hooks.pre.push(Arc::from(hook));
+    }
+    
+    /// Transform the output of every execution before it is returned
+    ///
+    /// The counterpart of `add_preprocessor`; postprocessors also run in
+    /// the order they were added.
+    pub fn add_postprocessor(&mut self, hook: Box<Hook>) {
+        self.hooks.post.push(Arc::from(hook));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{Algorithm, AlgorithmMetadata};
+    use crate::memory::MemoryManager;
+    use std::sync::Mutex;
+    
+    /// Records the inputs it is given and returns them unchanged
+    struct Spy(Arc<Mutex<Vec<Vec<u8>>>>);
+    
+    impl Algorithm for Spy {
+        fn process(&self, input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            self.0.lock().unwrap().push(input.to_vec());
+            Ok(input.to_vec())
+        }
+        
+        fn id(&self) -> &str {
+            "spy"
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata::default()
+        }
+    }
+    
+    #[test]
+    fn test_preprocessor_scrubs_input_before_process() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(Spy(seen.clone()))).unwrap();
+        // 0xff marks a dropped sample
+        engine.add_preprocessor(Box::new(|input| Ok(input.iter().map(|&b| if b == 0xff { 0 } else { b }).collect())));
+        engine.add_postprocessor(Box::new(|output| Ok([output, &[9]].concat())));
+        
+        assert_eq!(engine.execute_algorithm("spy", &[1, 0xff, 3]).unwrap(), vec![1, 0, 3, 9]);
+        assert_eq!(engine.execute_batch("spy", &[&[0xff]]).unwrap(), vec![vec![0, 9]]);
+        assert_eq!(*seen.lock().unwrap(), vec![vec![1, 0, 3], vec![0]]);
+    }
+    
+    #[test]
+    fn test_failing_hook_aborts_execution() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(Spy(seen.clone()))).unwrap();
+        engine.add_preprocessor(Box::new(|input| {
+            if input.is_empty() {
+                Err(CoreError::InvalidInput("empty input".to_string()))
+            } else {
+                Ok(input.to_vec())
+            }
+        }));
+        
+        assert_eq!(
+            engine.execute_algorithm("spy", &[]),
+            Err(CoreError::InvalidInput("empty input".to_string()))
+        );
+        assert!(seen.lock().unwrap().is_empty());
+    }
+}
@@ -1,60 +1,1399 @@
 //! Core Rust implementation for robotics-core1
 //! Handles performance-critical operations and low-level functionalities
+//!
+//! The core (algorithms, heap memory manager, built-ins) also builds for
+//! `wasm32-unknown-unknown`; anything needing threads or a filesystem is
+//! compiled out on that target. Enable the `wasm` feature for JS bindings.
+//!
+//! Without the default `std` feature the crate is `no_std` with `alloc`, for
+//! microcontroller targets with atomic compare-and-swap, such as Cortex-M3
+//! and up. What remains is `math`, whose kernels then choose their SIMD
+//! paths at compile time instead of by runtime detection, the `Algorithm`
+//! trait with the filters, PID controller and kinematics, and a
+//! `MemoryManager` without protected regions, TTLs, snapshots or
+//! thread-local arenas. Sensors, hardware, the engine and the bindings need
+//! `std`. Firmware using the crate provides the `#[global_allocator]` and
+//! `#[panic_handler]`; the Python and JS shared library is built by the
+//! `ffi` wrapper crate, so `cargo build --no-default-features` only builds
+//! the rlib.
+//!
+//! The library never panics on its own account: fallible paths return
+//! `CoreError`, and `unwrap`, `expect` and `panic!` are denied outside
+//! tests so none creep back in.
 
-mod memory;
-mod sensor;
-mod algorithm;
+#![cfg_attr(not(test), deny(clippy::unwrap_used, clippy::expect_used, clippy::panic))]
+#![cfg_attr(all(not(feature = "std"), not(test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod calibration;
+#[cfg(feature = "std")]
+pub mod clock;
+#[cfg(feature = "std")]
+pub mod compression;
+pub mod memory;
+#[cfg(feature = "std")]
+pub mod messages;
+#[cfg(feature = "std")]
+pub mod sensor;
+pub mod algorithm;
+#[cfg(feature = "std")]
 mod hardware;
+#[cfg(feature = "std")]
+pub mod bus;
+pub mod error;
+#[cfg(feature = "std")]
+pub mod hashing;
+pub mod math;
+#[cfg(feature = "std")]
+pub mod parameters;
+#[cfg(feature = "std")]
+pub mod pipeline;
+#[cfg(all(feature = "plugin", unix))]
+pub mod plugin;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub mod recorder;
+#[cfg(all(feature = "rpc", not(target_arch = "wasm32")))]
+pub mod rpc;
+#[cfg(feature = "std")]
+pub mod safety;
+#[cfg(feature = "std")]
+pub mod scheduler;
+#[cfg(feature = "sim")]
+pub mod sim;
+#[cfg(feature = "std")]
+pub mod telemetry;
+#[cfg(feature = "std")]
+pub mod transform;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+mod async_execution;
+#[cfg(feature = "std")]
+mod batch;
+#[cfg(feature = "std")]
+mod cache;
+#[cfg(feature = "std")]
+mod chunking;
+mod compat;
+#[cfg(feature = "std")]
+mod config;
+#[cfg(feature = "std")]
+mod cpu_time;
+#[cfg(feature = "std")]
+mod deadline;
+#[cfg(feature = "std")]
+mod executor;
+#[cfg(feature = "std")]
+mod hooks;
+#[cfg(feature = "std")]
+mod logging;
+#[cfg(feature = "std")]
+mod pool;
+#[cfg(feature = "std")]
+mod preemption;
+#[cfg(feature = "std")]
+mod streaming;
+mod wire;
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub use async_execution::{AsyncExecution, ExecutionState};
+#[cfg(feature = "std")]
+pub use batch::{BatchReport, BatchStatus, CancellationToken, ItemOutcome, Progress};
+#[cfg(feature = "std")]
+pub use chunking::{ChunkDecision, ChunkTuner};
+#[cfg(feature = "std")]
+pub use config::{EngineConfig, EngineSetup, MemoryConfig};
+#[cfg(feature = "std")]
+pub use cpu_time::ExecutionMetrics;
+#[cfg(feature = "std")]
+pub use deadline::DeadlineMiss;
+pub use error::{CoreError, Recoverability};
+#[cfg(feature = "std")]
+pub use executor::ExecutorHandle;
+#[cfg(feature = "std")]
+pub use hardware::{
+    CanDevice, CanFilter, CanFrame, CanInterface, CanReceiver, CanSensor, Device, FrameSplitter, HardwareDevice,
+    HardwareInterface, I2cBus, I2cRegisters, MockCan, MockHardware, MockI2c, MockSpi, NullDevice, OutputFrame,
+    OutputMapping, RegisterDevice, SpiDevice, SpiRegisters,
+};
+#[cfg(all(feature = "gpio", target_os = "linux"))]
+pub use hardware::SysfsGpio;
+#[cfg(all(feature = "pwm", target_os = "linux"))]
+pub use hardware::SysfsPwm;
+#[cfg(all(feature = "serial", unix))]
+pub use hardware::SerialPort;
+#[cfg(all(feature = "can", target_os = "linux"))]
+pub use hardware::SocketCan;
+#[cfg(all(feature = "i2c", target_os = "linux"))]
+pub use hardware::LinuxI2c;
+#[cfg(all(feature = "spi", target_os = "linux"))]
+pub use hardware::LinuxSpi;
+#[cfg(feature = "std")]
+pub use hooks::Hook;
+#[cfg(feature = "std")]
+pub use preemption::{ClassBudget, ExecutionClass};
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub use preemption::{RealTimeOptions, RealTimeThread};
+#[cfg(feature = "std")]
+pub use streaming::{AlgorithmStream, StreamCheckpoint};
+
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "std")]
+use std::panic::{self, AssertUnwindSafe};
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
+#[cfg(feature = "std")]
+use algorithm::registry::AlgorithmRegistry;
+#[cfg(feature = "std")]
+use algorithm::AlgorithmOutput;
+#[cfg(feature = "std")]
+use clock::{Clock, SystemClock};
+#[cfg(feature = "std")]
+use pool::WorkerPool;
 
 #[cfg(feature = "python-binding")]
 mod python_bindings;
 
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "bench-utils")]
+pub mod bench;
+
 /// Core execution engine for robotics algorithms
+#[cfg(feature = "std")]
 pub struct CoreEngine {
     memory_manager: memory::MemoryManager,
+    registry: AlgorithmRegistry,
+    clock: Arc<dyn Clock>,
+    hasher: Arc<dyn hashing::Hasher>,
+    context: algorithm::context::ContextSource,
+    pool: WorkerPool,
+    // Runs asynchronous executions of background algorithms
+    #[cfg(not(target_arch = "wasm32"))]
+    background: WorkerPool,
+    classes: preemption::Classes,
+    max_depth: usize,
+    deadlines: deadline::DeadlineMonitor,
+    // IDs registered through `register_pure`, and the caches enabled for them
+    pure: HashSet<String>,
+    caches: HashMap<String, cache::OutputCache>,
+    log_levels: logging::LogLevels,
+    hooks: hooks::Hooks,
+    devices: HashMap<String, Box<dyn hardware::Device>>,
+    pipelines: HashMap<String, pipeline::Pipeline>,
+    telemetry: Arc<telemetry::Telemetry>,
+    parameters: Arc<parameters::ParameterServer>,
+    bus: bus::MessageBus,
+    // Reported by `status`, once set through `set_safety_monitor`
+    safety: Option<Arc<safety::SafetyMonitor>>,
+    // Loaded plugin libraries and the IDs each registered
+    #[cfg(all(feature = "plugin", unix))]
+    plugins: HashMap<std::path::PathBuf, Vec<String>>,
+    // Set through `set_tracer`; compiled out without the `otel` feature
+    #[cfg(feature = "otel")]
+    tracer: Option<Arc<opentelemetry::global::BoxedTracer>>,
+}
+
+/// Builder for a `CoreEngine` with non-default configuration
+#[derive(Default)]
+#[cfg(feature = "std")]
+pub struct CoreEngineBuilder {
+    clock: Option<Arc<dyn Clock>>,
+    threads: Option<usize>,
+    background_threads: Option<usize>,
+    max_depth: Option<usize>,
+    seed: Option<u64>,
+    hasher: Option<Arc<dyn hashing::Hasher>>,
+    memory_pool: Option<(usize, usize)>,
+}
+
+#[cfg(feature = "std")]
+impl CoreEngineBuilder {
+    /// Take all timings from `clock` instead of the system clock
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+    
+    /// Cap the worker threads shared by batch and DAG execution
+    ///
+    /// Defaults to one per available core. The pool is started on first
+    /// use, so engines that never run in parallel spawn no threads.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+    
+    /// Cap the worker threads running asynchronous executions of
+    /// `ExecutionClass::Background` algorithms
+    ///
+    /// Defaults to one, so background work never takes more than a core.
+    pub fn background_threads(mut self, threads: usize) -> Self {
+        self.background_threads = Some(threads);
+        self
+    }
+    
+    /// Reject pipelines with more stages, and DAGs with longer dependency
+    /// chains, than `max_depth`
+    ///
+    /// Defaults to `pipeline::DEFAULT_MAX_DEPTH`. The limit guards against
+    /// malformed or generated definitions rather than reflecting an
+    /// execution constraint.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+    
+    /// Run deterministically: every execution's `algorithm::context::Context`
+    /// gets a clock frozen at zero and a generator seeded with `seed`
+    ///
+    /// Algorithms that draw time and randomness only from their context
+    /// then give the same output for the same input on every run, in
+    /// batches and DAGs too, whatever order items run in.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+    
+    /// Key output caches and compute fingerprints with `hasher` instead of
+    /// `hashing::Fnv1a`
+    ///
+    /// Set once here, so every digest within a run comes from the same
+    /// hasher.
+    pub fn hasher(mut self, hasher: Arc<dyn hashing::Hasher>) -> Self {
+        self.hasher = Some(hasher);
+        self
+    }
+    
+    /// Serve shared memory from `blocks` buffers of `block_size` bytes,
+    /// allocated as the engine is built
+    ///
+    /// Allocations then never reach the global allocator, and fail with
+    /// `CoreError::PoolExhausted` once every block is in use; see
+    /// `MemoryManager::with_fixed_pool`.
+    pub fn memory_pool(mut self, block_size: usize, blocks: usize) -> Self {
+        self.memory_pool = Some((block_size, blocks));
+        self
+    }
+    
+    /// Build the engine
+    pub fn build(self) -> CoreEngine {
+        let clock = self.clock.unwrap_or_else(|| Arc::new(SystemClock::new()));
+        let mut memory_manager = match self.memory_pool {
+            Some((block_size, blocks)) => memory::MemoryManager::with_fixed_pool(block_size, blocks),
+            None => memory::MemoryManager::new(),
+        };
+        memory_manager.set_clock(Arc::clone(&clock));
+        CoreEngine {
+            memory_manager,
+            registry: AlgorithmRegistry::new(),
+            context: algorithm::context::ContextSource::new(Arc::clone(&clock), self.seed),
+            telemetry: Arc::new(telemetry::Telemetry::new(Arc::clone(&clock))),
+            clock,
+            hasher: self.hasher.unwrap_or_else(|| Arc::new(hashing::Fnv1a)),
+            pool: WorkerPool::new(self.threads),
+            #[cfg(not(target_arch = "wasm32"))]
+            background: WorkerPool::named("robotics-core-background", Some(self.background_threads.unwrap_or(1))),
+            classes: preemption::Classes::new(),
+            max_depth: self.max_depth.unwrap_or(pipeline::DEFAULT_MAX_DEPTH),
+            deadlines: deadline::DeadlineMonitor::default(),
+            pure: HashSet::new(),
+            caches: HashMap::new(),
+            log_levels: logging::LogLevels::default(),
+            hooks: hooks::Hooks::default(),
+            devices: HashMap::new(),
+            pipelines: HashMap::new(),
+            parameters: Arc::default(),
+            bus: bus::MessageBus::new(),
+            safety: None,
+            #[cfg(all(feature = "plugin", unix))]
+            plugins: HashMap::new(),
+            #[cfg(feature = "otel")]
+            tracer: None,
+        }
+    }
 }
 
+/// Optional features compiled into this build of the crate
+///
+/// Each flag reports whether the matching cargo feature was enabled at
+/// compile time, not whether the facility works on this machine: `gpu` can
+/// be set while `algorithm::gpu::is_available` finds no adapter.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg(feature = "std")]
+pub struct Capabilities {
+    /// FFT built-in (`fft`)
+    pub fft: bool,
+    /// GPU-accelerated built-ins (`gpu`)
+    pub gpu: bool,
+    /// Zstandard frame logs, snapshots and archives (`compression`)
+    pub compression: bool,
+    /// LZ4 codec for snapshots and archives (`lz4`)
+    pub lz4: bool,
+    /// YAML algorithm definitions (`yaml`)
+    pub yaml: bool,
+    /// TOML algorithm definitions (`toml`)
+    pub toml: bool,
+    /// Python bindings (`python-binding`)
+    pub python: bool,
+    /// JS bindings (`wasm`)
+    pub wasm: bool,
+    /// Benchmark helpers (`bench-utils`)
+    pub bench_utils: bool,
+    /// OpenTelemetry execution spans (`otel`)
+    pub otel: bool,
+    /// Spilling oversized regions to memory-mapped files (`spill`)
+    pub spill: bool,
+    /// xxHash hasher (`xxhash`)
+    #[serde(default)]
+    pub xxhash: bool,
+    /// BLAKE3 hasher (`blake3`)
+    #[serde(default)]
+    pub blake3: bool,
+    /// Per-thread CPU time in `ExecutionMetrics` (`cpu-time`, Unix only)
+    #[serde(default)]
+    pub cpu_time: bool,
+    /// Sysfs GPIO backend (`gpio`, Linux only)
+    #[serde(default)]
+    pub gpio: bool,
+    /// Sysfs PWM backend (`pwm`, Linux only)
+    #[serde(default)]
+    pub pwm: bool,
+    /// Serial port backend (`serial`, Unix only)
+    #[serde(default)]
+    pub serial: bool,
+    /// Running asynchronous executions on a Tokio runtime (`tokio`)
+    #[serde(default)]
+    pub tokio: bool,
+    /// Regions shared with other processes through memory-mapped files
+    /// (`shared-mem`, Unix only)
+    #[serde(default)]
+    pub shared_mem: bool,
+    /// `SCHED_FIFO` scheduling of control loop threads (`realtime`, Linux
+    /// only)
+    #[serde(default)]
+    pub realtime: bool,
+    /// SocketCAN bus backend (`can`, Linux only)
+    #[serde(default)]
+    pub can: bool,
+    /// Kinematic chains from URDF robot descriptions (`urdf`)
+    #[serde(default)]
+    pub urdf: bool,
+    /// Linux I2C adapter backend (`i2c`, Linux only)
+    #[serde(default)]
+    pub i2c: bool,
+    /// Linux SPI device backend (`spi`, Linux only)
+    #[serde(default)]
+    pub spi: bool,
+    /// Serving the engine to remote clients over TCP (`rpc`)
+    #[serde(default)]
+    pub rpc: bool,
+    /// Algorithms loaded from shared libraries at runtime (`plugin`, Unix
+    /// only)
+    #[serde(default)]
+    pub plugin: bool,
+    /// Video4Linux camera backend (`v4l2`, Linux only)
+    #[serde(default)]
+    pub v4l2: bool,
+    /// Kinematic robot simulator (`sim`)
+    #[serde(default)]
+    pub sim: bool,
+}
+
+#[cfg(feature = "std")]
+impl Capabilities {
+    /// Cargo feature names of the flags set in `required` but not in `self`
+    pub fn missing(&self, required: &Capabilities) -> Vec<&'static str> {
+        [
+            (required.fft, self.fft, "fft"),
+            (required.gpu, self.gpu, "gpu"),
+            (required.compression, self.compression, "compression"),
+            (required.lz4, self.lz4, "lz4"),
+            (required.yaml, self.yaml, "yaml"),
+            (required.toml, self.toml, "toml"),
+            (required.python, self.python, "python-binding"),
+            (required.wasm, self.wasm, "wasm"),
+            (required.bench_utils, self.bench_utils, "bench-utils"),
+            (required.otel, self.otel, "otel"),
+            (required.spill, self.spill, "spill"),
+            (required.xxhash, self.xxhash, "xxhash"),
+            (required.blake3, self.blake3, "blake3"),
+            (required.cpu_time, self.cpu_time, "cpu-time"),
+            (required.gpio, self.gpio, "gpio"),
+            (required.pwm, self.pwm, "pwm"),
+            (required.serial, self.serial, "serial"),
+            (required.tokio, self.tokio, "tokio"),
+            (required.shared_mem, self.shared_mem, "shared-mem"),
+            (required.realtime, self.realtime, "realtime"),
+            (required.can, self.can, "can"),
+            (required.urdf, self.urdf, "urdf"),
+            (required.i2c, self.i2c, "i2c"),
+            (required.spi, self.spi, "spi"),
+            (required.rpc, self.rpc, "rpc"),
+            (required.plugin, self.plugin, "plugin"),
+            (required.v4l2, self.v4l2, "v4l2"),
+            (required.sim, self.sim, "sim"),
+        ]
+        .into_iter()
+        .filter(|&(needed, present, _)| needed && !present)
+        .map(|(_, _, name)| name)
+        .collect()
+    }
+}
+
+#[cfg(feature = "std")]
 impl CoreEngine {
     /// Create a new instance of the core engine
     pub fn new() -> Self {
-        Self {
-            memory_manager: memory::MemoryManager::new(),
+        Self::builder().build()
+    }
+    
+    /// Start configuring an engine
+    pub fn builder() -> CoreEngineBuilder {
+        CoreEngineBuilder::default()
+    }
+    
+    /// Create an engine that takes all timings from `clock`
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        Self::builder().clock(clock).build()
+    }
+    
+    /// Capture which algorithms are registered, with their metadata
+    pub fn export_registry(&self) -> algorithm::registry::RegistryManifest {
+        self.registry.export()
+    }
+    
+    /// The memory algorithms executed by this engine run against
+    pub fn memory(&self) -> &memory::MemoryManager {
+        &self.memory_manager
+    }
+    
+    /// Mutable access to the engine's memory, e.g. to set up input regions
+    pub fn memory_mut(&mut self) -> &mut memory::MemoryManager {
+        &mut self.memory_manager
+    }
+    
+    /// Release what the engine holds: every memory region no `RegionRef`
+    /// still holds, the registered devices and all cached outputs
+    ///
+    /// Devices are dropped, which closes their connections. Recorders are
+    /// owned by the caller, so flush those separately. Stateful algorithms
+    /// are shut down and initialized again by their next call. Algorithms
+    /// and configuration are kept, and the engine can be used again
+    /// afterwards from empty memory, bar any referenced regions, which are
+    /// logged.
+    pub fn shutdown(&mut self) {
+        for algorithm in self.registry.instances() {
+            if let Some(stateful) = algorithm.as_stateful() {
+                stateful.shutdown();
+            }
+        }
+        let kept = self.memory_manager.clear();
+        if !kept.is_empty() {
+            log::warn!("Shutdown kept regions still referenced: {}", kept.join(", "));
+        }
+        self.devices.clear();
+        for cache in self.caches.values_mut() {
+            cache.clear();
+        }
+    }
+    
+    /// Report which optional features this build includes
+    pub fn capabilities() -> Capabilities {
+        Capabilities {
+            fft: cfg!(feature = "fft"),
+            gpu: cfg!(all(feature = "gpu", not(target_arch = "wasm32"))),
+            compression: cfg!(all(feature = "compression", not(target_arch = "wasm32"))),
+            lz4: cfg!(feature = "lz4"),
+            yaml: cfg!(feature = "yaml"),
+            toml: cfg!(feature = "toml"),
+            python: cfg!(feature = "python-binding"),
+            wasm: cfg!(feature = "wasm"),
+            bench_utils: cfg!(feature = "bench-utils"),
+            otel: cfg!(feature = "otel"),
+            spill: cfg!(all(feature = "spill", not(target_arch = "wasm32"))),
+            xxhash: cfg!(feature = "xxhash"),
+            blake3: cfg!(feature = "blake3"),
+            cpu_time: cfg!(all(feature = "cpu-time", unix)),
+            gpio: cfg!(all(feature = "gpio", target_os = "linux")),
+            pwm: cfg!(all(feature = "pwm", target_os = "linux")),
+            serial: cfg!(all(feature = "serial", unix)),
+            tokio: cfg!(all(feature = "tokio", not(target_arch = "wasm32"))),
+            shared_mem: cfg!(all(feature = "shared-mem", unix)),
+            realtime: cfg!(all(feature = "realtime", target_os = "linux")),
+            can: cfg!(all(feature = "can", target_os = "linux")),
+            urdf: cfg!(feature = "urdf"),
+            i2c: cfg!(all(feature = "i2c", target_os = "linux")),
+            spi: cfg!(all(feature = "spi", target_os = "linux")),
+            rpc: cfg!(all(feature = "rpc", not(target_arch = "wasm32"))),
+            plugin: cfg!(all(feature = "plugin", unix)),
+            v4l2: cfg!(all(feature = "v4l2", target_os = "linux")),
+            sim: cfg!(feature = "sim"),
         }
     }
     
+    /// The clock the engine measures time with
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+    
+    /// The hasher keying output caches and computing fingerprints
+    pub fn hasher(&self) -> &Arc<dyn hashing::Hasher> {
+        &self.hasher
+    }
+    
+    /// Register an algorithm so it can be executed by ID
+    pub fn register(&mut self, algorithm: Box<dyn algorithm::Algorithm>) -> Result<(), CoreError> {
+        self.registry.register(algorithm)
+    }
+    
+    /// Remove a registered algorithm, with its fallback and output cache,
+    /// shutting it down if it is stateful
+    ///
+    /// Built-ins stay resolvable by ID, so unregistering an algorithm
+    /// registered under a built-in's ID makes the built-in serve again.
+    pub fn unregister(&mut self, algorithm_id: &str) -> Result<Arc<dyn algorithm::Algorithm>, CoreError> {
+        let algorithm = self.registry.unregister(algorithm_id)?;
+        if let Some(stateful) = algorithm.as_stateful() {
+            stateful.shutdown();
+        }
+        self.pure.remove(algorithm_id);
+        self.caches.remove(algorithm_id);
+        Ok(algorithm)
+    }
+    
+    /// Registered algorithm IDs with their metadata, in ID order
+    pub fn list_algorithms(&self) -> Vec<(String, algorithm::AlgorithmMetadata)> {
+        self.registry.list()
+    }
+    
+    /// Register an algorithm built once from `config` under `algorithm_id`
+    ///
+    /// The factory bakes the configuration into the instance, so it is
+    /// validated here and a bad config fails registration instead of the
+    /// first execution.
+    pub fn register_configured<F>(
+        &mut self,
+        algorithm_id: &str,
+        config: serde_json::Value,
+        factory: F,
+    ) -> Result<(), CoreError>
+    where
+        F: Fn(&serde_json::Value) -> Result<Box<dyn algorithm::Algorithm>, CoreError>,
+    {
+        let algorithm = factory(&config)?;
+        self.registry.register_as(algorithm_id, algorithm)
+    }
+    
+    /// Register a primary algorithm with a fallback under `algorithm_id`
+    ///
+    /// If the primary returns an error or panics, `execute` transparently
+    /// runs the fallback on the same input. The `served_by` output attribute
+    /// records which of the two (`primary` or `fallback`) produced the result,
+    /// and `primary_error` carries the primary's failure when it fell back.
+    pub fn register_with_fallback<P, F>(
+        &mut self,
+        algorithm_id: &str,
+        primary_factory: P,
+        fallback_factory: F,
+    ) -> Result<(), CoreError>
+    where
+        P: FnOnce() -> Box<dyn algorithm::Algorithm>,
+        F: FnOnce() -> Box<dyn algorithm::Algorithm>,
+    {
+        self.registry
+            .register_with_fallback(algorithm_id, primary_factory(), fallback_factory())
+    }
+    
     /// Execute an algorithm with the given input data
-    pub fn execute_algorithm(&mut self, algorithm_id: &str, input_data: &[u8]) -> Result<Vec<u8>, String> {
-        // Implementation of algorithm execution
-        log::info!("Executing algorithm: {}", algorithm_id);
-        
-        // Get algorithm from registry
-        let algorithm = match self.get_algorithm(algorithm_id) {
-            Some(algo) => algo,
-            None => return Err(format!("Algorithm not found: {}", algorithm_id)),
+    pub fn execute_algorithm(&mut self, algorithm_id: &str, input_data: &[u8]) -> Result<Vec<u8>, CoreError> {
+        self.execute(algorithm_id, input_data).map(|output| output.data)
+    }
+    
+    /// Execute an algorithm, returning its output along with any attributes
+    pub fn execute(&mut self, algorithm_id: &str, input_data: &[u8]) -> Result<AlgorithmOutput, CoreError> {
+        let _running = self.enter_class(algorithm_id);
+        #[cfg(feature = "otel")]
+        if let Some(tracer) = self.tracer.clone() {
+            return self.execute_traced(&tracer, algorithm_id, input_data);
+        }
+        self.execute_untraced(algorithm_id, input_data)
+    }
+    
+    fn execute_untraced(&mut self, algorithm_id: &str, input_data: &[u8]) -> Result<AlgorithmOutput, CoreError> {
+        if let Some(cache) = self.caches.get_mut(algorithm_id) {
+            if let Some(output) = cache.get(input_data) {
+                return Ok(output);
+            }
+            let output = self.execute_uncached(algorithm_id, input_data)?;
+            if let Some(cache) = self.caches.get_mut(algorithm_id) {
+                cache.insert(input_data, output.clone());
+            }
+            return Ok(output);
+        }
+        self.execute_uncached(algorithm_id, input_data)
+    }
+    
+    fn execute_uncached(&mut self, algorithm_id: &str, input_data: &[u8]) -> Result<AlgorithmOutput, CoreError> {
+        self.telemetry.timed(algorithm_id, input_data.len(), || {
+            execute_on(
+                &self.registry,
+                &self.log_levels,
+                &self.hooks,
+                &self.context,
+                &mut self.memory_manager,
+                algorithm_id,
+                input_data,
+            )
+        })
+    }
+    
+    /// Execute an algorithm on the contents of region `input_key`, storing
+    /// the output as region `output_key`
+    ///
+    /// Saves copying data that already lives in engine memory out and back
+    /// in: the input is read where it lies and the output buffer becomes the
+    /// region. A missing input region fails with
+    /// `CoreError::MemoryKeyMissing`. The output region is replaced whatever
+    /// its old length, or created per the missing-key policy, and must be
+    /// writable; it is left untouched if the algorithm fails. While the
+    /// algorithm runs the input region's bytes are detached, so the
+    /// algorithm cannot also reach that region by key.
+    pub fn execute_algorithm_on_region(
+        &mut self,
+        algorithm_id: &str,
+        input_key: &str,
+        output_key: &str,
+    ) -> Result<(), CoreError> {
+        let input = self.memory_manager.lend_region(input_key)?;
+        let result = self.execute_algorithm(algorithm_id, &input);
+        self.memory_manager.restore_region(input_key, input);
+        self.memory_manager.replace_region(output_key, result?)
+    }
+    
+    /// Transform a memory region in place, without an output buffer
+    ///
+    /// The algorithm must support in-place processing (see
+    /// `algorithm::InPlaceAlgorithm`) and the region must exist and be
+    /// writable. While the algorithm runs the region's bytes are detached,
+    /// so the algorithm cannot also reach the region by key.
+    pub fn execute_in_place(&mut self, algorithm_id: &str, region_key: &str) -> Result<(), CoreError> {
+        let algorithm = self
+            .get_algorithm(algorithm_id)
+            .ok_or_else(|| CoreError::AlgorithmNotFound(algorithm_id.to_string()))?;
+        let in_place = algorithm.as_in_place().ok_or_else(|| {
+            CoreError::InvalidParameter(format!("'{}' does not support in-place execution", algorithm_id))
+        })?;
+        let min_input_bytes = algorithm.metadata().min_input_bytes;
+        
+        let mut buffer = self.memory_manager.take_region(region_key)?;
+        let result = if buffer.len() < min_input_bytes {
+            Err(CoreError::InputTooSmall {
+                required: min_input_bytes,
+                actual: buffer.len(),
+            })
+        } else {
+            in_place.process_in_place(&mut buffer, &mut self.memory_manager)
         };
+        self.memory_manager.restore_region(region_key, buffer);
+        result
+    }
+    
+    /// Execute an algorithm on a typed input through
+    /// `Algorithm::process_typed`
+    ///
+    /// Like `execute_in_place`, this calls the algorithm directly against
+    /// engine memory: hooks, output caches and fallbacks, which work on
+    /// bytes, do not apply.
+    pub fn execute_typed(&mut self, algorithm_id: &str, input: &algorithm::tensor::Tensor) -> Result<algorithm::tensor::Tensor, CoreError> {
+        let algorithm = self
+            .get_algorithm(algorithm_id)
+            .ok_or_else(|| CoreError::AlgorithmNotFound(algorithm_id.to_string()))?;
+        let min_input_bytes = algorithm.metadata().min_input_bytes;
+        if input.data().len() < min_input_bytes {
+            return Err(CoreError::InputTooSmall {
+                required: min_input_bytes,
+                actual: input.data().len(),
+            });
+        }
+        algorithm.process_typed(input, &mut self.memory_manager)
+    }
+    
+    /// Run a multi-output algorithm, storing each named output in the
+    /// region `<execution_id>/<name>`
+    ///
+    /// The algorithm must support multi-output processing (see
+    /// `algorithm::MultiOutputAlgorithm`). Regions are allocated afresh,
+    /// replacing any left by an earlier execution under the same ID, so with
+    /// strict keys each execution ID can only be used once. Returns the keys
+    /// written, in sorted order; nothing is written if the algorithm fails.
+    pub fn execute_multi_output(
+        &mut self,
+        algorithm_id: &str,
+        execution_id: &str,
+        input_data: &[u8],
+    ) -> Result<Vec<String>, CoreError> {
+        let algorithm = self
+            .get_algorithm(algorithm_id)
+            .ok_or_else(|| CoreError::AlgorithmNotFound(algorithm_id.to_string()))?;
+        let multi_output = algorithm.as_multi_output().ok_or_else(|| {
+            CoreError::InvalidParameter(format!("'{}' does not support multi-output execution", algorithm_id))
+        })?;
+        let min_input_bytes = algorithm.metadata().min_input_bytes;
+        if input_data.len() < min_input_bytes {
+            return Err(CoreError::InputTooSmall {
+                required: min_input_bytes,
+                actual: input_data.len(),
+            });
+        }
         
+        let mut outputs: Vec<(String, Vec<u8>)> = multi_output
+            .process_multi(input_data, &mut self.memory_manager)?
+            .into_iter()
+            .map(|(name, data)| (format!("{}/{}", execution_id, name), data))
+            .collect();
+        outputs.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        let mut keys = Vec::with_capacity(outputs.len());
+        for (key, data) in outputs {
+            self.memory_manager.allocate(&key, data.len())?.copy_from_slice(&data);
+            keys.push(key);
+        }
+        Ok(keys)
+    }
+    
+    /// Resolve an ID against registered algorithms, then built-ins
+    fn get_algorithm(&self, algorithm_id: &str) -> Option<Arc<dyn algorithm::Algorithm>> {
+        resolve(&self.registry, algorithm_id)
+    }
+}
+
+/// Resolve an ID against `registry`, then built-ins
+#[cfg(feature = "std")]
+fn resolve(registry: &algorithm::registry::AlgorithmRegistry, algorithm_id: &str) -> Option<Arc<dyn algorithm::Algorithm>> {
+    registry
+        .get(algorithm_id)
+        .or_else(|| algorithm::get_algorithm_by_id(algorithm_id).map(Arc::from))
+}
+
+/// Execute an algorithm from `registry` against `memory`, between the
+/// engine's preprocessors and postprocessors
+#[cfg(feature = "std")]
+fn execute_on(
+    registry: &algorithm::registry::AlgorithmRegistry,
+    log_levels: &logging::LogLevels,
+    hooks: &hooks::Hooks,
+    context: &algorithm::context::ContextSource,
+    memory: &mut memory::MemoryManager,
+    algorithm_id: &str,
+    input_data: &[u8],
+) -> Result<AlgorithmOutput, CoreError> {
+    // Implementation of algorithm execution
+    logging::algorithm_log!(log_levels, algorithm_id, log::Level::Info, "Executing algorithm: {}", algorithm_id);
+    
+    let input_data = hooks.preprocess(input_data)?;
+    let mut output = execute_with_fallback(registry, log_levels, context, memory, algorithm_id, &input_data)?;
+    output.data = hooks.postprocess(output.data)?;
+    Ok(output)
+}
+
+/// Execute an algorithm from `registry`, running its fallback if it has one
+/// and fails
+#[cfg(feature = "std")]
+fn execute_with_fallback(
+    registry: &algorithm::registry::AlgorithmRegistry,
+    log_levels: &logging::LogLevels,
+    context: &algorithm::context::ContextSource,
+    memory: &mut memory::MemoryManager,
+    algorithm_id: &str,
+    input_data: &[u8],
+) -> Result<AlgorithmOutput, CoreError> {
+    // Get algorithm from registry
+    let algorithm = match resolve(registry, algorithm_id) {
+        Some(algo) => algo,
+        None => return Err(CoreError::AlgorithmNotFound(algorithm_id.to_string())),
+    };
+    
+    let Some(fallback) = registry.fallback(algorithm_id) else {
         // Process the input data using the algorithm
-        algorithm.process(input_data, &mut self.memory_manager)
+        return run_annotated(algorithm.as_ref(), context, input_data, memory);
+    };
+    
+    let primary = panic::catch_unwind(AssertUnwindSafe(|| run_annotated(algorithm.as_ref(), context, input_data, memory)))
+        .unwrap_or_else(|payload| {
+            Err(CoreError::ProcessingFailed(format!(
+                "'{}' panicked: {}",
+                algorithm_id,
+                panic_message(payload.as_ref())
+            )))
+        });
+    match primary {
+        Ok(output) => Ok(output.with_attribute("served_by", "primary")),
+        Err(e) => {
+            logging::algorithm_log!(
+                log_levels,
+                algorithm_id,
+                log::Level::Warn,
+                "Algorithm {} failed, running fallback: {}",
+                algorithm_id,
+                e
+            );
+            Ok(run_annotated(fallback.as_ref(), context, input_data, memory)?
+                .with_attribute("served_by", "fallback")
+                .with_attribute("primary_error", e.to_string()))
+        }
+    }
+}
+
+/// Run an algorithm and attach the attributes it reports for the output
+#[cfg(feature = "std")]
+fn run_annotated(
+    algorithm: &dyn algorithm::Algorithm,
+    context: &algorithm::context::ContextSource,
+    input_data: &[u8],
+    memory: &mut memory::MemoryManager,
+) -> Result<AlgorithmOutput, CoreError> {
+    let mut output = AlgorithmOutput::new(run_algorithm(algorithm, context, input_data, memory)?);
+    let attributes = algorithm.output_attributes(input_data, &output.data, memory);
+    output.attributes.extend(attributes);
+    Ok(output)
+}
+
+/// Best-effort text of a panic payload
+#[cfg(feature = "std")]
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => payload
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_else(|| "unknown panic".to_string()),
+    }
+}
+
+/// Run an algorithm like `run_algorithm`, between `hooks`
+#[cfg(feature = "std")]
+fn run_hooked(
+    algorithm: &dyn algorithm::Algorithm,
+    hooks: &hooks::Hooks,
+    context: &algorithm::context::ContextSource,
+    input_data: &[u8],
+    memory: &mut memory::MemoryManager,
+) -> Result<Vec<u8>, CoreError> {
+    let input_data = hooks.preprocess(input_data)?;
+    hooks.postprocess(run_algorithm(algorithm, context, &input_data, memory)?)
+}
+
+/// Run an algorithm with an output buffer pre-sized from its metadata, in
+/// a fresh context from `context`
+#[cfg(feature = "std")]
+fn run_algorithm(
+    algorithm: &dyn algorithm::Algorithm,
+    context: &algorithm::context::ContextSource,
+    input_data: &[u8],
+    memory: &mut memory::MemoryManager,
+) -> Result<Vec<u8>, CoreError> {
+    let metadata = algorithm.metadata();
+    run_sized(algorithm, metadata.min_input_bytes, metadata.output_size_hint, context, input_data, memory)
+}
+
+/// An algorithm resolved once along with the metadata every run needs,
+/// for feeding it many inputs without looking either up again
+#[cfg(feature = "std")]
+struct Prepared {
+    algorithm: Arc<dyn algorithm::Algorithm>,
+    min_input_bytes: usize,
+    output_size_hint: algorithm::OutputSizeHint,
+}
+
+#[cfg(feature = "std")]
+impl Prepared {
+    fn resolve(registry: &algorithm::registry::AlgorithmRegistry, algorithm_id: &str) -> Result<Self, CoreError> {
+        let algorithm =
+            resolve(registry, algorithm_id).ok_or_else(|| CoreError::AlgorithmNotFound(algorithm_id.to_string()))?;
+        let metadata = algorithm.metadata();
+        Ok(Self {
+            algorithm,
+            min_input_bytes: metadata.min_input_bytes,
+            output_size_hint: metadata.output_size_hint,
+        })
     }
     
-    fn get_algorithm(&self, algorithm_id: &str) -> Option<Box<dyn algorithm::Algorithm>> {
-        algorithm::get_algorithm_by_id(algorithm_id)
+    /// Run on `input_data` between `hooks`, like `run_hooked`
+    fn run(
+        &self,
+        hooks: &hooks::Hooks,
+        context: &algorithm::context::ContextSource,
+        input_data: &[u8],
+        memory: &mut memory::MemoryManager,
+    ) -> Result<Vec<u8>, CoreError> {
+        let input_data = hooks.preprocess(input_data)?;
+        let output = run_sized(
+            self.algorithm.as_ref(),
+            self.min_input_bytes,
+            self.output_size_hint,
+            context,
+            &input_data,
+            memory,
+        )?;
+        hooks.postprocess(output)
+    }
+}
+
+/// Run an algorithm whose metadata the caller has already read, refusing
+/// inputs under `min_input_bytes` and sizing the output from `hint`
+#[cfg(feature = "std")]
+fn run_sized(
+    algorithm: &dyn algorithm::Algorithm,
+    min_input_bytes: usize,
+    hint: algorithm::OutputSizeHint,
+    context: &algorithm::context::ContextSource,
+    input_data: &[u8],
+    memory: &mut memory::MemoryManager,
+) -> Result<Vec<u8>, CoreError> {
+    if input_data.len() < min_input_bytes {
+        return Err(CoreError::InputTooSmall {
+            required: min_input_bytes,
+            actual: input_data.len(),
+        });
     }
+    let mut output = Vec::new();
+    // A hint too large to satisfy is ignored rather than aborting
+    let _ = output.try_reserve_exact(hint.capacity_for(input_data.len()));
+    context.run(|context| algorithm.process_in_context(input_data, &mut output, memory, context))?;
+    Ok(output)
 }
 
+#[cfg(feature = "std")]
 impl Default for CoreEngine {
     fn default() -> Self {
         Self::new()
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     
+    use crate::algorithm::{Algorithm, AlgorithmMetadata, OutputSizeHint};
+    use crate::memory::MemoryManager;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    
     #[test]
     fn test_core_engine_creation() {
-        let engine = CoreEngine::new();
+        let _engine = CoreEngine::new();
         // Assert that the engine is created successfully
     }
+    
+    #[test]
+    fn test_shutdown_releases_memory_and_devices() {
+        use crate::algorithm::builtins::PassThrough;
+        
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(PassThrough)).unwrap();
+        engine.memory_mut().allocate("frame", 64).unwrap();
+        engine.memory_mut().freeze("frame").unwrap();
+        let pinned = engine.memory_mut().acquire("frame").unwrap();
+        engine.memory().append_protected("log", &[1, 2]).unwrap();
+        engine.register_device(Box::new(NullDevice::new("motor")));
+        
+        // A referenced region outlives the shutdown until released
+        engine.shutdown();
+        assert_eq!(engine.memory().stats().total_bytes, 64);
+        drop(pinned);
+        engine.shutdown();
+        assert_eq!(engine.memory().stats().total_bytes, 0);
+        assert_eq!(engine.memory().read_protected("log").unwrap(), None);
+        assert!(matches!(
+            engine.execute_to_device(PassThrough::ID, &[], "motor"),
+            Err(CoreError::DeviceNotFound(_))
+        ));
+        
+        // Still usable, from empty memory
+        engine.memory_mut().allocate("frame", 8).unwrap();
+        assert_eq!(engine.execute_algorithm(PassThrough::ID, &[7]).unwrap(), vec![7]);
+    }
+    
+    #[test]
+    fn test_in_place_matches_out_of_place() {
+        use crate::algorithm::builtins::{Clamp, NanPolicy, Scale};
+        use crate::algorithm::samples;
+        
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(Scale::new(-2.5).unwrap())).unwrap();
+        engine.register(Box::new(Clamp::new(-3.0, 3.0, NanPolicy::Zero).unwrap())).unwrap();
+        let signal = samples::f32_to_bytes(&[1.0, -0.5, 2.0, f32::NAN]);
+        engine.memory_mut().write("signal", &signal).unwrap();
+        
+        let mut expected = signal.clone();
+        for id in ["scale", "clamp"] {
+            expected = engine.execute_algorithm(id, &expected).unwrap();
+            engine.execute_in_place(id, "signal").unwrap();
+        }
+        assert_eq!(engine.memory().read("signal"), Some(expected.as_slice()));
+        assert_eq!(
+            samples::f32_from_bytes(&expected).unwrap()[..3],
+            [-2.5, 1.25, -3.0]
+        );
+    }
+    
+    #[test]
+    fn test_in_place_rejects_missing_or_unsupported_regions() {
+        let mut engine = CoreEngine::new();
+        assert_eq!(
+            engine.execute_in_place("passthrough", "signal"),
+            Err(CoreError::InvalidParameter("'passthrough' does not support in-place execution".to_string()))
+        );
+        engine
+            .register(Box::new(algorithm::builtins::Scale::new(2.0).unwrap()))
+            .unwrap();
+        assert_eq!(
+            engine.execute_in_place("scale", "signal"),
+            Err(CoreError::MemoryKeyMissing("signal".to_string()))
+        );
+        
+        engine.memory_mut().write("signal", &[0; 6]).unwrap();
+        assert!(matches!(engine.execute_in_place("scale", "signal"), Err(CoreError::InvalidInput(_))));
+        assert_eq!(engine.memory().read("signal"), Some(&[0u8; 6][..]), "failed call must restore the region");
+        engine.memory_mut().set_readonly("signal", true).unwrap();
+        assert!(matches!(engine.execute_in_place("scale", "signal"), Err(CoreError::PermissionDenied(_))));
+    }
+    
+    #[test]
+    fn test_execute_on_region_writes_output_region() {
+        use crate::algorithm::builtins::Scale;
+        use crate::algorithm::samples;
+        
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(Scale::new(2.0).unwrap())).unwrap();
+        engine
+            .memory_mut()
+            .allocate("raw", 8)
+            .unwrap()
+            .copy_from_slice(&samples::f32_to_bytes(&[1.5, -4.0]));
+        engine.memory_mut().set_readonly("raw", true).unwrap();
+        engine.memory_mut().write("scaled", &[0; 64]).unwrap();
+        
+        engine.execute_algorithm_on_region(Scale::ID, "raw", "scaled").unwrap();
+        assert_eq!(engine.memory().read_typed::<f32>("scaled").unwrap(), vec![3.0, -8.0]);
+        assert_eq!(engine.memory().read_typed::<f32>("raw").unwrap(), vec![1.5, -4.0]);
+        
+        assert_eq!(
+            engine.execute_algorithm_on_region(Scale::ID, "missing", "scaled"),
+            Err(CoreError::MemoryKeyMissing("missing".to_string()))
+        );
+        // A failed execution leaves both regions in place
+        engine.memory_mut().write("odd", &[0; 3]).unwrap();
+        assert!(engine.execute_algorithm_on_region(Scale::ID, "odd", "scaled").is_err());
+        assert_eq!(engine.memory().read("odd"), Some(&[0u8; 3][..]));
+        assert_eq!(engine.memory().read("scaled").map(<[u8]>::len), Some(8));
+    }
+    
+    #[test]
+    fn test_multi_output_writes_each_named_region() {
+        use crate::algorithm::MultiOutputAlgorithm;
+        
+        /// Splits its input into its even- and odd-indexed bytes
+        struct Stereo;
+        
+        impl Algorithm for Stereo {
+            fn process(&self, input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+                Ok(input.to_vec())
+            }
+            
+            fn as_multi_output(&self) -> Option<&dyn MultiOutputAlgorithm> {
+                Some(self)
+            }
+            
+            fn id(&self) -> &str {
+                "stereo"
+            }
+            
+            fn metadata(&self) -> AlgorithmMetadata {
+                AlgorithmMetadata::default()
+            }
+        }
+        
+        impl MultiOutputAlgorithm for Stereo {
+            fn process_multi(&self, input: &[u8], _memory: &mut MemoryManager) -> Result<HashMap<String, Vec<u8>>, CoreError> {
+                Ok(HashMap::from([
+                    ("depth".to_string(), input.iter().step_by(2).copied().collect()),
+                    ("confidence".to_string(), input.iter().skip(1).step_by(2).copied().collect()),
+                ]))
+            }
+        }
+        
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(Stereo)).unwrap();
+        let keys = engine.execute_multi_output("stereo", "run7", &[1, 2, 3, 4, 5]).unwrap();
+        assert_eq!(keys, vec!["run7/confidence", "run7/depth"]);
+        assert_eq!(engine.memory().read("run7/depth"), Some(&[1u8, 3, 5][..]));
+        assert_eq!(engine.memory().read("run7/confidence"), Some(&[2u8, 4][..]));
+        
+        assert!(matches!(
+            engine.execute_multi_output("passthrough", "run8", &[1]),
+            Err(CoreError::InvalidParameter(_))
+        ));
+    }
+    
+    #[test]
+    fn test_capabilities_match_enabled_features() {
+        let capabilities = CoreEngine::capabilities();
+        assert_eq!(capabilities.fft, cfg!(feature = "fft"));
+        assert_eq!(capabilities.lz4, cfg!(feature = "lz4"));
+        assert_eq!(capabilities.yaml, cfg!(feature = "yaml"));
+        assert_eq!(capabilities.toml, cfg!(feature = "toml"));
+        assert_eq!(capabilities.wasm, cfg!(feature = "wasm"));
+        assert_eq!(capabilities.bench_utils, cfg!(feature = "bench-utils"));
+        assert_eq!(capabilities.otel, cfg!(feature = "otel"));
+        assert_eq!(capabilities.spill, cfg!(all(feature = "spill", not(target_arch = "wasm32"))));
+        assert_eq!(capabilities.python, cfg!(feature = "python-binding"));
+        assert_eq!(capabilities.xxhash, cfg!(feature = "xxhash"));
+        assert_eq!(capabilities.blake3, cfg!(feature = "blake3"));
+        assert_eq!(capabilities.cpu_time, cfg!(all(feature = "cpu-time", unix)));
+        assert_eq!(capabilities.gpio, cfg!(all(feature = "gpio", target_os = "linux")));
+        assert_eq!(capabilities.pwm, cfg!(all(feature = "pwm", target_os = "linux")));
+        assert_eq!(capabilities.serial, cfg!(all(feature = "serial", unix)));
+        assert_eq!(capabilities.tokio, cfg!(all(feature = "tokio", not(target_arch = "wasm32"))));
+        assert_eq!(capabilities.shared_mem, cfg!(all(feature = "shared-mem", unix)));
+        assert_eq!(capabilities.realtime, cfg!(all(feature = "realtime", target_os = "linux")));
+        assert_eq!(capabilities.can, cfg!(all(feature = "can", target_os = "linux")));
+        assert_eq!(capabilities.urdf, cfg!(feature = "urdf"));
+        assert_eq!(capabilities.i2c, cfg!(all(feature = "i2c", target_os = "linux")));
+        assert_eq!(capabilities.spi, cfg!(all(feature = "spi", target_os = "linux")));
+        assert_eq!(capabilities.rpc, cfg!(all(feature = "rpc", not(target_arch = "wasm32"))));
+        assert_eq!(capabilities.plugin, cfg!(all(feature = "plugin", unix)));
+        assert_eq!(capabilities.v4l2, cfg!(all(feature = "v4l2", target_os = "linux")));
+        assert_eq!(capabilities.sim, cfg!(feature = "sim"));
+        assert_eq!(capabilities.gpu, cfg!(all(feature = "gpu", not(target_arch = "wasm32"))));
+        assert_eq!(
+            capabilities.compression,
+            cfg!(all(feature = "compression", not(target_arch = "wasm32")))
+        );
+        
+        // The FFT flag agrees with what the built-in table can construct
+        assert_eq!(algorithm::builtins::by_id("fft").is_some(), capabilities.fft);
+    }
+    
+    #[test]
+    fn test_execute_builtin_by_id() {
+        let mut engine = CoreEngine::new();
+        assert_eq!(engine.execute_algorithm("passthrough", &[1, 2, 3]).unwrap(), vec![1, 2, 3]);
+        assert!(engine.execute_algorithm("missing", &[]).is_err());
+    }
+    
+    /// Fails, either by returning an error or by panicking
+    struct Broken {
+        panics: bool,
+    }
+    
+    impl Algorithm for Broken {
+        fn process(&self, _input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            if self.panics {
+                panic!("sensor exploded");
+            }
+            Err(CoreError::ProcessingFailed("no signal".to_string()))
+        }
+        
+        fn id(&self) -> &str {
+            "broken"
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata::default()
+        }
+    }
+    
+    fn clamp_to_zero() -> Box<dyn Algorithm> {
+        Box::new(crate::algorithm::builtins::Clamp::new(0.0, 0.0, Default::default()).unwrap())
+    }
+    
+    /// Sums its input, which is meaningless without at least one sample
+    struct Total;
+    
+    impl Algorithm for Total {
+        fn process(&self, input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            let sum: f32 = crate::algorithm::samples::f32_from_bytes(input)?.iter().sum();
+            Ok(sum.to_le_bytes().to_vec())
+        }
+        
+        fn id(&self) -> &str {
+            "total"
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata {
+                min_input_bytes: 4,
+                ..Default::default()
+            }
+        }
+    }
+    
+    #[test]
+    fn test_empty_input_to_elementwise_builtins() {
+        let mut engine = CoreEngine::new();
+        for (id, params) in [
+            ("passthrough", serde_json::Value::Null),
+            ("clamp", serde_json::json!({ "min": 0.0, "max": 1.0 })),
+            ("scale", serde_json::json!({ "factor": 2.0 })),
+            ("lookup_table", serde_json::json!({ "points": [[0.0, 1.0]] })),
+        ] {
+            engine
+                .register_configured(id, params, |config| crate::algorithm::builtins::create(id, config))
+                .unwrap();
+            assert_eq!(engine.execute_algorithm(id, &[]), Ok(Vec::new()), "{}", id);
+        }
+    }
+    
+    #[test]
+    fn test_empty_input_rejected_when_data_required() {
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(Total)).unwrap();
+        assert_eq!(
+            engine.execute_algorithm("total", &[]),
+            Err(CoreError::InputTooSmall { required: 4, actual: 0 })
+        );
+        assert_eq!(engine.execute_algorithm("total", &2.0f32.to_le_bytes()).unwrap(), 2.0f32.to_le_bytes());
+    }
+    
+    #[test]
+    fn test_register_configured_validates_up_front() {
+        use crate::algorithm::builtins;
+        
+        let mut engine = CoreEngine::new();
+        let bad = serde_json::json!({ "min": 1.0, "max": -1.0 });
+        let result = engine.register_configured("limits", bad, |config| builtins::create("clamp", config));
+        assert!(matches!(result, Err(CoreError::InvalidParameter(_))));
+        assert_eq!(
+            engine.execute_algorithm("limits", &[]),
+            Err(CoreError::AlgorithmNotFound("limits".to_string()))
+        );
+        
+        let good = serde_json::json!({ "min": -1.0, "max": 1.0 });
+        engine
+            .register_configured("limits", good, |config| builtins::create("clamp", config))
+            .unwrap();
+        let output = engine.execute_algorithm("limits", &5.0f32.to_le_bytes()).unwrap();
+        assert_eq!(output, 1.0f32.to_le_bytes());
+    }
+    
+    #[test]
+    fn test_unregister_frees_id_and_cache() {
+        use crate::algorithm::builtins::Scale;
+        
+        let mut engine = CoreEngine::new();
+        engine.register_pure(Scale::new(2.0).unwrap()).unwrap();
+        engine.enable_cache(Scale::ID, 4).unwrap();
+        engine.execute_algorithm(Scale::ID, &1.0f32.to_le_bytes()).unwrap();
+        assert_eq!(engine.cached_outputs(Scale::ID), 1);
+        assert_eq!(engine.list_algorithms()[0].0, Scale::ID);
+        
+        engine.unregister(Scale::ID).unwrap();
+        assert!(engine.list_algorithms().is_empty());
+        assert_eq!(engine.cached_outputs(Scale::ID), 0);
+        assert!(matches!(engine.unregister(Scale::ID), Err(CoreError::AlgorithmNotFound(_))));
+        
+        engine.register(Box::new(Scale::new(3.0).unwrap())).unwrap();
+        assert_eq!(engine.execute_algorithm(Scale::ID, &1.0f32.to_le_bytes()).unwrap(), 3.0f32.to_le_bytes());
+    }
+    
+    #[test]
+    fn test_fallback_unused_when_primary_succeeds() {
+        let mut engine = CoreEngine::new();
+        engine
+            .register_with_fallback("sanitize", || Box::new(crate::algorithm::builtins::PassThrough), clamp_to_zero)
+            .unwrap();
+        
+        let output = engine.execute("sanitize", &1.0f32.to_le_bytes()).unwrap();
+        assert_eq!(output.data, 1.0f32.to_le_bytes());
+        assert_eq!(output.attributes["served_by"], "primary");
+    }
+    
+    #[test]
+    fn test_fallback_serves_when_primary_fails() {
+        for panics in [false, true] {
+            let mut engine = CoreEngine::new();
+            engine
+                .register_with_fallback("sanitize", move || Box::new(Broken { panics }), clamp_to_zero)
+                .unwrap();
+            
+            let output = engine.execute("sanitize", &1.0f32.to_le_bytes()).unwrap();
+            assert_eq!(output.data, 0.0f32.to_le_bytes());
+            assert_eq!(output.attributes["served_by"], "fallback");
+            assert!(output.attributes["primary_error"].contains(if panics { "sensor exploded" } else { "no signal" }));
+            
+            // The plain byte API is served by the fallback too
+            assert_eq!(engine.execute_algorithm("sanitize", &1.0f32.to_le_bytes()).unwrap(), 0.0f32.to_le_bytes());
+        }
+    }
+    
+    /// Copies its input byte by byte, counting how often the output reallocates
+    struct ByteCopy {
+        hint: OutputSizeHint,
+        reallocations: AtomicUsize,
+    }
+    
+    impl Algorithm for ByteCopy {
+        fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            let mut output = Vec::new();
+            self.process_into(input, &mut output, memory)?;
+            Ok(output)
+        }
+        
+        fn process_into(&self, input: &[u8], output: &mut Vec<u8>, _memory: &mut MemoryManager) -> Result<(), CoreError> {
+            for &byte in input {
+                let capacity = output.capacity();
+                output.push(byte);
+                if output.capacity() != capacity {
+                    self.reallocations.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            Ok(())
+        }
+        
+        fn id(&self) -> &str {
+            "byte_copy"
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata {
+                name: "Byte Copy".to_string(),
+                version: "1.0.0".to_string(),
+                description: "Copies input to output".to_string(),
+                output_size_hint: self.hint,
+                ..Default::default()
+            }
+        }
+    }
+    
+    fn reallocations_with(hint: OutputSizeHint, input: &[u8]) -> usize {
+        let algorithm = ByteCopy { hint, reallocations: AtomicUsize::new(0) };
+        let context = algorithm::context::ContextSource::new(Arc::new(SystemClock::new()), None);
+        let output = run_algorithm(&algorithm, &context, input, &mut memory::MemoryManager::new()).unwrap();
+        assert_eq!(output, input);
+        algorithm.reallocations.load(Ordering::Relaxed)
+    }
+    
+    #[test]
+    fn test_output_size_hint_avoids_reallocations() {
+        let input: Vec<u8> = (0..64 * 1024).map(|i| i as u8).collect();
+        
+        let presized = reallocations_with(OutputSizeHint::SameAsInput, &input);
+        let unsized_ = reallocations_with(OutputSizeHint::Unknown, &input);
+        
+        assert_eq!(presized, 0);
+        assert!(unsized_ > presized, "expected growth without a hint, got {}", unsized_);
+    }
+    
+    #[test]
+    fn test_wrong_output_size_hint_only_resizes() {
+        let input = vec![7u8; 4096];
+        
+        // Too small and absurdly large hints still produce the right output
+        assert!(reallocations_with(OutputSizeHint::Fixed(16), &input) > 0);
+        reallocations_with(OutputSizeHint::Ratio(0.5), &input);
+        reallocations_with(OutputSizeHint::Fixed(usize::MAX), &input);
+    }
 }
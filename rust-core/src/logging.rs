@@ -0,0 +1,123 @@
+//! Per-algorithm filtering of the engine's own log statements
+
+use log::{Level, LevelFilter};
+use std::collections::HashMap;
+
+use crate::CoreEngine;
+
+/// Most verbose level the engine logs at for each algorithm
+///
+/// This filters before the global logger sees a record, so it can only
+/// quieten an algorithm further than the logger's own filter, never make it
+/// louder.
+#[derive(Clone, Debug)]
+pub(crate) struct LogLevels {
+    default: LevelFilter,
+    overrides: HashMap<String, LevelFilter>,
+}
+
+impl Default for LogLevels {
+    fn default() -> Self {
+        Self {
+            default: LevelFilter::Trace,
+            overrides: HashMap::new(),
+        }
+    }
+}
+
+impl LogLevels {
+    /// Whether the engine should log a `level` record about `algorithm_id`
+    pub(crate) fn enabled(&self, algorithm_id: &str, level: Level) -> bool {
+        level <= *self.overrides.get(algorithm_id).unwrap_or(&self.default)
+    }
+}
+
+/// Log through the `log` crate if `LogLevels` allows it for the algorithm
+macro_rules! algorithm_log {
+    ($levels:expr, $algorithm_id:expr, $level:expr, $($arg:tt)+) => {
+        if $levels.enabled($algorithm_id, $level) {
+            log::log!($level, $($arg)+);
+        }
+    };
+}
+
+pub(crate) use algorithm_log;
+
+impl CoreEngine {
+    /// Limit the engine's log statements about `algorithm_id` to `level`
+    /// and above, e.g. `LevelFilter::Off` to silence a noisy algorithm
+    ///
+    /// Applies to the engine's execution, fallback and deadline messages,
+    /// not to anything the algorithm logs itself.
+    pub fn set_algorithm_log_level(&mut self, algorithm_id: &str, level: LevelFilter) {
+        self.log_levels.overrides.insert(algorithm_id.to_string(), level);
+    }
+    
+    /// Return `algorithm_id` to the default log level
+    pub fn clear_algorithm_log_level(&mut self, algorithm_id: &str) {
+        self.log_levels.overrides.remove(algorithm_id);
+    }
+    
+    /// Set the log level for algorithms without an override
+    ///
+    /// Defaults to `LevelFilter::Trace`, leaving filtering to the logger.
+    pub fn set_default_log_level(&mut self, level: LevelFilter) {
+        self.log_levels.default = level;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::builtins::PassThrough;
+    use log::{Log, Metadata, Record};
+    use std::sync::Mutex;
+    
+    /// Keeps every message logged in the test binary
+    struct Capture(Mutex<Vec<String>>);
+    
+    impl Log for Capture {
+        fn enabled(&self, _metadata: &Metadata) -> bool {
+            true
+        }
+        
+        fn log(&self, record: &Record) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+        
+        fn flush(&self) {}
+    }
+    
+    static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+    
+    fn lines_mentioning(id: &str) -> usize {
+        CAPTURE.0.lock().unwrap().iter().filter(|line| line.contains(id)).count()
+    }
+    
+    #[test]
+    fn test_silenced_algorithm_emits_no_engine_logs() {
+        let _ = log::set_logger(&CAPTURE);
+        log::set_max_level(LevelFilter::Trace);
+        
+        let mut engine = CoreEngine::new();
+        engine.registry.register_as("noisy_logging_test", Box::new(PassThrough)).unwrap();
+        engine.registry.register_as("quiet_logging_test", Box::new(PassThrough)).unwrap();
+        engine.set_algorithm_log_level("noisy_logging_test", LevelFilter::Off);
+        engine.set_default_log_level(LevelFilter::Info);
+        
+        for _ in 0..3 {
+            engine.execute_algorithm("noisy_logging_test", &[1]).unwrap();
+            engine.execute_algorithm("quiet_logging_test", &[1]).unwrap();
+        }
+        assert_eq!(lines_mentioning("noisy_logging_test"), 0);
+        assert_eq!(lines_mentioning("quiet_logging_test"), 3);
+        
+        engine.clear_algorithm_log_level("noisy_logging_test");
+        engine.execute_algorithm("noisy_logging_test", &[1]).unwrap();
+        assert_eq!(lines_mentioning("noisy_logging_test"), 1);
+        
+        engine.set_default_log_level(LevelFilter::Warn);
+        engine.execute_algorithm("quiet_logging_test", &[1]).unwrap();
+        assert_eq!(lines_mentioning("quiet_logging_test"), 3);
+    }
+}
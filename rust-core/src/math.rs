@@ -0,0 +1,453 @@
+//! Vectorized kernels for algorithm inner loops
+//!
+//! Each kernel checks at runtime for AVX and FMA on x86_64 and uses them
+//! when present, falling back to the portable versions in [`scalar`]
+//! otherwise. Quaternion products and point rotation use SSE, which every
+//! x86_64 CPU has. Results match the scalar versions up to floating-point
+//! summation order.
+//!
+//! The module only needs `core` and `alloc`. Without the `std` feature
+//! there is no runtime detection, so the AVX paths are taken only when the
+//! build enables those target features.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Whether the running CPU takes the AVX and FMA paths
+pub fn simd_available() -> bool {
+    #[cfg(all(target_arch = "x86_64", feature = "std"))]
+    {
+        std::is_x86_feature_detected!("avx") && std::is_x86_feature_detected!("fma")
+    }
+    #[cfg(all(target_arch = "x86_64", not(feature = "std")))]
+    {
+        cfg!(all(target_feature = "avx", target_feature = "fma"))
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        false
+    }
+}
+
+/// Sum of the products of paired elements, up to the shorter length
+pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+    #[cfg(target_arch = "x86_64")]
+    if simd_available() {
+        // SAFETY: AVX and FMA were detected, or enabled for the build, just
+        // above.
+        return unsafe { avx::dot(a, b) };
+    }
+    scalar::dot(a, b)
+}
+
+/// Add `alpha * x` to `y`, up to the shorter length
+pub fn axpy(alpha: f32, x: &[f32], y: &mut [f32]) {
+    #[cfg(target_arch = "x86_64")]
+    if simd_available() {
+        // SAFETY: AVX and FMA were detected, or enabled for the build, just
+        // above.
+        return unsafe { avx::axpy(alpha, x, y) };
+    }
+    scalar::axpy(alpha, x, y)
+}
+
+/// Product of a row-major `rows x inner` and `inner x cols` matrix
+pub fn mat_mul(lhs: &[f32], rhs: &[f32], rows: usize, inner: usize, cols: usize) -> Vec<f32> {
+    let mut product = vec![0.0f32; rows * cols];
+    for (lhs_row, product_row) in lhs.chunks_exact(inner.max(1)).zip(product.chunks_exact_mut(cols.max(1))) {
+        for (&a, rhs_row) in lhs_row.iter().zip(rhs.chunks_exact(cols.max(1))) {
+            axpy(a, rhs_row, product_row);
+        }
+    }
+    product
+}
+
+/// `dot` for `f64`
+pub fn dot_f64(a: &[f64], b: &[f64]) -> f64 {
+    #[cfg(target_arch = "x86_64")]
+    if simd_available() {
+        // SAFETY: AVX and FMA were detected, or enabled for the build, just
+        // above.
+        return unsafe { avx::dot_f64(a, b) };
+    }
+    scalar::dot_f64(a, b)
+}
+
+/// `axpy` for `f64`
+pub fn axpy_f64(alpha: f64, x: &[f64], y: &mut [f64]) {
+    #[cfg(target_arch = "x86_64")]
+    if simd_available() {
+        // SAFETY: AVX and FMA were detected, or enabled for the build, just
+        // above.
+        return unsafe { avx::axpy_f64(alpha, x, y) };
+    }
+    scalar::axpy_f64(alpha, x, y)
+}
+
+/// `mat_mul` for `f64`
+pub fn mat_mul_f64(lhs: &[f64], rhs: &[f64], rows: usize, inner: usize, cols: usize) -> Vec<f64> {
+    let mut product = vec![0.0f64; rows * cols];
+    mat_mul_f64_into(lhs, rhs, rows, inner, cols, &mut product);
+    product
+}
+
+/// Overwrite `product` with the product of a row-major `rows x inner` and
+/// `inner x cols` matrix, without allocating
+///
+/// Only the first `rows * cols` elements of `product` are written.
+pub fn mat_mul_f64_into(lhs: &[f64], rhs: &[f64], rows: usize, inner: usize, cols: usize, product: &mut [f64]) {
+    let len = (rows * cols).min(product.len());
+    let product = &mut product[..len];
+    product.fill(0.0);
+    for (lhs_row, product_row) in lhs.chunks_exact(inner.max(1)).zip(product.chunks_exact_mut(cols.max(1))) {
+        for (&a, rhs_row) in lhs_row.iter().zip(rhs.chunks_exact(cols.max(1))) {
+            axpy_f64(a, rhs_row, product_row);
+        }
+    }
+}
+
+/// Product of two 3x3 matrices, such as rotations
+pub fn mat3_mul_f64(a: &[[f64; 3]; 3], b: &[[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    let mut product = [[0.0; 3]; 3];
+    mat_mul_f64_into(a.as_flattened(), b.as_flattened(), 3, 3, 3, product.as_flattened_mut());
+    product
+}
+
+/// Write the transpose of a row-major `rows x cols` matrix into
+/// `transposed`, as a `cols x rows` matrix
+pub fn transpose_f64_into(matrix: &[f64], rows: usize, cols: usize, transposed: &mut [f64]) {
+    for (row, values) in matrix.chunks_exact(cols.max(1)).take(rows).enumerate() {
+        for (col, &value) in values.iter().enumerate() {
+            transposed[col * rows + row] = value;
+        }
+    }
+}
+
+/// Transpose of a 3x3 matrix
+pub fn transpose3_f64(m: &[[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    let mut transposed = [[0.0; 3]; 3];
+    transpose_f64_into(m.as_flattened(), 3, 3, transposed.as_flattened_mut());
+    transposed
+}
+
+/// Correlation of `signal` against `reference` at every lag from
+/// `1 - reference.len()` to `signal.len() - 1`
+pub fn correlate(signal: &[f32], reference: &[f32]) -> Vec<f32> {
+    let offset = reference.len().saturating_sub(1);
+    (0..(signal.len() + reference.len()).saturating_sub(1))
+        .map(|index| {
+            // lag = index - offset; pair signal[n + lag] with reference[n]
+            let first = offset.saturating_sub(index);
+            let last = reference.len().min((signal.len() + offset).saturating_sub(index));
+            dot(&signal[first + index - offset..last + index - offset], &reference[first..last])
+        })
+        .collect()
+}
+
+/// Full convolution of `signal` with `kernel`, `signal.len() +
+/// kernel.len() - 1` samples long
+pub fn convolve(signal: &[f32], kernel: &[f32]) -> Vec<f32> {
+    let reversed: Vec<f32> = kernel.iter().rev().copied().collect();
+    correlate(signal, &reversed)
+}
+
+/// Hamilton product `a * b` of quaternions given as `(w, x, y, z)`
+pub fn quaternion_multiply(a: [f32; 4], b: [f32; 4]) -> [f32; 4] {
+    #[cfg(target_arch = "x86_64")]
+    {
+        // SAFETY: SSE is part of the x86_64 baseline.
+        unsafe { sse::quaternion_multiply(a, b) }
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        scalar::quaternion_multiply(a, b)
+    }
+}
+
+/// Apply a row-major 3x3 `matrix` to consecutive `(x, y, z)` triples,
+/// appending the results to `output`
+///
+/// A trailing partial triple is ignored.
+pub fn rotate_points(matrix: &[[f32; 3]; 3], points: &[f32], output: &mut Vec<f32>) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        // SAFETY: SSE is part of the x86_64 baseline.
+        unsafe { sse::rotate_points(matrix, points, output) }
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        scalar::rotate_points(matrix, points, output)
+    }
+}
+
+/// Portable versions of the kernels, for targets without SIMD support and
+/// for comparison
+pub mod scalar {
+    use alloc::vec;
+    use alloc::vec::Vec;
+    
+    /// Sum of the products of paired elements, up to the shorter length
+    pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| x * y).sum()
+    }
+    
+    /// Add `alpha * x` to `y`, up to the shorter length
+    pub fn axpy(alpha: f32, x: &[f32], y: &mut [f32]) {
+        for (y, &x) in y.iter_mut().zip(x) {
+            *y += alpha * x;
+        }
+    }
+    
+    /// Product of a row-major `rows x inner` and `inner x cols` matrix
+    pub fn mat_mul(lhs: &[f32], rhs: &[f32], rows: usize, inner: usize, cols: usize) -> Vec<f32> {
+        let mut product = vec![0.0f32; rows * cols];
+        for (lhs_row, product_row) in lhs.chunks_exact(inner.max(1)).zip(product.chunks_exact_mut(cols.max(1))) {
+            for (&a, rhs_row) in lhs_row.iter().zip(rhs.chunks_exact(cols.max(1))) {
+                axpy(a, rhs_row, product_row);
+            }
+        }
+        product
+    }
+    
+    /// `dot` for `f64`
+    pub fn dot_f64(a: &[f64], b: &[f64]) -> f64 {
+        a.iter().zip(b).map(|(x, y)| x * y).sum()
+    }
+    
+    /// `axpy` for `f64`
+    pub fn axpy_f64(alpha: f64, x: &[f64], y: &mut [f64]) {
+        for (y, &x) in y.iter_mut().zip(x) {
+            *y += alpha * x;
+        }
+    }
+    
+    /// `mat_mul` for `f64`
+    pub fn mat_mul_f64(lhs: &[f64], rhs: &[f64], rows: usize, inner: usize, cols: usize) -> Vec<f64> {
+        let mut product = vec![0.0f64; rows * cols];
+        for (lhs_row, product_row) in lhs.chunks_exact(inner.max(1)).zip(product.chunks_exact_mut(cols.max(1))) {
+            for (&a, rhs_row) in lhs_row.iter().zip(rhs.chunks_exact(cols.max(1))) {
+                axpy_f64(a, rhs_row, product_row);
+            }
+        }
+        product
+    }
+    
+    /// Hamilton product `a * b` of quaternions given as `(w, x, y, z)`
+    pub fn quaternion_multiply([aw, ax, ay, az]: [f32; 4], [bw, bx, by, bz]: [f32; 4]) -> [f32; 4] {
+        [
+            aw * bw - ax * bx - ay * by - az * bz,
+            aw * bx + ax * bw + ay * bz - az * by,
+            aw * by - ax * bz + ay * bw + az * bx,
+            aw * bz + ax * by - ay * bx + az * bw,
+        ]
+    }
+    
+    /// Apply a row-major 3x3 `matrix` to consecutive `(x, y, z)` triples,
+    /// appending the results to `output`
+    pub fn rotate_points(matrix: &[[f32; 3]; 3], points: &[f32], output: &mut Vec<f32>) {
+        output.reserve(points.len() - points.len() % 3);
+        for point in points.chunks_exact(3) {
+            output.extend(matrix.map(|row| row[0] * point[0] + row[1] * point[1] + row[2] * point[2]));
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx {
+    use core::arch::x86_64::*;
+    
+    const LANES: usize = 8;
+    
+    #[target_feature(enable = "avx,fma")]
+    fn horizontal_sum(v: __m256) -> f32 {
+        let quad = _mm_add_ps(_mm256_castps256_ps128(v), _mm256_extractf128_ps::<1>(v));
+        let pair = _mm_add_ps(quad, _mm_movehl_ps(quad, quad));
+        _mm_cvtss_f32(_mm_add_ss(pair, _mm_movehdup_ps(pair)))
+    }
+    
+    #[target_feature(enable = "avx,fma")]
+    pub(super) fn dot(a: &[f32], b: &[f32]) -> f32 {
+        let len = a.len().min(b.len());
+        let (a, b) = (&a[..len], &b[..len]);
+        let mut sum = _mm256_setzero_ps();
+        for (x, y) in a.chunks_exact(LANES).zip(b.chunks_exact(LANES)) {
+            // SAFETY: both chunks hold exactly `LANES` floats.
+            let (x, y) = unsafe { (_mm256_loadu_ps(x.as_ptr()), _mm256_loadu_ps(y.as_ptr())) };
+            sum = _mm256_fmadd_ps(x, y, sum);
+        }
+        let tail = len - len % LANES;
+        horizontal_sum(sum) + super::scalar::dot(&a[tail..], &b[tail..])
+    }
+    
+    #[target_feature(enable = "avx,fma")]
+    pub(super) fn axpy(alpha: f32, x: &[f32], y: &mut [f32]) {
+        let len = x.len().min(y.len());
+        let (x, y) = (&x[..len], &mut y[..len]);
+        let scale = _mm256_set1_ps(alpha);
+        for (x, y) in x.chunks_exact(LANES).zip(y.chunks_exact_mut(LANES)) {
+            // SAFETY: both chunks hold exactly `LANES` floats, and `y` is
+            // exclusively borrowed.
+            unsafe {
+                let sum = _mm256_fmadd_ps(scale, _mm256_loadu_ps(x.as_ptr()), _mm256_loadu_ps(y.as_ptr()));
+                _mm256_storeu_ps(y.as_mut_ptr(), sum);
+            }
+        }
+        let tail = len - len % LANES;
+        super::scalar::axpy(alpha, &x[tail..], &mut y[tail..]);
+    }
+    
+    const LANES_F64: usize = 4;
+    
+    #[target_feature(enable = "avx,fma")]
+    fn horizontal_sum_f64(v: __m256d) -> f64 {
+        let pair = _mm_add_pd(_mm256_castpd256_pd128(v), _mm256_extractf128_pd::<1>(v));
+        _mm_cvtsd_f64(_mm_add_sd(pair, _mm_unpackhi_pd(pair, pair)))
+    }
+    
+    #[target_feature(enable = "avx,fma")]
+    pub(super) fn dot_f64(a: &[f64], b: &[f64]) -> f64 {
+        let len = a.len().min(b.len());
+        let (a, b) = (&a[..len], &b[..len]);
+        let mut sum = _mm256_setzero_pd();
+        for (x, y) in a.chunks_exact(LANES_F64).zip(b.chunks_exact(LANES_F64)) {
+            // SAFETY: both chunks hold exactly `LANES_F64` doubles.
+            let (x, y) = unsafe { (_mm256_loadu_pd(x.as_ptr()), _mm256_loadu_pd(y.as_ptr())) };
+            sum = _mm256_fmadd_pd(x, y, sum);
+        }
+        let tail = len - len % LANES_F64;
+        horizontal_sum_f64(sum) + super::scalar::dot_f64(&a[tail..], &b[tail..])
+    }
+    
+    #[target_feature(enable = "avx,fma")]
+    pub(super) fn axpy_f64(alpha: f64, x: &[f64], y: &mut [f64]) {
+        let len = x.len().min(y.len());
+        let (x, y) = (&x[..len], &mut y[..len]);
+        let scale = _mm256_set1_pd(alpha);
+        for (x, y) in x.chunks_exact(LANES_F64).zip(y.chunks_exact_mut(LANES_F64)) {
+            // SAFETY: both chunks hold exactly `LANES_F64` doubles, and `y`
+            // is exclusively borrowed.
+            unsafe {
+                let sum = _mm256_fmadd_pd(scale, _mm256_loadu_pd(x.as_ptr()), _mm256_loadu_pd(y.as_ptr()));
+                _mm256_storeu_pd(y.as_mut_ptr(), sum);
+            }
+        }
+        let tail = len - len % LANES_F64;
+        super::scalar::axpy_f64(alpha, &x[tail..], &mut y[tail..]);
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod sse {
+    use alloc::vec::Vec;
+    use core::arch::x86_64::*;
+    
+    #[target_feature(enable = "sse")]
+    fn to_array(v: __m128) -> [f32; 4] {
+        let mut lanes = [0.0f32; 4];
+        // SAFETY: `lanes` has room for the four floats stored.
+        unsafe { _mm_storeu_ps(lanes.as_mut_ptr(), v) };
+        lanes
+    }
+    
+    #[target_feature(enable = "sse")]
+    pub(super) fn quaternion_multiply([aw, ax, ay, az]: [f32; 4], [bw, bx, by, bz]: [f32; 4]) -> [f32; 4] {
+        // Each of `a`'s components scales a signed permutation of `b`; each
+        // two-bit field of a shuffle mask, lowest first, picks a lane of `b`
+        let b = _mm_setr_ps(bw, bx, by, bz);
+        let w = _mm_mul_ps(_mm_set1_ps(aw), b);
+        let x = _mm_mul_ps(
+            _mm_mul_ps(_mm_set1_ps(ax), _mm_shuffle_ps::<0b10_11_00_01>(b, b)),
+            _mm_setr_ps(-1.0, 1.0, -1.0, 1.0),
+        );
+        let y = _mm_mul_ps(
+            _mm_mul_ps(_mm_set1_ps(ay), _mm_shuffle_ps::<0b01_00_11_10>(b, b)),
+            _mm_setr_ps(-1.0, 1.0, 1.0, -1.0),
+        );
+        let z = _mm_mul_ps(
+            _mm_mul_ps(_mm_set1_ps(az), _mm_shuffle_ps::<0b00_01_10_11>(b, b)),
+            _mm_setr_ps(-1.0, -1.0, 1.0, 1.0),
+        );
+        to_array(_mm_add_ps(_mm_add_ps(w, x), _mm_add_ps(y, z)))
+    }
+    
+    #[target_feature(enable = "sse")]
+    pub(super) fn rotate_points(matrix: &[[f32; 3]; 3], points: &[f32], output: &mut Vec<f32>) {
+        // The rotated point is a weighted sum of the matrix columns
+        let columns = [0, 1, 2].map(|c| _mm_setr_ps(matrix[0][c], matrix[1][c], matrix[2][c], 0.0));
+        output.reserve(points.len() - points.len() % 3);
+        for point in points.chunks_exact(3) {
+            let rotated = _mm_add_ps(
+                _mm_add_ps(
+                    _mm_mul_ps(columns[0], _mm_set1_ps(point[0])),
+                    _mm_mul_ps(columns[1], _mm_set1_ps(point[1])),
+                ),
+                _mm_mul_ps(columns[2], _mm_set1_ps(point[2])),
+            );
+            output.extend_from_slice(&to_array(rotated)[..3]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn ramp(len: usize, step: f32) -> Vec<f32> {
+        (0..len).map(|i| ((i as f32) * step).sin()).collect()
+    }
+    
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() <= 1e-4 * e.abs().max(1.0), "{} != {}", a, e);
+        }
+    }
+    
+    #[test]
+    fn test_kernels_match_scalar() {
+        // Lengths straddle the vector width so the tail paths run too
+        for len in [0, 1, 7, 8, 9, 33] {
+            let (a, b) = (ramp(len, 0.3), ramp(len, 0.7));
+            assert_close(&[dot(&a, &b)], &[scalar::dot(&a, &b)]);
+        }
+        let (lhs, rhs) = (ramp(5 * 11, 0.2), ramp(11 * 13, 0.5));
+        assert_close(&mat_mul(&lhs, &rhs, 5, 11, 13), &scalar::mat_mul(&lhs, &rhs, 5, 11, 13));
+        
+        let (q, r) = ([0.5, -0.1, 0.7, 0.2], [0.3, 0.9, -0.4, 0.1]);
+        assert_close(&quaternion_multiply(q, r), &scalar::quaternion_multiply(q, r));
+        let matrix = [[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]];
+        let points = ramp(31, 0.4);
+        let (mut rotated, mut expected) = (Vec::new(), Vec::new());
+        rotate_points(&matrix, &points, &mut rotated);
+        scalar::rotate_points(&matrix, &points, &mut expected);
+        assert_eq!(rotated.len(), 30);
+        assert_close(&rotated, &expected);
+    }
+    
+    #[test]
+    fn test_f64_kernels_match_scalar() {
+        let ramp = |len: usize, step: f64| -> Vec<f64> { (0..len).map(|i| (i as f64 * step).sin()).collect() };
+        let close = |a: f64, e: f64| (a - e).abs() <= 1e-12 * e.abs().max(1.0);
+        for len in [0, 1, 3, 4, 5, 17] {
+            let (a, b) = (ramp(len, 0.3), ramp(len, 0.7));
+            assert!(close(dot_f64(&a, &b), scalar::dot_f64(&a, &b)));
+        }
+        let (lhs, rhs) = (ramp(5 * 7, 0.2), ramp(7 * 9, 0.5));
+        let product = mat_mul_f64(&lhs, &rhs, 5, 7, 9);
+        let expected = scalar::mat_mul_f64(&lhs, &rhs, 5, 7, 9);
+        assert!(product.iter().zip(&expected).all(|(&a, &e)| close(a, e)));
+        
+        let m = [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 10.0]];
+        assert_eq!(transpose3_f64(&m), [[1.0, 4.0, 7.0], [2.0, 5.0, 8.0], [3.0, 6.0, 10.0]]);
+        let identity = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        assert_eq!(mat3_mul_f64(&m, &identity), m);
+        assert_eq!(mat3_mul_f64(&identity, &m), m);
+    }
+    
+    #[test]
+    fn test_convolve_and_correlate() {
+        assert_eq!(convolve(&[1.0, 2.0, 3.0], &[1.0, -1.0]), vec![1.0, 1.0, 1.0, -3.0]);
+        assert_eq!(correlate(&[1.0, 2.0, 3.0], &[1.0, -1.0]), vec![-1.0, -1.0, -1.0, 3.0]);
+        assert!(convolve(&[], &[1.0]).is_empty());
+    }
+}
@@ -0,0 +1,251 @@
+//! Storage behind the shared regions of a `MemoryManager`
+
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::compat::HashMap;
+
+/// Key-value store holding a manager's shared regions
+///
+/// `MemoryManager` keeps its bookkeeping (references, tags, read-only flags,
+/// pooling) itself and only asks the backend to store region bytes, so a
+/// backend can place them anywhere another party can see them, such as a
+/// shared-memory segment. The trait is object-safe; managers hold a
+/// `Box<dyn MemoryBackend>`.
+pub trait MemoryBackend: Send + Sync {
+    /// Bytes of a region
+    fn get(&self, key: &str) -> Option<&[u8]>;
+    
+    /// Bytes of a region, for in-place writes
+    fn get_mut(&mut self, key: &str) -> Option<&mut [u8]>;
+    
+    /// Store a region, returning the buffer it replaced
+    fn put(&mut self, key: &str, data: Vec<u8>) -> Option<Vec<u8>>;
+    
+    /// Remove a region, returning its buffer
+    fn remove(&mut self, key: &str) -> Option<Vec<u8>>;
+    
+    /// All regions, in no particular order
+    fn iter(&self) -> Box<dyn Iterator<Item = (&str, &[u8])> + '_>;
+    
+    /// Number of regions
+    fn len(&self) -> usize;
+    
+    /// Whether no regions are stored
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    
+    /// Whether a region exists
+    fn contains(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+    
+    /// Grow a region by `data`, returning `false` if it does not exist
+    ///
+    /// The default replaces the whole buffer; backends that can grow a
+    /// region in place should override it.
+    fn extend(&mut self, key: &str, data: &[u8]) -> bool {
+        let Some(mut buffer) = self.remove(key) else {
+            return false;
+        };
+        buffer.extend_from_slice(data);
+        self.put(key, buffer);
+        true
+    }
+    
+    /// Publish in-place writes made through `get_mut` to other parties
+    /// sharing the regions
+    fn flush(&mut self) {}
+    
+    /// Make room for `additional` more regions
+    fn reserve(&mut self, _additional: usize) {}
+    
+    /// Release spare capacity, including each region's own when `buffers`
+    fn shrink_to_fit(&mut self, _buffers: bool) {}
+}
+
+/// Default backend keeping regions in a process-local `HashMap`
+#[derive(Clone, Debug, Default)]
+pub struct HeapBackend {
+    regions: HashMap<String, Vec<u8>>,
+}
+
+impl HeapBackend {
+    /// Create an empty backend
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl MemoryBackend for HeapBackend {
+    fn get(&self, key: &str) -> Option<&[u8]> {
+        self.regions.get(key).map(Vec::as_slice)
+    }
+    
+    fn get_mut(&mut self, key: &str) -> Option<&mut [u8]> {
+        self.regions.get_mut(key).map(Vec::as_mut_slice)
+    }
+    
+    fn put(&mut self, key: &str, data: Vec<u8>) -> Option<Vec<u8>> {
+        self.regions.insert(key.to_string(), data)
+    }
+    
+    fn remove(&mut self, key: &str) -> Option<Vec<u8>> {
+        self.regions.remove(key)
+    }
+    
+    fn iter(&self) -> Box<dyn Iterator<Item = (&str, &[u8])> + '_> {
+        Box::new(self.regions.iter().map(|(key, data)| (key.as_str(), data.as_slice())))
+    }
+    
+    fn len(&self) -> usize {
+        self.regions.len()
+    }
+    
+    fn extend(&mut self, key: &str, data: &[u8]) -> bool {
+        match self.regions.get_mut(key) {
+            Some(buffer) => {
+                buffer.extend_from_slice(data);
+                true
+            }
+            None => false,
+        }
+    }
+    
+    fn reserve(&mut self, additional: usize) {
+        self.regions.reserve(additional);
+    }
+    
+    fn shrink_to_fit(&mut self, buffers: bool) {
+        if buffers {
+            self.regions.values_mut().for_each(Vec::shrink_to_fit);
+        }
+        self.regions.shrink_to_fit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CoreError;
+    use crate::memory::{MemoryManager, RegionType};
+    use std::collections::BTreeMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    
+    /// Ordered backend relying on the trait defaults, counting stores and
+    /// reserved regions
+    #[derive(Default)]
+    struct CountingBackend {
+        regions: BTreeMap<String, Vec<u8>>,
+        puts: Arc<AtomicUsize>,
+        reserved: Arc<AtomicUsize>,
+    }
+    
+    impl MemoryBackend for CountingBackend {
+        fn get(&self, key: &str) -> Option<&[u8]> {
+            self.regions.get(key).map(Vec::as_slice)
+        }
+        
+        fn get_mut(&mut self, key: &str) -> Option<&mut [u8]> {
+            self.regions.get_mut(key).map(Vec::as_mut_slice)
+        }
+        
+        fn put(&mut self, key: &str, data: Vec<u8>) -> Option<Vec<u8>> {
+            self.puts.fetch_add(1, Ordering::SeqCst);
+            self.regions.insert(key.to_string(), data)
+        }
+        
+        fn remove(&mut self, key: &str) -> Option<Vec<u8>> {
+            self.regions.remove(key)
+        }
+        
+        fn iter(&self) -> Box<dyn Iterator<Item = (&str, &[u8])> + '_> {
+            Box::new(self.regions.iter().map(|(key, data)| (key.as_str(), data.as_slice())))
+        }
+        
+        fn len(&self) -> usize {
+            self.regions.len()
+        }
+        
+        fn reserve(&mut self, additional: usize) {
+            self.reserved.fetch_add(additional, Ordering::SeqCst);
+        }
+    }
+    
+    #[test]
+    fn test_reserve_grows_backend_without_a_pool() {
+        let backend = CountingBackend::default();
+        let reserved = Arc::clone(&backend.reserved);
+        let mut memory = MemoryManager::with_backend(Box::new(backend));
+        
+        memory.reserve(10_000);
+        assert_eq!(reserved.load(Ordering::SeqCst), 3, "room for 4 KiB regions");
+        assert!(memory.stats().pool.is_none());
+    }
+    
+    #[test]
+    fn test_manager_runs_on_custom_backend() {
+        let backend = CountingBackend::default();
+        let puts = Arc::clone(&backend.puts);
+        let mut memory = MemoryManager::with_backend(Box::new(backend));
+        
+        memory.allocate_typed("samples", RegionType::U16, 2).unwrap();
+        memory.write_range("samples", 2, &[7, 0]).unwrap();
+        assert_eq!(memory.read_typed::<u16>("samples").unwrap(), vec![0, 7]);
+        
+        memory.write("log", b"ab").unwrap();
+        memory.append("log", b"cd").unwrap();
+        assert_eq!(memory.read("log"), Some(&b"abcd"[..]));
+        assert_eq!(puts.load(Ordering::SeqCst), 3, "allocate, write and append all store");
+        
+        let stats = memory.stats();
+        assert_eq!((stats.region_count, stats.total_bytes), (2, 8));
+        memory.deallocate("samples").unwrap();
+        assert_eq!(memory.read("samples"), None);
+        assert_eq!(memory.stats().region_count, 1);
+    }
+    
+    /// Backend that is full and silently drops every store
+    struct FullBackend;
+    
+    impl MemoryBackend for FullBackend {
+        fn get(&self, _key: &str) -> Option<&[u8]> {
+            None
+        }
+        
+        fn get_mut(&mut self, _key: &str) -> Option<&mut [u8]> {
+            None
+        }
+        
+        fn put(&mut self, _key: &str, _data: Vec<u8>) -> Option<Vec<u8>> {
+            None
+        }
+        
+        fn remove(&mut self, _key: &str) -> Option<Vec<u8>> {
+            None
+        }
+        
+        fn iter(&self) -> Box<dyn Iterator<Item = (&str, &[u8])> + '_> {
+            Box::new(std::iter::empty())
+        }
+        
+        fn len(&self) -> usize {
+            0
+        }
+    }
+    
+    #[test]
+    fn test_dropped_store_errors_instead_of_panicking() {
+        let mut memory = MemoryManager::with_backend(Box::new(FullBackend));
+        assert!(matches!(memory.allocate("a", 4), Err(CoreError::MemoryError(_))));
+        assert!(matches!(memory.try_allocate("a", 4), Err(CoreError::MemoryError(_))));
+        assert!(matches!(memory.allocate_typed("a", RegionType::F32, 1), Err(CoreError::MemoryError(_))));
+        #[cfg(feature = "std")]
+        assert!(memory
+            .allocate_with_ttl("a", 4, std::time::Duration::from_secs(1))
+            .is_err());
+    }
+}
@@ -0,0 +1,112 @@
+//! Complex samples stored as interleaved I/Q pairs
+
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use super::MemoryManager;
+use crate::algorithm::samples;
+#[cfg(all(not(feature = "std"), not(test)))]
+use crate::compat::Float;
+use crate::error::CoreError;
+
+/// Complex `f32` samples decoded from interleaved little-endian `(re, im)`
+/// pairs, as produced by SDR front ends
+///
+/// A buffer must hold an even number of `f32`s; a trailing unpaired value
+/// is rejected rather than dropped.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ComplexBuffer {
+    samples: Vec<(f32, f32)>,
+}
+
+impl ComplexBuffer {
+    /// Decode interleaved I/Q bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CoreError> {
+        let values = samples::f32_from_bytes(bytes)?;
+        if !values.len().is_multiple_of(2) {
+            return Err(CoreError::InvalidInput(format!(
+                "{} f32 values do not form whole (re, im) pairs",
+                values.len()
+            )));
+        }
+        Ok(Self {
+            samples: values.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect(),
+        })
+    }
+    
+    /// Encode the samples as interleaved I/Q bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.samples
+            .iter()
+            .flat_map(|&(re, im)| re.to_le_bytes().into_iter().chain(im.to_le_bytes()))
+            .collect()
+    }
+    
+    /// The samples as `(re, im)` pairs
+    pub fn as_complex_slice(&self) -> &[(f32, f32)] {
+        &self.samples
+    }
+    
+    /// Magnitude of each sample
+    pub fn magnitudes(&self) -> impl Iterator<Item = f32> + '_ {
+        self.samples.iter().map(|&(re, im)| re.hypot(im))
+    }
+    
+    /// Phase of each sample in radians, in `-pi..=pi`
+    pub fn phases(&self) -> impl Iterator<Item = f32> + '_ {
+        self.samples.iter().map(|&(re, im)| im.atan2(re))
+    }
+    
+    /// Number of complex samples
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+    
+    /// Whether the buffer holds no samples
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+}
+
+impl MemoryManager {
+    /// Decode a region as interleaved I/Q samples
+    pub fn read_complex(&self, key: &str) -> Result<ComplexBuffer, CoreError> {
+        let data = self
+            .read(key)
+            .ok_or_else(|| CoreError::MemoryKeyMissing(key.to_string()))?;
+        ComplexBuffer::from_bytes(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+    
+    #[test]
+    fn test_decodes_known_iq_buffer() {
+        let mut memory = MemoryManager::new();
+        let bytes = samples::f32_to_bytes(&[3.0, 4.0, 0.0, -2.0, -1.0, 0.0]);
+        memory.write("iq", &bytes).unwrap();
+        
+        let buffer = memory.read_complex("iq").unwrap();
+        assert_eq!(buffer.as_complex_slice(), &[(3.0, 4.0), (0.0, -2.0), (-1.0, 0.0)]);
+        assert_eq!(buffer.magnitudes().collect::<Vec<_>>(), vec![5.0, 2.0, 1.0]);
+        let phases: Vec<f32> = buffer.phases().collect();
+        assert!((phases[0] - 4.0f32.atan2(3.0)).abs() < 1e-6);
+        assert_eq!(phases[1..], [-FRAC_PI_2, std::f32::consts::PI]);
+        assert_eq!(buffer.to_bytes(), bytes);
+    }
+    
+    #[test]
+    fn test_odd_length_buffer_rejected() {
+        let odd = samples::f32_to_bytes(&[1.0, 2.0, 3.0]);
+        assert!(matches!(ComplexBuffer::from_bytes(&odd), Err(CoreError::InvalidInput(_))));
+        assert!(ComplexBuffer::from_bytes(&[0; 6]).is_err());
+        assert_eq!(
+            MemoryManager::new().read_complex("iq"),
+            Err(CoreError::MemoryKeyMissing("iq".to_string()))
+        );
+    }
+}
@@ -0,0 +1,150 @@
+//! Bounded event log queryable by time range and category
+
+use alloc::collections::{BTreeSet, VecDeque};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+use crate::error::CoreError;
+
+/// One event recorded in a `TimeIndexedLog`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogEntry {
+    /// Event time in microseconds
+    pub timestamp: u64,
+    /// Free-form category, e.g. the subsystem that logged the event
+    pub category: String,
+    /// Event payload
+    pub data: Vec<u8>,
+}
+
+/// Ring buffer of events indexed by timestamp
+///
+/// Holds at most `capacity` entries; appending to a full log evicts the
+/// oldest-appended entry, which then no longer appears in queries.
+/// Timestamps need not arrive in order, since queries go through a sorted
+/// index rather than the ring.
+pub struct TimeIndexedLog {
+    capacity: usize,
+    entries: VecDeque<LogEntry>,
+    // Append sequence number of `entries[0]`
+    first_sequence: u64,
+    // (timestamp, sequence) of every entry in the ring
+    index: BTreeSet<(u64, u64)>,
+}
+
+impl TimeIndexedLog {
+    /// Create a log keeping the `capacity` most recent entries
+    pub fn new(capacity: usize) -> Result<Self, CoreError> {
+        if capacity == 0 {
+            return Err(CoreError::InvalidParameter("log capacity must be at least 1".to_string()));
+        }
+        Ok(Self {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+            first_sequence: 0,
+            index: BTreeSet::new(),
+        })
+    }
+    
+    /// Record an event, evicting the oldest entry if the log is full
+    pub fn append(&mut self, timestamp: u64, category: &str, data: &[u8]) {
+        if self.entries.len() == self.capacity {
+            if let Some(evicted) = self.entries.pop_front() {
+                self.index.remove(&(evicted.timestamp, self.first_sequence));
+            }
+            self.first_sequence += 1;
+        }
+        let sequence = self.first_sequence + self.entries.len() as u64;
+        self.index.insert((timestamp, sequence));
+        self.entries.push_back(LogEntry {
+            timestamp,
+            category: category.to_string(),
+            data: data.to_vec(),
+        });
+    }
+    
+    /// Entries with `start <= timestamp < end`, optionally only those in
+    /// `category`, ordered by timestamp and then by append order
+    pub fn query(&self, start: u64, end: u64, category: Option<&str>) -> Vec<LogEntry> {
+        if start >= end {
+            return Vec::new();
+        }
+        self.index
+            .range((start, 0)..(end, 0))
+            .filter_map(|&(_, sequence)| {
+                let position = usize::try_from(sequence - self.first_sequence).ok()?;
+                self.entries.get(position)
+            })
+            .filter(|entry| category.is_none_or(|category| entry.category == category))
+            .cloned()
+            .collect()
+    }
+    
+    /// Number of entries currently held
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    
+    /// Whether the log holds no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    
+    /// Maximum number of entries held
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn timestamps(entries: &[LogEntry]) -> Vec<u64> {
+        entries.iter().map(|entry| entry.timestamp).collect()
+    }
+    
+    fn log() -> TimeIndexedLog {
+        let mut log = TimeIndexedLog::new(8).unwrap();
+        log.append(10, "motor", b"start");
+        log.append(30, "sensor", b"contact");
+        log.append(20, "motor", b"stall");
+        log.append(40, "sensor", b"release");
+        log.append(30, "motor", b"stop");
+        log
+    }
+    
+    #[test]
+    fn test_query_by_overlapping_ranges() {
+        let log = log();
+        assert_eq!(timestamps(&log.query(0, 100, None)), vec![10, 20, 30, 30, 40]);
+        assert_eq!(timestamps(&log.query(15, 35, None)), vec![20, 30, 30]);
+        assert_eq!(timestamps(&log.query(30, 41, None)), vec![30, 30, 40]);
+        // Equal timestamps keep their append order; `end` is exclusive
+        assert_eq!(log.query(30, 40, None)[1].data, b"stop");
+        assert!(log.query(35, 40, None).is_empty());
+        assert!(log.query(40, 10, None).is_empty());
+    }
+    
+    #[test]
+    fn test_query_by_category() {
+        let log = log();
+        let motor = log.query(0, 100, Some("motor"));
+        assert_eq!(timestamps(&motor), vec![10, 20, 30]);
+        assert_eq!(motor[2].data, b"stop");
+        assert_eq!(timestamps(&log.query(25, 100, Some("sensor"))), vec![30, 40]);
+        assert!(log.query(0, 100, Some("gripper")).is_empty());
+    }
+    
+    #[test]
+    fn test_wraparound_evicts_oldest_appended() {
+        let mut log = TimeIndexedLog::new(3).unwrap();
+        for timestamp in [50, 10, 40, 20, 30] {
+            log.append(timestamp, "tick", &[]);
+        }
+        assert_eq!(log.len(), 3);
+        assert_eq!(timestamps(&log.query(0, 100, None)), vec![20, 30, 40]);
+        assert!(TimeIndexedLog::new(0).is_err());
+    }
+}
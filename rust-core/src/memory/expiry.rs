@@ -0,0 +1,191 @@
+//! Time-to-live for scratch regions that are written once and forgotten
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use super::MemoryManager;
+use crate::clock::{Clock, SystemClock};
+use crate::error::CoreError;
+
+/// Last-access times of the regions allocated with a TTL
+///
+/// Reads take `&self`, so the times sit behind a mutex. Only regions with a
+/// TTL are tracked; while none are, accessing a region takes neither the
+/// lock nor a clock reading.
+pub(super) struct Expiry {
+    clock: Arc<dyn Clock>,
+    leases: Mutex<HashMap<String, Lease>>,
+    // Number of leases, checked before taking the lock
+    leased: AtomicUsize,
+}
+
+#[derive(Clone, Copy)]
+struct Lease {
+    ttl: Duration,
+    last_access: Duration,
+}
+
+impl Default for Expiry {
+    fn default() -> Self {
+        Self {
+            clock: Arc::new(SystemClock::new()),
+            leases: Mutex::new(HashMap::new()),
+            leased: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl Clone for Expiry {
+    fn clone(&self) -> Self {
+        let leases = self.lock().clone();
+        Self {
+            clock: Arc::clone(&self.clock),
+            leased: AtomicUsize::new(leases.len()),
+            leases: Mutex::new(leases),
+        }
+    }
+}
+
+impl Expiry {
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Lease>> {
+        self.leases.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    /// Refresh the last-access time of `key`, if it has a TTL
+    pub(super) fn touch(&self, key: &str) {
+        if self.leased.load(Ordering::Acquire) == 0 {
+            return;
+        }
+        if let Some(lease) = self.lock().get_mut(key) {
+            lease.last_access = self.clock.now();
+        }
+    }
+    
+    /// Start tracking `key` with `ttl`, from now
+    fn lease(&self, key: &str, ttl: Duration) {
+        let last_access = self.clock.now();
+        let mut leases = self.lock();
+        leases.insert(key.to_string(), Lease { ttl, last_access });
+        self.leased.store(leases.len(), Ordering::Release);
+    }
+    
+    /// Stop tracking `key`
+    pub(super) fn forget(&self, key: &str) {
+        if self.leased.load(Ordering::Acquire) == 0 {
+            return;
+        }
+        let mut leases = self.lock();
+        leases.remove(key);
+        self.leased.store(leases.len(), Ordering::Release);
+    }
+}
+
+impl MemoryManager {
+    /// Read access times for region TTLs from `clock` instead of the system
+    /// clock
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.expiry.clock = clock;
+    }
+    
+    /// Allocate a region that `collect_expired` frees once it has gone `ttl`
+    /// without being accessed
+    ///
+    /// Reads, writes, appends and `acquire` all count as access. Otherwise
+    /// behaves like `allocate`; re-allocating the key without a TTL makes the
+    /// region permanent again. TTLs are not kept in snapshots.
+    pub fn allocate_with_ttl(&mut self, key: &str, size: usize, ttl: Duration) -> Result<&mut [u8], CoreError> {
+        self.allocate(key, size)?;
+        self.expiry.lease(key, ttl);
+        self.allocated_region(key)
+    }
+    
+    /// Free every region whose TTL has elapsed by `now`, returning their
+    /// keys in sorted order
+    ///
+    /// `now` is a reading of the manager's clock. Regions still referenced
+    /// through `acquire`, or marked read-only, are kept until a later
+    /// collection finds them released.
+    pub fn collect_expired(&mut self, now: Duration) -> Vec<String> {
+        let mut expired: Vec<String> = self
+            .expiry
+            .lock()
+            .iter()
+            .filter(|(_, lease)| now.saturating_sub(lease.last_access) >= lease.ttl)
+            .map(|(key, _)| key.clone())
+            .collect();
+        expired.retain(|key| self.ref_count(key) == 0 && !self.is_readonly(key));
+        expired.sort_unstable();
+        for key in &expired {
+            self.free_region(key);
+        }
+        expired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    
+    #[test]
+    fn test_collect_expired_frees_idle_regions() {
+        let clock = Arc::new(MockClock::new());
+        let mut memory = MemoryManager::new();
+        memory.set_clock(clock.clone());
+        memory.allocate_with_ttl("scratch", 4, Duration::from_secs(10)).unwrap();
+        memory.allocate_with_ttl("busy", 4, Duration::from_secs(10)).unwrap();
+        memory.allocate("permanent", 4).unwrap();
+        
+        clock.advance(Duration::from_secs(6));
+        memory.read("busy").unwrap();
+        assert!(memory.collect_expired(clock.now()).is_empty());
+        
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(memory.collect_expired(clock.now()), vec!["scratch".to_string()]);
+        assert!(memory.read("scratch").is_none());
+        
+        let held = memory.acquire("busy").unwrap();
+        clock.advance(Duration::from_secs(20));
+        assert!(memory.collect_expired(clock.now()).is_empty());
+        drop(held);
+        assert_eq!(memory.collect_expired(clock.now()), vec!["busy".to_string()]);
+        assert!(memory.read("permanent").is_some());
+    }
+    
+    #[test]
+    fn test_lease_count_tracks_ttl_regions() {
+        let mut memory = MemoryManager::new();
+        memory.allocate("plain", 4).unwrap();
+        memory.read("plain").unwrap();
+        assert_eq!(memory.expiry.leased.load(Ordering::Acquire), 0);
+        
+        memory.allocate_with_ttl("a", 4, Duration::from_secs(1)).unwrap();
+        memory.allocate_with_ttl("b", 4, Duration::from_secs(1)).unwrap();
+        assert_eq!(memory.fork().expiry.leased.load(Ordering::Acquire), 2);
+        memory.deallocate("a").unwrap();
+        memory.allocate("b", 4).unwrap();
+        assert_eq!(memory.expiry.leased.load(Ordering::Acquire), 0);
+    }
+    
+    #[test]
+    fn test_readonly_region_outlives_its_ttl_until_writable() {
+        let clock = Arc::new(MockClock::new());
+        let mut memory = MemoryManager::new();
+        memory.set_clock(clock.clone());
+        memory.allocate_with_ttl("table", 4, Duration::from_secs(1)).unwrap();
+        memory.set_readonly("table", true).unwrap();
+        
+        clock.advance(Duration::from_secs(5));
+        assert!(memory.collect_expired(clock.now()).is_empty());
+        assert!(memory.collect_expired(clock.now()).is_empty());
+        assert!(memory.read("table").is_some());
+        
+        memory.set_readonly("table", false).unwrap();
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(memory.collect_expired(clock.now()), vec!["table".to_string()]);
+        assert!(memory.read("table").is_none());
+        assert!(memory.collect_expired(clock.now()).is_empty());
+    }
+}
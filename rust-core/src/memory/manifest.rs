@@ -0,0 +1,108 @@
+//! Up-front declaration of the regions a deployment uses
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+use super::{MemoryManager, RegionType};
+use crate::compat::HashSet;
+use crate::error::CoreError;
+
+/// Regions to allocate before the first execution
+///
+/// Serializes as a plain list of regions, e.g.
+/// `[{"key": "imu", "size": 24, "type": "F32"}]`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MemoryManifest {
+    pub regions: Vec<ManifestRegion>,
+}
+
+/// One region of a `MemoryManifest`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestRegion {
+    /// Key the region is allocated under
+    pub key: String,
+    /// Size in bytes
+    pub size: usize,
+    /// Element type to tag the region with, if any
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub region_type: Option<RegionType>,
+}
+
+impl MemoryManager {
+    /// Allocate every region declared in `manifest`, zeroed
+    ///
+    /// The manifest is checked whole before anything is allocated: a key
+    /// listed twice fails with `CoreError::KeyAlreadyExists`, and a typed
+    /// region whose size is not a whole number of elements with
+    /// `CoreError::InvalidParameter`. Allocation then follows `allocate`,
+    /// so with strict keys a key that already exists in the manager also
+    /// fails, after the regions before it were allocated.
+    pub fn preallocate_from_manifest(&mut self, manifest: &MemoryManifest) -> Result<(), CoreError> {
+        let mut keys = HashSet::new();
+        for region in &manifest.regions {
+            if !keys.insert(region.key.as_str()) {
+                return Err(CoreError::KeyAlreadyExists(region.key.clone()));
+            }
+            if let Some(region_type) = region.region_type {
+                if !region.size.is_multiple_of(region_type.size()) {
+                    return Err(CoreError::InvalidParameter(format!(
+                        "region '{}' of {} bytes is not a whole number of {} elements",
+                        region.key, region.size, region_type
+                    )));
+                }
+            }
+        }
+        for region in &manifest.regions {
+            match region.region_type {
+                Some(region_type) => self.allocate_typed(&region.key, region_type, region.size / region_type.size())?,
+                None => self.allocate(&region.key, region.size)?,
+            };
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_manifest_regions_exist_before_execution() {
+        let manifest: MemoryManifest = serde_json::from_str(
+            r#"[
+                {"key": "imu", "size": 24, "type": "F32"},
+                {"key": "scratch", "size": 100}
+            ]"#,
+        )
+        .unwrap();
+        let mut memory = MemoryManager::new();
+        memory.preallocate_from_manifest(&manifest).unwrap();
+        
+        assert_eq!(memory.read("imu"), Some(&[0u8; 24][..]));
+        assert_eq!(memory.region_type("imu"), Some(RegionType::F32));
+        assert_eq!(memory.read("scratch").map(<[u8]>::len), Some(100));
+        assert_eq!(memory.region_type("scratch"), None);
+        assert_eq!(memory.stats().total_bytes, 124);
+    }
+    
+    #[test]
+    fn test_manifest_duplicate_key_errors_without_allocating() {
+        let region = |key: &str| ManifestRegion {
+            key: key.to_string(),
+            size: 8,
+            region_type: None,
+        };
+        let manifest = MemoryManifest {
+            regions: vec![region("a"), region("b"), region("a")],
+        };
+        let mut memory = MemoryManager::new();
+        assert_eq!(
+            memory.preallocate_from_manifest(&manifest),
+            Err(CoreError::KeyAlreadyExists("a".to_string()))
+        );
+        assert_eq!(memory.stats().region_count, 0);
+    }
+}
@@ -0,0 +1,1601 @@
+//! Memory management module for efficient data handling
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::fmt;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
+use std::cell::RefCell;
+
+use crate::compat::{HashMap, HashSet};
+#[cfg(feature = "std")]
+use crate::compression::{self, Compressor};
+use crate::error::CoreError;
+#[cfg(feature = "std")]
+use crate::wire;
+
+mod backend;
+mod complex;
+mod event_log;
+#[cfg(feature = "std")]
+mod expiry;
+mod manifest;
+mod patch;
+#[cfg(feature = "std")]
+mod protected;
+#[cfg(all(feature = "shared-mem", unix))]
+mod shared;
+#[cfg(all(feature = "spill", not(target_arch = "wasm32")))]
+mod spill;
+
+pub use backend::{HeapBackend, MemoryBackend};
+pub use complex::ComplexBuffer;
+pub use event_log::{LogEntry, TimeIndexedLog};
+pub use manifest::{ManifestRegion, MemoryManifest};
+pub use patch::RegionPatch;
+#[cfg(feature = "std")]
+pub use protected::MultiGuard;
+#[cfg(all(feature = "shared-mem", unix))]
+pub use shared::{SharedMemoryBackend, SharedRegionReader};
+#[cfg(all(feature = "spill", not(target_arch = "wasm32")))]
+pub use spill::SpillBackend;
+
+/// Regions a fork wrote, or removed as `None`, to merge back into the
+/// manager it was forked from
+#[cfg(feature = "std")]
+pub(crate) type RegionChanges = Vec<(String, Option<Vec<u8>>)>;
+
+/// Region size `reserve` assumes when no pool fixes one, to estimate how
+/// many regions a byte budget holds
+const ESTIMATED_REGION_BYTES: usize = 4096;
+
+/// Without `std` there is no clock to expire regions by, so no region has
+/// a TTL and access is never tracked
+#[cfg(not(feature = "std"))]
+mod expiry {
+    #[derive(Clone, Default)]
+    pub(super) struct Expiry {}
+    
+    impl Expiry {
+        pub(super) fn touch(&self, _key: &str) {}
+        
+        pub(super) fn forget(&self, _key: &str) {}
+    }
+}
+
+#[cfg(feature = "std")]
+thread_local! {
+    // Per-thread arena backing `MemoryManager::thread_local`
+    static THREAD_LOCAL_MEMORY: RefCell<MemoryManager> = RefCell::new(MemoryManager::new());
+}
+
+/// Manages memory allocations and access for algorithms
+pub struct MemoryManager {
+    // Memory regions accessible by algorithms
+    shared_memory: Box<dyn MemoryBackend>,
+    // Protected memory regions that require special access
+    #[cfg(feature = "std")]
+    protected_memory: Arc<protected::ProtectedMemory>,
+    // Regions that `write` and `write_range` refuse to modify
+    readonly: HashSet<String>,
+    // Regions made permanently immutable by `freeze`
+    frozen: HashSet<String>,
+    // Element types of regions allocated with `allocate_typed`
+    region_types: HashMap<String, RegionType>,
+    // Reference tokens for regions handed out via `acquire`
+    region_refs: HashMap<String, Arc<()>>,
+    // What `write` does when the target region does not exist
+    missing_key_policy: MissingKeyPolicy,
+    // Whether `allocate` refuses to replace an existing region
+    strict_keys: bool,
+    // Recycled fixed-size buffers, when the pool strategy is enabled
+    pool: Option<BufferPool>,
+    // Regions allocated with a TTL, and when they were last accessed
+    expiry: expiry::Expiry,
+}
+
+/// Free list of fixed-size buffers reused across allocations
+struct BufferPool {
+    block_size: usize,
+    free: Vec<Vec<u8>>,
+    // Regions whose buffer is a pool block, even if it has since grown
+    owned: HashSet<String>,
+    fresh_blocks: usize,
+    // Most blocks ever obtained, for a fixed pool
+    capacity: Option<usize>,
+    in_use: usize,
+    peak_in_use: usize,
+}
+
+impl BufferPool {
+    fn new(block_size: usize, capacity: Option<usize>) -> Self {
+        Self {
+            block_size,
+            free: Vec::new(),
+            owned: HashSet::new(),
+            fresh_blocks: 0,
+            capacity,
+            in_use: 0,
+            peak_in_use: 0,
+        }
+    }
+    
+    /// Add blocks to the free list until it holds `blocks`, within capacity
+    fn fill(&mut self, blocks: usize) {
+        let room = self.capacity.map_or(usize::MAX, |capacity| capacity - self.fresh_blocks);
+        let fresh = blocks.saturating_sub(self.free.len()).min(room);
+        self.free.extend((0..fresh).map(|_| Vec::with_capacity(self.block_size)));
+        self.fresh_blocks += fresh;
+    }
+}
+
+/// Behavior of `MemoryManager::write` when the target key does not exist
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MissingKeyPolicy {
+    /// Create a region holding the written data
+    #[default]
+    AutoCreate,
+    /// Fail with `CoreError::MemoryKeyMissing`, catching mistyped keys
+    Error,
+}
+
+/// Element type a region is declared to hold
+///
+/// Tags are documentation the manager can enforce: `read_typed` refuses to
+/// decode a tagged region as any other type. Elements are little-endian.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RegionType {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    F32,
+    F64,
+}
+
+impl RegionType {
+    /// Size of one element in bytes
+    pub fn size(&self) -> usize {
+        match self {
+            RegionType::U8 | RegionType::I8 => 1,
+            RegionType::U16 | RegionType::I16 => 2,
+            RegionType::U32 | RegionType::I32 | RegionType::F32 => 4,
+            RegionType::F64 => 8,
+        }
+    }
+    
+    // Snapshot tag; 0 marks an untagged region
+    #[cfg(feature = "std")]
+    fn tag(&self) -> u8 {
+        match self {
+            RegionType::U8 => 1,
+            RegionType::I8 => 2,
+            RegionType::U16 => 3,
+            RegionType::I16 => 4,
+            RegionType::U32 => 5,
+            RegionType::I32 => 6,
+            RegionType::F32 => 7,
+            RegionType::F64 => 8,
+        }
+    }
+    
+    #[cfg(feature = "std")]
+    fn from_tag(tag: u8) -> Option<Self> {
+        [
+            RegionType::U8,
+            RegionType::I8,
+            RegionType::U16,
+            RegionType::I16,
+            RegionType::U32,
+            RegionType::I32,
+            RegionType::F32,
+            RegionType::F64,
+        ]
+        .into_iter()
+        .find(|region_type| region_type.tag() == tag)
+    }
+}
+
+impl fmt::Display for RegionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RegionType::U8 => "u8",
+            RegionType::I8 => "i8",
+            RegionType::U16 => "u16",
+            RegionType::I16 => "i16",
+            RegionType::U32 => "u32",
+            RegionType::I32 => "i32",
+            RegionType::F32 => "f32",
+            RegionType::F64 => "f64",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Numeric type that can be decoded from a region by `read_typed`
+pub trait RegionElement: Copy {
+    /// Tag a region holding this type carries
+    const TYPE: RegionType;
+    
+    /// Decode one element from `TYPE.size()` little-endian bytes
+    fn from_le_slice(bytes: &[u8]) -> Self;
+}
+
+macro_rules! region_element {
+    ($($ty:ty => $tag:ident),* $(,)?) => {
+        $(
+            impl RegionElement for $ty {
+                const TYPE: RegionType = RegionType::$tag;
+                
+                fn from_le_slice(bytes: &[u8]) -> Self {
+                    let mut raw = [0u8; core::mem::size_of::<$ty>()];
+                    raw.copy_from_slice(bytes);
+                    <$ty>::from_le_bytes(raw)
+                }
+            }
+        )*
+    };
+}
+
+region_element!(
+    u8 => U8,
+    i8 => I8,
+    u16 => U16,
+    i16 => I16,
+    u32 => U32,
+    i32 => I32,
+    f32 => F32,
+    f64 => F64,
+);
+
+impl MemoryManager {
+    /// Create a new memory manager instance
+    pub fn new() -> Self {
+        Self::with_backend(Box::new(HeapBackend::new()))
+    }
+    
+    /// Create a memory manager storing its shared regions in `backend`
+    ///
+    /// Protected regions and forks made for batch and DAG execution stay on
+    /// the heap; only the manager's own shared regions use the backend.
+    pub fn with_backend(backend: Box<dyn MemoryBackend>) -> Self {
+        Self {
+            shared_memory: backend,
+            #[cfg(feature = "std")]
+            protected_memory: Arc::new(protected::ProtectedMemory::new()),
+            readonly: HashSet::new(),
+            frozen: HashSet::new(),
+            region_types: HashMap::new(),
+            region_refs: HashMap::new(),
+            missing_key_policy: MissingKeyPolicy::default(),
+            strict_keys: false,
+            pool: None,
+            expiry: expiry::Expiry::default(),
+        }
+    }
+    
+    /// Create a memory manager that serves regions up to `block_size` bytes
+    /// from a free list of recycled buffers
+    pub fn with_pool(block_size: usize) -> Self {
+        Self {
+            pool: Some(BufferPool::new(block_size, None)),
+            ..Self::new()
+        }
+    }
+    
+    /// Create a memory manager that serves every region from `blocks`
+    /// buffers of `block_size` bytes, all allocated up front
+    ///
+    /// Allocations never reach the global allocator: once every block is in
+    /// use they fail with `CoreError::PoolExhausted`, and regions larger than
+    /// a block with `CoreError::AllocationFailed`. The region map is sized
+    /// for `blocks` regions too. A region that `resize` or `append` grows
+    /// past the block size keeps its block, and frees it like any other.
+    pub fn with_fixed_pool(block_size: usize, blocks: usize) -> Self {
+        let mut pool = BufferPool::new(block_size, Some(blocks));
+        pool.fill(blocks);
+        let mut memory = Self {
+            pool: Some(pool),
+            ..Self::new()
+        };
+        memory.shared_memory.reserve(blocks);
+        memory
+    }
+    
+    /// Run `f` against the calling thread's own memory arena
+    ///
+    /// Each thread gets an independent manager, so the common per-thread
+    /// path needs no locking. Regions in a thread-local arena are never
+    /// visible to other threads; anything that must cross threads has to be
+    /// placed explicitly in a shared manager's protected region. Calling this
+    /// again from inside `f` returns an error instead of aliasing the arena.
+    #[cfg(feature = "std")]
+    pub fn thread_local<R>(f: impl FnOnce(&mut MemoryManager) -> R) -> Result<R, CoreError> {
+        THREAD_LOCAL_MEMORY.with(|memory| {
+            let mut memory = memory.try_borrow_mut().map_err(|_| {
+                CoreError::MemoryError("thread-local memory is already borrowed".to_string())
+            })?;
+            Ok(f(&mut memory))
+        })
+    }
+    
+    /// Copy the shared regions into a separate manager for isolated work
+    ///
+    /// The fork shares the protected region, which is the sanctioned path for
+    /// cross-thread data.
+    #[cfg(feature = "std")]
+    pub(crate) fn fork(&self) -> MemoryManager {
+        MemoryManager {
+            shared_memory: Box::new(self.heap_copy()),
+            protected_memory: Arc::clone(&self.protected_memory),
+            readonly: self.readonly.clone(),
+            frozen: self.frozen.clone(),
+            region_types: self.region_types.clone(),
+            region_refs: HashMap::new(),
+            missing_key_policy: self.missing_key_policy,
+            strict_keys: self.strict_keys,
+            pool: None,
+            expiry: self.expiry.clone(),
+        }
+    }
+    
+    /// Regions of this manager that differ from `base`, with `None` for
+    /// those `base` holds and this manager no longer does
+    #[cfg(feature = "std")]
+    pub(crate) fn changes_since(&self, base: &MemoryManager) -> RegionChanges {
+        let written = self
+            .shared_memory
+            .iter()
+            .filter(|(key, data)| base.shared_memory.get(key) != Some(*data))
+            .map(|(key, data)| (key.to_string(), Some(data.to_vec())));
+        let removed = base
+            .shared_memory
+            .iter()
+            .filter(|(key, _)| !self.shared_memory.contains(key))
+            .map(|(key, _)| (key.to_string(), None));
+        written.chain(removed).collect()
+    }
+    
+    /// Apply region changes collected with `changes_since`
+    ///
+    /// A removal is skipped while a `RegionRef` here still holds the region.
+    #[cfg(feature = "std")]
+    pub(crate) fn apply_changes(&mut self, changes: RegionChanges) {
+        for (key, data) in changes {
+            match data {
+                Some(data) => {
+                    if let Some(previous) = self.shared_memory.put(&key, data) {
+                        self.recycle(&key, previous);
+                    }
+                }
+                None if self.ref_count(&key) > 0 => {
+                    log::warn!("Kept region '{}' removed by a fork: it is still referenced", key);
+                }
+                None => self.free_region(&key),
+            }
+        }
+    }
+    
+    /// Serialize the shared regions, their type tags and read-only and
+    /// frozen flags, compressed with `compressor`
+    ///
+    /// The snapshot records its codec, so `from_snapshot` needs no hint.
+    /// Regions are written in key order, making snapshots of equal memory
+    /// byte-identical. Protected regions are not included.
+    #[cfg(feature = "std")]
+    pub fn snapshot(&self, compressor: &dyn Compressor) -> Result<Vec<u8>, CoreError> {
+        let mut regions: Vec<(&str, &[u8])> = self.shared_memory.iter().collect();
+        regions.sort_unstable_by_key(|&(key, _)| key);
+        let mut raw = Vec::new();
+        raw.extend_from_slice(&(regions.len() as u32).to_le_bytes());
+        for (key, data) in regions {
+            wire::put_short_str(&mut raw, key)?;
+            raw.push(self.region_type(key).map_or(0, |region_type| region_type.tag()));
+            raw.push(self.is_readonly(key) as u8 | (self.is_frozen(key) as u8) << 1);
+            wire::put_bytes(&mut raw, data)?;
+        }
+        compression::pack(&raw, compressor)
+    }
+    
+    /// Rebuild a manager from a `snapshot`, whichever codec wrote it
+    #[cfg(feature = "std")]
+    pub fn from_snapshot(snapshot: &[u8]) -> Result<Self, CoreError> {
+        let raw = compression::unpack(snapshot)?;
+        let mut reader = wire::Reader::new(&raw, "memory snapshot", CoreError::InvalidInput);
+        let mut memory = Self::new();
+        for _ in 0..reader.u32()? {
+            let key = reader.short_str()?;
+            let tag = reader.u8()?;
+            let flags = reader.u8()?;
+            memory.shared_memory.put(&key, reader.bytes()?.to_vec());
+            if tag != 0 {
+                let region_type = RegionType::from_tag(tag)
+                    .ok_or_else(|| reader.error(&format!("unknown region type {}", tag)))?;
+                memory.region_types.insert(key.clone(), region_type);
+            }
+            if flags & 2 != 0 {
+                memory.frozen.insert(key.clone());
+            }
+            if flags & 1 != 0 {
+                memory.readonly.insert(key);
+            }
+        }
+        reader.finish()?;
+        Ok(memory)
+    }
+    
+    #[cfg(feature = "std")]
+    fn heap_copy(&self) -> HeapBackend {
+        let mut copy = HeapBackend::new();
+        for (key, data) in self.shared_memory.iter() {
+            copy.put(key, data.to_vec());
+        }
+        copy
+    }
+    
+    /// Choose what `write` does when the target key does not exist
+    pub fn set_missing_key_policy(&mut self, policy: MissingKeyPolicy) {
+        self.missing_key_policy = policy;
+    }
+    
+    /// Make `allocate` fail on keys that already exist instead of replacing
+    /// them
+    ///
+    /// Off by default. With several components sharing one manager, strict
+    /// keys turn an accidental key collision into a
+    /// `CoreError::KeyAlreadyExists` rather than silently clobbered data;
+    /// reusing a key then requires an explicit `deallocate` first.
+    pub fn set_strict_keys(&mut self, strict: bool) {
+        self.strict_keys = strict;
+    }
+    
+    /// Settings recorded in the engine's configuration
+    #[cfg(feature = "std")]
+    pub(crate) fn config(&self) -> crate::config::MemoryConfig {
+        crate::config::MemoryConfig {
+            missing_key_policy: self.missing_key_policy,
+            strict_keys: self.strict_keys,
+            pool_block_size: self.pool.as_ref().map(|pool| pool.block_size),
+            pool_blocks: self.pool.as_ref().and_then(|pool| pool.capacity),
+        }
+    }
+    
+    /// Mark a region read-only, or writable again
+    ///
+    /// `write` and `write_range` to a read-only region fail with
+    /// `CoreError::PermissionDenied`, as do replacing it with `allocate`,
+    /// `try_allocate` or `adopt` and freeing it with `deallocate`, while
+    /// reads are unaffected. Clear the flag first to replace or free the
+    /// region.
+    pub fn set_readonly(&mut self, key: &str, readonly: bool) -> Result<(), CoreError> {
+        if !self.shared_memory.contains(key) {
+            return Err(CoreError::MemoryKeyMissing(key.to_string()));
+        }
+        if readonly {
+            self.readonly.insert(key.to_string());
+        } else {
+            self.readonly.remove(key);
+        }
+        Ok(())
+    }
+    
+    /// Whether a region is marked read-only
+    pub fn is_readonly(&self, key: &str) -> bool {
+        self.readonly.contains(key)
+    }
+    
+    /// Make a region permanently immutable, e.g. once a calibration table
+    /// has been written
+    ///
+    /// Unlike `set_readonly` this cannot be undone: `write`, `write_range`,
+    /// `append`, in-place execution and re-allocating the key all fail with
+    /// `CoreError::RegionFrozen` from then on, while reads are unaffected.
+    /// Only deallocating the region lifts the freeze.
+    pub fn freeze(&mut self, key: &str) -> Result<(), CoreError> {
+        if !self.shared_memory.contains(key) {
+            return Err(CoreError::MemoryKeyMissing(key.to_string()));
+        }
+        self.frozen.insert(key.to_string());
+        Ok(())
+    }
+    
+    /// Whether a region has been frozen
+    pub fn is_frozen(&self, key: &str) -> bool {
+        self.frozen.contains(key)
+    }
+    
+    fn check_writable(&self, key: &str) -> Result<(), CoreError> {
+        if self.frozen.contains(key) {
+            return Err(CoreError::RegionFrozen(key.to_string()));
+        }
+        if self.readonly.contains(key) {
+            return Err(CoreError::PermissionDenied(format!("region '{}' is read-only", key)));
+        }
+        Ok(())
+    }
+    
+    /// Fails unless `key` is free or its region may be replaced: writable,
+    /// unreferenced, and not under strict keys
+    fn check_replaceable(&self, key: &str) -> Result<(), CoreError> {
+        self.check_writable(key)?;
+        if self.strict_keys && self.shared_memory.contains(key) {
+            return Err(CoreError::KeyAlreadyExists(key.to_string()));
+        }
+        self.check_unreferenced(key)
+    }
+    
+    fn check_unreferenced(&self, key: &str) -> Result<(), CoreError> {
+        let refs = self.ref_count(key);
+        if refs > 0 {
+            return Err(CoreError::RegionInUse {
+                key: key.to_string(),
+                refs,
+            });
+        }
+        Ok(())
+    }
+    
+    /// Pre-reserve capacity for `total_bytes` of future allocations
+    ///
+    /// The region map grows to hold as many regions as `total_bytes` makes:
+    /// one per block with the pool strategy, or one per 4 KiB without it.
+    /// A pool also fills its free list with those blocks, so later
+    /// allocations touch neither the allocator nor the map's own storage; a
+    /// fixed pool already holds all its blocks. Without a pool, region
+    /// buffers are still allocated on demand.
+    pub fn reserve(&mut self, total_bytes: usize) {
+        let region_bytes = self.pool.as_ref().map_or(ESTIMATED_REGION_BYTES, |pool| pool.block_size);
+        let regions = total_bytes.div_ceil(region_bytes.max(1));
+        if let Some(pool) = self.pool.as_mut() {
+            pool.fill(regions);
+        }
+        self.shared_memory.reserve(regions);
+    }
+    
+    /// Release capacity not currently holding region data
+    ///
+    /// A fixed pool keeps its free blocks, as it could not replace them.
+    pub fn shrink_to_fit(&mut self) {
+        if let Some(pool) = self.pool.as_mut().filter(|pool| pool.capacity.is_none()) {
+            pool.free = Vec::new();
+        }
+        // Pool blocks keep their capacity so they can be recycled
+        self.shared_memory.shrink_to_fit(self.pool.is_none());
+        self.region_refs.shrink_to_fit();
+    }
+    
+    /// Free every protected region and every shared region no `RegionRef`
+    /// still holds, whatever its flags, returning the keys of the regions
+    /// kept because they are referenced
+    ///
+    /// Read-only, frozen and type tags go with their regions. The backend,
+    /// buffer pool, missing-key policy and strict keys are kept, so the
+    /// manager can be reused.
+    pub fn clear(&mut self) -> Vec<String> {
+        let (mut kept, freed): (Vec<String>, Vec<String>) = self
+            .shared_memory
+            .iter()
+            .map(|(key, _)| key.to_string())
+            .partition(|key| self.ref_count(key) > 0);
+        for key in freed {
+            self.free_region(&key);
+        }
+        self.region_refs.retain(|_, token| Arc::strong_count(token) > 1);
+        #[cfg(feature = "std")]
+        {
+            self.protected_memory = Arc::new(protected::ProtectedMemory::new());
+        }
+        self.shrink_to_fit();
+        kept.sort();
+        kept
+    }
+    
+    /// Allocate memory in the shared region
+    ///
+    /// An existing region under `key` is replaced, unless strict keys are
+    /// enabled or a `RegionRef` still holds it, which fails with
+    /// `CoreError::RegionInUse`. The new region is untagged.
+    pub fn allocate(&mut self, key: &str, size: usize) -> Result<&mut [u8], CoreError> {
+        self.check_replaceable(key)?;
+        let (buffer, pooled) = self.take_buffer(size)?;
+        self.insert_region(key, buffer, pooled)
+    }
+    
+    /// Allocate memory in the shared region, returning
+    /// `CoreError::AllocationFailed` instead of aborting when the system
+    /// cannot provide `size` bytes
+    ///
+    /// Otherwise behaves like `allocate`. On failure any existing region
+    /// under `key` is left untouched.
+    pub fn try_allocate(&mut self, key: &str, size: usize) -> Result<&mut [u8], CoreError> {
+        self.check_replaceable(key)?;
+        let (buffer, pooled) = match self.pool.as_ref() {
+            Some(pool) if size <= pool.block_size || pool.capacity.is_some() => self.take_buffer(size)?,
+            _ => {
+                let mut buffer = Vec::new();
+                buffer
+                    .try_reserve_exact(size)
+                    .map_err(|_| CoreError::AllocationFailed { size })?;
+                buffer.resize(size, 0);
+                (buffer, false)
+            }
+        };
+        self.insert_region(key, buffer, pooled)
+    }
+    
+    /// Store `buffer`, a pool block if `pooled`, as the untagged region
+    /// `key`, recycling any previous one
+    fn insert_region(&mut self, key: &str, buffer: Vec<u8>, pooled: bool) -> Result<&mut [u8], CoreError> {
+        self.region_types.remove(key);
+        self.expiry.forget(key);
+        if let Some(previous) = self.shared_memory.remove(key) {
+            self.recycle(key, previous);
+        }
+        self.store_region(key, buffer, pooled);
+        self.allocated_region(key)
+    }
+    
+    /// Put `buffer` under `key`, where no region is, noting whether it is a
+    /// pool block
+    fn store_region(&mut self, key: &str, buffer: Vec<u8>, pooled: bool) {
+        self.shared_memory.put(key, buffer);
+        if let Some(pool) = self.pool.as_mut().filter(|_| pooled) {
+            pool.owned.insert(key.to_string());
+        }
+    }
+    
+    /// A region that was just stored, failing if the backend did not keep it
+    fn allocated_region(&mut self, key: &str) -> Result<&mut [u8], CoreError> {
+        self.shared_memory
+            .get_mut(key)
+            .ok_or_else(|| CoreError::MemoryError(format!("backend did not store region '{}'", key)))
+    }
+    
+    /// Allocate a region of `len` elements tagged with their type
+    pub fn allocate_typed(&mut self, key: &str, region_type: RegionType, len: usize) -> Result<&mut [u8], CoreError> {
+        let size = len.checked_mul(region_type.size()).ok_or_else(|| {
+            CoreError::MemoryError(format!("{} {} elements overflow the address space", len, region_type))
+        })?;
+        self.allocate(key, size)?;
+        self.region_types.insert(key.to_string(), region_type);
+        self.allocated_region(key)
+    }
+    
+    /// Adopt `buffer` as the untagged region `key` without copying it, such
+    /// as a DMA buffer a sensor driver filled
+    ///
+    /// Fails with `CoreError::InvalidParameter` unless `align` is a power of
+    /// two and the buffer starts at a multiple of it, so consumers that
+    /// reinterpret the bytes in place can rely on the alignment. Otherwise
+    /// behaves like `allocate`.
+    pub fn adopt(&mut self, key: &str, buffer: Vec<u8>, align: usize) -> Result<&mut [u8], CoreError> {
+        if !align.is_power_of_two() {
+            return Err(CoreError::InvalidParameter(format!("alignment must be a power of two, got {}", align)));
+        }
+        if !buffer.as_ptr().addr().is_multiple_of(align) {
+            return Err(CoreError::InvalidParameter(format!(
+                "buffer for region '{}' is not aligned to {} bytes",
+                key, align
+            )));
+        }
+        self.check_replaceable(key)?;
+        self.insert_region(key, buffer, false)
+    }
+    
+    /// Largest power of two, up to 4096, that a region's start address is a
+    /// multiple of
+    ///
+    /// Empty regions have no storage and report 4096.
+    pub fn alignment(&self, key: &str) -> Option<usize> {
+        const PAGE: usize = 4096;
+        let data = self.shared_memory.get(key)?;
+        if data.is_empty() {
+            return Some(PAGE);
+        }
+        let addr = data.as_ptr().addr();
+        Some((1usize << addr.trailing_zeros().min(PAGE.trailing_zeros())).min(PAGE))
+    }
+    
+    /// Element type a region was tagged with, if any
+    pub fn region_type(&self, key: &str) -> Option<RegionType> {
+        self.region_types.get(key).copied()
+    }
+    
+    /// Get a zeroed buffer, and whether it is a pool block, from the pool
+    /// when it fits a block
+    ///
+    /// A fixed pool fails rather than fall back to the allocator.
+    fn take_buffer(&mut self, size: usize) -> Result<(Vec<u8>, bool), CoreError> {
+        let Some(pool) = self.pool.as_mut() else {
+            return Ok((vec![0u8; size], false));
+        };
+        if size > pool.block_size {
+            return match pool.capacity {
+                Some(_) => Err(CoreError::AllocationFailed { size }),
+                None => Ok((vec![0u8; size], false)),
+            };
+        }
+        let mut buffer = match pool.free.pop() {
+            Some(buffer) => buffer,
+            None if pool.capacity.is_some_and(|capacity| pool.fresh_blocks >= capacity) => {
+                return Err(CoreError::PoolExhausted {
+                    block_size: pool.block_size,
+                    blocks: pool.fresh_blocks,
+                });
+            }
+            None => {
+                pool.fresh_blocks += 1;
+                Vec::with_capacity(pool.block_size)
+            }
+        };
+        pool.in_use += 1;
+        pool.peak_in_use = pool.peak_in_use.max(pool.in_use);
+        buffer.clear();
+        buffer.resize(size, 0);
+        Ok((buffer, true))
+    }
+    
+    /// Return the buffer just removed from region `key` to the free list if
+    /// it is a pool block; other buffers are dropped
+    ///
+    /// A block that grew past the block size still frees its slot, so a
+    /// fixed pool never loses one.
+    fn recycle(&mut self, key: &str, buffer: Vec<u8>) {
+        if let Some(pool) = self.pool.as_mut() {
+            if pool.owned.remove(key) {
+                pool.free.push(buffer);
+                pool.in_use = pool.in_use.saturating_sub(1);
+            }
+        }
+    }
+    
+    /// Read data from shared memory
+    pub fn read(&self, key: &str) -> Option<&[u8]> {
+        self.expiry.touch(key);
+        self.shared_memory.get(key)
+    }
+    
+    /// Decode a region as little-endian elements of type `T`
+    ///
+    /// A tagged region must carry `T`'s tag or this fails with
+    /// `CoreError::TypeMismatch`; untagged regions are decoded as requested.
+    pub fn read_typed<T: RegionElement>(&self, key: &str) -> Result<Vec<T>, CoreError> {
+        let data = self
+            .read(key)
+            .ok_or_else(|| CoreError::MemoryKeyMissing(key.to_string()))?;
+        if let Some(actual) = self.region_type(key) {
+            if actual != T::TYPE {
+                return Err(CoreError::TypeMismatch {
+                    key: key.to_string(),
+                    expected: T::TYPE,
+                    actual,
+                });
+            }
+        }
+        let size = T::TYPE.size();
+        if !data.len().is_multiple_of(size) {
+            return Err(CoreError::MemoryError(format!(
+                "region '{}' holds {} bytes, not a whole number of {} elements",
+                key,
+                data.len(),
+                T::TYPE
+            )));
+        }
+        Ok(data.chunks_exact(size).map(T::from_le_slice).collect())
+    }
+    
+    /// Writable view of an existing region, for filling it in place
+    ///
+    /// The borrow keeps every other access to the manager out until it
+    /// ends, so the view acts as an exclusive write guard.
+    pub fn region_mut(&mut self, key: &str) -> Result<&mut [u8], CoreError> {
+        self.check_writable(key)?;
+        self.expiry.touch(key);
+        self.shared_memory
+            .get_mut(key)
+            .ok_or_else(|| CoreError::MemoryKeyMissing(key.to_string()))
+    }
+    
+    /// Change a region's length, keeping its leading bytes and zeroing any
+    /// new ones
+    ///
+    /// A region tagged with `allocate_typed` must stay a whole number of
+    /// elements. Tags, flags and references are kept.
+    pub fn resize(&mut self, key: &str, len: usize) -> Result<(), CoreError> {
+        self.check_writable(key)?;
+        if let Some(region_type) = self.region_type(key) {
+            if !len.is_multiple_of(region_type.size()) {
+                return Err(CoreError::InvalidParameter(format!(
+                    "region '{}' holds {} elements, {} bytes is not a whole number of them",
+                    key, region_type, len
+                )));
+            }
+        }
+        let current = self
+            .shared_memory
+            .get(key)
+            .ok_or_else(|| CoreError::MemoryKeyMissing(key.to_string()))?
+            .len();
+        self.expiry.touch(key);
+        if len > current {
+            self.shared_memory.extend(key, &vec![0u8; len - current]);
+        } else if len < current {
+            if let Some(mut buffer) = self.shared_memory.remove(key) {
+                buffer.truncate(len);
+                self.shared_memory.put(key, buffer);
+            }
+        }
+        Ok(())
+    }
+    
+    /// Detach a writable region's buffer for exclusive in-place work
+    ///
+    /// The region's tags, flags and references are kept; only the bytes are
+    /// absent until `restore_region` puts them back.
+    #[cfg(feature = "std")]
+    pub(crate) fn take_region(&mut self, key: &str) -> Result<Vec<u8>, CoreError> {
+        self.check_writable(key)?;
+        self.expiry.touch(key);
+        self.shared_memory
+            .remove(key)
+            .ok_or_else(|| CoreError::MemoryKeyMissing(key.to_string()))
+    }
+    
+    /// Reattach a buffer detached with `take_region` or `lend_region`
+    #[cfg(feature = "std")]
+    pub(crate) fn restore_region(&mut self, key: &str, buffer: Vec<u8>) {
+        self.shared_memory.put(key, buffer);
+    }
+    
+    /// Detach a region's buffer to read it while the manager is borrowed
+    /// mutably
+    ///
+    /// Unlike `take_region` the region need not be writable, as the buffer
+    /// must go back unchanged through `restore_region`.
+    #[cfg(feature = "std")]
+    pub(crate) fn lend_region(&mut self, key: &str) -> Result<Vec<u8>, CoreError> {
+        self.expiry.touch(key);
+        self.shared_memory
+            .remove(key)
+            .ok_or_else(|| CoreError::MemoryKeyMissing(key.to_string()))
+    }
+    
+    /// Make `buffer` the contents of region `key`, whatever its length,
+    /// without copying it
+    ///
+    /// Like `write`, a missing key is created per the missing-key policy.
+    #[cfg(feature = "std")]
+    pub(crate) fn replace_region(&mut self, key: &str, buffer: Vec<u8>) -> Result<(), CoreError> {
+        self.check_writable(key)?;
+        if self.shared_memory.get(key).is_none() && self.missing_key_policy == MissingKeyPolicy::Error {
+            return Err(CoreError::MemoryKeyMissing(key.to_string()));
+        }
+        self.expiry.touch(key);
+        if let Some(old) = self.shared_memory.put(key, buffer) {
+            self.recycle(key, old);
+        }
+        Ok(())
+    }
+    
+    /// Take a counted reference to a region, keeping it from being freed
+    ///
+    /// The reference is released when the returned `RegionRef` is dropped.
+    /// References only count holders; they never point back at the manager,
+    /// so holding one cannot create a reference cycle.
+    pub fn acquire(&mut self, key: &str) -> Result<RegionRef, CoreError> {
+        if !self.shared_memory.contains(key) {
+            return Err(CoreError::MemoryKeyMissing(key.to_string()));
+        }
+        self.expiry.touch(key);
+        let token = self.region_refs.entry(key.to_string()).or_default();
+        Ok(RegionRef {
+            key: key.to_string(),
+            _token: Arc::clone(token),
+        })
+    }
+    
+    /// Number of live references to a region
+    pub fn ref_count(&self, key: &str) -> usize {
+        self.region_refs
+            .get(key)
+            .map_or(0, |token| Arc::strong_count(token) - 1)
+    }
+    
+    /// Free a region, refusing while references to it are still held
+    pub fn deallocate(&mut self, key: &str) -> Result<(), CoreError> {
+        if !self.shared_memory.contains(key) {
+            return Err(CoreError::MemoryKeyMissing(key.to_string()));
+        }
+        if self.readonly.contains(key) {
+            return Err(CoreError::PermissionDenied(format!("region '{}' is read-only", key)));
+        }
+        self.check_unreferenced(key)?;
+        self.free_region(key);
+        Ok(())
+    }
+    
+    /// Remove a region along with its flags, recycling its buffer
+    fn free_region(&mut self, key: &str) {
+        self.region_refs.remove(key);
+        self.readonly.remove(key);
+        self.frozen.remove(key);
+        self.region_types.remove(key);
+        self.expiry.forget(key);
+        if let Some(buffer) = self.shared_memory.remove(key) {
+            self.recycle(key, buffer);
+        }
+    }
+    
+    /// Report how memory is distributed across the shared regions
+    pub fn stats(&self) -> MemoryStats {
+        let regions: BTreeMap<String, usize> = self
+            .shared_memory
+            .iter()
+            .map(|(key, buffer)| (key.to_string(), buffer.len()))
+            .collect();
+        let sizes: Vec<usize> = regions.values().copied().collect();
+        MemoryStats {
+            total_bytes: sizes.iter().sum(),
+            region_count: self.shared_memory.len(),
+            largest_region: sizes.iter().copied().max().unwrap_or(0),
+            smallest_region: sizes.iter().copied().min().unwrap_or(0),
+            pool: self.pool.as_ref().map(|pool| PoolStats {
+                block_size: pool.block_size,
+                free_blocks: pool.free.len(),
+                fresh_blocks: pool.fresh_blocks,
+                capacity: pool.capacity,
+                in_use: pool.in_use,
+                peak_in_use: pool.peak_in_use,
+            }),
+            regions,
+        }
+    }
+    
+    /// Write data to shared memory
+    pub fn write(&mut self, key: &str, data: &[u8]) -> Result<(), CoreError> {
+        self.check_writable(key)?;
+        self.expiry.touch(key);
+        if let Some(buffer) = self.shared_memory.get_mut(key) {
+            if buffer.len() >= data.len() {
+                buffer[..data.len()].copy_from_slice(data);
+                self.shared_memory.flush();
+                Ok(())
+            } else {
+                Err(CoreError::MemoryError(format!(
+                    "buffer '{}' holds {} bytes, {} written",
+                    key,
+                    buffer.len(),
+                    data.len()
+                )))
+            }
+        } else {
+            match self.missing_key_policy {
+                MissingKeyPolicy::AutoCreate => {
+                    let (mut buffer, pooled) = self.take_buffer(data.len())?;
+                    buffer.copy_from_slice(data);
+                    self.store_region(key, buffer, pooled);
+                    Ok(())
+                }
+                MissingKeyPolicy::Error => Err(CoreError::MemoryKeyMissing(key.to_string())),
+            }
+        }
+    }
+    
+    /// Extend a region with `data`, creating it per the missing-key policy
+    ///
+    /// The region grows geometrically, so repeated appends cost amortized
+    /// O(1) per byte.
+    pub fn append(&mut self, key: &str, data: &[u8]) -> Result<(), CoreError> {
+        self.check_writable(key)?;
+        self.expiry.touch(key);
+        if self.shared_memory.extend(key, data) {
+            Ok(())
+        } else {
+            self.write(key, data)
+        }
+    }
+    
+    /// Append `data` to a protected region, creating it if needed
+    ///
+    /// Takes `&self` so several threads can log into one manager at once:
+    /// the region's lock is held for the whole append, so each call's bytes
+    /// land contiguously and concurrent records never interleave. Waits while
+    /// a `lock_protected_multi` guard holds the region.
+    #[cfg(feature = "std")]
+    pub fn append_protected(&self, key: &str, data: &[u8]) -> Result<(), CoreError> {
+        self.protected_memory.append(key, data)
+    }
+    
+    /// Copy of a protected region's contents
+    #[cfg(feature = "std")]
+    pub fn read_protected(&self, key: &str) -> Result<Option<Vec<u8>>, CoreError> {
+        self.protected_memory.read(key)
+    }
+    
+    /// Overwrite part of an existing region, starting at byte `offset`
+    pub fn write_range(&mut self, key: &str, offset: usize, data: &[u8]) -> Result<(), CoreError> {
+        self.check_writable(key)?;
+        self.expiry.touch(key);
+        let buffer = self
+            .shared_memory
+            .get_mut(key)
+            .ok_or_else(|| CoreError::MemoryKeyMissing(key.to_string()))?;
+        let end = offset
+            .checked_add(data.len())
+            .filter(|&end| end <= buffer.len())
+            .ok_or_else(|| {
+                CoreError::MemoryError(format!(
+                    "buffer '{}' holds {} bytes, {} written at offset {}",
+                    key,
+                    buffer.len(),
+                    data.len(),
+                    offset
+                ))
+            })?;
+        buffer[offset..end].copy_from_slice(data);
+        self.shared_memory.flush();
+        Ok(())
+    }
+    
+    /// Publish writes made into slices from `allocate` or `region_mut`
+    ///
+    /// Only backends shared with other processes need this; `write`,
+    /// `write_range` and `append` publish their own writes.
+    pub fn flush(&mut self) {
+        self.shared_memory.flush();
+    }
+}
+
+/// Counted reference to a shared region, released on drop
+#[derive(Clone, Debug)]
+pub struct RegionRef {
+    key: String,
+    // Held only so the manager can count live references
+    _token: Arc<()>,
+}
+
+impl RegionRef {
+    /// Key of the referenced region
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+/// Snapshot of shared memory usage, for observability
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryStats {
+    /// Sum of all region sizes in bytes
+    pub total_bytes: usize,
+    /// Number of allocated regions
+    pub region_count: usize,
+    /// Size of the largest region, or 0 when empty
+    pub largest_region: usize,
+    /// Size of the smallest region, or 0 when empty
+    pub smallest_region: usize,
+    /// Free-list occupancy, when the pool strategy is enabled
+    pub pool: Option<PoolStats>,
+    /// Size of each region in bytes, by key
+    #[serde(default)]
+    pub regions: BTreeMap<String, usize>,
+}
+
+/// Free-list counters of a pooled memory manager
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolStats {
+    /// Capacity of each pooled buffer
+    pub block_size: usize,
+    /// Buffers waiting on the free list
+    pub free_blocks: usize,
+    /// Buffers ever obtained from the global allocator
+    pub fresh_blocks: usize,
+    /// Most buffers the pool may hold, for a fixed pool
+    #[serde(default)]
+    pub capacity: Option<usize>,
+    /// Buffers currently backing regions
+    #[serde(default)]
+    pub in_use: usize,
+    /// Most buffers ever backing regions at once
+    #[serde(default)]
+    pub peak_in_use: usize,
+}
+
+impl Default for MemoryManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_typed_read_checks_region_tag() {
+        let mut memory = MemoryManager::new();
+        memory
+            .allocate_typed("samples", RegionType::I16, 2)
+            .unwrap()
+            .copy_from_slice(&[0x01, 0x00, 0xff, 0xff]);
+        assert_eq!(memory.region_type("samples"), Some(RegionType::I16));
+        assert_eq!(memory.read_typed::<i16>("samples").unwrap(), vec![1, -1]);
+        assert_eq!(
+            memory.read_typed::<f32>("samples"),
+            Err(CoreError::TypeMismatch {
+                key: "samples".to_string(),
+                expected: RegionType::F32,
+                actual: RegionType::I16,
+            })
+        );
+        
+        // Reallocating without a type drops the tag
+        memory.allocate("samples", 4).unwrap();
+        assert_eq!(memory.region_type("samples"), None);
+        assert_eq!(memory.read_typed::<f32>("samples").unwrap(), vec![0.0]);
+    }
+    
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_snapshot_round_trip_keeps_tags_and_flags() {
+        let mut memory = MemoryManager::new();
+        memory.allocate_typed("samples", RegionType::I16, 3).unwrap().copy_from_slice(&[1, 0, 2, 0, 3, 0]);
+        memory.write("calibration", b"gain").unwrap();
+        memory.set_readonly("calibration", true).unwrap();
+        
+        let snapshot = memory.snapshot(&compression::Uncompressed).unwrap();
+        let restored = MemoryManager::from_snapshot(&snapshot).unwrap();
+        assert_eq!(restored.read_typed::<i16>("samples").unwrap(), vec![1, 2, 3]);
+        assert_eq!(restored.read("calibration"), Some(&b"gain"[..]));
+        assert!(restored.is_readonly("calibration"));
+        assert_eq!(restored.snapshot(&compression::Uncompressed).unwrap(), snapshot);
+        assert!(MemoryManager::from_snapshot(&snapshot[..snapshot.len() - 1]).is_err());
+    }
+    
+    #[test]
+    fn test_untagged_region_reads_as_any_type() {
+        let mut memory = MemoryManager::new();
+        memory.write("raw", &1.5f32.to_le_bytes()).unwrap();
+        assert_eq!(memory.read_typed::<f32>("raw").unwrap(), vec![1.5]);
+        assert_eq!(memory.read_typed::<u8>("raw").unwrap().len(), 4);
+        assert!(memory.read_typed::<f64>("raw").is_err());
+        assert!(matches!(memory.read_typed::<u8>("absent"), Err(CoreError::MemoryKeyMissing(_))));
+    }
+    
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_concurrent_protected_appends_stay_whole() {
+        const THREADS: u8 = 8;
+        const RECORDS: usize = 500;
+        let memory = MemoryManager::new();
+        std::thread::scope(|scope| {
+            for sensor in 0..THREADS {
+                let memory = &memory;
+                scope.spawn(move || {
+                    for _ in 0..RECORDS {
+                        memory.append_protected("telemetry", &[sensor; 16]).unwrap();
+                    }
+                });
+            }
+        });
+        
+        let log = memory.read_protected("telemetry").unwrap().unwrap();
+        assert_eq!(log.len(), THREADS as usize * RECORDS * 16);
+        let mut per_sensor = [0usize; THREADS as usize];
+        for record in log.chunks_exact(16) {
+            assert!(record.iter().all(|&byte| byte == record[0]), "interleaved record {:?}", record);
+            per_sensor[record[0] as usize] += 1;
+        }
+        assert_eq!(per_sensor, [RECORDS; THREADS as usize]);
+        assert_eq!(memory.read_protected("absent").unwrap(), None);
+    }
+    
+    #[test]
+    fn test_stats_match_allocations() {
+        let mut memory = MemoryManager::new();
+        assert_eq!(memory.stats(), MemoryStats::default());
+        
+        memory.allocate("a", 16).unwrap();
+        memory.allocate("b", 256).unwrap();
+        memory.write("c", &[1, 2, 3]).unwrap();
+        
+        let stats = memory.stats();
+        assert_eq!(stats.total_bytes, 16 + 256 + 3);
+        assert_eq!(stats.region_count, 3);
+        assert_eq!(stats.largest_region, 256);
+        assert_eq!(stats.smallest_region, 3);
+        
+        assert_eq!(stats.regions.get("b"), Some(&256));
+        
+        let json = serde_json::to_string(&stats).unwrap();
+        assert!(json.contains("\"total_bytes\":275"));
+    }
+    
+    #[test]
+    fn test_region_lifetime_resize_and_adopt() {
+        let mut memory = MemoryManager::new();
+        memory.allocate("frame", 4).unwrap();
+        memory.region_mut("frame").unwrap().copy_from_slice(&[1, 2, 3, 4]);
+        memory.resize("frame", 6).unwrap();
+        assert_eq!(memory.read("frame"), Some(&[1, 2, 3, 4, 0, 0][..]));
+        memory.resize("frame", 2).unwrap();
+        assert_eq!(memory.read("frame"), Some(&[1, 2][..]));
+        assert!(matches!(memory.resize("absent", 2), Err(CoreError::MemoryKeyMissing(_))));
+        
+        memory.allocate_typed("ticks", RegionType::U32, 2).unwrap();
+        assert!(memory.resize("ticks", 6).is_err());
+        memory.resize("ticks", 12).unwrap();
+        assert_eq!(memory.read_typed::<u32>("ticks").unwrap(), vec![0, 0, 0]);
+        
+        // The adopted buffer is the region: no bytes move
+        let dma = vec![7u8; 64];
+        let ptr = dma.as_ptr();
+        let region = memory.adopt("dma", dma, 1).unwrap();
+        assert_eq!(region.as_ptr(), ptr);
+        assert!(ptr.addr().is_multiple_of(memory.alignment("dma").unwrap()));
+        let buffer = vec![0u8; 8];
+        let over_aligned = 2 << buffer.as_ptr().addr().trailing_zeros();
+        assert!(memory.adopt("other", buffer, over_aligned).is_err());
+        assert!(memory.adopt("dma", vec![0; 4], 3).is_err());
+        
+        memory.set_readonly("dma", true).unwrap();
+        assert!(memory.region_mut("dma").is_err());
+        assert!(memory.resize("dma", 8).is_err());
+        memory.deallocate("frame").unwrap();
+        assert!(!memory.stats().regions.contains_key("frame"));
+    }
+    
+    #[test]
+    fn test_reserve_prevents_heap_growth() {
+        let mut memory = MemoryManager::with_pool(64);
+        memory.reserve(640);
+        let reserved = memory.stats().pool.unwrap();
+        assert_eq!(reserved.free_blocks, 10);
+        assert_eq!(reserved.fresh_blocks, 10);
+        
+        for i in 0..10 {
+            memory.allocate(&format!("region_{}", i), 16 + i).unwrap();
+        }
+        let pool = memory.stats().pool.unwrap();
+        assert_eq!(pool.fresh_blocks, 10, "allocations after reserve grew the heap");
+        assert_eq!(pool.free_blocks, 0);
+        
+        // Freed blocks return to the free list and are reused
+        memory.deallocate("region_0").unwrap();
+        assert_eq!(memory.stats().pool.unwrap().free_blocks, 1);
+        memory.write("fresh", &[1, 2, 3]).unwrap();
+        assert_eq!(memory.stats().pool.unwrap().fresh_blocks, 10);
+    }
+    
+    #[test]
+    fn test_fixed_pool_reports_exhaustion_and_high_water_mark() {
+        let mut memory = MemoryManager::with_fixed_pool(32, 3);
+        let pool = memory.stats().pool.unwrap();
+        assert_eq!((pool.fresh_blocks, pool.free_blocks, pool.capacity), (3, 3, Some(3)));
+        
+        for key in ["a", "b", "c"] {
+            memory.allocate(key, 16).unwrap();
+        }
+        assert_eq!(
+            memory.allocate("d", 8).err(),
+            Some(CoreError::PoolExhausted { block_size: 32, blocks: 3 })
+        );
+        assert_eq!(memory.write("d", &[1]), Err(CoreError::PoolExhausted { block_size: 32, blocks: 3 }));
+        assert_eq!(memory.try_allocate("d", 64).err(), Some(CoreError::AllocationFailed { size: 64 }));
+        
+        // Freed and replaced regions go back to the pool; nothing new is allocated
+        memory.deallocate("a").unwrap();
+        memory.allocate("b", 24).unwrap();
+        memory.clear();
+        memory.allocate("d", 8).unwrap();
+        let pool = memory.stats().pool.unwrap();
+        assert_eq!((pool.fresh_blocks, pool.free_blocks), (3, 2));
+        assert_eq!((pool.in_use, pool.peak_in_use), (1, 3));
+    }
+    
+    #[test]
+    fn test_fixed_pool_tracks_grown_and_foreign_buffers() {
+        let mut memory = MemoryManager::with_fixed_pool(8, 1);
+        memory.allocate("log", 4).unwrap();
+        memory.append("log", &[1; 32]).unwrap();
+        memory.deallocate("log").unwrap();
+        let pool = memory.stats().pool.unwrap();
+        assert_eq!((pool.in_use, pool.free_blocks), (0, 1), "the grown block lost its slot");
+        memory.allocate("again", 8).unwrap();
+        
+        // A buffer the pool never handed out is not taken for one of its blocks
+        let foreign = Vec::with_capacity(8);
+        assert_eq!(foreign.capacity(), 8);
+        memory.adopt("dma", foreign, 1).unwrap();
+        memory.deallocate("dma").unwrap();
+        let pool = memory.stats().pool.unwrap();
+        assert_eq!((pool.in_use, pool.free_blocks, pool.fresh_blocks), (1, 0, 1));
+        assert!(matches!(memory.allocate("more", 8), Err(CoreError::PoolExhausted { .. })));
+    }
+    
+    #[test]
+    fn test_shrink_to_fit_releases_free_blocks() {
+        let mut memory = MemoryManager::with_pool(32);
+        memory.reserve(128);
+        memory.allocate("kept", 8).unwrap();
+        memory.shrink_to_fit();
+        
+        assert_eq!(memory.stats().pool.unwrap().free_blocks, 0);
+        assert_eq!(memory.read("kept"), Some(&[0u8; 8][..]));
+        
+        // Without a pool, reserve only grows the region map and shrinking
+        // keeps data intact
+        let mut heap = MemoryManager::new();
+        heap.reserve(1024);
+        heap.write("data", &[1, 2]).unwrap();
+        heap.shrink_to_fit();
+        assert_eq!(heap.read("data"), Some(&[1, 2][..]));
+        assert!(heap.stats().pool.is_none());
+    }
+    
+    #[test]
+    fn test_try_allocate_reports_failure_instead_of_aborting() {
+        let mut memory = MemoryManager::new();
+        memory.write("frame", &[7; 4]).unwrap();
+        for size in [usize::MAX, isize::MAX as usize] {
+            assert_eq!(memory.try_allocate("frame", size).err(), Some(CoreError::AllocationFailed { size }));
+        }
+        assert_eq!(memory.read("frame"), Some(&[7u8; 4][..]));
+        
+        assert_eq!(memory.try_allocate("frame", 16).unwrap(), &[0u8; 16][..]);
+        assert_eq!(memory.stats().total_bytes, 16);
+    }
+    
+    #[test]
+    fn test_allocate_existing_key_replaces_by_default() {
+        let mut memory = MemoryManager::new();
+        memory.allocate("frame", 4).unwrap().copy_from_slice(&[1, 2, 3, 4]);
+        assert_eq!(memory.allocate("frame", 2).unwrap(), &[0, 0]);
+        assert_eq!(memory.read("frame"), Some(&[0, 0][..]));
+    }
+    
+    #[test]
+    fn test_allocate_existing_key_errors_with_strict_keys() {
+        let mut memory = MemoryManager::new();
+        memory.set_strict_keys(true);
+        memory.allocate("frame", 4).unwrap().copy_from_slice(&[1, 2, 3, 4]);
+        assert_eq!(
+            memory.allocate("frame", 2),
+            Err(CoreError::KeyAlreadyExists("frame".to_string()))
+        );
+        assert_eq!(memory.read("frame"), Some(&[1, 2, 3, 4][..]));
+        
+        memory.deallocate("frame").unwrap();
+        assert_eq!(memory.allocate("frame", 2).unwrap(), &[0, 0]);
+    }
+    
+    #[test]
+    fn test_write_missing_key_auto_creates_by_default() {
+        let mut memory = MemoryManager::new();
+        memory.write("typo", &[1, 2]).unwrap();
+        assert_eq!(memory.read("typo"), Some(&[1, 2][..]));
+    }
+    
+    #[test]
+    fn test_write_missing_key_errors_under_strict_policy() {
+        let mut memory = MemoryManager::new();
+        memory.set_missing_key_policy(MissingKeyPolicy::Error);
+        
+        assert_eq!(memory.write("typo", &[1]), Err(CoreError::MemoryKeyMissing("typo".to_string())));
+        assert!(memory.read("typo").is_none());
+        
+        memory.allocate("real", 4).unwrap();
+        assert!(memory.write("real", &[1]).is_ok());
+    }
+    
+    #[test]
+    fn test_write_range_updates_slice() {
+        let mut memory = MemoryManager::new();
+        memory.write("frame", &[0; 4]).unwrap();
+        memory.write_range("frame", 1, &[7, 8]).unwrap();
+        assert_eq!(memory.read("frame"), Some(&[0, 7, 8, 0][..]));
+        assert!(memory.write_range("frame", 3, &[1, 2]).is_err());
+        assert_eq!(
+            memory.write_range("absent", 0, &[1]),
+            Err(CoreError::MemoryKeyMissing("absent".to_string()))
+        );
+    }
+    
+    #[test]
+    fn test_append_accumulates_chunks() {
+        let mut memory = MemoryManager::new();
+        for chunk in [&b"ab"[..], b"", b"cde", b"f"] {
+            memory.append("log", chunk).unwrap();
+        }
+        assert_eq!(memory.read("log"), Some(&b"abcdef"[..]));
+        
+        memory.set_missing_key_policy(MissingKeyPolicy::Error);
+        assert!(matches!(memory.append("absent", b"x"), Err(CoreError::MemoryKeyMissing(_))));
+        memory.append("log", b"g").unwrap();
+        assert_eq!(memory.read("log"), Some(&b"abcdefg"[..]));
+    }
+    
+    #[test]
+    fn test_readonly_region_rejects_writes() {
+        let mut memory = MemoryManager::new();
+        memory.write("calibration", &[1, 2, 3]).unwrap();
+        memory.set_readonly("calibration", true).unwrap();
+        
+        assert!(matches!(memory.write("calibration", &[9]), Err(CoreError::PermissionDenied(_))));
+        assert!(matches!(
+            memory.write_range("calibration", 0, &[9]),
+            Err(CoreError::PermissionDenied(_))
+        ));
+        assert_eq!(memory.read("calibration"), Some(&[1, 2, 3][..]));
+        
+        // Nor can it be replaced or freed behind the flag
+        assert!(matches!(memory.allocate("calibration", 8), Err(CoreError::PermissionDenied(_))));
+        assert!(matches!(memory.try_allocate("calibration", 8), Err(CoreError::PermissionDenied(_))));
+        assert!(matches!(memory.adopt("calibration", vec![9], 1), Err(CoreError::PermissionDenied(_))));
+        assert!(matches!(memory.deallocate("calibration"), Err(CoreError::PermissionDenied(_))));
+        assert_eq!(memory.read("calibration"), Some(&[1, 2, 3][..]));
+        
+        memory.set_readonly("calibration", false).unwrap();
+        memory.write("calibration", &[9]).unwrap();
+        assert_eq!(memory.read("calibration"), Some(&[9, 2, 3][..]));
+        assert!(memory.set_readonly("absent", true).is_err());
+        memory.deallocate("calibration").unwrap();
+    }
+    
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_frozen_region_rejects_writes_but_reads() {
+        let mut memory = MemoryManager::new();
+        memory.write("calibration", &[1, 2, 3]).unwrap();
+        memory.freeze("calibration").unwrap();
+        assert!(memory.is_frozen("calibration"));
+        
+        let frozen = Err(CoreError::RegionFrozen("calibration".to_string()));
+        assert_eq!(memory.write("calibration", &[9]), frozen);
+        assert_eq!(memory.write_range("calibration", 1, &[9]), frozen);
+        assert_eq!(memory.append("calibration", &[9]), frozen);
+        assert_eq!(memory.allocate("calibration", 8).map(|_| ()), frozen);
+        assert_eq!(memory.read("calibration"), Some(&[1, 2, 3][..]));
+        
+        let restored = MemoryManager::from_snapshot(&memory.snapshot(&compression::Uncompressed).unwrap()).unwrap();
+        assert!(restored.is_frozen("calibration"));
+        assert!(!restored.is_readonly("calibration"));
+        
+        assert_eq!(memory.freeze("absent"), Err(CoreError::MemoryKeyMissing("absent".to_string())));
+        assert!(!memory.is_frozen("absent"));
+    }
+    
+    #[test]
+    fn test_acquire_and_drop_balance_refs() {
+        let mut memory = MemoryManager::new();
+        memory.allocate("table", 8).unwrap();
+        
+        let first = memory.acquire("table").unwrap();
+        let second = first.clone();
+        assert_eq!(memory.ref_count("table"), 2);
+        assert_eq!(second.key(), "table");
+        
+        drop(first);
+        assert_eq!(memory.ref_count("table"), 1);
+        drop(second);
+        assert_eq!(memory.ref_count("table"), 0);
+        
+        memory.deallocate("table").unwrap();
+        assert!(memory.read("table").is_none());
+        assert!(matches!(memory.acquire("table"), Err(CoreError::MemoryKeyMissing(_))));
+    }
+    
+    #[test]
+    fn test_deallocate_while_referenced_errors() {
+        let mut memory = MemoryManager::new();
+        memory.allocate("table", 8).unwrap();
+        let region = memory.acquire("table").unwrap();
+        
+        assert_eq!(
+            memory.deallocate("table"),
+            Err(CoreError::RegionInUse {
+                key: "table".to_string(),
+                refs: 1
+            })
+        );
+        assert!(memory.read("table").is_some());
+        
+        drop(region);
+        assert!(memory.deallocate("table").is_ok());
+    }
+    
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_fork_changes_include_deallocated_regions() {
+        let mut base = MemoryManager::new();
+        for key in ["kept", "dropped", "pinned"] {
+            base.write(key, &[1]).unwrap();
+        }
+        let pin = base.acquire("pinned").unwrap();
+        
+        let mut fork = base.fork();
+        fork.write("new", &[2]).unwrap();
+        fork.deallocate("dropped").unwrap();
+        fork.deallocate("pinned").unwrap();
+        let mut changes = fork.changes_since(&base);
+        changes.sort();
+        assert_eq!(
+            changes,
+            vec![
+                ("dropped".to_string(), None),
+                ("new".to_string(), Some(vec![2])),
+                ("pinned".to_string(), None),
+            ]
+        );
+        
+        // A region still referenced in the base survives the merge
+        base.apply_changes(changes);
+        assert_eq!(base.read("dropped"), None);
+        assert_eq!(base.read("new"), Some(&[2][..]));
+        assert_eq!(base.read("kept"), Some(&[1][..]));
+        assert_eq!(base.read("pinned"), Some(&[1][..]));
+        drop(pin);
+    }
+    
+    #[test]
+    fn test_referenced_region_is_not_replaced_or_cleared() {
+        let mut memory = MemoryManager::new();
+        memory.write("table", &[1, 2]).unwrap();
+        memory.write("scratch", &[3]).unwrap();
+        memory.set_readonly("table", true).unwrap();
+        let region = memory.acquire("table").unwrap();
+        
+        let in_use = Err(CoreError::RegionInUse {
+            key: "table".to_string(),
+            refs: 1,
+        });
+        memory.set_readonly("table", false).unwrap();
+        assert_eq!(memory.allocate("table", 8).map(|_| ()), in_use);
+        assert_eq!(memory.try_allocate("table", 8).map(|_| ()), in_use);
+        assert_eq!(memory.adopt("table", vec![0; 8], 1).map(|_| ()), in_use);
+        
+        // Only the referenced region survives a clear
+        assert_eq!(memory.clear(), ["table"]);
+        assert_eq!(memory.read("table"), Some(&[1, 2][..]));
+        assert_eq!(memory.read("scratch"), None);
+        assert_eq!(memory.ref_count("table"), 1);
+        
+        drop(region);
+        assert!(memory.clear().is_empty());
+        assert_eq!(memory.read("table"), None);
+    }
+    
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_thread_local_arenas_are_isolated() {
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                std::thread::spawn(move || {
+                    let key = format!("worker_{}", i);
+                    MemoryManager::thread_local(|memory| memory.write(&key, &[i as u8]).unwrap()).unwrap();
+                    
+                    // Later calls on the same thread see the same arena, and only it
+                    MemoryManager::thread_local(|memory| {
+                        assert_eq!(memory.read(&key), Some(&[i as u8][..]));
+                        assert_eq!(memory.stats().region_count, 1);
+                    })
+                    .unwrap();
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+    
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_nested_thread_local_access_errors() {
+        let nested = MemoryManager::thread_local(|_| MemoryManager::thread_local(|_| ())).unwrap();
+        assert!(nested.is_err());
+    }
+}
@@ -0,0 +1,137 @@
+//! Diffing a region against a baseline to send only what changed
+
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+use super::MemoryManager;
+use crate::error::CoreError;
+
+/// Unchanged runs shorter than this are folded into the surrounding
+/// patches, as a separate patch would cost more than resending the bytes
+const MERGE_GAP: usize = 8;
+
+/// Bytes replacing a region's contents starting at `offset`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegionPatch {
+    pub offset: usize,
+    pub bytes: Vec<u8>,
+}
+
+impl MemoryManager {
+    /// Spans of region `key` that differ from `baseline`, e.g. the copy a
+    /// remote peer last received
+    ///
+    /// Applying the patches to `baseline` reproduces the region, so
+    /// identical buffers give no patches. Changed spans separated by only a
+    /// few unchanged bytes come out as one patch. The region and baseline
+    /// must be the same length.
+    pub fn diff(&self, key: &str, baseline: &[u8]) -> Result<Vec<RegionPatch>, CoreError> {
+        let current = self.read(key).ok_or_else(|| CoreError::MemoryKeyMissing(key.to_string()))?;
+        if current.len() != baseline.len() {
+            return Err(CoreError::InvalidInput(format!(
+                "region '{}' holds {} bytes, baseline {}",
+                key,
+                current.len(),
+                baseline.len()
+            )));
+        }
+        
+        let mut patches: Vec<RegionPatch> = Vec::new();
+        let mut index = 0;
+        while index < current.len() {
+            if current[index] == baseline[index] {
+                index += 1;
+                continue;
+            }
+            let start = index;
+            while index < current.len() && current[index] != baseline[index] {
+                index += 1;
+            }
+            match patches.last_mut() {
+                Some(last) if start - (last.offset + last.bytes.len()) < MERGE_GAP => {
+                    last.bytes.extend_from_slice(&current[last.offset + last.bytes.len()..index]);
+                }
+                _ => patches.push(RegionPatch {
+                    offset: start,
+                    bytes: current[start..index].to_vec(),
+                }),
+            }
+        }
+        Ok(patches)
+    }
+    
+    /// Overwrite region `key` with each patch in turn
+    ///
+    /// Every patch is bounds-checked before any is written, so an error
+    /// leaves the region unchanged.
+    pub fn apply_patch(&mut self, key: &str, patches: &[RegionPatch]) -> Result<(), CoreError> {
+        let len = self.read(key).ok_or_else(|| CoreError::MemoryKeyMissing(key.to_string()))?.len();
+        if let Some(patch) = patches
+            .iter()
+            .find(|patch| patch.offset.checked_add(patch.bytes.len()).is_none_or(|end| end > len))
+        {
+            return Err(CoreError::InvalidInput(format!(
+                "patch of {} bytes at offset {} overruns region '{}' of {} bytes",
+                patch.bytes.len(),
+                patch.offset,
+                key,
+                len
+            )));
+        }
+        for patch in patches {
+            self.write_range(key, patch.offset, &patch.bytes)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_patches_reconstruct_target() {
+        let baseline: Vec<u8> = (0..64).collect();
+        let mut target = baseline.clone();
+        target[3] = 0xff;
+        target[5] = 0xff;
+        target[40..44].copy_from_slice(&[9, 9, 9, 9]);
+        target[63] = 0;
+        
+        let mut sender = MemoryManager::new();
+        sender.write("state", &target).unwrap();
+        let patches = sender.diff("state", &baseline).unwrap();
+        assert_eq!(
+            patches,
+            vec![
+                // The one-byte gap at 4 is folded in
+                RegionPatch { offset: 3, bytes: vec![0xff, 4, 0xff] },
+                RegionPatch { offset: 40, bytes: vec![9; 4] },
+                RegionPatch { offset: 63, bytes: vec![0] },
+            ]
+        );
+        
+        let mut receiver = MemoryManager::new();
+        receiver.write("state", &baseline).unwrap();
+        receiver.apply_patch("state", &patches).unwrap();
+        assert_eq!(receiver.read("state"), Some(&target[..]));
+        assert!(receiver.diff("state", &target).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_mismatched_or_overrunning_patches_rejected() {
+        let mut memory = MemoryManager::new();
+        memory.write("state", &[0; 8]).unwrap();
+        assert!(matches!(memory.diff("state", &[0; 4]), Err(CoreError::InvalidInput(_))));
+        assert!(matches!(memory.diff("missing", &[]), Err(CoreError::MemoryKeyMissing(_))));
+        
+        let patches = [
+            RegionPatch { offset: 0, bytes: vec![1] },
+            RegionPatch { offset: 6, bytes: vec![1; 4] },
+        ];
+        assert!(matches!(memory.apply_patch("state", &patches), Err(CoreError::InvalidInput(_))));
+        assert_eq!(memory.read("state"), Some(&[0; 8][..]));
+    }
+}
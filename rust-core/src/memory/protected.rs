@@ -0,0 +1,203 @@
+//! Protected regions shared between a manager and its forks
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Condvar, Mutex, MutexGuard};
+
+use super::MemoryManager;
+use crate::error::CoreError;
+
+/// Protected region bytes, with a lock per region
+///
+/// The map's own mutex is only held for single operations; exclusive
+/// access to a region across operations is tracked by holding its key.
+pub(super) struct ProtectedMemory {
+    regions: Mutex<HashMap<String, Vec<u8>>>,
+    // Keys currently held by a `MultiGuard`
+    held: Mutex<HashSet<String>>,
+    released: Condvar,
+}
+
+impl ProtectedMemory {
+    pub(super) fn new() -> Self {
+        Self {
+            regions: Mutex::new(HashMap::new()),
+            held: Mutex::new(HashSet::new()),
+            released: Condvar::new(),
+        }
+    }
+    
+    fn regions(&self) -> Result<MutexGuard<'_, HashMap<String, Vec<u8>>>, CoreError> {
+        self.regions
+            .lock()
+            .map_err(|_| CoreError::MemoryError("protected memory lock is poisoned".to_string()))
+    }
+    
+    /// Hold the region locks of `keys`, waiting for other holders
+    ///
+    /// Keys are taken in sorted order, so two callers asking for
+    /// overlapping sets can never each hold a key the other is waiting for.
+    fn hold(&self, keys: &[&str]) -> Result<MultiGuard<'_>, CoreError> {
+        let mut keys: Vec<String> = keys.iter().map(|key| key.to_string()).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        let poisoned = || CoreError::MemoryError("protected region locks are poisoned".to_string());
+        let mut held = self.held.lock().map_err(|_| poisoned())?;
+        for (index, key) in keys.iter().enumerate() {
+            while held.contains(key) {
+                held = match self.released.wait(held) {
+                    Ok(held) => held,
+                    Err(e) => {
+                        let mut held = e.into_inner();
+                        for key in &keys[..index] {
+                            held.remove(key);
+                        }
+                        return Err(poisoned());
+                    }
+                };
+            }
+            held.insert(key.clone());
+        }
+        Ok(MultiGuard { protected: self, keys })
+    }
+    
+    pub(super) fn append(&self, key: &str, data: &[u8]) -> Result<(), CoreError> {
+        self.hold(&[key])?.append(key, data)
+    }
+    
+    pub(super) fn read(&self, key: &str) -> Result<Option<Vec<u8>>, CoreError> {
+        let _guard = self.hold(&[key])?;
+        Ok(self.regions()?.get(key).cloned())
+    }
+}
+
+/// Exclusive access to several protected regions, released on drop
+///
+/// Only the regions the guard was created for can be accessed through it.
+pub struct MultiGuard<'a> {
+    protected: &'a ProtectedMemory,
+    // Sorted and unique
+    keys: Vec<String>,
+}
+
+impl MultiGuard<'_> {
+    /// Keys of the held regions, in sorted order
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+    
+    /// Copy of a held region's contents
+    pub fn read(&self, key: &str) -> Result<Vec<u8>, CoreError> {
+        self.check_held(key)?;
+        Ok(self.protected.regions()?.get(key).cloned().unwrap_or_default())
+    }
+    
+    /// Replace a held region's contents
+    pub fn write(&mut self, key: &str, data: &[u8]) -> Result<(), CoreError> {
+        self.check_held(key)?;
+        self.protected.regions()?.insert(key.to_string(), data.to_vec());
+        Ok(())
+    }
+    
+    /// Append `data` to a held region
+    pub fn append(&mut self, key: &str, data: &[u8]) -> Result<(), CoreError> {
+        self.check_held(key)?;
+        self.protected
+            .regions()?
+            .entry(key.to_string())
+            .or_default()
+            .extend_from_slice(data);
+        Ok(())
+    }
+    
+    fn check_held(&self, key: &str) -> Result<(), CoreError> {
+        if self.keys.binary_search_by(|held| held.as_str().cmp(key)).is_err() {
+            return Err(CoreError::PermissionDenied(format!(
+                "protected region '{}' is not held by this guard",
+                key
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for MultiGuard<'_> {
+    fn drop(&mut self) {
+        let mut held = self.protected.held.lock().unwrap_or_else(|e| e.into_inner());
+        for key in &self.keys {
+            held.remove(key);
+        }
+        self.protected.released.notify_all();
+    }
+}
+
+impl MemoryManager {
+    /// Take exclusive access to several protected regions at once,
+    /// creating any that do not exist
+    ///
+    /// The region locks are always acquired in sorted key order, whatever
+    /// order `keys` is given in, so callers locking overlapping sets cannot
+    /// deadlock one another. Repeated keys are held once. While the guard
+    /// lives, other callers' `append_protected`, `read_protected` and
+    /// `lock_protected_multi` on its regions wait; calling them for a held
+    /// region from the guard's own thread therefore blocks forever, so use
+    /// the guard's methods instead.
+    pub fn lock_protected_multi(&self, keys: &[&str]) -> Result<MultiGuard<'_>, CoreError> {
+        let guard = self.protected_memory.hold(keys)?;
+        let mut regions = self.protected_memory.regions()?;
+        for key in &guard.keys {
+            regions.entry(key.clone()).or_default();
+        }
+        drop(regions);
+        Ok(guard)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+    
+    #[test]
+    fn test_opposite_lock_orders_do_not_deadlock() {
+        const ROUNDS: usize = 2000;
+        let memory = std::sync::Arc::new(MemoryManager::new());
+        let (done, finished) = mpsc::channel();
+        for keys in [["left", "right"], ["right", "left"]] {
+            let memory = memory.clone();
+            let done = done.clone();
+            std::thread::spawn(move || {
+                for _ in 0..ROUNDS {
+                    let mut guard = memory.lock_protected_multi(&keys).unwrap();
+                    // Both regions only ever grow together under a guard
+                    assert_eq!(guard.read("left").unwrap().len(), guard.read("right").unwrap().len());
+                    guard.append(keys[0], &[1]).unwrap();
+                    guard.append(keys[1], &[1]).unwrap();
+                }
+                done.send(()).unwrap();
+            });
+        }
+        drop(done);
+        for _ in 0..2 {
+            finished
+                .recv_timeout(Duration::from_secs(30))
+                .expect("lockers deadlocked");
+        }
+        
+        for key in ["left", "right"] {
+            assert_eq!(memory.read_protected(key).unwrap().map(|data| data.len()), Some(2 * ROUNDS));
+        }
+    }
+    
+    #[test]
+    fn test_guard_only_reaches_its_regions() {
+        let memory = MemoryManager::new();
+        let mut guard = memory.lock_protected_multi(&["b", "a", "b"]).unwrap();
+        assert_eq!(guard.keys(), ["a", "b"]);
+        assert_eq!(guard.read("a").unwrap(), Vec::<u8>::new());
+        guard.write("b", &[7]).unwrap();
+        assert!(matches!(guard.append("c", &[1]), Err(CoreError::PermissionDenied(_))));
+        drop(guard);
+        assert_eq!(memory.read_protected("b").unwrap(), Some(vec![7]));
+    }
+}
@@ -0,0 +1,475 @@
+//! Regions shared with other processes through memory-mapped files
+
+use memmap2::{Mmap, MmapMut};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{fence, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use super::{HeapBackend, MemoryBackend, MemoryManager};
+use crate::error::CoreError;
+
+// Identifies a file as a shared region, written last when creating one
+const MAGIC: u64 = u64::from_le_bytes(*b"RCSHMEM1");
+// Header layout: magic, sequence number, length in bytes, then padding so
+// region bytes start 64-byte aligned
+const MAGIC_OFFSET: usize = 0;
+const SEQ_OFFSET: usize = 8;
+const LEN_OFFSET: usize = 16;
+const HEADER: usize = 64;
+// How long `SharedRegionReader::read` retries while a write is in progress
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// A word of a region file's header
+///
+/// The header is shared with other processes, so it is only ever accessed
+/// atomically.
+fn header_word(map: &[u8], offset: usize) -> &AtomicU64 {
+    debug_assert!(offset + 8 <= HEADER && map.len() >= HEADER);
+    // SAFETY: mappings start page-aligned and `offset` is a multiple of 8
+    // within the header, which every mapped region file holds, so the
+    // pointer is valid and aligned for a `u64` as long as `map` is borrowed.
+    // Every process touches header words only through atomics.
+    unsafe { AtomicU64::from_ptr(map.as_ptr().add(offset) as *mut u64) }
+}
+
+/// Directory region files go in by default: `/dev/shm` where it exists, so
+/// they live in RAM, and the temp directory elsewhere
+fn default_dir() -> PathBuf {
+    let shm = Path::new("/dev/shm");
+    if shm.is_dir() {
+        shm.to_path_buf()
+    } else {
+        std::env::temp_dir()
+    }
+}
+
+/// File backing region `key` of `namespace`
+///
+/// Characters other than ASCII letters, digits, `-` and `_` are escaped as
+/// `%XX`, so keys such as `sensor/imu` map to a single file name.
+fn region_path(dir: &Path, namespace: &str, key: &str) -> PathBuf {
+    let mut name = format!("robotics_core.{}.", namespace);
+    for byte in key.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            name.push(char::from(byte));
+        } else {
+            name.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    dir.join(name)
+}
+
+fn check_namespace(namespace: &str) -> Result<(), CoreError> {
+    if namespace.is_empty() || !namespace.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_') {
+        return Err(CoreError::InvalidParameter(format!(
+            "shared memory namespace must be non-empty ASCII letters, digits, '-' or '_', got '{}'",
+            namespace
+        )));
+    }
+    Ok(())
+}
+
+/// A region mapped from its own file, which is removed with the region
+struct MappedRegion {
+    file: File,
+    path: PathBuf,
+    map: MmapMut,
+    // Whether the sequence number is odd for writes through `get_mut`
+    writing: bool,
+}
+
+impl MappedRegion {
+    fn create(path: PathBuf, data: &[u8]) -> std::io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)?;
+        let mut region = Self {
+            map: Self::map(&file, data.len())?,
+            file,
+            path,
+            writing: false,
+        };
+        region.map[HEADER..HEADER + data.len()].copy_from_slice(data);
+        header_word(&region.map, LEN_OFFSET).store(data.len() as u64, Ordering::Relaxed);
+        header_word(&region.map, MAGIC_OFFSET).store(MAGIC, Ordering::Release);
+        Ok(region)
+    }
+    
+    /// Size `file` for `capacity` region bytes and map all of it
+    fn map(file: &File, capacity: usize) -> std::io::Result<MmapMut> {
+        file.set_len((HEADER + capacity) as u64)?;
+        // SAFETY: the file is only ever grown, never truncated, while this
+        // process maps it, so the mapping stays backed by the file. Other
+        // processes only read it, and the seqlock header tells them when
+        // what they read may be torn.
+        unsafe { MmapMut::map_mut(file) }
+    }
+    
+    fn len(&self) -> usize {
+        header_word(&self.map, LEN_OFFSET).load(Ordering::Relaxed) as usize
+    }
+    
+    fn data(&self) -> &[u8] {
+        &self.map[HEADER..HEADER + self.len()]
+    }
+    
+    /// Make the sequence number odd, telling readers a write is under way
+    fn begin_write(&mut self) {
+        if !self.writing {
+            header_word(&self.map, SEQ_OFFSET).fetch_add(1, Ordering::Relaxed);
+            fence(Ordering::Release);
+            self.writing = true;
+        }
+    }
+    
+    /// Make the sequence number even again, publishing the write
+    fn end_write(&mut self) {
+        if self.writing {
+            header_word(&self.map, SEQ_OFFSET).fetch_add(1, Ordering::Release);
+            self.writing = false;
+        }
+    }
+    
+    /// Replace the region's bytes, growing the file if they do not fit
+    fn set(&mut self, data: &[u8]) -> std::io::Result<()> {
+        self.begin_write();
+        let result = self.store_at(0, data);
+        self.end_write();
+        result
+    }
+    
+    fn extend(&mut self, data: &[u8]) -> std::io::Result<()> {
+        self.begin_write();
+        let result = self.store_at(self.len(), data);
+        self.end_write();
+        result
+    }
+    
+    /// Write `data` at `offset` and make the region end after it
+    fn store_at(&mut self, offset: usize, data: &[u8]) -> std::io::Result<()> {
+        let len = offset + data.len();
+        if HEADER + len > self.map.len() {
+            // Grow geometrically so repeated appends stay amortized O(1)
+            let capacity = len.max(2 * (self.map.len() - HEADER));
+            self.map = Self::map(&self.file, capacity)?;
+        }
+        self.map[HEADER + offset..HEADER + len].copy_from_slice(data);
+        header_word(&self.map, LEN_OFFSET).store(len as u64, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+impl Drop for MappedRegion {
+    fn drop(&mut self) {
+        self.end_write();
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Backend keeping each region in a memory-mapped file another process can
+/// open with `SharedRegionReader`
+///
+/// Every region file starts with a versioned header guarding it as a
+/// seqlock: the sequence number is odd while the region is being written,
+/// so readers retry instead of returning torn data. Writes through the
+/// manager's `write`, `write_range` and `append` are published when they
+/// return; writes into slices from `allocate` or `region_mut` are published
+/// by `MemoryManager::flush`. Region files are removed when their region
+/// is, or when the backend is dropped. If a file cannot be created the
+/// region is kept in process memory, invisible to other processes.
+pub struct SharedMemoryBackend {
+    dir: PathBuf,
+    namespace: String,
+    regions: HashMap<String, MappedRegion>,
+    local: HeapBackend,
+}
+
+impl SharedMemoryBackend {
+    /// Share regions under `namespace`, in `/dev/shm` where it exists
+    ///
+    /// Fails with `CoreError::InvalidParameter` unless the namespace is made
+    /// of ASCII letters, digits, `-` and `_`.
+    pub fn new(namespace: &str) -> Result<Self, CoreError> {
+        Self::with_dir(namespace, default_dir())
+    }
+    
+    /// Share regions under `namespace`, with their files in `dir`
+    pub fn with_dir(namespace: &str, dir: impl AsRef<Path>) -> Result<Self, CoreError> {
+        check_namespace(namespace)?;
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+            namespace: namespace.to_string(),
+            regions: HashMap::new(),
+            local: HeapBackend::new(),
+        })
+    }
+    
+    /// Whether a region is visible to other processes
+    pub fn is_shared(&self, key: &str) -> bool {
+        self.regions.contains_key(key)
+    }
+    
+    fn remove_shared(&mut self, key: &str) -> Option<Vec<u8>> {
+        self.regions.remove(key).map(|region| region.data().to_vec())
+    }
+}
+
+impl MemoryBackend for SharedMemoryBackend {
+    fn get(&self, key: &str) -> Option<&[u8]> {
+        match self.regions.get(key) {
+            Some(region) => Some(region.data()),
+            None => self.local.get(key),
+        }
+    }
+    
+    fn get_mut(&mut self, key: &str) -> Option<&mut [u8]> {
+        match self.regions.get_mut(key) {
+            Some(region) => {
+                region.begin_write();
+                let len = region.len();
+                Some(&mut region.map[HEADER..HEADER + len])
+            }
+            None => self.local.get_mut(key),
+        }
+    }
+    
+    fn put(&mut self, key: &str, data: Vec<u8>) -> Option<Vec<u8>> {
+        if let Some(region) = self.regions.get_mut(key) {
+            let previous = region.data().to_vec();
+            match region.set(&data) {
+                Ok(()) => return Some(previous),
+                Err(e) => log::warn!("Moving region '{}' out of shared memory, resizing failed: {}", key, e),
+            }
+        }
+        let previous = self.remove(key);
+        match MappedRegion::create(region_path(&self.dir, &self.namespace, key), &data) {
+            Ok(region) => {
+                self.regions.insert(key.to_string(), region);
+            }
+            Err(e) => {
+                log::warn!("Keeping region '{}' in process memory, sharing failed: {}", key, e);
+                self.local.put(key, data);
+            }
+        }
+        previous
+    }
+    
+    fn remove(&mut self, key: &str) -> Option<Vec<u8>> {
+        self.remove_shared(key).or_else(|| self.local.remove(key))
+    }
+    
+    fn iter(&self) -> Box<dyn Iterator<Item = (&str, &[u8])> + '_> {
+        let shared = self.regions.iter().map(|(key, region)| (key.as_str(), region.data()));
+        Box::new(self.local.iter().chain(shared))
+    }
+    
+    fn len(&self) -> usize {
+        self.local.len() + self.regions.len()
+    }
+    
+    fn contains(&self, key: &str) -> bool {
+        self.regions.contains_key(key) || self.local.contains(key)
+    }
+    
+    fn extend(&mut self, key: &str, data: &[u8]) -> bool {
+        let Some(region) = self.regions.get_mut(key) else {
+            return self.local.extend(key, data);
+        };
+        match region.extend(data) {
+            Ok(()) => true,
+            Err(e) => {
+                log::warn!("Moving region '{}' out of shared memory, growing it failed: {}", key, e);
+                let Some(mut buffer) = self.remove_shared(key) else {
+                    return false;
+                };
+                buffer.extend_from_slice(data);
+                self.local.put(key, buffer);
+                true
+            }
+        }
+    }
+    
+    fn flush(&mut self) {
+        self.regions.values_mut().for_each(MappedRegion::end_write);
+    }
+    
+    fn reserve(&mut self, additional: usize) {
+        self.local.reserve(additional);
+    }
+    
+    fn shrink_to_fit(&mut self, buffers: bool) {
+        self.local.shrink_to_fit(buffers);
+        self.regions.shrink_to_fit();
+    }
+}
+
+/// Read-only view of a region another process shares through a
+/// `SharedMemoryBackend`
+///
+/// The view stays readable after the writer removes the region or exits,
+/// holding the last bytes it published.
+pub struct SharedRegionReader {
+    key: String,
+    file: File,
+    map: Mmap,
+}
+
+impl SharedRegionReader {
+    /// Open region `key` shared under `namespace` in the default directory
+    pub fn open(namespace: &str, key: &str) -> Result<Self, CoreError> {
+        Self::open_in(default_dir(), namespace, key)
+    }
+    
+    /// Open region `key` shared under `namespace` with its file in `dir`
+    ///
+    /// Fails with `CoreError::MemoryKeyMissing` if no such region is shared.
+    pub fn open_in(dir: impl AsRef<Path>, namespace: &str, key: &str) -> Result<Self, CoreError> {
+        check_namespace(namespace)?;
+        let file = File::open(region_path(dir.as_ref(), namespace, key))
+            .map_err(|_| CoreError::MemoryKeyMissing(key.to_string()))?;
+        let map = Self::map(&file)?;
+        if map.len() < HEADER || header_word(&map, MAGIC_OFFSET).load(Ordering::Acquire) != MAGIC {
+            return Err(CoreError::MemoryError(format!("'{}' is not a shared region", key)));
+        }
+        Ok(Self {
+            key: key.to_string(),
+            file,
+            map,
+        })
+    }
+    
+    fn map(file: &File) -> Result<Mmap, CoreError> {
+        // SAFETY: the writer only ever grows region files while they are
+        // shared, so the mapping stays backed by the file; concurrent
+        // writes are detected through the seqlock header and never observed
+        // as a result.
+        Ok(unsafe { Mmap::map(file) }?)
+    }
+    
+    /// Times the region has been written, counting its creation as none
+    pub fn version(&self) -> u64 {
+        header_word(&self.map, SEQ_OFFSET).load(Ordering::Acquire) / 2
+    }
+    
+    /// Copy of the region's bytes as of its last completed write
+    ///
+    /// Retries while a write is in progress, failing with
+    /// `CoreError::MemoryError` if one stays in progress for 100ms.
+    pub fn read(&mut self) -> Result<Vec<u8>, CoreError> {
+        let deadline = Instant::now() + READ_TIMEOUT;
+        loop {
+            let seq = header_word(&self.map, SEQ_OFFSET).load(Ordering::Acquire);
+            if seq.is_multiple_of(2) {
+                let len = header_word(&self.map, LEN_OFFSET).load(Ordering::Relaxed) as usize;
+                if HEADER + len > self.map.len() {
+                    // The writer grew the file past this mapping
+                    self.map = Self::map(&self.file)?;
+                    continue;
+                }
+                let data = self.map[HEADER..HEADER + len].to_vec();
+                fence(Ordering::Acquire);
+                if header_word(&self.map, SEQ_OFFSET).load(Ordering::Relaxed) == seq {
+                    return Ok(data);
+                }
+            }
+            if Instant::now() >= deadline {
+                return Err(CoreError::MemoryError(format!(
+                    "shared region '{}' stayed busy being written",
+                    self.key
+                )));
+            }
+            std::thread::yield_now();
+        }
+    }
+}
+
+impl MemoryManager {
+    /// Create a memory manager whose regions other processes can read with
+    /// `SharedRegionReader`, under `namespace`
+    ///
+    /// Forks made for batch and DAG execution keep their regions in process
+    /// memory; only the manager itself shares them.
+    pub fn with_shared_memory(namespace: &str) -> Result<Self, CoreError> {
+        Ok(Self::with_backend(Box::new(SharedMemoryBackend::new(namespace)?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("robotics_core_shm_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+    
+    #[test]
+    fn test_reader_sees_published_writes_and_growth() {
+        let dir = test_dir("publish");
+        let backend = SharedMemoryBackend::with_dir("vision", &dir).unwrap();
+        let mut memory = MemoryManager::with_backend(Box::new(backend));
+        memory.write("sensor/camera", &[1, 2, 3, 4]).unwrap();
+        assert!(SharedMemoryBackend::with_dir("bad/name", &dir).is_err());
+        
+        let mut reader = SharedRegionReader::open_in(&dir, "vision", "sensor/camera").unwrap();
+        assert_eq!((reader.read().unwrap(), reader.version()), (vec![1, 2, 3, 4], 0));
+        memory.write_range("sensor/camera", 2, &[9, 9]).unwrap();
+        assert_eq!((reader.read().unwrap(), reader.version()), (vec![1, 2, 9, 9], 1));
+        memory.append("sensor/camera", &[5; 100]).unwrap();
+        assert_eq!(reader.read().unwrap().len(), 104);
+        
+        // Writes into a handed-out slice are published by `flush`
+        memory.region_mut("sensor/camera").unwrap()[0] = 7;
+        assert!(reader.read().is_err(), "write in progress until flushed");
+        memory.flush();
+        assert_eq!(reader.read().unwrap()[0], 7);
+        
+        assert!(matches!(
+            SharedRegionReader::open_in(&dir, "vision", "absent"),
+            Err(CoreError::MemoryKeyMissing(_))
+        ));
+        let path = region_path(&dir, "vision", "sensor/camera");
+        assert!(path.exists());
+        drop(memory);
+        assert!(!path.exists(), "region file outlived its backend");
+        assert_eq!(reader.read().unwrap()[0], 7);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_concurrent_reads_are_never_torn() {
+        let dir = test_dir("seqlock");
+        let mut memory = MemoryManager::with_backend(Box::new(SharedMemoryBackend::with_dir("race", &dir).unwrap()));
+        memory.write("frame", &[0; 4096]).unwrap();
+        let mut reader = SharedRegionReader::open_in(&dir, "race", "frame").unwrap();
+        
+        let done = Arc::new(AtomicBool::new(false));
+        let writer = {
+            let done = Arc::clone(&done);
+            std::thread::spawn(move || {
+                for value in 1..=200u8 {
+                    memory.write("frame", &[value; 4096]).unwrap();
+                }
+                done.store(true, Ordering::SeqCst);
+                memory
+            })
+        };
+        while !done.load(Ordering::SeqCst) {
+            if let Ok(frame) = reader.read() {
+                assert!(frame.iter().all(|&b| b == frame[0]), "torn read");
+            }
+        }
+        let _memory = writer.join().unwrap();
+        assert_eq!(reader.read().unwrap(), vec![200; 4096]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,242 @@
+//! Regions too large for RAM, kept in memory-mapped temp files
+
+use memmap2::MmapMut;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::{HeapBackend, MemoryBackend, MemoryManager};
+
+// Distinguishes spill files of one process
+static NEXT_SPILL_FILE: AtomicU64 = AtomicU64::new(0);
+
+/// A region stored in its own temp file, deleted when the region goes
+struct SpilledRegion {
+    file: File,
+    path: PathBuf,
+    map: MmapMut,
+}
+
+impl SpilledRegion {
+    fn create(data: &[u8]) -> std::io::Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "robotics_core_spill_{}_{}",
+            std::process::id(),
+            NEXT_SPILL_FILE.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        let mut region = Self {
+            map: Self::map(&file, data.len())?,
+            file,
+            path,
+        };
+        region.map.copy_from_slice(data);
+        Ok(region)
+    }
+    
+    /// Size `file` to `len` bytes and map all of it
+    fn map(file: &File, len: usize) -> std::io::Result<MmapMut> {
+        file.set_len(len as u64)?;
+        // SAFETY: the file was created by this process under a unique name
+        // and nothing else opens it, so the mapping cannot change or shrink
+        // underneath the slices handed out from it.
+        unsafe { MmapMut::map_mut(file) }
+    }
+    
+    fn extend(&mut self, data: &[u8]) -> std::io::Result<()> {
+        let len = self.map.len();
+        self.map = Self::map(&self.file, len + data.len())?;
+        self.map[len..].copy_from_slice(data);
+        Ok(())
+    }
+}
+
+impl Drop for SpilledRegion {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Backend keeping regions larger than a threshold in memory-mapped temp
+/// files, and the rest in an inner backend
+///
+/// Spilled regions are read and written in place through the mapping, so
+/// only the pages in use stay resident and the OS can page the rest out.
+/// Storing a region still passes its bytes through RAM once, as does
+/// removing one, since `remove` hands the buffer back. If a temp file
+/// cannot be created the region stays in the inner backend instead.
+pub struct SpillBackend {
+    inner: Box<dyn MemoryBackend>,
+    threshold: usize,
+    spilled: HashMap<String, SpilledRegion>,
+}
+
+impl SpillBackend {
+    /// Spill regions of more than `threshold` bytes from `inner`
+    pub fn new(inner: Box<dyn MemoryBackend>, threshold: usize) -> Self {
+        Self {
+            inner,
+            threshold,
+            spilled: HashMap::new(),
+        }
+    }
+    
+    /// Whether a region is currently backed by a temp file
+    pub fn is_spilled(&self, key: &str) -> bool {
+        self.spilled.contains_key(key)
+    }
+    
+    fn remove_spilled(&mut self, key: &str) -> Option<Vec<u8>> {
+        self.spilled.remove(key).map(|region| region.map.to_vec())
+    }
+}
+
+impl MemoryBackend for SpillBackend {
+    fn get(&self, key: &str) -> Option<&[u8]> {
+        match self.spilled.get(key) {
+            Some(region) => Some(&region.map),
+            None => self.inner.get(key),
+        }
+    }
+    
+    fn get_mut(&mut self, key: &str) -> Option<&mut [u8]> {
+        match self.spilled.get_mut(key) {
+            Some(region) => Some(&mut region.map),
+            None => self.inner.get_mut(key),
+        }
+    }
+    
+    fn put(&mut self, key: &str, data: Vec<u8>) -> Option<Vec<u8>> {
+        let previous = self.remove(key);
+        if data.len() > self.threshold {
+            match SpilledRegion::create(&data) {
+                Ok(region) => {
+                    self.spilled.insert(key.to_string(), region);
+                    return previous;
+                }
+                Err(e) => log::warn!("Keeping region '{}' in memory, spilling failed: {}", key, e),
+            }
+        }
+        self.inner.put(key, data);
+        previous
+    }
+    
+    fn remove(&mut self, key: &str) -> Option<Vec<u8>> {
+        self.remove_spilled(key).or_else(|| self.inner.remove(key))
+    }
+    
+    fn iter(&self) -> Box<dyn Iterator<Item = (&str, &[u8])> + '_> {
+        let spilled = self.spilled.iter().map(|(key, region)| (key.as_str(), &region.map[..]));
+        Box::new(self.inner.iter().chain(spilled))
+    }
+    
+    fn len(&self) -> usize {
+        self.inner.len() + self.spilled.len()
+    }
+    
+    fn contains(&self, key: &str) -> bool {
+        self.spilled.contains_key(key) || self.inner.contains(key)
+    }
+    
+    fn extend(&mut self, key: &str, data: &[u8]) -> bool {
+        if let Some(region) = self.spilled.get_mut(key) {
+            match region.extend(data) {
+                Ok(()) => return true,
+                Err(e) => {
+                    // Fall back to growing an in-memory copy
+                    log::warn!("Moving region '{}' back into memory, growing it failed: {}", key, e);
+                    let Some(mut buffer) = self.remove_spilled(key) else {
+                        return false;
+                    };
+                    buffer.extend_from_slice(data);
+                    self.inner.put(key, buffer);
+                    return true;
+                }
+            }
+        }
+        if self.inner.get(key).map(|region| region.len() + data.len() > self.threshold) == Some(true) {
+            let Some(mut buffer) = self.inner.remove(key) else {
+                return false;
+            };
+            buffer.extend_from_slice(data);
+            self.put(key, buffer);
+            return true;
+        }
+        self.inner.extend(key, data)
+    }
+    
+    fn flush(&mut self) {
+        self.inner.flush();
+    }
+    
+    fn reserve(&mut self, additional: usize) {
+        self.inner.reserve(additional);
+    }
+    
+    fn shrink_to_fit(&mut self, buffers: bool) {
+        self.inner.shrink_to_fit(buffers);
+        self.spilled.shrink_to_fit();
+    }
+}
+
+impl MemoryManager {
+    /// Create a memory manager that spills regions of more than `threshold`
+    /// bytes to memory-mapped temp files
+    ///
+    /// Spilling is invisible through the manager's API: spilled regions are
+    /// read, written and appended to like any other. Forks made for batch
+    /// and DAG execution copy regions into RAM, so keep oversized regions
+    /// out of those.
+    pub fn with_spill_threshold(threshold: usize) -> Self {
+        Self::with_backend(Box::new(SpillBackend::new(Box::new(HeapBackend::new()), threshold)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_spilled_region_reads_and_writes_through_file() {
+        let mut backend = SpillBackend::new(Box::new(HeapBackend::new()), 8);
+        backend.put("small", vec![1; 8]);
+        backend.put("large", vec![2; 64]);
+        assert!(!backend.is_spilled("small"));
+        assert!(backend.is_spilled("large"));
+        let path = backend.spilled["large"].path.clone();
+        assert_eq!(fs::metadata(&path).unwrap().len(), 64);
+        
+        let mut memory = MemoryManager::with_backend(Box::new(backend));
+        assert_eq!(memory.read("large"), Some(&[2u8; 64][..]));
+        memory.write_range("large", 60, &[9, 9, 9, 9]).unwrap();
+        memory.append("large", &[7; 16]).unwrap();
+        memory.append("small", &[3; 4]).unwrap();
+        
+        let large = memory.read("large").unwrap();
+        assert_eq!(large.len(), 80);
+        assert_eq!((&large[56..64], &large[64..]), (&[2, 2, 2, 2, 9, 9, 9, 9][..], &[7u8; 16][..]));
+        assert_eq!(memory.read("small"), Some(&[1, 1, 1, 1, 1, 1, 1, 1, 3, 3, 3, 3][..]));
+        assert_eq!(memory.stats().total_bytes, 92);
+        
+        memory.deallocate("large").unwrap();
+        assert_eq!(memory.read("large"), None);
+        assert!(!path.exists(), "spill file outlived its region");
+    }
+    
+    #[test]
+    fn test_manager_spills_allocations_over_threshold() {
+        let mut memory = MemoryManager::with_spill_threshold(16);
+        memory.allocate("frame", 4096).unwrap()[4095] = 1;
+        memory.write("frame", &[5; 10]).unwrap();
+        
+        let frame = memory.read("frame").unwrap();
+        assert_eq!(&frame[..11], &[5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 0]);
+        assert_eq!(frame[4095], 1);
+        assert_eq!(memory.take_region("frame").unwrap().len(), 4096);
+    }
+}
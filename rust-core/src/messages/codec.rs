@@ -0,0 +1,264 @@
+//! The protobuf wire format: tagged varint, fixed-width and
+//! length-delimited fields
+
+use super::Message;
+use crate::error::CoreError;
+
+const VARINT: u32 = 0;
+const FIXED64: u32 = 1;
+const LENGTH_DELIMITED: u32 = 2;
+const FIXED32: u32 = 5;
+
+/// Builds the encoding of one message, field by field
+///
+/// Scalars holding their default value (zero, false or empty) are left
+/// out, as proto3 does; nested messages are always written.
+#[derive(Debug, Default)]
+pub struct Encoder {
+    buf: Vec<u8>,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// The encoded fields
+    pub fn finish(self) -> Vec<u8> {
+        self.buf
+    }
+    
+    fn put_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buf.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.buf.push(value as u8);
+    }
+    
+    fn key(&mut self, field: u32, wire_type: u32) {
+        self.put_varint(u64::from(field) << 3 | u64::from(wire_type));
+    }
+    
+    pub(super) fn put_u64(&mut self, field: u32, value: u64) {
+        self.key(field, VARINT);
+        self.put_varint(value);
+    }
+    
+    pub(super) fn put_f64(&mut self, field: u32, value: f64) {
+        self.key(field, FIXED64);
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+    
+    pub(super) fn put_bytes(&mut self, field: u32, bytes: &[u8]) {
+        self.key(field, LENGTH_DELIMITED);
+        self.put_varint(bytes.len() as u64);
+        self.buf.extend_from_slice(bytes);
+    }
+    
+    /// A `uint64`, `uint32`, `bool` or enum field
+    pub fn uint(&mut self, field: u32, value: u64) {
+        if value != 0 {
+            self.put_u64(field, value);
+        }
+    }
+    
+    /// An `int64` or `int32` field, in two's complement
+    pub fn int(&mut self, field: u32, value: i64) {
+        self.uint(field, value as u64);
+    }
+    
+    pub fn bool(&mut self, field: u32, value: bool) {
+        self.uint(field, u64::from(value));
+    }
+    
+    /// A `double` field
+    pub fn double(&mut self, field: u32, value: f64) {
+        // -0.0 is kept, as its bits are not the default's
+        if value.to_bits() != 0 {
+            self.put_f64(field, value);
+        }
+    }
+    
+    /// A `float` field
+    pub fn float(&mut self, field: u32, value: f32) {
+        if value.to_bits() != 0 {
+            self.key(field, FIXED32);
+            self.buf.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    
+    /// A `repeated float` field, packed
+    pub fn floats(&mut self, field: u32, values: &[f32]) {
+        if !values.is_empty() {
+            let bytes: Vec<u8> = values.iter().flat_map(|value| value.to_le_bytes()).collect();
+            self.put_bytes(field, &bytes);
+        }
+    }
+    
+    pub fn bytes(&mut self, field: u32, bytes: &[u8]) {
+        if !bytes.is_empty() {
+            self.put_bytes(field, bytes);
+        }
+    }
+    
+    pub fn string(&mut self, field: u32, text: &str) {
+        self.bytes(field, text.as_bytes());
+    }
+    
+    /// A nested message field
+    pub fn message<M: Message>(&mut self, field: u32, message: &M) {
+        self.put_bytes(field, &message.encode());
+    }
+}
+
+/// The value of one field as read off the wire, to interpret by the
+/// field's declared type
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldValue<'a> {
+    Varint(u64),
+    Fixed64([u8; 8]),
+    Fixed32([u8; 4]),
+    LengthDelimited(&'a [u8]),
+}
+
+fn wire_error(message: &str) -> CoreError {
+    CoreError::InvalidInput(format!("malformed protobuf message: {}", message))
+}
+
+impl<'a> FieldValue<'a> {
+    fn mismatch(&self, expected: &str) -> CoreError {
+        wire_error(&format!("expected a {} field, got {:?}", expected, self))
+    }
+    
+    /// A `uint64`, `uint32` or enum value
+    pub fn as_u64(&self) -> Result<u64, CoreError> {
+        match *self {
+            FieldValue::Varint(value) => Ok(value),
+            _ => Err(self.mismatch("varint")),
+        }
+    }
+    
+    /// A `uint32` value, failing if it does not fit
+    pub fn as_u32(&self) -> Result<u32, CoreError> {
+        u32::try_from(self.as_u64()?).map_err(|_| wire_error("uint32 field out of range"))
+    }
+    
+    /// An `int64` or `int32` value
+    pub fn as_i64(&self) -> Result<i64, CoreError> {
+        self.as_u64().map(|value| value as i64)
+    }
+    
+    pub fn as_bool(&self) -> Result<bool, CoreError> {
+        self.as_u64().map(|value| value != 0)
+    }
+    
+    /// A `double` value
+    pub fn as_f64(&self) -> Result<f64, CoreError> {
+        match *self {
+            FieldValue::Fixed64(bytes) => Ok(f64::from_le_bytes(bytes)),
+            _ => Err(self.mismatch("double")),
+        }
+    }
+    
+    /// A `float` value
+    pub fn as_f32(&self) -> Result<f32, CoreError> {
+        match *self {
+            FieldValue::Fixed32(bytes) => Ok(f32::from_le_bytes(bytes)),
+            _ => Err(self.mismatch("float")),
+        }
+    }
+    
+    pub fn as_bytes(&self) -> Result<&'a [u8], CoreError> {
+        match *self {
+            FieldValue::LengthDelimited(bytes) => Ok(bytes),
+            _ => Err(self.mismatch("length-delimited")),
+        }
+    }
+    
+    pub fn as_str(&self) -> Result<&'a str, CoreError> {
+        core::str::from_utf8(self.as_bytes()?).map_err(|_| wire_error("string field is not UTF-8"))
+    }
+    
+    /// A nested message
+    pub fn as_message<M: Message>(&self) -> Result<M, CoreError> {
+        M::decode(self.as_bytes()?)
+    }
+    
+    /// Append the values of a `repeated float` field, packed or not
+    pub fn extend_floats(&self, values: &mut Vec<f32>) -> Result<(), CoreError> {
+        match *self {
+            FieldValue::LengthDelimited(bytes) => {
+                if !bytes.len().is_multiple_of(4) {
+                    return Err(wire_error("packed floats are not a multiple of 4 bytes"));
+                }
+                values.extend(bytes.chunks_exact(4).map(|chunk| {
+                    f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])
+                }));
+                Ok(())
+            }
+            _ => {
+                values.push(self.as_f32()?);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Reads the fields of one message in order
+pub struct Decoder<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { rest: bytes }
+    }
+    
+    fn take(&mut self, len: usize) -> Result<&'a [u8], CoreError> {
+        if self.rest.len() < len {
+            return Err(wire_error("truncated"));
+        }
+        let (head, tail) = self.rest.split_at(len);
+        self.rest = tail;
+        Ok(head)
+    }
+    
+    fn varint(&mut self) -> Result<u64, CoreError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(wire_error("varint longer than 10 bytes"))
+    }
+    
+    /// The next field's number and value, or `None` at the end
+    ///
+    /// Fields of any number are returned, so callers skip those they do
+    /// not know, as newer senders may add them.
+    pub fn next_field(&mut self) -> Result<Option<(u32, FieldValue<'a>)>, CoreError> {
+        if self.rest.is_empty() {
+            return Ok(None);
+        }
+        let key = self.varint()?;
+        let field = u32::try_from(key >> 3)
+            .ok()
+            .filter(|&field| field > 0)
+            .ok_or_else(|| wire_error("invalid field number"))?;
+        let value = match (key & 7) as u32 {
+            VARINT => FieldValue::Varint(self.varint()?),
+            FIXED64 => FieldValue::Fixed64(self.take(8)?.try_into().map_err(|_| wire_error("truncated"))?),
+            FIXED32 => FieldValue::Fixed32(self.take(4)?.try_into().map_err(|_| wire_error("truncated"))?),
+            LENGTH_DELIMITED => {
+                let len = usize::try_from(self.varint()?).map_err(|_| wire_error("length out of range"))?;
+                FieldValue::LengthDelimited(self.take(len)?)
+            }
+            wire_type => return Err(wire_error(&format!("unsupported wire type {}", wire_type))),
+        };
+        Ok(Some((field, value)))
+    }
+}
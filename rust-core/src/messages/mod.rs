@@ -0,0 +1,580 @@
+//! Canonical messages for algorithm input and output, in the protobuf
+//! wire format
+//!
+//! The schema lives in `integration/proto/robotics_core/messages.proto`,
+//! from which the Go and Python layers generate their types; the types
+//! here are written against it and encode to the same bytes. Each
+//! implements `Message`, so an algorithm can take one as its input
+//! through `MessageAlgorithm`, and callers can run it with
+//! `CoreEngine::execute_message`.
+//!
+//! Decoding skips fields it does not know, so older readers accept
+//! messages from newer writers.
+
+use serde_json::{Number, Value};
+use std::collections::BTreeMap;
+
+use crate::algorithm::odometry::Pose2D;
+use crate::error::CoreError;
+use crate::sensor::camera::{CameraFrame, PixelFormat};
+use crate::sensor::lidar;
+
+mod adapter;
+mod codec;
+
+pub use adapter::MessageAlgorithm;
+pub use codec::{Decoder, Encoder, FieldValue};
+
+/// Deepest nesting of lists and maps accepted in a `ParameterMap`
+pub const MAX_PARAMETER_DEPTH: usize = 64;
+
+/// A type with a protobuf encoding
+///
+/// Implement `encode_fields` and `merge_field`; `encode` and `decode`
+/// follow from them.
+pub trait Message: Default {
+    /// Write every field to `out`
+    fn encode_fields(&self, out: &mut Encoder);
+    
+    /// Take in one field read off the wire, ignoring unknown field numbers
+    fn merge_field(&mut self, field: u32, value: FieldValue<'_>) -> Result<(), CoreError>;
+    
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Encoder::new();
+        self.encode_fields(&mut out);
+        out.finish()
+    }
+    
+    /// Fails with `CoreError::InvalidInput` on malformed bytes or a field
+    /// of the wrong type
+    fn decode(bytes: &[u8]) -> Result<Self, CoreError> {
+        let mut message = Self::default();
+        let mut decoder = Decoder::new(bytes);
+        while let Some((field, value)) = decoder.next_field()? {
+            message.merge_field(field, value)?;
+        }
+        Ok(message)
+    }
+}
+
+/// When and in which coordinate frame a message was measured
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Header {
+    /// Microseconds, like `SensorFrame::timestamp`
+    pub timestamp: u64,
+    pub frame_id: String,
+}
+
+impl Header {
+    pub fn new(timestamp: u64, frame_id: &str) -> Self {
+        Self {
+            timestamp,
+            frame_id: frame_id.to_string(),
+        }
+    }
+}
+
+impl Message for Header {
+    fn encode_fields(&self, out: &mut Encoder) {
+        out.uint(1, self.timestamp);
+        out.string(2, &self.frame_id);
+    }
+    
+    fn merge_field(&mut self, field: u32, value: FieldValue<'_>) -> Result<(), CoreError> {
+        match field {
+            1 => self.timestamp = value.as_u64()?,
+            2 => self.frame_id = value.as_str()?.to_string(),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vector3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Message for Vector3 {
+    fn encode_fields(&self, out: &mut Encoder) {
+        out.double(1, self.x);
+        out.double(2, self.y);
+        out.double(3, self.z);
+    }
+    
+    fn merge_field(&mut self, field: u32, value: FieldValue<'_>) -> Result<(), CoreError> {
+        match field {
+            1 => self.x = value.as_f64()?,
+            2 => self.y = value.as_f64()?,
+            3 => self.z = value.as_f64()?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// A rotation as a unit quaternion
+///
+/// The default is all zero, as an unset protobuf field decodes, which is
+/// not a rotation; start from `Quaternion::IDENTITY` instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    pub const IDENTITY: Self = Self {
+        w: 1.0,
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    
+    /// Rotation by `yaw` radians about Z
+    pub fn from_yaw(yaw: f64) -> Self {
+        let (sin, cos) = (yaw / 2.0).sin_cos();
+        Self {
+            w: cos,
+            z: sin,
+            ..Self::IDENTITY
+        }
+    }
+    
+    /// Heading about Z, from -π to π
+    pub fn yaw(&self) -> f64 {
+        let Self { w, x, y, z } = *self;
+        (2.0 * (w * z + x * y)).atan2(1.0 - 2.0 * (y * y + z * z))
+    }
+}
+
+impl Message for Quaternion {
+    fn encode_fields(&self, out: &mut Encoder) {
+        out.double(1, self.w);
+        out.double(2, self.x);
+        out.double(3, self.y);
+        out.double(4, self.z);
+    }
+    
+    fn merge_field(&mut self, field: u32, value: FieldValue<'_>) -> Result<(), CoreError> {
+        match field {
+            1 => self.w = value.as_f64()?,
+            2 => self.x = value.as_f64()?,
+            3 => self.y = value.as_f64()?,
+            4 => self.z = value.as_f64()?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Pose {
+    pub header: Header,
+    pub position: Vector3,
+    pub orientation: Quaternion,
+}
+
+impl Pose {
+    /// A pose on the ground plane
+    pub fn from_pose_2d(header: Header, pose: &Pose2D) -> Self {
+        Self {
+            header,
+            position: Vector3 {
+                x: pose.x,
+                y: pose.y,
+                z: 0.0,
+            },
+            orientation: Quaternion::from_yaw(pose.theta),
+        }
+    }
+    
+    /// The pose projected onto the ground plane
+    pub fn to_pose_2d(&self) -> Pose2D {
+        Pose2D {
+            x: self.position.x,
+            y: self.position.y,
+            theta: self.orientation.yaw(),
+        }
+    }
+}
+
+impl Message for Pose {
+    fn encode_fields(&self, out: &mut Encoder) {
+        out.message(1, &self.header);
+        out.message(2, &self.position);
+        out.message(3, &self.orientation);
+    }
+    
+    fn merge_field(&mut self, field: u32, value: FieldValue<'_>) -> Result<(), CoreError> {
+        match field {
+            1 => self.header = value.as_message()?,
+            2 => self.position = value.as_message()?,
+            3 => self.orientation = value.as_message()?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Linear velocity in m/s and angular velocity in rad/s
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Twist {
+    pub header: Header,
+    pub linear: Vector3,
+    pub angular: Vector3,
+}
+
+impl Message for Twist {
+    fn encode_fields(&self, out: &mut Encoder) {
+        out.message(1, &self.header);
+        out.message(2, &self.linear);
+        out.message(3, &self.angular);
+    }
+    
+    fn merge_field(&mut self, field: u32, value: FieldValue<'_>) -> Result<(), CoreError> {
+        match field {
+            1 => self.header = value.as_message()?,
+            2 => self.linear = value.as_message()?,
+            3 => self.angular = value.as_message()?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// A `sensor::lidar::LaserScan` with its header
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LaserScan {
+    pub header: Header,
+    pub scan: lidar::LaserScan,
+}
+
+impl Message for LaserScan {
+    fn encode_fields(&self, out: &mut Encoder) {
+        let scan = &self.scan;
+        out.message(1, &self.header);
+        out.float(2, scan.angle_min);
+        out.float(3, scan.angle_increment);
+        out.float(4, scan.range_min);
+        out.float(5, scan.range_max);
+        out.floats(6, &scan.ranges);
+        out.floats(7, &scan.intensities);
+    }
+    
+    fn merge_field(&mut self, field: u32, value: FieldValue<'_>) -> Result<(), CoreError> {
+        let scan = &mut self.scan;
+        match field {
+            1 => self.header = value.as_message()?,
+            2 => scan.angle_min = value.as_f32()?,
+            3 => scan.angle_increment = value.as_f32()?,
+            4 => scan.range_min = value.as_f32()?,
+            5 => scan.range_max = value.as_f32()?,
+            6 => value.extend_floats(&mut scan.ranges)?,
+            7 => value.extend_floats(&mut scan.intensities)?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// An encoded image, convertible to and from a `CameraFrame`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Image {
+    pub header: Header,
+    /// Position of the image in its capture, counting from 0
+    pub sequence: u64,
+    pub width: u32,
+    pub height: u32,
+    /// `None` if the sender left the format unspecified
+    pub format: Option<PixelFormat>,
+    pub data: Vec<u8>,
+}
+
+impl From<CameraFrame> for Image {
+    /// The frame's pixels, with an empty frame ID; copied only if a clone
+    /// still shares them
+    fn from(frame: CameraFrame) -> Self {
+        Self {
+            header: Header::new(frame.timestamp, ""),
+            sequence: frame.sequence,
+            width: frame.width,
+            height: frame.height,
+            format: Some(frame.format),
+            data: frame.into_bytes(),
+        }
+    }
+}
+
+impl TryFrom<Image> for CameraFrame {
+    type Error = CoreError;
+    
+    /// Fails with `CoreError::InvalidInput` if the format is unspecified or
+    /// the pixels do not fill the image, as `CameraFrame::new` checks
+    fn try_from(image: Image) -> Result<Self, CoreError> {
+        let format = image
+            .format
+            .ok_or_else(|| CoreError::InvalidInput("image has no pixel format".to_string()))?;
+        CameraFrame::new(
+            image.sequence,
+            image.header.timestamp,
+            image.width,
+            image.height,
+            format,
+            image.data,
+        )
+    }
+}
+
+impl Message for Image {
+    fn encode_fields(&self, out: &mut Encoder) {
+        out.message(1, &self.header);
+        out.uint(2, self.sequence);
+        out.uint(3, u64::from(self.width));
+        out.uint(4, u64::from(self.height));
+        let format = match self.format {
+            None => 0,
+            Some(PixelFormat::Yuyv) => 1,
+            Some(PixelFormat::Mjpeg) => 2,
+        };
+        out.uint(5, format);
+        out.bytes(6, &self.data);
+    }
+    
+    fn merge_field(&mut self, field: u32, value: FieldValue<'_>) -> Result<(), CoreError> {
+        match field {
+            1 => self.header = value.as_message()?,
+            2 => self.sequence = value.as_u64()?,
+            3 => self.width = value.as_u32()?,
+            4 => self.height = value.as_u32()?,
+            5 => {
+                // Formats added to the schema later read as unspecified
+                self.format = match value.as_u64()? {
+                    1 => Some(PixelFormat::Yuyv),
+                    2 => Some(PixelFormat::Mjpeg),
+                    _ => None,
+                }
+            }
+            6 => self.data = value.as_bytes()?.to_vec(),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Named parameter values, as the `ParameterServer` and algorithm
+/// parameters hold them
+///
+/// Integers that fit an `i64` travel as `int_value` and other numbers as
+/// `double_value`, so a `u64` above `i64::MAX` comes back as a float.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParameterMap {
+    pub values: BTreeMap<String, Value>,
+}
+
+impl ParameterMap {
+    /// The values as a JSON object
+    pub fn to_json(&self) -> Value {
+        Value::Object(self.values.clone().into_iter().collect())
+    }
+    
+    /// Fails with `CoreError::InvalidInput` unless `value` is an object
+    pub fn from_json(value: Value) -> Result<Self, CoreError> {
+        match value {
+            Value::Object(object) => Ok(Self {
+                values: object.into_iter().collect(),
+            }),
+            other => Err(CoreError::InvalidInput(format!("parameters must be a JSON object, got {}", other))),
+        }
+    }
+}
+
+fn encode_entries<'a>(entries: impl Iterator<Item = (&'a String, &'a Value)>, out: &mut Encoder) {
+    for (key, value) in entries {
+        let mut entry = Encoder::new();
+        entry.string(1, key);
+        entry.put_bytes(2, &encode_value(value));
+        out.put_bytes(1, &entry.finish());
+    }
+}
+
+/// A `ParameterValue`, writing the `oneof` field even when it holds a
+/// default, so `false` and `0` do not read back as null
+fn encode_value(value: &Value) -> Vec<u8> {
+    let mut out = Encoder::new();
+    match value {
+        Value::Null => {}
+        Value::Bool(flag) => out.put_u64(1, u64::from(*flag)),
+        Value::Number(number) => match number.as_i64() {
+            Some(integer) => out.put_u64(2, integer as u64),
+            None => out.put_f64(3, number.as_f64().unwrap_or(f64::NAN)),
+        },
+        Value::String(text) => out.put_bytes(4, text.as_bytes()),
+        Value::Array(items) => {
+            let mut list = Encoder::new();
+            for item in items {
+                list.put_bytes(1, &encode_value(item));
+            }
+            out.put_bytes(5, &list.finish());
+        }
+        Value::Object(object) => {
+            let mut map = Encoder::new();
+            encode_entries(object.iter(), &mut map);
+            out.put_bytes(6, &map.finish());
+        }
+    }
+    out.finish()
+}
+
+fn check_depth(depth: usize) -> Result<(), CoreError> {
+    if depth > MAX_PARAMETER_DEPTH {
+        return Err(CoreError::InvalidInput(format!(
+            "parameters nest deeper than {} levels",
+            MAX_PARAMETER_DEPTH
+        )));
+    }
+    Ok(())
+}
+
+fn decode_entry(bytes: &[u8], depth: usize) -> Result<(String, Value), CoreError> {
+    let (mut key, mut value) = (String::new(), Value::Null);
+    let mut decoder = Decoder::new(bytes);
+    while let Some((field, field_value)) = decoder.next_field()? {
+        match field {
+            1 => key = field_value.as_str()?.to_string(),
+            2 => value = decode_value(field_value.as_bytes()?, depth)?,
+            _ => {}
+        }
+    }
+    Ok((key, value))
+}
+
+fn decode_value(bytes: &[u8], depth: usize) -> Result<Value, CoreError> {
+    check_depth(depth)?;
+    let mut value = Value::Null;
+    let mut decoder = Decoder::new(bytes);
+    while let Some((field, field_value)) = decoder.next_field()? {
+        value = match field {
+            1 => Value::Bool(field_value.as_bool()?),
+            2 => Value::from(field_value.as_i64()?),
+            3 => {
+                let float = field_value.as_f64()?;
+                Number::from_f64(float).map(Value::Number).ok_or_else(|| {
+                    CoreError::InvalidInput(format!("parameter value {} has no JSON representation", float))
+                })?
+            }
+            4 => Value::String(field_value.as_str()?.to_string()),
+            5 => {
+                let mut items = Vec::new();
+                let mut list = Decoder::new(field_value.as_bytes()?);
+                while let Some((item_field, item)) = list.next_field()? {
+                    if item_field == 1 {
+                        items.push(decode_value(item.as_bytes()?, depth + 1)?);
+                    }
+                }
+                Value::Array(items)
+            }
+            6 => {
+                let mut object = serde_json::Map::new();
+                let mut map = Decoder::new(field_value.as_bytes()?);
+                while let Some((entry_field, entry)) = map.next_field()? {
+                    if entry_field == 1 {
+                        let (key, item) = decode_entry(entry.as_bytes()?, depth + 1)?;
+                        object.insert(key, item);
+                    }
+                }
+                Value::Object(object)
+            }
+            _ => continue,
+        };
+    }
+    Ok(value)
+}
+
+impl Message for ParameterMap {
+    fn encode_fields(&self, out: &mut Encoder) {
+        encode_entries(self.values.iter(), out);
+    }
+    
+    fn merge_field(&mut self, field: u32, value: FieldValue<'_>) -> Result<(), CoreError> {
+        if field == 1 {
+            let (key, value) = decode_entry(value.as_bytes()?, 0)?;
+            self.values.insert(key, value);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[test]
+    fn test_encoding_matches_protobuf_and_skips_unknown_fields() {
+        // Bytes as protoc-generated code writes them
+        let header = Header::new(150, "map");
+        assert_eq!(header.encode(), [0x08, 0x96, 0x01, 0x12, 0x03, b'm', b'a', b'p']);
+        let mut with_unknown = header.encode();
+        with_unknown.extend([0x18, 0x05, 0x25, 0, 0, 0, 0, 0x2a, 0x01, 0xff]);
+        assert_eq!(Header::decode(&with_unknown).unwrap(), header);
+        assert!(Header::decode(&[0x12, 0x05, b'm']).is_err(), "truncated");
+        assert!(Header::decode(&[0x09, 0, 0, 0, 0, 0, 0, 0, 0]).is_err(), "double for a uint64");
+        
+        let pose = Pose::from_pose_2d(header, &Pose2D { x: 1.5, y: -2.0, theta: 0.5 });
+        let decoded = Pose::decode(&pose.encode()).unwrap();
+        assert_eq!(decoded, pose);
+        assert!((decoded.to_pose_2d().theta - 0.5).abs() < 1e-12);
+        
+        let scan = LaserScan {
+            header: Header::new(7, "laser"),
+            scan: lidar::LaserScan {
+                angle_min: -1.0,
+                angle_increment: 0.5,
+                range_min: 0.1,
+                range_max: 10.0,
+                ranges: vec![1.0, f32::NAN, 3.0],
+                intensities: Vec::new(),
+            },
+        };
+        let decoded = LaserScan::decode(&scan.encode()).unwrap();
+        assert_eq!(decoded.scan.ranges.len(), 3);
+        assert!(decoded.scan.ranges[1].is_nan() && decoded.scan.intensities.is_empty());
+        // Unpacked repeated floats, as older writers send them, read the same
+        let unpacked = [0x35, 0, 0, 0x80, 0x3f, 0x35, 0, 0, 0x40, 0x40];
+        assert_eq!(LaserScan::decode(&unpacked).unwrap().scan.ranges, [1.0, 3.0]);
+        
+        let frame = CameraFrame::new(3, 40, 2, 1, PixelFormat::Yuyv, vec![1, 2, 3, 4]).unwrap();
+        let image = Image::decode(&Image::from(frame.clone()).encode()).unwrap();
+        assert_eq!(CameraFrame::try_from(image).unwrap(), frame);
+        assert!(CameraFrame::try_from(Image::default()).is_err());
+    }
+    
+    #[test]
+    fn test_parameter_map_round_trips_json_values() {
+        let json = json!({
+            "gain": 0.5,
+            "count": -3,
+            "enabled": false,
+            "zero": 0,
+            "name": "arm",
+            "missing": null,
+            "nested": {"limits": [1, 2.5, {"deep": true}], "empty": []}
+        });
+        let parameters = ParameterMap::from_json(json.clone()).unwrap();
+        let decoded = ParameterMap::decode(&parameters.encode()).unwrap();
+        assert_eq!(decoded.to_json(), json);
+        assert!(ParameterMap::from_json(json!([1])).is_err());
+        
+        let mut deep = json!(1);
+        for _ in 0..=MAX_PARAMETER_DEPTH {
+            deep = json!([deep]);
+        }
+        let parameters = ParameterMap::from_json(json!({ "deep": deep })).unwrap();
+        assert!(ParameterMap::decode(&parameters.encode()).is_err());
+    }
+}
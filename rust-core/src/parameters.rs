@@ -0,0 +1,407 @@
+//! Runtime parameters with typed access and change notifications
+//!
+//! A `ParameterServer` holds the current value of every declared parameter,
+//! checked against its `ParameterType` on each update. Algorithms keep a
+//! `ParameterWatch` on the gains and thresholds they use and read the
+//! latest value while running, and `subscribe` callbacks hear of each
+//! change as it happens. Values can be saved to and restored from a JSON
+//! file, so tuning survives restarts.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+
+use crate::algorithm::{ParameterDefinition, ParameterType};
+use crate::error::CoreError;
+use crate::CoreEngine;
+
+/// One update of a parameter's value
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParameterChange {
+    pub name: String,
+    /// Value before the update, if it had one
+    pub old: Option<Value>,
+    pub new: Value,
+}
+
+type Callback = Arc<dyn Fn(&ParameterChange) + Send + Sync>;
+
+struct Entry {
+    definition: ParameterDefinition,
+    value: Option<Value>,
+    // Bumped on every change, for `ParameterWatch::has_changed`
+    version: u64,
+}
+
+struct Subscriber {
+    id: u64,
+    prefix: String,
+    callback: Callback,
+}
+
+/// Declared parameters and their current values
+///
+/// Shared through an `Arc`, so algorithms, control loops and remote
+/// tooling can all read and update the same values. Names are free-form;
+/// `CoreEngine::declare_parameters` uses `<algorithm ID>.<parameter>`.
+#[derive(Default)]
+pub struct ParameterServer {
+    entries: RwLock<BTreeMap<String, Entry>>,
+    subscribers: Mutex<Vec<Subscriber>>,
+    next_subscriber: AtomicU64,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Fail unless `value` is of `parameter_type`
+///
+/// Integers are accepted as floats, but not the other way round.
+fn check_type(name: &str, parameter_type: ParameterType, value: &Value) -> Result<(), CoreError> {
+    let matches = match parameter_type {
+        ParameterType::Integer => value.is_i64(),
+        ParameterType::Float => value.is_number(),
+        ParameterType::Boolean => value.is_boolean(),
+        ParameterType::String => value.is_string(),
+        ParameterType::Array => value.is_array(),
+        ParameterType::Object => value.is_object(),
+    };
+    if !matches {
+        return Err(CoreError::InvalidParameter(format!(
+            "'{}' must be {}, got {}",
+            name,
+            parameter_type.json_schema_type(),
+            value
+        )));
+    }
+    Ok(())
+}
+
+impl ParameterServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Declare a parameter, taking its default as the initial value
+    ///
+    /// Redeclaring a parameter of the same type keeps its current value;
+    /// a different type fails with `CoreError::InvalidParameter`, as does a
+    /// default that does not parse as the type.
+    pub fn declare(&self, definition: ParameterDefinition) -> Result<(), CoreError> {
+        let default = match &definition.default_value {
+            Some(default) => Some(Value::from(definition.parameter_type.parse(default).map_err(|e| {
+                CoreError::InvalidParameter(format!("default of '{}': {}", definition.name, e))
+            })?)),
+            None => None,
+        };
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = entries.get_mut(&definition.name) {
+            if entry.definition.parameter_type != definition.parameter_type {
+                return Err(CoreError::InvalidParameter(format!(
+                    "'{}' is already declared as {}",
+                    definition.name,
+                    entry.definition.parameter_type.json_schema_type()
+                )));
+            }
+            entry.definition = definition;
+            return Ok(());
+        }
+        entries.insert(
+            definition.name.clone(),
+            Entry {
+                definition,
+                value: default,
+                version: 0,
+            },
+        );
+        Ok(())
+    }
+    
+    /// Definition of a declared parameter
+    pub fn definition(&self, name: &str) -> Option<ParameterDefinition> {
+        self.read_entries().get(name).map(|entry| entry.definition.clone())
+    }
+    
+    /// Names of the declared parameters, sorted
+    pub fn names(&self) -> Vec<String> {
+        self.read_entries().keys().cloned().collect()
+    }
+    
+    /// Current value of `name`, if it is declared and has one
+    pub fn get_value(&self, name: &str) -> Option<Value> {
+        self.read_entries().get(name).and_then(|entry| entry.value.clone())
+    }
+    
+    /// Current value of `name` as a `T`
+    ///
+    /// Fails with `CoreError::InvalidParameter` if the parameter is not
+    /// declared, has no value, or does not convert to `T`.
+    pub fn get<T: DeserializeOwned>(&self, name: &str) -> Result<T, CoreError> {
+        let value = self
+            .get_value(name)
+            .ok_or_else(|| CoreError::InvalidParameter(format!("'{}' has no value", name)))?;
+        serde_json::from_value(value).map_err(|e| CoreError::InvalidParameter(format!("'{}': {}", name, e)))
+    }
+    
+    /// Update `name` to `value` and notify subscribers, if it changed
+    ///
+    /// Fails with `CoreError::InvalidParameter` if the parameter is not
+    /// declared or the value is not of its type.
+    pub fn set(&self, name: &str, value: impl Serialize) -> Result<(), CoreError> {
+        let value = serde_json::to_value(value)
+            .map_err(|e| CoreError::InvalidParameter(format!("'{}' does not serialize: {}", name, e)))?;
+        let change = {
+            let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+            let entry = entries
+                .get_mut(name)
+                .ok_or_else(|| CoreError::InvalidParameter(format!("'{}' is not declared", name)))?;
+            check_type(name, entry.definition.parameter_type, &value)?;
+            if entry.value.as_ref() == Some(&value) {
+                return Ok(());
+            }
+            entry.version += 1;
+            ParameterChange {
+                name: name.to_string(),
+                old: entry.value.replace(value.clone()),
+                new: value,
+            }
+        };
+        self.notify(&change);
+        Ok(())
+    }
+    
+    /// Update `name` from text in the encoding of `ParameterType::parse`,
+    /// such as a command-line override
+    pub fn set_str(&self, name: &str, value: &str) -> Result<(), CoreError> {
+        let parameter_type = self
+            .definition(name)
+            .ok_or_else(|| CoreError::InvalidParameter(format!("'{}' is not declared", name)))?
+            .parameter_type;
+        self.set(name, Value::from(parameter_type.parse(value)?))
+    }
+    
+    /// Call `callback` after every change to a parameter whose name starts
+    /// with `prefix`, returning an ID for `unsubscribe`
+    ///
+    /// Callbacks run on the thread that made the change, after it is
+    /// visible to `get`.
+    pub fn subscribe(&self, prefix: &str, callback: impl Fn(&ParameterChange) + Send + Sync + 'static) -> u64 {
+        let id = self.next_subscriber.fetch_add(1, Ordering::Relaxed);
+        lock(&self.subscribers).push(Subscriber {
+            id,
+            prefix: prefix.to_string(),
+            callback: Arc::new(callback),
+        });
+        id
+    }
+    
+    /// Stop calling a subscriber, returning whether it existed
+    pub fn unsubscribe(&self, id: u64) -> bool {
+        let mut subscribers = lock(&self.subscribers);
+        let before = subscribers.len();
+        subscribers.retain(|subscriber| subscriber.id != id);
+        subscribers.len() != before
+    }
+    
+    /// Watch `name` for changes, such as from inside an algorithm
+    pub fn watch(self: &Arc<Self>, name: &str) -> Result<ParameterWatch, CoreError> {
+        let version = self
+            .version(name)
+            .ok_or_else(|| CoreError::InvalidParameter(format!("'{}' is not declared", name)))?;
+        Ok(ParameterWatch {
+            server: Arc::clone(self),
+            name: name.to_string(),
+            seen: AtomicU64::new(version),
+        })
+    }
+    
+    /// Write every value to `path` as a JSON object
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CoreError> {
+        let values: Map<String, Value> = self
+            .read_entries()
+            .iter()
+            .filter_map(|(name, entry)| entry.value.clone().map(|value| (name.clone(), value)))
+            .collect();
+        let json = serde_json::to_string_pretty(&values)
+            .map_err(|e| CoreError::ProcessingFailed(format!("parameters do not serialize: {}", e)))?;
+        Ok(fs::write(path, json)?)
+    }
+    
+    /// Apply the values saved at `path`, notifying subscribers of each
+    /// change
+    ///
+    /// Every value is checked before any is applied, so a file naming an
+    /// undeclared parameter or holding a value of the wrong type changes
+    /// nothing.
+    pub fn load(&self, path: impl AsRef<Path>) -> Result<(), CoreError> {
+        let path = path.as_ref();
+        let values: Map<String, Value> = serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| CoreError::InvalidInput(format!("{}: {}", path.display(), e)))?;
+        {
+            let entries = self.read_entries();
+            for (name, value) in &values {
+                let entry = entries
+                    .get(name)
+                    .ok_or_else(|| CoreError::InvalidParameter(format!("'{}' is not declared", name)))?;
+                check_type(name, entry.definition.parameter_type, value)?;
+            }
+        }
+        for (name, value) in values {
+            self.set(&name, value)?;
+        }
+        Ok(())
+    }
+    
+    fn version(&self, name: &str) -> Option<u64> {
+        self.read_entries().get(name).map(|entry| entry.version)
+    }
+    
+    fn read_entries(&self) -> std::sync::RwLockReadGuard<'_, BTreeMap<String, Entry>> {
+        self.entries.read().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    fn notify(&self, change: &ParameterChange) {
+        // Called outside the lock, so callbacks may update parameters too
+        let callbacks: Vec<Callback> = lock(&self.subscribers)
+            .iter()
+            .filter(|subscriber| change.name.starts_with(&subscriber.prefix))
+            .map(|subscriber| Arc::clone(&subscriber.callback))
+            .collect();
+        for callback in callbacks {
+            callback(change);
+        }
+    }
+}
+
+/// A handle on one parameter, telling whether it changed since last read
+///
+/// Reads take `&self`, so an algorithm can hold a watch and check it from
+/// `process` on every execution.
+pub struct ParameterWatch {
+    server: Arc<ParameterServer>,
+    name: String,
+    seen: AtomicU64,
+}
+
+impl ParameterWatch {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    
+    /// Whether the value changed since the watch was created or last read
+    pub fn has_changed(&self) -> bool {
+        self.server.version(&self.name) != Some(self.seen.load(Ordering::Acquire))
+    }
+    
+    /// The current value as a `T`, marking it as seen
+    pub fn get<T: DeserializeOwned>(&self) -> Result<T, CoreError> {
+        if let Some(version) = self.server.version(&self.name) {
+            self.seen.store(version, Ordering::Release);
+        }
+        self.server.get(&self.name)
+    }
+}
+
+impl CoreEngine {
+    /// The engine's runtime parameters
+    pub fn parameters(&self) -> &Arc<ParameterServer> {
+        &self.parameters
+    }
+    
+    /// Declare the parameters in `algorithm_id`'s metadata, each as
+    /// `<algorithm_id>.<name>`
+    pub fn declare_parameters(&self, algorithm_id: &str) -> Result<(), CoreError> {
+        let algorithm = self
+            .get_algorithm(algorithm_id)
+            .ok_or_else(|| CoreError::AlgorithmNotFound(algorithm_id.to_string()))?;
+        for parameter in algorithm.metadata().parameters {
+            self.parameters.declare(ParameterDefinition {
+                name: format!("{}.{}", algorithm_id, parameter.name),
+                ..parameter
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::builtins::Clamp;
+    
+    fn gain() -> ParameterDefinition {
+        ParameterDefinition {
+            name: "pid.kp".to_string(),
+            parameter_type: ParameterType::Float,
+            description: "Proportional gain".to_string(),
+            default_value: Some("0.5".to_string()),
+        }
+    }
+    
+    #[test]
+    fn test_typed_updates_notify_watchers_and_subscribers() {
+        let server = Arc::new(ParameterServer::new());
+        server.declare(gain()).unwrap();
+        assert_eq!(server.get::<f64>("pid.kp"), Ok(0.5));
+        let watch = server.watch("pid.kp").unwrap();
+        assert!(!watch.has_changed());
+        
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let seen = changes.clone();
+        let id = server.subscribe("pid.", move |change| seen.lock().unwrap().push(change.clone()));
+        server.set("pid.kp", 2).unwrap();
+        server.set("pid.kp", 2).unwrap();
+        assert!(watch.has_changed());
+        assert_eq!(watch.get::<f64>(), Ok(2.0));
+        assert!(!watch.has_changed());
+        assert_eq!(
+            *changes.lock().unwrap(),
+            [ParameterChange {
+                name: "pid.kp".to_string(),
+                old: Some(Value::from(0.5)),
+                new: Value::from(2),
+            }]
+        );
+        
+        assert!(matches!(server.set("pid.kp", "fast"), Err(CoreError::InvalidParameter(_))));
+        assert!(server.set("pid.ki", 1.0).is_err());
+        server.set_str("pid.kp", "1_000").unwrap();
+        assert_eq!(server.get::<f64>("pid.kp"), Ok(1000.0));
+        assert!(server.unsubscribe(id));
+        server.set("pid.kp", 3.0).unwrap();
+        assert_eq!(changes.lock().unwrap().len(), 2);
+        assert!(server.declare(ParameterDefinition { parameter_type: ParameterType::Boolean, ..gain() }).is_err());
+    }
+    
+    #[test]
+    fn test_values_persist_and_engine_declares_metadata() {
+        let mut engine = CoreEngine::new();
+        assert!(engine.declare_parameters(Clamp::ID).is_err());
+        engine.register(Box::new(Clamp::new(0.0, 1.0, Default::default()).unwrap())).unwrap();
+        engine.declare_parameters(Clamp::ID).unwrap();
+        let names = engine.parameters().names();
+        assert!(names.iter().all(|name| name.starts_with("clamp.")), "{:?}", names);
+        assert!(!names.is_empty());
+        
+        let server = ParameterServer::new();
+        server.declare(gain()).unwrap();
+        server.set("pid.kp", 4.5).unwrap();
+        let path = std::env::temp_dir().join(format!("robotics_core_parameters_{}.json", std::process::id()));
+        server.save(&path).unwrap();
+        
+        let restored = ParameterServer::new();
+        restored.declare(gain()).unwrap();
+        restored.load(&path).unwrap();
+        assert_eq!(restored.get::<f64>("pid.kp"), Ok(4.5));
+        fs::write(&path, r#"{ "pid.kp": 1.0, "pid.kd": 2.0 }"#).unwrap();
+        assert!(restored.load(&path).is_err());
+        assert_eq!(restored.get::<f64>("pid.kp"), Ok(4.5));
+        fs::remove_file(&path).unwrap();
+    }
+}
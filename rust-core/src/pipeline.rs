@@ -0,0 +1,913 @@
+//! Composite execution of multiple algorithms
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use crate::algorithm::context::ContextSource;
+use crate::algorithm::Algorithm;
+use crate::batch::Progress;
+use crate::error::CoreError;
+use crate::hooks::Hooks;
+use crate::memory::{MemoryManager, RegionChanges};
+use crate::telemetry::Span;
+use crate::CoreEngine;
+
+/// Deepest pipeline or DAG an engine executes unless configured otherwise
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+
+/// Captured output of one stage of a traced pipeline run
+#[derive(Clone, Debug, PartialEq)]
+pub struct StageTrace {
+    /// ID of the algorithm the stage ran
+    pub algorithm_id: String,
+    /// The stage's output, which is also the next stage's input
+    pub output_bytes: Vec<u8>,
+    /// Time spent in the stage, as measured by the engine's clock
+    pub duration: Duration,
+}
+
+/// Outcome of a pipeline that keeps the work of the stages that succeeded
+#[derive(Clone, Debug, PartialEq)]
+pub struct PartialResult {
+    /// Every stage that succeeded, in order
+    pub stages: Vec<StageTrace>,
+    /// Why the pipeline stopped early, if it did
+    ///
+    /// The failed stage is the one at index `stages.len()`.
+    pub error: Option<CoreError>,
+}
+
+impl PartialResult {
+    /// Whether every stage ran successfully
+    pub fn is_complete(&self) -> bool {
+        self.error.is_none()
+    }
+    
+    /// Output of the last stage that succeeded, if any did
+    pub fn last_output(&self) -> Option<&[u8]> {
+        self.stages.last().map(|stage| &stage.output_bytes[..])
+    }
+}
+
+/// One algorithm invocation within a `Dag`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DagNode {
+    /// Unique name of the node within the graph
+    pub name: String,
+    /// ID of the algorithm the node runs
+    pub algorithm_id: String,
+    /// Upstream nodes whose outputs, concatenated in order, form this
+    /// node's input; a node without inputs receives the graph input
+    pub inputs: Vec<String>,
+}
+
+/// Directed acyclic graph of algorithms
+///
+/// Independent nodes run concurrently on the engine's thread pool, up to
+/// `max_parallelism` at a time.
+/// Each node runs against its own fork of the engine memory, so regions
+/// written or deallocated by parallel branches stay isolated; once every
+/// node finishes the changes are merged back into the engine in node
+/// declaration order. If a node fails, nodes that have not started are
+/// cancelled, running siblings finish their current call, nothing is
+/// merged, and the first error is returned.
+#[derive(Clone, Debug)]
+pub struct Dag {
+    nodes: Vec<DagNode>,
+    max_parallelism: usize,
+}
+
+impl Dag {
+    /// Create an empty graph limited only by the engine's thread pool
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            max_parallelism: usize::MAX,
+        }
+    }
+    
+    /// Add a node fed by the named upstream nodes
+    pub fn node(mut self, name: &str, algorithm_id: &str, inputs: &[&str]) -> Self {
+        self.nodes.push(DagNode {
+            name: name.to_string(),
+            algorithm_id: algorithm_id.to_string(),
+            inputs: inputs.iter().map(|input| input.to_string()).collect(),
+        });
+        self
+    }
+    
+    /// Limit how many nodes run at once (at least one)
+    pub fn with_max_parallelism(mut self, max_parallelism: usize) -> Self {
+        self.max_parallelism = max_parallelism.max(1);
+        self
+    }
+    
+    /// Nodes in declaration order
+    pub fn nodes(&self) -> &[DagNode] {
+        &self.nodes
+    }
+    
+    /// Resolve each node's inputs to node indices, rejecting malformed graphs
+    ///
+    /// Also returns the graph's depth: the number of nodes on its longest
+    /// dependency chain.
+    fn dependencies(&self) -> Result<(Vec<Vec<usize>>, usize), CoreError> {
+        let mut index = HashMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            if index.insert(node.name.as_str(), i).is_some() {
+                return Err(CoreError::InvalidDefinition(format!("duplicate DAG node '{}'", node.name)));
+            }
+        }
+        let dependencies = self
+            .nodes
+            .iter()
+            .map(|node| {
+                node.inputs
+                    .iter()
+                    .map(|input| {
+                        index.get(input.as_str()).copied().ok_or_else(|| {
+                            CoreError::InvalidDefinition(format!("node '{}' depends on unknown node '{}'", node.name, input))
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        // Kahn's algorithm: every node must become ready for the graph to be acyclic
+        let mut pending: Vec<usize> = dependencies.iter().map(Vec::len).collect();
+        let mut ready: Vec<usize> = (0..self.nodes.len()).filter(|&i| pending[i] == 0).collect();
+        let mut visited = 0;
+        let mut depths = vec![1; self.nodes.len()];
+        while let Some(i) = ready.pop() {
+            visited += 1;
+            for (j, deps) in dependencies.iter().enumerate() {
+                for _ in deps.iter().filter(|&&d| d == i) {
+                    depths[j] = depths[j].max(depths[i] + 1);
+                    pending[j] -= 1;
+                    if pending[j] == 0 {
+                        ready.push(j);
+                    }
+                }
+            }
+        }
+        if visited != self.nodes.len() {
+            return Err(CoreError::InvalidDefinition("DAG contains a cycle".to_string()));
+        }
+        Ok((dependencies, depths.into_iter().max().unwrap_or(0)))
+    }
+}
+
+impl Default for Dag {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// One stage of a `Pipeline`, reading and writing memory regions
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PipelineStage {
+    /// Unique name of the stage within the pipeline
+    pub name: String,
+    /// ID of the algorithm the stage runs
+    pub algorithm_id: String,
+    /// Regions whose contents, concatenated in order, form the stage's input
+    pub input_keys: Vec<String>,
+    /// Region the stage's output is stored in
+    pub output_key: String,
+}
+
+/// Ordered graph of algorithms exchanging data through the engine's memory,
+/// registered with `CoreEngine::register_pipeline`
+///
+/// A stage depends on the stages writing the regions it reads, and must
+/// come after them; regions no stage writes are read as the engine's memory
+/// holds them when the pipeline runs, such as sensor frames.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pipeline {
+    id: String,
+    stages: Vec<PipelineStage>,
+}
+
+impl Pipeline {
+    /// Create an empty pipeline registered under `id`
+    pub fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            stages: Vec::new(),
+        }
+    }
+    
+    /// Add a stage reading `input_keys` and writing `output_key`
+    pub fn stage(mut self, name: &str, algorithm_id: &str, input_keys: &[&str], output_key: &str) -> Self {
+        self.stages.push(PipelineStage {
+            name: name.to_string(),
+            algorithm_id: algorithm_id.to_string(),
+            input_keys: input_keys.iter().map(|key| key.to_string()).collect(),
+            output_key: output_key.to_string(),
+        });
+        self
+    }
+    
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+    
+    /// Stages in execution order
+    pub fn stages(&self) -> &[PipelineStage] {
+        &self.stages
+    }
+    
+    /// Check the graph, returning its depth: the number of stages on its
+    /// longest dependency chain
+    ///
+    /// Fails with `CoreError::InvalidDefinition` if the pipeline has no
+    /// stages, two stages share a name or an output region, or a stage reads
+    /// a region written by itself or a later stage.
+    pub fn validate(&self) -> Result<usize, CoreError> {
+        if self.stages.is_empty() {
+            return Err(CoreError::InvalidDefinition(format!("pipeline '{}' has no stages", self.id)));
+        }
+        let mut names = HashSet::new();
+        let mut writers = HashMap::new();
+        for (i, stage) in self.stages.iter().enumerate() {
+            if !names.insert(stage.name.as_str()) {
+                return Err(CoreError::InvalidDefinition(format!(
+                    "pipeline '{}' has duplicate stage '{}'",
+                    self.id, stage.name
+                )));
+            }
+            if let Some(&other) = writers.get(stage.output_key.as_str()) {
+                let other: &PipelineStage = &self.stages[other];
+                return Err(CoreError::InvalidDefinition(format!(
+                    "stages '{}' and '{}' both write region '{}'",
+                    other.name, stage.name, stage.output_key
+                )));
+            }
+            writers.insert(stage.output_key.as_str(), i);
+        }
+        let mut depths = vec![1; self.stages.len()];
+        for (i, stage) in self.stages.iter().enumerate() {
+            for key in &stage.input_keys {
+                let Some(&writer) = writers.get(key.as_str()) else {
+                    continue;
+                };
+                if writer >= i {
+                    return Err(CoreError::InvalidDefinition(format!(
+                        "stage '{}' reads region '{}' before stage '{}' writes it",
+                        stage.name, key, self.stages[writer].name
+                    )));
+                }
+                depths[i] = depths[i].max(depths[writer] + 1);
+            }
+        }
+        Ok(depths.into_iter().max().unwrap_or(0))
+    }
+}
+
+/// Time one stage of a registered pipeline took
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StageTiming {
+    /// Name of the stage
+    pub stage: String,
+    /// ID of the algorithm the stage ran
+    pub algorithm_id: String,
+    /// Time spent in the stage, including reading its inputs and storing its
+    /// output, as measured by the engine's clock
+    pub duration: Duration,
+}
+
+/// Shared bookkeeping for DAG workers
+struct DagState {
+    ready: VecDeque<usize>,
+    pending: Vec<usize>,
+    outputs: Vec<Option<Vec<u8>>>,
+    changes: Vec<RegionChanges>,
+    running: usize,
+    completed: usize,
+    error: Option<CoreError>,
+}
+
+/// Immutable inputs shared by DAG workers
+struct DagRun<'a> {
+    algorithms: Vec<Arc<dyn Algorithm>>,
+    dependencies: Vec<Vec<usize>>,
+    dependents: Vec<Vec<usize>>,
+    input: &'a [u8],
+    hooks: &'a Hooks,
+    context: &'a ContextSource,
+    progress: Option<&'a Sender<Progress>>,
+    base: &'a MemoryManager,
+    state: Mutex<DagState>,
+    wake: Condvar,
+}
+
+impl DagRun<'_> {
+    /// Claim ready nodes and run them until the graph completes or fails
+    fn work(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            if state.error.is_some() || state.completed == self.algorithms.len() {
+                return;
+            }
+            let Some(node) = state.ready.pop_front() else {
+                if state.running == 0 {
+                    return;
+                }
+                state = self.wake.wait(state).unwrap_or_else(|e| e.into_inner());
+                continue;
+            };
+            state.running += 1;
+            let input = if self.dependencies[node].is_empty() {
+                self.input.to_vec()
+            } else {
+                self.dependencies[node]
+                    .iter()
+                    .flat_map(|&d| state.outputs[d].iter().flatten().copied())
+                    .collect()
+            };
+            drop(state);
+            
+            let mut memory = self.base.fork();
+            let algorithm = self.algorithms[node].as_ref();
+            // A panic must still release the node, or sibling workers wait on it forever
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                crate::run_hooked(algorithm, self.hooks, self.context, &input, &mut memory)
+            }))
+            .unwrap_or_else(|payload| {
+                Err(CoreError::ProcessingFailed(format!(
+                    "'{}' panicked: {}",
+                    algorithm.id(),
+                    crate::panic_message(payload.as_ref())
+                )))
+            });
+            
+            state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.running -= 1;
+            match result {
+                Ok(output) => {
+                    state.outputs[node] = Some(output);
+                    state.changes[node] = memory.changes_since(self.base);
+                    state.completed += 1;
+                    crate::batch::report(self.progress, state.completed, self.algorithms.len());
+                    for &dependent in &self.dependents[node] {
+                        state.pending[dependent] -= 1;
+                        if state.pending[dependent] == 0 {
+                            state.ready.push_back(dependent);
+                        }
+                    }
+                }
+                Err(e) => {
+                    state.error.get_or_insert(e);
+                }
+            }
+            self.wake.notify_all();
+        }
+    }
+}
+
+impl CoreEngine {
+    fn check_depth(&self, depth: usize) -> Result<(), CoreError> {
+        if depth > self.max_depth {
+            return Err(CoreError::MaxDepthExceeded {
+                depth,
+                max: self.max_depth,
+            });
+        }
+        Ok(())
+    }
+    
+    /// Span timing stage `stage` of a pipeline, running `algorithm_id`
+    fn stage_span(&self, stage: usize, algorithm_id: &str) -> Span {
+        self.telemetry
+            .span("pipeline_stage")
+            .with("stage", stage)
+            .with("algorithm.id", algorithm_id)
+    }
+    
+    /// Execute algorithms in sequence, feeding each output to the next
+    ///
+    /// Fails with `CoreError::MaxDepthExceeded` before running anything if
+    /// there are more stages than the engine's maximum depth.
+    pub fn execute_pipeline(&mut self, algorithm_ids: &[&str], input: &[u8]) -> Result<Vec<u8>, CoreError> {
+        self.check_depth(algorithm_ids.len())?;
+        let mut data = input.to_vec();
+        for (stage, &algorithm_id) in algorithm_ids.iter().enumerate() {
+            let _span = self.stage_span(stage, algorithm_id);
+            data = self.execute_algorithm(algorithm_id, &data)?;
+        }
+        Ok(data)
+    }
+    
+    /// Execute a pipeline like `execute_pipeline`, also capturing every
+    /// stage's output
+    ///
+    /// Every intermediate buffer is kept until the call returns, so this is
+    /// meant for debugging rather than production runs.
+    pub fn execute_pipeline_traced(
+        &mut self,
+        algorithm_ids: &[&str],
+        input: &[u8],
+    ) -> Result<(Vec<u8>, Vec<StageTrace>), CoreError> {
+        let partial = self.execute_pipeline_partial(algorithm_ids, input);
+        if let Some(e) = partial.error {
+            return Err(e);
+        }
+        let output = partial.last_output().unwrap_or(input).to_vec();
+        Ok((output, partial.stages))
+    }
+    
+    /// Execute a pipeline like `execute_pipeline_traced`, but on failure
+    /// return the stages that succeeded alongside the error
+    ///
+    /// A diagnostic aid for finding where and on what data a long pipeline
+    /// broke. Stages after the failed one are not run; a pipeline deeper
+    /// than the engine's maximum fails before any stage.
+    pub fn execute_pipeline_partial(&mut self, algorithm_ids: &[&str], input: &[u8]) -> PartialResult {
+        let mut result = PartialResult {
+            stages: Vec::with_capacity(algorithm_ids.len()),
+            error: self.check_depth(algorithm_ids.len()).err(),
+        };
+        if result.error.is_some() {
+            return result;
+        }
+        for (stage, &algorithm_id) in algorithm_ids.iter().enumerate() {
+            let _span = self.stage_span(stage, algorithm_id);
+            let stage_input = result.last_output().unwrap_or(input);
+            let start = self.clock.now();
+            match self.execute_algorithm(algorithm_id, stage_input) {
+                Ok(output) => result.stages.push(StageTrace {
+                    algorithm_id: algorithm_id.to_string(),
+                    output_bytes: output,
+                    duration: self.clock.now() - start,
+                }),
+                Err(e) => {
+                    result.error = Some(e);
+                    break;
+                }
+            }
+        }
+        result
+    }
+    
+    /// Register a pipeline under its ID, returning any pipeline it replaces
+    ///
+    /// The pipeline is validated first, and rejected with
+    /// `CoreError::MaxDepthExceeded` if its longest dependency chain exceeds
+    /// the engine's maximum depth. Its algorithms may be registered later.
+    pub fn register_pipeline(&mut self, pipeline: Pipeline) -> Result<Option<Pipeline>, CoreError> {
+        self.check_depth(pipeline.validate()?)?;
+        Ok(self.pipelines.insert(pipeline.id.clone(), pipeline))
+    }
+    
+    /// Remove a pipeline, returning it
+    pub fn unregister_pipeline(&mut self, pipeline_id: &str) -> Option<Pipeline> {
+        self.pipelines.remove(pipeline_id)
+    }
+    
+    /// Run a registered pipeline's stages in order, returning how long each
+    /// took
+    ///
+    /// Each stage runs like `execute_algorithm` on the concatenation of its
+    /// input regions, and its output replaces its output region, which is
+    /// created if needed. Every algorithm is looked up before any stage runs;
+    /// a missing input region or a failing stage stops the pipeline, leaving
+    /// the regions earlier stages wrote in place.
+    pub fn execute_registered_pipeline(&mut self, pipeline_id: &str) -> Result<Vec<StageTiming>, CoreError> {
+        let pipeline = self
+            .pipelines
+            .get(pipeline_id)
+            .cloned()
+            .ok_or_else(|| CoreError::PipelineNotFound(pipeline_id.to_string()))?;
+        if let Some(stage) = pipeline.stages.iter().find(|stage| self.get_algorithm(&stage.algorithm_id).is_none()) {
+            return Err(CoreError::AlgorithmNotFound(stage.algorithm_id.clone()));
+        }
+        let mut timings = Vec::with_capacity(pipeline.stages.len());
+        for (index, stage) in pipeline.stages.into_iter().enumerate() {
+            let _span = self
+                .stage_span(index, &stage.algorithm_id)
+                .with("pipeline", pipeline.id.as_str())
+                .with("stage.name", stage.name.as_str());
+            let start = self.clock.now();
+            let mut input = Vec::new();
+            for key in &stage.input_keys {
+                let region = self
+                    .memory_manager
+                    .read(key)
+                    .ok_or_else(|| CoreError::MemoryKeyMissing(key.clone()))?;
+                input.extend_from_slice(region);
+            }
+            let output = self.execute_algorithm(&stage.algorithm_id, &input)?;
+            if self.memory_manager.read(&stage.output_key).is_some() {
+                self.memory_manager.replace_region(&stage.output_key, output)?;
+            } else {
+                self.memory_manager.allocate(&stage.output_key, output.len())?.copy_from_slice(&output);
+            }
+            timings.push(StageTiming {
+                stage: stage.name,
+                algorithm_id: stage.algorithm_id,
+                duration: self.clock.now() - start,
+            });
+        }
+        Ok(timings)
+    }
+    
+    /// Execute a DAG, returning every node's output by node name
+    ///
+    /// A graph whose longest dependency chain exceeds the engine's maximum
+    /// depth is rejected with `CoreError::MaxDepthExceeded`.
+    pub fn execute_dag(&mut self, dag: &Dag, input: &[u8]) -> Result<HashMap<String, Vec<u8>>, CoreError> {
+        self.execute_dag_with_progress(dag, input, None)
+    }
+    
+    /// Execute a DAG like `execute_dag`, sending a `Progress` to `progress`
+    /// after each node succeeds
+    pub fn execute_dag_with_progress(
+        &mut self,
+        dag: &Dag,
+        input: &[u8],
+        progress: Option<Sender<Progress>>,
+    ) -> Result<HashMap<String, Vec<u8>>, CoreError> {
+        let (dependencies, depth) = dag.dependencies()?;
+        self.check_depth(depth)?;
+        let algorithms = dag
+            .nodes
+            .iter()
+            .map(|node| {
+                self.get_algorithm(&node.algorithm_id)
+                    .ok_or_else(|| CoreError::AlgorithmNotFound(node.algorithm_id.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        let count = dag.nodes.len();
+        let mut dependents = vec![Vec::new(); count];
+        for (node, deps) in dependencies.iter().enumerate() {
+            for &dep in deps {
+                dependents[dep].push(node);
+            }
+        }
+        let pending: Vec<usize> = dependencies.iter().map(Vec::len).collect();
+        let run = DagRun {
+            algorithms,
+            dependencies,
+            dependents,
+            input,
+            hooks: &self.hooks,
+            context: &self.context,
+            progress: progress.as_ref(),
+            base: &self.memory_manager,
+            state: Mutex::new(DagState {
+                ready: (0..count).filter(|&i| pending[i] == 0).collect(),
+                pending,
+                outputs: vec![None; count],
+                changes: vec![Vec::new(); count],
+                running: 0,
+                completed: 0,
+                error: None,
+            }),
+            wake: Condvar::new(),
+        };
+        
+        self.pool.run(dag.max_parallelism.min(count), || run.work());
+        
+        let state = run.state.into_inner().unwrap_or_else(|e| e.into_inner());
+        if let Some(e) = state.error {
+            return Err(e);
+        }
+        for changes in state.changes {
+            self.memory_manager.apply_changes(changes);
+        }
+        Ok(dag
+            .nodes
+            .iter()
+            .zip(state.outputs)
+            .map(|(node, output)| (node.name.clone(), output.unwrap_or_default()))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::AlgorithmMetadata;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Instant;
+    
+    type Spans = Arc<Mutex<Vec<(Instant, Instant)>>>;
+    
+    /// Appends a marker byte after sleeping, recording when it ran
+    struct Tag {
+        id: String,
+        marker: u8,
+        delay: Duration,
+        spans: Spans,
+    }
+    
+    impl Algorithm for Tag {
+        fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            let start = Instant::now();
+            std::thread::sleep(self.delay);
+            memory.write(&self.id, &[self.marker])?;
+            self.spans.lock().unwrap().push((start, Instant::now()));
+            
+            let mut output = input.to_vec();
+            output.push(self.marker);
+            Ok(output)
+        }
+        
+        fn id(&self) -> &str {
+            &self.id
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata::default()
+        }
+    }
+    
+    #[test]
+    fn test_traced_pipeline_captures_each_stage() {
+        let mut engine = CoreEngine::new();
+        let spans = Spans::default();
+        for (id, marker) in [("first", 1), ("second", 2)] {
+            engine
+                .register(Box::new(Tag { id: id.to_string(), marker, delay: Duration::ZERO, spans: spans.clone() }))
+                .unwrap();
+        }
+        
+        let (output, traces) = engine.execute_pipeline_traced(&["first", "second"], &[0]).unwrap();
+        assert_eq!(output, vec![0, 1, 2]);
+        assert_eq!(output, engine.execute_pipeline(&["first", "second"], &[0]).unwrap());
+        
+        let stages: Vec<(&str, &[u8])> = traces
+            .iter()
+            .map(|trace| (trace.algorithm_id.as_str(), &trace.output_bytes[..]))
+            .collect();
+        assert_eq!(stages, vec![("first", &[0, 1][..]), ("second", &[0, 1, 2][..])]);
+    }
+    
+    /// Always fails, counting its invocations
+    struct Fail(Arc<AtomicUsize>);
+    
+    impl Algorithm for Fail {
+        fn process(&self, _input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Err(CoreError::ProcessingFailed("boom".to_string()))
+        }
+        
+        fn id(&self) -> &str {
+            "fail"
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata::default()
+        }
+    }
+    
+    #[test]
+    fn test_partial_pipeline_keeps_stages_before_failure() {
+        let mut engine = CoreEngine::new();
+        let spans = Spans::default();
+        for (id, marker) in [("first", 1), ("second", 2), ("fourth", 4)] {
+            engine
+                .register(Box::new(Tag { id: id.to_string(), marker, delay: Duration::ZERO, spans: spans.clone() }))
+                .unwrap();
+        }
+        let calls = Arc::new(AtomicUsize::new(0));
+        engine.register(Box::new(Fail(calls.clone()))).unwrap();
+        
+        let partial = engine.execute_pipeline_partial(&["first", "second", "fail", "fourth"], &[0]);
+        assert!(!partial.is_complete());
+        assert_eq!(partial.error, Some(CoreError::ProcessingFailed("boom".to_string())));
+        let outputs: Vec<&[u8]> = partial.stages.iter().map(|stage| &stage.output_bytes[..]).collect();
+        assert_eq!(outputs, vec![&[0, 1][..], &[0, 1, 2][..]]);
+        assert_eq!(engine.memory().read("fourth"), None, "stages after the failure must not run");
+        
+        let complete = engine.execute_pipeline_partial(&["first", "second"], &[0]);
+        assert!(complete.is_complete());
+        assert_eq!(complete.last_output(), Some(&[0, 1, 2][..]));
+    }
+    
+    fn engine_with_tags(spans: &Spans, delay: Duration) -> CoreEngine {
+        // Enough threads for the branches to overlap even on a single core
+        let mut engine = CoreEngine::builder().threads(4).build();
+        for (id, marker) in [("source", 0), ("left", 1), ("middle", 2), ("right", 3), ("sink", 4)] {
+            engine
+                .register(Box::new(Tag {
+                    id: id.to_string(),
+                    marker,
+                    delay,
+                    spans: Arc::clone(spans),
+                }))
+                .unwrap();
+        }
+        engine
+    }
+    
+    fn fan_out_fan_in() -> Dag {
+        Dag::new()
+            .node("source", "source", &[])
+            .node("left", "left", &["source"])
+            .node("middle", "middle", &["source"])
+            .node("right", "right", &["source"])
+            .node("sink", "sink", &["left", "middle", "right"])
+    }
+    
+    #[test]
+    fn test_fan_out_fan_in_results_and_overlap() {
+        let spans: Spans = Arc::default();
+        let mut engine = engine_with_tags(&spans, Duration::from_millis(50));
+        
+        let outputs = engine.execute_dag(&fan_out_fan_in().with_max_parallelism(3), &[9]).unwrap();
+        assert_eq!(outputs["source"], vec![9, 0]);
+        assert_eq!(outputs["left"], vec![9, 0, 1]);
+        assert_eq!(outputs["right"], vec![9, 0, 3]);
+        assert_eq!(outputs["sink"], vec![9, 0, 1, 9, 0, 2, 9, 0, 3, 4]);
+        
+        // Regions written by every branch are merged back
+        for (key, marker) in [("left", 1u8), ("middle", 2), ("right", 3)] {
+            assert_eq!(engine.memory_manager.read(key), Some(&[marker][..]));
+        }
+        
+        // The three branch spans come after the source and overlap each other
+        let spans = spans.lock().unwrap();
+        let mut branches: Vec<_> = spans[1..4].to_vec();
+        branches.sort();
+        assert!(branches[1].0 < branches[0].1, "branches did not overlap");
+        assert!(branches[2].0 < branches[0].1, "branches did not overlap");
+    }
+    
+    #[test]
+    fn test_branch_failure_cancels_pending_siblings() {
+        let spans: Spans = Arc::default();
+        let mut engine = engine_with_tags(&spans, Duration::ZERO);
+        let calls = Arc::new(AtomicUsize::new(0));
+        engine.register(Box::new(Fail(Arc::clone(&calls)))).unwrap();
+        
+        let dag = Dag::new()
+            .node("source", "source", &[])
+            .node("bad", "fail", &["source"])
+            .node("left", "left", &["source"])
+            .node("sink", "sink", &["bad", "left"])
+            .with_max_parallelism(1);
+        let result = engine.execute_dag(&dag, &[]);
+        
+        assert_eq!(result, Err(CoreError::ProcessingFailed("boom".to_string())));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        // Only the source ran; the sibling queued behind the failure was skipped
+        assert_eq!(spans.lock().unwrap().len(), 1);
+        assert!(engine.memory_manager.read("source").is_none());
+    }
+    
+    /// Panics on every call
+    struct Explode;
+    
+    impl Algorithm for Explode {
+        fn process(&self, _input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            panic!("exploded")
+        }
+        
+        fn id(&self) -> &str {
+            "explode"
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata::default()
+        }
+    }
+    
+    #[test]
+    fn test_panicking_node_fails_the_dag_across_workers() {
+        let spans: Spans = Arc::default();
+        let mut engine = engine_with_tags(&spans, Duration::from_millis(20));
+        engine.register(Box::new(Explode)).unwrap();
+        
+        let dag = Dag::new()
+            .node("source", "source", &[])
+            .node("bad", "explode", &["source"])
+            .node("left", "left", &["source"])
+            .node("right", "right", &["source"])
+            .node("sink", "sink", &["bad", "left", "right"])
+            .with_max_parallelism(3);
+        let result = engine.execute_dag(&dag, &[]);
+        
+        assert_eq!(result, Err(CoreError::ProcessingFailed("'explode' panicked: exploded".to_string())));
+        assert!(engine.memory_manager.read("sink").is_none());
+    }
+    
+    #[test]
+    fn test_malformed_graphs_rejected() {
+        let mut engine = CoreEngine::new();
+        let unknown = Dag::new().node("a", "passthrough", &["missing"]);
+        let cycle = Dag::new()
+            .node("a", "passthrough", &["b"])
+            .node("b", "passthrough", &["a"]);
+        let duplicate = Dag::new()
+            .node("a", "passthrough", &[])
+            .node("a", "passthrough", &[]);
+        
+        for dag in [unknown, cycle, duplicate] {
+            assert!(matches!(engine.execute_dag(&dag, &[]), Err(CoreError::InvalidDefinition(_))));
+        }
+        assert!(matches!(
+            engine.execute_dag(&Dag::new().node("a", "nope", &[]), &[]),
+            Err(CoreError::AlgorithmNotFound(_))
+        ));
+    }
+    
+    #[test]
+    fn test_registered_pipeline_binds_stages_to_regions() {
+        let clock = Arc::new(crate::clock::MockClock::new());
+        let mut engine = CoreEngine::builder().clock(clock.clone()).build();
+        let spans = Spans::default();
+        for (id, marker) in [("preprocess", 1), ("filter", 2), ("planner", 3)] {
+            engine
+                .register(Box::new(Tag { id: id.to_string(), marker, delay: Duration::ZERO, spans: spans.clone() }))
+                .unwrap();
+        }
+        let pipeline = Pipeline::new("nav")
+            .stage("pre", "preprocess", &["sensor/lidar"], "cleaned")
+            .stage("filter", "filter", &["cleaned"], "filtered")
+            .stage("plan", "planner", &["filtered", "goal"], "plan");
+        assert_eq!(pipeline.validate(), Ok(3));
+        assert_eq!(engine.register_pipeline(pipeline), Ok(None));
+        
+        assert!(matches!(engine.execute_registered_pipeline("nav"), Err(CoreError::MemoryKeyMissing(_))));
+        engine.memory_manager.write("sensor/lidar", &[7]).unwrap();
+        engine.memory_manager.write("goal", &[8]).unwrap();
+        let timings = engine.execute_registered_pipeline("nav").unwrap();
+        let stages: Vec<&str> = timings.iter().map(|timing| timing.stage.as_str()).collect();
+        assert_eq!(stages, vec!["pre", "filter", "plan"]);
+        assert!(timings.iter().all(|timing| timing.duration == Duration::ZERO));
+        assert_eq!(engine.memory_manager.read("filtered"), Some(&[7, 1, 2][..]));
+        assert_eq!(engine.memory_manager.read("plan"), Some(&[7, 1, 2, 8, 3][..]));
+        
+        // A rerun replaces the outputs rather than appending to them
+        engine.memory_manager.write("sensor/lidar", &[9]).unwrap();
+        engine.execute_registered_pipeline("nav").unwrap();
+        assert_eq!(engine.memory_manager.read("plan"), Some(&[9, 1, 2, 8, 3][..]));
+        
+        assert!(matches!(engine.execute_registered_pipeline("absent"), Err(CoreError::PipelineNotFound(_))));
+        engine.register_pipeline(Pipeline::new("broken").stage("a", "nope", &[], "out")).unwrap();
+        assert!(matches!(engine.execute_registered_pipeline("broken"), Err(CoreError::AlgorithmNotFound(_))));
+        assert!(engine.unregister_pipeline("nav").is_some());
+    }
+    
+    #[test]
+    fn test_malformed_pipelines_rejected() {
+        let mut engine = CoreEngine::builder().max_depth(2).build();
+        let malformed = [
+            Pipeline::new("empty"),
+            Pipeline::new("names")
+                .stage("a", "passthrough", &[], "x")
+                .stage("a", "passthrough", &[], "y"),
+            Pipeline::new("outputs")
+                .stage("a", "passthrough", &[], "x")
+                .stage("b", "passthrough", &[], "x"),
+            Pipeline::new("order")
+                .stage("a", "passthrough", &["y"], "x")
+                .stage("b", "passthrough", &["x"], "y"),
+            Pipeline::new("self").stage("a", "passthrough", &["x"], "x"),
+        ];
+        for pipeline in malformed {
+            assert!(matches!(engine.register_pipeline(pipeline), Err(CoreError::InvalidDefinition(_))));
+        }
+        let deep = Pipeline::new("deep")
+            .stage("a", "passthrough", &[], "x")
+            .stage("b", "passthrough", &["x"], "y")
+            .stage("c", "passthrough", &["y"], "z");
+        assert_eq!(engine.register_pipeline(deep), Err(CoreError::MaxDepthExceeded { depth: 3, max: 2 }));
+    }
+    
+    #[test]
+    fn test_depth_limit_rejects_deep_pipelines_and_dags() {
+        let mut engine = CoreEngine::builder().max_depth(8).build();
+        let stages = ["passthrough"; 9];
+        assert_eq!(
+            engine.execute_pipeline(&stages, &[1]),
+            Err(CoreError::MaxDepthExceeded { depth: 9, max: 8 })
+        );
+        assert!(engine.execute_pipeline_traced(&stages, &[1]).is_err());
+        assert_eq!(engine.execute_pipeline(&stages[..8], &[1]).unwrap(), vec![1]);
+        
+        // A chain of nine nodes is too deep; nine siblings are not
+        let names: Vec<String> = (0..9).map(|i| format!("n{}", i)).collect();
+        let mut chain = Dag::new().node(&names[0], "passthrough", &[]);
+        let mut wide = Dag::new();
+        for i in 1..9 {
+            chain = chain.node(&names[i], "passthrough", &[&names[i - 1]]);
+        }
+        for name in &names {
+            wide = wide.node(name, "passthrough", &[]);
+        }
+        assert_eq!(
+            engine.execute_dag(&chain, &[1]),
+            Err(CoreError::MaxDepthExceeded { depth: 9, max: 8 })
+        );
+        assert_eq!(engine.execute_dag(&wide, &[1]).unwrap().len(), 9);
+        
+        let default_limit = vec!["passthrough"; DEFAULT_MAX_DEPTH + 1];
+        assert!(CoreEngine::new().execute_pipeline(&default_limit, &[]).is_err());
+    }
+}
@@ -0,0 +1,505 @@
+//! Algorithms loaded at runtime from shared libraries
+//!
+//! A plugin is a shared library, usually a `cdylib` crate, exporting a C
+//! function named by `PLUGIN_ENTRY_POINT` that takes no arguments and
+//! returns a pointer to a `PluginDescriptor`. Only the `#[repr(C)]` types
+//! here cross the boundary, so a plugin may be built with another compiler
+//! version, or in another language, than the engine loading it.
+//!
+//! The descriptor, its algorithm table and the strings it points to must
+//! stay valid while the library is loaded. `process` functions may be
+//! called from several threads at once. A library is unloaded once its
+//! algorithms are unregistered and no execution still holds one, after its
+//! `unload` function, if any, has run.
+
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::os::unix::ffi::OsStrExt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::algorithm::{Algorithm, AlgorithmMetadata};
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+use crate::CoreEngine;
+
+/// Version of the plugin ABI this build understands
+///
+/// Plugins report the version they were built against in
+/// `PluginDescriptor::abi_version`; any other value is rejected.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// Symbol every plugin exports, of type `extern "C" fn() -> *const PluginDescriptor`
+pub const PLUGIN_ENTRY_POINT: &str = "robotics_core_plugin";
+
+/// Most algorithms one plugin may declare
+const MAX_PLUGIN_ALGORITHMS: usize = 4096;
+
+/// Appends `len` bytes at `data` to the buffer behind `sink`
+pub type PluginWriteFn = unsafe extern "C" fn(sink: *mut c_void, data: *const u8, len: usize);
+
+/// Processes `input_len` bytes at `input`, writing the output through
+/// `write(sink, ..)` and returning `0`
+///
+/// Any other return value fails the execution, with whatever was written
+/// taken as the error message.
+pub type PluginProcessFn = unsafe extern "C" fn(
+    user_data: *mut c_void,
+    input: *const u8,
+    input_len: usize,
+    sink: *mut c_void,
+    write: PluginWriteFn,
+) -> i32;
+
+/// One algorithm offered by a plugin
+#[repr(C)]
+pub struct PluginAlgorithm {
+    /// NUL-terminated UTF-8 ID the algorithm is registered under
+    pub id: *const c_char,
+    /// NUL-terminated JSON `AlgorithmMetadata`, or null to name the
+    /// algorithm by its ID
+    pub metadata_json: *const c_char,
+    /// Passed back unchanged to `process`
+    pub user_data: *mut c_void,
+    pub process: PluginProcessFn,
+}
+
+/// What a plugin's entry point returns
+#[repr(C)]
+pub struct PluginDescriptor {
+    /// Must equal `PLUGIN_ABI_VERSION`
+    pub abi_version: u32,
+    pub algorithm_count: usize,
+    /// `algorithm_count` entries, or null if there are none
+    pub algorithms: *const PluginAlgorithm,
+    /// Called once just before the library is unloaded, if not null
+    pub unload: Option<unsafe extern "C" fn()>,
+}
+
+/// An open shared library, closed when the last algorithm from it is dropped
+struct Library {
+    handle: *mut c_void,
+    path: PathBuf,
+    unload: Option<unsafe extern "C" fn()>,
+}
+
+// SAFETY: the handle is only passed to `dlsym` while loading and to
+// `dlclose` once on drop; both are thread-safe.
+unsafe impl Send for Library {}
+// SAFETY: as above; a shared `Library` is never used to call into the handle.
+unsafe impl Sync for Library {}
+
+fn c_path(path: &Path) -> Result<CString, CoreError> {
+    CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| CoreError::InvalidParameter(format!("plugin path {} contains NUL", path.display())))
+}
+
+/// Copy the library at `path` to a new, unique file, beside it if the
+/// directory is writable and in the temporary directory otherwise
+fn fresh_copy(path: &Path) -> Result<PathBuf, CoreError> {
+    static COPIES: AtomicU64 = AtomicU64::new(0);
+    let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let unique = format!(".{}.{}-{}", name, std::process::id(), COPIES.fetch_add(1, Ordering::Relaxed));
+    let beside = path.with_file_name(&unique);
+    if fs::copy(path, &beside).is_ok() {
+        return Ok(beside);
+    }
+    let temp = std::env::temp_dir().join(&unique);
+    fs::copy(path, &temp)?;
+    Ok(temp)
+}
+
+impl Library {
+    /// Load the library at `path`
+    ///
+    /// The loader hands back the library already loaded from a path, old
+    /// code and all, so if an earlier version is still loaded from `path`
+    /// this loads a fresh copy of the file instead.
+    fn open(path: &Path) -> Result<Self, CoreError> {
+        let name = c_path(path)?;
+        // SAFETY: `name` is NUL-terminated; `RTLD_NOLOAD` only looks the
+        // library up, and a handle it returns is released straight away.
+        let loaded = unsafe {
+            let handle = libc::dlopen(name.as_ptr(), libc::RTLD_NOW | libc::RTLD_NOLOAD);
+            !handle.is_null() && libc::dlclose(handle) == 0
+        };
+        let copy = if loaded { Some(fresh_copy(path)?) } else { None };
+        let name = match &copy {
+            Some(copy) => c_path(copy)?,
+            None => name,
+        };
+        // SAFETY: `name` is a valid NUL-terminated string for the call.
+        let handle = unsafe { libc::dlopen(name.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        let error = handle.is_null().then(dl_error);
+        // The mapping outlives the file, so the copy is not needed once loaded
+        if let Some(copy) = copy {
+            if let Err(e) = fs::remove_file(&copy) {
+                log::warn!("Could not remove plugin copy {}: {}", copy.display(), e);
+            }
+        }
+        if let Some(error) = error {
+            return Err(CoreError::IoError(format!("cannot load plugin {}: {}", path.display(), error)));
+        }
+        Ok(Self {
+            handle,
+            path: path.to_path_buf(),
+            unload: None,
+        })
+    }
+    
+    /// The library's descriptor, as returned by its entry point
+    fn descriptor(&self) -> Result<*const PluginDescriptor, CoreError> {
+        let symbol = CString::new(PLUGIN_ENTRY_POINT)
+            .map_err(|_| CoreError::InvalidDefinition("entry point name contains NUL".to_string()))?;
+        // SAFETY: `handle` is open and `symbol` is NUL-terminated.
+        let entry = unsafe { libc::dlsym(self.handle, symbol.as_ptr()) };
+        if entry.is_null() {
+            return Err(CoreError::InvalidDefinition(format!(
+                "{} does not export {}",
+                self.path.display(),
+                PLUGIN_ENTRY_POINT
+            )));
+        }
+        // SAFETY: the plugin contract gives the entry point this signature.
+        let entry: unsafe extern "C" fn() -> *const PluginDescriptor = unsafe { std::mem::transmute(entry) };
+        // SAFETY: the entry point takes no arguments and has no preconditions.
+        Ok(unsafe { entry() })
+    }
+}
+
+impl Drop for Library {
+    fn drop(&mut self) {
+        if let Some(unload) = self.unload {
+            // SAFETY: the plugin asked for this call before unloading, and the
+            // library is still loaded.
+            unsafe { unload() };
+        }
+        // SAFETY: `handle` came from `dlopen` and is closed only here.
+        if unsafe { libc::dlclose(self.handle) } != 0 {
+            log::warn!("Closing plugin {} failed: {}", self.path.display(), dl_error());
+        }
+    }
+}
+
+fn dl_error() -> String {
+    // SAFETY: `dlerror` returns null or a NUL-terminated message that stays
+    // valid until the next `dl*` call on this thread.
+    unsafe {
+        let message = libc::dlerror();
+        if message.is_null() {
+            "unknown error".to_string()
+        } else {
+            CStr::from_ptr(message).to_string_lossy().into_owned()
+        }
+    }
+}
+
+/// Read a NUL-terminated UTF-8 string from a descriptor
+///
+/// # Safety
+///
+/// `text` must be null or point at a NUL-terminated string.
+unsafe fn descriptor_str(text: *const c_char, what: &str) -> Result<Option<String>, CoreError> {
+    if text.is_null() {
+        return Ok(None);
+    }
+    CStr::from_ptr(text)
+        .to_str()
+        .map(|text| Some(text.to_string()))
+        .map_err(|_| CoreError::InvalidDefinition(format!("plugin {} is not UTF-8", what)))
+}
+
+/// An algorithm implemented by a plugin
+struct PluginAlgorithmImpl {
+    id: String,
+    metadata: AlgorithmMetadata,
+    user_data: *mut c_void,
+    process: PluginProcessFn,
+    // Keeps the code behind `process` loaded
+    _library: Arc<Library>,
+}
+
+// SAFETY: the plugin contract requires `process` to be callable from any
+// thread, concurrently, with the same `user_data`.
+unsafe impl Send for PluginAlgorithmImpl {}
+// SAFETY: as above.
+unsafe impl Sync for PluginAlgorithmImpl {}
+
+/// Appends to the `Vec<u8>` behind `sink`
+unsafe extern "C" fn write_output(sink: *mut c_void, data: *const u8, len: usize) {
+    if sink.is_null() || data.is_null() || len == 0 {
+        return;
+    }
+    // SAFETY: `process` passes the sink it was given, which points at the
+    // output vector, and the plugin vouches for `len` bytes at `data`.
+    let (output, data) = unsafe { (&mut *(sink as *mut Vec<u8>), std::slice::from_raw_parts(data, len)) };
+    output.extend_from_slice(data);
+}
+
+impl Algorithm for PluginAlgorithmImpl {
+    fn process(&self, input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+        let mut output: Vec<u8> = Vec::new();
+        // SAFETY: `input` is valid for its length, the sink points at
+        // `output` for the duration of the call, and the library stays
+        // loaded while `self` holds it.
+        let status = unsafe {
+            (self.process)(
+                self.user_data,
+                input.as_ptr(),
+                input.len(),
+                &mut output as *mut Vec<u8> as *mut c_void,
+                write_output,
+            )
+        };
+        if status != 0 {
+            return Err(CoreError::ProcessingFailed(format!(
+                "plugin algorithm '{}' failed with status {}: {}",
+                self.id,
+                status,
+                String::from_utf8_lossy(&output)
+            )));
+        }
+        Ok(output)
+    }
+    
+    fn id(&self) -> &str {
+        &self.id
+    }
+    
+    fn metadata(&self) -> AlgorithmMetadata {
+        self.metadata.clone()
+    }
+}
+
+/// Validate `descriptor` and wrap its algorithms, which keep `library` open
+///
+/// # Safety
+///
+/// `descriptor` must be null or point at a descriptor that, with
+/// everything it references, stays valid while `library` is loaded.
+unsafe fn instantiate(
+    mut library: Library,
+    descriptor: *const PluginDescriptor,
+) -> Result<Vec<Box<dyn Algorithm>>, CoreError> {
+    let path = library.path.display().to_string();
+    let descriptor = descriptor
+        .as_ref()
+        .ok_or_else(|| CoreError::InvalidDefinition(format!("plugin {} returned no descriptor", path)))?;
+    if descriptor.abi_version != PLUGIN_ABI_VERSION {
+        return Err(CoreError::InvalidDefinition(format!(
+            "plugin {} targets ABI version {}, this build supports {}",
+            path, descriptor.abi_version, PLUGIN_ABI_VERSION
+        )));
+    }
+    let entries = match descriptor.algorithm_count {
+        0 => &[][..],
+        count if count > MAX_PLUGIN_ALGORITHMS || descriptor.algorithms.is_null() => {
+            return Err(CoreError::InvalidDefinition(format!(
+                "plugin {} declares {} algorithms without a valid table",
+                path, count
+            )));
+        }
+        count => std::slice::from_raw_parts(descriptor.algorithms, count),
+    };
+    
+    let mut algorithms = Vec::with_capacity(entries.len());
+    let mut ids = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let id = descriptor_str(entry.id, "algorithm ID")?
+            .filter(|id| !id.is_empty())
+            .ok_or_else(|| CoreError::InvalidDefinition(format!("plugin {} has an algorithm without an ID", path)))?;
+        if ids.contains(&id) {
+            return Err(CoreError::InvalidDefinition(format!("plugin {} declares '{}' twice", path, id)));
+        }
+        let metadata = match descriptor_str(entry.metadata_json, "metadata")? {
+            Some(json) => serde_json::from_str(&json).map_err(|e| {
+                CoreError::InvalidDefinition(format!("plugin {} metadata for '{}': {}", path, id, e))
+            })?,
+            None => AlgorithmMetadata {
+                name: id.clone(),
+                ..Default::default()
+            },
+        };
+        ids.push(id.clone());
+        algorithms.push((id, metadata, entry.user_data, entry.process));
+    }
+    
+    library.unload = descriptor.unload;
+    let library = Arc::new(library);
+    Ok(algorithms
+        .into_iter()
+        .map(|(id, metadata, user_data, process)| {
+            Box::new(PluginAlgorithmImpl {
+                id,
+                metadata,
+                user_data,
+                process,
+                _library: Arc::clone(&library),
+            }) as Box<dyn Algorithm>
+        })
+        .collect())
+}
+
+/// Load the plugin at `path` and wrap its algorithms
+fn open(path: &Path) -> Result<Vec<Box<dyn Algorithm>>, CoreError> {
+    let library = Library::open(path)?;
+    let descriptor = library.descriptor()?;
+    // SAFETY: the plugin contract keeps the descriptor valid while the
+    // library is loaded, and the algorithms keep it loaded.
+    unsafe { instantiate(library, descriptor) }
+}
+
+impl CoreEngine {
+    /// Load the plugin library at `path` and register its algorithms,
+    /// returning their IDs
+    ///
+    /// Nothing is registered unless every algorithm can be: the ABI version
+    /// must match, the descriptor must be well formed, and no ID may be
+    /// taken already. A library that is already loaded is refused; use
+    /// `reload_plugin` to replace it.
+    pub fn load_plugin(&mut self, path: impl AsRef<Path>) -> Result<Vec<String>, CoreError> {
+        let path = path.as_ref().canonicalize()?;
+        if self.plugins.contains_key(&path) {
+            return Err(CoreError::PreconditionFailed(format!(
+                "plugin {} is already loaded",
+                path.display()
+            )));
+        }
+        let algorithms = open(&path)?;
+        self.install_plugin(path, algorithms)
+    }
+    
+    /// Unregister the algorithms of the plugin loaded from `path`
+    ///
+    /// Executions already running finish with the old code; the library
+    /// itself is unloaded once the last of them, and any `ExecutorHandle`
+    /// made while it was loaded, lets go of its algorithms.
+    pub fn unload_plugin(&mut self, path: impl AsRef<Path>) -> Result<(), CoreError> {
+        let path = path.as_ref().canonicalize()?;
+        let ids = self
+            .plugins
+            .remove(&path)
+            .ok_or_else(|| CoreError::PreconditionFailed(format!("plugin {} is not loaded", path.display())))?;
+        for id in ids {
+            self.unregister(&id)?;
+        }
+        Ok(())
+    }
+    
+    /// Swap the plugin loaded from `path` for the library now at `path`,
+    /// returning the new algorithm IDs
+    ///
+    /// The new library is loaded before the old algorithms are removed, so
+    /// if it fails to load the old ones keep serving. Replace the file by
+    /// renaming a new one over it rather than rewriting it in place, which
+    /// can crash a process still running code from the old file.
+    pub fn reload_plugin(&mut self, path: impl AsRef<Path>) -> Result<Vec<String>, CoreError> {
+        let path = path.as_ref().canonicalize()?;
+        let algorithms = open(&path)?;
+        if let Some(ids) = self.plugins.remove(&path) {
+            for id in &ids {
+                self.unregister(id)?;
+            }
+        }
+        self.install_plugin(path, algorithms)
+    }
+    
+    /// Paths of the loaded plugins
+    pub fn plugins(&self) -> Vec<&Path> {
+        self.plugins.keys().map(PathBuf::as_path).collect()
+    }
+    
+    /// Register a plugin's algorithms under `path`, all or none
+    fn install_plugin(&mut self, path: PathBuf, algorithms: Vec<Box<dyn Algorithm>>) -> Result<Vec<String>, CoreError> {
+        let ids: Vec<String> = algorithms.iter().map(|algorithm| algorithm.id().to_string()).collect();
+        if let Some(taken) = ids.iter().find(|id| self.registry.get(id).is_some()) {
+            return Err(CoreError::DuplicateAlgorithm(taken.clone()));
+        }
+        for algorithm in algorithms {
+            self.register(algorithm)?;
+        }
+        log::info!("Loaded plugin {} with algorithms {:?}", path.display(), ids);
+        self.plugins.insert(path, ids.clone());
+        Ok(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // Doubles every byte, failing with a message on empty input
+    unsafe extern "C" fn doubler(
+        user_data: *mut c_void,
+        input: *const u8,
+        input_len: usize,
+        sink: *mut c_void,
+        write: PluginWriteFn,
+    ) -> i32 {
+        assert!(user_data.is_null());
+        if input_len == 0 {
+            let message = b"empty input";
+            write(sink, message.as_ptr(), message.len());
+            return 7;
+        }
+        for &byte in std::slice::from_raw_parts(input, input_len) {
+            let doubled = byte.wrapping_mul(2);
+            write(sink, &doubled, 1);
+        }
+        0
+    }
+    
+    // The test binary itself, which stands in for a plugin library
+    fn this_process() -> Library {
+        // SAFETY: a null name opens the main program.
+        let handle = unsafe { libc::dlopen(std::ptr::null(), libc::RTLD_NOW) };
+        assert!(!handle.is_null());
+        Library {
+            handle,
+            path: PathBuf::from("self"),
+            unload: None,
+        }
+    }
+    
+    #[test]
+    fn test_descriptor_validation_and_registration() {
+        let table = [PluginAlgorithm {
+            id: c"double".as_ptr(),
+            metadata_json: std::ptr::null(),
+            user_data: std::ptr::null_mut(),
+            process: doubler,
+        }];
+        let mut descriptor = PluginDescriptor {
+            abi_version: PLUGIN_ABI_VERSION + 1,
+            algorithm_count: table.len(),
+            algorithms: table.as_ptr(),
+            unload: None,
+        };
+        // SAFETY: the descriptor and table outlive every algorithm made here.
+        let load = |descriptor: &PluginDescriptor| unsafe { instantiate(this_process(), descriptor) };
+        assert!(matches!(load(&descriptor), Err(CoreError::InvalidDefinition(m)) if m.contains("ABI version 2")));
+        descriptor.abi_version = PLUGIN_ABI_VERSION;
+        descriptor.algorithms = std::ptr::null();
+        assert!(load(&descriptor).is_err());
+        descriptor.algorithms = table.as_ptr();
+        
+        let mut engine = CoreEngine::new();
+        let path = PathBuf::from("/plugins/libdouble.so");
+        assert_eq!(engine.install_plugin(path.clone(), load(&descriptor).unwrap()).unwrap(), ["double"]);
+        assert_eq!(engine.execute_algorithm("double", &[1, 2, 200]).unwrap(), vec![2, 4, 144]);
+        let error = engine.execute_algorithm("double", &[]).unwrap_err();
+        assert!(matches!(error, CoreError::ProcessingFailed(m) if m.ends_with("status 7: empty input")));
+        assert_eq!(engine.list_algorithms()[0].1.name, "double");
+        assert_eq!(engine.plugins(), [path.as_path()]);
+        assert!(matches!(
+            engine.install_plugin(PathBuf::from("other"), load(&descriptor).unwrap()),
+            Err(CoreError::DuplicateAlgorithm(_))
+        ));
+        
+        assert!(matches!(
+            engine.load_plugin("/nonexistent/libplugin.so"),
+            Err(CoreError::IoError(_))
+        ));
+    }
+}
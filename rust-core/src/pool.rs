@@ -0,0 +1,104 @@
+//! Worker threads shared by the engine's parallel operations
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::{Arc, OnceLock};
+
+/// The engine's thread pool, started on first use
+///
+/// Every parallel operation runs on this one pool, so `threads` caps the
+/// engine's total worker threads. Threads are unavailable in the browser, so
+/// on wasm the pool has a single "thread": the caller.
+pub(crate) struct WorkerPool {
+    // Prefix of worker thread names
+    #[cfg(not(target_arch = "wasm32"))]
+    name: &'static str,
+    threads: usize,
+    #[cfg(not(target_arch = "wasm32"))]
+    pool: OnceLock<Option<Arc<rayon::ThreadPool>>>,
+}
+
+impl WorkerPool {
+    /// Create a pool of `threads` workers, or one per available core
+    pub(crate) fn new(threads: Option<usize>) -> Self {
+        Self::named("robotics-core", threads)
+    }
+    
+    /// Create a pool like `new` whose threads are named after `name`
+    pub(crate) fn named(name: &'static str, threads: Option<usize>) -> Self {
+        #[cfg(target_arch = "wasm32")]
+        let _ = name;
+        let threads = if cfg!(target_arch = "wasm32") {
+            1
+        } else {
+            threads.unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()))
+        };
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            name,
+            threads: threads.max(1),
+            #[cfg(not(target_arch = "wasm32"))]
+            pool: OnceLock::new(),
+        }
+    }
+    
+    pub(crate) fn threads(&self) -> usize {
+        self.threads
+    }
+    
+    /// Run `work` on up to `workers` pool threads at once, returning when
+    /// every copy has finished
+    ///
+    /// With a single worker, or if the pool cannot be started, `work` runs
+    /// once on the calling thread; callers must therefore be written as
+    /// worker loops that drain all remaining work.
+    pub(crate) fn run(&self, workers: usize, work: impl Fn() + Sync) {
+        let workers = workers.min(self.threads);
+        if workers > 1 {
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(pool) = self.pool() {
+                pool.scope(|scope| {
+                    for _ in 0..workers {
+                        scope.spawn(|_| work());
+                    }
+                });
+                return;
+            }
+        }
+        work();
+    }
+    
+    /// Run `work` on a pool thread without waiting for it
+    ///
+    /// The job keeps the pool alive, so it still runs if the engine is
+    /// dropped first. If the pool cannot be started, `work` runs on the
+    /// calling thread.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn spawn(&self, work: impl FnOnce() + Send + 'static) {
+        match self.pool() {
+            Some(pool) => {
+                let keep_alive = Arc::clone(pool);
+                pool.spawn(move || {
+                    let _pool = keep_alive;
+                    work();
+                });
+            }
+            None => work(),
+        }
+    }
+    
+    #[cfg(not(target_arch = "wasm32"))]
+    fn pool(&self) -> Option<&Arc<rayon::ThreadPool>> {
+        let name = self.name;
+        self.pool
+            .get_or_init(|| {
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(self.threads)
+                    .thread_name(move |index| format!("{}-{}", name, index))
+                    .build()
+                    .map(Arc::new)
+                    .map_err(|e| log::warn!("Thread pool failed to start, running inline: {}", e))
+                    .ok()
+            })
+            .as_ref()
+    }
+}
@@ -0,0 +1,678 @@
+//! Execution classes: dedicated real-time threads, background work that
+//! yields to more urgent executions, and per-class CPU budgets
+//!
+//! Preemption is cooperative. A background execution only pauses where
+//! its algorithm calls `Context::yield_now`, and once before it starts, so
+//! an algorithm that never yields runs to completion however urgent the
+//! work waiting behind it.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::batch::CancellationToken;
+use crate::cpu_time::thread_cpu_time;
+use crate::error::CoreError;
+use crate::telemetry::Telemetry;
+use crate::CoreEngine;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use realtime::{RealTimeOptions, RealTimeThread};
+
+/// Hold-ups at yield points at least this long count as starvation unless
+/// the engine is told otherwise
+const DEFAULT_STARVATION_THRESHOLD: Duration = Duration::from_secs(1);
+
+/// How often a paused execution checks whether it was cancelled
+const CANCEL_POLL: Duration = Duration::from_millis(10);
+
+/// How urgently an algorithm's executions must run, most urgent first
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ExecutionClass {
+    /// Periodic control work on a dedicated thread started with
+    /// `CoreEngine::spawn_realtime`; never throttled
+    RealTime,
+    /// Ordinary executions; asynchronous ones pause at yield points while
+    /// a real-time run is in progress
+    #[default]
+    High,
+    /// Work that can wait: asynchronous executions run on the background
+    /// pool and pause at yield points while any more urgent execution runs
+    Background,
+}
+
+impl ExecutionClass {
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// CPU time the executions of one class may consume together in each
+/// window
+///
+/// Once a class has used its budget, its asynchronous executions pause at
+/// their next yield point until the window ends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClassBudget {
+    pub cpu: Duration,
+    pub window: Duration,
+}
+
+impl ClassBudget {
+    /// Allow `cpu` of CPU time per `window`, which may exceed `window`
+    /// when the class runs on several threads
+    pub fn new(cpu: Duration, window: Duration) -> Result<Self, CoreError> {
+        if cpu.is_zero() || window.is_zero() {
+            return Err(CoreError::InvalidParameter(format!(
+                "CPU budget needs a positive time and window, got {:?} per {:?}",
+                cpu, window
+            )));
+        }
+        Ok(Self { cpu, window })
+    }
+}
+
+#[derive(Default)]
+struct Usage {
+    window_start: Option<Instant>,
+    used: Duration,
+}
+
+struct ArbiterState {
+    // Executions in progress, per class
+    running: [usize; 3],
+    budgets: [Option<ClassBudget>; 3],
+    usage: [Usage; 3],
+    starvation_threshold: Duration,
+}
+
+impl ArbiterState {
+    fn charge(&mut self, class: ExecutionClass, cpu: Duration, now: Instant) {
+        let Some(budget) = self.budgets[class.index()] else {
+            return;
+        };
+        let usage = &mut self.usage[class.index()];
+        if usage.window_start.is_none_or(|start| now.duration_since(start) >= budget.window) {
+            *usage = Usage {
+                window_start: Some(now),
+                used: Duration::ZERO,
+            };
+        }
+        usage.used = usage.used.saturating_add(cpu);
+    }
+    
+    /// When the current window ends, if `class` has used up its budget
+    fn exhausted_until(&self, class: ExecutionClass, now: Instant) -> Option<Instant> {
+        let budget = self.budgets[class.index()]?;
+        let usage = &self.usage[class.index()];
+        let end = usage.window_start? + budget.window;
+        (usage.used >= budget.cpu && now < end).then_some(end)
+    }
+    
+    fn more_urgent_running(&self, class: ExecutionClass) -> bool {
+        self.running[..class.index()].iter().any(|&n| n > 0)
+    }
+}
+
+/// Tracks which classes are executing and what each has consumed, so less
+/// urgent executions know when to pause
+pub(crate) struct Arbiter {
+    state: Mutex<ArbiterState>,
+    changed: Condvar,
+}
+
+impl Arbiter {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: Mutex::new(ArbiterState {
+                running: [0; 3],
+                budgets: [None; 3],
+                usage: Default::default(),
+                starvation_threshold: DEFAULT_STARVATION_THRESHOLD,
+            }),
+            changed: Condvar::new(),
+        }
+    }
+    
+    fn state(&self) -> MutexGuard<'_, ArbiterState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    /// Count an execution of `class` as running until the guard drops
+    pub(crate) fn enter(self: &Arc<Self>, class: ExecutionClass) -> Running {
+        self.state().running[class.index()] += 1;
+        Running {
+            arbiter: Arc::clone(self),
+            class,
+        }
+    }
+}
+
+/// An execution counted as running by its class
+pub(crate) struct Running {
+    arbiter: Arc<Arbiter>,
+    class: ExecutionClass,
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        let mut state = self.arbiter.state();
+        let running = &mut state.running[self.class.index()];
+        *running = running.saturating_sub(1);
+        drop(state);
+        self.arbiter.changed.notify_all();
+    }
+}
+
+/// CPU time of the calling thread, or the wall time when there is no
+/// thread CPU clock
+#[derive(Clone, Copy)]
+struct Reading {
+    cpu: Option<Duration>,
+    wall: Instant,
+}
+
+impl Reading {
+    fn now() -> Self {
+        Self {
+            cpu: thread_cpu_time(),
+            wall: Instant::now(),
+        }
+    }
+    
+    fn since(&self, earlier: &Reading) -> Duration {
+        match (self.cpu, earlier.cpu) {
+            (Some(now), Some(before)) => now.saturating_sub(before),
+            _ => self.wall.saturating_duration_since(earlier.wall),
+        }
+    }
+}
+
+/// The class an asynchronous execution runs in, and what its yield points
+/// report to
+pub(crate) struct Lane {
+    arbiter: Arc<Arbiter>,
+    class: ExecutionClass,
+    algorithm_id: String,
+    telemetry: Arc<Telemetry>,
+    // Taken at the previous yield point; time since is charged at the next
+    mark: Mutex<Option<Reading>>,
+}
+
+impl fmt::Debug for Lane {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Lane")
+            .field("class", &self.class)
+            .field("algorithm_id", &self.algorithm_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Lane {
+    pub(crate) fn class(&self) -> ExecutionClass {
+        self.class
+    }
+    
+    /// Count the execution as running, pausing first as at a yield point
+    pub(crate) fn start(&self, cancel: &CancellationToken) -> Running {
+        let running = self.arbiter.enter(self.class);
+        self.checkpoint(cancel);
+        running
+    }
+    
+    /// Charge the time since the previous yield point to the class
+    pub(crate) fn finish(&self) {
+        if let Some(mark) = self.lock_mark().take() {
+            let now = Reading::now();
+            self.arbiter.state().charge(self.class, now.since(&mark), now.wall);
+        }
+    }
+    
+    /// Charge the time since the previous yield point, then wait while a
+    /// more urgent execution runs or the class is over budget
+    ///
+    /// Real-time executions never wait. A wait ends early if `cancel` is
+    /// set, and one lasting the starvation threshold or longer is
+    /// recorded in telemetry.
+    pub(crate) fn checkpoint(&self, cancel: &CancellationToken) {
+        let started = Reading::now();
+        let mut state = self.arbiter.state();
+        if let Some(mark) = self.lock_mark().take() {
+            state.charge(self.class, started.since(&mark), started.wall);
+        }
+        if self.class != ExecutionClass::RealTime {
+            while !cancel.is_cancelled() {
+                let now = Instant::now();
+                let timeout = match state.exhausted_until(self.class, now) {
+                    Some(end) => end.saturating_duration_since(now).min(CANCEL_POLL),
+                    None if state.more_urgent_running(self.class) => CANCEL_POLL,
+                    None => break,
+                };
+                state = self
+                    .arbiter
+                    .changed
+                    .wait_timeout(state, timeout)
+                    .unwrap_or_else(|e| e.into_inner())
+                    .0;
+            }
+        }
+        let threshold = state.starvation_threshold;
+        drop(state);
+        let resumed = Reading::now();
+        let waited = resumed.wall.saturating_duration_since(started.wall);
+        if waited >= threshold && !waited.is_zero() {
+            self.telemetry.record_starvation(&self.algorithm_id, self.class, waited);
+        }
+        *self.lock_mark() = Some(resumed);
+    }
+    
+    fn lock_mark(&self) -> MutexGuard<'_, Option<Reading>> {
+        self.mark.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Per-algorithm classes, and the arbiter their executions go through
+pub(crate) struct Classes {
+    classes: HashMap<String, ExecutionClass>,
+    pub(crate) arbiter: Arc<Arbiter>,
+}
+
+impl Classes {
+    pub(crate) fn new() -> Self {
+        Self {
+            classes: HashMap::new(),
+            arbiter: Arc::new(Arbiter::new()),
+        }
+    }
+    
+    pub(crate) fn get(&self, algorithm_id: &str) -> ExecutionClass {
+        self.classes.get(algorithm_id).copied().unwrap_or_default()
+    }
+}
+
+impl CoreEngine {
+    /// Run `algorithm_id`'s executions as `class`
+    ///
+    /// Algorithms are `ExecutionClass::High` until told otherwise. The
+    /// class decides which pool asynchronous executions run on and when
+    /// they yield; a synchronous `execute` of a real-time or high
+    /// algorithm holds back background work at its yield points for as
+    /// long as it runs.
+    pub fn set_execution_class(&mut self, algorithm_id: &str, class: ExecutionClass) {
+        if class == ExecutionClass::default() {
+            self.classes.classes.remove(algorithm_id);
+        } else {
+            self.classes.classes.insert(algorithm_id.to_string(), class);
+        }
+    }
+    
+    pub fn execution_class(&self, algorithm_id: &str) -> ExecutionClass {
+        self.classes.get(algorithm_id)
+    }
+    
+    /// Limit the CPU time asynchronous executions of `class` consume, or
+    /// lift the limit with `None`
+    ///
+    /// Real-time work cannot be throttled, so giving it a budget fails with
+    /// `CoreError::InvalidParameter`.
+    pub fn set_class_budget(&mut self, class: ExecutionClass, budget: Option<ClassBudget>) -> Result<(), CoreError> {
+        if class == ExecutionClass::RealTime && budget.is_some() {
+            return Err(CoreError::InvalidParameter(
+                "real-time executions are never throttled, so take no CPU budget".to_string(),
+            ));
+        }
+        let mut state = self.classes.arbiter.state();
+        state.budgets[class.index()] = budget;
+        state.usage[class.index()] = Usage::default();
+        Ok(())
+    }
+    
+    /// Record a starvation event in telemetry whenever an execution waits
+    /// `threshold` or longer at one yield point
+    ///
+    /// Defaults to one second. Real-time threads record one whenever a run
+    /// starts a whole period late, whatever the threshold.
+    pub fn set_starvation_threshold(&mut self, threshold: Duration) {
+        self.classes.arbiter.state().starvation_threshold = threshold;
+    }
+    
+    /// The lane an asynchronous execution of `algorithm_id` runs in
+    pub(crate) fn lane(&self, algorithm_id: &str) -> Lane {
+        Lane {
+            arbiter: Arc::clone(&self.classes.arbiter),
+            class: self.classes.get(algorithm_id),
+            algorithm_id: algorithm_id.to_string(),
+            telemetry: Arc::clone(&self.telemetry),
+            mark: Mutex::new(None),
+        }
+    }
+    
+    /// Count a synchronous execution of `algorithm_id` as running, unless
+    /// it is background work that nothing waits on
+    pub(crate) fn enter_class(&self, algorithm_id: &str) -> Option<Running> {
+        let class = self.classes.get(algorithm_id);
+        (class != ExecutionClass::Background).then(|| self.classes.arbiter.enter(class))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod realtime {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{mpsc, Arc};
+    use std::thread::{self, JoinHandle};
+    use std::time::Duration;
+    
+    use super::ExecutionClass;
+    use crate::error::CoreError;
+    use crate::scheduler::{ScheduledRun, TaskStats};
+    use crate::CoreEngine;
+    
+    /// Where and at what priority a real-time thread runs
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct RealTimeOptions {
+        /// Core to pin the thread to, if any
+        pub cpu: Option<usize>,
+        /// `SCHED_FIFO` priority, from 1 to 99, if any
+        pub priority: Option<i32>,
+    }
+    
+    /// A dedicated thread running one algorithm periodically
+    ///
+    /// Stops when `stop` is called or the handle is dropped.
+    pub struct RealTimeThread {
+        stop: Arc<AtomicBool>,
+        thread: Option<JoinHandle<TaskStats>>,
+    }
+    
+    impl RealTimeThread {
+        /// Stop after the current period, returning the timing of every run
+        pub fn stop(mut self) -> TaskStats {
+            self.join()
+        }
+        
+        fn join(&mut self) -> TaskStats {
+            self.stop.store(true, Ordering::Relaxed);
+            self.thread
+                .take()
+                .and_then(|thread| thread.join().ok())
+                .unwrap_or_default()
+        }
+    }
+    
+    impl Drop for RealTimeThread {
+        fn drop(&mut self) {
+            self.join();
+        }
+    }
+    
+    /// Pin the calling thread to `cpu`
+    #[cfg(all(feature = "realtime", target_os = "linux"))]
+    fn pin_to_cpu(cpu: usize) -> Result<(), CoreError> {
+        if cpu >= libc::CPU_SETSIZE as usize {
+            return Err(CoreError::InvalidParameter(format!("no CPU {}", cpu)));
+        }
+        // SAFETY: an all-zero `cpu_set_t` is a valid, empty set.
+        let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        // SAFETY: `cpu` was checked to be within the set.
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+        // SAFETY: `set` is a valid `cpu_set_t` of the given size for the
+        // whole call, and pid 0 names the calling thread.
+        if unsafe { libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+    
+    #[cfg(not(all(feature = "realtime", target_os = "linux")))]
+    fn pin_to_cpu(cpu: usize) -> Result<(), CoreError> {
+        Err(CoreError::UnsupportedCapability(format!(
+            "pinning to CPU {} needs the `realtime` feature on Linux",
+            cpu
+        )))
+    }
+    
+    #[cfg(all(feature = "realtime", target_os = "linux"))]
+    fn raise_priority(priority: i32) -> Result<(), CoreError> {
+        crate::scheduler::set_realtime_priority(priority)
+    }
+    
+    #[cfg(not(all(feature = "realtime", target_os = "linux")))]
+    fn raise_priority(priority: i32) -> Result<(), CoreError> {
+        Err(CoreError::UnsupportedCapability(format!(
+            "SCHED_FIFO priority {} needs the `realtime` feature on Linux",
+            priority
+        )))
+    }
+    
+    fn configure(options: RealTimeOptions) -> Result<(), CoreError> {
+        if let Some(cpu) = options.cpu {
+            pin_to_cpu(cpu)?;
+        }
+        if let Some(priority) = options.priority {
+            raise_priority(priority)?;
+        }
+        Ok(())
+    }
+    
+    impl CoreEngine {
+        /// Run `algorithm_id` every `period` on a dedicated thread, handing
+        /// each run to `on_run`
+        ///
+        /// The thread is pinned and raised to `SCHED_FIFO` as `options`
+        /// ask, failing with the error of whichever step fails before any
+        /// run. Runs go through an `executor` handle with an empty input,
+        /// are timed by the engine's clock, and show up in `status` like a
+        /// `Scheduler`'s. While one runs, background and high executions
+        /// pause at their yield points. A run starting a whole period late
+        /// is recorded as starvation; runs missed that way are skipped.
+        pub fn spawn_realtime(
+            &self,
+            algorithm_id: &str,
+            period: Duration,
+            options: RealTimeOptions,
+            mut on_run: impl FnMut(ScheduledRun) + Send + 'static,
+        ) -> Result<RealTimeThread, CoreError> {
+            if self.get_algorithm(algorithm_id).is_none() {
+                return Err(CoreError::AlgorithmNotFound(algorithm_id.to_string()));
+            }
+            if period.is_zero() {
+                return Err(CoreError::InvalidParameter("real-time period must be positive".to_string()));
+            }
+            let executor = self.executor();
+            let clock = Arc::clone(&self.clock);
+            let telemetry = Arc::clone(&self.telemetry);
+            let arbiter = Arc::clone(&self.classes.arbiter);
+            let stop = Arc::new(AtomicBool::new(false));
+            let stopped = Arc::clone(&stop);
+            let id = algorithm_id.to_string();
+            let (ready, configured) = mpsc::channel();
+            let thread = thread::Builder::new()
+                .name(format!("robotics-core-rt-{}", id))
+                .spawn(move || {
+                    let mut stats = TaskStats::default();
+                    let setup = configure(options);
+                    let failed = setup.is_err();
+                    let _ = ready.send(setup);
+                    if failed {
+                        return stats;
+                    }
+                    let mut due = clock.now();
+                    while !stopped.load(Ordering::Relaxed) {
+                        let now = clock.now();
+                        if now < due {
+                            clock.sleep(due - now);
+                            continue;
+                        }
+                        let jitter = now - due;
+                        if jitter >= period {
+                            telemetry.record_starvation(&id, ExecutionClass::RealTime, jitter);
+                        }
+                        let result = {
+                            let _running = arbiter.enter(ExecutionClass::RealTime);
+                            executor.execute(&id, &[]).map(|output| output.data)
+                        };
+                        let duration = clock.now().saturating_sub(now);
+                        let run = ScheduledRun {
+                            algorithm_id: id.clone(),
+                            result,
+                            jitter,
+                            duration,
+                            deadline_missed: jitter + duration > period,
+                        };
+                        stats.record(&run);
+                        telemetry.record_scheduled_run(&run);
+                        on_run(run);
+                        let behind = clock.now().saturating_sub(due).as_nanos() / period.as_nanos();
+                        due += period * u32::try_from(behind).unwrap_or(u32::MAX).max(1);
+                    }
+                    stats
+                })?;
+            let mut handle = RealTimeThread {
+                stop,
+                thread: Some(thread),
+            };
+            match configured.recv() {
+                Ok(Ok(())) => Ok(handle),
+                Ok(Err(e)) => {
+                    handle.join();
+                    Err(e)
+                }
+                Err(_) => {
+                    handle.join();
+                    Err(CoreError::ProcessingFailed(format!("real-time thread for '{}' exited", algorithm_id)))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::context::Context;
+    use crate::algorithm::{Algorithm, AlgorithmMetadata};
+    use crate::memory::MemoryManager;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    
+    /// Spins for `steps` milliseconds, yielding between them and counting
+    /// the steps done
+    struct Planner {
+        steps: usize,
+        done: Arc<AtomicUsize>,
+    }
+    
+    impl Algorithm for Planner {
+        fn process(&self, input: &[u8], memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            let mut output = Vec::new();
+            Context::with_default(|context| self.process_in_context(input, &mut output, memory, context))?;
+            Ok(output)
+        }
+        
+        fn process_in_context(
+            &self,
+            _input: &[u8],
+            output: &mut Vec<u8>,
+            _memory: &mut MemoryManager,
+            context: &mut Context<'_>,
+        ) -> Result<(), CoreError> {
+            for _ in 0..self.steps {
+                context.yield_now();
+                if context.is_cancelled() {
+                    return Err(CoreError::Cancelled);
+                }
+                let start = Instant::now();
+                while start.elapsed() < Duration::from_millis(1) {
+                    std::hint::spin_loop();
+                }
+                self.done.fetch_add(1, Ordering::SeqCst);
+            }
+            output.push(1);
+            Ok(())
+        }
+        
+        fn id(&self) -> &str {
+            "planner"
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata::default()
+        }
+    }
+    
+    fn planner(engine: &mut CoreEngine, steps: usize) -> Arc<AtomicUsize> {
+        let done = Arc::new(AtomicUsize::new(0));
+        engine
+            .register(Box::new(Planner {
+                steps,
+                done: Arc::clone(&done),
+            }))
+            .unwrap();
+        engine.set_execution_class("planner", ExecutionClass::Background);
+        done
+    }
+    
+    #[test]
+    fn test_background_pauses_while_urgent_work_runs() {
+        let mut engine = CoreEngine::new();
+        let done = planner(&mut engine, 20);
+        engine.set_starvation_threshold(Duration::from_millis(30));
+        assert_eq!(engine.execution_class("planner"), ExecutionClass::Background);
+        assert_eq!(engine.execution_class("passthrough"), ExecutionClass::High);
+        
+        // Hold a high-priority execution open while the planner starts
+        let running = engine.classes.arbiter.enter(ExecutionClass::High);
+        let execution = engine.execute_algorithm_async("planner", &[]).unwrap();
+        thread::sleep(Duration::from_millis(60));
+        assert_eq!(done.load(Ordering::SeqCst), 0, "background work waits for urgent work");
+        drop(running);
+        assert_eq!(execution.wait().unwrap(), vec![1]);
+        assert_eq!(done.load(Ordering::SeqCst), 20);
+        
+        let starvation = &engine.metrics().starvation["planner"];
+        assert_eq!((starvation.class, starvation.events), (ExecutionClass::Background, 1));
+        assert!(starvation.max_delay >= Duration::from_millis(30));
+    }
+    
+    #[test]
+    fn test_cpu_budget_throttles_background_class() {
+        let mut engine = CoreEngine::new();
+        planner(&mut engine, 30);
+        assert!(ClassBudget::new(Duration::ZERO, Duration::from_millis(10)).is_err());
+        // 5 ms of every 20 ms: 30 ms of work spans at least five windows
+        let budget = ClassBudget::new(Duration::from_millis(5), Duration::from_millis(20)).unwrap();
+        assert!(engine.set_class_budget(ExecutionClass::RealTime, Some(budget)).is_err());
+        engine.set_class_budget(ExecutionClass::Background, Some(budget)).unwrap();
+        let start = Instant::now();
+        engine.execute_algorithm_async("planner", &[]).unwrap().wait().unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(80), "{:?}", start.elapsed());
+    }
+    
+    #[test]
+    fn test_realtime_thread_runs_periodically() {
+        let engine = CoreEngine::new();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&runs);
+        let rt = engine
+            .spawn_realtime("passthrough", Duration::from_millis(2), RealTimeOptions::default(), move |run| {
+                assert!(run.result.is_ok());
+                counted.fetch_add(1, Ordering::SeqCst);
+            })
+            .unwrap();
+        thread::sleep(Duration::from_millis(30));
+        let stats = rt.stop();
+        assert!(stats.runs >= 3, "{:?}", stats);
+        assert_eq!(stats.runs, runs.load(Ordering::SeqCst) as u64);
+        assert_eq!(engine.status().scheduler["passthrough"].runs, stats.runs);
+        
+        let pinned = RealTimeOptions {
+            cpu: Some(usize::MAX),
+            ..Default::default()
+        };
+        assert!(engine.spawn_realtime("passthrough", Duration::from_millis(2), pinned, |_| {}).is_err());
+        let missing = engine.spawn_realtime("missing", Duration::from_millis(2), RealTimeOptions::default(), |_| {});
+        assert!(matches!(missing, Err(CoreError::AlgorithmNotFound(_))));
+    }
+}
@@ -0,0 +1,289 @@
+//! Python bindings for the engine, built with the `python-binding` feature
+
+use numpy::{IntoPyArray, PyArray1, PyReadonlyArray1};
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::algorithm::{samples, AlgorithmMetadata};
+use crate::error::CoreError;
+use crate::sensor::{MockSensor, Sensor, SensorFrame};
+use crate::CoreEngine;
+
+fn to_py_err(error: CoreError) -> PyErr {
+    PyRuntimeError::new_err(error.to_string())
+}
+
+/// Description of a registered algorithm
+#[pyclass(name = "AlgorithmMetadata")]
+struct PyAlgorithmMetadata {
+    #[pyo3(get)]
+    id: String,
+    #[pyo3(get)]
+    name: String,
+    #[pyo3(get)]
+    version: String,
+    #[pyo3(get)]
+    description: String,
+    /// `(name, type, default)` of each parameter, the default `None` if
+    /// the parameter is required
+    #[pyo3(get)]
+    parameters: Vec<(String, String, Option<String>)>,
+    #[pyo3(get)]
+    min_input_bytes: usize,
+}
+
+impl PyAlgorithmMetadata {
+    fn new(id: &str, metadata: AlgorithmMetadata) -> Self {
+        Self {
+            id: id.to_string(),
+            name: metadata.name,
+            version: metadata.version,
+            description: metadata.description,
+            parameters: metadata
+                .parameters
+                .into_iter()
+                .map(|parameter| {
+                    let kind = parameter.parameter_type.json_schema_type().to_string();
+                    (parameter.name, kind, parameter.default_value)
+                })
+                .collect(),
+            min_input_bytes: metadata.min_input_bytes,
+        }
+    }
+}
+
+#[pymethods]
+impl PyAlgorithmMetadata {
+    fn __repr__(&self) -> String {
+        format!("AlgorithmMetadata(id={:?}, name={:?}, version={:?})", self.id, self.name, self.version)
+    }
+}
+
+/// One frame read from a `SensorStream`
+#[pyclass(name = "SensorReading")]
+struct PySensorReading {
+    frame: SensorFrame,
+}
+
+#[pymethods]
+impl PySensorReading {
+    #[getter]
+    fn sensor_id(&self) -> &str {
+        &self.frame.sensor_id
+    }
+    
+    /// Capture time in microseconds
+    #[getter]
+    fn timestamp(&self) -> u64 {
+        self.frame.timestamp
+    }
+    
+    /// Raw frame bytes
+    #[getter]
+    fn payload<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, &self.frame.payload)
+    }
+    
+    /// The payload decoded as a float32 array, in the frame's byte order
+    fn samples<'py>(&self, py: Python<'py>) -> &'py PyArray1<f32> {
+        self.frame.payload_as_f32().into_pyarray(py)
+    }
+}
+
+/// Iterator over a sensor's frames, yielding `SensorReading`s
+///
+/// Stops after `limit` frames if one was given, or when the sensor reports
+/// the end of its stream.
+#[pyclass(name = "SensorStream")]
+struct PySensorStream {
+    sensor: Box<dyn Sensor>,
+    remaining: Option<usize>,
+}
+
+#[pymethods]
+impl PySensorStream {
+    /// Stream from a sensor replaying `samples` at `rate_hz`
+    #[staticmethod]
+    #[pyo3(signature = (sensor_id, rate_hz, samples, limit = None))]
+    fn mock(sensor_id: &str, rate_hz: f64, samples: Vec<f32>, limit: Option<usize>) -> PyResult<Self> {
+        Ok(Self {
+            sensor: Box::new(MockSensor::new(sensor_id, rate_hz, samples).map_err(to_py_err)?),
+            remaining: limit,
+        })
+    }
+    
+    #[getter]
+    fn sensor_id(&self) -> &str {
+        self.sensor.id()
+    }
+    
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+    
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<PySensorReading>> {
+        if self.remaining == Some(0) {
+            return Ok(None);
+        }
+        // Release the GIL while a real sensor blocks for its next frame
+        let sensor = &mut self.sensor;
+        match py.allow_threads(|| sensor.read_frame()) {
+            Ok(frame) => {
+                if let Some(remaining) = self.remaining.as_mut() {
+                    *remaining -= 1;
+                }
+                Ok(Some(PySensorReading { frame }))
+            }
+            Err(CoreError::EndOfStream) => Ok(None),
+            Err(e) => Err(to_py_err(e)),
+        }
+    }
+}
+
+/// `CoreEngine` for Python, usable as a context manager
+///
+/// Leaving a `with` block shuts the engine down, also when the block
+/// raised, and the exception then propagates as usual. A shut-down engine
+/// refuses further work but still answers `memory_bytes` and `closed`.
+#[pyclass(name = "CoreEngine")]
+struct PyCoreEngine {
+    engine: CoreEngine,
+    closed: bool,
+}
+
+impl PyCoreEngine {
+    fn open_engine(&mut self) -> PyResult<&mut CoreEngine> {
+        if self.closed {
+            return Err(PyRuntimeError::new_err("engine has been shut down"));
+        }
+        Ok(&mut self.engine)
+    }
+}
+
+#[pymethods]
+impl PyCoreEngine {
+    #[new]
+    fn new() -> Self {
+        Self {
+            engine: CoreEngine::new(),
+            closed: false,
+        }
+    }
+    
+    /// Run an algorithm on `input`, returning its output bytes
+    fn execute<'py>(&mut self, py: Python<'py>, algorithm_id: &str, input: &[u8]) -> PyResult<&'py PyBytes> {
+        let output = self
+            .open_engine()?
+            .execute_algorithm(algorithm_id, input)
+            .map_err(to_py_err)?;
+        Ok(PyBytes::new(py, &output))
+    }
+    
+    /// Run an algorithm on a float32 array, returning a float32 array
+    ///
+    /// The input is read in place when it is contiguous and the host is
+    /// little-endian, and the output buffer becomes the returned array's
+    /// storage, so neither side is copied.
+    fn execute_array<'py>(
+        &mut self,
+        py: Python<'py>,
+        algorithm_id: &str,
+        input: PyReadonlyArray1<'py, f32>,
+    ) -> PyResult<&'py PyAny> {
+        let output = match input.as_slice() {
+            Ok(values) if cfg!(target_endian = "little") => {
+                // SAFETY: `values` is a live, initialized `f32` slice, any
+                // byte of which is a valid `u8`, and the view covers exactly
+                // its bytes; the array stays borrowed for the whole call.
+                let bytes =
+                    unsafe { std::slice::from_raw_parts(values.as_ptr().cast::<u8>(), std::mem::size_of_val(values)) };
+                self.open_engine()?.execute_algorithm(algorithm_id, bytes)
+            }
+            _ => {
+                let bytes = samples::f32_to_bytes(&input.as_array().iter().copied().collect::<Vec<f32>>());
+                self.open_engine()?.execute_algorithm(algorithm_id, &bytes)
+            }
+        }
+        .map_err(to_py_err)?;
+        // Reinterpreting the bytes as little-endian float32 is a view, not
+        // a copy
+        output.into_pyarray(py).call_method1("view", ("<f4",))
+    }
+    
+    /// IDs of the registered algorithms
+    fn algorithms(&self) -> Vec<String> {
+        self.engine.list_algorithms().into_iter().map(|(id, _)| id).collect()
+    }
+    
+    /// Metadata of a registered algorithm
+    fn metadata(&self, algorithm_id: &str) -> PyResult<PyAlgorithmMetadata> {
+        let algorithm = self
+            .engine
+            .get_algorithm(algorithm_id)
+            .ok_or_else(|| to_py_err(CoreError::AlgorithmNotFound(algorithm_id.to_string())))?;
+        Ok(PyAlgorithmMetadata::new(algorithm_id, algorithm.metadata()))
+    }
+    
+    /// Register an algorithm described by a JSON definition, returning its
+    /// ID
+    fn register_definition(&mut self, definition: &str) -> PyResult<String> {
+        let algorithm = crate::algorithm::create_algorithm_from_json(definition).map_err(to_py_err)?;
+        let id = algorithm.id().to_string();
+        self.open_engine()?.register(algorithm).map_err(to_py_err)?;
+        Ok(id)
+    }
+    
+    /// Allocate a zeroed memory region of `size` bytes
+    fn allocate(&mut self, key: &str, size: usize) -> PyResult<()> {
+        self.open_engine()?
+            .memory_mut()
+            .allocate(key, size)
+            .map_err(to_py_err)?;
+        Ok(())
+    }
+    
+    /// Bytes held in shared memory regions
+    fn memory_bytes(&self) -> usize {
+        self.engine.memory().stats().total_bytes
+    }
+    
+    /// Whether `shutdown` has run
+    #[getter]
+    fn closed(&self) -> bool {
+        self.closed
+    }
+    
+    /// Free all memory and devices; later calls do nothing
+    fn shutdown(&mut self) {
+        if !self.closed {
+            self.engine.shutdown();
+            self.closed = true;
+        }
+    }
+    
+    fn __enter__(mut slf: PyRefMut<'_, Self>) -> PyResult<PyRefMut<'_, Self>> {
+        slf.open_engine()?;
+        Ok(slf)
+    }
+    
+    fn __exit__(
+        &mut self,
+        _exc_type: Option<&PyAny>,
+        _exc_value: Option<&PyAny>,
+        _traceback: Option<&PyAny>,
+    ) -> bool {
+        self.shutdown();
+        // Never swallow the block's exception
+        false
+    }
+}
+
+#[pymodule]
+fn robotics_core(_py: Python<'_>, module: &PyModule) -> PyResult<()> {
+    module.add_class::<PyCoreEngine>()?;
+    module.add_class::<PyAlgorithmMetadata>()?;
+    module.add_class::<PySensorReading>()?;
+    module.add_class::<PySensorStream>()?;
+    Ok(())
+}
@@ -0,0 +1,359 @@
+//! On-disk logs of algorithm executions and sensor data for offline debugging
+//!
+//! A recording is a log file of length-prefixed records plus a sidecar index
+//! (the log path with `.idx` appended) holding each record's byte offset as
+//! a little-endian `u64`. Each record also carries its sequence number, so a
+//! reader can tell when the index no longer matches the log.
+//!
+//! To reproduce field runs offline, a session interleaves sensor frames
+//! with executions in a chunked, compressed file: `SessionRecorder` writes
+//! one and `ReplaySource` plays it back into a `SensorManager` or engine at
+//! the original or a scaled speed.
+
+use std::fs::{self, File};
+use std::io::{BufWriter, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::compression::{self, Compressor};
+use crate::error::CoreError;
+use crate::wire;
+use crate::CoreEngine;
+
+mod replay;
+mod session;
+
+pub use replay::{Divergence, ReplayReport, ReplaySource, ReplayedSensor};
+pub use session::{RecordedSensor, SessionEntry, SessionEvent, SessionReader, SessionRecorder, SESSION_MAGIC};
+
+/// Bytes of the record header: body length (`u32`) and sequence (`u64`)
+const HEADER_BYTES: u64 = 12;
+
+/// One recorded execution
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Record {
+    /// ID of the algorithm that ran
+    pub algorithm_id: String,
+    /// Engine clock reading when the execution started, in microseconds
+    pub timestamp: u64,
+    /// Bytes the algorithm was given
+    pub input: Vec<u8>,
+    /// Bytes the algorithm returned
+    pub output: Vec<u8>,
+}
+
+impl Record {
+    fn encode(&self) -> Result<Vec<u8>, CoreError> {
+        let mut body = Vec::with_capacity(18 + self.algorithm_id.len() + self.input.len() + self.output.len());
+        wire::put_short_str(&mut body, &self.algorithm_id)?;
+        body.extend_from_slice(&self.timestamp.to_le_bytes());
+        wire::put_bytes(&mut body, &self.input)?;
+        wire::put_bytes(&mut body, &self.output)?;
+        Ok(body)
+    }
+    
+    fn decode(body: &[u8]) -> Result<Self, CoreError> {
+        let mut reader = wire::Reader::new(body, "record", CoreError::IoError);
+        let record = Self {
+            algorithm_id: reader.short_str()?,
+            timestamp: reader.u64()?,
+            input: reader.bytes()?.to_vec(),
+            output: reader.bytes()?.to_vec(),
+        };
+        reader.finish()?;
+        Ok(record)
+    }
+}
+
+/// Path of the index belonging to the log at `path`
+pub fn index_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".idx");
+    PathBuf::from(name)
+}
+
+/// Compress the recording at `log` into a single archive file
+///
+/// The index is left out; readers rebuild it from the log after `unarchive`.
+pub fn archive(log: impl AsRef<Path>, archive: impl AsRef<Path>, compressor: &dyn Compressor) -> Result<(), CoreError> {
+    let data = fs::read(log)?;
+    fs::write(archive, compression::pack(&data, compressor)?).map_err(CoreError::from)
+}
+
+/// Restore the recording in `archive` to `log`, detecting its codec
+///
+/// Any index already at `log` is removed, as it would describe another log.
+pub fn unarchive(archive: impl AsRef<Path>, log: impl AsRef<Path>) -> Result<(), CoreError> {
+    let log = log.as_ref();
+    let data = compression::unpack(&fs::read(archive)?)?;
+    fs::write(log, data)?;
+    match fs::remove_file(index_path(log)) {
+        Err(err) if err.kind() != ErrorKind::NotFound => Err(CoreError::from(err)),
+        _ => Ok(()),
+    }
+}
+
+/// Appends executions to a recording
+pub struct Recorder {
+    log: BufWriter<File>,
+    index: BufWriter<File>,
+    offset: u64,
+    count: u64,
+}
+
+impl Recorder {
+    /// Start a new recording at `path`, replacing any existing one
+    pub fn create(path: impl AsRef<Path>) -> Result<Self, CoreError> {
+        let path = path.as_ref();
+        Ok(Self {
+            log: BufWriter::new(File::create(path)?),
+            index: BufWriter::new(File::create(index_path(path))?),
+            offset: 0,
+            count: 0,
+        })
+    }
+    
+    /// Append a record
+    pub fn record(&mut self, record: &Record) -> Result<(), CoreError> {
+        let body = record.encode()?;
+        let body_len = u32::try_from(body.len())
+            .map_err(|_| CoreError::InvalidInput("record too long".to_string()))?;
+        self.log.write_all(&body_len.to_le_bytes())?;
+        self.log.write_all(&self.count.to_le_bytes())?;
+        self.log.write_all(&body)?;
+        self.index.write_all(&self.offset.to_le_bytes())?;
+        self.offset += HEADER_BYTES + body.len() as u64;
+        self.count += 1;
+        Ok(())
+    }
+    
+    /// Number of records written so far
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+    
+    /// Whether nothing has been recorded yet
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+    
+    /// Write buffered records and index entries through to disk
+    pub fn flush(&mut self) -> Result<(), CoreError> {
+        self.log.flush()?;
+        self.index.flush().map_err(CoreError::from)
+    }
+}
+
+/// Random access to the records of a recording
+///
+/// Seeking uses the index when it is present and agrees with the log. A
+/// missing, truncated or stale index is not an error: the reader falls back
+/// to scanning record headers from the start of the log and keeps the
+/// offsets it rebuilds for later seeks.
+pub struct RecordingReader {
+    log: File,
+    log_len: u64,
+    offsets: Vec<u64>,
+    // Whether `offsets` came from a full scan and so covers the whole log
+    scanned: bool,
+}
+
+impl RecordingReader {
+    /// Open the recording at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CoreError> {
+        let path = path.as_ref();
+        let log = File::open(path)?;
+        let log_len = log.metadata()?.len();
+        let offsets = match fs::read(index_path(path)) {
+            Ok(index) => index
+                .chunks_exact(8)
+                .filter_map(|entry| entry.try_into().ok().map(u64::from_le_bytes))
+                .collect(),
+            Err(err) if err.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(CoreError::from(err)),
+        };
+        Ok(Self {
+            log,
+            log_len,
+            offsets,
+            scanned: false,
+        })
+    }
+    
+    /// Read the record of the `n`th execution, counting from zero
+    pub fn seek_to(&mut self, n: usize) -> Result<Record, CoreError> {
+        if let Some(&offset) = self.offsets.get(n) {
+            if let Ok(Some(record)) = self.read_at(offset, n as u64) {
+                return Ok(record);
+            }
+        }
+        if !self.scanned {
+            self.scan()?;
+            if let Some(&offset) = self.offsets.get(n) {
+                if let Some(record) = self.read_at(offset, n as u64)? {
+                    return Ok(record);
+                }
+            }
+        }
+        Err(CoreError::InvalidInput(format!(
+            "recording holds {} records, record {} requested",
+            self.offsets.len(),
+            n
+        )))
+    }
+    
+    /// Read the record at `offset`, or `None` if it is not record `sequence`
+    fn read_at(&mut self, offset: u64, sequence: u64) -> Result<Option<Record>, CoreError> {
+        let Some((body_len, found)) = self.header_at(offset)? else {
+            return Ok(None);
+        };
+        if found != sequence {
+            return Ok(None);
+        }
+        let mut body = vec![0u8; body_len as usize];
+        self.log.read_exact(&mut body)?;
+        Record::decode(&body).map(Some)
+    }
+    
+    /// Body length and sequence of the record at `offset`, if one fits
+    fn header_at(&mut self, offset: u64) -> Result<Option<(u64, u64)>, CoreError> {
+        if offset + HEADER_BYTES > self.log_len {
+            return Ok(None);
+        }
+        let mut header = [0u8; HEADER_BYTES as usize];
+        self.log.seek(SeekFrom::Start(offset))?;
+        self.log.read_exact(&mut header)?;
+        let [l0, l1, l2, l3, sequence @ ..] = header;
+        let body_len = u32::from_le_bytes([l0, l1, l2, l3]) as u64;
+        let sequence = u64::from_le_bytes(sequence);
+        if offset + HEADER_BYTES + body_len > self.log_len {
+            return Ok(None);
+        }
+        Ok(Some((body_len, sequence)))
+    }
+    
+    /// Rebuild the offsets by walking record headers through the whole log
+    fn scan(&mut self) -> Result<(), CoreError> {
+        let mut offsets = Vec::new();
+        let mut offset = 0;
+        while let Some((body_len, sequence)) = self.header_at(offset)? {
+            if sequence != offsets.len() as u64 {
+                return Err(CoreError::IoError(format!(
+                    "record at offset {} has sequence {}, expected {}",
+                    offset,
+                    sequence,
+                    offsets.len()
+                )));
+            }
+            offsets.push(offset);
+            offset += HEADER_BYTES + body_len;
+        }
+        self.offsets = offsets;
+        self.scanned = true;
+        Ok(())
+    }
+}
+
+impl CoreEngine {
+    /// Execute an algorithm and append the execution to `recorder`
+    ///
+    /// Only successful executions are recorded.
+    pub fn execute_recorded(
+        &mut self,
+        algorithm_id: &str,
+        input_data: &[u8],
+        recorder: &mut Recorder,
+    ) -> Result<Vec<u8>, CoreError> {
+        let timestamp = self.clock.now().as_micros() as u64;
+        let output = self.execute_algorithm(algorithm_id, input_data)?;
+        recorder.record(&Record {
+            algorithm_id: algorithm_id.to_string(),
+            timestamp,
+            input: input_data.to_vec(),
+            output: output.clone(),
+        })?;
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::builtins::PassThrough;
+    use std::fs::OpenOptions;
+    
+    fn temp_log(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("robotics_core_{}_{}.log", name, std::process::id()))
+    }
+    
+    fn record_hundred(path: &Path) {
+        let mut engine = CoreEngine::new();
+        let mut recorder = Recorder::create(path).unwrap();
+        for i in 0..100u32 {
+            let input = vec![i as u8; (i % 7) as usize];
+            engine.execute_recorded(PassThrough::ID, &input, &mut recorder).unwrap();
+        }
+        assert_eq!(recorder.len(), 100);
+        recorder.flush().unwrap();
+    }
+    
+    fn check(reader: &mut RecordingReader, n: usize) {
+        let record = reader.seek_to(n).unwrap();
+        assert_eq!(record.algorithm_id, PassThrough::ID);
+        assert_eq!(record.input, vec![n as u8; n % 7]);
+        assert_eq!(record.output, record.input);
+    }
+    
+    #[test]
+    fn test_seek_by_index() {
+        let path = temp_log("seek");
+        record_hundred(&path);
+        
+        let mut reader = RecordingReader::open(&path).unwrap();
+        for n in [57, 0, 99, 13] {
+            check(&mut reader, n);
+        }
+        assert!(reader.seek_to(100).is_err());
+        fs::remove_file(index_path(&path)).unwrap();
+        fs::remove_file(&path).unwrap();
+    }
+    
+    #[test]
+    fn test_missing_or_stale_index_falls_back_to_scan() {
+        let path = temp_log("stale");
+        record_hundred(&path);
+        
+        // Stale: every offset shifted so none lands on its record
+        let shifted: Vec<u8> = fs::read(index_path(&path))
+            .unwrap()
+            .chunks_exact(8)
+            .flat_map(|entry| (u64::from_le_bytes(entry.try_into().unwrap()) + 1).to_le_bytes())
+            .collect();
+        fs::write(index_path(&path), shifted).unwrap();
+        let mut reader = RecordingReader::open(&path).unwrap();
+        check(&mut reader, 42);
+        check(&mut reader, 99);
+        
+        // Missing, with a torn record left at the end of the log
+        fs::remove_file(index_path(&path)).unwrap();
+        OpenOptions::new().append(true).open(&path).unwrap().write_all(&[9, 0, 0]).unwrap();
+        let mut reader = RecordingReader::open(&path).unwrap();
+        check(&mut reader, 99);
+        assert!(reader.seek_to(100).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+    
+    #[test]
+    fn test_archive_round_trip_rebuilds_index() {
+        let path = temp_log("archive");
+        let archived = path.with_extension("rcpk");
+        record_hundred(&path);
+        archive(&path, &archived, &compression::Uncompressed).unwrap();
+        
+        unarchive(&archived, &path).unwrap();
+        assert!(!index_path(&path).exists());
+        let mut reader = RecordingReader::open(&path).unwrap();
+        check(&mut reader, 77);
+        fs::remove_file(&archived).unwrap();
+        fs::remove_file(&path).unwrap();
+    }
+}
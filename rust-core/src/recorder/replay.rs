@@ -0,0 +1,311 @@
+//! Playing recorded sessions back into sensors and the engine
+
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::session::{SessionEntry, SessionEvent, SessionReader};
+use crate::clock::{Clock, SystemClock};
+use crate::error::CoreError;
+use crate::sensor::{Sensor, SensorFrame, SensorManager};
+use crate::CoreEngine;
+
+/// An execution whose replayed output differs from the recorded one
+#[derive(Clone, Debug, PartialEq)]
+pub struct Divergence {
+    /// Session time of the execution, in microseconds
+    pub time: u64,
+    pub algorithm_id: String,
+    /// Output recorded in the session
+    pub recorded: Vec<u8>,
+    /// What the execution returned on replay
+    pub replayed: Result<Vec<u8>, CoreError>,
+}
+
+/// Outcome of `ReplaySource::replay`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReplayReport {
+    /// Frames written to memory
+    pub frames: u64,
+    /// Executions rerun
+    pub executions: u64,
+    /// Executions that did not reproduce their recorded output
+    pub divergences: Vec<Divergence>,
+}
+
+impl ReplayReport {
+    /// Whether every execution reproduced its recorded output
+    pub fn is_deterministic(&self) -> bool {
+        self.divergences.is_empty()
+    }
+}
+
+/// Delivers the entries of a session file with their recorded timing
+///
+/// The first entry is delivered at once; each later one is delivered when
+/// its session time, divided by the speed, has passed on the replay clock.
+/// On a `MockClock` waiting advances the clock, so replays run instantly
+/// and identically every time.
+pub struct ReplaySource {
+    reader: SessionReader,
+    clock: Arc<dyn Clock>,
+    speed: f64,
+    // Clock reading and session time of the first entry delivered
+    origin: Option<(Duration, u64)>,
+}
+
+impl ReplaySource {
+    /// Replay the session at `path` at its original speed on the system clock
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CoreError> {
+        Ok(Self {
+            reader: SessionReader::open(path)?,
+            clock: Arc::new(SystemClock::new()),
+            speed: 1.0,
+            origin: None,
+        })
+    }
+    
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+    
+    /// Play `speed` times faster than recorded, or without waiting at all if
+    /// `f64::INFINITY`
+    pub fn with_speed(mut self, speed: f64) -> Result<Self, CoreError> {
+        if speed.is_nan() || speed <= 0.0 {
+            return Err(CoreError::InvalidParameter(format!(
+                "replay speed must be positive, got {}",
+                speed
+            )));
+        }
+        self.speed = speed;
+        Ok(self)
+    }
+    
+    /// Wait until the next entry is due and return it, or `None` at the end
+    /// of the session
+    pub fn next_entry(&mut self) -> Result<Option<SessionEntry>, CoreError> {
+        let Some(entry) = self.reader.next_entry()? else {
+            return Ok(None);
+        };
+        let (started, first) = *self.origin.get_or_insert((self.clock.now(), entry.time));
+        let offset = entry.time.saturating_sub(first) as f64 / 1e6 / self.speed;
+        let due = started.saturating_add(Duration::try_from_secs_f64(offset).unwrap_or(Duration::MAX));
+        let now = self.clock.now();
+        if due > now {
+            self.clock.sleep(due - now);
+        }
+        Ok(Some(entry))
+    }
+    
+    /// Replay the rest of the session into `engine`
+    ///
+    /// Each frame replaces the memory region `SensorManager` would publish
+    /// it to, and each execution is rerun on its recorded input and checked
+    /// against its recorded output. Reruns that fail or differ are reported
+    /// rather than stopping the replay.
+    pub fn replay(&mut self, engine: &mut CoreEngine) -> Result<ReplayReport, CoreError> {
+        let mut report = ReplayReport::default();
+        while let Some(entry) = self.next_entry()? {
+            match entry.event {
+                SessionEvent::Frame(frame) => {
+                    let key = SensorManager::region_key(&frame.sensor_id);
+                    engine.memory_mut().replace_region(&key, frame.payload)?;
+                    report.frames += 1;
+                }
+                SessionEvent::Execution(record) => {
+                    let replayed = engine.execute_algorithm(&record.algorithm_id, &record.input);
+                    report.executions += 1;
+                    if !matches!(&replayed, Ok(output) if *output == record.output) {
+                        report.divergences.push(Divergence {
+                            time: entry.time,
+                            algorithm_id: record.algorithm_id,
+                            recorded: record.output,
+                            replayed,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(report)
+    }
+    
+    /// Split the frames in the rest of the session into one sensor per
+    /// recorded sensor ID, in order of first appearance
+    ///
+    /// The sensors are meant to be added to a `SensorManager` in place of
+    /// the originals. Executions are skipped.
+    pub fn into_sensors(mut self) -> Result<Vec<ReplayedSensor>, CoreError> {
+        let mut sensors: Vec<ReplayedSensor> = Vec::new();
+        let mut spans: Vec<(u64, u64)> = Vec::new();
+        while let Some(entry) = self.reader.next_entry()? {
+            let SessionEvent::Frame(frame) = entry.event else {
+                continue;
+            };
+            match sensors.iter().position(|sensor| sensor.id == frame.sensor_id) {
+                Some(index) => {
+                    sensors[index].frames.push_back(frame);
+                    spans[index].1 = entry.time;
+                }
+                None => {
+                    sensors.push(ReplayedSensor {
+                        id: frame.sensor_id.clone(),
+                        frames: VecDeque::from([frame]),
+                        rate_hz: None,
+                    });
+                    spans.push((entry.time, entry.time));
+                }
+            }
+        }
+        for (sensor, (first, last)) in sensors.iter_mut().zip(spans) {
+            let intervals = sensor.frames.len().saturating_sub(1) as f64;
+            let rate = intervals / ((last - first) as f64 / 1e6) * self.speed;
+            sensor.rate_hz = Some(rate).filter(|rate| rate.is_finite() && *rate > 0.0);
+        }
+        Ok(sensors)
+    }
+}
+
+/// The recorded frames of one sensor, delivered in order
+///
+/// Its sample rate is the recorded one scaled by the replay speed, so a
+/// `SensorManager` polls it as it polled the original. It has none if the
+/// sensor delivered fewer than two frames or the speed is unlimited. Once
+/// the frames run out, reads fail with `CoreError::EndOfStream`.
+pub struct ReplayedSensor {
+    id: String,
+    frames: VecDeque<SensorFrame>,
+    rate_hz: Option<f64>,
+}
+
+impl ReplayedSensor {
+    /// Frames not yet read
+    pub fn remaining(&self) -> usize {
+        self.frames.len()
+    }
+}
+
+impl Sensor for ReplayedSensor {
+    fn id(&self) -> &str {
+        &self.id
+    }
+    
+    fn read_frame(&mut self) -> Result<SensorFrame, CoreError> {
+        self.frames.pop_front().ok_or(CoreError::EndOfStream)
+    }
+    
+    fn sample_rate(&self) -> Option<f64> {
+        self.rate_hz
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::builtins::PassThrough;
+    use crate::clock::MockClock;
+    use crate::compression::Uncompressed;
+    use crate::memory::MemoryManager;
+    use crate::recorder::session::tests::temp_session;
+    use crate::recorder::{Record, RecordedSensor, SessionRecorder};
+    use crate::sensor::FunctionSensor;
+    use std::fs;
+    
+    // Two sensors at 100 Hz and 25 Hz over 100 ms, with an execution on
+    // every fast frame and one with a doctored output at the end
+    fn record(path: &Path) {
+        let clock = Arc::new(MockClock::new());
+        let recorder = Arc::new(SessionRecorder::start(path, clock.clone(), Box::new(Uncompressed)).unwrap());
+        let mut fast = RecordedSensor::new(
+            FunctionSensor::new("fast", Duration::from_millis(10), |t| t.to_le_bytes().to_vec()).unwrap(),
+            recorder.clone(),
+        );
+        let mut slow = RecordedSensor::new(
+            FunctionSensor::new("slow", Duration::from_millis(40), |t| t.to_le_bytes().to_vec()).unwrap(),
+            recorder.clone(),
+        );
+        let mut engine = CoreEngine::new();
+        for tick in 0..10 {
+            let frame = fast.read_frame().unwrap();
+            engine.execute_in_session(PassThrough::ID, &frame.payload, &recorder).unwrap();
+            if tick % 4 == 0 {
+                slow.read_frame().unwrap();
+            }
+            clock.advance(Duration::from_millis(10));
+        }
+        recorder
+            .record_execution(&Record {
+                algorithm_id: PassThrough::ID.to_string(),
+                input: vec![1],
+                output: vec![2],
+                ..Default::default()
+            })
+            .unwrap();
+        recorder.stop().unwrap();
+    }
+    
+    #[test]
+    fn test_replay_into_engine_is_timed_and_checked() {
+        let path = temp_session("replay");
+        record(&path);
+        assert!(ReplaySource::open(&path).unwrap().with_speed(0.0).is_err());
+        
+        let clock = Arc::new(MockClock::new());
+        let mut source = ReplaySource::open(&path).unwrap().with_clock(clock.clone()).with_speed(4.0).unwrap();
+        let mut engine = CoreEngine::new();
+        let report = source.replay(&mut engine).unwrap();
+        assert_eq!((report.frames, report.executions), (13, 11));
+        // The session spans 100 ms, played four times faster
+        assert_eq!(clock.now(), Duration::from_millis(25));
+        assert_eq!(report.divergences.len(), 1);
+        assert!(!report.is_deterministic());
+        assert_eq!(report.divergences[0].replayed, Ok(vec![1]));
+        let region = engine.memory().read(&SensorManager::region_key("slow")).unwrap();
+        assert_eq!(region, 80_000u64.to_le_bytes());
+        
+        let clock = Arc::new(MockClock::new());
+        let mut source = ReplaySource::open(&path)
+            .unwrap()
+            .with_clock(clock.clone())
+            .with_speed(f64::INFINITY)
+            .unwrap();
+        assert_eq!(source.replay(&mut CoreEngine::new()).unwrap().frames, 13);
+        assert_eq!(clock.now(), Duration::ZERO);
+        fs::remove_file(&path).unwrap();
+    }
+    
+    #[test]
+    fn test_replayed_sensors_feed_a_sensor_manager() {
+        let path = temp_session("sensors");
+        record(&path);
+        let sensors = ReplaySource::open(&path).unwrap().into_sensors().unwrap();
+        let ids: Vec<&str> = sensors.iter().map(|sensor| sensor.id()).collect();
+        assert_eq!(ids, ["fast", "slow"]);
+        assert_eq!(sensors.iter().map(|sensor| sensor.remaining()).collect::<Vec<_>>(), [10, 3]);
+        
+        let mut manager = SensorManager::new();
+        for sensor in sensors {
+            let rate = sensor.sample_rate().unwrap();
+            assert!([100.0, 25.0].iter().any(|expected| (rate - expected).abs() < 1e-9));
+            manager.add(Box::new(sensor), None).unwrap();
+        }
+        let mut memory = MemoryManager::new();
+        let mut stamps = Vec::new();
+        for ms in (0..100).step_by(10) {
+            for poll in manager.poll(&mut memory, Duration::from_millis(ms)) {
+                if poll.sensor_id == "slow" {
+                    stamps.push(poll.result.unwrap());
+                }
+            }
+        }
+        assert_eq!(stamps, [0, 40_000, 80_000]);
+        assert_eq!(manager.latest("fast").unwrap().timestamp, 90_000);
+        assert_eq!(
+            manager.poll(&mut memory, Duration::from_millis(100))[0].result,
+            Err(CoreError::EndOfStream)
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,408 @@
+//! Chunked, compressed recordings of sensor frames and executions
+//!
+//! A session file starts with the magic `RCSN`, followed by chunks, each a
+//! little-endian `u32` length and a `compression` container. A decompressed
+//! chunk is a run of entries: the time since the session started in
+//! microseconds (`u64`), a kind byte (`0` frame, `1` execution) and the
+//! encoded frame or execution with a `u32` length prefix. A chunk is written
+//! once it reaches the chunk size and on `stop`, so a crash loses at most
+//! the chunk being filled.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::Record;
+use crate::calibration::Calibration;
+use crate::clock::Clock;
+use crate::compression::{self, Compressor};
+use crate::error::CoreError;
+use crate::sensor::{Sensor, SensorFrame, SensorMetadata};
+use crate::wire;
+use crate::CoreEngine;
+
+/// Leading bytes of every session file
+pub const SESSION_MAGIC: [u8; 4] = *b"RCSN";
+
+const DEFAULT_CHUNK_BYTES: usize = 1024 * 1024;
+
+/// Largest chunk accepted from a session file, compressed or not
+const MAX_CHUNK_BYTES: usize = 256 * 1024 * 1024;
+
+/// Something that happened during a session
+#[derive(Clone, Debug, PartialEq)]
+pub enum SessionEvent {
+    /// A sensor delivered a frame
+    Frame(SensorFrame),
+    /// An algorithm ran
+    Execution(Record),
+}
+
+/// An event and when it happened
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionEntry {
+    /// Microseconds since the session started, on the recorder's clock
+    pub time: u64,
+    pub event: SessionEvent,
+}
+
+impl SessionEntry {
+    fn encode_into(&self, chunk: &mut Vec<u8>) -> Result<(), CoreError> {
+        let (kind, body) = match &self.event {
+            SessionEvent::Frame(frame) => (0, frame.encode()?),
+            SessionEvent::Execution(record) => (1, record.encode()?),
+        };
+        chunk.extend_from_slice(&self.time.to_le_bytes());
+        chunk.push(kind);
+        wire::put_bytes(chunk, &body)
+    }
+    
+    fn decode_from(reader: &mut wire::Reader) -> Result<Self, CoreError> {
+        let time = reader.u64()?;
+        let kind = reader.u8()?;
+        let body = reader.bytes()?;
+        let event = match kind {
+            0 => SessionEvent::Frame(SensorFrame::decode(body)?),
+            1 => SessionEvent::Execution(Record::decode(body)?),
+            other => return Err(reader.error(&format!("unknown entry kind {}", other))),
+        };
+        Ok(Self { time, event })
+    }
+}
+
+struct SessionWriter {
+    file: BufWriter<File>,
+    chunk: Vec<u8>,
+    last_time: u64,
+}
+
+/// Records sensor frames and executions into a session file
+///
+/// Methods take `&self`, so one recorder can be shared through an `Arc`
+/// by `RecordedSensor`s and the engine. Entry times come from the
+/// recorder's clock and never go backwards. After `stop` the file is
+/// complete and further recording fails.
+pub struct SessionRecorder {
+    clock: Arc<dyn Clock>,
+    started: Duration,
+    compressor: Box<dyn Compressor>,
+    chunk_bytes: usize,
+    writer: Mutex<Option<SessionWriter>>,
+    entries: Mutex<u64>,
+}
+
+impl SessionRecorder {
+    /// Start recording to `path`, replacing any existing file, with chunks
+    /// compressed by `compressor`
+    pub fn start(
+        path: impl AsRef<Path>,
+        clock: Arc<dyn Clock>,
+        compressor: Box<dyn Compressor>,
+    ) -> Result<Self, CoreError> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(&SESSION_MAGIC)?;
+        Ok(Self {
+            started: clock.now(),
+            clock,
+            compressor,
+            chunk_bytes: DEFAULT_CHUNK_BYTES,
+            writer: Mutex::new(Some(SessionWriter {
+                file,
+                chunk: Vec::new(),
+                last_time: 0,
+            })),
+            entries: Mutex::new(0),
+        })
+    }
+    
+    /// Write a chunk once it holds `bytes` of entries, 1 MiB by default
+    pub fn with_chunk_bytes(mut self, bytes: usize) -> Self {
+        self.chunk_bytes = bytes.max(1);
+        self
+    }
+    
+    /// Whether `stop` has not been called yet
+    pub fn is_recording(&self) -> bool {
+        self.writer.lock().unwrap_or_else(|e| e.into_inner()).is_some()
+    }
+    
+    /// Entries recorded so far
+    pub fn len(&self) -> u64 {
+        *self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    /// Whether nothing has been recorded yet
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    
+    /// Record a frame delivered by a sensor
+    pub fn record_frame(&self, frame: &SensorFrame) -> Result<(), CoreError> {
+        self.record(SessionEvent::Frame(frame.clone()))
+    }
+    
+    /// Record an execution
+    pub fn record_execution(&self, record: &Record) -> Result<(), CoreError> {
+        self.record(SessionEvent::Execution(record.clone()))
+    }
+    
+    fn record(&self, event: SessionEvent) -> Result<(), CoreError> {
+        let elapsed = self.clock.now().saturating_sub(self.started);
+        let mut guard = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let writer = guard
+            .as_mut()
+            .ok_or_else(|| CoreError::PreconditionFailed("session recording has stopped".to_string()))?;
+        let time = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX).max(writer.last_time);
+        SessionEntry { time, event }.encode_into(&mut writer.chunk)?;
+        writer.last_time = time;
+        *self.entries.lock().unwrap_or_else(|e| e.into_inner()) += 1;
+        if writer.chunk.len() >= self.chunk_bytes {
+            self.write_chunk(writer)?;
+        }
+        Ok(())
+    }
+    
+    fn write_chunk(&self, writer: &mut SessionWriter) -> Result<(), CoreError> {
+        if writer.chunk.is_empty() {
+            return Ok(());
+        }
+        let packed = compression::pack(&writer.chunk, self.compressor.as_ref())?;
+        let len = u32::try_from(packed.len())
+            .ok()
+            .filter(|&len| len as usize <= MAX_CHUNK_BYTES)
+            .ok_or_else(|| CoreError::InvalidInput("session chunk too long".to_string()))?;
+        writer.file.write_all(&len.to_le_bytes())?;
+        writer.file.write_all(&packed)?;
+        writer.file.flush()?;
+        writer.chunk.clear();
+        Ok(())
+    }
+    
+    /// Write the last chunk and close the file, returning the number of
+    /// entries recorded
+    ///
+    /// Stopping a stopped recorder does nothing.
+    pub fn stop(&self) -> Result<u64, CoreError> {
+        let mut guard = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(mut writer) = guard.take() {
+            self.write_chunk(&mut writer)?;
+        }
+        Ok(self.len())
+    }
+}
+
+impl Drop for SessionRecorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.stop() {
+            log::warn!("Session recording lost its last chunk: {}", e);
+        }
+    }
+}
+
+/// Reads the entries of a session file in order
+///
+/// A chunk cut short at the end of the file, as left by a crash, ends the
+/// session; a corrupt chunk fails the read.
+pub struct SessionReader {
+    file: BufReader<File>,
+    pending: std::vec::IntoIter<SessionEntry>,
+}
+
+impl SessionReader {
+    /// Open the session file at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CoreError> {
+        let mut file = BufReader::new(File::open(path)?);
+        let mut magic = [0u8; 4];
+        file.read_exact(&mut magic)?;
+        if magic != SESSION_MAGIC {
+            return Err(CoreError::InvalidInput("not a session file".to_string()));
+        }
+        Ok(Self {
+            file,
+            pending: Vec::new().into_iter(),
+        })
+    }
+    
+    /// The next entry, or `None` at the end of the session
+    pub fn next_entry(&mut self) -> Result<Option<SessionEntry>, CoreError> {
+        loop {
+            if let Some(entry) = self.pending.next() {
+                return Ok(Some(entry));
+            }
+            match self.read_chunk()? {
+                Some(entries) => self.pending = entries.into_iter(),
+                None => return Ok(None),
+            }
+        }
+    }
+    
+    fn read_chunk(&mut self) -> Result<Option<Vec<SessionEntry>>, CoreError> {
+        let mut len = [0u8; 4];
+        let mut packed = Vec::new();
+        match self.file.read_exact(&mut len) {
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            result => result?,
+        }
+        let len = u32::from_le_bytes(len) as usize;
+        if len > MAX_CHUNK_BYTES {
+            return Err(CoreError::InvalidInput(format!(
+                "session chunk of {} bytes exceeds the {} byte limit",
+                len, MAX_CHUNK_BYTES
+            )));
+        }
+        (&mut self.file).take(len as u64).read_to_end(&mut packed)?;
+        if packed.len() < len {
+            log::warn!("Session file ends inside a chunk; the chunk is dropped");
+            return Ok(None);
+        }
+        let chunk = compression::unpack(&packed)?;
+        let mut reader = wire::Reader::new(&chunk, "session chunk", CoreError::InvalidInput);
+        let mut entries = Vec::new();
+        while !reader.is_empty() {
+            entries.push(SessionEntry::decode_from(&mut reader)?);
+        }
+        Ok(Some(entries))
+    }
+}
+
+/// Wraps a sensor so every frame it delivers is recorded into a session
+///
+/// A frame that cannot be recorded, such as after the recorder stopped, is
+/// still returned; the failure is logged.
+pub struct RecordedSensor<S: Sensor> {
+    inner: S,
+    recorder: Arc<SessionRecorder>,
+}
+
+impl<S: Sensor> RecordedSensor<S> {
+    pub fn new(inner: S, recorder: Arc<SessionRecorder>) -> Self {
+        Self { inner, recorder }
+    }
+    
+    /// Unwrap the inner sensor
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Sensor> Sensor for RecordedSensor<S> {
+    fn id(&self) -> &str {
+        self.inner.id()
+    }
+    
+    fn read_frame(&mut self) -> Result<SensorFrame, CoreError> {
+        let frame = self.inner.read_frame()?;
+        if let Err(e) = self.recorder.record_frame(&frame) {
+            log::warn!("Frame from sensor {} was not recorded: {}", self.inner.id(), e);
+        }
+        Ok(frame)
+    }
+    
+    fn reconnect(&mut self) -> Result<(), CoreError> {
+        self.inner.reconnect()
+    }
+    
+    fn sample_rate(&self) -> Option<f64> {
+        self.inner.sample_rate()
+    }
+    
+    fn calibrate(&mut self) -> Result<(), CoreError> {
+        self.inner.calibrate()
+    }
+    
+    fn calibration(&self) -> Option<Calibration> {
+        self.inner.calibration()
+    }
+    
+    fn set_calibration(&mut self, calibration: &Calibration) -> Result<(), CoreError> {
+        self.inner.set_calibration(calibration)
+    }
+    
+    fn metadata(&self) -> SensorMetadata {
+        self.inner.metadata()
+    }
+}
+
+impl CoreEngine {
+    /// Execute an algorithm and record the execution into `session`
+    ///
+    /// Only successful executions are recorded.
+    pub fn execute_in_session(
+        &mut self,
+        algorithm_id: &str,
+        input_data: &[u8],
+        session: &SessionRecorder,
+    ) -> Result<Vec<u8>, CoreError> {
+        let timestamp = self.clock.now().as_micros() as u64;
+        let output = self.execute_algorithm(algorithm_id, input_data)?;
+        session.record_execution(&Record {
+            algorithm_id: algorithm_id.to_string(),
+            timestamp,
+            input: input_data.to_vec(),
+            output: output.clone(),
+        })?;
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use crate::algorithm::builtins::PassThrough;
+    use crate::clock::MockClock;
+    use crate::compression::Uncompressed;
+    use crate::sensor::MockSensor;
+    use std::fs::{self, OpenOptions};
+    use std::path::PathBuf;
+    
+    pub(in crate::recorder) fn temp_session(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("robotics_core_{}_{}.session", name, std::process::id()))
+    }
+    
+    #[test]
+    fn test_session_round_trips_across_chunks() {
+        let path = temp_session("chunks");
+        let clock = Arc::new(MockClock::new());
+        let recorder = SessionRecorder::start(&path, clock.clone(), Box::new(Uncompressed))
+            .unwrap()
+            .with_chunk_bytes(100);
+        let mut sensor = RecordedSensor::new(MockSensor::new("imu", 100.0, vec![0.5]).unwrap(), Arc::new(recorder));
+        let recorder = sensor.recorder.clone();
+        let mut engine = CoreEngine::new();
+        for i in 0..20u8 {
+            sensor.read_frame().unwrap();
+            engine.execute_in_session(PassThrough::ID, &[i], &recorder).unwrap();
+            clock.advance(Duration::from_millis(10));
+        }
+        assert_eq!(recorder.len(), 40);
+        assert_eq!(recorder.stop().unwrap(), 40);
+        assert!(!recorder.is_recording());
+        assert!(recorder.record_frame(&SensorFrame::default()).is_err());
+        // The sensor keeps working once the recording is over
+        assert!(sensor.read_frame().is_ok());
+        
+        // A crash part way through writing a chunk leaves a torn tail
+        OpenOptions::new().append(true).open(&path).unwrap().write_all(&[200, 0, 0, 0, 1]).unwrap();
+        let mut reader = SessionReader::open(&path).unwrap();
+        let mut entries = Vec::new();
+        while let Some(entry) = reader.next_entry().unwrap() {
+            entries.push(entry);
+        }
+        assert_eq!(entries.len(), 40);
+        assert_eq!(entries[38].time, 190_000);
+        let SessionEvent::Frame(frame) = &entries[2].event else {
+            panic!("expected a frame");
+        };
+        assert_eq!((frame.sensor_id.as_str(), frame.timestamp), ("imu", 10_000));
+        let SessionEvent::Execution(record) = &entries[39].event else {
+            panic!("expected an execution");
+        };
+        assert_eq!((record.input.as_slice(), record.output.as_slice()), (&[19][..], &[19][..]));
+        
+        fs::write(&path, b"RCPK").unwrap();
+        assert!(SessionReader::open(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,102 @@
+//! Calling an `RpcServer` from Rust
+
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use super::{parse_reply, read_message, write_message, MetricsFormat, Request};
+use crate::algorithm::AlgorithmMetadata;
+use crate::error::CoreError;
+use crate::sensor::{Sensor, SensorFrame};
+use crate::wire;
+
+/// A connection to an `RpcServer`
+///
+/// Calls are answered in order on the one connection; use a client per
+/// thread for concurrent calls.
+pub struct RpcClient {
+    stream: TcpStream,
+}
+
+impl RpcClient {
+    pub fn connect(address: impl ToSocketAddrs) -> Result<Self, CoreError> {
+        let stream = TcpStream::connect(address)?;
+        stream.set_nodelay(true)?;
+        Ok(Self { stream })
+    }
+    
+    /// Fail calls with `CoreError::IoError` after waiting `timeout` for the
+    /// server, or wait indefinitely if `None`
+    pub fn set_timeout(&self, timeout: Option<Duration>) -> Result<(), CoreError> {
+        self.stream.set_read_timeout(timeout)?;
+        Ok(self.stream.set_write_timeout(timeout)?)
+    }
+    
+    fn call(&mut self, request: &Request) -> Result<Vec<u8>, CoreError> {
+        write_message(&mut self.stream, &request.encode()?)?;
+        let reply = read_message(&mut self.stream)?
+            .ok_or_else(|| CoreError::IoError("server closed the connection".to_string()))?;
+        parse_reply(&reply).map(<[u8]>::to_vec)
+    }
+    
+    /// Execute an algorithm on the server's engine
+    pub fn execute(&mut self, algorithm_id: &str, input_data: &[u8]) -> Result<Vec<u8>, CoreError> {
+        self.call(&Request::ExecuteAlgorithm {
+            algorithm_id: algorithm_id.to_string(),
+            input: input_data.to_vec(),
+        })
+    }
+    
+    /// IDs and metadata of the algorithms registered on the server
+    pub fn list_algorithms(&mut self) -> Result<Vec<(String, AlgorithmMetadata)>, CoreError> {
+        let result = self.call(&Request::ListAlgorithms)?;
+        let mut reader = wire::Reader::new(&result, "algorithm list", CoreError::IoError);
+        let count = reader.u32()?;
+        let mut algorithms = Vec::new();
+        for _ in 0..count {
+            let id = reader.short_str()?;
+            let metadata = serde_json::from_str(&reader.str()?)
+                .map_err(|e| CoreError::IoError(format!("algorithm list: {}", e)))?;
+            algorithms.push((id, metadata));
+        }
+        reader.finish()?;
+        Ok(algorithms)
+    }
+    
+    /// The server engine's metrics, rendered in `format`
+    pub fn metrics(&mut self, format: MetricsFormat) -> Result<String, CoreError> {
+        String::from_utf8(self.call(&Request::GetMetrics(format))?)
+            .map_err(|_| CoreError::IoError("metrics are not UTF-8".to_string()))
+    }
+    
+    /// Turn the connection into a stream of the frames the server publishes
+    /// for sensor `id`
+    pub fn stream_sensor(mut self, id: &str) -> Result<RemoteSensor, CoreError> {
+        self.call(&Request::StreamSensorData {
+            sensor_id: id.to_string(),
+        })?;
+        Ok(RemoteSensor {
+            id: id.to_string(),
+            stream: self.stream,
+        })
+    }
+}
+
+/// Frames of a sensor streamed from an `RpcServer`
+///
+/// Reads block until the server publishes a frame, or fail with
+/// `CoreError::EndOfStream` once the server shuts down.
+pub struct RemoteSensor {
+    id: String,
+    stream: TcpStream,
+}
+
+impl Sensor for RemoteSensor {
+    fn id(&self) -> &str {
+        &self.id
+    }
+    
+    fn read_frame(&mut self) -> Result<SensorFrame, CoreError> {
+        let reply = read_message(&mut self.stream)?.ok_or(CoreError::EndOfStream)?;
+        SensorFrame::decode(parse_reply(&reply)?)
+    }
+}
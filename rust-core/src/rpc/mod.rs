@@ -0,0 +1,246 @@
+//! Driving an engine over the network
+//!
+//! `RpcServer` serves an engine over TCP with a small length-prefixed
+//! protocol that any language can speak; `RpcClient` is the Rust side of it.
+//! Every message is a little-endian `u32` body length followed by the body,
+//! using the crate's usual encodings: `u32`-prefixed bytes and text, and
+//! `u16`-prefixed IDs.
+//!
+//! A request starts with a method byte:
+//!
+//! - `1` ExecuteAlgorithm: algorithm ID and input bytes; replies with the
+//!   output bytes
+//! - `2` ListAlgorithms: replies with a `u32` count, then each algorithm's ID
+//!   and its `AlgorithmMetadata` as JSON text
+//! - `3` StreamSensorData: sensor ID; replies with an empty acknowledgement,
+//!   then one reply per frame published for that sensor, each a record
+//!   encoded by `SensorFrame::encode`. The connection carries only the stream
+//!   from then on.
+//! - `4` GetMetrics: a format byte (`0` JSON, `1` Prometheus); replies with
+//!   the exported metrics as text
+//!
+//! A reply starts with a status byte: `0` and the result, or `1`, the
+//! `CoreError` variant name as an ID and its message as text.
+
+use crate::error::CoreError;
+use crate::wire;
+
+mod client;
+mod server;
+
+pub use client::{RemoteSensor, RpcClient};
+pub use server::RpcServer;
+
+use std::io::{ErrorKind, Read, Write};
+
+/// Largest message body accepted from the network
+const MAX_MESSAGE_BYTES: usize = 64 * 1024 * 1024;
+
+/// How `GetMetrics` renders the metrics
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MetricsFormat {
+    /// `JsonExporter` output
+    #[default]
+    Json,
+    /// `PrometheusExporter` output
+    Prometheus,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Request {
+    ExecuteAlgorithm { algorithm_id: String, input: Vec<u8> },
+    ListAlgorithms,
+    StreamSensorData { sensor_id: String },
+    GetMetrics(MetricsFormat),
+}
+
+impl Request {
+    fn encode(&self) -> Result<Vec<u8>, CoreError> {
+        let mut body = Vec::new();
+        match self {
+            Request::ExecuteAlgorithm { algorithm_id, input } => {
+                body.push(1);
+                wire::put_short_str(&mut body, algorithm_id)?;
+                wire::put_bytes(&mut body, input)?;
+            }
+            Request::ListAlgorithms => body.push(2),
+            Request::StreamSensorData { sensor_id } => {
+                body.push(3);
+                wire::put_short_str(&mut body, sensor_id)?;
+            }
+            Request::GetMetrics(format) => body.extend([4, *format as u8]),
+        }
+        Ok(body)
+    }
+    
+    fn decode(body: &[u8]) -> Result<Self, CoreError> {
+        let mut reader = wire::Reader::new(body, "rpc request", CoreError::InvalidInput);
+        let request = match reader.u8()? {
+            1 => Request::ExecuteAlgorithm {
+                algorithm_id: reader.short_str()?,
+                input: reader.bytes()?.to_vec(),
+            },
+            2 => Request::ListAlgorithms,
+            3 => Request::StreamSensorData {
+                sensor_id: reader.short_str()?,
+            },
+            4 => Request::GetMetrics(match reader.u8()? {
+                0 => MetricsFormat::Json,
+                1 => MetricsFormat::Prometheus,
+                other => return Err(reader.error(&format!("unknown metrics format {}", other))),
+            }),
+            other => return Err(reader.error(&format!("unknown method {}", other))),
+        };
+        reader.finish()?;
+        Ok(request)
+    }
+}
+
+/// A successful reply carrying `result`
+fn ok_reply(result: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(1 + result.len());
+    body.push(0);
+    body.extend_from_slice(result);
+    body
+}
+
+/// A failed reply describing `error`
+fn error_reply(error: &CoreError) -> Result<Vec<u8>, CoreError> {
+    let mut body = vec![1];
+    wire::put_short_str(&mut body, &crate::telemetry::error_kind(error))?;
+    wire::put_bytes(&mut body, error.to_string().as_bytes())?;
+    Ok(body)
+}
+
+/// The result carried by a reply, or the error it reports
+///
+/// Remote errors come back as `CoreError::ProcessingFailed` naming the
+/// original variant, except `EndOfStream`, which is kept as is.
+fn parse_reply(body: &[u8]) -> Result<&[u8], CoreError> {
+    let mut reader = wire::Reader::new(body, "rpc reply", CoreError::IoError);
+    match reader.u8()? {
+        0 => reader.take(body.len() - 1),
+        1 => {
+            let kind = reader.short_str()?;
+            let message = reader.str()?;
+            Err(match kind.as_str() {
+                "EndOfStream" => CoreError::EndOfStream,
+                _ => CoreError::ProcessingFailed(format!("remote {}: {}", kind, message)),
+            })
+        }
+        other => Err(reader.error(&format!("unknown status {}", other))),
+    }
+}
+
+fn write_message(stream: &mut impl Write, body: &[u8]) -> Result<(), CoreError> {
+    let len = u32::try_from(body.len())
+        .ok()
+        .filter(|&len| len as usize <= MAX_MESSAGE_BYTES)
+        .ok_or_else(|| CoreError::InvalidInput(format!("message of {} bytes is too long to send", body.len())))?;
+    stream.write_all(&len.to_le_bytes())?;
+    stream.write_all(body)?;
+    stream.flush().map_err(CoreError::from)
+}
+
+/// Read one message, or `None` if the peer closed the connection between
+/// messages
+fn read_message(stream: &mut impl Read) -> Result<Option<Vec<u8>>, CoreError> {
+    let mut len = [0u8; 4];
+    let mut filled = 0;
+    while filled < len.len() {
+        match stream.read(&mut len[filled..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => return Err(CoreError::IoError("connection closed inside a message".to_string())),
+            Ok(read) => filled += read,
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) => return Err(CoreError::from(err)),
+        }
+    }
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_MESSAGE_BYTES {
+        return Err(CoreError::InvalidInput(format!(
+            "message of {} bytes exceeds the {} byte limit",
+            len, MAX_MESSAGE_BYTES
+        )));
+    }
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body)?;
+    Ok(Some(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::builtins::{PassThrough, Scale};
+    use crate::sensor::{Sensor, SensorFrame};
+    use crate::CoreEngine;
+    use std::time::Duration;
+    
+    #[test]
+    fn test_requests_round_trip_and_bad_ones_fail() {
+        for request in [
+            Request::ExecuteAlgorithm {
+                algorithm_id: "fft".to_string(),
+                input: vec![1, 2, 3],
+            },
+            Request::ListAlgorithms,
+            Request::StreamSensorData {
+                sensor_id: "imu".to_string(),
+            },
+            Request::GetMetrics(MetricsFormat::Prometheus),
+        ] {
+            assert_eq!(Request::decode(&request.encode().unwrap()).unwrap(), request);
+        }
+        assert!(Request::decode(&[9]).is_err());
+        assert!(Request::decode(&[4, 2]).is_err());
+        assert!(Request::decode(&[2, 0]).is_err());
+        
+        let reply = error_reply(&CoreError::AlgorithmNotFound("fft".to_string())).unwrap();
+        let error = parse_reply(&reply).unwrap_err();
+        assert_eq!(
+            error,
+            CoreError::ProcessingFailed("remote AlgorithmNotFound: Algorithm not found: fft".to_string())
+        );
+        assert_eq!(parse_reply(&ok_reply(&[7])), Ok(&[7][..]));
+    }
+    
+    #[test]
+    fn test_client_drives_server() {
+        let server = RpcServer::start(CoreEngine::new(), "127.0.0.1:0").unwrap();
+        server.with_engine(|engine| engine.register(Box::new(Scale::new(2.0).unwrap()))).unwrap();
+        let mut client = RpcClient::connect(server.local_addr()).unwrap();
+        client.set_timeout(Some(Duration::from_secs(5))).unwrap();
+        
+        assert_eq!(client.execute(PassThrough::ID, &[1, 2, 3]).unwrap(), vec![1, 2, 3]);
+        let error = client.execute("missing", &[]).unwrap_err();
+        assert!(matches!(error, CoreError::ProcessingFailed(message) if message.contains("AlgorithmNotFound")));
+        let algorithms = client.list_algorithms().unwrap();
+        assert!(algorithms.iter().any(|(id, _)| id == Scale::ID));
+        let metrics = client.metrics(MetricsFormat::Prometheus).unwrap();
+        assert!(metrics.contains(&format!(
+            "robotics_core_algorithm_latency_seconds_count{{algorithm=\"{}\"}} 1",
+            PassThrough::ID
+        )));
+        assert!(client.metrics(MetricsFormat::Json).unwrap().starts_with('{'));
+        
+        let mut sensor = RpcClient::connect(server.local_addr()).unwrap().stream_sensor("imu").unwrap();
+        let frame = |timestamp| SensorFrame {
+            sensor_id: "imu".to_string(),
+            timestamp,
+            payload: vec![timestamp as u8],
+            ..Default::default()
+        };
+        assert_eq!(server.publish(&SensorFrame::default()), 0);
+        assert_eq!(server.publish(&frame(1)), 1);
+        assert_eq!(server.publish(&frame(2)), 1);
+        assert_eq!(sensor.id(), "imu");
+        assert_eq!(sensor.read_frame().unwrap(), frame(1));
+        assert_eq!(sensor.read_frame().unwrap(), frame(2));
+        
+        // Shutting down ends the stream and hands the engine back
+        let engine = server.shutdown().unwrap();
+        assert_eq!(sensor.read_frame(), Err(CoreError::EndOfStream));
+        assert_eq!(engine.metrics().algorithms[PassThrough::ID].latency.count, 1);
+        assert!(client.execute(PassThrough::ID, &[]).is_err());
+    }
+}
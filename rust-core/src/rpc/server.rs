@@ -0,0 +1,256 @@
+//! Serving an engine to remote clients
+
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+use super::{error_reply, ok_reply, read_message, write_message, MetricsFormat, Request};
+use crate::error::CoreError;
+use crate::sensor::SensorFrame;
+use crate::telemetry::{JsonExporter, PrometheusExporter};
+use crate::wire;
+use crate::CoreEngine;
+
+/// Frames queued per streaming client before new ones are dropped
+const STREAM_CAPACITY: usize = 64;
+
+struct Subscriber {
+    sensor_id: String,
+    sender: SyncSender<SensorFrame>,
+}
+
+struct Shared {
+    engine: Mutex<CoreEngine>,
+    subscribers: Mutex<Vec<Subscriber>>,
+    // Clones of open connections, so shutdown can close them
+    connections: Mutex<Vec<(u64, TcpStream)>>,
+    next_connection: AtomicU64,
+    stopping: AtomicBool,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Serves an engine over TCP, with a thread per connection
+///
+/// Requests from all clients execute on the one engine in turn. Sensor
+/// data reaches streaming clients through `publish`; like `SensorHub`, each
+/// client has its own bounded queue and misses the newest frames while it
+/// is full. Dropping the server shuts it down.
+pub struct RpcServer {
+    local_addr: SocketAddr,
+    shared: Arc<Shared>,
+    accept: Option<JoinHandle<()>>,
+}
+
+impl RpcServer {
+    /// Serve `engine` on `address`, such as `0.0.0.0:50051`
+    pub fn start(engine: CoreEngine, address: impl ToSocketAddrs) -> Result<Self, CoreError> {
+        let listener = TcpListener::bind(address)?;
+        let local_addr = listener.local_addr()?;
+        let shared = Arc::new(Shared {
+            engine: Mutex::new(engine),
+            subscribers: Mutex::new(Vec::new()),
+            connections: Mutex::new(Vec::new()),
+            next_connection: AtomicU64::new(0),
+            stopping: AtomicBool::new(false),
+        });
+        let accept = {
+            let shared = Arc::clone(&shared);
+            thread::Builder::new()
+                .name("rpc-accept".to_string())
+                .spawn(move || accept_loop(&shared, listener))?
+        };
+        log::info!("RPC server listening on {}", local_addr);
+        Ok(Self {
+            local_addr,
+            shared,
+            accept: Some(accept),
+        })
+    }
+    
+    /// Address the server is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+    
+    /// Run `f` with exclusive access to the engine, e.g. to register
+    /// algorithms while serving
+    pub fn with_engine<R>(&self, f: impl FnOnce(&mut CoreEngine) -> R) -> R {
+        f(&mut lock(&self.shared.engine))
+    }
+    
+    /// Send `frame` to every client streaming its sensor, returning how many
+    /// queued it
+    pub fn publish(&self, frame: &SensorFrame) -> usize {
+        let mut delivered = 0;
+        lock(&self.shared.subscribers).retain(|subscriber| {
+            if subscriber.sensor_id != frame.sensor_id {
+                return true;
+            }
+            match subscriber.sender.try_send(frame.clone()) {
+                Ok(()) => {
+                    delivered += 1;
+                    true
+                }
+                Err(TrySendError::Full(_)) => true,
+                Err(TrySendError::Disconnected(_)) => false,
+            }
+        });
+        delivered
+    }
+    
+    /// Stop serving and hand the engine back
+    ///
+    /// Open connections are closed and streaming clients receive
+    /// `CoreError::EndOfStream`.
+    pub fn shutdown(mut self) -> Result<CoreEngine, CoreError> {
+        self.stop();
+        let shared = Arc::clone(&self.shared);
+        drop(self);
+        Arc::try_unwrap(shared)
+            .map(|shared| shared.engine.into_inner().unwrap_or_else(|e| e.into_inner()))
+            .map_err(|_| CoreError::PreconditionFailed("RPC connections are still running".to_string()))
+    }
+    
+    fn stop(&mut self) {
+        let Some(accept) = self.accept.take() else {
+            return;
+        };
+        self.shared.stopping.store(true, Ordering::SeqCst);
+        // Wake the accept loop so it sees the flag
+        let mut wake = self.local_addr;
+        if wake.ip().is_unspecified() {
+            wake.set_ip(match wake {
+                SocketAddr::V4(_) => std::net::Ipv4Addr::LOCALHOST.into(),
+                SocketAddr::V6(_) => std::net::Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+        let _ = TcpStream::connect(wake);
+        if accept.join().is_err() {
+            log::error!("RPC accept thread panicked");
+        }
+    }
+}
+
+impl Drop for RpcServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn accept_loop(shared: &Arc<Shared>, listener: TcpListener) {
+    let mut handlers: Vec<JoinHandle<()>> = Vec::new();
+    for stream in listener.incoming() {
+        if shared.stopping.load(Ordering::SeqCst) {
+            break;
+        }
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                log::warn!("RPC accept failed: {}", e);
+                continue;
+            }
+        };
+        let id = shared.next_connection.fetch_add(1, Ordering::Relaxed);
+        match stream.try_clone() {
+            Ok(clone) => lock(&shared.connections).push((id, clone)),
+            Err(e) => {
+                log::warn!("RPC connection dropped: {}", e);
+                continue;
+            }
+        }
+        let handler_shared = Arc::clone(shared);
+        let spawned = thread::Builder::new().name("rpc-connection".to_string()).spawn(move || {
+            if let Err(e) = serve_connection(&handler_shared, stream, id) {
+                log::debug!("RPC connection closed: {}", e);
+            }
+            lock(&handler_shared.connections).retain(|(open, _)| *open != id);
+        });
+        match spawned {
+            Ok(handler) => handlers.push(handler),
+            Err(e) => log::warn!("RPC connection dropped: {}", e),
+        }
+        handlers.retain(|handler| !handler.is_finished());
+    }
+    for (_, connection) in lock(&shared.connections).iter() {
+        let _ = connection.shutdown(Shutdown::Both);
+    }
+    lock(&shared.subscribers).clear();
+    for handler in handlers {
+        if handler.join().is_err() {
+            log::error!("RPC connection thread panicked");
+        }
+    }
+}
+
+fn serve_connection(shared: &Shared, mut stream: TcpStream, id: u64) -> Result<(), CoreError> {
+    while let Some(body) = read_message(&mut stream)? {
+        let reply = match Request::decode(&body) {
+            Ok(Request::StreamSensorData { sensor_id }) => {
+                // A stream is ended by shutdown dropping its subscription, so
+                // the connection stays open for the final reply
+                lock(&shared.connections).retain(|(open, _)| *open != id);
+                return stream_frames(shared, stream, sensor_id);
+            }
+            Ok(request) => answer(shared, request),
+            Err(e) => Err(e),
+        };
+        let reply = match reply {
+            Ok(result) => ok_reply(&result),
+            Err(e) => error_reply(&e)?,
+        };
+        write_message(&mut stream, &reply)?;
+    }
+    Ok(())
+}
+
+fn answer(shared: &Shared, request: Request) -> Result<Vec<u8>, CoreError> {
+    let mut engine = lock(&shared.engine);
+    match request {
+        Request::ExecuteAlgorithm { algorithm_id, input } => engine.execute_algorithm(&algorithm_id, &input),
+        Request::ListAlgorithms => {
+            let algorithms = engine.list_algorithms();
+            let mut result = (algorithms.len() as u32).to_le_bytes().to_vec();
+            for (id, metadata) in algorithms {
+                let metadata = serde_json::to_string(&metadata)
+                    .map_err(|e| CoreError::ProcessingFailed(format!("metadata does not serialize: {}", e)))?;
+                wire::put_short_str(&mut result, &id)?;
+                wire::put_bytes(&mut result, metadata.as_bytes())?;
+            }
+            Ok(result)
+        }
+        Request::GetMetrics(format) => {
+            let text = match format {
+                MetricsFormat::Json => engine.export_metrics(&JsonExporter::new())?,
+                MetricsFormat::Prometheus => engine.export_metrics(&PrometheusExporter::new())?,
+            };
+            Ok(text.into_bytes())
+        }
+        Request::StreamSensorData { .. } => {
+            Err(CoreError::InvalidInput("streams are served by serve_connection".to_string()))
+        }
+    }
+}
+
+fn stream_frames(shared: &Shared, mut stream: TcpStream, sensor_id: String) -> Result<(), CoreError> {
+    let (sender, receiver): (_, Receiver<SensorFrame>) = mpsc::sync_channel(STREAM_CAPACITY);
+    {
+        // Checked under the lock shutdown clears subscriptions with, so a
+        // stream cannot subscribe after the clear and wait forever
+        let mut subscribers = lock(&shared.subscribers);
+        if shared.stopping.load(Ordering::SeqCst) {
+            drop(subscribers);
+            return write_message(&mut stream, &error_reply(&CoreError::EndOfStream)?);
+        }
+        subscribers.push(Subscriber { sensor_id, sender });
+    }
+    write_message(&mut stream, &ok_reply(&[]))?;
+    while let Ok(frame) = receiver.recv() {
+        write_message(&mut stream, &ok_reply(&frame.encode()?))?;
+    }
+    write_message(&mut stream, &error_reply(&CoreError::EndOfStream)?)
+}
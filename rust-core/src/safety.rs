@@ -0,0 +1,571 @@
+//! Heartbeat watchdog and the safety state of a robot
+//!
+//! A `SafetyMonitor` watches named heartbeat sources: tasks and sensors,
+//! by ID, or anything else that pets it. A source that stays silent past
+//! its timeouts degrades the state and then stops the robot, as does a
+//! missed deadline or a manual `emergency_stop`. Every change of state runs
+//! the registered `SafetyAction`s, such as a `PwmCutoff` idling the motors.
+//!
+//! `Degraded` clears by itself once every source is petting again, while
+//! `EmergencyStop` latches until `reset`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::clock::{Clock, SystemClock};
+use crate::error::CoreError;
+use crate::hardware::HardwareInterface;
+use crate::scheduler::ScheduledRun;
+use crate::sensor::SensorPoll;
+use crate::DeadlineMiss;
+
+/// How far a robot may go on acting, from least to most restricted
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum SafetyState {
+    /// Everything is on time
+    #[default]
+    Operational,
+    /// Something is late; carry on with caution
+    Degraded,
+    /// Actuators must be stopped until an operator resets the monitor
+    EmergencyStop,
+}
+
+/// Why the safety state changed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SafetyCause {
+    /// A watched source went without a heartbeat for `silent_for`
+    HeartbeatMissed { source: String, silent_for: Duration },
+    /// An execution or scheduled run finished past its deadline
+    DeadlineMissed { algorithm_id: String },
+    /// `emergency_stop` was called
+    Manual(String),
+    /// Every source is petting again and the recovery delay has passed
+    Recovered,
+    /// `reset` cleared an emergency stop
+    Reset,
+}
+
+/// A change of safety state, passed to every `SafetyAction`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SafetyTransition {
+    pub from: SafetyState,
+    pub to: SafetyState,
+    pub cause: SafetyCause,
+}
+
+/// Something to do when the safety state changes, such as cutting power to
+/// the motors
+///
+/// Actions run on the thread that caused the change, in the order they
+/// were added. A failing action is logged and the rest still run. Actions
+/// must not call back into the monitor's `check`, `trip` or `reset`.
+pub trait SafetyAction: Send {
+    fn on_transition(&mut self, transition: &SafetyTransition) -> Result<(), CoreError>;
+}
+
+impl<F> SafetyAction for F
+where
+    F: FnMut(&SafetyTransition) -> Result<(), CoreError> + Send,
+{
+    fn on_transition(&mut self, transition: &SafetyTransition) -> Result<(), CoreError> {
+        self(transition)
+    }
+}
+
+/// Sets PWM channels to a duty cycle of 0 on an emergency stop
+///
+/// Give it its own handle to the PWM hardware, separate from the one the
+/// engine's device drives, so it works whatever state the engine is in.
+pub struct PwmCutoff {
+    hardware: Box<dyn HardwareInterface>,
+    channels: Vec<u32>,
+}
+
+impl PwmCutoff {
+    pub fn new(hardware: Box<dyn HardwareInterface>, channels: Vec<u32>) -> Self {
+        Self { hardware, channels }
+    }
+}
+
+impl SafetyAction for PwmCutoff {
+    fn on_transition(&mut self, transition: &SafetyTransition) -> Result<(), CoreError> {
+        if transition.to != SafetyState::EmergencyStop {
+            return Ok(());
+        }
+        // Try every channel even if one fails
+        let mut result = Ok(());
+        for &channel in &self.channels {
+            if let Err(e) = self.hardware.set_pwm(channel, 0.0) {
+                result = Err(e);
+            }
+        }
+        result
+    }
+}
+
+struct Watched {
+    degrade_after: Duration,
+    stop_after: Duration,
+    last_pet: Duration,
+}
+
+struct State {
+    state: SafetyState,
+    sources: BTreeMap<String, Watched>,
+    // When something last went wrong, for the recovery delay
+    last_fault: Option<Duration>,
+}
+
+/// Heartbeat watchdog and safety state machine
+///
+/// All methods take `&self`, so one monitor can be shared through an `Arc`
+/// by the control loop, algorithms and a `Watchdog` thread. Time
+/// comes from the monitor's clock; nothing is checked until `check` runs.
+pub struct SafetyMonitor {
+    clock: Arc<dyn Clock>,
+    recovery_delay: Duration,
+    deadline_miss_state: SafetyState,
+    state: Mutex<State>,
+    // Locked before `state` whenever both are, so actions see transitions
+    // in order
+    actions: Mutex<Vec<Box<dyn SafetyAction>>>,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl SafetyMonitor {
+    /// Create an operational monitor on the system clock that degrades on
+    /// deadline misses and recovers a second after the last fault
+    pub fn new() -> Self {
+        Self {
+            clock: Arc::new(SystemClock::new()),
+            recovery_delay: Duration::from_secs(1),
+            deadline_miss_state: SafetyState::Degraded,
+            state: Mutex::new(State {
+                state: SafetyState::Operational,
+                sources: BTreeMap::new(),
+                last_fault: None,
+            }),
+            actions: Mutex::new(Vec::new()),
+        }
+    }
+    
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+    
+    /// Stay `Degraded` until `delay` has passed without a fault
+    pub fn with_recovery_delay(mut self, delay: Duration) -> Self {
+        self.recovery_delay = delay;
+        self
+    }
+    
+    /// Enter `state` on a deadline miss, such as `EmergencyStop` for hard
+    /// real-time loops
+    pub fn with_deadline_miss_state(mut self, state: SafetyState) -> Self {
+        self.deadline_miss_state = state;
+        self
+    }
+    
+    /// Expect heartbeats from `source`, degrading once it has been silent
+    /// for `degrade_after` and stopping after `stop_after`
+    ///
+    /// The source counts as petted now. Watching it again replaces its
+    /// timeouts.
+    pub fn watch(&self, source: &str, degrade_after: Duration, stop_after: Duration) -> Result<(), CoreError> {
+        if degrade_after.is_zero() || stop_after < degrade_after {
+            return Err(CoreError::InvalidParameter(format!(
+                "timeouts of '{}' must satisfy 0 < degrade_after <= stop_after, got {:?} and {:?}",
+                source, degrade_after, stop_after
+            )));
+        }
+        let last_pet = self.clock.now();
+        lock(&self.state).sources.insert(
+            source.to_string(),
+            Watched {
+                degrade_after,
+                stop_after,
+                last_pet,
+            },
+        );
+        Ok(())
+    }
+    
+    /// Stop expecting heartbeats from `source`
+    pub fn unwatch(&self, source: &str) -> bool {
+        lock(&self.state).sources.remove(source).is_some()
+    }
+    
+    /// Record a heartbeat from `source`; sources not watched are ignored
+    pub fn pet(&self, source: &str) {
+        let now = self.clock.now();
+        if let Some(watched) = lock(&self.state).sources.get_mut(source) {
+            watched.last_pet = now;
+        }
+    }
+    
+    /// Pet the sensors that were read successfully
+    pub fn observe_polls(&self, polls: &[SensorPoll]) {
+        for poll in polls.iter().filter(|poll| poll.result.is_ok()) {
+            self.pet(&poll.sensor_id);
+        }
+    }
+    
+    /// Pet the tasks that ran successfully and on time, and trip on those
+    /// that missed their deadline
+    pub fn observe_runs(&self, runs: &[ScheduledRun]) {
+        for run in runs {
+            if run.deadline_missed {
+                self.trip(
+                    self.deadline_miss_state,
+                    SafetyCause::DeadlineMissed {
+                        algorithm_id: run.algorithm_id.clone(),
+                    },
+                );
+            } else if run.result.is_ok() {
+                self.pet(&run.algorithm_id);
+            }
+        }
+    }
+    
+    /// Trip on a deadline missed by a timed execution, as reported to
+    /// `CoreEngine::on_deadline_miss`
+    pub fn report_deadline_miss(&self, miss: &DeadlineMiss) {
+        self.trip(
+            self.deadline_miss_state,
+            SafetyCause::DeadlineMissed {
+                algorithm_id: miss.algorithm_id.clone(),
+            },
+        );
+    }
+    
+    /// Stop at once
+    pub fn emergency_stop(&self, reason: &str) {
+        self.trip(SafetyState::EmergencyStop, SafetyCause::Manual(reason.to_string()));
+    }
+    
+    /// Move to `state` if that is more restricted than the current one
+    pub fn trip(&self, state: SafetyState, cause: SafetyCause) {
+        let mut actions = lock(&self.actions);
+        let transition = {
+            let mut current = lock(&self.state);
+            current.last_fault = Some(self.clock.now());
+            transition(&mut current, state, cause)
+        };
+        run_actions(&mut actions, transition);
+    }
+    
+    /// Compare every source's silence with its timeouts, update the state
+    /// and return it
+    ///
+    /// Call this regularly from the control loop, or run a `Watchdog` that
+    /// does, so a loop that hangs is still caught.
+    pub fn check(&self) -> SafetyState {
+        let mut actions = lock(&self.actions);
+        let (state, transition) = {
+            let mut current = lock(&self.state);
+            let now = self.clock.now();
+            let mut worst = (SafetyState::Operational, None);
+            for (source, watched) in &current.sources {
+                let silent_for = now.saturating_sub(watched.last_pet);
+                let level = if silent_for >= watched.stop_after {
+                    SafetyState::EmergencyStop
+                } else if silent_for >= watched.degrade_after {
+                    SafetyState::Degraded
+                } else {
+                    continue;
+                };
+                if level > worst.0 {
+                    let cause = SafetyCause::HeartbeatMissed {
+                        source: source.clone(),
+                        silent_for,
+                    };
+                    worst = (level, Some(cause));
+                }
+            }
+            let transition = match worst {
+                (level, Some(cause)) => {
+                    current.last_fault = Some(now);
+                    transition(&mut current, level, cause)
+                }
+                (_, None) if current.state == SafetyState::Degraded => {
+                    let settled = current
+                        .last_fault
+                        .is_none_or(|fault| now.saturating_sub(fault) >= self.recovery_delay);
+                    if settled {
+                        Some(set_state(&mut current, SafetyState::Operational, SafetyCause::Recovered))
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            };
+            (current.state, transition)
+        };
+        run_actions(&mut actions, transition);
+        state
+    }
+    
+    /// Clear an emergency stop, returning to `Operational`
+    ///
+    /// Fails with `CoreError::PreconditionFailed` while a source is still
+    /// past its timeout, naming it.
+    pub fn reset(&self) -> Result<(), CoreError> {
+        let mut actions = lock(&self.actions);
+        let transition = {
+            let mut current = lock(&self.state);
+            let now = self.clock.now();
+            if let Some((source, _)) = current
+                .sources
+                .iter()
+                .find(|(_, watched)| now.saturating_sub(watched.last_pet) >= watched.degrade_after)
+            {
+                return Err(CoreError::PreconditionFailed(format!(
+                    "source '{}' is still missing heartbeats",
+                    source
+                )));
+            }
+            current.last_fault = None;
+            (current.state != SafetyState::Operational)
+                .then(|| set_state(&mut current, SafetyState::Operational, SafetyCause::Reset))
+        };
+        run_actions(&mut actions, transition);
+        Ok(())
+    }
+    
+    /// Current state, as of the last check or trip
+    pub fn state(&self) -> SafetyState {
+        lock(&self.state).state
+    }
+    
+    /// How long each watched source has gone without a heartbeat
+    pub fn silences(&self) -> BTreeMap<String, Duration> {
+        let now = self.clock.now();
+        lock(&self.state)
+            .sources
+            .iter()
+            .map(|(source, watched)| (source.clone(), now.saturating_sub(watched.last_pet)))
+            .collect()
+    }
+    
+    /// Whether actuators may be driven normally
+    pub fn is_operational(&self) -> bool {
+        self.state() == SafetyState::Operational
+    }
+    
+    /// Run `action` on every later change of state
+    pub fn add_action(&self, action: Box<dyn SafetyAction>) {
+        lock(&self.actions).push(action);
+    }
+}
+
+impl Default for SafetyMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Escalate to `state`, never relaxing a more restricted one
+fn transition(current: &mut State, state: SafetyState, cause: SafetyCause) -> Option<SafetyTransition> {
+    (state > current.state).then(|| set_state(current, state, cause))
+}
+
+fn set_state(current: &mut State, state: SafetyState, cause: SafetyCause) -> SafetyTransition {
+    let transition = SafetyTransition {
+        from: current.state,
+        to: state,
+        cause,
+    };
+    current.state = state;
+    match state {
+        SafetyState::Operational => log::info!("Safety state {:?} -> {:?}", transition.from, state),
+        _ => log::warn!("Safety state {:?} -> {:?}: {:?}", transition.from, state, transition.cause),
+    }
+    transition
+}
+
+fn run_actions(actions: &mut [Box<dyn SafetyAction>], transition: Option<SafetyTransition>) {
+    let Some(transition) = transition else {
+        return;
+    };
+    for action in actions {
+        if let Err(e) = action.on_transition(&transition) {
+            log::error!("Safety action failed on {:?} -> {:?}: {}", transition.from, transition.to, e);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use watchdog::Watchdog;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod watchdog {
+    use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+    use std::sync::Arc;
+    use std::thread::{self, JoinHandle};
+    use std::time::Duration;
+    
+    use super::SafetyMonitor;
+    use crate::error::CoreError;
+    
+    /// A thread checking a `SafetyMonitor` every period of wall-clock time,
+    /// stopped when dropped
+    pub struct Watchdog {
+        stop: Option<Sender<()>>,
+        thread: Option<JoinHandle<()>>,
+    }
+    
+    impl Watchdog {
+        pub fn spawn(monitor: Arc<SafetyMonitor>, period: Duration) -> Result<Self, CoreError> {
+            if period.is_zero() {
+                return Err(CoreError::InvalidParameter("watchdog period must be positive".to_string()));
+            }
+            let (stop, stopped) = mpsc::channel::<()>();
+            let thread = thread::Builder::new().name("safety-watchdog".to_string()).spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(period) {
+                    monitor.check();
+                }
+            })?;
+            Ok(Self {
+                stop: Some(stop),
+                thread: Some(thread),
+            })
+        }
+    }
+    
+    impl Drop for Watchdog {
+        fn drop(&mut self) {
+            drop(self.stop.take());
+            if let Some(thread) = self.thread.take() {
+                if thread.join().is_err() {
+                    log::error!("Safety watchdog thread panicked");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::hardware::MockHardware;
+    
+    fn monitor() -> (Arc<MockClock>, SafetyMonitor, Arc<Mutex<Vec<SafetyTransition>>>) {
+        let clock = Arc::new(MockClock::new());
+        let monitor = SafetyMonitor::new()
+            .with_clock(clock.clone())
+            .with_recovery_delay(Duration::from_millis(100));
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        monitor.add_action(Box::new(move |transition: &SafetyTransition| {
+            log.lock().unwrap().push(transition.clone());
+            Ok(())
+        }));
+        (clock, monitor, seen)
+    }
+    
+    #[test]
+    fn test_silent_source_degrades_then_stops_until_reset() {
+        let (clock, monitor, seen) = monitor();
+        let hardware = MockHardware::new("motors");
+        let mut motors = hardware.clone();
+        motors.set_pwm(0, 0.8).unwrap();
+        monitor.add_action(Box::new(PwmCutoff::new(Box::new(hardware.clone()), vec![0])));
+        assert!(monitor.watch("imu", Duration::ZERO, Duration::ZERO).is_err());
+        monitor
+            .watch("imu", Duration::from_millis(20), Duration::from_millis(50))
+            .unwrap();
+        
+        clock.advance(Duration::from_millis(15));
+        monitor.pet("imu");
+        monitor.pet("unwatched");
+        clock.advance(Duration::from_millis(15));
+        assert_eq!(monitor.check(), SafetyState::Operational);
+        clock.advance(Duration::from_millis(10));
+        assert_eq!(monitor.check(), SafetyState::Degraded);
+        
+        // Petting again recovers only once the delay has passed
+        monitor.pet("imu");
+        assert_eq!(monitor.check(), SafetyState::Degraded);
+        clock.advance(Duration::from_millis(100));
+        monitor.pet("imu");
+        assert_eq!(monitor.check(), SafetyState::Operational);
+        
+        clock.advance(Duration::from_millis(60));
+        assert_eq!(monitor.check(), SafetyState::EmergencyStop);
+        assert_eq!(hardware.duty(0), Some(0.0));
+        assert!(matches!(monitor.reset(), Err(CoreError::PreconditionFailed(message)) if message.contains("imu")));
+        monitor.pet("imu");
+        assert_eq!(monitor.check(), SafetyState::EmergencyStop);
+        monitor.reset().unwrap();
+        assert!(monitor.is_operational());
+        
+        let path: Vec<(SafetyState, SafetyState)> = seen.lock().unwrap().iter().map(|t| (t.from, t.to)).collect();
+        use SafetyState::*;
+        let expected = [(Operational, Degraded), (Degraded, Operational), (Operational, EmergencyStop)];
+        assert_eq!(path[..3], expected);
+        assert_eq!(path[3], (EmergencyStop, Operational));
+        assert_eq!(
+            seen.lock().unwrap()[2].cause,
+            SafetyCause::HeartbeatMissed {
+                source: "imu".to_string(),
+                silent_for: Duration::from_millis(60)
+            }
+        );
+    }
+    
+    #[test]
+    fn test_deadline_misses_and_manual_stop() {
+        let (_clock, monitor, seen) = monitor();
+        let monitor = monitor.with_deadline_miss_state(SafetyState::EmergencyStop);
+        monitor.watch("control", Duration::from_millis(5), Duration::from_millis(10)).unwrap();
+        let run = |deadline_missed| ScheduledRun {
+            algorithm_id: "control".to_string(),
+            result: Ok(Vec::new()),
+            jitter: Duration::ZERO,
+            duration: Duration::ZERO,
+            deadline_missed,
+        };
+        monitor.observe_runs(&[run(false)]);
+        assert!(monitor.is_operational());
+        monitor.observe_runs(&[run(true)]);
+        assert_eq!(monitor.state(), SafetyState::EmergencyStop);
+        // Latched: a manual stop on top changes nothing
+        monitor.emergency_stop("bumper");
+        assert_eq!(seen.lock().unwrap().len(), 1);
+        assert_eq!(
+            seen.lock().unwrap()[0].cause,
+            SafetyCause::DeadlineMissed {
+                algorithm_id: "control".to_string()
+            }
+        );
+        
+        monitor.reset().unwrap();
+        monitor.emergency_stop("bumper");
+        assert_eq!(seen.lock().unwrap()[2].cause, SafetyCause::Manual("bumper".to_string()));
+    }
+    
+    #[test]
+    fn test_watchdog_thread_checks_in_background() {
+        let clock = Arc::new(MockClock::new());
+        let monitor = Arc::new(SafetyMonitor::new().with_clock(clock.clone()));
+        monitor.watch("loop", Duration::from_millis(1), Duration::from_millis(2)).unwrap();
+        let watchdog = Watchdog::spawn(monitor.clone(), Duration::from_millis(1)).unwrap();
+        clock.advance(Duration::from_millis(5));
+        for _ in 0..1000 {
+            if monitor.state() == SafetyState::EmergencyStop {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        drop(watchdog);
+        assert_eq!(monitor.state(), SafetyState::EmergencyStop);
+    }
+}
@@ -0,0 +1,583 @@
+//! Cooperative scheduling of periodic algorithms
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::batch::CancellationToken;
+use crate::error::CoreError;
+use crate::CoreEngine;
+
+/// Outcome of one algorithm run triggered by `Scheduler::tick`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScheduledRun {
+    /// ID of the algorithm that ran
+    pub algorithm_id: String,
+    /// What the algorithm returned for its (empty) input
+    pub result: Result<Vec<u8>, CoreError>,
+    /// How long after its due time the run started
+    pub jitter: Duration,
+    /// How long the run took, by the engine's clock
+    pub duration: Duration,
+    /// Whether the run finished later than its deadline after its due time
+    pub deadline_missed: bool,
+}
+
+/// A periodic algorithm run by a `Scheduler`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Task {
+    algorithm_id: String,
+    period: Duration,
+    priority: u8,
+    deadline: Option<Duration>,
+}
+
+impl Task {
+    /// Run `algorithm_id` every `period` at priority 0, with a deadline of
+    /// one period
+    pub fn new(algorithm_id: &str, period: Duration) -> Self {
+        Self {
+            algorithm_id: algorithm_id.to_string(),
+            period,
+            priority: 0,
+            deadline: None,
+        }
+    }
+    
+    /// Run before lower-priority tasks due on the same tick
+    pub fn with_priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+    
+    /// Count a run as a deadline miss if it finishes more than `deadline`
+    /// after its due time
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+}
+
+/// Timing of one task's runs, accumulated since it was added
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskStats {
+    pub runs: u64,
+    /// Runs that finished past their deadline
+    pub deadline_misses: u64,
+    /// Sum of every run's `ScheduledRun::jitter`
+    pub total_jitter: Duration,
+    pub max_jitter: Duration,
+    pub max_duration: Duration,
+}
+
+impl TaskStats {
+    /// Average delay between a run's due time and its start
+    pub fn mean_jitter(&self) -> Duration {
+        match u32::try_from(self.runs) {
+            Ok(0) => Duration::ZERO,
+            Ok(runs) => self.total_jitter / runs,
+            Err(_) => Duration::from_secs_f64(self.total_jitter.as_secs_f64() / self.runs as f64),
+        }
+    }
+    
+    pub(crate) fn record(&mut self, run: &ScheduledRun) {
+        self.runs = self.runs.saturating_add(1);
+        self.deadline_misses += u64::from(run.deadline_missed);
+        self.total_jitter = self.total_jitter.saturating_add(run.jitter);
+        self.max_jitter = self.max_jitter.max(run.jitter);
+        self.max_duration = self.max_duration.max(run.duration);
+    }
+}
+
+/// Backpressure counters of a `Scheduler`, accumulated since creation
+///
+/// A scheduler is ticked through `&mut self`, so a snapshot from
+/// `Scheduler::stats` always reflects whole ticks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SchedulerStats {
+    /// Runs that fell due, whether they ran or were skipped
+    pub submitted: u64,
+    /// Runs skipped because their entry fell more than a period behind
+    pub rejected: u64,
+    /// Total time runs waited past their due time before starting
+    pub blocked_total_time: Duration,
+    /// Most entries due at once at the start of a tick
+    pub max_queue_depth: usize,
+}
+
+struct Entry {
+    task: Task,
+    // `None` until the first run, which is due immediately
+    next_due: Option<Duration>,
+    stats: TaskStats,
+}
+
+/// Runs algorithms at fixed periods on the caller's thread
+///
+/// Nothing runs in the background: each `tick` runs the entries that are
+/// due at the given time, with empty input, against the engine's memory.
+/// When more entries are due than a tick may run, the scan resumes after the
+/// last entry that ran, so everything due is served round-robin and an
+/// overdue entry runs within as many ticks as there are entries. An entry
+/// that falls more than a period behind skips the missed runs instead of
+/// running them in a burst. Among the entries due on a tick, higher
+/// priorities run first.
+pub struct Scheduler {
+    entries: Vec<Entry>,
+    max_runs_per_tick: usize,
+    cursor: usize,
+    paused: bool,
+    stats: SchedulerStats,
+}
+
+impl Scheduler {
+    /// Create a scheduler that runs everything due on each tick
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            max_runs_per_tick: usize::MAX,
+            cursor: 0,
+            paused: false,
+            stats: SchedulerStats::default(),
+        }
+    }
+    
+    /// Run at most `max` algorithms per tick, time-slicing the rest
+    pub fn max_runs_per_tick(mut self, max: usize) -> Self {
+        self.max_runs_per_tick = max.max(1);
+        self
+    }
+    
+    /// Run `algorithm_id` every `period`, starting on the next tick
+    pub fn add(&mut self, algorithm_id: &str, period: Duration) -> Result<(), CoreError> {
+        self.add_task(Task::new(algorithm_id, period))
+    }
+    
+    /// Run a task, starting on the next tick
+    pub fn add_task(&mut self, task: Task) -> Result<(), CoreError> {
+        if task.period.is_zero() {
+            return Err(CoreError::InvalidParameter(format!(
+                "period of '{}' must be positive",
+                task.algorithm_id
+            )));
+        }
+        self.entries.push(Entry {
+            task,
+            next_due: None,
+            stats: TaskStats::default(),
+        });
+        Ok(())
+    }
+    
+    /// Timing of every task, in the order they were added
+    pub fn task_stats(&self) -> Vec<(String, TaskStats)> {
+        self.entries
+            .iter()
+            .map(|entry| (entry.task.algorithm_id.clone(), entry.stats))
+            .collect()
+    }
+    
+    /// Stop running algorithms until `resume`
+    ///
+    /// Ticks while paused run nothing and change no state. Entries that fall
+    /// due meanwhile run once each on the first tick after resuming, like
+    /// any other overdue entry, continuing the round-robin where it stopped.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+    
+    /// Let ticks run due algorithms again
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+    
+    /// Whether the scheduler is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+    
+    /// Backpressure so far
+    ///
+    /// A growing `blocked_total_time` or a `max_queue_depth` above
+    /// `max_runs_per_tick` means due work waits for a slot; `rejected` runs
+    /// mean entries fell whole periods behind.
+    pub fn stats(&self) -> SchedulerStats {
+        self.stats
+    }
+    
+    /// Run the algorithms due at `now`, reporting each run in order
+    ///
+    /// Returns nothing while paused.
+    pub fn tick(&mut self, engine: &mut CoreEngine, now: Duration) -> Vec<ScheduledRun> {
+        if self.paused {
+            return Vec::new();
+        }
+        let count = self.entries.len();
+        let depth = self.entries.iter().filter(|entry| entry.next_due.is_none_or(|due| due <= now)).count();
+        let mut span = engine.telemetry().span("scheduler_tick").with("due", depth);
+        self.stats.max_queue_depth = self.stats.max_queue_depth.max(depth);
+        // Due entries in round-robin order from the cursor, then by priority
+        let mut due_entries: Vec<usize> = (0..count)
+            .map(|offset| (self.cursor + offset) % count)
+            .filter(|&index| self.entries[index].next_due.is_none_or(|due| due <= now))
+            .collect();
+        due_entries.sort_by_key(|&index| std::cmp::Reverse(self.entries[index].task.priority));
+        due_entries.truncate(self.max_runs_per_tick);
+        
+        let clock = engine.clock().clone();
+        let tick_start = clock.now();
+        let mut runs = Vec::new();
+        let mut last_run = None;
+        for index in due_entries {
+            let entry = &mut self.entries[index];
+            let due = entry.next_due.unwrap_or(now);
+            let late = now - due;
+            let missed = u64::try_from(late.as_nanos() / entry.task.period.as_nanos()).unwrap_or(u64::MAX);
+            self.stats.submitted = self.stats.submitted.saturating_add(missed.saturating_add(1));
+            self.stats.rejected = self.stats.rejected.saturating_add(missed);
+            self.stats.blocked_total_time = self.stats.blocked_total_time.saturating_add(late);
+            let next_due = due.saturating_add(entry.task.period);
+            entry.next_due = Some(if next_due > now {
+                next_due
+            } else {
+                now.saturating_add(entry.task.period)
+            });
+            
+            // Runs earlier in the tick delay this one's start
+            let start = clock.now();
+            let jitter = late.saturating_add(start.saturating_sub(tick_start));
+            let result = engine.execute_algorithm(&entry.task.algorithm_id, &[]);
+            let duration = clock.now().saturating_sub(start);
+            let deadline_missed = jitter.saturating_add(duration) > entry.task.deadline.unwrap_or(entry.task.period);
+            
+            let run = ScheduledRun {
+                algorithm_id: entry.task.algorithm_id.clone(),
+                result,
+                jitter,
+                duration,
+                deadline_missed,
+            };
+            entry.stats.record(&run);
+            engine.telemetry().record_scheduled_run(&run);
+            runs.push(run);
+            last_run = Some(index);
+        }
+        if let Some(index) = last_run {
+            self.cursor = (index + 1) % count;
+        }
+        span.record("runs", runs.len());
+        runs
+    }
+}
+
+impl Scheduler {
+    /// Drive the scheduler from the engine's clock until `stop` is
+    /// cancelled, sleeping between due times and passing every run to
+    /// `on_run`
+    ///
+    /// Runs on the calling thread, which a control loop would dedicate to
+    /// the scheduler and, on Linux, raise with `set_realtime_priority`.
+    /// Returns at once if there are no tasks. While paused it wakes once
+    /// per shortest period to check for `resume` and `stop`.
+    pub fn run_until(&mut self, engine: &mut CoreEngine, stop: &CancellationToken, mut on_run: impl FnMut(&ScheduledRun)) {
+        let clock = engine.clock().clone();
+        let Some(shortest) = self.entries.iter().map(|entry| entry.task.period).min() else {
+            return;
+        };
+        while !stop.is_cancelled() {
+            let now = clock.now();
+            for run in self.tick(engine, now) {
+                on_run(&run);
+            }
+            let wake = if self.paused {
+                now.saturating_add(shortest)
+            } else {
+                self.entries
+                    .iter()
+                    .map(|entry| entry.next_due.unwrap_or(now))
+                    .min()
+                    .unwrap_or(now)
+            };
+            let wait = wake.saturating_sub(clock.now());
+            if !wait.is_zero() && !stop.is_cancelled() {
+                clock.sleep(wait);
+            }
+        }
+    }
+}
+
+/// Switch the calling thread to the `SCHED_FIFO` real-time policy at
+/// `priority`, from 1 to 99
+///
+/// A real-time thread preempts every normal one, so jitter no longer
+/// depends on what else the system runs. The process needs `CAP_SYS_NICE`
+/// or an `RLIMIT_RTPRIO` of at least `priority`, or this fails with
+/// `CoreError::PermissionDenied`.
+#[cfg(all(feature = "realtime", target_os = "linux"))]
+pub fn set_realtime_priority(priority: i32) -> Result<(), CoreError> {
+    // SAFETY: both calls only read their integer argument.
+    let (min, max) = unsafe { (libc::sched_get_priority_min(libc::SCHED_FIFO), libc::sched_get_priority_max(libc::SCHED_FIFO)) };
+    if !(min..=max).contains(&priority) {
+        return Err(CoreError::InvalidParameter(format!(
+            "SCHED_FIFO priority must be within {}..={}, got {}",
+            min, max, priority
+        )));
+    }
+    let param = libc::sched_param { sched_priority: priority };
+    // SAFETY: `pthread_self` is always a valid handle for the calling
+    // thread, and `param` is a valid `sched_param` for the whole call.
+    match unsafe { libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_FIFO, &param) } {
+        0 => Ok(()),
+        libc::EPERM => Err(CoreError::PermissionDenied(format!(
+            "SCHED_FIFO priority {} needs CAP_SYS_NICE or a higher RLIMIT_RTPRIO",
+            priority
+        ))),
+        code => Err(std::io::Error::from_raw_os_error(code).into()),
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{Algorithm, AlgorithmMetadata};
+    use crate::clock::{Clock, MockClock};
+    use crate::memory::MemoryManager;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    
+    struct Noop(&'static str);
+    
+    impl Algorithm for Noop {
+        fn process(&self, _input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            Ok(Vec::new())
+        }
+        
+        fn id(&self) -> &str {
+            self.0
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata::default()
+        }
+    }
+    
+    fn engine(ids: &[&'static str]) -> CoreEngine {
+        let mut engine = CoreEngine::new();
+        for id in ids {
+            engine.register(Box::new(Noop(id))).unwrap();
+        }
+        engine
+    }
+    
+    /// Tick every `step` for `ticks` ticks, counting runs per algorithm
+    fn run(scheduler: &mut Scheduler, engine: &mut CoreEngine, step: Duration, ticks: usize) -> HashMap<String, usize> {
+        let clock = MockClock::new();
+        let mut counts = HashMap::new();
+        for _ in 0..ticks {
+            for run in scheduler.tick(engine, clock.now()) {
+                assert!(run.result.is_ok());
+                *counts.entry(run.algorithm_id).or_insert(0) += 1;
+            }
+            clock.advance(step);
+        }
+        counts
+    }
+    
+    #[test]
+    fn test_algorithms_run_at_their_periods() {
+        let mut engine = engine(&["fast", "slow"]);
+        let mut scheduler = Scheduler::new().max_runs_per_tick(1);
+        scheduler.add("fast", Duration::from_millis(10)).unwrap();
+        scheduler.add("slow", Duration::from_millis(25)).unwrap();
+        
+        let counts = run(&mut scheduler, &mut engine, Duration::from_millis(5), 100);
+        assert_eq!(counts["fast"], 50);
+        assert_eq!(counts["slow"], 20);
+    }
+    
+    #[test]
+    fn test_overdue_algorithm_runs_under_contention() {
+        let mut engine = engine(&["hog_a", "hog_b", "rare"]);
+        let mut scheduler = Scheduler::new().max_runs_per_tick(1);
+        scheduler.add("hog_a", Duration::from_millis(1)).unwrap();
+        scheduler.add("hog_b", Duration::from_millis(1)).unwrap();
+        scheduler.add("rare", Duration::from_millis(30)).unwrap();
+        
+        let counts = run(&mut scheduler, &mut engine, Duration::from_millis(1), 90);
+        assert_eq!(counts["hog_a"] + counts["hog_b"] + counts["rare"], 90);
+        assert!(counts["rare"] >= 3, "rare ran {} times", counts["rare"]);
+        assert!(counts["hog_a"].abs_diff(counts["hog_b"]) <= 1);
+    }
+    
+    #[test]
+    fn test_paused_ticks_run_nothing_and_resume_catches_up_once() {
+        let mut engine = engine(&["fast", "slow"]);
+        let mut scheduler = Scheduler::new();
+        scheduler.add("fast", Duration::from_millis(10)).unwrap();
+        scheduler.add("slow", Duration::from_millis(25)).unwrap();
+        assert_eq!(scheduler.tick(&mut engine, Duration::ZERO).len(), 2);
+        
+        scheduler.pause();
+        assert!(scheduler.is_paused());
+        for ms in (10..100).step_by(10) {
+            assert!(scheduler.tick(&mut engine, Duration::from_millis(ms)).is_empty());
+        }
+        
+        scheduler.resume();
+        assert!(!scheduler.is_paused());
+        let ids = |runs: Vec<ScheduledRun>| runs.into_iter().map(|run| run.algorithm_id).collect::<Vec<_>>();
+        assert_eq!(ids(scheduler.tick(&mut engine, Duration::from_millis(100))), vec!["fast", "slow"]);
+        assert!(scheduler.tick(&mut engine, Duration::from_millis(105)).is_empty());
+        assert_eq!(ids(scheduler.tick(&mut engine, Duration::from_millis(110))), vec!["fast"]);
+        assert_eq!(ids(scheduler.tick(&mut engine, Duration::from_millis(125))), vec!["slow", "fast"]);
+    }
+    
+    #[test]
+    fn test_tick_reports_failures_and_rejects_zero_period() {
+        let mut engine = CoreEngine::new();
+        let mut scheduler = Scheduler::new();
+        assert!(scheduler.add("missing", Duration::ZERO).is_err());
+        scheduler.add("missing", Duration::from_millis(1)).unwrap();
+        
+        let runs = scheduler.tick(&mut engine, Duration::ZERO);
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].result, Err(CoreError::AlgorithmNotFound("missing".to_string())));
+        assert!(scheduler.tick(&mut engine, Duration::from_micros(500)).is_empty());
+    }
+    
+    #[test]
+    fn test_stats_reflect_overload() {
+        let mut engine = engine(&["a", "b", "c"]);
+        let mut scheduler = Scheduler::new().max_runs_per_tick(1);
+        for id in ["a", "b", "c"] {
+            scheduler.add(id, Duration::from_millis(10)).unwrap();
+        }
+        let ms = Duration::from_millis;
+        
+        // All three are due at once but only one runs per tick
+        assert_eq!(scheduler.tick(&mut engine, ms(0))[0].algorithm_id, "a");
+        assert_eq!(scheduler.tick(&mut engine, ms(5))[0].algorithm_id, "b");
+        assert_eq!(scheduler.tick(&mut engine, ms(30))[0].algorithm_id, "c");
+        // "a" was due at 10: it starts 21 ms late, two periods behind
+        assert_eq!(scheduler.tick(&mut engine, ms(31))[0].algorithm_id, "a");
+        
+        assert_eq!(
+            scheduler.stats(),
+            SchedulerStats {
+                submitted: 6,
+                rejected: 2,
+                blocked_total_time: ms(21),
+                max_queue_depth: 3,
+            }
+        );
+    }
+    
+    #[test]
+    fn test_stats_stay_quiet_without_overload() {
+        let mut engine = engine(&["fast", "slow"]);
+        let mut scheduler = Scheduler::new();
+        scheduler.add("fast", Duration::from_millis(10)).unwrap();
+        scheduler.add("slow", Duration::from_millis(25)).unwrap();
+        run(&mut scheduler, &mut engine, Duration::from_millis(5), 100);
+        
+        let stats = scheduler.stats();
+        assert_eq!((stats.submitted, stats.rejected), (70, 0));
+        assert_eq!(stats.blocked_total_time, Duration::ZERO);
+        assert_eq!(stats.max_queue_depth, 2);
+    }
+    
+    /// Advances a mock clock by a fixed cost per run
+    struct Busy(&'static str, Arc<MockClock>, Duration);
+    
+    impl Algorithm for Busy {
+        fn process(&self, _input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            self.1.advance(self.2);
+            Ok(Vec::new())
+        }
+        
+        fn id(&self) -> &str {
+            self.0
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata::default()
+        }
+    }
+    
+    #[test]
+    fn test_priority_jitter_and_deadline_misses() {
+        let clock = Arc::new(MockClock::new());
+        let mut engine = CoreEngine::builder().clock(clock.clone()).build();
+        engine.register(Box::new(Busy("planner", clock.clone(), Duration::from_micros(600)))).unwrap();
+        engine.register(Box::new(Busy("motor", clock.clone(), Duration::from_micros(100)))).unwrap();
+        let mut scheduler = Scheduler::new();
+        scheduler.add_task(Task::new("planner", Duration::from_millis(10))).unwrap();
+        scheduler
+            .add_task(Task::new("motor", Duration::from_millis(1)).with_priority(9).with_deadline(Duration::from_micros(500)))
+            .unwrap();
+        
+        // The motor task outranks the planner added before it
+        let runs = scheduler.tick(&mut engine, clock.now());
+        let ids: Vec<&str> = runs.iter().map(|run| run.algorithm_id.as_str()).collect();
+        assert_eq!(ids, vec!["motor", "planner"]);
+        assert_eq!((runs[0].jitter, runs[0].duration), (Duration::ZERO, Duration::from_micros(100)));
+        // The planner started after the motor's run, within its own deadline
+        assert_eq!(runs[1].jitter, Duration::from_micros(100));
+        assert!(!runs[1].deadline_missed);
+        
+        // The first tick took 700us, so this one is 450us late and leaves
+        // the motor 50us for a 100us run
+        clock.advance(Duration::from_micros(750));
+        let runs = scheduler.tick(&mut engine, clock.now());
+        assert!(runs[0].deadline_missed);
+        let stats = scheduler.task_stats();
+        assert_eq!(stats[1].0, "motor");
+        assert_eq!((stats[1].1.runs, stats[1].1.deadline_misses), (2, 1));
+        assert_eq!(stats[1].1.max_jitter, Duration::from_micros(450));
+        assert_eq!(stats[1].1.mean_jitter(), Duration::from_micros(225));
+    }
+    
+    #[test]
+    fn test_run_until_sleeps_between_due_times() {
+        let clock = Arc::new(MockClock::new());
+        let mut engine = CoreEngine::builder().clock(clock.clone()).build();
+        let mut scheduler = Scheduler::new();
+        scheduler.add("passthrough", Duration::from_millis(10)).unwrap();
+        
+        let stop = CancellationToken::new();
+        let mut starts = Vec::new();
+        scheduler.run_until(&mut engine, &stop, |run| {
+            assert!(run.result.is_ok() && run.jitter.is_zero());
+            starts.push(clock.now());
+            if starts.len() == 3 {
+                stop.cancel();
+            }
+        });
+        assert_eq!(starts, vec![Duration::ZERO, Duration::from_millis(10), Duration::from_millis(20)]);
+        
+        // Without tasks there is nothing to wait for
+        Scheduler::new().run_until(&mut engine, &CancellationToken::new(), |_| unreachable!());
+    }
+    
+    #[cfg(all(feature = "realtime", target_os = "linux"))]
+    #[test]
+    fn test_realtime_priority_out_of_range_rejected() {
+        assert!(matches!(set_realtime_priority(0), Err(CoreError::InvalidParameter(_))));
+        assert!(matches!(set_realtime_priority(1000), Err(CoreError::InvalidParameter(_))));
+    }
+    
+    #[test]
+    fn test_huge_period_saturates_instead_of_overflowing() {
+        let mut engine = CoreEngine::new();
+        let mut scheduler = Scheduler::new();
+        scheduler.add("passthrough", Duration::MAX).unwrap();
+        
+        assert_eq!(scheduler.tick(&mut engine, Duration::from_secs(1)).len(), 1);
+        assert!(scheduler.tick(&mut engine, Duration::from_secs(2)).is_empty());
+    }
+}
@@ -0,0 +1,308 @@
+//! Camera capture
+//!
+//! A `CameraSensor` reads frames from a `CameraBackend`: `SimulatedCamera`
+//! for tests, or `V4l2Camera` for Video4Linux devices with the `v4l2`
+//! feature on Linux. Frames hold their pixels behind an `Arc`, so passing
+//! them between threads or stages never copies the image, and
+//! `CameraFrame::into_region` moves a frame's bytes into a memory region
+//! for algorithms to read.
+
+use std::sync::Arc;
+
+use crate::error::CoreError;
+use crate::memory::{MemoryManager, RegionRef};
+use crate::sensor::{Sensor, SensorFrame, SensorMetadata};
+
+mod simulated;
+#[cfg(all(feature = "v4l2", target_os = "linux"))]
+mod v4l2;
+
+pub use simulated::SimulatedCamera;
+#[cfg(all(feature = "v4l2", target_os = "linux"))]
+pub use v4l2::V4l2Camera;
+
+/// Encoding of a frame's pixels
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// Packed 4:2:2 YUV, two bytes per pixel: `Y0 U Y1 V` for each pair
+    #[default]
+    Yuyv,
+    /// Each frame a complete JPEG image
+    Mjpeg,
+}
+
+impl PixelFormat {
+    /// Video4Linux code of the format
+    pub fn fourcc(&self) -> u32 {
+        let code = match self {
+            PixelFormat::Yuyv => *b"YUYV",
+            PixelFormat::Mjpeg => *b"MJPG",
+        };
+        u32::from_le_bytes(code)
+    }
+    
+    /// Bytes in a `width` by `height` frame, or `None` for compressed
+    /// formats whose frames vary in size
+    pub fn frame_len(&self, width: u32, height: u32) -> Option<usize> {
+        match self {
+            PixelFormat::Yuyv => Some(width as usize * height as usize * 2),
+            PixelFormat::Mjpeg => None,
+        }
+    }
+}
+
+/// Resolution, rate and format to capture at
+///
+/// Devices may not support the exact values asked for; a backend reports
+/// what it settled on through `CameraBackend::config`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraConfig {
+    pub width: u32,
+    pub height: u32,
+    /// Frames per second
+    pub fps: f64,
+    pub format: PixelFormat,
+}
+
+impl Default for CameraConfig {
+    /// 640x480 YUYV at 30 frames per second, which most USB cameras support
+    fn default() -> Self {
+        Self {
+            width: 640,
+            height: 480,
+            fps: 30.0,
+            format: PixelFormat::Yuyv,
+        }
+    }
+}
+
+impl CameraConfig {
+    pub fn new(width: u32, height: u32, fps: f64, format: PixelFormat) -> Self {
+        Self {
+            width,
+            height,
+            fps,
+            format,
+        }
+    }
+    
+    /// Fails with `CoreError::InvalidParameter` unless the size and rate are
+    /// positive and YUYV frames have an even width
+    pub fn validate(&self) -> Result<(), CoreError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(CoreError::InvalidParameter(format!(
+                "camera resolution must be non-zero, got {}x{}",
+                self.width, self.height
+            )));
+        }
+        if !self.fps.is_finite() || self.fps <= 0.0 {
+            return Err(CoreError::InvalidParameter(format!("camera fps must be positive, got {}", self.fps)));
+        }
+        if self.format == PixelFormat::Yuyv && !self.width.is_multiple_of(2) {
+            return Err(CoreError::InvalidParameter(format!(
+                "YUYV frames need an even width, got {}",
+                self.width
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// One captured image
+///
+/// Clones share the pixel data.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraFrame {
+    /// Position of the frame in the capture, counting from 0
+    pub sequence: u64,
+    /// Capture time in microseconds
+    pub timestamp: u64,
+    pub width: u32,
+    pub height: u32,
+    pub format: PixelFormat,
+    data: Arc<Vec<u8>>,
+}
+
+impl CameraFrame {
+    /// Wrap `data` encoded as `format`
+    ///
+    /// Fails with `CoreError::InvalidInput` if an uncompressed frame is not
+    /// exactly `width * height` pixels long.
+    pub fn new(
+        sequence: u64,
+        timestamp: u64,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        data: Vec<u8>,
+    ) -> Result<Self, CoreError> {
+        if let Some(expected) = format.frame_len(width, height) {
+            if data.len() != expected {
+                return Err(CoreError::InvalidInput(format!(
+                    "{:?} frame of {}x{} needs {} bytes, got {}",
+                    format,
+                    width,
+                    height,
+                    expected,
+                    data.len()
+                )));
+            }
+        }
+        Ok(Self {
+            sequence,
+            timestamp,
+            width,
+            height,
+            format,
+            data: Arc::new(data),
+        })
+    }
+    
+    /// The encoded pixels
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+    
+    /// Number of frames and clones sharing these pixels
+    pub fn share_count(&self) -> usize {
+        Arc::strong_count(&self.data)
+    }
+    
+    /// The encoded pixels, copied only if a clone still shares them
+    pub fn into_bytes(self) -> Vec<u8> {
+        Arc::try_unwrap(self.data).unwrap_or_else(|shared| shared.as_ref().clone())
+    }
+    
+    /// Brightness of each pixel, row by row, taken from the Y samples of a
+    /// YUYV frame
+    ///
+    /// Fails with `CoreError::InvalidInput` for compressed frames, which
+    /// need decoding first.
+    pub fn luma(&self) -> Result<Vec<u8>, CoreError> {
+        match self.format {
+            PixelFormat::Yuyv => Ok(self.data.iter().step_by(2).copied().collect()),
+            PixelFormat::Mjpeg => Err(CoreError::InvalidInput("MJPEG frames must be decoded for luma".to_string())),
+        }
+    }
+    
+    /// Store the pixels in region `key` of `memory`, returning a reference
+    /// that keeps the region allocated
+    ///
+    /// The bytes move into the region without copying unless a clone of
+    /// the frame still shares them.
+    pub fn into_region(self, memory: &mut MemoryManager, key: &str) -> Result<RegionRef, CoreError> {
+        memory.adopt(key, self.into_bytes(), 1)?;
+        memory.acquire(key)
+    }
+}
+
+/// A source of camera frames
+pub trait CameraBackend: Send {
+    /// The settings the device is capturing with
+    fn config(&self) -> CameraConfig;
+    
+    /// Wait for the next frame
+    fn capture(&mut self) -> Result<CameraFrame, CoreError>;
+}
+
+/// A camera as a `Sensor`, each frame's payload being its encoded pixels
+pub struct CameraSensor {
+    id: String,
+    backend: Box<dyn CameraBackend>,
+}
+
+impl CameraSensor {
+    pub fn new(id: &str, backend: Box<dyn CameraBackend>) -> Self {
+        Self {
+            id: id.to_string(),
+            backend,
+        }
+    }
+    
+    pub fn config(&self) -> CameraConfig {
+        self.backend.config()
+    }
+    
+    /// Wait for the next frame
+    pub fn capture(&mut self) -> Result<CameraFrame, CoreError> {
+        self.backend.capture()
+    }
+    
+    /// Capture the next frame straight into region `key` of `memory`,
+    /// without copying its pixels
+    ///
+    /// Returns the frame's timestamp and a reference keeping the region
+    /// allocated.
+    pub fn capture_into(&mut self, memory: &mut MemoryManager, key: &str) -> Result<(u64, RegionRef), CoreError> {
+        let frame = self.backend.capture()?;
+        let timestamp = frame.timestamp;
+        Ok((timestamp, frame.into_region(memory, key)?))
+    }
+}
+
+impl Sensor for CameraSensor {
+    fn id(&self) -> &str {
+        &self.id
+    }
+    
+    fn read_frame(&mut self) -> Result<SensorFrame, CoreError> {
+        let frame = self.backend.capture()?;
+        Ok(SensorFrame {
+            sensor_id: self.id.clone(),
+            timestamp: frame.timestamp,
+            payload: frame.into_bytes(),
+            ..Default::default()
+        })
+    }
+    
+    fn sample_rate(&self) -> Option<f64> {
+        Some(self.backend.config().fps)
+    }
+    
+    fn metadata(&self) -> SensorMetadata {
+        let config = self.backend.config();
+        SensorMetadata {
+            name: self.id.clone(),
+            description: format!("{}x{} {:?} camera frames", config.width, config.height, config.format),
+            unit: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_frames_move_into_memory_without_copying() {
+        let config = CameraConfig::new(4, 2, 10.0, PixelFormat::Yuyv);
+        let mut camera = CameraSensor::new("front", Box::new(SimulatedCamera::new(config).unwrap()));
+        let mut memory = MemoryManager::new();
+        
+        let frame = camera.capture().unwrap();
+        let pixels = frame.data().as_ptr();
+        let region = frame.into_region(&mut memory, "image").unwrap();
+        assert_eq!(memory.read("image").unwrap().as_ptr(), pixels);
+        assert_eq!(memory.ref_count(region.key()), 1);
+        assert!(memory.deallocate("image").is_err(), "the region is referenced");
+        drop(region);
+        memory.deallocate("image").unwrap();
+        
+        let (timestamp, _region) = camera.capture_into(&mut memory, "image").unwrap();
+        assert_eq!(timestamp, 100_000);
+        assert_eq!(memory.read("image").unwrap().len(), 16);
+        
+        let frame = camera.capture().unwrap();
+        let shared = frame.clone();
+        assert_eq!(shared.share_count(), 2);
+        assert_eq!(frame.luma().unwrap().len(), 8);
+        
+        let reading = camera.read_frame().unwrap();
+        assert_eq!(reading.payload.len(), 16);
+        assert_eq!(reading.timestamp, 300_000);
+        assert_eq!(camera.sample_rate(), Some(10.0));
+        assert!(CameraFrame::new(0, 0, 4, 2, PixelFormat::Yuyv, vec![0; 15]).is_err());
+        assert!(CameraConfig::new(3, 2, 10.0, PixelFormat::Yuyv).validate().is_err());
+        assert_eq!(PixelFormat::Yuyv.fourcc(), 0x5659_5559);
+    }
+}
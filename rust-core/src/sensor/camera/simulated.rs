@@ -0,0 +1,96 @@
+//! Camera producing synthetic or recorded frames
+
+use super::{CameraBackend, CameraConfig, CameraFrame, PixelFormat};
+use crate::error::CoreError;
+
+/// A camera that needs no device, for tests and development
+///
+/// `new` draws YUYV frames of a diagonal gradient that shifts one pixel
+/// per frame; `replay` plays recorded frames, such as JPEG images, in a
+/// loop. Timestamps advance by exactly one frame period from 0, and
+/// capturing never waits.
+pub struct SimulatedCamera {
+    config: CameraConfig,
+    recorded: Vec<Vec<u8>>,
+    sequence: u64,
+}
+
+impl SimulatedCamera {
+    /// Draw a moving gradient at `config`'s resolution
+    ///
+    /// Fails with `CoreError::InvalidParameter` for an MJPEG config, which
+    /// has no image to encode; replay recorded frames instead.
+    pub fn new(config: CameraConfig) -> Result<Self, CoreError> {
+        config.validate()?;
+        if config.format != PixelFormat::Yuyv {
+            return Err(CoreError::InvalidParameter(
+                "a simulated camera draws YUYV frames; replay recorded frames for other formats".to_string(),
+            ));
+        }
+        Ok(Self {
+            config,
+            recorded: Vec::new(),
+            sequence: 0,
+        })
+    }
+    
+    /// Play `frames`, encoded as `config.format`, in a loop
+    pub fn replay(config: CameraConfig, frames: Vec<Vec<u8>>) -> Result<Self, CoreError> {
+        config.validate()?;
+        if frames.is_empty() {
+            return Err(CoreError::InvalidParameter("a replayed camera needs at least one frame".to_string()));
+        }
+        if let Some(expected) = config.format.frame_len(config.width, config.height) {
+            if let Some(frame) = frames.iter().find(|frame| frame.len() != expected) {
+                return Err(CoreError::InvalidParameter(format!(
+                    "recorded frame of {} bytes does not match {} byte frames",
+                    frame.len(),
+                    expected
+                )));
+            }
+        }
+        Ok(Self {
+            config,
+            recorded: frames,
+            sequence: 0,
+        })
+    }
+    
+    fn draw(&self) -> Vec<u8> {
+        let CameraConfig { width, height, .. } = self.config;
+        let shift = self.sequence as usize;
+        let mut data = Vec::with_capacity(width as usize * height as usize * 2);
+        for row in 0..height as usize {
+            for pair in 0..width as usize / 2 {
+                let luma = |column: usize| ((row + column + shift) % 256) as u8;
+                data.extend_from_slice(&[luma(pair * 2), 128, luma(pair * 2 + 1), 128]);
+            }
+        }
+        data
+    }
+}
+
+impl CameraBackend for SimulatedCamera {
+    fn config(&self) -> CameraConfig {
+        self.config
+    }
+    
+    fn capture(&mut self) -> Result<CameraFrame, CoreError> {
+        let data = if self.recorded.is_empty() {
+            self.draw()
+        } else {
+            self.recorded[(self.sequence % self.recorded.len() as u64) as usize].clone()
+        };
+        let timestamp = (self.sequence as f64 * 1e6 / self.config.fps).round() as u64;
+        let frame = CameraFrame::new(
+            self.sequence,
+            timestamp,
+            self.config.width,
+            self.config.height,
+            self.config.format,
+            data,
+        )?;
+        self.sequence += 1;
+        Ok(frame)
+    }
+}
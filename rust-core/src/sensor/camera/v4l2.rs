@@ -0,0 +1,475 @@
+//! Video4Linux capture devices through `/dev/video*`
+
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::mem;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::ptr;
+use std::time::Duration;
+
+use super::{CameraBackend, CameraConfig, CameraFrame, PixelFormat};
+use crate::error::CoreError;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+// Buffers the driver fills in turn while earlier frames are read out
+const BUFFER_COUNT: u32 = 4;
+
+const BUF_TYPE_VIDEO_CAPTURE: u32 = 1;
+const MEMORY_MMAP: u32 = 1;
+const FIELD_NONE: u32 = 1;
+const CAP_VIDEO_CAPTURE: u32 = 0x0000_0001;
+const CAP_STREAMING: u32 = 0x0400_0000;
+const CAP_DEVICE_CAPS: u32 = 0x8000_0000;
+const CAP_TIMEPERFRAME: u32 = 0x1000;
+const BUF_FLAG_ERROR: u32 = 0x0040;
+
+// From linux/videodev2.h, encoded as by _IOC on x86 and ARM
+const fn ioc(direction: libc::c_ulong, number: libc::c_ulong, size: usize) -> libc::c_ulong {
+    (direction << 30) | ((size as libc::c_ulong) << 16) | ((b'V' as libc::c_ulong) << 8) | number
+}
+const READ: libc::c_ulong = 2;
+const WRITE: libc::c_ulong = 1;
+const VIDIOC_QUERYCAP: libc::c_ulong = ioc(READ, 0, mem::size_of::<Capability>());
+const VIDIOC_S_FMT: libc::c_ulong = ioc(READ | WRITE, 5, mem::size_of::<Format>());
+const VIDIOC_REQBUFS: libc::c_ulong = ioc(READ | WRITE, 8, mem::size_of::<RequestBuffers>());
+const VIDIOC_QUERYBUF: libc::c_ulong = ioc(READ | WRITE, 9, mem::size_of::<Buffer>());
+const VIDIOC_QBUF: libc::c_ulong = ioc(READ | WRITE, 15, mem::size_of::<Buffer>());
+const VIDIOC_DQBUF: libc::c_ulong = ioc(READ | WRITE, 17, mem::size_of::<Buffer>());
+const VIDIOC_STREAMON: libc::c_ulong = ioc(WRITE, 18, mem::size_of::<libc::c_int>());
+const VIDIOC_STREAMOFF: libc::c_ulong = ioc(WRITE, 19, mem::size_of::<libc::c_int>());
+const VIDIOC_S_PARM: libc::c_ulong = ioc(READ | WRITE, 22, mem::size_of::<StreamParm>());
+
+#[repr(C)]
+struct Capability {
+    driver: [u8; 16],
+    card: [u8; 32],
+    bus_info: [u8; 32],
+    version: u32,
+    capabilities: u32,
+    device_caps: u32,
+    reserved: [u32; 3],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct PixFormat {
+    width: u32,
+    height: u32,
+    pixelformat: u32,
+    field: u32,
+    bytesperline: u32,
+    sizeimage: u32,
+    colorspace: u32,
+    private: u32,
+    flags: u32,
+    ycbcr_enc: u32,
+    quantization: u32,
+    xfer_func: u32,
+}
+
+// The format union is 200 bytes, aligned for the pointers some members hold
+#[repr(C)]
+struct Format {
+    kind: u32,
+    _align: [*const libc::c_void; 0],
+    pix: PixFormat,
+    _rest: [u8; 200 - mem::size_of::<PixFormat>()],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct Fract {
+    numerator: u32,
+    denominator: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct CaptureParm {
+    capability: u32,
+    capturemode: u32,
+    timeperframe: Fract,
+    extendedmode: u32,
+    readbuffers: u32,
+    reserved: [u32; 4],
+}
+
+// The parameter union is 200 bytes
+#[repr(C)]
+struct StreamParm {
+    kind: u32,
+    capture: CaptureParm,
+    _rest: [u8; 200 - mem::size_of::<CaptureParm>()],
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct RequestBuffers {
+    count: u32,
+    kind: u32,
+    memory: u32,
+    capabilities: u32,
+    flags: u8,
+    reserved: [u8; 3],
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct Timecode {
+    kind: u32,
+    flags: u32,
+    frames: u8,
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    userbits: [u8; 4],
+}
+
+// The memory union, of which only the mmap offset is used
+#[repr(C)]
+#[derive(Default)]
+struct BufferMemory {
+    offset: u32,
+    _rest: [u8; mem::size_of::<libc::c_ulong>() - 4],
+    _align: [libc::c_ulong; 0],
+}
+
+#[repr(C)]
+struct Buffer {
+    index: u32,
+    kind: u32,
+    bytesused: u32,
+    flags: u32,
+    field: u32,
+    timestamp: libc::timeval,
+    timecode: Timecode,
+    sequence: u32,
+    memory: u32,
+    m: BufferMemory,
+    length: u32,
+    reserved2: u32,
+    request_fd: i32,
+}
+
+impl Buffer {
+    fn new(index: u32) -> Self {
+        Self {
+            index,
+            kind: BUF_TYPE_VIDEO_CAPTURE,
+            bytesused: 0,
+            flags: 0,
+            field: 0,
+            timestamp: libc::timeval { tv_sec: 0, tv_usec: 0 },
+            timecode: Timecode::default(),
+            sequence: 0,
+            memory: MEMORY_MMAP,
+            m: BufferMemory::default(),
+            length: 0,
+            reserved2: 0,
+            request_fd: 0,
+        }
+    }
+}
+
+/// Issue `request` on `fd`, retrying when interrupted by a signal
+///
+/// # Safety
+///
+/// `arg` must be the structure `request` encodes the size of, and `fd` an
+/// open descriptor.
+unsafe fn ioctl<T>(fd: libc::c_int, request: libc::c_ulong, arg: &mut T) -> io::Result<()> {
+    loop {
+        // SAFETY: upheld by the caller; `arg` is valid for reads and writes
+        // of its whole size for the duration of the call.
+        if unsafe { libc::ioctl(fd, request as _, arg as *mut T) } >= 0 {
+            return Ok(());
+        }
+        let error = io::Error::last_os_error();
+        if error.kind() != io::ErrorKind::Interrupted {
+            return Err(error);
+        }
+    }
+}
+
+/// One driver buffer mapped into this process
+struct Mapping {
+    start: *mut libc::c_void,
+    len: usize,
+}
+
+/// A Video4Linux capture device such as `/dev/video0`, streaming through
+/// buffers shared with the driver
+///
+/// The driver writes each image into a buffer mapped into this process;
+/// capturing copies it out once into the frame and hands the buffer back,
+/// so the driver is never short of buffers however long frames are kept.
+/// YUYV rows the driver pads are packed tight.
+pub struct V4l2Camera {
+    file: File,
+    config: CameraConfig,
+    bytes_per_line: usize,
+    mappings: Vec<Mapping>,
+    timeout: Duration,
+}
+
+// SAFETY: the mappings are only read through `&mut self`, and unmapping
+// them on drop is valid from any thread.
+unsafe impl Send for V4l2Camera {}
+
+impl V4l2Camera {
+    /// Open the device at `path` and start streaming as close to `config`
+    /// as it allows
+    ///
+    /// Fails with `CoreError::UnsupportedCapability` if the device cannot
+    /// stream video or refuses the pixel format. Devices that cannot set
+    /// their frame rate keep their own, but report the rate asked for.
+    pub fn open(path: impl AsRef<Path>, config: CameraConfig) -> Result<Self, CoreError> {
+        config.validate()?;
+        let path = path.as_ref();
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(path)?;
+        let fd = file.as_raw_fd();
+        
+        // SAFETY: all-zero bytes are a valid `Capability`.
+        let mut capability: Capability = unsafe { mem::zeroed() };
+        // SAFETY: `capability` is the structure VIDIOC_QUERYCAP fills.
+        unsafe { ioctl(fd, VIDIOC_QUERYCAP, &mut capability)? };
+        let caps = if capability.capabilities & CAP_DEVICE_CAPS != 0 {
+            capability.device_caps
+        } else {
+            capability.capabilities
+        };
+        if caps & CAP_VIDEO_CAPTURE == 0 || caps & CAP_STREAMING == 0 {
+            return Err(CoreError::UnsupportedCapability(format!("{} cannot stream video capture", path.display())));
+        }
+        
+        let mut format = Format {
+            kind: BUF_TYPE_VIDEO_CAPTURE,
+            _align: [],
+            pix: PixFormat {
+                width: config.width,
+                height: config.height,
+                pixelformat: config.format.fourcc(),
+                field: FIELD_NONE,
+                ..Default::default()
+            },
+            _rest: [0; 200 - mem::size_of::<PixFormat>()],
+        };
+        // SAFETY: `format` is the structure VIDIOC_S_FMT reads and updates.
+        unsafe { ioctl(fd, VIDIOC_S_FMT, &mut format)? };
+        if format.pix.pixelformat != config.format.fourcc() {
+            return Err(CoreError::UnsupportedCapability(format!(
+                "{} does not capture {:?}",
+                path.display(),
+                config.format
+            )));
+        }
+        
+        let mut config = CameraConfig {
+            width: format.pix.width,
+            height: format.pix.height,
+            ..config
+        };
+        let mut parm = StreamParm {
+            kind: BUF_TYPE_VIDEO_CAPTURE,
+            capture: CaptureParm {
+                timeperframe: Fract {
+                    numerator: 1000,
+                    denominator: (config.fps * 1000.0).round() as u32,
+                },
+                ..Default::default()
+            },
+            _rest: [0; 200 - mem::size_of::<CaptureParm>()],
+        };
+        // SAFETY: `parm` is the structure VIDIOC_S_PARM reads and updates.
+        let rate_set = unsafe { ioctl(fd, VIDIOC_S_PARM, &mut parm) }.is_ok();
+        let period = parm.capture.timeperframe;
+        if rate_set && parm.capture.capability & CAP_TIMEPERFRAME != 0 && period.numerator > 0 {
+            config.fps = f64::from(period.denominator) / f64::from(period.numerator);
+        }
+        
+        let mut camera = Self {
+            file,
+            config,
+            bytes_per_line: format.pix.bytesperline as usize,
+            mappings: Vec::new(),
+            timeout: DEFAULT_TIMEOUT,
+        };
+        camera.map_buffers()?;
+        let mut kind = BUF_TYPE_VIDEO_CAPTURE as libc::c_int;
+        // SAFETY: VIDIOC_STREAMON reads the buffer type as an int.
+        unsafe { ioctl(fd, VIDIOC_STREAMON, &mut kind)? };
+        Ok(camera)
+    }
+    
+    /// Fail a capture with `CoreError::SensorError` after `timeout` without a
+    /// frame, two seconds unless changed
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+    
+    /// Ask the driver for buffers, map each one and queue it for filling
+    fn map_buffers(&mut self) -> Result<(), CoreError> {
+        let fd = self.file.as_raw_fd();
+        let mut request = RequestBuffers {
+            count: BUFFER_COUNT,
+            kind: BUF_TYPE_VIDEO_CAPTURE,
+            memory: MEMORY_MMAP,
+            ..Default::default()
+        };
+        // SAFETY: `request` is the structure VIDIOC_REQBUFS reads and updates.
+        unsafe { ioctl(fd, VIDIOC_REQBUFS, &mut request)? };
+        if request.count < 2 {
+            return Err(CoreError::SensorError(format!(
+                "video device granted {} capture buffers, at least 2 are needed",
+                request.count
+            )));
+        }
+        for index in 0..request.count {
+            let mut buffer = Buffer::new(index);
+            // SAFETY: `buffer` is the structure VIDIOC_QUERYBUF fills.
+            unsafe { ioctl(fd, VIDIOC_QUERYBUF, &mut buffer)? };
+            let len = buffer.length as usize;
+            // SAFETY: maps a region the driver just described, at the offset
+            // it gave; the result is checked before use.
+            let start = unsafe {
+                libc::mmap(
+                    ptr::null_mut(),
+                    len,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_SHARED,
+                    fd,
+                    buffer.m.offset as libc::off_t,
+                )
+            };
+            if start == libc::MAP_FAILED {
+                return Err(io::Error::last_os_error().into());
+            }
+            self.mappings.push(Mapping { start, len });
+            // SAFETY: `buffer` is the structure VIDIOC_QBUF reads.
+            unsafe { ioctl(fd, VIDIOC_QBUF, &mut buffer)? };
+        }
+        Ok(())
+    }
+    
+    /// Wait until the driver has filled a buffer
+    fn wait(&self) -> Result<(), CoreError> {
+        let mut poll = libc::pollfd {
+            fd: self.file.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout = libc::c_int::try_from(self.timeout.as_millis()).unwrap_or(libc::c_int::MAX);
+        loop {
+            // SAFETY: `poll` is one valid `pollfd`, as the count says.
+            let ready = unsafe { libc::poll(&mut poll, 1, timeout) };
+            if ready > 0 {
+                return Ok(());
+            }
+            if ready == 0 {
+                return Err(CoreError::SensorError(format!("no camera frame within {:?}", self.timeout)));
+            }
+            let error = io::Error::last_os_error();
+            if error.kind() != io::ErrorKind::Interrupted {
+                return Err(error.into());
+            }
+        }
+    }
+    
+    /// Copy a filled buffer's image out, packing padded YUYV rows
+    fn copy_out(&self, buffer: &Buffer) -> Result<Vec<u8>, CoreError> {
+        let mapping = self
+            .mappings
+            .get(buffer.index as usize)
+            .ok_or_else(|| CoreError::SensorError(format!("driver returned unknown buffer {}", buffer.index)))?;
+        let used = (buffer.bytesused as usize).min(mapping.len);
+        // SAFETY: the mapping is `mapping.len` bytes long and stays mapped
+        // while `self` lives; the driver does not write to a dequeued buffer.
+        let bytes = unsafe { std::slice::from_raw_parts(mapping.start as *const u8, used) };
+        let row = self.config.width as usize * 2;
+        match self.config.format {
+            PixelFormat::Yuyv if self.bytes_per_line > row => Ok(bytes
+                .chunks(self.bytes_per_line)
+                .take(self.config.height as usize)
+                .flat_map(|line| &line[..row.min(line.len())])
+                .copied()
+                .collect()),
+            _ => Ok(bytes.to_vec()),
+        }
+    }
+}
+
+impl CameraBackend for V4l2Camera {
+    fn config(&self) -> CameraConfig {
+        self.config
+    }
+    
+    fn capture(&mut self) -> Result<CameraFrame, CoreError> {
+        let fd = self.file.as_raw_fd();
+        let mut buffer = Buffer::new(0);
+        loop {
+            self.wait()?;
+            // SAFETY: `buffer` is the structure VIDIOC_DQBUF fills.
+            match unsafe { ioctl(fd, VIDIOC_DQBUF, &mut buffer) } {
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => continue,
+                result => break result?,
+            }
+        }
+        let image = if buffer.flags & BUF_FLAG_ERROR == 0 {
+            self.copy_out(&buffer)
+        } else {
+            Err(CoreError::SensorError("camera driver flagged a corrupt frame".to_string()))
+        };
+        // SAFETY: `buffer` is the structure VIDIOC_QBUF reads, describing the
+        // buffer just dequeued.
+        unsafe { ioctl(fd, VIDIOC_QBUF, &mut buffer)? };
+        let timestamp = buffer.timestamp.tv_sec as u64 * 1_000_000 + buffer.timestamp.tv_usec as u64;
+        CameraFrame::new(
+            u64::from(buffer.sequence),
+            timestamp,
+            self.config.width,
+            self.config.height,
+            self.config.format,
+            image?,
+        )
+    }
+}
+
+impl Drop for V4l2Camera {
+    fn drop(&mut self) {
+        let mut kind = BUF_TYPE_VIDEO_CAPTURE as libc::c_int;
+        // SAFETY: VIDIOC_STREAMOFF reads the buffer type as an int. Stopping
+        // can only fail if streaming never started, which is harmless.
+        let _ = unsafe { ioctl(self.file.as_raw_fd(), VIDIOC_STREAMOFF, &mut kind) };
+        for mapping in &self.mappings {
+            // SAFETY: each mapping came from a successful mmap of `len` bytes
+            // and is unmapped once; nothing borrows it past `self`.
+            unsafe { libc::munmap(mapping.start, mapping.len) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_request_layout_and_missing_device() {
+        // Request numbers as listed in linux/videodev2.h for 64-bit targets
+        if cfg!(target_pointer_width = "64") {
+            assert_eq!(VIDIOC_QUERYCAP, 0x8068_5600);
+            assert_eq!(VIDIOC_S_FMT, 0xC0D0_5605);
+            assert_eq!(VIDIOC_REQBUFS, 0xC014_5608);
+            assert_eq!(VIDIOC_QBUF, 0xC058_560F);
+            assert_eq!(VIDIOC_S_PARM, 0xC0CC_5616);
+            assert_eq!(VIDIOC_STREAMON, 0x4004_5612);
+        }
+        assert!(V4l2Camera::open("/dev/video-robotics-core-nope", CameraConfig::default()).is_err());
+    }
+}
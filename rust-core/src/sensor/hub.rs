@@ -0,0 +1,171 @@
+//! Fanning one sensor's frames out to several consumers
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError, TrySendError};
+use std::sync::Arc;
+
+use super::{Sensor, SensorFrame};
+use crate::error::CoreError;
+
+/// Broadcasts every frame read from a sensor to all subscribers
+///
+/// Each subscriber has its own bounded queue. Delivery never blocks: when a
+/// subscriber's queue is full the new frame is dropped for that subscriber
+/// only and counted in its `FrameReceiver::dropped`, so a slow consumer
+/// loses the newest frames while the others keep receiving everything. The
+/// frames it already queued stay in order. Subscribers whose receiver has
+/// been dropped are forgotten on the next delivery.
+pub struct SensorHub<S: Sensor> {
+    sensor: S,
+    capacity: usize,
+    subscribers: Vec<Subscriber>,
+}
+
+struct Subscriber {
+    sender: SyncSender<SensorFrame>,
+    dropped: Arc<AtomicU64>,
+}
+
+/// A consumer's end of a `SensorHub` subscription
+pub struct FrameReceiver {
+    receiver: Receiver<SensorFrame>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl<S: Sensor> SensorHub<S> {
+    /// Wrap `sensor`, queueing up to `capacity` frames per subscriber by
+    /// default
+    pub fn new(sensor: S, capacity: usize) -> Result<Self, CoreError> {
+        if capacity == 0 {
+            return Err(CoreError::InvalidParameter("subscriber capacity must be at least 1".to_string()));
+        }
+        Ok(Self {
+            sensor,
+            capacity,
+            subscribers: Vec::new(),
+        })
+    }
+    
+    /// Add a subscriber with the hub's default queue capacity
+    pub fn subscribe(&mut self) -> FrameReceiver {
+        self.subscribe_with_capacity(self.capacity)
+    }
+    
+    /// Add a subscriber queueing up to `capacity` frames, at least one
+    pub fn subscribe_with_capacity(&mut self, capacity: usize) -> FrameReceiver {
+        let (sender, receiver) = mpsc::sync_channel(capacity.max(1));
+        let dropped = Arc::new(AtomicU64::new(0));
+        self.subscribers.push(Subscriber {
+            sender,
+            dropped: Arc::clone(&dropped),
+        });
+        FrameReceiver { receiver, dropped }
+    }
+    
+    /// Number of live subscribers, as of the last delivery
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.len()
+    }
+    
+    /// Read one frame from the sensor and deliver it to every subscriber
+    ///
+    /// Returns how many subscribers queued the frame. A sensor error is
+    /// returned as is, with nothing delivered.
+    pub fn pump(&mut self) -> Result<usize, CoreError> {
+        let frame = self.sensor.read_frame()?;
+        let mut delivered = 0;
+        self.subscribers
+            .retain(|subscriber| match subscriber.sender.try_send(frame.clone()) {
+                Ok(()) => {
+                    delivered += 1;
+                    true
+                }
+                Err(TrySendError::Full(_)) => {
+                    subscriber.dropped.fetch_add(1, Ordering::Relaxed);
+                    true
+                }
+                Err(TrySendError::Disconnected(_)) => false,
+            });
+        Ok(delivered)
+    }
+    
+    /// Unwrap the sensor, closing every subscription
+    pub fn into_inner(self) -> S {
+        self.sensor
+    }
+}
+
+impl FrameReceiver {
+    /// Wait for the next frame
+    ///
+    /// Returns `CoreError::EndOfStream` once the hub is gone and the queue
+    /// is drained.
+    pub fn recv(&self) -> Result<SensorFrame, CoreError> {
+        self.receiver.recv().map_err(|_| CoreError::EndOfStream)
+    }
+    
+    /// Take the next queued frame without waiting, if there is one
+    pub fn try_recv(&self) -> Result<Option<SensorFrame>, CoreError> {
+        match self.receiver.try_recv() {
+            Ok(frame) => Ok(Some(frame)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(CoreError::EndOfStream),
+        }
+    }
+    
+    /// Frames dropped because this subscriber's queue was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensor::FunctionSensor;
+    use std::time::Duration;
+    
+    fn counter() -> impl Sensor {
+        FunctionSensor::new("counter", Duration::from_millis(1), |t| vec![(t / 1000) as u8]).unwrap()
+    }
+    
+    fn drain(receiver: &FrameReceiver) -> Vec<u8> {
+        std::iter::from_fn(|| receiver.try_recv().unwrap())
+            .map(|frame| frame.payload[0])
+            .collect()
+    }
+    
+    #[test]
+    fn test_subscribers_receive_same_frames_and_slow_one_drops() {
+        let mut hub = SensorHub::new(counter(), 16).unwrap();
+        let first = hub.subscribe();
+        let second = hub.subscribe();
+        let slow = hub.subscribe_with_capacity(2);
+        
+        for _ in 0..5 {
+            hub.pump().unwrap();
+        }
+        let expected: Vec<u8> = (0..5).collect();
+        assert_eq!(drain(&first), expected);
+        assert_eq!(drain(&second), expected);
+        assert_eq!((first.dropped(), second.dropped()), (0, 0));
+        
+        // The slow subscriber kept the oldest frames and lost the rest
+        assert_eq!(drain(&slow), vec![0, 1]);
+        assert_eq!(slow.dropped(), 3);
+    }
+    
+    #[test]
+    fn test_dropped_receivers_are_forgotten() {
+        let mut hub = SensorHub::new(counter(), 4).unwrap();
+        let kept = hub.subscribe();
+        drop(hub.subscribe());
+        assert_eq!(hub.pump().unwrap(), 1);
+        assert_eq!(hub.subscriber_count(), 1);
+        
+        drop(hub);
+        assert_eq!(kept.recv().unwrap().payload, vec![0]);
+        assert_eq!(kept.recv(), Err(CoreError::EndOfStream));
+        assert!(SensorHub::new(counter(), 0).is_err());
+    }
+}
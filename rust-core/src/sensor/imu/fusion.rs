@@ -0,0 +1,188 @@
+//! Orientation from accelerometer and gyroscope readings
+
+use crate::math;
+
+type Quaternion = [f32; 4];
+
+const IDENTITY: Quaternion = [1.0, 0.0, 0.0, 0.0];
+
+fn normalized<const N: usize>(v: [f32; N]) -> Option<[f32; N]> {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    (norm > 0.0 && norm.is_finite()).then(|| v.map(|x| x / norm))
+}
+
+/// Advance `q` by `rate`, its derivative, over `dt` seconds
+fn integrate(q: Quaternion, rate: Quaternion, dt: f32) -> Quaternion {
+    let stepped = [0, 1, 2, 3].map(|i| q[i] + rate[i] * dt);
+    normalized(stepped).unwrap_or(IDENTITY)
+}
+
+/// Derivative of `q` turning at angular rate `gyro` in the body frame
+fn rate_of_change(q: Quaternion, [gx, gy, gz]: [f32; 3]) -> Quaternion {
+    math::quaternion_multiply(q, [0.0, gx, gy, gz]).map(|c| 0.5 * c)
+}
+
+/// Madgwick's gradient-descent orientation filter
+///
+/// Each update integrates the gyroscope and steps the estimate down the
+/// gradient of the error between measured and predicted gravity. `beta`
+/// sets how hard the accelerometer pulls: larger values cancel gyroscope
+/// drift faster but let linear acceleration tilt the estimate.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Madgwick {
+    beta: f32,
+    q: Quaternion,
+}
+
+impl Madgwick {
+    pub fn new(beta: f32) -> Self {
+        Self { beta, q: IDENTITY }
+    }
+    
+    fn update(&mut self, gyro: [f32; 3], accel: [f32; 3], dt: f32) {
+        let mut rate = rate_of_change(self.q, gyro);
+        // A free-falling or zero reading says nothing about gravity
+        if let Some([ax, ay, az]) = normalized(accel) {
+            let [w, x, y, z] = self.q;
+            // Error between gravity rotated into the body frame and the
+            // measured direction, and its gradient
+            let f = [
+                2.0 * (x * z - w * y) - ax,
+                2.0 * (w * x + y * z) - ay,
+                2.0 * (0.5 - x * x - y * y) - az,
+            ];
+            let gradient = [
+                -2.0 * y * f[0] + 2.0 * x * f[1],
+                2.0 * z * f[0] + 2.0 * w * f[1] - 4.0 * x * f[2],
+                -2.0 * w * f[0] + 2.0 * z * f[1] - 4.0 * y * f[2],
+                2.0 * x * f[0] + 2.0 * y * f[1],
+            ];
+            if let Some(step) = normalized(gradient) {
+                rate = [0, 1, 2, 3].map(|i| rate[i] - self.beta * step[i]);
+            }
+        }
+        self.q = integrate(self.q, rate, dt);
+    }
+}
+
+/// Mahony's complementary orientation filter
+///
+/// The cross product of measured and predicted gravity is an angular rate
+/// error, fed back into the gyroscope reading with proportional gain `kp`
+/// and integral gain `ki`; the integral learns a constant gyroscope bias.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mahony {
+    kp: f32,
+    ki: f32,
+    q: Quaternion,
+    integral: [f32; 3],
+}
+
+impl Mahony {
+    pub fn new(kp: f32, ki: f32) -> Self {
+        Self {
+            kp,
+            ki,
+            q: IDENTITY,
+            integral: [0.0; 3],
+        }
+    }
+    
+    fn update(&mut self, gyro: [f32; 3], accel: [f32; 3], dt: f32) {
+        let mut corrected = gyro;
+        if let Some([ax, ay, az]) = normalized(accel) {
+            let [w, x, y, z] = self.q;
+            // Gravity direction predicted by the estimate
+            let v = [2.0 * (x * z - w * y), 2.0 * (w * x + y * z), w * w - x * x - y * y + z * z];
+            let error = [ay * v[2] - az * v[1], az * v[0] - ax * v[2], ax * v[1] - ay * v[0]];
+            for axis in 0..3 {
+                if self.ki > 0.0 {
+                    self.integral[axis] += self.ki * error[axis] * dt;
+                }
+                corrected[axis] += self.kp * error[axis] + self.integral[axis];
+            }
+        }
+        self.q = integrate(self.q, rate_of_change(self.q, corrected), dt);
+    }
+}
+
+/// A filter fusing gyroscope and accelerometer readings into an
+/// orientation, from the body frame to a world frame with z up
+///
+/// Without a magnetometer the heading about z is only integrated from the
+/// gyroscope, so it drifts; roll and pitch are held to gravity.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OrientationFilter {
+    Madgwick(Madgwick),
+    Mahony(Mahony),
+}
+
+impl OrientationFilter {
+    /// Advance the estimate by one reading taken `dt` seconds after the
+    /// previous one
+    pub fn update(&mut self, gyro: [f32; 3], accel: [f32; 3], dt: f32) {
+        match self {
+            Self::Madgwick(filter) => filter.update(gyro, accel, dt),
+            Self::Mahony(filter) => filter.update(gyro, accel, dt),
+        }
+    }
+    
+    /// Current orientation as a unit `(w, x, y, z)` quaternion
+    pub fn quaternion(&self) -> [f32; 4] {
+        match self {
+            Self::Madgwick(filter) => filter.q,
+            Self::Mahony(filter) => filter.q,
+        }
+    }
+    
+    /// Return to level, forgetting any learned bias
+    pub fn reset(&mut self) {
+        match self {
+            Self::Madgwick(filter) => filter.q = IDENTITY,
+            Self::Mahony(filter) => {
+                filter.q = IDENTITY;
+                filter.integral = [0.0; 3];
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Gravity direction in the body frame that `q` predicts
+    fn predicted_gravity([w, x, y, z]: Quaternion) -> [f32; 3] {
+        [2.0 * (x * z - w * y), 2.0 * (w * x + y * z), w * w - x * x - y * y + z * z]
+    }
+    
+    #[test]
+    fn test_filters_settle_on_gravity_and_follow_rotation() {
+        // Rolled 30 degrees about x and held still; the normalised Madgwick
+        // step chatters about the answer by roughly `beta * dt`
+        let (s, c) = (30.0f32.to_radians().sin(), 30.0f32.to_radians().cos());
+        let accel = [0.0, s * 9.81, c * 9.81];
+        let filters = [
+            OrientationFilter::Madgwick(Madgwick::new(0.5)),
+            OrientationFilter::Mahony(Mahony::new(2.0, 0.1)),
+        ];
+        for mut filter in filters {
+            for _ in 0..2000 {
+                filter.update([0.0; 3], accel, 0.01);
+            }
+            let gravity = predicted_gravity(filter.quaternion());
+            for (g, a) in gravity.iter().zip([0.0, s, c]) {
+                assert!((g - a).abs() < 1e-2, "{:?}: {:?}", filter, gravity);
+            }
+            
+            // A pure yaw rate with gravity on z turns the estimate about z
+            filter.reset();
+            for _ in 0..100 {
+                filter.update([0.0, 0.0, 0.5], [0.0, 0.0, 9.81], 0.01);
+            }
+            let [w, _, _, z] = filter.quaternion();
+            let yaw = 2.0 * z.atan2(w);
+            assert!((yaw - 0.5).abs() < 1e-3, "{:?}: yaw {}", filter, yaw);
+        }
+    }
+}
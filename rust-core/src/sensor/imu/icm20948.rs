@@ -0,0 +1,134 @@
+//! TDK InvenSense ICM-20948 nine-axis IMU
+
+use super::{divider, ImuConfig, ImuDriver, ImuSample};
+use crate::error::CoreError;
+use crate::hardware::RegisterDevice;
+
+// Present in every bank
+const REG_BANK_SEL: u8 = 0x7F;
+
+// Bank 0
+const WHO_AM_I: u8 = 0x00;
+const PWR_MGMT_1: u8 = 0x06;
+const PWR_MGMT_2: u8 = 0x07;
+const ACCEL_XOUT_H: u8 = 0x2D;
+
+// Bank 2
+const GYRO_SMPLRT_DIV: u8 = 0x00;
+const GYRO_CONFIG_1: u8 = 0x01;
+const ACCEL_SMPLRT_DIV_2: u8 = 0x11;
+const ACCEL_CONFIG: u8 = 0x14;
+
+const DEVICE_ID: u8 = 0xEA;
+// Wake from sleep with the best available clock
+const CLOCK_AUTO: u8 = 0x01;
+// Low-pass filter enabled at its 3rd setting (about 50 Hz), with the range
+// in bits 2:1
+const DLPF_3: u8 = (3 << 3) | 0x01;
+const OUTPUT_HZ: f64 = 1125.0;
+
+/// An ICM-20948 over I2C (address `0x68` or `0x69`) or SPI
+///
+/// Only the accelerometer and gyroscope are read; the magnetometer behind
+/// the chip's auxiliary I2C master is left off.
+pub struct Icm20948<R: RegisterDevice> {
+    registers: R,
+    config: ImuConfig,
+    sample_rate: f64,
+}
+
+impl<R: RegisterDevice> Icm20948<R> {
+    /// Wake the chip and configure its ranges, low-pass filters and rate
+    ///
+    /// Fails with `CoreError::DeviceNotFound` if the identity register does
+    /// not hold the ICM-20948's.
+    pub fn new(mut registers: R, config: ImuConfig) -> Result<Self, CoreError> {
+        registers.write_register(REG_BANK_SEL, 0)?;
+        let id = registers.read_register(WHO_AM_I)?;
+        if id != DEVICE_ID {
+            return Err(CoreError::DeviceNotFound(format!(
+                "ICM-20948 identity {:#04x} expected, read {:#04x}",
+                DEVICE_ID, id
+            )));
+        }
+        let divider = divider(OUTPUT_HZ, config.sample_rate, u16::from(u8::MAX))? as u8;
+        registers.write_register(PWR_MGMT_1, CLOCK_AUTO)?;
+        registers.write_register(PWR_MGMT_2, 0)?;
+        registers.write_register(REG_BANK_SEL, 2 << 4)?;
+        registers.write_register(GYRO_SMPLRT_DIV, divider)?;
+        registers.write_register(GYRO_CONFIG_1, DLPF_3 | config.gyro_range.bits() << 1)?;
+        registers.write_register(ACCEL_SMPLRT_DIV_2, divider)?;
+        registers.write_register(ACCEL_CONFIG, DLPF_3 | config.accel_range.bits() << 1)?;
+        registers.write_register(REG_BANK_SEL, 0)?;
+        Ok(Self {
+            registers,
+            config,
+            sample_rate: OUTPUT_HZ / (1.0 + f64::from(divider)),
+        })
+    }
+    
+    pub fn into_inner(self) -> R {
+        self.registers
+    }
+}
+
+impl<R: RegisterDevice> ImuDriver for Icm20948<R> {
+    fn read_sample(&mut self) -> Result<ImuSample, CoreError> {
+        // Accelerometer, gyroscope and temperature as big-endian i16s
+        let mut raw = [0u8; 14];
+        self.registers.read_registers(ACCEL_XOUT_H, &mut raw)?;
+        let value = |i: usize| f32::from(i16::from_be_bytes([raw[2 * i], raw[2 * i + 1]]));
+        let (accel, gyro) = (self.config.accel_range.scale(), self.config.gyro_range.scale());
+        Ok(ImuSample {
+            accel: [0, 1, 2].map(|axis| value(axis) * accel),
+            gyro: [3, 4, 5].map(|axis| value(axis) * gyro),
+            temperature: value(6) / 333.87 + 21.0,
+        })
+    }
+    
+    fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::{MockSpi, SpiRegisters};
+    use crate::sensor::imu::{AccelRange, GyroRange, GRAVITY};
+    
+    #[test]
+    fn test_configures_over_spi_and_scales_readings() {
+        let device = MockSpi::new();
+        device.set_registers(WHO_AM_I, &[DEVICE_ID]);
+        let config = ImuConfig {
+            accel_range: AccelRange::G8,
+            gyro_range: GyroRange::Dps2000,
+            sample_rate: 100.0,
+        };
+        let mut imu = Icm20948::new(SpiRegisters::new(device.clone()), config).unwrap();
+        assert_eq!(imu.sample_rate(), 1125.0 / 11.0);
+        // The mock has a single bank, so bank 2 settings land beside bank 0's
+        assert_eq!(device.register(GYRO_CONFIG_1), DLPF_3 | 0x06);
+        assert_eq!(device.register(ACCEL_CONFIG), DLPF_3 | 0x04);
+        assert_eq!(device.register(ACCEL_SMPLRT_DIV_2), 10);
+        assert_eq!((device.register(PWR_MGMT_1), device.register(REG_BANK_SEL)), (CLOCK_AUTO, 0));
+        
+        // -1 g on x, 16 counts (about 1 degree per second) on z, 21 degrees C
+        let mut raw = Vec::new();
+        for value in [-4096i16, 0, 0, 0, 0, 16, 0] {
+            raw.extend_from_slice(&value.to_be_bytes());
+        }
+        device.set_registers(ACCEL_XOUT_H, &raw);
+        let sample = imu.read_sample().unwrap();
+        assert!((sample.accel[0] + GRAVITY).abs() < 1e-5);
+        assert!((sample.gyro[2] - 16.0 / 16.384 * std::f32::consts::PI / 180.0).abs() < 1e-6);
+        assert_eq!(sample.temperature, 21.0);
+        
+        device.set_registers(WHO_AM_I, &[0x00]);
+        assert!(matches!(
+            Icm20948::new(SpiRegisters::new(device), ImuConfig::default()),
+            Err(CoreError::DeviceNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,381 @@
+//! Inertial measurement units over I2C or SPI
+//!
+//! `Mpu6050` and `Icm20948` drive the chips through any `RegisterDevice`,
+//! such as `I2cRegisters` over a `LinuxI2c` adapter. `ImuSensor` turns a
+//! driver into a `Sensor` delivering calibrated readings and, optionally,
+//! an orientation fused by `OrientationFilter`. Add it to a
+//! `SensorManager` to publish its readings to memory at a chosen rate.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{Endianness, Sensor, SensorFrame, SensorMetadata};
+use crate::calibration::Calibration;
+use crate::clock::{Clock, SystemClock};
+use crate::error::CoreError;
+
+mod fusion;
+mod icm20948;
+mod mpu6050;
+
+pub use fusion::{Madgwick, Mahony, OrientationFilter};
+pub use icm20948::Icm20948;
+pub use mpu6050::Mpu6050;
+
+/// Standard gravity in m/s²
+pub const GRAVITY: f32 = 9.80665;
+
+/// Full-scale range of an accelerometer
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AccelRange {
+    /// ±2 g
+    #[default]
+    G2,
+    /// ±4 g
+    G4,
+    /// ±8 g
+    G8,
+    /// ±16 g
+    G16,
+}
+
+impl AccelRange {
+    /// Two-bit field selecting the range in the configuration register
+    fn bits(self) -> u8 {
+        self as u8
+    }
+    
+    /// Acceleration in m/s² of one count of the raw reading
+    fn scale(self) -> f32 {
+        GRAVITY / (16384 >> self.bits()) as f32
+    }
+}
+
+/// Full-scale range of a gyroscope
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GyroRange {
+    /// ±250 °/s
+    #[default]
+    Dps250,
+    /// ±500 °/s
+    Dps500,
+    /// ±1000 °/s
+    Dps1000,
+    /// ±2000 °/s
+    Dps2000,
+}
+
+impl GyroRange {
+    fn bits(self) -> u8 {
+        self as u8
+    }
+    
+    /// Angular rate in rad/s of one count of the raw reading
+    fn scale(self) -> f32 {
+        (250 << self.bits()) as f32 / 32768.0 * std::f32::consts::PI / 180.0
+    }
+}
+
+/// Measurement ranges and output rate an IMU driver configures
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImuConfig {
+    pub accel_range: AccelRange,
+    pub gyro_range: GyroRange,
+    /// Requested output data rate in Hz; the driver picks the nearest rate
+    /// the chip supports at or above it
+    pub sample_rate: f64,
+}
+
+impl Default for ImuConfig {
+    fn default() -> Self {
+        Self {
+            accel_range: AccelRange::default(),
+            gyro_range: GyroRange::default(),
+            sample_rate: 100.0,
+        }
+    }
+}
+
+/// Clock divider giving the lowest rate at or above `requested` from a
+/// `base_hz` internal rate, for chips whose rate is `base_hz / (1 + divider)`
+fn divider(base_hz: f64, requested: f64, max: u16) -> Result<u16, CoreError> {
+    if !(requested.is_finite() && requested > 0.0) {
+        return Err(CoreError::InvalidParameter(format!(
+            "sample rate must be positive and finite, got {}",
+            requested
+        )));
+    }
+    Ok((base_hz / requested - 1.0).floor().clamp(0.0, f64::from(max)) as u16)
+}
+
+/// One reading of an IMU in SI units
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ImuSample {
+    /// Specific force along x, y and z in m/s², which reads +g on the axis
+    /// pointing up at rest
+    pub accel: [f32; 3],
+    /// Angular rate about x, y and z in rad/s
+    pub gyro: [f32; 3],
+    /// Die temperature in °C
+    pub temperature: f32,
+}
+
+/// A chip driver reading accelerations and angular rates
+pub trait ImuDriver: Send {
+    /// Read the latest sample
+    fn read_sample(&mut self) -> Result<ImuSample, CoreError>;
+    
+    /// Output data rate the chip is configured for, in Hz
+    fn sample_rate(&self) -> f64;
+}
+
+/// Offsets subtracted from raw IMU readings
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImuCalibration {
+    pub accel_bias: [f32; 3],
+    pub gyro_bias: [f32; 3],
+}
+
+impl ImuCalibration {
+    fn apply(&self, sample: &mut ImuSample) {
+        for axis in 0..3 {
+            sample.accel[axis] -= self.accel_bias[axis];
+            sample.gyro[axis] -= self.gyro_bias[axis];
+        }
+    }
+}
+
+/// An IMU driver as a `Sensor`
+///
+/// Each frame's payload is the calibrated acceleration (m/s²) and angular
+/// rate (rad/s) along x, y and z as little-endian `f32`s, followed with a
+/// fusion filter by the orientation as a `(w, x, y, z)` quaternion. The
+/// filter integrates over the time between reads on the sensor's clock.
+pub struct ImuSensor<D: ImuDriver> {
+    id: String,
+    driver: D,
+    calibration: ImuCalibration,
+    calibration_samples: usize,
+    fusion: Option<OrientationFilter>,
+    clock: Arc<dyn Clock>,
+    last_read: Option<Duration>,
+}
+
+impl<D: ImuDriver> ImuSensor<D> {
+    pub fn new(id: &str, driver: D) -> Self {
+        Self {
+            id: id.to_string(),
+            driver,
+            calibration: ImuCalibration::default(),
+            calibration_samples: 100,
+            fusion: None,
+            clock: Arc::new(SystemClock::new()),
+            last_read: None,
+        }
+    }
+    
+    /// Fuse readings into an orientation with `filter`
+    pub fn with_fusion(mut self, filter: OrientationFilter) -> Self {
+        self.fusion = Some(filter);
+        self
+    }
+    
+    /// Start from a stored calibration instead of none
+    pub fn with_calibration(mut self, calibration: ImuCalibration) -> Self {
+        self.calibration = calibration;
+        self
+    }
+    
+    /// Average `samples` readings in `calibrate`, 100 by default
+    pub fn with_calibration_samples(mut self, samples: usize) -> Self {
+        self.calibration_samples = samples.max(1);
+        self
+    }
+    
+    /// Time reads on `clock` rather than the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+    
+    pub fn calibration(&self) -> ImuCalibration {
+        self.calibration
+    }
+    
+    /// Latest fused orientation as a `(w, x, y, z)` quaternion, if fusing
+    pub fn orientation(&self) -> Option<[f32; 4]> {
+        self.fusion.as_ref().map(OrientationFilter::quaternion)
+    }
+    
+    /// Read one calibrated sample, updating the fused orientation
+    pub fn read_sample(&mut self) -> Result<ImuSample, CoreError> {
+        let mut sample = self.driver.read_sample()?;
+        self.calibration.apply(&mut sample);
+        let now = self.clock.now();
+        // The first read has no previous one, so it spans a nominal period
+        let dt = match self.last_read {
+            Some(last) => now.saturating_sub(last).as_secs_f32(),
+            None => (1.0 / self.driver.sample_rate()) as f32,
+        };
+        self.last_read = Some(now);
+        if let Some(fusion) = &mut self.fusion {
+            fusion.update(sample.gyro, sample.accel, dt);
+        }
+        Ok(sample)
+    }
+    
+    pub fn into_inner(self) -> D {
+        self.driver
+    }
+}
+
+impl<D: ImuDriver> Sensor for ImuSensor<D> {
+    fn id(&self) -> &str {
+        &self.id
+    }
+    
+    fn read_frame(&mut self) -> Result<SensorFrame, CoreError> {
+        let sample = self.read_sample()?;
+        let values = sample.accel.into_iter().chain(sample.gyro).chain(self.orientation().into_iter().flatten());
+        Ok(SensorFrame {
+            sensor_id: self.id.clone(),
+            timestamp: u64::try_from(self.clock.now().as_micros()).unwrap_or(u64::MAX),
+            payload: values.flat_map(f32::to_le_bytes).collect(),
+            endianness: Endianness::Little,
+        })
+    }
+    
+    fn sample_rate(&self) -> Option<f64> {
+        Some(self.driver.sample_rate())
+    }
+    
+    /// Measure the biases of an IMU lying still and level, z axis up
+    ///
+    /// The gyroscope bias is the mean rate and the accelerometer bias the
+    /// mean less gravity along +z. The fused orientation restarts level.
+    fn calibrate(&mut self) -> Result<(), CoreError> {
+        let mut accel = [0.0f64; 3];
+        let mut gyro = [0.0f64; 3];
+        for _ in 0..self.calibration_samples {
+            let sample = self.driver.read_sample()?;
+            for axis in 0..3 {
+                accel[axis] += f64::from(sample.accel[axis]);
+                gyro[axis] += f64::from(sample.gyro[axis]);
+            }
+        }
+        let count = self.calibration_samples as f64;
+        let mut calibration = ImuCalibration {
+            accel_bias: accel.map(|sum| (sum / count) as f32),
+            gyro_bias: gyro.map(|sum| (sum / count) as f32),
+        };
+        calibration.accel_bias[2] -= GRAVITY;
+        self.calibration = calibration;
+        if let Some(fusion) = &mut self.fusion {
+            fusion.reset();
+        }
+        self.last_read = None;
+        Ok(())
+    }
+    
+    fn calibration(&self) -> Option<Calibration> {
+        Some(Calibration::Imu(self.calibration))
+    }
+    
+    /// Take the biases from a `Calibration::Imu`, restarting the fused
+    /// orientation as `calibrate` does
+    fn set_calibration(&mut self, calibration: &Calibration) -> Result<(), CoreError> {
+        let Calibration::Imu(imu) = calibration else {
+            return Err(CoreError::InvalidParameter(format!(
+                "IMU '{}' takes IMU biases, not a {} calibration",
+                self.id,
+                calibration.kind()
+            )));
+        };
+        self.calibration = *imu;
+        if let Some(fusion) = &mut self.fusion {
+            fusion.reset();
+        }
+        self.last_read = None;
+        Ok(())
+    }
+    
+    fn metadata(&self) -> SensorMetadata {
+        let orientation = if self.fusion.is_some() { ", then orientation (w, x, y, z)" } else { "" };
+        SensorMetadata {
+            name: self.id.clone(),
+            description: format!(
+                "Acceleration x, y, z (m/s²) and angular rate x, y, z (rad/s) as little-endian f32{}",
+                orientation
+            ),
+            unit: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::memory::MemoryManager;
+    use crate::sensor::SensorManager;
+    
+    /// A driver replaying one sample forever
+    struct Still(ImuSample);
+    
+    impl ImuDriver for Still {
+        fn read_sample(&mut self) -> Result<ImuSample, CoreError> {
+            Ok(self.0)
+        }
+        
+        fn sample_rate(&self) -> f64 {
+            200.0
+        }
+    }
+    
+    #[test]
+    fn test_calibration_removes_biases() {
+        let biased = ImuSample {
+            accel: [0.2, -0.1, GRAVITY + 0.3],
+            gyro: [0.01, 0.02, -0.03],
+            temperature: 25.0,
+        };
+        let mut imu = ImuSensor::new("imu", Still(biased)).with_calibration_samples(10);
+        imu.calibrate().unwrap();
+        let sample = imu.read_sample().unwrap();
+        for axis in 0..3 {
+            assert!(sample.gyro[axis].abs() < 1e-6);
+            assert!((sample.accel[axis] - [0.0, 0.0, GRAVITY][axis]).abs() < 1e-5);
+        }
+        assert_eq!(imu.sample_rate(), Some(200.0));
+    }
+    
+    #[test]
+    fn test_fused_frames_reach_memory_at_the_manager_rate() {
+        let clock = Arc::new(MockClock::new());
+        let imu = ImuSensor::new("imu", Still(ImuSample {
+            accel: [0.0, 0.0, GRAVITY],
+            ..Default::default()
+        }))
+        .with_fusion(OrientationFilter::Madgwick(Madgwick::new(0.1)))
+        .with_clock(clock.clone());
+        assert!(imu.metadata().description.contains("orientation"));
+        
+        let mut manager = SensorManager::new();
+        manager.add(Box::new(imu), Some(50.0)).unwrap();
+        let mut memory = MemoryManager::new();
+        for (millis, reads) in [(0, 1), (10, 0), (20, 1)] {
+            clock.advance(Duration::from_millis(millis) - clock.now());
+            assert_eq!(manager.poll(&mut memory, clock.now()).len(), reads, "at {} ms", millis);
+        }
+        
+        let frame = manager.latest("imu").unwrap();
+        assert_eq!(memory.read(&SensorManager::region_key("imu")), Some(frame.payload.as_slice()));
+        assert_eq!(frame.timestamp, 20_000);
+        // Level and still: gravity on z and no rotation away from identity
+        let values = frame.payload_as_f32();
+        assert_eq!(values.len(), 10);
+        assert_eq!(&values[..6], &[0.0, 0.0, GRAVITY, 0.0, 0.0, 0.0]);
+        assert!((values[6] - 1.0).abs() < 1e-6 && values[7..].iter().all(|v| v.abs() < 1e-6), "{:?}", values);
+    }
+}
@@ -0,0 +1,122 @@
+//! InvenSense MPU-6050 six-axis IMU
+
+use super::{divider, ImuConfig, ImuDriver, ImuSample};
+use crate::error::CoreError;
+use crate::hardware::RegisterDevice;
+
+const SMPLRT_DIV: u8 = 0x19;
+const CONFIG: u8 = 0x1A;
+const GYRO_CONFIG: u8 = 0x1B;
+const ACCEL_CONFIG: u8 = 0x1C;
+const ACCEL_XOUT_H: u8 = 0x3B;
+const PWR_MGMT_1: u8 = 0x6B;
+const WHO_AM_I: u8 = 0x75;
+
+const DEVICE_ID: u8 = 0x68;
+// Wake from sleep, clocked from the x gyroscope's PLL
+const CLOCK_PLL_X: u8 = 0x01;
+// 44 Hz low-pass filter, under which the gyroscope outputs at 1 kHz
+const DLPF_44_HZ: u8 = 0x03;
+const GYRO_OUTPUT_HZ: f64 = 1000.0;
+
+/// An MPU-6050, or the register-compatible MPU-6000 over SPI
+///
+/// Its default I2C address is `0x68`, or `0x69` with AD0 pulled high.
+pub struct Mpu6050<R: RegisterDevice> {
+    registers: R,
+    config: ImuConfig,
+    sample_rate: f64,
+}
+
+impl<R: RegisterDevice> Mpu6050<R> {
+    /// Wake the chip and configure its ranges, low-pass filter and rate
+    ///
+    /// Fails with `CoreError::DeviceNotFound` if the identity register does
+    /// not hold the MPU-6050's.
+    pub fn new(mut registers: R, config: ImuConfig) -> Result<Self, CoreError> {
+        let id = registers.read_register(WHO_AM_I)?;
+        if id != DEVICE_ID {
+            return Err(CoreError::DeviceNotFound(format!(
+                "MPU-6050 identity {:#04x} expected, read {:#04x}",
+                DEVICE_ID, id
+            )));
+        }
+        let divider = divider(GYRO_OUTPUT_HZ, config.sample_rate, u16::from(u8::MAX))?;
+        registers.write_register(PWR_MGMT_1, CLOCK_PLL_X)?;
+        registers.write_register(CONFIG, DLPF_44_HZ)?;
+        registers.write_register(SMPLRT_DIV, divider as u8)?;
+        registers.write_register(GYRO_CONFIG, config.gyro_range.bits() << 3)?;
+        registers.write_register(ACCEL_CONFIG, config.accel_range.bits() << 3)?;
+        Ok(Self {
+            registers,
+            config,
+            sample_rate: GYRO_OUTPUT_HZ / (1.0 + f64::from(divider)),
+        })
+    }
+    
+    pub fn into_inner(self) -> R {
+        self.registers
+    }
+}
+
+impl<R: RegisterDevice> ImuDriver for Mpu6050<R> {
+    fn read_sample(&mut self) -> Result<ImuSample, CoreError> {
+        // Accelerometer, temperature and gyroscope as big-endian i16s
+        let mut raw = [0u8; 14];
+        self.registers.read_registers(ACCEL_XOUT_H, &mut raw)?;
+        let value = |i: usize| f32::from(i16::from_be_bytes([raw[2 * i], raw[2 * i + 1]]));
+        let (accel, gyro) = (self.config.accel_range.scale(), self.config.gyro_range.scale());
+        Ok(ImuSample {
+            accel: [0, 1, 2].map(|axis| value(axis) * accel),
+            gyro: [4, 5, 6].map(|axis| value(axis) * gyro),
+            temperature: value(3) / 340.0 + 36.53,
+        })
+    }
+    
+    fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::{I2cRegisters, MockI2c};
+    use crate::sensor::imu::{AccelRange, GyroRange, GRAVITY};
+    
+    #[test]
+    fn test_configures_and_scales_readings() {
+        let bus = MockI2c::new().with_device(0x68);
+        bus.set_registers(0x68, WHO_AM_I, &[DEVICE_ID]);
+        let config = ImuConfig {
+            accel_range: AccelRange::G4,
+            gyro_range: GyroRange::Dps500,
+            sample_rate: 200.0,
+        };
+        let mut imu = Mpu6050::new(I2cRegisters::new(bus.clone(), 0x68), config).unwrap();
+        assert_eq!(imu.sample_rate(), 200.0);
+        assert_eq!(bus.register(0x68, SMPLRT_DIV), Some(4));
+        assert_eq!(bus.register(0x68, GYRO_CONFIG), Some(0x08));
+        assert_eq!(bus.register(0x68, ACCEL_CONFIG), Some(0x08));
+        assert_eq!(bus.register(0x68, PWR_MGMT_1), Some(CLOCK_PLL_X));
+        
+        // 1 g on z, 0 degrees C, and 65 counts (about 1 degree per second)
+        // on x
+        let mut raw = Vec::new();
+        for value in [0i16, 0, 8192, -12422, 65, 0, 0] {
+            raw.extend_from_slice(&value.to_be_bytes());
+        }
+        bus.set_registers(0x68, ACCEL_XOUT_H, &raw);
+        let sample = imu.read_sample().unwrap();
+        assert!((sample.accel[2] - GRAVITY).abs() < 1e-5);
+        assert!(sample.temperature.abs() < 0.01, "{}", sample.temperature);
+        assert!((sample.gyro[0] - 65.0 / 65.536 * std::f32::consts::PI / 180.0).abs() < 1e-6);
+        
+        let other = MockI2c::new().with_device(0x68);
+        other.set_registers(0x68, WHO_AM_I, &[0x71]);
+        assert!(matches!(
+            Mpu6050::new(I2cRegisters::new(other, 0x68), ImuConfig::default()),
+            Err(CoreError::DeviceNotFound(_))
+        ));
+    }
+}
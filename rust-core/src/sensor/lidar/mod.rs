@@ -0,0 +1,344 @@
+//! Lidar scans and point clouds
+//!
+//! Scans and clouds travel through frames and shared memory as plain
+//! little-endian `f32` values, so algorithms can read them without this
+//! module:
+//!
+//! - a `LaserScan` is `angle_min, angle_increment, range_min, range_max`
+//!   followed by a `(range, intensity)` pair per beam
+//! - a `PointCloud` is an `(x, y, z, intensity)` quadruple per point
+
+use std::collections::BTreeMap;
+use std::f32::consts::TAU;
+
+use crate::algorithm::kinematics::Transform;
+use crate::algorithm::samples;
+use crate::error::CoreError;
+
+mod packet;
+mod sensor;
+
+pub use packet::{
+    parse_ld06, parse_rplidar_node, parse_vlp16, ScanAssembler, ScanPoint, SerialLidarDecoder, SerialLidarFormat,
+    Vlp16Packet,
+};
+pub use sensor::{LidarSensor, Vlp16Sensor};
+
+const SCAN_HEADER: usize = 4;
+
+/// One sweep of a planar lidar, with beams at evenly spaced angles
+///
+/// Angles are in radians counter-clockwise from the sensor's x axis and
+/// ranges in metres. A beam with no return holds `NaN`; ranges outside
+/// `range_min..=range_max` are also treated as no return.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LaserScan {
+    /// Angle of the first beam
+    pub angle_min: f32,
+    /// Angle between consecutive beams
+    pub angle_increment: f32,
+    /// Shortest range the sensor measures
+    pub range_min: f32,
+    /// Longest range the sensor measures
+    pub range_max: f32,
+    /// Range of each beam
+    pub ranges: Vec<f32>,
+    /// Return strength of each beam, or empty if the sensor has none
+    pub intensities: Vec<f32>,
+}
+
+impl LaserScan {
+    /// Angle of beam `index`
+    pub fn angle(&self, index: usize) -> f32 {
+        self.angle_min + self.angle_increment * index as f32
+    }
+    
+    /// Whether `range` is a usable return
+    pub fn is_valid(&self, range: f32) -> bool {
+        range >= self.range_min && range <= self.range_max
+    }
+    
+    /// Intensity of beam `index`, 0 if the scan has none
+    fn intensity(&self, index: usize) -> f32 {
+        self.intensities.get(index).copied().unwrap_or(0.0)
+    }
+    
+    /// Sensor-frame `(x, y)` of each usable return, in beam order
+    pub fn points(&self) -> impl Iterator<Item = [f32; 2]> + '_ {
+        self.ranges
+            .iter()
+            .enumerate()
+            .filter(|&(_, &range)| self.is_valid(range))
+            .map(|(index, &range)| {
+                let angle = self.angle(index);
+                [range * angle.cos(), range * angle.sin()]
+            })
+    }
+    
+    /// The usable returns as a cloud in the sensor's plane
+    pub fn to_point_cloud(&self) -> PointCloud {
+        let mut cloud = PointCloud::default();
+        for (index, &range) in self.ranges.iter().enumerate() {
+            if self.is_valid(range) {
+                let angle = self.angle(index);
+                cloud.points.push([range * angle.cos(), range * angle.sin(), 0.0]);
+                cloud.intensities.push(self.intensity(index));
+            }
+        }
+        cloud
+    }
+    
+    /// Merge each run of `factor` beams into one, keeping the nearest
+    /// usable return so obstacles are never thinned away
+    ///
+    /// The merged beam points at the middle of its run. A trailing run
+    /// shorter than `factor` is merged on its own.
+    pub fn downsample(&self, factor: usize) -> Result<Self, CoreError> {
+        if factor == 0 {
+            return Err(CoreError::InvalidParameter("downsample factor must be at least 1".to_string()));
+        }
+        let mut merged = Self {
+            angle_min: self.angle_min + self.angle_increment * (factor - 1) as f32 / 2.0,
+            angle_increment: self.angle_increment * factor as f32,
+            range_min: self.range_min,
+            range_max: self.range_max,
+            ranges: Vec::with_capacity(self.ranges.len().div_ceil(factor)),
+            intensities: Vec::new(),
+        };
+        for start in (0..self.ranges.len()).step_by(factor) {
+            let end = (start + factor).min(self.ranges.len());
+            let nearest = (start..end)
+                .filter(|&index| self.is_valid(self.ranges[index]))
+                .min_by(|&a, &b| self.ranges[a].total_cmp(&self.ranges[b]));
+            merged.ranges.push(nearest.map_or(f32::NAN, |index| self.ranges[index]));
+            if !self.intensities.is_empty() {
+                merged.intensities.push(nearest.map_or(0.0, |index| self.intensity(index)));
+            }
+        }
+        Ok(merged)
+    }
+    
+    /// Encode the scan as little-endian `f32` values
+    ///
+    /// A scan without intensities is written with intensities of 0.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut values = Vec::with_capacity(SCAN_HEADER + 2 * self.ranges.len());
+        values.extend([self.angle_min, self.angle_increment, self.range_min, self.range_max]);
+        for (index, &range) in self.ranges.iter().enumerate() {
+            values.extend([range, self.intensity(index)]);
+        }
+        samples::f32_to_bytes(&values)
+    }
+    
+    /// Parse bytes produced by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CoreError> {
+        let values = samples::f32_from_bytes(bytes)?;
+        if values.len() < SCAN_HEADER || !(values.len() - SCAN_HEADER).is_multiple_of(2) {
+            return Err(CoreError::InvalidInput(format!(
+                "a laser scan is a 4 value header and (range, intensity) pairs, got {} values",
+                values.len()
+            )));
+        }
+        let beams = &values[SCAN_HEADER..];
+        Ok(Self {
+            angle_min: values[0],
+            angle_increment: values[1],
+            range_min: values[2],
+            range_max: values[3],
+            ranges: beams.iter().step_by(2).copied().collect(),
+            intensities: beams.iter().skip(1).step_by(2).copied().collect(),
+        })
+    }
+}
+
+/// A set of 3-D points in metres, each with a return strength
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PointCloud {
+    /// Position of each point
+    pub points: Vec<[f32; 3]>,
+    /// Return strength of each point, or empty if the sensor has none
+    pub intensities: Vec<f32>,
+}
+
+impl PointCloud {
+    /// Number of points
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+    
+    /// Whether the cloud holds no points
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+    
+    fn intensity(&self, index: usize) -> f32 {
+        self.intensities.get(index).copied().unwrap_or(0.0)
+    }
+    
+    /// Replace the points in each cube of side `size` with their centroid
+    /// and mean intensity
+    ///
+    /// The result is ordered by voxel, so the same cloud always filters to
+    /// the same output.
+    pub fn voxel_filter(&self, size: f32) -> Result<Self, CoreError> {
+        if !(size > 0.0 && size.is_finite()) {
+            return Err(CoreError::InvalidParameter(format!("voxel size must be positive, got {}", size)));
+        }
+        // Sum of positions, sum of intensities and count in each voxel
+        let mut voxels: BTreeMap<[i64; 3], ([f64; 3], f64, usize)> = BTreeMap::new();
+        for (index, point) in self.points.iter().enumerate() {
+            if !point.iter().all(|v| v.is_finite()) {
+                continue;
+            }
+            let key = point.map(|v| (v / size).floor() as i64);
+            let (sum, intensity, count) = voxels.entry(key).or_default();
+            for axis in 0..3 {
+                sum[axis] += f64::from(point[axis]);
+            }
+            *intensity += f64::from(self.intensity(index));
+            *count += 1;
+        }
+        let mut filtered = Self::default();
+        for (sum, intensity, count) in voxels.into_values() {
+            let n = count as f64;
+            filtered.points.push(sum.map(|v| (v / n) as f32));
+            if !self.intensities.is_empty() {
+                filtered.intensities.push((intensity / n) as f32);
+            }
+        }
+        Ok(filtered)
+    }
+    
+    /// Points whose horizontal distance from the origin lies in
+    /// `min..=max`, dropping the sensor's own body and far clutter
+    pub fn crop_range(&self, min: f32, max: f32) -> Self {
+        let mut cropped = Self::default();
+        for (index, point) in self.points.iter().enumerate() {
+            if (min..=max).contains(&point[0].hypot(point[1])) {
+                cropped.points.push(*point);
+                if !self.intensities.is_empty() {
+                    cropped.intensities.push(self.intensity(index));
+                }
+            }
+        }
+        cropped
+    }
+    
+    /// The cloud with every point mapped by `transform`, such as one looked
+    /// up from a `transform::TransformTree` to bring it into the robot's frame
+    pub fn transformed(&self, transform: &Transform) -> Self {
+        Self {
+            points: self
+                .points
+                .iter()
+                .map(|point| transform.apply(point.map(f64::from)).map(|v| v as f32))
+                .collect(),
+            intensities: self.intensities.clone(),
+        }
+    }
+    
+    /// Bin the points by bearing into a planar scan of `beams` beams over a
+    /// full turn, keeping the nearest horizontal range in each
+    ///
+    /// Use it to feed 2-D algorithms from a 3-D sensor after cropping the
+    /// cloud to the heights of interest.
+    pub fn to_laser_scan(&self, beams: usize, range_min: f32, range_max: f32) -> Result<LaserScan, CoreError> {
+        let mut assembler = ScanAssembler::new(beams, range_min, range_max)?;
+        for (index, point) in self.points.iter().enumerate() {
+            assembler.insert(ScanPoint {
+                angle: point[1].atan2(point[0]).rem_euclid(TAU),
+                range: point[0].hypot(point[1]),
+                intensity: self.intensity(index),
+            });
+        }
+        Ok(assembler.finish())
+    }
+    
+    /// Encode the cloud as little-endian `(x, y, z, intensity)` values
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let values: Vec<f32> = self
+            .points
+            .iter()
+            .enumerate()
+            .flat_map(|(index, &[x, y, z])| [x, y, z, self.intensity(index)])
+            .collect();
+        samples::f32_to_bytes(&values)
+    }
+    
+    /// Parse bytes produced by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CoreError> {
+        let values = samples::f32_from_bytes(bytes)?;
+        if !values.len().is_multiple_of(4) {
+            return Err(CoreError::InvalidInput(format!(
+                "{} f32 values do not form whole (x, y, z, intensity) points",
+                values.len()
+            )));
+        }
+        Ok(Self {
+            points: values.chunks_exact(4).map(|point| [point[0], point[1], point[2]]).collect(),
+            intensities: values.chunks_exact(4).map(|point| point[3]).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+    
+    fn scan(ranges: Vec<f32>) -> LaserScan {
+        LaserScan {
+            angle_min: 0.0,
+            angle_increment: FRAC_PI_2 / 2.0,
+            range_min: 0.1,
+            range_max: 10.0,
+            intensities: (0..ranges.len()).map(|i| i as f32).collect(),
+            ranges,
+        }
+    }
+    
+    #[test]
+    fn test_scan_points_downsample_and_round_trip() {
+        let scan = scan(vec![1.0, f32::NAN, 2.0, 0.05, 3.0]);
+        let points: Vec<[f32; 2]> = scan.points().collect();
+        assert_eq!(points.len(), 3);
+        assert!((points[1][0]).abs() < 1e-6 && (points[1][1] - 2.0).abs() < 1e-6);
+        assert_eq!(scan.to_point_cloud().intensities, vec![0.0, 2.0, 4.0]);
+        
+        // Runs of two keep their nearest usable return; the short return
+        // at index 3 is below range_min
+        let merged = scan.downsample(2).unwrap();
+        assert_eq!(merged.angle_increment, FRAC_PI_2);
+        assert_eq!(merged.angle_min, FRAC_PI_2 / 4.0);
+        assert_eq!(merged.ranges, vec![1.0, 2.0, 3.0]);
+        assert_eq!(merged.intensities, vec![0.0, 2.0, 4.0]);
+        assert!(scan.downsample(0).is_err());
+        
+        let decoded = LaserScan::from_bytes(&scan.to_bytes()).unwrap();
+        assert!(decoded.ranges[1].is_nan());
+        assert_eq!(decoded.to_bytes(), scan.to_bytes());
+        assert!(LaserScan::from_bytes(&scan.to_bytes()[..20]).is_err());
+    }
+    
+    #[test]
+    fn test_voxel_filter_crop_and_flatten() {
+        let cloud = PointCloud {
+            points: vec![[0.1, 0.1, 0.0], [0.3, 0.3, 0.2], [1.1, 0.0, 0.0], [-0.2, 0.0, 0.0], [f32::NAN, 0.0, 0.0]],
+            intensities: vec![1.0, 3.0, 5.0, 7.0, 9.0],
+        };
+        let filtered = cloud.voxel_filter(0.5).unwrap();
+        assert_eq!(filtered.points, vec![[-0.2, 0.0, 0.0], [0.2, 0.2, 0.1], [1.1, 0.0, 0.0]]);
+        assert_eq!(filtered.intensities, vec![7.0, 2.0, 5.0]);
+        assert!(cloud.voxel_filter(0.0).is_err());
+        
+        assert_eq!(cloud.crop_range(0.3, 2.0).intensities, vec![3.0, 5.0]);
+        assert_eq!(PointCloud::from_bytes(&filtered.to_bytes()).unwrap(), filtered);
+        
+        // Each centroid lands in the beam nearest its bearing
+        let flat = filtered.to_laser_scan(8, 0.1, 10.0).unwrap();
+        assert_eq!(flat.ranges[0], 1.1);
+        assert!((flat.ranges[1] - 0.2 * 2f32.sqrt()).abs() < 1e-6);
+        assert!((flat.ranges[4] - 0.2).abs() < 1e-6);
+        assert_eq!(flat.ranges.iter().filter(|r| r.is_nan()).count(), 5);
+    }
+}
@@ -0,0 +1,466 @@
+//! Packet formats of common lidars and assembly of their returns into scans
+
+use std::f32::consts::{PI, TAU};
+
+use super::{LaserScan, PointCloud};
+use crate::error::CoreError;
+
+/// One return of a planar lidar
+///
+/// Angles are in radians, in `0..2pi` counter-clockwise from the sensor's
+/// x axis, and ranges in metres.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ScanPoint {
+    pub angle: f32,
+    pub range: f32,
+    pub intensity: f32,
+}
+
+/// Convert a clockwise angle in degrees, as serial lidars report them, to
+/// counter-clockwise radians in `0..2pi`
+fn counter_clockwise(degrees: f32) -> f32 {
+    (-degrees.to_radians()).rem_euclid(TAU)
+}
+
+const LD06_HEADER: [u8; 2] = [0x54, 0x2C];
+const LD06_LEN: usize = 47;
+const LD06_POINTS: usize = 12;
+
+/// CRC-8 with polynomial `0x4D`, as LDROBOT lidars append to each packet
+fn ld06_crc(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |mut crc, &byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x4D } else { crc << 1 };
+        }
+        crc
+    })
+}
+
+fn le16(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+/// Parse one 47-byte packet of an LDROBOT LD06 or LD19
+///
+/// The packet carries 12 returns between a start and an end angle, spread
+/// evenly between them. Returns with a range of 0 are dropped. Fails with
+/// `CoreError::InvalidInput` on a bad header, length or checksum.
+pub fn parse_ld06(packet: &[u8]) -> Result<Vec<ScanPoint>, CoreError> {
+    if packet.len() != LD06_LEN || packet[..2] != LD06_HEADER {
+        return Err(CoreError::InvalidInput(format!(
+            "LD06 packet must be {} bytes starting 54 2C",
+            LD06_LEN
+        )));
+    }
+    if ld06_crc(&packet[..LD06_LEN - 1]) != packet[LD06_LEN - 1] {
+        return Err(CoreError::InvalidInput("LD06 packet checksum mismatch".to_string()));
+    }
+    // Angles in hundredths of a degree
+    let start = f32::from(le16(packet, 4));
+    let end = f32::from(le16(packet, 6 + 3 * LD06_POINTS));
+    let step = (end - start).rem_euclid(36000.0) / (LD06_POINTS - 1) as f32;
+    Ok((0..LD06_POINTS)
+        .filter_map(|i| {
+            let at = 6 + 3 * i;
+            let millimetres = le16(packet, at);
+            (millimetres > 0).then(|| ScanPoint {
+                angle: counter_clockwise((start + step * i as f32) / 100.0),
+                range: f32::from(millimetres) / 1000.0,
+                intensity: f32::from(packet[at + 2]),
+            })
+        })
+        .collect())
+}
+
+const RPLIDAR_NODE_LEN: usize = 5;
+const RPLIDAR_DESCRIPTOR: [u8; 2] = [0xA5, 0x5A];
+const RPLIDAR_DESCRIPTOR_LEN: usize = 7;
+
+/// Parse one 5-byte measurement node of an RPLIDAR's standard scan
+///
+/// Returns `None` for a node without a return. Fails with
+/// `CoreError::InvalidInput` if the node's check bits are wrong, which is
+/// how a reader finds node boundaries in the stream.
+pub fn parse_rplidar_node(node: &[u8]) -> Result<Option<ScanPoint>, CoreError> {
+    let &[flags, angle_low, angle_high, distance_low, distance_high] = node else {
+        return Err(CoreError::InvalidInput(format!("RPLIDAR node must be {} bytes", RPLIDAR_NODE_LEN)));
+    };
+    // The start flag and its inverse, then a check bit that is always set
+    if (flags & 0x01) == ((flags >> 1) & 0x01) || angle_low & 0x01 == 0 {
+        return Err(CoreError::InvalidInput("RPLIDAR node check bits are wrong".to_string()));
+    }
+    // Angle in 1/64 degree and distance in 1/4 mm
+    let angle = (u16::from(angle_high) << 7) | u16::from(angle_low >> 1);
+    let distance = u16::from_le_bytes([distance_low, distance_high]);
+    Ok((distance > 0).then(|| ScanPoint {
+        angle: counter_clockwise(f32::from(angle) / 64.0),
+        range: f32::from(distance) / 4000.0,
+        intensity: f32::from(flags >> 2),
+    }))
+}
+
+/// Packet formats of serial lidars
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SerialLidarFormat {
+    /// LDROBOT LD06 and LD19, streaming on power-up at 230400 baud
+    Ld06,
+    /// Slamtec RPLIDAR A1/A2 standard scan, at 115200 baud on the A1
+    Rplidar,
+}
+
+/// Finds packets in a serial lidar's byte stream and parses them
+///
+/// Bytes may arrive in any chunks. Corrupt packets are skipped by sliding
+/// forward a byte at a time until a valid one lines up, and counted in
+/// `rejected`.
+#[derive(Clone, Debug)]
+pub struct SerialLidarDecoder {
+    format: SerialLidarFormat,
+    buffer: Vec<u8>,
+    rejected: u64,
+}
+
+impl SerialLidarDecoder {
+    pub fn new(format: SerialLidarFormat) -> Self {
+        Self {
+            format,
+            buffer: Vec::new(),
+            rejected: 0,
+        }
+    }
+    
+    pub fn format(&self) -> SerialLidarFormat {
+        self.format
+    }
+    
+    /// Packets skipped as corrupt so far
+    pub fn rejected(&self) -> u64 {
+        self.rejected
+    }
+    
+    /// Add received bytes, returning the returns of every packet they
+    /// complete
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<ScanPoint> {
+        self.buffer.extend_from_slice(bytes);
+        let mut points = Vec::new();
+        let mut start = 0;
+        loop {
+            let rest = &self.buffer[start..];
+            let (len, parsed) = match self.format {
+                SerialLidarFormat::Ld06 => match rest.iter().position(|&b| b == LD06_HEADER[0]) {
+                    // Nothing here can start a packet
+                    None => {
+                        start = self.buffer.len();
+                        break;
+                    }
+                    Some(0) if rest.len() >= LD06_LEN => (LD06_LEN, parse_ld06(&rest[..LD06_LEN])),
+                    Some(0) => break,
+                    Some(skip) => {
+                        start += skip;
+                        continue;
+                    }
+                },
+                SerialLidarFormat::Rplidar if rest.starts_with(&RPLIDAR_DESCRIPTOR) => {
+                    // The response descriptor sent once before the nodes
+                    if rest.len() < RPLIDAR_DESCRIPTOR_LEN {
+                        break;
+                    }
+                    start += RPLIDAR_DESCRIPTOR_LEN;
+                    continue;
+                }
+                SerialLidarFormat::Rplidar if rest.len() >= RPLIDAR_NODE_LEN => (
+                    RPLIDAR_NODE_LEN,
+                    parse_rplidar_node(&rest[..RPLIDAR_NODE_LEN]).map(|point| point.into_iter().collect()),
+                ),
+                SerialLidarFormat::Rplidar => break,
+            };
+            match parsed {
+                Ok(parsed) => {
+                    points.extend(parsed);
+                    start += len;
+                }
+                Err(_) => {
+                    self.rejected += 1;
+                    start += 1;
+                }
+            }
+        }
+        self.buffer.drain(..start);
+        points
+    }
+}
+
+const VLP16_LEN: usize = 1206;
+const VLP16_BLOCKS: usize = 12;
+const VLP16_BLOCK_LEN: usize = 100;
+const VLP16_BLOCK_FLAG: [u8; 2] = [0xFF, 0xEE];
+/// Elevation of each of the 16 lasers in firing order, in degrees
+const VLP16_ELEVATIONS: [f32; 16] = [
+    -15.0, 1.0, -13.0, 3.0, -11.0, 5.0, -9.0, 7.0, -7.0, 9.0, -5.0, 11.0, -3.0, 13.0, -1.0, 15.0,
+];
+
+/// One data packet of a Velodyne VLP-16
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Vlp16Packet {
+    /// Microseconds past the hour at the first firing
+    pub timestamp: u32,
+    /// Clockwise azimuth of the first firing, in radians in `0..2pi`
+    pub azimuth: f32,
+    /// The returns, x forward, y left and z up
+    pub cloud: PointCloud,
+}
+
+/// Parse one 1206-byte UDP data packet of a Velodyne VLP-16 in single
+/// return mode
+///
+/// Each of the 12 blocks holds two firings of all 16 lasers; the second
+/// firing's azimuth is taken halfway to the next block's. Returns with a
+/// range of 0 are dropped. Fails with `CoreError::InvalidInput` on a bad
+/// length or block flag.
+pub fn parse_vlp16(packet: &[u8]) -> Result<Vlp16Packet, CoreError> {
+    if packet.len() != VLP16_LEN {
+        return Err(CoreError::InvalidInput(format!(
+            "VLP-16 data packet must be {} bytes, got {}",
+            VLP16_LEN,
+            packet.len()
+        )));
+    }
+    let blocks: Vec<&[u8]> = packet[..VLP16_BLOCKS * VLP16_BLOCK_LEN].chunks_exact(VLP16_BLOCK_LEN).collect();
+    if blocks.iter().any(|block| block[..2] != VLP16_BLOCK_FLAG) {
+        return Err(CoreError::InvalidInput("VLP-16 block flag is not FF EE".to_string()));
+    }
+    // Azimuths in hundredths of a degree
+    let azimuths: Vec<f32> = blocks.iter().map(|block| f32::from(le16(block, 2))).collect();
+    let mut cloud = PointCloud::default();
+    for (index, block) in blocks.iter().enumerate() {
+        let gap = if index + 1 < VLP16_BLOCKS {
+            azimuths[index + 1] - azimuths[index]
+        } else {
+            azimuths[index] - azimuths[index - 1]
+        }
+        .rem_euclid(36000.0);
+        for channel in 0..32 {
+            let at = 4 + 3 * channel;
+            let distance = le16(block, at);
+            if distance == 0 {
+                continue;
+            }
+            let firing = if channel < 16 { 0.0 } else { gap / 2.0 };
+            let azimuth = ((azimuths[index] + firing) / 100.0).to_radians();
+            let elevation = VLP16_ELEVATIONS[channel % 16].to_radians();
+            // Distance in 2 mm units
+            let range = f32::from(distance) * 0.002;
+            let horizontal = range * elevation.cos();
+            cloud.points.push([
+                horizontal * azimuth.cos(),
+                -horizontal * azimuth.sin(),
+                range * elevation.sin(),
+            ]);
+            cloud.intensities.push(f32::from(block[at + 2]));
+        }
+    }
+    let tail = VLP16_BLOCKS * VLP16_BLOCK_LEN;
+    Ok(Vlp16Packet {
+        timestamp: u32::from_le_bytes([packet[tail], packet[tail + 1], packet[tail + 2], packet[tail + 3]]),
+        azimuth: (azimuths[0] / 100.0).to_radians().rem_euclid(TAU),
+        cloud,
+    })
+}
+
+/// Collects a rotating lidar's returns into one `LaserScan` per revolution
+///
+/// Returns are binned by angle into `beams` beams over a full turn starting
+/// at angle 0, keeping the nearest usable return in each bin; empty bins
+/// hold `NaN`.
+#[derive(Clone, Debug)]
+pub struct ScanAssembler {
+    scan: LaserScan,
+    last_angle: Option<f32>,
+    started: bool,
+}
+
+impl ScanAssembler {
+    pub fn new(beams: usize, range_min: f32, range_max: f32) -> Result<Self, CoreError> {
+        if beams == 0 {
+            return Err(CoreError::InvalidParameter("a scan needs at least 1 beam".to_string()));
+        }
+        if !(range_min >= 0.0 && range_min < range_max) {
+            return Err(CoreError::InvalidParameter(format!(
+                "range limits {}..{} are not an increasing, non-negative range",
+                range_min, range_max
+            )));
+        }
+        Ok(Self {
+            scan: LaserScan {
+                angle_min: 0.0,
+                angle_increment: TAU / beams as f32,
+                range_min,
+                range_max,
+                ranges: vec![f32::NAN; beams],
+                intensities: vec![0.0; beams],
+            },
+            last_angle: None,
+            started: false,
+        })
+    }
+    
+    /// Bin a return into the scan in progress
+    pub fn insert(&mut self, point: ScanPoint) {
+        if !self.scan.is_valid(point.range) {
+            return;
+        }
+        let beams = self.scan.ranges.len();
+        let bin = (point.angle.rem_euclid(TAU) / self.scan.angle_increment).round() as usize % beams;
+        if self.scan.ranges[bin].is_nan() || point.range < self.scan.ranges[bin] {
+            self.scan.ranges[bin] = point.range;
+            self.scan.intensities[bin] = point.intensity;
+        }
+    }
+    
+    /// Take the scan in progress and start an empty one
+    pub fn finish(&mut self) -> LaserScan {
+        let empty = LaserScan {
+            ranges: vec![f32::NAN; self.scan.ranges.len()],
+            intensities: vec![0.0; self.scan.ranges.len()],
+            ..self.scan.clone()
+        };
+        std::mem::replace(&mut self.scan, empty)
+    }
+    
+    /// Add a return in arrival order, returning the finished scan when the
+    /// return starts a new revolution
+    ///
+    /// A revolution ends where the angle jumps by more than half a turn, so
+    /// either direction of rotation works. Returns before the first such
+    /// jump are discarded, since the sweep they belong to was joined part
+    /// way.
+    pub fn push(&mut self, point: ScanPoint) -> Option<LaserScan> {
+        let wrapped = self.last_angle.is_some_and(|last| (point.angle - last).abs() > PI);
+        self.last_angle = Some(point.angle);
+        let finished = if wrapped {
+            let scan = self.finish();
+            std::mem::replace(&mut self.started, true).then_some(scan)
+        } else {
+            None
+        };
+        if self.started {
+            self.insert(point);
+        }
+        finished
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    
+    /// An LD06 packet with the returns `(mm, intensity)` from `start` to
+    /// `end` hundredths of a degree
+    pub(in crate::sensor::lidar) fn ld06_packet(start: u16, end: u16, returns: &[(u16, u8); 12]) -> Vec<u8> {
+        let mut packet = LD06_HEADER.to_vec();
+        packet.extend_from_slice(&3600u16.to_le_bytes());
+        packet.extend_from_slice(&start.to_le_bytes());
+        for &(mm, intensity) in returns {
+            packet.extend_from_slice(&mm.to_le_bytes());
+            packet.push(intensity);
+        }
+        packet.extend_from_slice(&end.to_le_bytes());
+        packet.extend_from_slice(&1234u16.to_le_bytes());
+        packet.push(ld06_crc(&packet));
+        packet
+    }
+    
+    /// A VLP-16 packet whose blocks start at `azimuth` hundredths of a
+    /// degree, 20 apart, with every laser returning `mm`
+    pub(in crate::sensor::lidar) fn vlp16_packet(azimuth: u16, mm: u16) -> Vec<u8> {
+        let mut packet = Vec::with_capacity(VLP16_LEN);
+        for block in 0..VLP16_BLOCKS as u16 {
+            packet.extend_from_slice(&VLP16_BLOCK_FLAG);
+            packet.extend_from_slice(&((azimuth + 20 * block) % 36000).to_le_bytes());
+            for _ in 0..32 {
+                packet.extend_from_slice(&(mm / 2).to_le_bytes());
+                packet.push(100);
+            }
+        }
+        packet.extend_from_slice(&5_000u32.to_le_bytes());
+        packet.extend_from_slice(&[0x37, 0x22]);
+        packet
+    }
+    
+    #[test]
+    fn test_ld06_and_rplidar_streams_decode() {
+        let mut returns = [(1000u16, 200u8); 12];
+        returns[3] = (0, 0);
+        // 0 to 11 degrees clockwise
+        let packet = ld06_packet(0, 1100, &returns);
+        let points = parse_ld06(&packet).unwrap();
+        assert_eq!(points.len(), 11);
+        assert_eq!(points[0], ScanPoint { angle: 0.0, range: 1.0, intensity: 200.0 });
+        assert!((points[1].angle - (TAU - 1f32.to_radians())).abs() < 1e-5);
+        
+        let mut corrupt = packet.clone();
+        corrupt[10] ^= 0xFF;
+        assert!(parse_ld06(&corrupt).is_err());
+        
+        // Noise, a corrupt packet and a good one split mid-packet
+        let mut decoder = SerialLidarDecoder::new(SerialLidarFormat::Ld06);
+        let mut stream = vec![0x00, 0x54, 0x13];
+        stream.extend_from_slice(&corrupt);
+        stream.extend_from_slice(&packet);
+        assert!(decoder.push(&stream[..60]).is_empty());
+        assert_eq!(decoder.push(&stream[60..]).len(), 11);
+        assert!(decoder.rejected() >= 1);
+        
+        // Start node at 90 degrees clockwise and 500 mm, then a node with no
+        // return
+        let start = [(10 << 2) | 0x01, (((90 * 64) & 0x7F) << 1) as u8 | 0x01, ((90 * 64) >> 7) as u8, 0xD0, 0x07];
+        let point = parse_rplidar_node(&start).unwrap().unwrap();
+        assert!((point.angle - 1.5 * PI).abs() < 1e-5 && point.range == 0.5 && point.intensity == 10.0);
+        let empty = [0x02, 0x01, 0x00, 0x00, 0x00];
+        assert_eq!(parse_rplidar_node(&empty).unwrap(), None);
+        assert!(parse_rplidar_node(&[0x03, 0x01, 0, 0, 0]).is_err());
+        
+        let mut decoder = SerialLidarDecoder::new(SerialLidarFormat::Rplidar);
+        let mut stream = vec![0xA5, 0x5A, 0x05, 0x00, 0x00, 0x40, 0x81];
+        stream.extend_from_slice(&start);
+        stream.extend_from_slice(&empty);
+        stream.extend_from_slice(&start[..2]);
+        assert_eq!(decoder.push(&stream), vec![point]);
+        assert_eq!(decoder.push(&start[2..]), vec![point]);
+        assert_eq!(decoder.rejected(), 0);
+    }
+    
+    #[test]
+    fn test_vlp16_packet_geometry() {
+        let packet = parse_vlp16(&vlp16_packet(9000, 2000)).unwrap();
+        assert_eq!(packet.timestamp, 5_000);
+        assert!((packet.azimuth - PI / 2.0).abs() < 1e-6);
+        assert_eq!(packet.cloud.len(), 12 * 32);
+        // First laser of the first firing: 90 degrees clockwise (to the
+        // right) and 15 degrees down
+        let [x, y, z] = packet.cloud.points[0];
+        let down = 15f32.to_radians();
+        assert!(x.abs() < 1e-5 && (y + 2.0 * down.cos()).abs() < 1e-5 && (z + 2.0 * down.sin()).abs() < 1e-5);
+        assert!(parse_vlp16(&vlp16_packet(0, 2000)[1..]).is_err());
+    }
+    
+    #[test]
+    fn test_assembler_emits_whole_revolutions() {
+        let mut assembler = ScanAssembler::new(4, 0.1, 10.0).unwrap();
+        let point = |angle: f32, range: f32| ScanPoint { angle, range, intensity: range };
+        // Joined part way through a sweep
+        assert_eq!(assembler.push(point(5.0, 1.0)), None);
+        assert_eq!(assembler.push(point(0.1, 2.0)), None);
+        assert_eq!(assembler.push(point(0.2, 1.5)), None);
+        assert_eq!(assembler.push(point(PI, 0.05)), None);
+        assert_eq!(assembler.push(point(1.4 * PI, 3.0)), None);
+        let scan = assembler.push(point(0.0, 4.0)).unwrap();
+        assert_eq!(scan.ranges[0], 1.5);
+        assert_eq!(scan.ranges[3], 3.0);
+        assert!(scan.ranges[1].is_nan() && scan.ranges[2].is_nan());
+        assert_eq!(assembler.finish().ranges[0], 4.0);
+        assert!(ScanAssembler::new(0, 0.1, 10.0).is_err());
+        assert!(ScanAssembler::new(4, 1.0, 1.0).is_err());
+    }
+}
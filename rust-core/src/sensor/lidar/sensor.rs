@@ -0,0 +1,286 @@
+//! Sensors delivering one lidar revolution per frame
+
+use std::collections::VecDeque;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use super::packet::{parse_vlp16, ScanAssembler, SerialLidarDecoder, SerialLidarFormat};
+use super::{LaserScan, PointCloud};
+use crate::error::CoreError;
+use crate::hardware::HardwareInterface;
+use crate::sensor::{Sensor, SensorFrame, SensorMetadata};
+
+// Start a standard scan on an RPLIDAR
+const RPLIDAR_SCAN: [u8; 2] = [0xA5, 0x20];
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(1);
+
+fn elapsed_micros(started: Instant) -> u64 {
+    u64::try_from(started.elapsed().as_micros()).unwrap_or(u64::MAX)
+}
+
+/// A planar lidar on a serial line, delivering a `LaserScan` per revolution
+///
+/// Each frame's payload is the scan encoded by `LaserScan::to_bytes`. A
+/// read fails with `CoreError::SensorError` if the line stays silent for
+/// the idle timeout, one second unless changed with `with_idle_timeout`.
+pub struct LidarSensor {
+    id: String,
+    interface: Box<dyn HardwareInterface>,
+    decoder: SerialLidarDecoder,
+    assembler: ScanAssembler,
+    ready: VecDeque<LaserScan>,
+    idle_timeout: Duration,
+    started: Instant,
+}
+
+impl LidarSensor {
+    /// Create a sensor `id` reading `format` packets from `interface` into
+    /// scans of `beams` beams
+    ///
+    /// An RPLIDAR is sent the command to start scanning.
+    pub fn new(
+        id: &str,
+        mut interface: Box<dyn HardwareInterface>,
+        format: SerialLidarFormat,
+        beams: usize,
+        range_min: f32,
+        range_max: f32,
+    ) -> Result<Self, CoreError> {
+        let assembler = ScanAssembler::new(beams, range_min, range_max)?;
+        if format == SerialLidarFormat::Rplidar {
+            interface.write_serial(&RPLIDAR_SCAN)?;
+        }
+        Ok(Self {
+            id: id.to_string(),
+            interface,
+            decoder: SerialLidarDecoder::new(format),
+            assembler,
+            ready: VecDeque::new(),
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            started: Instant::now(),
+        })
+    }
+    
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+    
+    /// Packets skipped as corrupt so far
+    pub fn rejected(&self) -> u64 {
+        self.decoder.rejected()
+    }
+    
+    /// Read until a revolution completes
+    ///
+    /// Revolutions that complete within the same read are queued and
+    /// returned by the following calls.
+    pub fn read_scan(&mut self) -> Result<LaserScan, CoreError> {
+        let mut buffer = [0u8; 512];
+        let mut last_data = Instant::now();
+        loop {
+            if let Some(scan) = self.ready.pop_front() {
+                return Ok(scan);
+            }
+            let count = self.interface.read_serial(&mut buffer)?;
+            if count == 0 {
+                if last_data.elapsed() >= self.idle_timeout {
+                    return Err(CoreError::SensorError(format!(
+                        "lidar {} sent nothing for {:?}",
+                        self.id, self.idle_timeout
+                    )));
+                }
+                continue;
+            }
+            last_data = Instant::now();
+            for point in self.decoder.push(&buffer[..count]) {
+                self.ready.extend(self.assembler.push(point));
+            }
+        }
+    }
+}
+
+impl Sensor for LidarSensor {
+    fn id(&self) -> &str {
+        &self.id
+    }
+    
+    fn read_frame(&mut self) -> Result<SensorFrame, CoreError> {
+        let scan = self.read_scan()?;
+        Ok(SensorFrame {
+            sensor_id: self.id.clone(),
+            timestamp: elapsed_micros(self.started),
+            payload: scan.to_bytes(),
+            ..Default::default()
+        })
+    }
+    
+    fn metadata(&self) -> SensorMetadata {
+        SensorMetadata {
+            name: self.id.clone(),
+            description: format!("{:?} lidar scans (LaserScan layout)", self.decoder.format()),
+            unit: Some("m".to_string()),
+        }
+    }
+}
+
+/// A Velodyne VLP-16 streaming over UDP, delivering a `PointCloud` per
+/// revolution
+///
+/// Each frame's payload is the cloud encoded by `PointCloud::to_bytes`. A
+/// revolution ends when a packet's azimuth falls below the previous one's;
+/// the revolution under way when the sensor starts is discarded.
+pub struct Vlp16Sensor {
+    id: String,
+    socket: UdpSocket,
+    cloud: PointCloud,
+    last_azimuth: Option<f32>,
+    started_revolution: bool,
+    started: Instant,
+}
+
+impl Vlp16Sensor {
+    /// Create a sensor `id` receiving data packets on `address`, usually
+    /// `0.0.0.0:2368`
+    pub fn bind(id: &str, address: impl ToSocketAddrs) -> Result<Self, CoreError> {
+        Ok(Self {
+            id: id.to_string(),
+            socket: UdpSocket::bind(address)?,
+            cloud: PointCloud::default(),
+            last_azimuth: None,
+            started_revolution: false,
+            started: Instant::now(),
+        })
+    }
+    
+    /// Address the socket is bound to
+    pub fn local_addr(&self) -> Result<SocketAddr, CoreError> {
+        Ok(self.socket.local_addr()?)
+    }
+    
+    /// Fail a read with `CoreError::IoError` after `timeout` without a
+    /// packet, or wait indefinitely if `None`
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<(), CoreError> {
+        Ok(self.socket.set_read_timeout(timeout)?)
+    }
+    
+    /// Receive packets until a revolution completes
+    ///
+    /// A packet that is not a valid data packet fails the read and is
+    /// dropped; the revolution under way is kept.
+    pub fn read_cloud(&mut self) -> Result<PointCloud, CoreError> {
+        let mut buffer = [0u8; 1500];
+        loop {
+            let count = self.socket.recv(&mut buffer)?;
+            let packet = parse_vlp16(&buffer[..count])?;
+            let wrapped = self.last_azimuth.is_some_and(|last| packet.azimuth < last);
+            self.last_azimuth = Some(packet.azimuth);
+            if wrapped {
+                let cloud = std::mem::replace(&mut self.cloud, packet.cloud);
+                if std::mem::replace(&mut self.started_revolution, true) {
+                    return Ok(cloud);
+                }
+            } else if self.started_revolution {
+                self.cloud.points.extend(packet.cloud.points);
+                self.cloud.intensities.extend(packet.cloud.intensities);
+            }
+        }
+    }
+}
+
+impl Sensor for Vlp16Sensor {
+    fn id(&self) -> &str {
+        &self.id
+    }
+    
+    fn read_frame(&mut self) -> Result<SensorFrame, CoreError> {
+        let cloud = self.read_cloud()?;
+        Ok(SensorFrame {
+            sensor_id: self.id.clone(),
+            timestamp: elapsed_micros(self.started),
+            payload: cloud.to_bytes(),
+            ..Default::default()
+        })
+    }
+    
+    fn metadata(&self) -> SensorMetadata {
+        SensorMetadata {
+            name: self.id.clone(),
+            description: "VLP-16 point clouds (PointCloud layout)".to_string(),
+            unit: Some("m".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::MockHardware;
+    use crate::sensor::lidar::packet::tests::{ld06_packet, vlp16_packet};
+    
+    #[test]
+    fn test_serial_lidar_frames_hold_whole_scans() {
+        let line = MockHardware::new("ld06");
+        let mut sensor = LidarSensor::new("front", Box::new(line.clone()), SerialLidarFormat::Ld06, 360, 0.02, 12.0)
+            .unwrap()
+            .with_idle_timeout(Duration::from_millis(20));
+        assert!(line.serial_written().is_empty());
+        
+        // Two and a bit revolutions of 30 packets, 12 degrees each, with the
+        // range growing by packet
+        for revolution in 0..3u16 {
+            for packet in 0..30u16 {
+                let start = packet * 1200;
+                let range = 1000 + 10 * packet + revolution;
+                line.queue_serial(&ld06_packet(start, start + 1100, &[(range, 50); 12]));
+                if revolution == 2 && packet == 0 {
+                    break;
+                }
+            }
+        }
+        let frame = sensor.read_frame().unwrap();
+        let scan = LaserScan::from_bytes(&frame.payload).unwrap();
+        assert_eq!(scan.ranges.len(), 360);
+        // Clockwise angles count down counter-clockwise, so a revolution
+        // runs from 359 degrees down to the next 0
+        assert_eq!(scan.ranges[359], 1.0);
+        assert_eq!(scan.ranges[348], 1.01);
+        assert_eq!(scan.ranges[0], 1.001);
+        assert!(scan.ranges.iter().all(|r| r.is_finite()));
+        let scan = LaserScan::from_bytes(&sensor.read_frame().unwrap().payload).unwrap();
+        assert_eq!((scan.ranges[359], scan.ranges[0]), (1.001, 1.002));
+        assert!(matches!(sensor.read_frame(), Err(CoreError::SensorError(_))));
+        
+        let rplidar = MockHardware::new("a1");
+        LidarSensor::new("rear", Box::new(rplidar.clone()), SerialLidarFormat::Rplidar, 360, 0.15, 12.0).unwrap();
+        assert_eq!(rplidar.serial_written(), RPLIDAR_SCAN.to_vec());
+    }
+    
+    #[test]
+    fn test_vlp16_frames_hold_whole_revolutions() {
+        let mut sensor = Vlp16Sensor::bind("roof", "127.0.0.1:0").unwrap();
+        sensor.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = sensor.local_addr().unwrap();
+        // Packets 30 degrees apart, so a revolution is 12 of them, starting
+        // part way through the first
+        for revolution in 0..3u16 {
+            for packet in 0..12u16 {
+                if revolution == 0 && packet < 6 {
+                    continue;
+                }
+                sender.send_to(&vlp16_packet(packet * 3000, 2000 + 2 * revolution), address).unwrap();
+                if revolution == 2 {
+                    break;
+                }
+            }
+        }
+        sender.send_to(&[0u8; 10], address).unwrap();
+        
+        let cloud = PointCloud::from_bytes(&sensor.read_frame().unwrap().payload).unwrap();
+        assert_eq!(cloud.len(), 12 * 12 * 32);
+        let range = |[x, y, z]: [f32; 3]| (x * x + y * y + z * z).sqrt();
+        assert!(cloud.points.iter().all(|&point| (range(point) - 2.002).abs() < 1e-4));
+        assert!(matches!(sensor.read_frame(), Err(CoreError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,285 @@
+//! Polling registered sensors into memory regions
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{Sensor, SensorFrame, SensorMetadata};
+use crate::calibration::CalibrationStore;
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+use crate::telemetry::Telemetry;
+
+/// Outcome of one sensor read triggered by `SensorManager::poll`
+#[derive(Clone, Debug, PartialEq)]
+pub struct SensorPoll {
+    /// ID of the sensor that was read
+    pub sensor_id: String,
+    /// Timestamp of the frame that was read
+    pub result: Result<u64, CoreError>,
+}
+
+struct Entry {
+    sensor: Box<dyn Sensor>,
+    period: Duration,
+    // `None` until the first read, which is due immediately
+    next_due: Option<Duration>,
+    latest: Option<SensorFrame>,
+}
+
+/// Reads sensors at their rates on the caller's thread and publishes the
+/// latest payloads to memory
+///
+/// Like `Scheduler`, nothing runs in the background: each `poll` reads the
+/// sensors that are due at the given time. Each frame's payload replaces
+/// the region at `region_key(id)`, so algorithms read the latest reading
+/// from memory without knowing about sensors. A sensor that falls more
+/// than a period behind skips the missed reads rather than reading in a
+/// burst, and a failed read leaves its region and latest frame as they were.
+pub struct SensorManager {
+    entries: Vec<Entry>,
+    telemetry: Option<Arc<Telemetry>>,
+    calibrations: Option<Arc<CalibrationStore>>,
+}
+
+impl SensorManager {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            telemetry: None,
+            calibrations: None,
+        }
+    }
+    
+    /// Record every read in `telemetry`, usually `CoreEngine::telemetry`
+    pub fn with_telemetry(mut self, telemetry: Arc<Telemetry>) -> Self {
+        self.telemetry = Some(telemetry);
+        self
+    }
+    
+    /// Start sensors added from now on from their calibration in `store`,
+    /// and keep what `start_calibration` measures there
+    pub fn with_calibration_store(mut self, store: Arc<CalibrationStore>) -> Self {
+        self.calibrations = Some(store);
+        self
+    }
+    
+    pub fn calibration_store(&self) -> Option<&Arc<CalibrationStore>> {
+        self.calibrations.as_ref()
+    }
+    
+    /// Memory region holding the latest payload of sensor `id`
+    pub fn region_key(id: &str) -> String {
+        format!("sensor/{}", id)
+    }
+    
+    /// Poll `sensor` at `rate_hz`, or at its own `sample_rate` if `None`
+    ///
+    /// Fails if another sensor has the same ID, or if there is no rate or it
+    /// is not finite and positive. With a calibration store, a calibration
+    /// stored under the sensor's ID is applied first, and the sensor is
+    /// not added if that fails.
+    pub fn add(&mut self, mut sensor: Box<dyn Sensor>, rate_hz: Option<f64>) -> Result<(), CoreError> {
+        let id = sensor.id().to_string();
+        if self.position(&id).is_some() {
+            return Err(CoreError::InvalidParameter(format!("sensor '{}' is already registered", id)));
+        }
+        let period = rate_hz
+            .or_else(|| sensor.sample_rate())
+            .filter(|rate| rate.is_finite() && *rate > 0.0)
+            .and_then(|rate| Duration::try_from_secs_f64(1.0 / rate).ok())
+            .filter(|period| !period.is_zero())
+            .ok_or_else(|| {
+                CoreError::InvalidParameter(format!(
+                    "sensor '{}' needs a positive, representable polling rate",
+                    id
+                ))
+            })?;
+        if let Some(record) = self.calibrations.as_ref().and_then(|store| store.get(&id)) {
+            sensor.set_calibration(&record.calibration)?;
+        }
+        self.entries.push(Entry {
+            sensor,
+            period,
+            next_due: None,
+            latest: None,
+        });
+        Ok(())
+    }
+    
+    /// Stop polling sensor `id` and hand it back
+    pub fn remove(&mut self, id: &str) -> Result<Box<dyn Sensor>, CoreError> {
+        let index = self.position(id).ok_or_else(|| Self::not_found(id))?;
+        Ok(self.entries.remove(index).sensor)
+    }
+    
+    /// IDs of the registered sensors, in registration order
+    pub fn ids(&self) -> Vec<&str> {
+        self.entries.iter().map(|entry| entry.sensor.id()).collect()
+    }
+    
+    /// Metadata of sensor `id`
+    pub fn metadata(&self, id: &str) -> Option<SensorMetadata> {
+        self.entry(id).map(|entry| entry.sensor.metadata())
+    }
+    
+    /// The last frame read from sensor `id`, if any read succeeded
+    pub fn latest(&self, id: &str) -> Option<&SensorFrame> {
+        self.entry(id).and_then(|entry| entry.latest.as_ref())
+    }
+    
+    /// Calibrate sensor `id`
+    pub fn calibrate(&mut self, id: &str) -> Result<(), CoreError> {
+        let index = self.position(id).ok_or_else(|| Self::not_found(id))?;
+        self.entries[index].sensor.calibrate()
+    }
+    
+    /// Calibrate every sensor, stopping at the first failure
+    pub fn calibrate_all(&mut self) -> Result<(), CoreError> {
+        self.entries.iter_mut().try_for_each(|entry| entry.sensor.calibrate())
+    }
+    
+    /// Read the sensors due at `now` into `memory`, reporting each read in
+    /// registration order
+    ///
+    /// Regions are created as needed, so under
+    /// `MissingKeyPolicy::Error` they must be allocated up front.
+    pub fn poll(&mut self, memory: &mut MemoryManager, now: Duration) -> Vec<SensorPoll> {
+        let mut polls = Vec::new();
+        for entry in &mut self.entries {
+            let due = entry.next_due.unwrap_or(now);
+            if due > now {
+                continue;
+            }
+            let next_due = due.saturating_add(entry.period);
+            entry.next_due = Some(if next_due > now {
+                next_due
+            } else {
+                now.saturating_add(entry.period)
+            });
+            
+            let sensor_id = entry.sensor.id().to_string();
+            let result = entry.sensor.read_frame().and_then(|frame| {
+                memory.replace_region(&Self::region_key(&sensor_id), frame.payload.clone())?;
+                let timestamp = frame.timestamp;
+                entry.latest = Some(frame);
+                Ok(timestamp)
+            });
+            if let Err(e) = &result {
+                log::warn!("Sensor {} poll failed: {}", sensor_id, e);
+            }
+            if let Some(telemetry) = &self.telemetry {
+                match &result {
+                    Ok(timestamp) => telemetry.record_sensor_frame(&sensor_id, *timestamp),
+                    Err(e) => telemetry.record_sensor_error(&sensor_id, e),
+                }
+            }
+            polls.push(SensorPoll { sensor_id, result });
+        }
+        polls
+    }
+    
+    fn position(&self, id: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.sensor.id() == id)
+    }
+    
+    fn entry(&self, id: &str) -> Option<&Entry> {
+        self.position(id).map(|index| &self.entries[index])
+    }
+    
+    pub(crate) fn sensor_mut(&mut self, id: &str) -> Result<&mut dyn Sensor, CoreError> {
+        let index = self.position(id).ok_or_else(|| Self::not_found(id))?;
+        Ok(self.entries[index].sensor.as_mut())
+    }
+    
+    fn not_found(id: &str) -> CoreError {
+        CoreError::InvalidParameter(format!("no sensor '{}' is registered", id))
+    }
+}
+
+impl Default for SensorManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensor::{FunctionSensor, MockSensor};
+    
+    fn counter(id: &str, interval: Duration) -> Box<dyn Sensor> {
+        Box::new(FunctionSensor::new(id, interval, |t| t.to_le_bytes().to_vec()).unwrap())
+    }
+    
+    #[test]
+    fn test_sensors_polled_at_their_rates_into_memory() {
+        let mut manager = SensorManager::new();
+        manager.add(counter("fast", Duration::from_millis(10)), None).unwrap();
+        manager.add(counter("slow", Duration::from_millis(10)), Some(25.0)).unwrap();
+        let mut memory = MemoryManager::new();
+        
+        let mut reads = std::collections::HashMap::new();
+        for ms in (0..100).step_by(5) {
+            for poll in manager.poll(&mut memory, Duration::from_millis(ms)) {
+                assert!(poll.result.is_ok());
+                *reads.entry(poll.sensor_id).or_insert(0) += 1;
+            }
+        }
+        assert_eq!(reads["fast"], 10);
+        assert_eq!(reads["slow"], 3);
+        
+        // The slow sensor's own interval stamps its frames; the rate only
+        // decides when it is read
+        let latest = manager.latest("slow").unwrap();
+        assert_eq!(latest.timestamp, 20_000);
+        let region = memory.read(&SensorManager::region_key("slow")).unwrap();
+        assert_eq!(region, 20_000u64.to_le_bytes());
+    }
+    
+    #[test]
+    fn test_calibration_reaches_published_readings() {
+        let mut manager = SensorManager::new();
+        manager
+            .add(Box::new(MockSensor::new("gyro", 100.0, vec![0.25, 0.75]).unwrap()), None)
+            .unwrap();
+        let mut memory = MemoryManager::new();
+        manager.calibrate("gyro").unwrap();
+        manager.poll(&mut memory, Duration::ZERO);
+        assert_eq!(manager.latest("gyro").unwrap().payload_as_f32(), vec![0.5]);
+        assert_eq!(manager.metadata("gyro").unwrap().name, "gyro");
+        assert!(manager.calibrate("missing").is_err());
+    }
+    
+    #[test]
+    fn test_registration_rejects_duplicates_and_missing_rates() {
+        struct Unrated;
+        
+        impl Sensor for Unrated {
+            fn id(&self) -> &str {
+                "unrated"
+            }
+            
+            fn read_frame(&mut self) -> Result<SensorFrame, CoreError> {
+                Err(CoreError::SensorError("unplugged".to_string()))
+            }
+        }
+        
+        let mut manager = SensorManager::new();
+        manager.add(counter("imu", Duration::from_millis(1)), None).unwrap();
+        assert!(manager.add(counter("imu", Duration::from_millis(1)), None).is_err());
+        assert!(manager.add(Box::new(Unrated), None).is_err());
+        assert!(manager.add(Box::new(Unrated), Some(-1.0)).is_err());
+        
+        // A failed read is reported and publishes nothing
+        manager.add(Box::new(Unrated), Some(10.0)).unwrap();
+        assert_eq!(manager.ids(), vec!["imu", "unrated"]);
+        let mut memory = MemoryManager::new();
+        let polls = manager.poll(&mut memory, Duration::ZERO);
+        assert!(polls[1].result.is_err());
+        assert!(manager.latest("unrated").is_none());
+        assert!(memory.read(&SensorManager::region_key("unrated")).is_none());
+        
+        assert_eq!(manager.remove("imu").unwrap().id(), "imu");
+        assert!(manager.remove("imu").is_err());
+    }
+}
@@ -0,0 +1,134 @@
+//! Scripted sensor for tests and simulations
+
+use super::{Sensor, SensorFrame, SensorMetadata};
+use crate::calibration::Calibration;
+use crate::error::CoreError;
+
+/// A sensor replaying a fixed script of `f32` samples, with a simulated
+/// zero-offset calibration
+///
+/// Each frame carries one sample in native byte order, looping back to the
+/// start of the script after the last, and is stamped `1 / rate_hz` after
+/// the previous one starting at 0. `calibrate` takes the next sample as a
+/// reading at rest and subtracts it from every later sample, as zeroing a
+/// gyro's bias would.
+pub struct MockSensor {
+    id: String,
+    rate_hz: f64,
+    samples: Vec<f32>,
+    position: usize,
+    frames: u64,
+    offset: f32,
+}
+
+impl MockSensor {
+    /// Create a sensor replaying `samples` at `rate_hz`
+    ///
+    /// Fails if the script is empty or the rate is not finite and positive.
+    pub fn new(id: &str, rate_hz: f64, samples: Vec<f32>) -> Result<Self, CoreError> {
+        if samples.is_empty() {
+            return Err(CoreError::InvalidParameter(format!("mock sensor '{}' needs samples", id)));
+        }
+        if !rate_hz.is_finite() || rate_hz <= 0.0 {
+            return Err(CoreError::InvalidParameter(format!(
+                "rate must be finite and positive, got {}",
+                rate_hz
+            )));
+        }
+        Ok(Self {
+            id: id.to_string(),
+            rate_hz,
+            samples,
+            position: 0,
+            frames: 0,
+            offset: 0.0,
+        })
+    }
+    
+    /// Offset subtracted from samples since the last calibration
+    pub fn offset(&self) -> f32 {
+        self.offset
+    }
+    
+    fn next_sample(&mut self) -> f32 {
+        let sample = self.samples[self.position];
+        self.position = (self.position + 1) % self.samples.len();
+        sample
+    }
+}
+
+impl Sensor for MockSensor {
+    fn id(&self) -> &str {
+        &self.id
+    }
+    
+    fn read_frame(&mut self) -> Result<SensorFrame, CoreError> {
+        let timestamp = (self.frames as f64 * 1e6 / self.rate_hz) as u64;
+        self.frames += 1;
+        let value = self.next_sample() - self.offset;
+        Ok(SensorFrame {
+            sensor_id: self.id.clone(),
+            timestamp,
+            payload: value.to_ne_bytes().to_vec(),
+            ..Default::default()
+        })
+    }
+    
+    fn sample_rate(&self) -> Option<f64> {
+        Some(self.rate_hz)
+    }
+    
+    fn calibrate(&mut self) -> Result<(), CoreError> {
+        self.offset = self.next_sample();
+        Ok(())
+    }
+    
+    fn calibration(&self) -> Option<Calibration> {
+        Some(Calibration::Offsets(vec![f64::from(self.offset)]))
+    }
+    
+    /// Take the offset from a one-channel `Calibration::Offsets`
+    fn set_calibration(&mut self, calibration: &Calibration) -> Result<(), CoreError> {
+        match calibration {
+            Calibration::Offsets(offsets) if offsets.len() == 1 => {
+                self.offset = offsets[0] as f32;
+                Ok(())
+            }
+            _ => Err(CoreError::InvalidParameter(format!(
+                "mock sensor '{}' takes a single offset, not a {} calibration",
+                self.id,
+                calibration.kind()
+            ))),
+        }
+    }
+    
+    fn metadata(&self) -> SensorMetadata {
+        SensorMetadata {
+            name: self.id.clone(),
+            description: format!("Mock sensor replaying {} samples", self.samples.len()),
+            unit: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_replays_script_and_zeroes_offset() {
+        let mut sensor = MockSensor::new("gyro", 100.0, vec![0.5, 1.5, 0.5]).unwrap();
+        let first = sensor.read_frame().unwrap();
+        assert_eq!((first.timestamp, first.payload_as_f32()), (0, vec![0.5]));
+        
+        sensor.calibrate().unwrap();
+        assert_eq!(sensor.offset(), 1.5);
+        let next = sensor.read_frame().unwrap();
+        assert_eq!((next.timestamp, next.payload_as_f32()), (10_000, vec![-1.0]));
+        // The script loops
+        assert_eq!(sensor.read_frame().unwrap().payload_as_f32(), vec![-1.0]);
+        
+        assert!(MockSensor::new("empty", 100.0, Vec::new()).is_err());
+        assert!(MockSensor::new("stopped", 0.0, vec![1.0]).is_err());
+    }
+}
@@ -0,0 +1,255 @@
+//! Sensor abstraction for frame-producing devices
+
+use crate::calibration::Calibration;
+use crate::error::CoreError;
+use crate::wire;
+
+pub mod camera;
+#[cfg(all(feature = "compression", not(target_arch = "wasm32")))]
+mod compressed;
+mod function;
+mod hub;
+pub mod imu;
+pub mod lidar;
+mod manager;
+mod mock;
+mod partial;
+mod rate_limit;
+mod reconnect;
+
+#[cfg(all(feature = "compression", not(target_arch = "wasm32")))]
+pub use compressed::{CompressedFrameReader, CompressedFrameWriter};
+pub use function::FunctionSensor;
+pub use hub::{FrameReceiver, SensorHub};
+pub use manager::{SensorManager, SensorPoll};
+pub use mock::MockSensor;
+pub use partial::PartialBuffer;
+pub use rate_limit::RateLimitedSensor;
+pub use reconnect::{Jitter, ReconnectingSensor};
+
+/// Byte order of multi-byte values in a frame payload
+///
+/// Defaults to the host's native order, which is what frames assumed before
+/// the order was recorded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Endianness {
+    /// Least significant byte first
+    #[cfg_attr(target_endian = "little", default)]
+    Little,
+    /// Most significant byte first, as in network byte order
+    #[cfg_attr(target_endian = "big", default)]
+    Big,
+}
+
+impl Endianness {
+    /// Byte order of the host
+    pub fn native() -> Self {
+        Self::default()
+    }
+}
+
+/// One reading delivered by a sensor
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SensorFrame {
+    /// ID of the sensor that produced the frame
+    pub sensor_id: String,
+    /// Capture time in microseconds
+    pub timestamp: u64,
+    /// Raw frame bytes, in the sensor's own encoding
+    pub payload: Vec<u8>,
+    /// Byte order of multi-byte values in `payload`
+    pub endianness: Endianness,
+}
+
+impl SensorFrame {
+    /// Decode the payload as `f32` samples in the frame's byte order
+    ///
+    /// Trailing bytes that do not form a whole sample are ignored.
+    pub fn payload_as_f32(&self) -> Vec<f32> {
+        self.payload
+            .chunks_exact(4)
+            .map(|chunk| {
+                let bytes = [chunk[0], chunk[1], chunk[2], chunk[3]];
+                match self.endianness {
+                    Endianness::Little => f32::from_le_bytes(bytes),
+                    Endianness::Big => f32::from_be_bytes(bytes),
+                }
+            })
+            .collect()
+    }
+    
+    /// Serialize the frame into a compact binary record
+    ///
+    /// The record holds the sensor ID (`u16` length and UTF-8 bytes), the
+    /// timestamp (`u64`), the byte order (`0` little, `1` big) and the payload
+    /// (`u32` length and bytes), with all lengths and the timestamp stored
+    /// little-endian.
+    pub fn encode(&self) -> Result<Vec<u8>, CoreError> {
+        let mut record = Vec::with_capacity(15 + self.sensor_id.len() + self.payload.len());
+        wire::put_short_str(&mut record, &self.sensor_id)?;
+        record.extend_from_slice(&self.timestamp.to_le_bytes());
+        record.push(match self.endianness {
+            Endianness::Little => 0,
+            Endianness::Big => 1,
+        });
+        wire::put_bytes(&mut record, &self.payload)?;
+        Ok(record)
+    }
+    
+    /// Parse a record produced by `encode`
+    pub fn decode(record: &[u8]) -> Result<Self, CoreError> {
+        let mut reader = wire::Reader::new(record, "frame record", CoreError::InvalidInput);
+        let sensor_id = reader.short_str()?;
+        let timestamp = reader.u64()?;
+        let endianness = match reader.u8()? {
+            0 => Endianness::Little,
+            1 => Endianness::Big,
+            other => return Err(reader.error(&format!("unknown byte order {}", other))),
+        };
+        let payload = reader.bytes()?.to_vec();
+        reader.finish()?;
+        Ok(Self {
+            sensor_id,
+            timestamp,
+            payload,
+            endianness,
+        })
+    }
+}
+
+/// The result of a read that may have delivered only part of a frame
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PartialFrame {
+    /// The frame as read; `payload` holds only the bytes this read delivered
+    pub data: SensorFrame,
+    /// Whether this read finished the frame
+    pub is_complete: bool,
+}
+
+/// Description of a sensor
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SensorMetadata {
+    pub name: String,
+    pub description: String,
+    /// Physical unit of the payload's values, if any
+    pub unit: Option<String>,
+}
+
+/// A source of sensor frames
+pub trait Sensor: Send {
+    /// Get the sensor's unique identifier
+    fn id(&self) -> &str;
+    
+    /// Read the next frame, blocking until one is available
+    fn read_frame(&mut self) -> Result<SensorFrame, CoreError>;
+    
+    /// Read whatever part of the next frame is available, keeping a
+    /// truncated frame's bytes rather than failing
+    ///
+    /// Feed the pieces to a `PartialBuffer` to stitch them back together.
+    /// The default implementation reads a whole frame, for sensors that
+    /// never deliver partial ones.
+    fn read_frame_partial(&mut self) -> Result<PartialFrame, CoreError> {
+        Ok(PartialFrame {
+            data: self.read_frame()?,
+            is_complete: true,
+        })
+    }
+    
+    /// Re-establish the connection to the device after a failed read
+    ///
+    /// The default implementation does nothing, for sensors with no
+    /// connection to lose.
+    fn reconnect(&mut self) -> Result<(), CoreError> {
+        Ok(())
+    }
+    
+    /// Frames the sensor delivers per second, if it has a nominal rate
+    fn sample_rate(&self) -> Option<f64> {
+        None
+    }
+    
+    /// Calibrate the sensor, such as zeroing its offset while at rest
+    ///
+    /// The default implementation does nothing, for sensors that need no
+    /// calibration.
+    fn calibrate(&mut self) -> Result<(), CoreError> {
+        Ok(())
+    }
+    
+    /// The calibration in effect, as `calibrate` measured it or
+    /// `set_calibration` applied it, for a `CalibrationStore` to keep
+    ///
+    /// The default implementation returns `None`, for sensors whose
+    /// calibration cannot be stored.
+    fn calibration(&self) -> Option<Calibration> {
+        None
+    }
+    
+    /// Apply a stored calibration instead of measuring one
+    ///
+    /// The default implementation fails with
+    /// `CoreError::UnsupportedCapability`.
+    fn set_calibration(&mut self, calibration: &Calibration) -> Result<(), CoreError> {
+        Err(CoreError::UnsupportedCapability(format!(
+            "sensor '{}' cannot apply a stored {} calibration",
+            self.id(),
+            calibration.kind()
+        )))
+    }
+    
+    /// Describe the sensor
+    ///
+    /// The default implementation names the sensor by its ID.
+    fn metadata(&self) -> SensorMetadata {
+        SensorMetadata {
+            name: self.id().to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn frame(payload: Vec<u8>, endianness: Endianness) -> SensorFrame {
+        SensorFrame {
+            payload,
+            endianness,
+            ..Default::default()
+        }
+    }
+    
+    #[test]
+    fn test_big_endian_payload_decodes_on_any_host() {
+        // 1.0 and -2.5 as big-endian IEEE 754
+        let payload = vec![0x3f, 0x80, 0x00, 0x00, 0xc0, 0x20, 0x00, 0x00];
+        assert_eq!(frame(payload, Endianness::Big).payload_as_f32(), vec![1.0, -2.5]);
+    }
+    
+    #[test]
+    fn test_encode_decode_round_trip() {
+        let frame = SensorFrame {
+            sensor_id: "imu".to_string(),
+            timestamp: 1_234_567,
+            payload: vec![1, 2, 3],
+            endianness: Endianness::Big,
+        };
+        let record = frame.encode().unwrap();
+        assert_eq!(SensorFrame::decode(&record).unwrap(), frame);
+        assert!(SensorFrame::decode(&record[..record.len() - 1]).is_err());
+    }
+    
+    #[test]
+    fn test_little_endian_and_native_default() {
+        let payload = [1.0f32.to_le_bytes(), 0.5f32.to_le_bytes()].concat();
+        assert_eq!(frame(payload, Endianness::Little).payload_as_f32(), vec![1.0, 0.5]);
+        
+        // A trailing partial sample is dropped
+        let mut native = 3.0f32.to_ne_bytes().to_vec();
+        native.extend([0xff, 0xff]);
+        assert_eq!(SensorFrame::default().endianness, Endianness::native());
+        assert_eq!(frame(native, Endianness::default()).payload_as_f32(), vec![3.0]);
+    }
+}
@@ -0,0 +1,110 @@
+//! Stitching frames that arrive over several reads
+
+use super::{PartialFrame, SensorFrame};
+
+/// Accumulates partial reads into complete frames
+///
+/// Pieces are joined in the order they are pushed. The assembled frame
+/// takes its sensor ID, timestamp and byte order from the first piece,
+/// which is when the frame started arriving.
+#[derive(Debug, Default)]
+pub struct PartialBuffer {
+    pending: Option<SensorFrame>,
+}
+
+impl PartialBuffer {
+    /// Create an empty buffer
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Add one read, returning the whole frame once its final piece arrives
+    pub fn push(&mut self, partial: PartialFrame) -> Option<SensorFrame> {
+        match &mut self.pending {
+            Some(pending) => pending.payload.extend_from_slice(&partial.data.payload),
+            None => self.pending = Some(partial.data),
+        }
+        if partial.is_complete {
+            self.pending.take()
+        } else {
+            None
+        }
+    }
+    
+    /// Bytes buffered towards the next frame
+    pub fn pending_len(&self) -> usize {
+        self.pending.as_ref().map_or(0, |frame| frame.payload.len())
+    }
+    
+    /// Drop any buffered pieces, e.g. after the sensor reconnects mid-frame
+    pub fn clear(&mut self) {
+        self.pending = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CoreError;
+    use crate::sensor::Sensor;
+    
+    /// Delivers each frame in two reads
+    struct SplitSensor {
+        frames: Vec<Vec<u8>>,
+        second_half: Option<Vec<u8>>,
+        timestamp: u64,
+    }
+    
+    impl Sensor for SplitSensor {
+        fn id(&self) -> &str {
+            "serial"
+        }
+        
+        fn read_frame(&mut self) -> Result<SensorFrame, CoreError> {
+            Err(CoreError::SensorError("serial frames arrive in pieces".to_string()))
+        }
+        
+        fn read_frame_partial(&mut self) -> Result<PartialFrame, CoreError> {
+            self.timestamp += 1;
+            let (payload, is_complete) = match self.second_half.take() {
+                Some(rest) => (rest, true),
+                None => {
+                    let mut first = self.frames.pop().ok_or(CoreError::EndOfStream)?;
+                    self.second_half = Some(first.split_off(first.len() / 2));
+                    (first, false)
+                }
+            };
+            Ok(PartialFrame {
+                data: SensorFrame {
+                    sensor_id: self.id().to_string(),
+                    timestamp: self.timestamp,
+                    payload,
+                    ..Default::default()
+                },
+                is_complete,
+            })
+        }
+    }
+    
+    #[test]
+    fn test_frame_reassembled_from_two_reads() {
+        let mut sensor = SplitSensor {
+            frames: vec![vec![1, 2, 3, 4, 5]],
+            second_half: None,
+            timestamp: 0,
+        };
+        let mut buffer = PartialBuffer::new();
+        
+        let first = sensor.read_frame_partial().unwrap();
+        assert!(!first.is_complete);
+        assert_eq!(buffer.push(first), None);
+        assert_eq!(buffer.pending_len(), 2);
+        
+        let frame = buffer.push(sensor.read_frame_partial().unwrap()).unwrap();
+        assert_eq!(frame.payload, vec![1, 2, 3, 4, 5]);
+        assert_eq!(frame.sensor_id, "serial");
+        assert_eq!(frame.timestamp, 1);
+        assert_eq!(buffer.pending_len(), 0);
+        assert_eq!(sensor.read_frame_partial(), Err(CoreError::EndOfStream));
+    }
+}
@@ -0,0 +1,141 @@
+//! Capping how often a sensor is read
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{Sensor, SensorFrame, SensorMetadata};
+use crate::calibration::Calibration;
+use crate::clock::{Clock, SystemClock};
+use crate::error::CoreError;
+
+/// Wraps a sensor so frames are read no faster than a maximum rate
+///
+/// A `read_frame` that comes sooner than `1 / max_rate_hz` after the
+/// previous one sleeps on the limiter's clock until the interval has
+/// elapsed, then reads.
+pub struct RateLimitedSensor<S: Sensor> {
+    inner: S,
+    min_interval: Duration,
+    clock: Arc<dyn Clock>,
+    last_read: Option<Duration>,
+}
+
+impl<S: Sensor> RateLimitedSensor<S> {
+    /// Limit a sensor to `max_rate_hz` reads per second on the system clock
+    pub fn new(inner: S, max_rate_hz: f64) -> Result<Self, CoreError> {
+        Self::with_clock(inner, max_rate_hz, Arc::new(SystemClock::new()))
+    }
+    
+    /// Limit a sensor to `max_rate_hz` reads per second on the given clock
+    pub fn with_clock(inner: S, max_rate_hz: f64, clock: Arc<dyn Clock>) -> Result<Self, CoreError> {
+        let min_interval = Some(max_rate_hz)
+            .filter(|rate| rate.is_finite() && *rate > 0.0)
+            .and_then(|rate| Duration::try_from_secs_f64(1.0 / rate).ok())
+            .ok_or_else(|| {
+                CoreError::InvalidParameter(format!(
+                    "max rate must be positive and representable, got {}",
+                    max_rate_hz
+                ))
+            })?;
+        Ok(Self {
+            inner,
+            min_interval,
+            clock,
+            last_read: None,
+        })
+    }
+    
+    /// Unwrap the inner sensor
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Sensor> Sensor for RateLimitedSensor<S> {
+    fn id(&self) -> &str {
+        self.inner.id()
+    }
+    
+    fn read_frame(&mut self) -> Result<SensorFrame, CoreError> {
+        if let Some(last_read) = self.last_read {
+            let due = last_read + self.min_interval;
+            let now = self.clock.now();
+            if now < due {
+                self.clock.sleep(due - now);
+            }
+        }
+        self.last_read = Some(self.clock.now());
+        self.inner.read_frame()
+    }
+    
+    fn reconnect(&mut self) -> Result<(), CoreError> {
+        self.inner.reconnect()
+    }
+    
+    /// The inner sensor's rate, capped at the limit
+    fn sample_rate(&self) -> Option<f64> {
+        let limit = 1.0 / self.min_interval.as_secs_f64();
+        Some(self.inner.sample_rate().map_or(limit, |rate| rate.min(limit)))
+    }
+    
+    fn calibrate(&mut self) -> Result<(), CoreError> {
+        self.inner.calibrate()
+    }
+    
+    fn calibration(&self) -> Option<Calibration> {
+        self.inner.calibration()
+    }
+    
+    fn set_calibration(&mut self, calibration: &Calibration) -> Result<(), CoreError> {
+        self.inner.set_calibration(calibration)
+    }
+    
+    fn metadata(&self) -> SensorMetadata {
+        self.inner.metadata()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    
+    /// Stamps each frame with the time it was read
+    struct Stamped(Arc<MockClock>);
+    
+    impl Sensor for Stamped {
+        fn id(&self) -> &str {
+            "stamped"
+        }
+        
+        fn read_frame(&mut self) -> Result<SensorFrame, CoreError> {
+            Ok(SensorFrame {
+                timestamp: self.0.now().as_millis() as u64,
+                ..Default::default()
+            })
+        }
+    }
+    
+    #[test]
+    fn test_reads_are_spaced_by_min_interval() {
+        let clock = Arc::new(MockClock::new());
+        let mut sensor = RateLimitedSensor::with_clock(Stamped(clock.clone()), 10.0, clock.clone()).unwrap();
+        
+        let stamps: Vec<u64> = (0..3).map(|_| sensor.read_frame().unwrap().timestamp).collect();
+        assert_eq!(stamps, vec![0, 100, 200]);
+        
+        // Time that already passed counts towards the interval
+        clock.advance(Duration::from_millis(250));
+        assert_eq!(sensor.read_frame().unwrap().timestamp, 450);
+        clock.advance(Duration::from_millis(40));
+        assert_eq!(sensor.read_frame().unwrap().timestamp, 550);
+    }
+    
+    #[test]
+    fn test_invalid_rate_rejected() {
+        let clock = Arc::new(MockClock::new());
+        assert!(RateLimitedSensor::with_clock(Stamped(clock.clone()), 0.0, clock.clone()).is_err());
+        // An interval too long for a `Duration` is an error, not a panic
+        assert!(RateLimitedSensor::with_clock(Stamped(clock.clone()), 1e-300, clock).is_err());
+    }
+}
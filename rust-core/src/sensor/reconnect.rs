@@ -0,0 +1,301 @@
+//! Transparent reconnection for sensors with flaky links
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{Sensor, SensorFrame, SensorMetadata};
+use crate::algorithm::context::{RngCore, SeededRng};
+use crate::calibration::Calibration;
+use crate::clock::{Clock, SystemClock};
+use crate::error::CoreError;
+
+/// How a backoff delay is randomized, so that many sensors losing a shared
+/// link do not all reconnect in lockstep
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Jitter {
+    /// Wait exactly the backoff
+    #[default]
+    None,
+    /// Wait a uniform random time in `[0, backoff]`
+    Full,
+    /// Wait half the backoff plus a uniform random time in
+    /// `[0, backoff / 2]`, so the wait never drops below half
+    Equal,
+}
+
+impl Jitter {
+    /// Randomize `backoff`, drawing from `rng`
+    pub fn apply(self, backoff: Duration, rng: &mut dyn RngCore) -> Duration {
+        // 53 random bits fill an f64 mantissa exactly
+        let mut unit = || (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        match self {
+            Jitter::None => backoff,
+            Jitter::Full => backoff.mul_f64(unit()),
+            Jitter::Equal => backoff / 2 + (backoff / 2).mul_f64(unit()),
+        }
+    }
+}
+
+/// Wraps a sensor so failed reads reconnect and retry with exponential backoff
+///
+/// When `read_frame` fails, the wrapper waits, calls the inner sensor's
+/// `reconnect`, and reads again, doubling the wait after each failure up to
+/// a ceiling. A read that succeeds after reconnecting is returned as if
+/// nothing happened; once `max_retries` reconnect attempts have failed, the
+/// last error is surfaced. With `with_jitter`, each wait is randomized
+/// around the backoff.
+pub struct ReconnectingSensor<S: Sensor> {
+    inner: S,
+    max_retries: usize,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: Jitter,
+    rng: Box<dyn RngCore + Send>,
+    clock: Arc<dyn Clock>,
+    reconnects: usize,
+}
+
+impl<S: Sensor> ReconnectingSensor<S> {
+    /// Wrap a sensor with 5 retries backing off from 100 ms up to 5 s
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            jitter: Jitter::None,
+            rng: Box::new(SeededRng::from_entropy()),
+            clock: Arc::new(SystemClock::new()),
+            reconnects: 0,
+        }
+    }
+    
+    /// Set how many times a single read may reconnect before failing
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+    
+    /// Set the first wait and the ceiling the doubling wait is capped at
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+    
+    /// Randomize each wait with `jitter`
+    ///
+    /// The doubling backoff still sets the bounds; jitter only picks a wait
+    /// within them.
+    pub fn with_jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
+        self
+    }
+    
+    /// Draw jitter from `rng` instead of a generator seeded from entropy,
+    /// e.g. a `SeededRng` for reproducible waits
+    pub fn with_rng(mut self, rng: Box<dyn RngCore + Send>) -> Self {
+        self.rng = rng;
+        self
+    }
+    
+    /// Wait out backoff delays on the given clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+    
+    /// Successful reconnections since the wrapper was created
+    pub fn reconnects(&self) -> usize {
+        self.reconnects
+    }
+    
+    /// Unwrap the inner sensor
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Sensor> Sensor for ReconnectingSensor<S> {
+    fn id(&self) -> &str {
+        self.inner.id()
+    }
+    
+    fn read_frame(&mut self) -> Result<SensorFrame, CoreError> {
+        let mut backoff = self.initial_backoff;
+        let mut retries = 0;
+        loop {
+            let error = match self.inner.read_frame() {
+                Ok(frame) => return Ok(frame),
+                Err(e) => e,
+            };
+            if retries == self.max_retries {
+                return Err(error);
+            }
+            retries += 1;
+            let delay = self.jitter.apply(backoff, self.rng.as_mut());
+            log::warn!("Sensor {} read failed, reconnecting in {:?}: {}", self.inner.id(), delay, error);
+            
+            self.clock.sleep(delay);
+            backoff = backoff.saturating_mul(2).min(self.max_backoff);
+            match self.inner.reconnect() {
+                Ok(()) => self.reconnects += 1,
+                Err(e) => log::warn!("Sensor {} reconnect failed: {}", self.inner.id(), e),
+            }
+        }
+    }
+    
+    fn reconnect(&mut self) -> Result<(), CoreError> {
+        self.inner.reconnect()
+    }
+    
+    fn sample_rate(&self) -> Option<f64> {
+        self.inner.sample_rate()
+    }
+    
+    fn calibrate(&mut self) -> Result<(), CoreError> {
+        self.inner.calibrate()
+    }
+    
+    fn calibration(&self) -> Option<Calibration> {
+        self.inner.calibration()
+    }
+    
+    fn set_calibration(&mut self, calibration: &Calibration) -> Result<(), CoreError> {
+        self.inner.set_calibration(calibration)
+    }
+    
+    fn metadata(&self) -> SensorMetadata {
+        self.inner.metadata()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    
+    /// Fails a set number of reads, then delivers numbered frames while
+    /// connected
+    struct Flaky {
+        failures_left: usize,
+        connected: bool,
+        reconnect_calls: usize,
+        next: u64,
+    }
+    
+    impl Flaky {
+        fn failing(failures: usize) -> Self {
+            Self {
+                failures_left: failures,
+                connected: true,
+                reconnect_calls: 0,
+                next: 0,
+            }
+        }
+    }
+    
+    impl Sensor for Flaky {
+        fn id(&self) -> &str {
+            "flaky"
+        }
+        
+        fn read_frame(&mut self) -> Result<SensorFrame, CoreError> {
+            if self.failures_left > 0 {
+                self.failures_left -= 1;
+                self.connected = false;
+                return Err(CoreError::SensorError("connection reset".to_string()));
+            }
+            if !self.connected {
+                return Err(CoreError::SensorError("not connected".to_string()));
+            }
+            self.next += 1;
+            Ok(SensorFrame {
+                sensor_id: self.id().to_string(),
+                timestamp: self.next,
+                payload: vec![self.next as u8],
+                ..Default::default()
+            })
+        }
+        
+        fn reconnect(&mut self) -> Result<(), CoreError> {
+            self.reconnect_calls += 1;
+            self.connected = true;
+            Ok(())
+        }
+    }
+    
+    fn wrap(sensor: Flaky, max_retries: usize, clock: Arc<MockClock>) -> ReconnectingSensor<Flaky> {
+        ReconnectingSensor::new(sensor)
+            .with_max_retries(max_retries)
+            .with_backoff(Duration::from_millis(100), Duration::from_millis(300))
+            .with_clock(clock)
+    }
+    
+    #[test]
+    fn test_recovers_transparently() {
+        let clock = Arc::new(MockClock::new());
+        let mut sensor = wrap(Flaky::failing(3), 5, clock.clone());
+        let frame = sensor.read_frame().unwrap();
+        assert_eq!(frame.payload, vec![1]);
+        assert_eq!(sensor.read_frame().unwrap().payload, vec![2]);
+        assert_eq!(sensor.reconnects(), 3);
+        // Backoff doubles up to its ceiling: 100 + 200 + 300 ms
+        assert_eq!(clock.now(), Duration::from_millis(600));
+        assert_eq!(sensor.into_inner().reconnect_calls, 3);
+    }
+    
+    #[test]
+    fn test_surfaces_error_after_max_retries() {
+        let mut sensor = wrap(Flaky::failing(10), 2, Arc::new(MockClock::new()));
+        assert_eq!(
+            sensor.read_frame(),
+            Err(CoreError::SensorError("connection reset".to_string()))
+        );
+        assert_eq!(sensor.into_inner().reconnect_calls, 2);
+    }
+    
+    /// Records every wait instead of sleeping
+    #[derive(Default)]
+    struct Sleeps(std::sync::Mutex<Vec<Duration>>);
+    
+    impl Clock for Sleeps {
+        fn now(&self) -> Duration {
+            Duration::ZERO
+        }
+        
+        fn sleep(&self, duration: Duration) {
+            self.0.lock().unwrap().push(duration);
+        }
+    }
+    
+    fn waits(jitter: Jitter, seed: u64) -> Vec<Duration> {
+        let clock = Arc::new(Sleeps::default());
+        let mut sensor = ReconnectingSensor::new(Flaky::failing(4))
+            .with_backoff(Duration::from_millis(100), Duration::from_millis(800))
+            .with_clock(clock.clone())
+            .with_jitter(jitter)
+            .with_rng(Box::new(SeededRng::new(seed)));
+        sensor.read_frame().unwrap();
+        let waits = clock.0.lock().unwrap().clone();
+        waits
+    }
+    
+    #[test]
+    fn test_jittered_waits_stay_within_bounds() {
+        let backoffs = [100, 200, 400, 800].map(Duration::from_millis);
+        
+        let full = waits(Jitter::Full, 1);
+        assert_eq!(full.len(), 4);
+        assert!(full.iter().zip(backoffs).all(|(&wait, backoff)| wait <= backoff));
+        let equal = waits(Jitter::Equal, 1);
+        assert!(equal.iter().zip(backoffs).all(|(&wait, backoff)| wait >= backoff / 2 && wait <= backoff));
+        
+        // Each attempt draws afresh, so waits no longer simply double, and
+        // differently seeded sensors spread out
+        assert!(full.windows(2).any(|w| w[0] * 2 != w[1]));
+        assert_ne!(full, waits(Jitter::Full, 2));
+        assert_eq!(full, waits(Jitter::Full, 1));
+        assert_eq!(waits(Jitter::None, 1), backoffs);
+    }
+}
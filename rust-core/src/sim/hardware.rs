@@ -0,0 +1,81 @@
+//! Motors and encoders of the simulated robot as a `HardwareInterface`
+
+use std::collections::BTreeMap;
+use std::f64::consts::TAU;
+
+use super::Simulator;
+use crate::error::CoreError;
+use crate::hardware::{check_duty, HardwareInterface};
+
+/// Levels of the `A` and `B` channels at each quarter of a quadrature
+/// cycle
+const QUADRATURE: [(bool, bool); 4] = [(false, false), (true, false), (true, true), (false, true)];
+
+/// The simulated robot's wiring
+///
+/// Setting the duty cycle of a wheel's PWM channel drives it at that
+/// fraction of `RobotModel::max_wheel_speed`, backwards while its
+/// direction pin is high. Reading a wheel's encoder pins gives the
+/// quadrature levels of its angle as the sensors see it, after the
+/// sensor latency. Other pins read back what was last written to them.
+pub struct SimHardware {
+    name: String,
+    sim: Simulator,
+    pins: BTreeMap<u32, bool>,
+}
+
+impl SimHardware {
+    pub(super) fn new(name: &str, sim: Simulator) -> Self {
+        Self {
+            name: name.to_string(),
+            sim,
+            pins: BTreeMap::new(),
+        }
+    }
+}
+
+impl HardwareInterface for SimHardware {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn write_pin(&mut self, pin: u32, high: bool) -> Result<(), CoreError> {
+        let mut world = self.sim.world();
+        if let Some(wheel) = world.model.direction_pins.iter().position(|&p| p == pin) {
+            world.reversed[wheel] = high;
+            world.command(wheel);
+        }
+        self.pins.insert(pin, high);
+        Ok(())
+    }
+    
+    fn read_pin(&mut self, pin: u32) -> Result<bool, CoreError> {
+        let world = self.sim.world();
+        let encoder = world
+            .model
+            .encoder_pins
+            .iter()
+            .enumerate()
+            .find_map(|(wheel, pins)| pins.iter().position(|&p| p == pin).map(|channel| (wheel, channel)));
+        let Some((wheel, channel)) = encoder else {
+            return Ok(self.pins.get(&pin).copied().unwrap_or(false));
+        };
+        let count = (world.observed().angles[wheel] / TAU * world.model.ticks_per_rev).floor() as i64;
+        let (a, b) = QUADRATURE[count.rem_euclid(4) as usize];
+        Ok(if channel == 0 { a } else { b })
+    }
+    
+    fn set_pwm(&mut self, channel: u32, duty: f64) -> Result<(), CoreError> {
+        check_duty(duty)?;
+        let mut world = self.sim.world();
+        let wheel = world
+            .model
+            .pwm_channels
+            .iter()
+            .position(|&c| c == channel)
+            .ok_or_else(|| CoreError::InvalidParameter(format!("no simulated motor on PWM channel {}", channel)))?;
+        world.duties[wheel] = duty;
+        world.command(wheel);
+        Ok(())
+    }
+}
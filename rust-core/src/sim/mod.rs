@@ -0,0 +1,443 @@
+//! Kinematic robot simulator behind the sensor and hardware traits
+//!
+//! A `Simulator` moves a wheeled base by the speeds its motors are driven
+//! at, so the engine, scheduler, pipelines and safety monitor can run end
+//! to end without a robot. `SimHardware` takes PWM duty cycles and
+//! direction pins for the motors and serves quadrature encoder levels;
+//! `SimImu`, `SimEncoders` and `SimPoseSensor` read the motion back as
+//! sensor frames. Sensor noise and latency and actuator latency are
+//! configurable, and noise is drawn from a seeded generator, so a run
+//! repeats exactly.
+//!
+//! Simulated time only moves through `Simulator::step`, `advance_to` or
+//! sleeping on `Simulator::clock`, which lets an engine built on that
+//! clock run its control loop faster than real time.
+
+use std::collections::VecDeque;
+use std::f64::consts::TAU;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::algorithm::context::{RngCore, SeededRng};
+use crate::algorithm::odometry::{DriveGeometry, Pose2D};
+use crate::clock::Clock;
+use crate::error::CoreError;
+
+mod hardware;
+mod sensors;
+
+pub use hardware::SimHardware;
+pub use sensors::{SimEncoders, SimImu, SimPoseSensor};
+
+/// Longest stretch of time integrated in one go
+const SUBSTEP: Duration = Duration::from_millis(1);
+
+/// Standard gravity, in m/s², which a level IMU reads on its Z axis
+const GRAVITY: f64 = 9.806_65;
+
+/// The simulated robot: its base and how its motors and encoders are wired
+#[derive(Clone, Debug, PartialEq)]
+pub struct RobotModel {
+    pub geometry: DriveGeometry,
+    /// Wheel speed at full duty, in rad/s
+    pub max_wheel_speed: f64,
+    /// Seconds a wheel takes to close 63% of the gap to a new speed, as a
+    /// first-order motor would; 0 changes speed at once
+    pub motor_time_constant: f64,
+    /// Quadrature counts per wheel revolution
+    pub ticks_per_rev: f64,
+    /// PWM channel driving each wheel, in the geometry's wheel order
+    pub pwm_channels: Vec<u32>,
+    /// Pin reversing each wheel while high
+    pub direction_pins: Vec<u32>,
+    /// `A` and `B` encoder pins of each wheel
+    pub encoder_pins: Vec<[u32; 2]>,
+}
+
+impl RobotModel {
+    /// A base with wheel `i` on PWM channel `i`, direction pin `i` and
+    /// encoder pins `100 + 2i` and `101 + 2i`, counting 1024 ticks per
+    /// revolution, with motors that respond at once
+    pub fn new(geometry: DriveGeometry, max_wheel_speed: f64) -> Self {
+        let wheels = geometry.wheels() as u32;
+        Self {
+            geometry,
+            max_wheel_speed,
+            motor_time_constant: 0.0,
+            ticks_per_rev: 1024.0,
+            pwm_channels: (0..wheels).collect(),
+            direction_pins: (0..wheels).collect(),
+            encoder_pins: (0..wheels).map(|i| [100 + 2 * i, 101 + 2 * i]).collect(),
+        }
+    }
+    
+    pub fn with_motor_time_constant(mut self, seconds: f64) -> Self {
+        self.motor_time_constant = seconds;
+        self
+    }
+    
+    /// Fails with `CoreError::InvalidParameter` for non-positive sizes,
+    /// speeds or tick counts, a negative time constant, or wiring that
+    /// does not list one entry per wheel
+    pub fn validate(&self) -> Result<(), CoreError> {
+        self.geometry.check()?;
+        let positive = [("max_wheel_speed", self.max_wheel_speed), ("ticks_per_rev", self.ticks_per_rev)];
+        for (name, value) in positive {
+            if !(value.is_finite() && value > 0.0) {
+                return Err(CoreError::InvalidParameter(format!("{} must be finite and positive, got {}", name, value)));
+            }
+        }
+        if !(self.motor_time_constant.is_finite() && self.motor_time_constant >= 0.0) {
+            return Err(CoreError::InvalidParameter(format!(
+                "motor_time_constant must be finite and non-negative, got {}",
+                self.motor_time_constant
+            )));
+        }
+        let wheels = self.geometry.wheels();
+        let wiring = [
+            ("pwm_channels", self.pwm_channels.len()),
+            ("direction_pins", self.direction_pins.len()),
+            ("encoder_pins", self.encoder_pins.len()),
+        ];
+        for (name, len) in wiring {
+            if len != wheels {
+                return Err(CoreError::InvalidParameter(format!(
+                    "{} lists {} wheels, the base has {}",
+                    name, len, wheels
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Standard deviations of the Gaussian noise added to sensor readings
+///
+/// All zero by default, for exact readings.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SimNoise {
+    /// Gyro noise, in rad/s
+    pub gyro: f64,
+    /// Accelerometer noise, in m/s²
+    pub accel: f64,
+    /// Noise on each coordinate of a pose reading, in metres
+    pub position: f64,
+    /// Noise on the heading of a pose reading, in radians
+    pub heading: f64,
+}
+
+/// What the sensors can observe of one instant
+#[derive(Clone, Debug, Default)]
+struct Snapshot {
+    time: Duration,
+    pose: Pose2D,
+    /// Wheel angles in radians
+    angles: Vec<f64>,
+    /// Forward and leftward velocity in m/s and turn rate in rad/s
+    twist: [f64; 3],
+    /// Forward and leftward acceleration in m/s², in the base's frame
+    acceleration: [f64; 2],
+}
+
+struct World {
+    model: RobotModel,
+    noise: SimNoise,
+    sensor_latency: Duration,
+    actuator_latency: Duration,
+    rng: SeededRng,
+    now: Snapshot,
+    /// Wheel speeds in rad/s
+    speeds: Vec<f64>,
+    /// Speeds each wheel is driven towards, once the latency has passed
+    targets: Vec<f64>,
+    duties: Vec<f64>,
+    reversed: Vec<bool>,
+    /// Wheel speeds commanded but not yet applied, by when they apply
+    pending: VecDeque<(Duration, usize, f64)>,
+    /// Recent snapshots, oldest first, back to the one sensors observe
+    history: VecDeque<Snapshot>,
+}
+
+impl World {
+    fn step(&mut self, dt: Duration) {
+        let wheels = self.model.geometry.wheels();
+        let h = dt.as_secs_f64();
+        while self.pending.front().is_some_and(|&(at, _, _)| at <= self.now.time) {
+            if let Some((_, wheel, speed)) = self.pending.pop_front() {
+                self.targets[wheel] = speed;
+            }
+        }
+        let response = if self.model.motor_time_constant > 0.0 {
+            1.0 - (-h / self.model.motor_time_constant).exp()
+        } else {
+            1.0
+        };
+        for wheel in 0..wheels {
+            self.speeds[wheel] += (self.targets[wheel] - self.speeds[wheel]) * response;
+            self.now.angles[wheel] += self.speeds[wheel] * h;
+        }
+        
+        let radius = self.model.geometry.wheel_radius();
+        let travel: Vec<f64> = self.speeds.iter().map(|speed| speed * radius * h).collect();
+        let [forward, left, turn] = self.model.geometry.body_motion(&travel);
+        let Pose2D { x, y, theta } = self.now.pose;
+        let (sin, cos) = (theta + turn / 2.0).sin_cos();
+        self.now.pose = Pose2D {
+            x: x + forward * cos - left * sin,
+            y: y + forward * sin + left * cos,
+            theta: theta + turn,
+        };
+        let twist = [forward / h, left / h, turn / h];
+        let [previous_forward, previous_left, _] = self.now.twist;
+        // Acceleration in a rotating frame picks up the turn rate crossed
+        // with the velocity
+        self.now.acceleration = [
+            (twist[0] - previous_forward) / h - twist[2] * twist[1],
+            (twist[1] - previous_left) / h + twist[2] * twist[0],
+        ];
+        self.now.twist = twist;
+        self.now.time += dt;
+        
+        self.history.push_back(self.now.clone());
+        let observed = self.now.time.saturating_sub(self.sensor_latency);
+        while self.history.get(1).is_some_and(|next| next.time <= observed) {
+            self.history.pop_front();
+        }
+    }
+    
+    fn advance(&mut self, duration: Duration) {
+        let mut remaining = duration;
+        while !remaining.is_zero() {
+            let dt = remaining.min(SUBSTEP);
+            self.step(dt);
+            remaining -= dt;
+        }
+    }
+    
+    /// The latest instant the sensors can see, `sensor_latency` ago
+    fn observed(&self) -> &Snapshot {
+        let observed = self.now.time.saturating_sub(self.sensor_latency);
+        self.history
+            .iter()
+            .rev()
+            .find(|snapshot| snapshot.time <= observed)
+            .or(self.history.front())
+            .unwrap_or(&self.now)
+    }
+    
+    /// Command wheel `wheel` from its duty cycle and direction pin
+    fn command(&mut self, wheel: usize) {
+        let sign = if self.reversed[wheel] { -1.0 } else { 1.0 };
+        let speed = sign * self.duties[wheel] * self.model.max_wheel_speed;
+        let at = self.now.time + self.actuator_latency;
+        self.pending.push_back((at, wheel, speed));
+    }
+    
+    /// A sample from a normal distribution with deviation `sigma`
+    fn gaussian(&mut self, sigma: f64) -> f64 {
+        if sigma == 0.0 {
+            return 0.0;
+        }
+        // Box-Muller, with the first uniform in (0, 1] so its log is finite
+        let unit = |rng: &mut SeededRng| (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        let radius = (-2.0 * (1.0 - unit(&mut self.rng)).ln()).sqrt();
+        sigma * radius * (TAU * unit(&mut self.rng)).cos()
+    }
+}
+
+/// A simulated robot, shared by the hardware, sensors and clock made from
+/// it
+///
+/// Clones share the simulation.
+#[derive(Clone)]
+pub struct Simulator {
+    world: Arc<Mutex<World>>,
+}
+
+impl Simulator {
+    /// Simulate `model` at rest at the origin, with exact sensors and no
+    /// latency
+    pub fn new(model: RobotModel) -> Result<Self, CoreError> {
+        model.validate()?;
+        let wheels = model.geometry.wheels();
+        let now = Snapshot {
+            angles: vec![0.0; wheels],
+            ..Default::default()
+        };
+        Ok(Self {
+            world: Arc::new(Mutex::new(World {
+                model,
+                noise: SimNoise::default(),
+                sensor_latency: Duration::ZERO,
+                actuator_latency: Duration::ZERO,
+                rng: SeededRng::new(0),
+                history: VecDeque::from([now.clone()]),
+                now,
+                speeds: vec![0.0; wheels],
+                targets: vec![0.0; wheels],
+                duties: vec![0.0; wheels],
+                reversed: vec![false; wheels],
+                pending: VecDeque::new(),
+            })),
+        })
+    }
+    
+    /// Add `noise` to sensor readings
+    pub fn with_noise(self, noise: SimNoise) -> Self {
+        self.world().noise = noise;
+        self
+    }
+    
+    /// Draw noise from a generator seeded with `seed`; the default is 0
+    pub fn with_seed(self, seed: u64) -> Self {
+        self.world().rng = SeededRng::new(seed);
+        self
+    }
+    
+    /// Have sensors report the robot as it was `sensor` ago, and motors
+    /// respond to commands `actuator` after they are given
+    pub fn with_latency(self, sensor: Duration, actuator: Duration) -> Self {
+        {
+            let mut world = self.world();
+            world.sensor_latency = sensor;
+            world.actuator_latency = actuator;
+        }
+        self
+    }
+    
+    fn world(&self) -> MutexGuard<'_, World> {
+        self.world.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    /// Move simulated time forward by `duration`
+    pub fn step(&self, duration: Duration) {
+        self.world().advance(duration);
+    }
+    
+    /// Move simulated time forward to `time`; earlier times do nothing
+    pub fn advance_to(&self, time: Duration) {
+        let mut world = self.world();
+        let remaining = time.saturating_sub(world.now.time);
+        world.advance(remaining);
+    }
+    
+    /// Simulated time since the start
+    pub fn time(&self) -> Duration {
+        self.world().now.time
+    }
+    
+    /// Where the robot truly is
+    pub fn pose(&self) -> Pose2D {
+        self.world().now.pose
+    }
+    
+    /// Place the robot at `pose`, e.g. to start a test elsewhere
+    pub fn set_pose(&self, pose: Pose2D) {
+        self.world().now.pose = pose;
+    }
+    
+    /// How fast each wheel turns, in rad/s
+    pub fn wheel_speeds(&self) -> Vec<f64> {
+        self.world().speeds.clone()
+    }
+    
+    /// A clock reading simulated time, whose `sleep` steps the simulation
+    ///
+    /// Build the engine and safety monitor on it so deadlines, timeouts
+    /// and telemetry follow the simulation.
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        Arc::new(SimClock(self.clone()))
+    }
+    
+    /// Motor and encoder wiring of the robot, named `name`
+    pub fn hardware(&self, name: &str) -> SimHardware {
+        SimHardware::new(name, self.clone())
+    }
+    
+    /// An IMU on the base, with ID `id`
+    pub fn imu(&self, id: &str) -> SimImu {
+        SimImu::new(id, self.clone())
+    }
+    
+    /// The wheel encoders, read as counts since the previous frame, with
+    /// ID `id`
+    pub fn encoders(&self, id: &str) -> SimEncoders {
+        SimEncoders::new(id, self.clone())
+    }
+    
+    /// A sensor reporting the robot's pose, such as motion capture, with
+    /// ID `id`
+    pub fn pose_sensor(&self, id: &str) -> SimPoseSensor {
+        SimPoseSensor::new(id, self.clone())
+    }
+}
+
+/// Simulated time as a `Clock`
+struct SimClock(Simulator);
+
+impl Clock for SimClock {
+    fn now(&self) -> Duration {
+        self.0.time()
+    }
+    
+    fn sleep(&self, duration: Duration) {
+        self.0.step(duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::odometry::{WheelInput, WheelOdometry};
+    use crate::algorithm::samples;
+    use crate::hardware::{HardwareDevice, OutputMapping};
+    use crate::safety::{PwmCutoff, SafetyMonitor};
+    use crate::scheduler::Scheduler;
+    use crate::sensor::Sensor;
+    use crate::CoreEngine;
+    
+    fn differential() -> RobotModel {
+        let geometry = DriveGeometry::Differential {
+            wheel_radius: 0.05,
+            track_width: 0.3,
+        };
+        RobotModel::new(geometry, 10.0)
+    }
+    
+    #[test]
+    fn test_engine_drives_simulated_robot_end_to_end() {
+        let sim = Simulator::new(differential()).unwrap();
+        let mut engine = CoreEngine::with_clock(sim.clock());
+        let motors = HardwareDevice::new("motors", Box::new(sim.hardware("board")), OutputMapping::Pwm(vec![0, 1]));
+        engine.register_device(Box::new(motors));
+        let monitor = SafetyMonitor::new().with_clock(sim.clock());
+        monitor.add_action(Box::new(PwmCutoff::new(Box::new(sim.hardware("board")), vec![0, 1])));
+        
+        // Full speed on both wheels: 0.5 m/s straight ahead
+        let duties = samples::f32_to_bytes(&[1.0, 1.0]);
+        engine.execute_to_device("passthrough", &duties, "motors").unwrap();
+        let mut encoders = sim.encoders("wheels");
+        let ticks = WheelInput::Ticks { ticks_per_rev: 1024.0 };
+        let odometry = WheelOdometry::new(differential().geometry, ticks).unwrap();
+        let mut scheduler = Scheduler::new();
+        scheduler.add("passthrough", Duration::from_millis(100)).unwrap();
+        for tick in 0..10 {
+            sim.advance_to(Duration::from_millis(100 * (tick + 1)));
+            assert_eq!(scheduler.tick(&mut engine, sim.time()).len(), 1);
+            let ticks = encoders.read_frame().unwrap().payload_as_f32();
+            odometry.update(&ticks.iter().map(|&t| f64::from(t)).collect::<Vec<_>>()).unwrap();
+        }
+        let pose = sim.pose();
+        assert!((pose.x - 0.5).abs() < 1e-9 && pose.y.abs() < 1e-9, "{:?}", pose);
+        assert!((odometry.pose().x - 0.5).abs() < 0.001, "encoders quantize to 1/1024 rev");
+        
+        // An emergency stop idles the motors, and the robot stops
+        monitor.emergency_stop("test");
+        sim.step(Duration::from_millis(10));
+        assert_eq!(sim.wheel_speeds(), [0.0, 0.0]);
+        let stopped = sim.pose();
+        sim.step(Duration::from_secs(1));
+        assert_eq!(sim.pose(), stopped);
+        assert!(Simulator::new(RobotModel { pwm_channels: vec![0], ..differential() }).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+//! Sensors observing the simulated robot
+
+use std::f64::consts::TAU;
+
+use super::{Simulator, GRAVITY};
+use crate::algorithm::samples;
+use crate::error::CoreError;
+use crate::sensor::{Endianness, Sensor, SensorFrame, SensorMetadata};
+
+/// Frame of little-endian `f32` `values` from sensor `id`, stamped with
+/// `time`
+fn frame(id: &str, time: std::time::Duration, values: &[f64]) -> SensorFrame {
+    let values: Vec<f32> = values.iter().map(|&v| v as f32).collect();
+    SensorFrame {
+        sensor_id: id.to_string(),
+        timestamp: time.as_micros() as u64,
+        payload: samples::f32_to_bytes(&values),
+        endianness: Endianness::Little,
+    }
+}
+
+fn metadata(id: &str, description: &str, unit: &str) -> SensorMetadata {
+    SensorMetadata {
+        name: id.to_string(),
+        description: description.to_string(),
+        unit: Some(unit.to_string()),
+    }
+}
+
+/// An IMU mounted level at the centre of the base
+///
+/// Each frame holds six `f32`s: acceleration along X, Y and Z in m/s²,
+/// with gravity on Z, then angular velocity about X, Y and Z in rad/s.
+pub struct SimImu {
+    id: String,
+    sim: Simulator,
+}
+
+impl SimImu {
+    pub(super) fn new(id: &str, sim: Simulator) -> Self {
+        Self { id: id.to_string(), sim }
+    }
+}
+
+impl Sensor for SimImu {
+    fn id(&self) -> &str {
+        &self.id
+    }
+    
+    /// Read the motion as of the sensor latency ago, without waiting
+    fn read_frame(&mut self) -> Result<SensorFrame, CoreError> {
+        let mut world = self.sim.world();
+        let observed = world.observed().clone();
+        let noise = world.noise;
+        let mut values = [
+            observed.acceleration[0],
+            observed.acceleration[1],
+            GRAVITY,
+            0.0,
+            0.0,
+            observed.twist[2],
+        ];
+        for (i, value) in values.iter_mut().enumerate() {
+            *value += world.gaussian(if i < 3 { noise.accel } else { noise.gyro });
+        }
+        Ok(frame(&self.id, observed.time, &values))
+    }
+    
+    fn metadata(&self) -> SensorMetadata {
+        metadata(&self.id, "Simulated accelerometer (x, y, z) and gyro (x, y, z)", "m/s², rad/s")
+    }
+}
+
+/// The wheel encoders, counting quadrature ticks
+///
+/// Each frame holds one `f32` per wheel: the ticks counted since the
+/// previous frame, signed by direction, as `WheelOdometry` reads with
+/// `WheelInput::Ticks`.
+pub struct SimEncoders {
+    id: String,
+    sim: Simulator,
+    last: Option<Vec<i64>>,
+}
+
+impl SimEncoders {
+    pub(super) fn new(id: &str, sim: Simulator) -> Self {
+        Self {
+            id: id.to_string(),
+            sim,
+            last: None,
+        }
+    }
+}
+
+impl Sensor for SimEncoders {
+    fn id(&self) -> &str {
+        &self.id
+    }
+    
+    /// Read the counts as of the sensor latency ago, without waiting;
+    /// the first frame counts from the start of the simulation
+    fn read_frame(&mut self) -> Result<SensorFrame, CoreError> {
+        let world = self.sim.world();
+        let observed = world.observed();
+        let counts: Vec<i64> = observed
+            .angles
+            .iter()
+            .map(|angle| (angle / TAU * world.model.ticks_per_rev).floor() as i64)
+            .collect();
+        let last = self.last.take().unwrap_or_else(|| vec![0; counts.len()]);
+        let ticks: Vec<f64> = counts.iter().zip(&last).map(|(now, before)| (now - before) as f64).collect();
+        let frame = frame(&self.id, observed.time, &ticks);
+        self.last = Some(counts);
+        Ok(frame)
+    }
+    
+    fn metadata(&self) -> SensorMetadata {
+        metadata(&self.id, "Simulated wheel encoder ticks since the previous frame", "ticks")
+    }
+}
+
+/// The base's pose in the simulation frame, as motion capture or a
+/// localization system would report it
+///
+/// Each frame holds three `f32`s: `x` and `y` in metres and the heading
+/// in radians.
+pub struct SimPoseSensor {
+    id: String,
+    sim: Simulator,
+}
+
+impl SimPoseSensor {
+    pub(super) fn new(id: &str, sim: Simulator) -> Self {
+        Self { id: id.to_string(), sim }
+    }
+}
+
+impl Sensor for SimPoseSensor {
+    fn id(&self) -> &str {
+        &self.id
+    }
+    
+    /// Read the pose as of the sensor latency ago, without waiting
+    fn read_frame(&mut self) -> Result<SensorFrame, CoreError> {
+        let mut world = self.sim.world();
+        let observed = world.observed().clone();
+        let noise = world.noise;
+        let values = [
+            observed.pose.x + world.gaussian(noise.position),
+            observed.pose.y + world.gaussian(noise.position),
+            observed.pose.theta + world.gaussian(noise.heading),
+        ];
+        Ok(frame(&self.id, observed.time, &values))
+    }
+    
+    fn metadata(&self) -> SensorMetadata {
+        metadata(&self.id, "Simulated pose (x, y, heading)", "m, rad")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::odometry::DriveGeometry;
+    use crate::hardware::HardwareInterface;
+    use crate::sim::{RobotModel, SimNoise};
+    use std::time::Duration;
+    
+    fn turning(seed: u64) -> Simulator {
+        let geometry = DriveGeometry::Differential {
+            wheel_radius: 0.1,
+            track_width: 0.5,
+        };
+        let sim = Simulator::new(RobotModel::new(geometry, 5.0))
+            .unwrap()
+            .with_noise(SimNoise {
+                gyro: 0.01,
+                ..Default::default()
+            })
+            .with_seed(seed)
+            .with_latency(Duration::from_millis(50), Duration::from_millis(20));
+        // Left wheel backwards, right forwards: turning in place at 2 rad/s
+        let mut board = sim.hardware("board");
+        board.write_pin(0, true).unwrap();
+        board.set_pwm(0, 1.0).unwrap();
+        board.set_pwm(1, 1.0).unwrap();
+        assert!(board.set_pwm(7, 0.5).is_err());
+        sim
+    }
+    
+    #[test]
+    fn test_latency_delays_commands_and_readings() {
+        let sim = turning(7);
+        let mut imu = sim.imu("imu");
+        sim.step(Duration::from_millis(20));
+        assert_eq!(sim.wheel_speeds(), [0.0, 0.0], "the command has not arrived yet");
+        sim.step(Duration::from_millis(40));
+        assert_eq!(sim.wheel_speeds(), [-5.0, 5.0]);
+        let reading = imu.read_frame().unwrap();
+        assert_eq!(reading.timestamp, 10_000, "sensors see 50 ms into the past");
+        assert!(reading.payload_as_f32()[5].abs() < 0.1, "still at rest 10 ms in");
+        
+        sim.step(Duration::from_millis(100));
+        let gyro = imu.read_frame().unwrap().payload_as_f32()[5];
+        assert!((gyro - 2.0).abs() < 0.05, "{}", gyro);
+        assert!((imu.read_frame().unwrap().payload_as_f32()[2] - 9.81).abs() < 0.01);
+        // The same seed draws the same noise
+        let twin = turning(7);
+        let mut twin_imu = twin.imu("imu");
+        twin.step(Duration::from_millis(60));
+        twin_imu.read_frame().unwrap();
+        twin.step(Duration::from_millis(100));
+        assert_eq!(twin_imu.read_frame().unwrap().payload_as_f32()[5], gyro);
+        let mut encoders = sim.encoders("wheels");
+        let ticks = encoders.read_frame().unwrap().payload_as_f32();
+        assert!(ticks[0] < 0.0 && ticks[1] > 0.0);
+        assert_eq!(encoders.read_frame().unwrap().payload_as_f32(), [0.0, 0.0]);
+    }
+}
@@ -0,0 +1,350 @@
+//! Feeding an algorithm a stream of samples, and checkpointing streaming
+//! algorithms so an interrupted stream can resume
+
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::error::CoreError;
+use crate::memory::MemoryManager;
+use crate::wire;
+use crate::{CoreEngine, Prepared};
+
+type StreamOutput = Result<Vec<u8>, CoreError>;
+
+/// An algorithm fed samples on a worker thread, through channels
+///
+/// Made by `CoreEngine::open_stream`. The worker resolves the algorithm
+/// once and runs it on every sample in order. Like an `ExecutorHandle`,
+/// the stream captures the engine's hooks and works on its own copy of
+/// engine memory, handed back by `close`. Every sample is recorded in the
+/// engine's telemetry.
+pub struct AlgorithmStream {
+    samples: Option<SyncSender<Vec<u8>>>,
+    outputs: Receiver<StreamOutput>,
+    worker: Option<JoinHandle<MemoryManager>>,
+}
+
+impl AlgorithmStream {
+    /// Queue `sample` for the worker, waiting while the queue is full
+    ///
+    /// Fails with `CoreError::ProcessingFailed` if the worker has stopped
+    /// after a panic.
+    pub fn send(&self, sample: &[u8]) -> Result<(), CoreError> {
+        self.samples
+            .as_ref()
+            .and_then(|samples| samples.send(sample.to_vec()).ok())
+            .ok_or_else(|| CoreError::ProcessingFailed("stream worker has stopped".to_string()))
+    }
+    
+    /// Take the oldest output without waiting, if there is one
+    ///
+    /// Outputs queue in sample order until taken, a failed sample giving
+    /// its error; later samples still run.
+    pub fn try_recv(&self) -> Option<StreamOutput> {
+        self.outputs.try_recv().ok()
+    }
+    
+    /// Wait up to `timeout` for the next output
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<StreamOutput> {
+        self.outputs.recv_timeout(timeout).ok()
+    }
+    
+    /// Finish the samples already queued and stop the worker, returning the
+    /// outputs not yet taken and the stream's memory
+    pub fn close(mut self) -> Result<(Vec<StreamOutput>, MemoryManager), CoreError> {
+        let memory = self.stop()?;
+        Ok((self.outputs.try_iter().collect(), memory))
+    }
+    
+    fn stop(&mut self) -> Result<MemoryManager, CoreError> {
+        self.samples = None;
+        self.worker
+            .take()
+            .ok_or_else(|| CoreError::ProcessingFailed("stream already closed".to_string()))?
+            .join()
+            .map_err(|_| CoreError::ProcessingFailed("stream worker panicked".to_string()))
+    }
+}
+
+impl Drop for AlgorithmStream {
+    fn drop(&mut self) {
+        // Let the worker finish what is queued rather than outlive the stream
+        let _ = self.stop();
+    }
+}
+
+/// A streaming algorithm's state together with how far into the stream it
+/// had got
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StreamCheckpoint {
+    /// ID the algorithm is registered under
+    pub algorithm_id: String,
+    /// Caller-defined stream position, e.g. bytes or frames consumed
+    pub position: u64,
+    /// State saved by `StreamingAlgorithm::checkpoint`
+    pub state: Vec<u8>,
+}
+
+impl StreamCheckpoint {
+    /// Serialize the checkpoint for storage
+    ///
+    /// The record holds the algorithm ID (`u16` length and UTF-8 bytes),
+    /// the position (`u64`) and the state (`u32` length and bytes), all
+    /// little-endian.
+    pub fn encode(&self) -> Result<Vec<u8>, CoreError> {
+        let mut record = Vec::with_capacity(14 + self.algorithm_id.len() + self.state.len());
+        wire::put_short_str(&mut record, &self.algorithm_id)?;
+        record.extend_from_slice(&self.position.to_le_bytes());
+        wire::put_bytes(&mut record, &self.state)?;
+        Ok(record)
+    }
+    
+    /// Parse a record produced by `encode`
+    pub fn decode(record: &[u8]) -> Result<Self, CoreError> {
+        let mut reader = wire::Reader::new(record, "stream checkpoint", CoreError::InvalidInput);
+        let algorithm_id = reader.short_str()?;
+        let position = reader.u64()?;
+        let state = reader.bytes()?.to_vec();
+        reader.finish()?;
+        Ok(Self {
+            algorithm_id,
+            position,
+            state,
+        })
+    }
+}
+
+impl CoreEngine {
+    /// Start feeding `algorithm_id` samples on a worker thread, queueing up
+    /// to `capacity` samples before `AlgorithmStream::send` waits
+    ///
+    /// Saves the per-call lookup of `execute_algorithm` for high-rate
+    /// inputs such as encoder samples. Algorithms registered later are not
+    /// visible to the stream. Fails with `CoreError::AlgorithmNotFound` for
+    /// an unknown ID and `CoreError::InvalidParameter` for a capacity of 0.
+    pub fn open_stream(&self, algorithm_id: &str, capacity: usize) -> Result<AlgorithmStream, CoreError> {
+        if capacity == 0 {
+            return Err(CoreError::InvalidParameter("stream capacity must be at least 1".to_string()));
+        }
+        let prepared = Prepared::resolve(&self.registry, algorithm_id)?;
+        let (samples, queued) = mpsc::sync_channel::<Vec<u8>>(capacity);
+        let (finished, outputs) = mpsc::channel();
+        let algorithm_id = algorithm_id.to_string();
+        let hooks = self.hooks.clone();
+        let context = self.context.clone();
+        let telemetry = Arc::clone(&self.telemetry);
+        let mut memory = self.memory_manager.fork();
+        let worker = thread::Builder::new()
+            .name(format!("stream-{}", algorithm_id))
+            .spawn(move || {
+                for sample in queued {
+                    let output = telemetry.timed(&algorithm_id, sample.len(), || {
+                        prepared.run(&hooks, &context, &sample, &mut memory)
+                    });
+                    if finished.send(output).is_err() {
+                        break;
+                    }
+                }
+                memory
+            })?;
+        Ok(AlgorithmStream {
+            samples: Some(samples),
+            outputs,
+            worker: Some(worker),
+        })
+    }
+    
+    /// Save the state of a registered streaming algorithm along with the
+    /// stream `position` it has processed up to
+    ///
+    /// Only registered algorithms keep state between executions, so IDs
+    /// resolving to a built-in fail with `CoreError::AlgorithmNotFound`;
+    /// algorithms without streaming support (see
+    /// `algorithm::StreamingAlgorithm`) fail with
+    /// `CoreError::InvalidParameter`.
+    pub fn checkpoint_stream(&self, algorithm_id: &str, position: u64) -> Result<StreamCheckpoint, CoreError> {
+        let state = self.with_streaming(algorithm_id, |streaming| Ok(streaming.checkpoint()))?;
+        Ok(StreamCheckpoint {
+            algorithm_id: algorithm_id.to_string(),
+            position,
+            state,
+        })
+    }
+    
+    /// Restore a checkpoint into the algorithm registered under its ID,
+    /// returning the stream position to resume feeding data from
+    ///
+    /// Fails as `checkpoint_stream` does, or with the algorithm's error for
+    /// a state it cannot take.
+    pub fn resume_stream(&self, checkpoint: &StreamCheckpoint) -> Result<u64, CoreError> {
+        self.with_streaming(&checkpoint.algorithm_id, |streaming| {
+            streaming.restore_checkpoint(&checkpoint.state)
+        })?;
+        Ok(checkpoint.position)
+    }
+    
+    /// Input bytes a registered streaming algorithm is holding back, left
+    /// in place (see `StreamingAlgorithm::peek_buffered`)
+    ///
+    /// Fails as `checkpoint_stream` does.
+    pub fn peek_stream_buffer(&self, algorithm_id: &str) -> Result<Vec<u8>, CoreError> {
+        self.with_streaming(algorithm_id, |streaming| Ok(streaming.peek_buffered()))
+    }
+    
+    fn with_streaming<T>(
+        &self,
+        algorithm_id: &str,
+        f: impl FnOnce(&dyn crate::algorithm::StreamingAlgorithm) -> Result<T, CoreError>,
+    ) -> Result<T, CoreError> {
+        let algorithm = self
+            .registry
+            .get(algorithm_id)
+            .ok_or_else(|| CoreError::AlgorithmNotFound(algorithm_id.to_string()))?;
+        let streaming = algorithm.as_streaming().ok_or_else(|| {
+            CoreError::InvalidParameter(format!("'{}' does not support stream checkpoints", algorithm_id))
+        })?;
+        f(streaming)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::builtins::{Biquad, Scale};
+    use crate::algorithm::{samples, Algorithm, AlgorithmMetadata, StreamingAlgorithm};
+    use std::sync::Mutex;
+    
+    /// Emits complete newline-terminated lines, holding back any partial
+    /// line until its newline arrives
+    #[derive(Default)]
+    struct Lines(Mutex<Vec<u8>>);
+    
+    impl Algorithm for Lines {
+        fn process(&self, input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            let mut pending = self.0.lock().unwrap();
+            pending.extend_from_slice(input);
+            let complete = pending.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+            Ok(pending.drain(..complete).collect())
+        }
+        
+        fn as_streaming(&self) -> Option<&dyn StreamingAlgorithm> {
+            Some(self)
+        }
+        
+        fn id(&self) -> &str {
+            "lines"
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata::default()
+        }
+    }
+    
+    impl StreamingAlgorithm for Lines {
+        fn checkpoint(&self) -> Vec<u8> {
+            self.0.lock().unwrap().clone()
+        }
+        
+        fn restore_checkpoint(&self, state: &[u8]) -> Result<(), CoreError> {
+            *self.0.lock().unwrap() = state.to_vec();
+            Ok(())
+        }
+        
+        fn peek_buffered(&self) -> Vec<u8> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+    
+    fn engine() -> CoreEngine {
+        let mut engine = CoreEngine::new();
+        let filter = Biquad::new(0.2, 0.4, 0.2, -0.5, 0.3).unwrap().stateful(true);
+        engine.register(Box::new(filter)).unwrap();
+        engine
+    }
+    
+    #[test]
+    fn test_resumed_stream_matches_uninterrupted_run() {
+        let stream = samples::f32_to_bytes(&(0..64).map(|i| (i as f32 * 0.3).sin()).collect::<Vec<_>>());
+        let whole = engine().execute_algorithm(Biquad::ID, &stream).unwrap();
+        
+        // Process part of the stream, then stop and persist the checkpoint
+        let split = 40 * 4;
+        let mut interrupted = engine();
+        let mut output = interrupted.execute_algorithm(Biquad::ID, &stream[..split]).unwrap();
+        let record = interrupted.checkpoint_stream(Biquad::ID, split as u64).unwrap().encode().unwrap();
+        drop(interrupted);
+        
+        let mut resumed = engine();
+        let position = resumed.resume_stream(&StreamCheckpoint::decode(&record).unwrap()).unwrap();
+        assert_eq!(position, split as u64);
+        output.extend(resumed.execute_algorithm(Biquad::ID, &stream[position as usize..]).unwrap());
+        assert_eq!(output, whole);
+    }
+    
+    #[test]
+    fn test_checkpoint_requires_registered_streaming_algorithm() {
+        let mut engine = engine();
+        engine.register(Box::new(Scale::new(2.0).unwrap())).unwrap();
+        assert!(matches!(engine.checkpoint_stream(Scale::ID, 0), Err(CoreError::InvalidParameter(_))));
+        assert_eq!(
+            engine.checkpoint_stream("polyphase_resampler", 0),
+            Err(CoreError::AlgorithmNotFound("polyphase_resampler".to_string()))
+        );
+        
+        let bad = StreamCheckpoint {
+            algorithm_id: Biquad::ID.to_string(),
+            position: 0,
+            state: vec![0; 3],
+        };
+        assert!(matches!(engine.resume_stream(&bad), Err(CoreError::InvalidInput(_))));
+        assert!(StreamCheckpoint::decode(&bad.encode().unwrap()[1..]).is_err());
+    }
+    
+    #[test]
+    fn test_stream_runs_one_instance_over_every_sample() {
+        let input: Vec<f32> = (0..8).map(|i| i as f32).collect();
+        let whole = engine().execute_algorithm(Biquad::ID, &samples::f32_to_bytes(&input)).unwrap();
+        let mut engine = engine();
+        engine.register(Box::new(Lines::default())).unwrap();
+        engine.memory_mut().write("origin", &[1]).unwrap();
+        assert!(engine.open_stream("missing", 4).is_err());
+        assert!(engine.open_stream("lines", 0).is_err());
+        
+        let stream = engine.open_stream("lines", 4).unwrap();
+        for sample in [&b"ab"[..], b"c\nd", b"e\n", b"f"] {
+            stream.send(sample).unwrap();
+        }
+        assert_eq!(stream.recv_timeout(Duration::from_secs(5)), Some(Ok(Vec::new())));
+        let (rest, memory) = stream.close().unwrap();
+        assert_eq!(rest, [Ok(b"abc\n".to_vec()), Ok(b"de\n".to_vec()), Ok(Vec::new())]);
+        assert_eq!(memory.read("origin"), Some(&[1][..]));
+        assert_eq!(engine.metrics().algorithms["lines"].latency.count, 4);
+        
+        // Filtering sample by sample matches filtering the whole signal
+        let stream = engine.open_stream(Biquad::ID, 16).unwrap();
+        for &sample in &input {
+            stream.send(&samples::f32_to_bytes(&[sample])).unwrap();
+        }
+        let (outputs, _) = stream.close().unwrap();
+        let streamed: Vec<u8> = outputs.into_iter().flat_map(Result::unwrap).collect();
+        assert_eq!(streamed, whole);
+    }
+    
+    #[test]
+    fn test_peek_shows_partial_chunk_without_consuming_it() {
+        let mut engine = CoreEngine::new();
+        engine.register(Box::new(Lines::default())).unwrap();
+        
+        assert_eq!(engine.execute_algorithm("lines", b"ok\npart").unwrap(), b"ok\n");
+        assert_eq!(engine.peek_stream_buffer("lines").unwrap(), b"part");
+        assert_eq!(engine.peek_stream_buffer("lines").unwrap(), b"part");
+        assert_eq!(engine.execute_algorithm("lines", b"ial\n").unwrap(), b"partial\n");
+        assert!(engine.peek_stream_buffer("lines").unwrap().is_empty());
+        
+        // Algorithms that hold nothing back report an empty buffer
+        let engine = self::engine();
+        assert!(engine.peek_stream_buffer(Biquad::ID).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,215 @@
+//! Rendering metrics for monitoring systems
+
+use std::fmt::Write;
+
+use super::{MetricsSnapshot, LATENCY_BUCKETS};
+use crate::error::CoreError;
+
+/// Renders a metrics snapshot in some monitoring system's format
+pub trait MetricsExporter {
+    fn export(&self, snapshot: &MetricsSnapshot) -> Result<String, CoreError>;
+}
+
+/// Prometheus text exposition format, for serving on a `/metrics` endpoint
+///
+/// Metric names start with the namespace, `robotics_core` by default.
+#[derive(Clone, Debug)]
+pub struct PrometheusExporter {
+    namespace: String,
+}
+
+impl Default for PrometheusExporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PrometheusExporter {
+    pub fn new() -> Self {
+        Self {
+            namespace: "robotics_core".to_string(),
+        }
+    }
+    
+    /// Prefix metric names with `namespace` instead
+    ///
+    /// Fails unless the namespace is a valid Prometheus metric name.
+    pub fn with_namespace(namespace: &str) -> Result<Self, CoreError> {
+        let mut chars = namespace.chars();
+        let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == ':')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':');
+        if !valid {
+            return Err(CoreError::InvalidParameter(format!(
+                "'{}' is not a valid Prometheus metric name",
+                namespace
+            )));
+        }
+        Ok(Self {
+            namespace: namespace.to_string(),
+        })
+    }
+    
+    fn header(&self, out: &mut String, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {}_{} {}", self.namespace, name, help);
+        let _ = writeln!(out, "# TYPE {}_{} {}", self.namespace, name, kind);
+    }
+}
+
+/// Escape a label value per the exposition format
+fn label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+impl MetricsExporter for PrometheusExporter {
+    fn export(&self, snapshot: &MetricsSnapshot) -> Result<String, CoreError> {
+        let ns = &self.namespace;
+        let mut out = String::new();
+        
+        self.header(&mut out, "algorithm_latency_seconds", "histogram", "Algorithm execution latency");
+        for (id, metrics) in &snapshot.algorithms {
+            let id = label(id);
+            let latency = &metrics.latency;
+            let bounds = LATENCY_BUCKETS.iter().map(|bound| bound.to_string()).chain(["+Inf".to_string()]);
+            let counts = latency.buckets.iter().take(LATENCY_BUCKETS.len()).chain([&latency.count]);
+            for (bound, count) in bounds.zip(counts) {
+                let _ = writeln!(
+                    out,
+                    "{ns}_algorithm_latency_seconds_bucket{{algorithm=\"{id}\",le=\"{bound}\"}} {count}"
+                );
+            }
+            let _ = writeln!(out, "{ns}_algorithm_latency_seconds_sum{{algorithm=\"{id}\"}} {}", latency.sum);
+            let _ = writeln!(out, "{ns}_algorithm_latency_seconds_count{{algorithm=\"{id}\"}} {}", latency.count);
+        }
+        self.header(&mut out, "algorithm_errors_total", "counter", "Algorithm executions that failed");
+        for (id, metrics) in &snapshot.algorithms {
+            let _ = writeln!(out, "{ns}_algorithm_errors_total{{algorithm=\"{}\"}} {}", label(id), metrics.errors);
+        }
+        self.header(&mut out, "errors_total", "counter", "Errors by kind");
+        for (kind, count) in &snapshot.errors {
+            let _ = writeln!(out, "{ns}_errors_total{{kind=\"{}\"}} {}", label(kind), count);
+        }
+        
+        self.header(&mut out, "sensor_frames_total", "counter", "Sensor frames read");
+        for (id, sensor) in &snapshot.sensors {
+            let _ = writeln!(out, "{ns}_sensor_frames_total{{sensor=\"{}\"}} {}", label(id), sensor.frames);
+        }
+        self.header(&mut out, "sensor_errors_total", "counter", "Sensor reads that failed");
+        for (id, sensor) in &snapshot.sensors {
+            let _ = writeln!(out, "{ns}_sensor_errors_total{{sensor=\"{}\"}} {}", label(id), sensor.errors);
+        }
+        self.header(&mut out, "sensor_rate_hertz", "gauge", "Recent sensor frame rate");
+        for (id, sensor) in &snapshot.sensors {
+            if let Some(rate) = sensor.rate_hz {
+                let _ = writeln!(out, "{ns}_sensor_rate_hertz{{sensor=\"{}\"}} {}", label(id), rate);
+            }
+        }
+        
+        self.header(&mut out, "starvation_events_total", "counter", "Executions starved of CPU time");
+        for (id, starvation) in &snapshot.starvation {
+            let _ = writeln!(
+                out,
+                "{ns}_starvation_events_total{{algorithm=\"{}\",class=\"{:?}\"}} {}",
+                label(id),
+                starvation.class,
+                starvation.events
+            );
+        }
+        
+        let memory = &snapshot.memory;
+        self.header(&mut out, "memory_bytes", "gauge", "Bytes held in memory regions");
+        let _ = writeln!(out, "{ns}_memory_bytes {}", memory.total_bytes);
+        self.header(&mut out, "memory_regions", "gauge", "Allocated memory regions");
+        let _ = writeln!(out, "{ns}_memory_regions {}", memory.region_count);
+        self.header(&mut out, "memory_region_bytes", "gauge", "Bytes held in each memory region");
+        for (key, bytes) in &memory.regions {
+            let _ = writeln!(out, "{ns}_memory_region_bytes{{region=\"{}\"}} {}", label(key), bytes);
+        }
+        Ok(out)
+    }
+}
+
+/// The snapshot as a JSON document, compact unless made `pretty`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JsonExporter {
+    pretty: bool,
+}
+
+impl JsonExporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Indent the document for reading
+    pub fn pretty(mut self) -> Self {
+        self.pretty = true;
+        self
+    }
+}
+
+impl MetricsExporter for JsonExporter {
+    fn export(&self, snapshot: &MetricsSnapshot) -> Result<String, CoreError> {
+        let json = if self.pretty {
+            serde_json::to_string_pretty(snapshot)
+        } else {
+            serde_json::to_string(snapshot)
+        };
+        json.map_err(|e| CoreError::ProcessingFailed(format!("metrics do not serialize: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::Telemetry;
+    use crate::clock::MockClock;
+    use crate::memory::MemoryStats;
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+    
+    #[test]
+    fn test_prometheus_and_json_output() {
+        let telemetry = Telemetry::new(Arc::new(MockClock::new()));
+        telemetry.record_execution("lowpass \"fast\"", Duration::from_micros(300), None);
+        telemetry.record_execution("lowpass \"fast\"", Duration::from_secs(20), Some(&CoreError::EndOfStream));
+        telemetry.record_sensor_frame("imu", 0);
+        telemetry.record_sensor_frame("imu", 20_000);
+        let memory = MemoryStats {
+            total_bytes: 64,
+            region_count: 1,
+            regions: BTreeMap::from([("sensor/imu".to_string(), 64)]),
+            ..Default::default()
+        };
+        let snapshot = telemetry.snapshot(memory);
+        
+        let text = PrometheusExporter::new().export(&snapshot).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        for expected in [
+            "# TYPE robotics_core_algorithm_latency_seconds histogram",
+            r#"robotics_core_algorithm_latency_seconds_bucket{algorithm="lowpass \"fast\"",le="0.00025"} 0"#,
+            r#"robotics_core_algorithm_latency_seconds_bucket{algorithm="lowpass \"fast\"",le="0.0005"} 1"#,
+            r#"robotics_core_algorithm_latency_seconds_bucket{algorithm="lowpass \"fast\"",le="10"} 1"#,
+            r#"robotics_core_algorithm_latency_seconds_bucket{algorithm="lowpass \"fast\"",le="+Inf"} 2"#,
+            r#"robotics_core_algorithm_latency_seconds_count{algorithm="lowpass \"fast\""} 2"#,
+            r#"robotics_core_algorithm_errors_total{algorithm="lowpass \"fast\""} 1"#,
+            r#"robotics_core_errors_total{kind="EndOfStream"} 1"#,
+            r#"robotics_core_sensor_rate_hertz{sensor="imu"} 50"#,
+            "robotics_core_memory_bytes 64",
+            r#"robotics_core_memory_region_bytes{region="sensor/imu"} 64"#,
+        ] {
+            assert!(lines.contains(&expected), "missing {}", expected);
+        }
+        assert_eq!(snapshot.algorithms["lowpass \"fast\""].latency.quantile(1.0), Some(20.0));
+        
+        let custom = PrometheusExporter::with_namespace("arm").unwrap().export(&snapshot).unwrap();
+        assert!(custom.contains("\narm_memory_regions 1\n"));
+        assert!(PrometheusExporter::with_namespace("2arm").is_err());
+        
+        let json = JsonExporter::new().export(&snapshot).unwrap();
+        let parsed: MetricsSnapshot = serde_json::from_str(&json).unwrap();
+        let buckets = |snapshot: &MetricsSnapshot| snapshot.algorithms["lowpass \"fast\""].latency.buckets.clone();
+        assert_eq!(buckets(&parsed), buckets(&snapshot));
+        assert_eq!((parsed.sensors, parsed.memory), (snapshot.sensors.clone(), snapshot.memory.clone()));
+        assert!(JsonExporter::new().pretty().export(&snapshot).unwrap().contains('\n'));
+    }
+}
@@ -0,0 +1,485 @@
+//! Execution, memory and sensor metrics, with exporters for monitoring
+//! systems
+//!
+//! Every engine records into a shared `Telemetry`: the latency and outcome
+//! of each algorithm execution, including those run through batches, DAGs
+//! and `ExecutorHandle`s, and the frames and failures of any
+//! `SensorManager` given the engine's telemetry. `CoreEngine::metrics`
+//! combines these with the current memory usage into a `MetricsSnapshot`.
+//!
+//! Once `Telemetry::record_spans` is called it also keeps timed spans of
+//! each execution, pipeline stage, scheduler tick and device write, which
+//! `CoreEngine::export_chrome_trace` renders for a trace viewer.
+//!
+//! Starved executions are counted too: background work held at a yield
+//! point past the starvation threshold, and real-time runs starting a
+//! whole period late.
+//!
+//! `CoreEngine::status` summarizes the same records, with device writes,
+//! scheduled runs and the safety state, into an `EngineStatus` for a
+//! supervisor deciding whether the engine is healthy.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::clock::Clock;
+use crate::error::CoreError;
+use crate::memory::MemoryStats;
+use crate::preemption::ExecutionClass;
+use crate::scheduler::{ScheduledRun, TaskStats};
+use crate::CoreEngine;
+
+mod export;
+#[cfg(feature = "otel")]
+mod otel;
+mod spans;
+mod status;
+
+pub use export::{JsonExporter, MetricsExporter, PrometheusExporter};
+pub use spans::{chrome_trace, Span, SpanRecord};
+pub use status::{AlgorithmStatus, DeviceStatus, EngineStatus, SafetyStatus, SensorStatus};
+
+/// Upper bounds of the latency histogram buckets, in seconds, from 10us to
+/// 10s
+pub const LATENCY_BUCKETS: [f64; 19] = [
+    0.00001, 0.000025, 0.00005, 0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25,
+    0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+// Frames a sensor's rate is measured over
+const RATE_WINDOW: usize = 16;
+
+/// Distribution of execution latencies over `LATENCY_BUCKETS`
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyHistogram {
+    /// Executions no slower than each bucket's bound, cumulative as in
+    /// Prometheus, with a final entry for the slower ones
+    pub buckets: Vec<u64>,
+    /// Number of executions
+    pub count: u64,
+    /// Total latency in seconds
+    pub sum: f64,
+    /// Slowest latency in seconds
+    pub max: f64,
+}
+
+impl LatencyHistogram {
+    fn new() -> Self {
+        Self {
+            buckets: vec![0; LATENCY_BUCKETS.len() + 1],
+            ..Default::default()
+        }
+    }
+    
+    fn record(&mut self, latency: Duration) {
+        let seconds = latency.as_secs_f64();
+        let first = LATENCY_BUCKETS.partition_point(|&bound| bound < seconds);
+        for bucket in &mut self.buckets[first..] {
+            *bucket += 1;
+        }
+        self.count += 1;
+        self.sum += seconds;
+        self.max = self.max.max(seconds);
+    }
+    
+    /// Mean latency in seconds, or `None` before any execution
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+    
+    /// Estimate the `q` quantile (0 to 1) in seconds by interpolating
+    /// within its bucket, or `None` before any execution
+    ///
+    /// Latencies beyond the last bound are estimated as `max`.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if self.count == 0 || !(0.0..=1.0).contains(&q) {
+            return None;
+        }
+        let rank = q * self.count as f64;
+        let index = self.buckets.iter().position(|&count| count as f64 >= rank)?;
+        let Some(&upper) = LATENCY_BUCKETS.get(index) else {
+            return Some(self.max);
+        };
+        let (lower, below) = match index {
+            0 => (0.0, 0),
+            _ => (LATENCY_BUCKETS[index - 1], self.buckets[index - 1]),
+        };
+        let within = self.buckets[index] - below;
+        let fraction = if within == 0 { 1.0 } else { (rank - below as f64) / within as f64 };
+        Some((lower + (upper - lower) * fraction).min(self.max))
+    }
+}
+
+/// Executions of one algorithm
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AlgorithmMetrics {
+    /// Executions that returned an error
+    pub errors: u64,
+    /// Latency of every execution, failed ones included
+    pub latency: LatencyHistogram,
+}
+
+/// Frames delivered by one sensor
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SensorMetrics {
+    /// Frames read successfully
+    pub frames: u64,
+    /// Reads that failed
+    pub errors: u64,
+    /// Frames per second over the most recent frames' timestamps, once two
+    /// frames with distinct timestamps have arrived
+    pub rate_hz: Option<f64>,
+}
+
+/// Times one algorithm's executions were held up
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StarvationMetrics {
+    /// Class of the executions held up
+    pub class: ExecutionClass,
+    pub events: u64,
+    /// Sum of every hold-up
+    pub total_delay: Duration,
+    pub max_delay: Duration,
+}
+
+/// Everything recorded, as of one moment
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    /// When the snapshot was taken, by the engine's clock
+    pub timestamp: Duration,
+    /// Per algorithm ID
+    pub algorithms: BTreeMap<String, AlgorithmMetrics>,
+    /// Errors from executions, sensor reads and device writes, by
+    /// `CoreError` variant
+    pub errors: BTreeMap<String, u64>,
+    /// Per sensor ID
+    pub sensors: BTreeMap<String, SensorMetrics>,
+    /// Engine memory usage
+    pub memory: MemoryStats,
+    /// Per algorithm ID, for those whose executions were starved: background
+    /// work paused past the starvation threshold, or real-time runs
+    /// starting a period late
+    #[serde(default)]
+    pub starvation: BTreeMap<String, StarvationMetrics>,
+}
+
+#[derive(Default)]
+struct SensorState {
+    metrics: SensorMetrics,
+    recent: VecDeque<u64>,
+    // Clock time of the latest frame, and the error of the latest read if
+    // it failed
+    last_seen: Option<Duration>,
+    last_error: Option<String>,
+}
+
+#[derive(Default)]
+struct State {
+    algorithms: HashMap<String, AlgorithmMetrics>,
+    errors: HashMap<String, u64>,
+    sensors: HashMap<String, SensorState>,
+    devices: HashMap<String, DeviceStatus>,
+    scheduled: HashMap<String, TaskStats>,
+    starvation: HashMap<String, StarvationMetrics>,
+}
+
+/// Thread-safe store of execution and sensor metrics
+///
+/// Shared through an `Arc` by an engine, its executor handles and any
+/// sensor managers it is given to.
+pub struct Telemetry {
+    clock: Arc<dyn Clock>,
+    state: Mutex<State>,
+    spans: Arc<spans::SpanLog>,
+}
+
+/// Name of `error`'s variant, such as `InvalidInput`
+pub(crate) fn error_kind(error: &CoreError) -> String {
+    let debug = format!("{:?}", error);
+    debug
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+impl Telemetry {
+    /// Create an empty store timing executions with `clock`
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            spans: Arc::new(spans::SpanLog::new(Arc::clone(&clock))),
+            clock,
+            state: Mutex::default(),
+        }
+    }
+    
+    /// The clock executions are timed with
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+    
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    /// Record an execution of `algorithm_id` that took `latency` and failed
+    /// with `error`, if it did
+    pub fn record_execution(&self, algorithm_id: &str, latency: Duration, error: Option<&CoreError>) {
+        let mut state = self.state();
+        let algorithm = state
+            .algorithms
+            .entry(algorithm_id.to_string())
+            .or_insert_with(|| AlgorithmMetrics {
+                errors: 0,
+                latency: LatencyHistogram::new(),
+            });
+        algorithm.latency.record(latency);
+        if let Some(error) = error {
+            algorithm.errors += 1;
+            *state.errors.entry(error_kind(error)).or_default() += 1;
+        }
+    }
+    
+    /// Run `execute` on `input_bytes` of input and record it as an
+    /// execution of `algorithm_id`, in an `execute_algorithm` span
+    pub(crate) fn timed<T>(
+        &self,
+        algorithm_id: &str,
+        input_bytes: usize,
+        execute: impl FnOnce() -> Result<T, CoreError>,
+    ) -> Result<T, CoreError> {
+        let mut span = self
+            .span("execute_algorithm")
+            .with("algorithm.id", algorithm_id)
+            .with("input_bytes", input_bytes);
+        let started = self.clock.now();
+        let result = execute();
+        let latency = self.clock.now().saturating_sub(started);
+        self.record_execution(algorithm_id, latency, result.as_ref().err());
+        span.record_result(&result);
+        result
+    }
+    
+    /// Keep the spans opened from now on, up to the most recent `capacity`
+    ///
+    /// Restarting discards the spans kept so far.
+    pub fn record_spans(&self, capacity: usize) {
+        self.spans.start(capacity);
+    }
+    
+    /// Stop keeping spans, returning those kept, oldest first
+    pub fn stop_spans(&self) -> Vec<SpanRecord> {
+        self.spans.stop()
+    }
+    
+    /// Take the spans kept so far, oldest first, while still recording
+    pub fn take_spans(&self) -> Vec<SpanRecord> {
+        self.spans.take()
+    }
+    
+    /// Open a span timing work until it is dropped
+    ///
+    /// The span is only kept if spans are being recorded.
+    pub fn span(&self, name: &'static str) -> Span {
+        Span::open(&self.spans, name)
+    }
+    
+    /// Record a frame read from `sensor_id`, stamped `timestamp`
+    /// microseconds
+    pub fn record_sensor_frame(&self, sensor_id: &str, timestamp: u64) {
+        let now = self.clock.now();
+        let mut state = self.state();
+        let sensor = state.sensors.entry(sensor_id.to_string()).or_default();
+        sensor.metrics.frames += 1;
+        sensor.last_seen = Some(now);
+        sensor.last_error = None;
+        if sensor.recent.len() == RATE_WINDOW {
+            sensor.recent.pop_front();
+        }
+        sensor.recent.push_back(timestamp);
+        if let (Some(&first), Some(&last)) = (sensor.recent.front(), sensor.recent.back()) {
+            if last > first {
+                sensor.metrics.rate_hz = Some((sensor.recent.len() - 1) as f64 * 1e6 / (last - first) as f64);
+            }
+        }
+    }
+    
+    /// Record a failed read of `sensor_id`
+    pub fn record_sensor_error(&self, sensor_id: &str, error: &CoreError) {
+        let mut state = self.state();
+        let sensor = state.sensors.entry(sensor_id.to_string()).or_default();
+        sensor.metrics.errors += 1;
+        sensor.last_error = Some(error.to_string());
+        *state.errors.entry(error_kind(error)).or_default() += 1;
+    }
+    
+    /// Record a command written to `device`, which failed with `error` if
+    /// it did
+    pub fn record_device_write(&self, device: &str, error: Option<&CoreError>) {
+        let mut state = self.state();
+        let status = state.devices.entry(device.to_string()).or_default();
+        status.writes += 1;
+        status.last_error = error.map(|e| e.to_string());
+        if let Some(error) = error {
+            status.errors += 1;
+            *state.errors.entry(error_kind(error)).or_default() += 1;
+        }
+    }
+    
+    /// Record a run made by a `Scheduler`
+    pub fn record_scheduled_run(&self, run: &ScheduledRun) {
+        self.state().scheduled.entry(run.algorithm_id.clone()).or_default().record(run);
+    }
+    
+    /// Record an execution of `algorithm_id`, in `class`, held up for
+    /// `delay`
+    pub fn record_starvation(&self, algorithm_id: &str, class: ExecutionClass, delay: Duration) {
+        let mut state = self.state();
+        let starvation = state.starvation.entry(algorithm_id.to_string()).or_default();
+        starvation.class = class;
+        starvation.events += 1;
+        starvation.total_delay = starvation.total_delay.saturating_add(delay);
+        starvation.max_delay = starvation.max_delay.max(delay);
+    }
+    
+    /// Forget everything recorded so far, spans included
+    pub fn reset(&self) {
+        *self.state() = State::default();
+        self.spans.take();
+    }
+    
+    /// Capture the recorded metrics, with `memory` as the memory usage
+    pub fn snapshot(&self, memory: MemoryStats) -> MetricsSnapshot {
+        let state = self.state();
+        MetricsSnapshot {
+            timestamp: self.clock.now(),
+            algorithms: state.algorithms.iter().map(|(id, m)| (id.clone(), m.clone())).collect(),
+            errors: state.errors.iter().map(|(kind, &n)| (kind.clone(), n)).collect(),
+            sensors: state
+                .sensors
+                .iter()
+                .map(|(id, sensor)| (id.clone(), sensor.metrics.clone()))
+                .collect(),
+            memory,
+            starvation: state.starvation.iter().map(|(id, m)| (id.clone(), m.clone())).collect(),
+        }
+    }
+}
+
+impl CoreEngine {
+    /// The engine's metrics store, to share with a `SensorManager` or to
+    /// record custom executions into
+    pub fn telemetry(&self) -> &Arc<Telemetry> {
+        &self.telemetry
+    }
+    
+    /// Capture the metrics recorded so far and the current memory usage
+    ///
+    /// The snapshot serializes to JSON as is, or through `JsonExporter`.
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.telemetry.snapshot(self.memory_manager.stats())
+    }
+    
+    /// Render the current metrics with `exporter`
+    pub fn export_metrics(&self, exporter: &dyn MetricsExporter) -> Result<String, CoreError> {
+        exporter.export(&self.metrics())
+    }
+    
+    /// Take the spans recorded so far and render them with `chrome_trace`,
+    /// for `chrome://tracing` or the Perfetto UI
+    ///
+    /// Empty unless spans were turned on with `Telemetry::record_spans`.
+    pub fn export_chrome_trace(&self) -> Result<String, CoreError> {
+        chrome_trace(&self.telemetry.take_spans())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{Algorithm, AlgorithmMetadata};
+    use crate::clock::MockClock;
+    use crate::memory::MemoryManager;
+    use crate::sensor::{MockSensor, Sensor, SensorFrame, SensorManager};
+    
+    /// Advances the clock by a millisecond per input byte, failing on empty
+    /// input
+    struct Sleepy(Arc<MockClock>);
+    
+    impl Algorithm for Sleepy {
+        fn process(&self, input: &[u8], _memory: &mut MemoryManager) -> Result<Vec<u8>, CoreError> {
+            self.0.advance(Duration::from_millis(input.len() as u64));
+            if input.is_empty() {
+                return Err(CoreError::InvalidInput("empty".to_string()));
+            }
+            Ok(input.to_vec())
+        }
+        
+        fn id(&self) -> &str {
+            "sleepy"
+        }
+        
+        fn metadata(&self) -> AlgorithmMetadata {
+            AlgorithmMetadata::default()
+        }
+    }
+    
+    struct Unplugged;
+    
+    impl Sensor for Unplugged {
+        fn id(&self) -> &str {
+            "gps"
+        }
+        
+        fn read_frame(&mut self) -> Result<SensorFrame, CoreError> {
+            Err(CoreError::SensorError("unplugged".to_string()))
+        }
+    }
+    
+    #[test]
+    fn test_executions_sensors_and_memory_are_recorded() {
+        let clock = Arc::new(MockClock::new());
+        let mut engine = CoreEngine::with_clock(clock.clone());
+        engine.register(Box::new(Sleepy(clock.clone()))).unwrap();
+        engine.execute_algorithm("sleepy", &[0; 2]).unwrap();
+        engine.execute_algorithm("sleepy", &[0; 30]).unwrap();
+        assert!(engine.execute_algorithm("sleepy", &[]).is_err());
+        assert!(engine.execute_algorithm("missing", &[1]).is_err());
+        // Executions through a handle land in the same store
+        engine.executor().execute("sleepy", &[0; 4]).unwrap();
+        engine.memory_mut().write("pose", &[0; 12]).unwrap();
+        
+        let mut sensors = SensorManager::new().with_telemetry(Arc::clone(engine.telemetry()));
+        sensors.add(Box::new(MockSensor::new("imu", 100.0, vec![1.0]).unwrap()), None).unwrap();
+        sensors.add(Box::new(Unplugged), Some(50.0)).unwrap();
+        for ms in [0, 10, 20, 30] {
+            sensors.poll(engine.memory_mut(), Duration::from_millis(ms));
+        }
+        
+        let metrics = engine.metrics();
+        let sleepy = &metrics.algorithms["sleepy"];
+        assert_eq!((sleepy.latency.count, sleepy.errors), (4, 1));
+        assert!((sleepy.latency.sum - 0.036).abs() < 1e-9);
+        assert_eq!(sleepy.latency.max, 0.03);
+        // 0 and 2ms within 2.5ms, 4ms within 5ms, 30ms within 50ms
+        assert_eq!(&sleepy.latency.buckets[6..12], &[1, 2, 3, 3, 3, 4]);
+        assert_eq!(sleepy.latency.quantile(0.5), Some(0.0025));
+        assert_eq!(metrics.algorithms["missing"].errors, 1);
+        assert_eq!(metrics.errors["InvalidInput"], 1);
+        assert_eq!(metrics.errors["AlgorithmNotFound"], 1);
+        assert_eq!(metrics.memory.regions["pose"], 12);
+        
+        let imu = &metrics.sensors["imu"];
+        assert_eq!((imu.frames, imu.errors, imu.rate_hz), (4, 0, Some(100.0)));
+        let gps = &metrics.sensors["gps"];
+        assert_eq!((gps.frames, gps.errors, gps.rate_hz), (0, 2, None));
+        assert_eq!(metrics.errors["SensorError"], 2);
+        
+        let json = serde_json::to_value(&metrics).unwrap();
+        assert_eq!(json["algorithms"]["sleepy"]["latency"]["count"], 4);
+        engine.telemetry().reset();
+        assert!(engine.metrics().algorithms.is_empty());
+    }
+}
@@ -0,0 +1,99 @@
+//! OpenTelemetry spans around executions, behind the `otel` feature
+
+use opentelemetry::global::BoxedTracer;
+use opentelemetry::trace::{Span, Status, Tracer};
+use opentelemetry::KeyValue;
+use std::sync::Arc;
+
+use crate::algorithm::AlgorithmOutput;
+use crate::error::CoreError;
+use crate::CoreEngine;
+
+impl CoreEngine {
+    /// Emit a span through `tracer` for every `execute` and
+    /// `execute_algorithm`
+    ///
+    /// Each span is named `execute_algorithm` and carries the
+    /// `algorithm.id` and `algorithm.input_bytes` attributes, plus
+    /// `algorithm.output_bytes` on success. A failure is recorded as an
+    /// exception event and sets the span's status to error. Cache hits are
+    /// traced like any other execution.
+    pub fn set_tracer<T>(&mut self, tracer: T)
+    where
+        T: Tracer + Send + Sync + 'static,
+        T::Span: Send + Sync + 'static,
+    {
+        self.tracer = Some(Arc::new(BoxedTracer::new(Box::new(tracer))));
+    }
+    
+    /// Stop emitting spans
+    pub fn clear_tracer(&mut self) {
+        self.tracer = None;
+    }
+    
+    /// Run `execute` inside a span on `tracer`
+    pub(crate) fn execute_traced(
+        &mut self,
+        tracer: &BoxedTracer,
+        algorithm_id: &str,
+        input_data: &[u8],
+    ) -> Result<AlgorithmOutput, CoreError> {
+        let mut span = tracer
+            .span_builder("execute_algorithm")
+            .with_attributes([
+                KeyValue::new("algorithm.id", algorithm_id.to_string()),
+                KeyValue::new("algorithm.input_bytes", input_data.len() as i64),
+            ])
+            .start(tracer);
+        let result = self.execute_untraced(algorithm_id, input_data);
+        match &result {
+            Ok(output) => span.set_attribute(KeyValue::new("algorithm.output_bytes", output.data.len() as i64)),
+            Err(e) => {
+                span.record_error(e);
+                span.set_status(Status::error(e.to_string()));
+            }
+        }
+        span.end();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry::Value;
+    use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+    
+    fn attribute<'a>(attributes: &'a [KeyValue], key: &str) -> Option<&'a Value> {
+        attributes.iter().find(|kv| kv.key.as_str() == key).map(|kv| &kv.value)
+    }
+    
+    #[test]
+    fn test_execution_emits_span_with_attributes() {
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let mut engine = CoreEngine::new();
+        engine.set_tracer(provider.tracer("robotics_core"));
+        
+        engine.execute_algorithm("passthrough", &[1, 2, 3]).unwrap();
+        assert!(engine.execute_algorithm("missing", &[4]).is_err());
+        
+        let spans = exporter.get_finished_spans().unwrap();
+        assert_eq!(spans.len(), 2);
+        let ok = &spans[0];
+        assert_eq!(ok.name, "execute_algorithm");
+        assert_eq!(attribute(&ok.attributes, "algorithm.id"), Some(&Value::from("passthrough")));
+        assert_eq!(attribute(&ok.attributes, "algorithm.input_bytes"), Some(&Value::I64(3)));
+        assert_eq!(attribute(&ok.attributes, "algorithm.output_bytes"), Some(&Value::I64(3)));
+        assert_eq!(ok.status, Status::Unset);
+        
+        let failed = &spans[1];
+        assert_eq!(attribute(&failed.attributes, "algorithm.id"), Some(&Value::from("missing")));
+        assert!(attribute(&failed.attributes, "algorithm.output_bytes").is_none());
+        assert!(matches!(failed.status, Status::Error { .. }));
+        assert_eq!(failed.events.events[0].name, "exception");
+    }
+}
@@ -0,0 +1,231 @@
+//! Timed spans of engine work, exportable as a Chrome trace
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::clock::Clock;
+use crate::error::CoreError;
+
+static NEXT_THREAD: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    // Small, stable number for the current thread, as trace viewers expect
+    static THREAD: u64 = NEXT_THREAD.fetch_add(1, Ordering::Relaxed);
+}
+
+/// One finished span
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SpanRecord {
+    /// What the span timed, such as `execute_algorithm`
+    pub name: &'static str,
+    /// When the span opened, by the engine's clock
+    pub start: Duration,
+    pub duration: Duration,
+    /// Number of the thread the span ran on, counting from 1
+    pub thread: u64,
+    /// Details such as `algorithm.id`
+    pub fields: BTreeMap<&'static str, Value>,
+}
+
+/// Buffer of finished spans, while recording
+pub(super) struct SpanLog {
+    clock: Arc<dyn Clock>,
+    recording: Mutex<Option<Recording>>,
+}
+
+struct Recording {
+    spans: VecDeque<SpanRecord>,
+    capacity: usize,
+}
+
+impl SpanLog {
+    pub(super) fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            clock,
+            recording: Mutex::new(None),
+        }
+    }
+    
+    fn lock(&self) -> MutexGuard<'_, Option<Recording>> {
+        self.recording.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    pub(super) fn start(&self, capacity: usize) {
+        *self.lock() = Some(Recording {
+            spans: VecDeque::with_capacity(capacity.min(4096)),
+            capacity,
+        });
+    }
+    
+    pub(super) fn stop(&self) -> Vec<SpanRecord> {
+        self.lock().take().map(|recording| recording.spans.into()).unwrap_or_default()
+    }
+    
+    pub(super) fn take(&self) -> Vec<SpanRecord> {
+        self.lock()
+            .as_mut()
+            .map(|recording| recording.spans.drain(..).collect())
+            .unwrap_or_default()
+    }
+    
+    pub(super) fn is_recording(&self) -> bool {
+        self.lock().is_some()
+    }
+    
+    fn push(&self, span: SpanRecord) {
+        if let Some(recording) = self.lock().as_mut() {
+            if recording.spans.len() == recording.capacity {
+                recording.spans.pop_front();
+            }
+            recording.spans.push_back(span);
+        }
+    }
+}
+
+/// An open span, recorded when dropped
+///
+/// Spans opened while the telemetry is not recording are inert: they
+/// neither read the clock nor keep their fields.
+#[must_use = "a span times the scope it is held in"]
+pub struct Span {
+    log: Option<Arc<SpanLog>>,
+    name: &'static str,
+    start: Duration,
+    fields: BTreeMap<&'static str, Value>,
+}
+
+impl Span {
+    pub(super) fn open(log: &Arc<SpanLog>, name: &'static str) -> Self {
+        let recording = log.is_recording();
+        Self {
+            start: if recording { log.clock.now() } else { Duration::ZERO },
+            log: recording.then(|| Arc::clone(log)),
+            name,
+            fields: BTreeMap::new(),
+        }
+    }
+    
+    /// Attach `key` with `value`
+    pub fn with(mut self, key: &'static str, value: impl Into<Value>) -> Self {
+        self.record(key, value);
+        self
+    }
+    
+    /// Attach `key` with `value`, replacing any earlier value
+    pub fn record(&mut self, key: &'static str, value: impl Into<Value>) {
+        if self.log.is_some() {
+            self.fields.insert(key, value.into());
+        }
+    }
+    
+    /// Attach the outcome of the work the span timed: the `error` kind, if
+    /// it failed
+    pub fn record_result<T>(&mut self, result: &Result<T, CoreError>) {
+        if let Err(error) = result {
+            self.record("error", super::error_kind(error));
+        }
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if let Some(log) = self.log.take() {
+            let duration = log.clock.now().saturating_sub(self.start);
+            log.push(SpanRecord {
+                name: self.name,
+                start: self.start,
+                duration,
+                thread: THREAD.with(|thread| *thread),
+                fields: std::mem::take(&mut self.fields),
+            });
+        }
+    }
+}
+
+/// Render `spans` in the Chrome trace event format, which
+/// `chrome://tracing` and the Perfetto UI open directly
+///
+/// Each span becomes a complete (`X`) event with its fields as arguments;
+/// viewers nest spans on the same thread by time.
+pub fn chrome_trace(spans: &[SpanRecord]) -> Result<String, CoreError> {
+    let micros = |duration: Duration| duration.as_nanos() as f64 / 1000.0;
+    let events: Vec<Value> = spans
+        .iter()
+        .map(|span| {
+            json!({
+                "name": span.name,
+                "cat": "robotics_core",
+                "ph": "X",
+                "ts": micros(span.start),
+                "dur": micros(span.duration),
+                "pid": std::process::id(),
+                "tid": span.thread,
+                "args": span.fields,
+            })
+        })
+        .collect();
+    serde_json::to_string(&json!({ "traceEvents": events, "displayTimeUnit": "ns" }))
+        .map_err(|e| CoreError::ProcessingFailed(format!("cannot encode trace: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::NullDevice;
+    use crate::scheduler::Scheduler;
+    use crate::CoreEngine;
+    
+    #[test]
+    fn test_engine_work_is_traced_when_recording() {
+        let mut engine = CoreEngine::new();
+        engine.execute_algorithm("passthrough", &[1]).unwrap();
+        assert!(engine.telemetry().take_spans().is_empty(), "spans are off by default");
+        
+        engine.telemetry().record_spans(64);
+        engine.execute_pipeline(&["passthrough", "passthrough"], &[1, 2, 3]).unwrap();
+        assert!(engine.execute_algorithm("missing", &[4]).is_err());
+        engine.register_device(Box::new(NullDevice::new("motor")));
+        engine.execute_to_device("passthrough", &[5, 6], "motor").unwrap();
+        let mut scheduler = Scheduler::new();
+        scheduler.add("passthrough", Duration::from_millis(10)).unwrap();
+        scheduler.tick(&mut engine, Duration::ZERO);
+        
+        let spans = engine.telemetry().take_spans();
+        let names: Vec<&str> = spans.iter().map(|span| span.name).collect();
+        assert_eq!(
+            names,
+            [
+                "execute_algorithm",
+                "pipeline_stage",
+                "execute_algorithm",
+                "pipeline_stage",
+                "execute_algorithm",
+                "execute_algorithm",
+                "device_write",
+                "execute_algorithm",
+                "scheduler_tick"
+            ]
+        );
+        assert_eq!(spans[0].fields["algorithm.id"], "passthrough");
+        assert_eq!(spans[0].fields["input_bytes"], 3);
+        assert_eq!(spans[3].fields["stage"], 1);
+        assert_eq!(spans[4].fields["error"], "AlgorithmNotFound");
+        assert_eq!(spans[6].fields["bytes"], 2);
+        assert_eq!(spans[8].fields["runs"], 1);
+        // A stage encloses the execution it ran
+        assert!(spans[1].start <= spans[0].start && spans[0].duration <= spans[1].duration);
+        
+        engine.execute_algorithm("passthrough", &[1]).unwrap();
+        let trace: Value = serde_json::from_str(&engine.export_chrome_trace().unwrap()).unwrap();
+        let event = &trace["traceEvents"][0];
+        assert_eq!((event["name"].as_str(), event["ph"].as_str()), (Some("execute_algorithm"), Some("X")));
+        assert_eq!(event["args"]["algorithm.id"], "passthrough");
+        assert_eq!(engine.telemetry().stop_spans().len(), 0);
+        engine.execute_algorithm("passthrough", &[1]).unwrap();
+        assert!(engine.telemetry().take_spans().is_empty());
+    }
+}
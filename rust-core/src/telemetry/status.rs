@@ -0,0 +1,232 @@
+//! Machine-readable health of a running engine
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::SensorState;
+use crate::memory::MemoryStats;
+use crate::safety::{SafetyMonitor, SafetyState};
+use crate::scheduler::TaskStats;
+use crate::CoreEngine;
+
+/// Frame intervals a sensor with a measured rate may miss before it
+/// counts as silent
+const STALE_FRAMES: f64 = 3.0;
+
+/// A registered algorithm and how its executions went
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlgorithmStatus {
+    pub name: String,
+    pub version: String,
+    pub executions: u64,
+    /// Executions that returned an error
+    pub errors: u64,
+    /// Timed executions over the algorithm's `expected_duration`
+    pub deadline_misses: u64,
+}
+
+/// Whether a sensor is still delivering frames
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SensorStatus {
+    /// Frames read successfully
+    pub frames: u64,
+    /// Reads that failed
+    pub errors: u64,
+    /// Timestamp of the latest frame, in microseconds
+    pub last_timestamp: Option<u64>,
+    /// Time since the latest frame was read, by the engine's clock
+    pub silent_for: Option<Duration>,
+    /// Error of the latest read, if it failed
+    pub last_error: Option<String>,
+    /// Whether the latest read succeeded and, once the sensor's rate is
+    /// known, no more than three frame intervals have passed since
+    pub live: bool,
+}
+
+/// Writes to a registered device
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceStatus {
+    pub writes: u64,
+    /// Writes that failed
+    pub errors: u64,
+    /// Error of the latest write, if it failed
+    pub last_error: Option<String>,
+}
+
+/// State of the safety monitor given to `CoreEngine::set_safety_monitor`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SafetyStatus {
+    /// State as of the monitor's last check or trip
+    pub state: SafetyState,
+    /// How long each watched source has gone without a heartbeat
+    pub silences: BTreeMap<String, Duration>,
+}
+
+/// Everything a supervisor needs to judge a running engine, as of one
+/// moment
+///
+/// Sensors appear once a `SensorManager` sharing the engine's telemetry
+/// has polled them, and scheduled tasks once a `Scheduler` has run them
+/// on the engine.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct EngineStatus {
+    /// When the status was taken, by the engine's clock
+    pub timestamp: Duration,
+    /// Per algorithm ID, registered or a built-in that has been executed
+    pub algorithms: BTreeMap<String, AlgorithmStatus>,
+    /// Per sensor ID
+    pub sensors: BTreeMap<String, SensorStatus>,
+    /// Per registered device name
+    pub devices: BTreeMap<String, DeviceStatus>,
+    /// Deadline statistics of scheduled runs, per algorithm ID
+    pub scheduler: BTreeMap<String, TaskStats>,
+    /// Engine memory usage
+    pub memory: MemoryStats,
+    /// `None` unless a safety monitor was given to the engine
+    pub safety: Option<SafetyStatus>,
+}
+
+impl EngineStatus {
+    /// Whether the engine can carry on: not in an emergency stop, with
+    /// every sensor live and no device whose latest write failed
+    pub fn is_healthy(&self) -> bool {
+        let stopped = self
+            .safety
+            .as_ref()
+            .is_some_and(|safety| safety.state == SafetyState::EmergencyStop);
+        !stopped
+            && self.sensors.values().all(|sensor| sensor.live)
+            && self.devices.values().all(|device| device.last_error.is_none())
+    }
+}
+
+impl SensorState {
+    fn status(&self, now: Duration) -> SensorStatus {
+        let silent_for = self.last_seen.map(|seen| now.saturating_sub(seen));
+        let stale = match (silent_for, self.metrics.rate_hz) {
+            (Some(silent_for), Some(rate)) => silent_for.as_secs_f64() > STALE_FRAMES / rate,
+            _ => false,
+        };
+        SensorStatus {
+            frames: self.metrics.frames,
+            errors: self.metrics.errors,
+            last_timestamp: self.recent.back().copied(),
+            silent_for,
+            last_error: self.last_error.clone(),
+            live: self.metrics.frames > 0 && self.last_error.is_none() && !stale,
+        }
+    }
+}
+
+impl CoreEngine {
+    /// Report `monitor`'s state in `status`, replacing any previous one
+    pub fn set_safety_monitor(&mut self, monitor: Arc<SafetyMonitor>) {
+        self.safety = Some(monitor);
+    }
+    
+    /// Capture the engine's health: registered algorithms and how their
+    /// executions went, sensor liveness, device writes, scheduler
+    /// deadlines, memory usage and the safety state
+    ///
+    /// The status serializes to JSON as is.
+    pub fn status(&self) -> EngineStatus {
+        let metrics = self.telemetry.snapshot(self.memory_manager.stats());
+        // Built-ins are never registered, so they are listed once they run
+        let builtins = metrics
+            .algorithms
+            .keys()
+            .filter(|id| self.registry.get(id).is_none())
+            .filter_map(|id| Some((id.clone(), self.get_algorithm(id)?.metadata())));
+        let algorithms = self
+            .list_algorithms()
+            .into_iter()
+            .chain(builtins)
+            .map(|(id, metadata)| {
+                let recorded = metrics.algorithms.get(&id);
+                let status = AlgorithmStatus {
+                    name: metadata.name,
+                    version: metadata.version,
+                    executions: recorded.map_or(0, |m| m.latency.count),
+                    errors: recorded.map_or(0, |m| m.errors),
+                    deadline_misses: self.deadline_misses(&id),
+                };
+                (id, status)
+            })
+            .collect();
+        let state = self.telemetry.state();
+        let devices = self
+            .devices
+            .keys()
+            .map(|name| (name.clone(), state.devices.get(name).cloned().unwrap_or_default()))
+            .collect();
+        EngineStatus {
+            timestamp: metrics.timestamp,
+            algorithms,
+            sensors: state
+                .sensors
+                .iter()
+                .map(|(id, sensor)| (id.clone(), sensor.status(metrics.timestamp)))
+                .collect(),
+            devices,
+            scheduler: state.scheduled.iter().map(|(id, &stats)| (id.clone(), stats)).collect(),
+            memory: metrics.memory,
+            safety: self.safety.as_ref().map(|monitor| SafetyStatus {
+                state: monitor.state(),
+                silences: monitor.silences(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, MockClock};
+    use crate::hardware::NullDevice;
+    use crate::scheduler::Scheduler;
+    use crate::sensor::{MockSensor, SensorManager};
+    
+    #[test]
+    fn test_status_reports_sensors_devices_schedule_and_safety() {
+        let clock = Arc::new(MockClock::new());
+        let mut engine = CoreEngine::with_clock(clock.clone());
+        engine.register_device(Box::new(NullDevice::new("motor")));
+        engine.execute_to_device("passthrough", &[1], "motor").unwrap();
+        let monitor = Arc::new(SafetyMonitor::new().with_clock(clock.clone()));
+        monitor.watch("imu", Duration::from_millis(50), Duration::from_millis(200)).unwrap();
+        engine.set_safety_monitor(monitor.clone());
+        
+        let mut sensors = SensorManager::new().with_telemetry(Arc::clone(engine.telemetry()));
+        sensors.add(Box::new(MockSensor::new("imu", 100.0, vec![1.0]).unwrap()), None).unwrap();
+        let mut scheduler = Scheduler::new();
+        scheduler.add("passthrough", Duration::from_millis(10)).unwrap();
+        for ms in [0, 10, 20] {
+            clock.advance(Duration::from_millis(ms) - clock.now());
+            monitor.observe_polls(&sensors.poll(engine.memory_mut(), clock.now()));
+            scheduler.tick(&mut engine, clock.now());
+        }
+        
+        let status = engine.status();
+        assert!(status.is_healthy());
+        let passthrough = &status.algorithms["passthrough"];
+        assert_eq!((passthrough.executions, passthrough.errors), (4, 0));
+        assert!(!passthrough.version.is_empty());
+        assert_eq!(status.devices["motor"].writes, 1);
+        assert_eq!((status.scheduler["passthrough"].runs, status.scheduler["passthrough"].deadline_misses), (3, 0));
+        let imu = &status.sensors["imu"];
+        assert_eq!((imu.frames, imu.last_timestamp, imu.live), (3, Some(20_000), true));
+        assert_eq!(status.safety.as_ref().map(|s| s.state), Some(SafetyState::Operational));
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["sensors"]["imu"]["live"], true);
+        
+        // A sensor silent for over three periods is no longer live, and
+        // the stop it leads to makes the engine unhealthy
+        clock.advance(Duration::from_millis(280));
+        assert_eq!(monitor.check(), SafetyState::EmergencyStop);
+        let status = engine.status();
+        assert_eq!(status.sensors["imu"].silent_for, Some(Duration::from_millis(280)));
+        assert!(!status.sensors["imu"].live && !status.is_healthy());
+    }
+}
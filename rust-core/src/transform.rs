@@ -0,0 +1,329 @@
+//! A tree of named coordinate frames linked by rigid transforms
+//!
+//! Each frame but the roots has one parent, and the link between them is
+//! the child's pose in the parent's frame: either static, such as a lidar
+//! bolted to the base, or a history of timestamped transforms, such as the
+//! base moving in the world. Looking a transform up at a time interpolates
+//! between the samples either side of it, so data can be expressed in any
+//! connected frame as of its capture time.
+//!
+//! Timestamps are microseconds, like `SensorFrame::timestamp`.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use crate::algorithm::kinematics::Transform;
+use crate::error::CoreError;
+
+/// History kept for each dynamic link unless configured otherwise
+pub const DEFAULT_HISTORY: Duration = Duration::from_secs(10);
+
+enum Link {
+    Static(Transform),
+    // Ordered by timestamp
+    Dynamic(VecDeque<(u64, Transform)>),
+}
+
+struct Edge {
+    parent: String,
+    link: Link,
+}
+
+/// Coordinate frames and the transforms between them
+pub struct TransformTree {
+    // Link to its parent, by child frame
+    edges: HashMap<String, Edge>,
+    history_micros: u64,
+}
+
+impl TransformTree {
+    pub fn new() -> Self {
+        Self {
+            edges: HashMap::new(),
+            history_micros: DEFAULT_HISTORY.as_micros() as u64,
+        }
+    }
+    
+    /// Keep each dynamic link's samples for `history` behind its newest
+    pub fn with_history(mut self, history: Duration) -> Self {
+        self.history_micros = u64::try_from(history.as_micros()).unwrap_or(u64::MAX);
+        self
+    }
+    
+    /// Fix `child`'s pose in `parent` for all time
+    pub fn set_static(&mut self, parent: &str, child: &str, transform: Transform) -> Result<(), CoreError> {
+        self.check_link(parent, child, true)?;
+        self.edges.insert(
+            child.to_string(),
+            Edge {
+                parent: parent.to_string(),
+                link: Link::Static(transform),
+            },
+        );
+        Ok(())
+    }
+    
+    /// Record `child`'s pose in `parent` at `timestamp`
+    ///
+    /// Samples may arrive out of order; one at an existing timestamp
+    /// replaces it. A child keeps the parent it was first linked to, and a
+    /// link that would close a loop is refused.
+    pub fn set(&mut self, parent: &str, child: &str, transform: Transform, timestamp: u64) -> Result<(), CoreError> {
+        self.check_link(parent, child, false)?;
+        let edge = self.edges.entry(child.to_string()).or_insert_with(|| Edge {
+            parent: parent.to_string(),
+            link: Link::Dynamic(VecDeque::new()),
+        });
+        let Link::Dynamic(samples) = &mut edge.link else {
+            return Err(CoreError::InvalidParameter(format!("'{}' is linked statically", child)));
+        };
+        let index = samples.partition_point(|&(time, _)| time < timestamp);
+        match samples.get_mut(index) {
+            Some(sample) if sample.0 == timestamp => sample.1 = transform,
+            _ => samples.insert(index, (timestamp, transform)),
+        }
+        if let Some(&(newest, _)) = samples.back() {
+            let oldest = newest.saturating_sub(self.history_micros);
+            while samples.front().is_some_and(|&(time, _)| time < oldest) {
+                samples.pop_front();
+            }
+        }
+        Ok(())
+    }
+    
+    fn check_link(&self, parent: &str, child: &str, fixed: bool) -> Result<(), CoreError> {
+        if parent == child {
+            return Err(CoreError::InvalidParameter(format!("frame '{}' cannot be its own parent", child)));
+        }
+        if let Some(edge) = self.edges.get(child) {
+            if edge.parent != parent {
+                return Err(CoreError::InvalidParameter(format!(
+                    "'{}' already has parent '{}', not '{}'",
+                    child, edge.parent, parent
+                )));
+            }
+            if matches!(edge.link, Link::Static(_)) != fixed {
+                let kind = if fixed { "dynamically" } else { "statically" };
+                return Err(CoreError::InvalidParameter(format!("'{}' is linked {}", child, kind)));
+            }
+            return Ok(());
+        }
+        if self.ancestors(parent).any(|frame| frame == child) {
+            return Err(CoreError::InvalidParameter(format!(
+                "linking '{}' under '{}' would close a loop",
+                child, parent
+            )));
+        }
+        Ok(())
+    }
+    
+    /// `frame` and each frame above it, up to its root
+    fn ancestors<'a>(&'a self, frame: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        std::iter::successors(Some(frame), |&frame| self.edges.get(frame).map(|edge| edge.parent.as_str()))
+    }
+    
+    /// Whether `frame` has been linked, as parent or child
+    pub fn contains(&self, frame: &str) -> bool {
+        self.edges.contains_key(frame) || self.edges.values().any(|edge| edge.parent == frame)
+    }
+    
+    /// Parent of `frame`, if it is linked under one
+    pub fn parent(&self, frame: &str) -> Option<&str> {
+        self.edges.get(frame).map(|edge| edge.parent.as_str())
+    }
+    
+    /// Every frame in the tree, sorted
+    pub fn frames(&self) -> Vec<&str> {
+        let mut frames: Vec<&str> = self
+            .edges
+            .iter()
+            .flat_map(|(child, edge)| [child.as_str(), edge.parent.as_str()])
+            .collect();
+        frames.sort_unstable();
+        frames.dedup();
+        frames
+    }
+    
+    /// The transform mapping coordinates in frame `from` to coordinates in
+    /// frame `to`, as of `timestamp`
+    ///
+    /// Dynamic links are interpolated between the samples either side of
+    /// `timestamp`; a time outside a link's samples fails with
+    /// `CoreError::InvalidInput` rather than extrapolating. Static links
+    /// hold at any time.
+    pub fn lookup(&self, from: &str, to: &str, timestamp: u64) -> Result<Transform, CoreError> {
+        let (from_chain, to_chain) = self.chains(from, to)?;
+        let from_pose = self.pose_along(&from_chain, timestamp)?;
+        let to_pose = self.pose_along(&to_chain, timestamp)?;
+        Ok(to_pose.inverse().then(&from_pose))
+    }
+    
+    /// `lookup` at the latest time every link between the frames has data
+    /// for, returning that time with the transform
+    ///
+    /// The time is 0 when only static links are involved.
+    pub fn lookup_latest(&self, from: &str, to: &str) -> Result<(u64, Transform), CoreError> {
+        let (from_chain, to_chain) = self.chains(from, to)?;
+        let mut latest = None::<u64>;
+        for frame in from_chain.iter().chain(&to_chain) {
+            if let Some(Edge {
+                link: Link::Dynamic(samples),
+                ..
+            }) = self.edges.get(*frame)
+            {
+                let newest = samples.back().map_or(0, |&(time, _)| time);
+                latest = Some(latest.map_or(newest, |latest| latest.min(newest)));
+            }
+        }
+        let timestamp = latest.unwrap_or(0);
+        Ok((timestamp, self.lookup(from, to, timestamp)?))
+    }
+    
+    /// Map `point` from frame `from` into frame `to` as of `timestamp`
+    pub fn transform_point(
+        &self,
+        from: &str,
+        to: &str,
+        timestamp: u64,
+        point: [f64; 3],
+    ) -> Result<[f64; 3], CoreError> {
+        Ok(self.lookup(from, to, timestamp)?.apply(point))
+    }
+    
+    /// The frames from `from` and from `to` up to, but excluding, their
+    /// nearest common ancestor
+    fn chains<'a>(&'a self, from: &'a str, to: &'a str) -> Result<(Vec<&'a str>, Vec<&'a str>), CoreError> {
+        for frame in [from, to] {
+            if !self.contains(frame) {
+                return Err(CoreError::InvalidInput(format!("unknown frame '{}'", frame)));
+            }
+        }
+        let to_ancestors: Vec<&str> = self.ancestors(to).collect();
+        let mut from_chain = Vec::new();
+        for frame in self.ancestors(from) {
+            if let Some(position) = to_ancestors.iter().position(|&ancestor| ancestor == frame) {
+                return Ok((from_chain, to_ancestors[..position].to_vec()));
+            }
+            from_chain.push(frame);
+        }
+        Err(CoreError::InvalidInput(format!("frames '{}' and '{}' are not connected", from, to)))
+    }
+    
+    /// Pose of the first frame of `chain` in the parent of its last
+    fn pose_along(&self, chain: &[&str], timestamp: u64) -> Result<Transform, CoreError> {
+        chain.iter().try_fold(Transform::identity(), |pose, frame| {
+            Ok(self.link_at(frame, timestamp)?.then(&pose))
+        })
+    }
+    
+    fn link_at(&self, child: &str, timestamp: u64) -> Result<Transform, CoreError> {
+        let Some(edge) = self.edges.get(child) else {
+            return Err(CoreError::InvalidInput(format!("frame '{}' has no parent", child)));
+        };
+        let samples = match &edge.link {
+            Link::Static(transform) => return Ok(*transform),
+            Link::Dynamic(samples) => samples,
+        };
+        let index = samples.partition_point(|&(time, _)| time < timestamp);
+        match (index.checked_sub(1).and_then(|before| samples.get(before)), samples.get(index)) {
+            (_, Some(&(time, transform))) if time == timestamp => Ok(transform),
+            (Some(&(before, earlier)), Some(&(after, later))) => {
+                let t = (timestamp - before) as f64 / (after - before) as f64;
+                Ok(earlier.interpolate(&later, t))
+            }
+            _ => {
+                let span = match (samples.front(), samples.back()) {
+                    (Some((first, _)), Some((last, _))) => format!("{}..={}us", first, last),
+                    _ => "nothing".to_string(),
+                };
+                Err(CoreError::InvalidInput(format!(
+                    "no transform from '{}' to '{}' at {}us; samples cover {}",
+                    child, edge.parent, timestamp, span
+                )))
+            }
+        }
+    }
+}
+
+impl Default for TransformTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensor::lidar::PointCloud;
+    use std::f64::consts::FRAC_PI_2;
+    
+    fn assert_close(actual: [f64; 3], expected: [f64; 3]) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-9, "{:?} != {:?}", actual, expected);
+        }
+    }
+    
+    /// A base driving along X in the world, turning a quarter turn over a
+    /// second, with a lidar mounted 0.5 m ahead of its centre
+    fn robot() -> TransformTree {
+        let mut tree = TransformTree::new();
+        tree.set_static("base", "lidar", Transform::from_translation([0.5, 0.0, 0.0])).unwrap();
+        tree.set("world", "base", Transform::identity(), 0).unwrap();
+        let turned = Transform::from_translation([2.0, 0.0, 0.0]).then(&Transform::from_rpy(0.0, 0.0, FRAC_PI_2));
+        tree.set("world", "base", turned, 1_000_000).unwrap();
+        tree.set_static("world", "dock", Transform::from_translation([0.0, 3.0, 0.0])).unwrap();
+        tree
+    }
+    
+    #[test]
+    fn test_lookup_composes_and_interpolates() {
+        let tree = robot();
+        assert_eq!(tree.frames(), ["base", "dock", "lidar", "world"]);
+        assert_eq!(tree.parent("lidar"), Some("base"));
+        
+        // A point 1 m ahead of the lidar, seen from the world and the dock
+        assert_close(tree.transform_point("lidar", "world", 0, [1.0, 0.0, 0.0]).unwrap(), [1.5, 0.0, 0.0]);
+        assert_close(tree.transform_point("lidar", "world", 1_000_000, [1.0, 0.0, 0.0]).unwrap(), [2.0, 1.5, 0.0]);
+        assert_close(tree.transform_point("lidar", "dock", 0, [1.0, 0.0, 0.0]).unwrap(), [1.5, -3.0, 0.0]);
+        let cloud = PointCloud {
+            points: vec![[1.0, 0.0, 0.0]],
+            intensities: vec![7.0],
+        };
+        let moved = cloud.transformed(&tree.lookup("lidar", "world", 1_000_000).unwrap());
+        assert_eq!(moved.points, [[2.0, 1.5, 0.0]]);
+        
+        // Halfway: 1 m along and an eighth of a turn
+        let halfway = tree.lookup("base", "world", 500_000).unwrap();
+        assert_close(halfway.translation(), [1.0, 0.0, 0.0]);
+        let heading = halfway.apply([1.0, 0.0, 0.0]);
+        assert_close(heading, [1.0 + 0.5f64.sqrt(), 0.5f64.sqrt(), 0.0]);
+        
+        // The inverse lookup undoes it
+        let back = tree.lookup("world", "lidar", 500_000).unwrap();
+        let point = tree.transform_point("lidar", "world", 500_000, [0.3, -0.2, 0.1]).unwrap();
+        assert_close(back.apply(point), [0.3, -0.2, 0.1]);
+        
+        let (time, latest) = tree.lookup_latest("lidar", "world").unwrap();
+        assert_eq!(time, 1_000_000);
+        assert_close(latest.translation(), [2.0, 0.5, 0.0]);
+        assert!(matches!(tree.lookup("lidar", "world", 1_000_001), Err(CoreError::InvalidInput(_))));
+        assert_close(tree.lookup("dock", "world", u64::MAX).unwrap().translation(), [0.0, 3.0, 0.0]);
+    }
+    
+    #[test]
+    fn test_links_are_validated() {
+        let mut tree = robot().with_history(Duration::from_millis(500));
+        assert!(tree.set_static("lidar", "world", Transform::identity()).is_err());
+        assert!(tree.set_static("dock", "base", Transform::identity()).is_err());
+        assert!(tree.set("world", "dock", Transform::identity(), 0).is_err());
+        assert!(tree.set_static("world", "world", Transform::identity()).is_err());
+        assert!(matches!(tree.lookup("lidar", "moon", 0), Err(CoreError::InvalidInput(_))));
+        tree.set_static("moon", "rover", Transform::identity()).unwrap();
+        assert!(tree.lookup("lidar", "rover", 0).is_err());
+        
+        // Samples older than the history are dropped
+        tree.set("world", "base", Transform::identity(), 1_400_000).unwrap();
+        assert!(tree.lookup("base", "world", 500_000).is_err());
+        assert!(tree.lookup("base", "world", 1_200_000).is_ok());
+    }
+}
@@ -0,0 +1,57 @@
+//! JavaScript bindings for running the engine in the browser
+
+use wasm_bindgen::prelude::*;
+
+/// Engine handle exported to JavaScript
+#[wasm_bindgen(js_name = CoreEngine)]
+pub struct WasmEngine {
+    inner: crate::CoreEngine,
+}
+
+#[wasm_bindgen(js_class = CoreEngine)]
+impl WasmEngine {
+    /// Create a new engine instance
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            inner: crate::CoreEngine::new(),
+        }
+    }
+    
+    /// Execute an algorithm on a `Uint8Array`, returning a `Uint8Array`
+    #[wasm_bindgen(js_name = executeAlgorithm)]
+    pub fn execute_algorithm(&mut self, algorithm_id: &str, input: &[u8]) -> Result<Vec<u8>, JsValue> {
+        self.inner
+            .execute_algorithm(algorithm_id, input)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+}
+
+impl Default for WasmEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Execute an algorithm on a fresh engine
+#[wasm_bindgen(js_name = executeAlgorithm)]
+pub fn execute_algorithm(algorithm_id: &str, input: &[u8]) -> Result<Vec<u8>, JsValue> {
+    WasmEngine::new().execute_algorithm(algorithm_id, input)
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+    
+    #[wasm_bindgen_test]
+    fn test_execute_builtin() {
+        let output = execute_algorithm("passthrough", &[1, 2, 3]).unwrap();
+        assert_eq!(output, vec![1, 2, 3]);
+    }
+    
+    #[wasm_bindgen_test]
+    fn test_unknown_algorithm_errors() {
+        assert!(WasmEngine::new().execute_algorithm("missing", &[]).is_err());
+    }
+}
@@ -0,0 +1,116 @@
+//! Helpers for the crate's little-endian binary record formats
+
+// Without `std`, the modules reading and writing the short forms are gone
+#![cfg_attr(not(feature = "std"), allow(dead_code))]
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::error::CoreError;
+
+/// Reads little-endian fields off the front of a buffer
+///
+/// Every failure is reported through `error`, so each format can surface
+/// malformed data as the `CoreError` variant that fits its source.
+pub(crate) struct Reader<'a> {
+    rest: &'a [u8],
+    what: &'static str,
+    error: fn(String) -> CoreError,
+}
+
+impl<'a> Reader<'a> {
+    /// Read a `what` (used in error messages) from `bytes`
+    pub(crate) fn new(bytes: &'a [u8], what: &'static str, error: fn(String) -> CoreError) -> Self {
+        Self { rest: bytes, what, error }
+    }
+    
+    /// Build an error about the record being read
+    pub(crate) fn error(&self, message: &str) -> CoreError {
+        (self.error)(format!("{}: {}", self.what, message))
+    }
+    
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], CoreError> {
+        if self.rest.len() < len {
+            return Err(self.error("truncated"));
+        }
+        let (head, tail) = self.rest.split_at(len);
+        self.rest = tail;
+        Ok(head)
+    }
+    
+    /// The next `N` bytes as an array
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], CoreError> {
+        self.take(N)?.try_into().map_err(|_| self.error("truncated"))
+    }
+    
+    pub(crate) fn u8(&mut self) -> Result<u8, CoreError> {
+        self.array().map(u8::from_le_bytes)
+    }
+    
+    pub(crate) fn u16(&mut self) -> Result<u16, CoreError> {
+        self.array().map(u16::from_le_bytes)
+    }
+    
+    pub(crate) fn u32(&mut self) -> Result<u32, CoreError> {
+        self.array().map(u32::from_le_bytes)
+    }
+    
+    pub(crate) fn u64(&mut self) -> Result<u64, CoreError> {
+        self.array().map(u64::from_le_bytes)
+    }
+    
+    /// Bytes preceded by a `u32` length
+    pub(crate) fn bytes(&mut self) -> Result<&'a [u8], CoreError> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+    
+    /// UTF-8 text preceded by a `u16` length
+    pub(crate) fn short_str(&mut self) -> Result<String, CoreError> {
+        let len = self.u16()? as usize;
+        self.utf8(len)
+    }
+    
+    /// UTF-8 text preceded by a `u32` length
+    pub(crate) fn str(&mut self) -> Result<String, CoreError> {
+        let len = self.u32()? as usize;
+        self.utf8(len)
+    }
+    
+    fn utf8(&mut self, len: usize) -> Result<String, CoreError> {
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| self.error("text is not UTF-8"))
+    }
+    
+    /// Whether the whole buffer was consumed
+    pub(crate) fn is_empty(&self) -> bool {
+        self.rest.is_empty()
+    }
+    
+    /// Require that the whole buffer was consumed
+    pub(crate) fn finish(self) -> Result<(), CoreError> {
+        if !self.rest.is_empty() {
+            return Err(self.error("trailing bytes"));
+        }
+        Ok(())
+    }
+}
+
+/// Append `bytes` preceded by a `u32` length
+pub(crate) fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) -> Result<(), CoreError> {
+    let len = u32::try_from(bytes.len())
+        .map_err(|_| CoreError::InvalidInput(format!("{} bytes are too long to encode", bytes.len())))?;
+    out.extend_from_slice(&len.to_le_bytes());
+    out.extend_from_slice(bytes);
+    Ok(())
+}
+
+/// Append `text` preceded by a `u16` length
+pub(crate) fn put_short_str(out: &mut Vec<u8>, text: &str) -> Result<(), CoreError> {
+    let len = u16::try_from(text.len())
+        .map_err(|_| CoreError::InvalidInput(format!("text of {} bytes is too long to encode", text.len())))?;
+    out.extend_from_slice(&len.to_le_bytes());
+    out.extend_from_slice(text.as_bytes());
+    Ok(())
+}